@@ -0,0 +1,168 @@
+#ifndef CRUMBEEZ_H
+#define CRUMBEEZ_H
+
+/* Generated by cbindgen from crates/crumbeez-ffi; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Shift was held.
+#define CRUMBEEZ_MOD_SHIFT 1
+
+// Ctrl was held.
+#define CRUMBEEZ_MOD_CTRL (1 << 1)
+
+// Alt (Option, Meta) was held.
+#define CRUMBEEZ_MOD_ALT (1 << 2)
+
+// Super (Cmd, Windows) was held.
+#define CRUMBEEZ_MOD_SUPER (1 << 3)
+
+// How a call went.
+typedef enum CrumbeezStatus {
+  CRUMBEEZ_STATUS_OK = 0,
+  // A required pointer was NULL.
+  CRUMBEEZ_STATUS_NULL_ARGUMENT,
+  // An index was past the end of the log.
+  CRUMBEEZ_STATUS_OUT_OF_RANGE,
+  // The log couldn't be encoded.
+  CRUMBEEZ_STATUS_ENCODE_FAILED,
+  // The bytes weren't a readable event log.
+  CRUMBEEZ_STATUS_DECODE_FAILED,
+} CrumbeezStatus;
+
+// One classified event.
+typedef struct CrumbeezEvent CrumbeezEvent;
+
+// An event log, as the plugin keeps one.
+typedef struct CrumbeezLog CrumbeezLog;
+
+// Bytes owned by the library; free with [`crumbeez_bytes_free`].
+typedef struct CrumbeezBytes {
+  uint8_t *data;
+  size_t len;
+} CrumbeezBytes;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Classify the key called `key`, pressed with the `CRUMBEEZ_MOD_*` flags
+// in `modifiers`, as the plugin would.  Keys are named as summaries show
+// them: a single character (already shifted), or `Enter`, `Tab`,
+// `Backspace`, `Delete`, `Insert`, `Esc`, an arrow (`Left`, ...), `Home`,
+// `End`, `PageUp`, `PageDown`, `F1`–`F12`, or a lock or system key
+// (`CapsLock`, `Menu`, ...).  Returns NULL for an unknown key.
+//
+// # Safety
+//
+// `key` must be NULL or a NUL-terminated string.
+CrumbeezEvent *crumbeez_classify(const char *key, uint8_t modifiers);
+
+// A run of typed text, for callers that see text rather than keys.
+// Returns NULL for an empty string.
+//
+// # Safety
+//
+// `text` must be NULL or a NUL-terminated string.
+CrumbeezEvent *crumbeez_event_text(const char *text);
+
+// Focus moving to a pane, buffer, or window titled `title`, running
+// `command` (NULL if there isn't one) in the tab or workspace `tab` (NULL
+// if unknown).  Its role is looked up in the built-in profiles, so keys
+// after it summarize as they would in the plugin.
+//
+// # Safety
+//
+// Each argument must be NULL or a NUL-terminated string.
+CrumbeezEvent *crumbeez_event_focus(const char *tab, const char *title, const char *command);
+
+// The event as the plugin logs it, e.g. `shortcut Ctrl+r` or
+// `typed "ls"`.  Free with [`crumbeez_string_free`].
+//
+// # Safety
+//
+// `event` must be NULL or a live handle.
+char *crumbeez_event_to_string(const CrumbeezEvent *event);
+
+// # Safety
+//
+// `event` must be NULL or a live handle, not used again afterwards.
+void crumbeez_event_free(CrumbeezEvent *event);
+
+// # Safety
+//
+// `string` must be NULL or a string returned by this library, not used
+// again afterwards.
+void crumbeez_string_free(char *string);
+
+// An empty log.
+CrumbeezLog *crumbeez_log_new(void);
+
+// # Safety
+//
+// `log` must be NULL or a live handle, not used again afterwards.
+void crumbeez_log_free(CrumbeezLog *log);
+
+// Append a copy of `event`, timestamped `timestamp_ms` since the Unix
+// epoch.  The caller keeps `event`.
+//
+// # Safety
+//
+// `log` and `event` must each be NULL or a live handle.
+CrumbeezStatus crumbeez_log_push_event(CrumbeezLog *log,
+                                       const CrumbeezEvent *event,
+                                       uint64_t timestamp_ms);
+
+// Entries in the log, or 0 for NULL.
+//
+// # Safety
+//
+// `log` must be NULL or a live handle.
+size_t crumbeez_log_len(const CrumbeezLog *log);
+
+// A copy of the event of entry `index`, oldest first, or NULL if there's
+// no such entry.
+//
+// # Safety
+//
+// `log` must be NULL or a live handle.
+CrumbeezEvent *crumbeez_log_event(const CrumbeezLog *log, size_t index);
+
+// Store the timestamp of entry `index` in `out`.
+//
+// # Safety
+//
+// `log` must be NULL or a live handle, and `out` NULL or writable.
+CrumbeezStatus crumbeez_log_timestamp(const CrumbeezLog *log, size_t index, uint64_t *out);
+
+// Encode the log in the format of the plugin's `.crumbeez/events.bin`
+// into `out`, to be freed with [`crumbeez_bytes_free`].
+//
+// # Safety
+//
+// `log` must be NULL or a live handle, and `out` NULL or writable.
+CrumbeezStatus crumbeez_log_serialize(const CrumbeezLog *log, CrumbeezBytes *out);
+
+// Decode a log written by the plugin or [`crumbeez_log_serialize`],
+// storing the new handle in `out`.
+//
+// # Safety
+//
+// `data` must be NULL or point to `len` readable bytes, and `out` NULL or
+// writable.
+CrumbeezStatus crumbeez_log_deserialize(const uint8_t *data, size_t len, CrumbeezLog **out);
+
+// # Safety
+//
+// `bytes` must have come from this library and not been freed already.
+void crumbeez_bytes_free(CrumbeezBytes bytes);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CRUMBEEZ_H */
@@ -0,0 +1,144 @@
+//! Classifying a key given by name, by the same rules the plugin applies to
+//! Zellij's keys (see its `keystroke` module).
+//!
+//! Keys are named as summaries show them: a single character, or `Enter`,
+//! `Tab`, `Backspace`, `Delete`, `Insert`, `Esc`, `Left`, `Right`, `Up`,
+//! `Down`, `Home`, `End`, `PageUp`, `PageDown`, `F1`–`F12`, `CapsLock`,
+//! `ScrollLock`, `NumLock`, `PrintScreen`, `Pause`, or `Menu`.
+
+use crumbeez_lib::{
+    EditControlEvent, KeystrokeEvent, NavDirection, NavigationEvent, ShortcutEvent, ShortcutKey,
+    SystemKeyEvent,
+};
+
+/// Modifiers held with a key.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub super_key: bool,
+}
+
+/// A key named in full.
+#[derive(Debug, Clone)]
+enum Key {
+    Char(char),
+    Named(NamedKey),
+    System(SystemKeyEvent),
+}
+
+#[derive(Debug, Clone)]
+enum NamedKey {
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Insert,
+    Esc,
+    Nav(NavDirection),
+    F(u8),
+}
+
+fn parse(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c));
+    }
+    let named = match name {
+        "Enter" => NamedKey::Enter,
+        "Tab" => NamedKey::Tab,
+        "Backspace" => NamedKey::Backspace,
+        "Delete" => NamedKey::Delete,
+        "Insert" => NamedKey::Insert,
+        "Esc" => NamedKey::Esc,
+        "Left" => NamedKey::Nav(NavDirection::Left),
+        "Right" => NamedKey::Nav(NavDirection::Right),
+        "Up" => NamedKey::Nav(NavDirection::Up),
+        "Down" => NamedKey::Nav(NavDirection::Down),
+        "Home" => NamedKey::Nav(NavDirection::Home),
+        "End" => NamedKey::Nav(NavDirection::End),
+        "PageUp" => NamedKey::Nav(NavDirection::PageUp),
+        "PageDown" => NamedKey::Nav(NavDirection::PageDown),
+        "CapsLock" => return Some(Key::System(SystemKeyEvent::CapsLock)),
+        "ScrollLock" => return Some(Key::System(SystemKeyEvent::ScrollLock)),
+        "NumLock" => return Some(Key::System(SystemKeyEvent::NumLock)),
+        "PrintScreen" => return Some(Key::System(SystemKeyEvent::PrintScreen)),
+        "Pause" => return Some(Key::System(SystemKeyEvent::Pause)),
+        "Menu" => return Some(Key::System(SystemKeyEvent::Menu)),
+        _ => {
+            let n: u8 = name.strip_prefix('F')?.parse().ok()?;
+            if !(1..=12).contains(&n) {
+                return None;
+            }
+            NamedKey::F(n)
+        }
+    };
+    Some(Key::Named(named))
+}
+
+fn shortcut_key(key: Key) -> ShortcutKey {
+    match key {
+        Key::Char(c) => ShortcutKey::Char(c),
+        Key::Named(NamedKey::Enter) => ShortcutKey::Enter,
+        Key::Named(NamedKey::Tab) => ShortcutKey::Tab,
+        Key::Named(NamedKey::Backspace) => ShortcutKey::Backspace,
+        Key::Named(NamedKey::Delete) => ShortcutKey::Delete,
+        Key::Named(NamedKey::Insert) => ShortcutKey::Insert,
+        Key::Named(NamedKey::Esc) => ShortcutKey::Esc,
+        Key::Named(NamedKey::Nav(direction)) => match direction {
+            NavDirection::Left => ShortcutKey::Left,
+            NavDirection::Right => ShortcutKey::Right,
+            NavDirection::Up => ShortcutKey::Up,
+            NavDirection::Down => ShortcutKey::Down,
+            NavDirection::Home => ShortcutKey::Home,
+            NavDirection::End => ShortcutKey::End,
+            NavDirection::PageUp => ShortcutKey::PageUp,
+            NavDirection::PageDown => ShortcutKey::PageDown,
+        },
+        Key::Named(NamedKey::F(n)) => ShortcutKey::F(n),
+        // The same placeholders the plugin uses for these in a chord.
+        Key::System(SystemKeyEvent::CapsLock) => ShortcutKey::Char('⇪'),
+        Key::System(SystemKeyEvent::ScrollLock) => ShortcutKey::Char('⤓'),
+        Key::System(SystemKeyEvent::NumLock) => ShortcutKey::Char('⇭'),
+        Key::System(SystemKeyEvent::PrintScreen) => ShortcutKey::Char('⎙'),
+        Key::System(SystemKeyEvent::Pause) => ShortcutKey::Char('⏸'),
+        Key::System(SystemKeyEvent::Menu) => ShortcutKey::Char('≡'),
+    }
+}
+
+/// The event for the key called `name` pressed with `mods`, or `None` if
+/// there's no such key.
+pub fn classify(name: &str, mods: Modifiers) -> Option<KeystrokeEvent> {
+    let key = parse(name)?;
+    if mods.ctrl || mods.alt || mods.super_key {
+        return Some(KeystrokeEvent::Shortcut(ShortcutEvent {
+            key: shortcut_key(key),
+            ctrl: mods.ctrl,
+            alt: mods.alt,
+            shift: mods.shift,
+            super_key: mods.super_key,
+        }));
+    }
+    Some(match key {
+        Key::Char(c) => KeystrokeEvent::TextTyped(c.to_string()),
+        Key::Named(NamedKey::Nav(direction)) => KeystrokeEvent::Navigation(NavigationEvent {
+            direction,
+            count: 1,
+            with_shift: mods.shift,
+            with_ctrl: false,
+        }),
+        Key::Named(NamedKey::Enter) => KeystrokeEvent::EditControl(EditControlEvent::Enter),
+        Key::Named(NamedKey::Tab) => KeystrokeEvent::EditControl(EditControlEvent::Tab),
+        Key::Named(NamedKey::Backspace) => {
+            KeystrokeEvent::EditControl(EditControlEvent::Backspace { count: 1 })
+        }
+        Key::Named(NamedKey::Delete) => {
+            KeystrokeEvent::EditControl(EditControlEvent::Delete { count: 1 })
+        }
+        Key::Named(NamedKey::Insert) => KeystrokeEvent::EditControl(EditControlEvent::Insert),
+        Key::Named(NamedKey::Esc) => KeystrokeEvent::Escape,
+        Key::Named(NamedKey::F(n)) => KeystrokeEvent::FunctionKey(n),
+        Key::System(key) => KeystrokeEvent::SystemKey(key),
+    })
+}
@@ -0,0 +1,340 @@
+//! A C ABI over `crumbeez-lib`, so editor plugins in other languages (a
+//! Neovim plugin through LuaJIT's FFI, a helper for a VS Code extension
+//! host) can write breadcrumbs in the same format the Zellij plugin does.
+//!
+//! The header is `include/crumbeez.h`, generated by cbindgen; see
+//! `cbindgen.toml`.
+//!
+//! ### Conventions
+//!
+//! - Events and logs are opaque handles.  Each one returned is owned by the
+//!   caller and freed with its `_free` function, as are returned strings
+//!   and byte buffers.
+//! - Strings are NUL-terminated UTF-8.  One that isn't UTF-8 is treated
+//!   as NULL.
+//! - A NULL handle or required string gets NULL back, or
+//!   [`CrumbeezStatus::NullArgument`]; it's never dereferenced.
+//! - Nothing here is thread-safe: use a handle from one thread at a time.
+
+mod key;
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crumbeez_lib::{EventLog, KeystrokeEvent, PaneFocusedEvent, ProfileRegistry};
+
+/// Shift was held.
+pub const CRUMBEEZ_MOD_SHIFT: u8 = 1;
+/// Ctrl was held.
+pub const CRUMBEEZ_MOD_CTRL: u8 = 1 << 1;
+/// Alt (Option, Meta) was held.
+pub const CRUMBEEZ_MOD_ALT: u8 = 1 << 2;
+/// Super (Cmd, Windows) was held.
+pub const CRUMBEEZ_MOD_SUPER: u8 = 1 << 3;
+
+/// One classified event.
+pub struct CrumbeezEvent(KeystrokeEvent);
+
+/// An event log, as the plugin keeps one.
+pub struct CrumbeezLog(EventLog);
+
+/// Bytes owned by the library; free with [`crumbeez_bytes_free`].
+#[repr(C)]
+pub struct CrumbeezBytes {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// How a call went.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrumbeezStatus {
+    Ok = 0,
+    /// A required pointer was NULL.
+    NullArgument,
+    /// An index was past the end of the log.
+    OutOfRange,
+    /// The log couldn't be encoded.
+    EncodeFailed,
+    /// The bytes weren't a readable event log.
+    DecodeFailed,
+}
+
+// ── Helpers ──────────────────────────────────────────────────────
+
+/// The string at `ptr`, or `None` if it's NULL or not UTF-8.
+///
+/// # Safety
+///
+/// `ptr` must be NULL or point to a NUL-terminated string that outlives
+/// `'a`.
+unsafe fn str_arg<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok()
+}
+
+fn boxed<T>(value: T) -> *mut T {
+    Box::into_raw(Box::new(value))
+}
+
+fn event(event: KeystrokeEvent) -> *mut CrumbeezEvent {
+    boxed(CrumbeezEvent(event))
+}
+
+// ── Events ───────────────────────────────────────────────────────
+
+/// Classify the key called `key`, pressed with the `CRUMBEEZ_MOD_*` flags
+/// in `modifiers`, as the plugin would.  Keys are named as summaries show
+/// them: a single character (already shifted), or `Enter`, `Tab`,
+/// `Backspace`, `Delete`, `Insert`, `Esc`, an arrow (`Left`, ...), `Home`,
+/// `End`, `PageUp`, `PageDown`, `F1`–`F12`, or a lock or system key
+/// (`CapsLock`, `Menu`, ...).  Returns NULL for an unknown key.
+///
+/// # Safety
+///
+/// `key` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_classify(
+    key: *const c_char,
+    modifiers: u8,
+) -> *mut CrumbeezEvent {
+    let Some(name) = str_arg(key) else {
+        return ptr::null_mut();
+    };
+    let mods = key::Modifiers {
+        shift: modifiers & CRUMBEEZ_MOD_SHIFT != 0,
+        ctrl: modifiers & CRUMBEEZ_MOD_CTRL != 0,
+        alt: modifiers & CRUMBEEZ_MOD_ALT != 0,
+        super_key: modifiers & CRUMBEEZ_MOD_SUPER != 0,
+    };
+    key::classify(name, mods).map_or(ptr::null_mut(), event)
+}
+
+/// A run of typed text, for callers that see text rather than keys.
+/// Returns NULL for an empty string.
+///
+/// # Safety
+///
+/// `text` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_event_text(text: *const c_char) -> *mut CrumbeezEvent {
+    match str_arg(text) {
+        Some(text) if !text.is_empty() => event(KeystrokeEvent::TextTyped(text.to_string())),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Focus moving to a pane, buffer, or window titled `title`, running
+/// `command` (NULL if there isn't one) in the tab or workspace `tab` (NULL
+/// if unknown).  Its role is looked up in the built-in profiles, so keys
+/// after it summarize as they would in the plugin.
+///
+/// # Safety
+///
+/// Each argument must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_event_focus(
+    tab: *const c_char,
+    title: *const c_char,
+    command: *const c_char,
+) -> *mut CrumbeezEvent {
+    let Some(title) = str_arg(title) else {
+        return ptr::null_mut();
+    };
+    let command = str_arg(command);
+    event(KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+        tab_name: str_arg(tab).map(str::to_string),
+        pane_title: title.to_string(),
+        command: command.map(str::to_string),
+        is_plugin: false,
+        role: ProfileRegistry::default().role_for(title, command),
+        ..PaneFocusedEvent::default()
+    }))
+}
+
+/// The event as the plugin logs it, e.g. `shortcut Ctrl+r` or
+/// `typed "ls"`.  Free with [`crumbeez_string_free`].
+///
+/// # Safety
+///
+/// `event` must be NULL or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_event_to_string(event: *const CrumbeezEvent) -> *mut c_char {
+    let Some(event) = event.as_ref() else {
+        return ptr::null_mut();
+    };
+    // Typed text may hold a NUL; C can't.
+    let text = event.0.to_string().replace('\0', "");
+    CString::new(text).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// # Safety
+///
+/// `event` must be NULL or a live handle, not used again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_event_free(event: *mut CrumbeezEvent) {
+    if !event.is_null() {
+        drop(Box::from_raw(event));
+    }
+}
+
+/// # Safety
+///
+/// `string` must be NULL or a string returned by this library, not used
+/// again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+// ── Logs ─────────────────────────────────────────────────────────
+
+/// An empty log.
+#[no_mangle]
+pub extern "C" fn crumbeez_log_new() -> *mut CrumbeezLog {
+    boxed(CrumbeezLog(EventLog::new()))
+}
+
+/// # Safety
+///
+/// `log` must be NULL or a live handle, not used again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_log_free(log: *mut CrumbeezLog) {
+    if !log.is_null() {
+        drop(Box::from_raw(log));
+    }
+}
+
+/// Append a copy of `event`, timestamped `timestamp_ms` since the Unix
+/// epoch.  The caller keeps `event`.
+///
+/// # Safety
+///
+/// `log` and `event` must each be NULL or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_log_push_event(
+    log: *mut CrumbeezLog,
+    event: *const CrumbeezEvent,
+    timestamp_ms: u64,
+) -> CrumbeezStatus {
+    let (Some(log), Some(event)) = (log.as_mut(), event.as_ref()) else {
+        return CrumbeezStatus::NullArgument;
+    };
+    log.0.append(event.0.clone(), timestamp_ms);
+    CrumbeezStatus::Ok
+}
+
+/// Entries in the log, or 0 for NULL.
+///
+/// # Safety
+///
+/// `log` must be NULL or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_log_len(log: *const CrumbeezLog) -> usize {
+    log.as_ref().map_or(0, |log| log.0.total_count())
+}
+
+/// A copy of the event of entry `index`, oldest first, or NULL if there's
+/// no such entry.
+///
+/// # Safety
+///
+/// `log` must be NULL or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_log_event(
+    log: *const CrumbeezLog,
+    index: usize,
+) -> *mut CrumbeezEvent {
+    log.as_ref()
+        .and_then(|log| log.0.entries().nth(index))
+        .map_or(ptr::null_mut(), |entry| event(entry.event.clone()))
+}
+
+/// Store the timestamp of entry `index` in `out`.
+///
+/// # Safety
+///
+/// `log` must be NULL or a live handle, and `out` NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_log_timestamp(
+    log: *const CrumbeezLog,
+    index: usize,
+    out: *mut u64,
+) -> CrumbeezStatus {
+    let (Some(log), false) = (log.as_ref(), out.is_null()) else {
+        return CrumbeezStatus::NullArgument;
+    };
+    match log.0.entries().nth(index) {
+        Some(entry) => {
+            *out = entry.timestamp_ms;
+            CrumbeezStatus::Ok
+        }
+        None => CrumbeezStatus::OutOfRange,
+    }
+}
+
+/// Encode the log in the format of the plugin's `.crumbeez/events.bin`
+/// into `out`, to be freed with [`crumbeez_bytes_free`].
+///
+/// # Safety
+///
+/// `log` must be NULL or a live handle, and `out` NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_log_serialize(
+    log: *const CrumbeezLog,
+    out: *mut CrumbeezBytes,
+) -> CrumbeezStatus {
+    let (Some(log), false) = (log.as_ref(), out.is_null()) else {
+        return CrumbeezStatus::NullArgument;
+    };
+    let Ok(bytes) = log.0.serialize() else {
+        return CrumbeezStatus::EncodeFailed;
+    };
+    let bytes = Box::into_raw(bytes.into_boxed_slice());
+    *out = CrumbeezBytes {
+        data: bytes.cast(),
+        len: bytes.len(),
+    };
+    CrumbeezStatus::Ok
+}
+
+/// Decode a log written by the plugin or [`crumbeez_log_serialize`],
+/// storing the new handle in `out`.
+///
+/// # Safety
+///
+/// `data` must be NULL or point to `len` readable bytes, and `out` NULL or
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_log_deserialize(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut CrumbeezLog,
+) -> CrumbeezStatus {
+    if data.is_null() || out.is_null() {
+        return CrumbeezStatus::NullArgument;
+    }
+    match EventLog::deserialize(std::slice::from_raw_parts(data, len)) {
+        Ok(log) => {
+            *out = boxed(CrumbeezLog(log));
+            CrumbeezStatus::Ok
+        }
+        Err(_) => CrumbeezStatus::DecodeFailed,
+    }
+}
+
+/// # Safety
+///
+/// `bytes` must have come from this library and not been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn crumbeez_bytes_free(bytes: CrumbeezBytes) {
+    if !bytes.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            bytes.data, bytes.len,
+        )));
+    }
+}
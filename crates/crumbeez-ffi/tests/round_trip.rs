@@ -0,0 +1,154 @@
+//! Drives the C ABI the way a C caller would, and checks that logs written
+//! through it read back, and that the header declares all of it.
+//!
+//! Run natively (the default build target is wasm):
+//!
+//! ```sh
+//! cargo test-native -p crumbeez-ffi --test round_trip
+//! ```
+
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use crumbeez::*;
+
+/// 2024-03-01 09:15:00 UTC
+const START_MS: u64 = 1_709_284_500_000;
+
+fn c(text: &str) -> CString {
+    CString::new(text).unwrap()
+}
+
+/// The event's display text, freeing both.
+unsafe fn take_string(event: *mut CrumbeezEvent) -> String {
+    assert!(!event.is_null());
+    let raw = crumbeez_event_to_string(event);
+    let text = CStr::from_ptr(raw).to_str().unwrap().to_string();
+    crumbeez_string_free(raw);
+    crumbeez_event_free(event);
+    text
+}
+
+unsafe fn classified(key: &str, modifiers: u8) -> String {
+    take_string(crumbeez_classify(c(key).as_ptr(), modifiers))
+}
+
+#[test]
+fn classifies_keys_by_name() {
+    unsafe {
+        assert_eq!(classified("x", 0), "typed \"x\"");
+        assert_eq!(classified("r", CRUMBEEZ_MOD_CTRL), "shortcut Ctrl+r");
+        assert_eq!(
+            classified("Left", CRUMBEEZ_MOD_ALT | CRUMBEEZ_MOD_SHIFT),
+            "shortcut Alt+Shift+←"
+        );
+        assert_eq!(classified("F5", 0), "F5");
+        assert!(crumbeez_classify(c("F13").as_ptr(), 0).is_null());
+        assert!(crumbeez_classify(c("Hyper").as_ptr(), 0).is_null());
+        assert!(crumbeez_classify(ptr::null(), 0).is_null());
+    }
+}
+
+#[test]
+fn null_arguments_are_refused() {
+    unsafe {
+        let mut bytes = CrumbeezBytes {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            crumbeez_log_serialize(ptr::null(), &mut bytes),
+            CrumbeezStatus::NullArgument
+        );
+        assert_eq!(
+            crumbeez_log_push_event(ptr::null_mut(), ptr::null(), 0),
+            CrumbeezStatus::NullArgument
+        );
+        assert_eq!(crumbeez_log_len(ptr::null()), 0);
+        assert!(crumbeez_event_text(c("").as_ptr()).is_null());
+        assert!(crumbeez_event_focus(ptr::null(), ptr::null(), ptr::null()).is_null());
+        // Freeing NULL is a no-op, as with free().
+        crumbeez_event_free(ptr::null_mut());
+        crumbeez_log_free(ptr::null_mut());
+        crumbeez_string_free(ptr::null_mut());
+        crumbeez_bytes_free(bytes);
+    }
+}
+
+#[test]
+fn log_round_trips() {
+    unsafe {
+        let log = crumbeez_log_new();
+        let events = [
+            crumbeez_event_focus(
+                c("code").as_ptr(),
+                c("nvim src/main.rs").as_ptr(),
+                c("nvim").as_ptr(),
+            ),
+            crumbeez_event_text(c("fn main() {}").as_ptr()),
+            crumbeez_classify(c("Esc").as_ptr(), 0),
+            crumbeez_classify(c("s").as_ptr(), CRUMBEEZ_MOD_CTRL),
+        ];
+        for (i, event) in events.iter().enumerate() {
+            let at = START_MS + i as u64 * 1000;
+            assert_eq!(crumbeez_log_push_event(log, *event, at), CrumbeezStatus::Ok);
+        }
+
+        let mut bytes = CrumbeezBytes {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(crumbeez_log_serialize(log, &mut bytes), CrumbeezStatus::Ok);
+        let mut read: *mut CrumbeezLog = ptr::null_mut();
+        assert_eq!(
+            crumbeez_log_deserialize(bytes.data, bytes.len, &mut read),
+            CrumbeezStatus::Ok
+        );
+        crumbeez_bytes_free(bytes);
+
+        let len = events.len();
+        assert_eq!(crumbeez_log_len(read), len);
+        for (i, event) in events.into_iter().enumerate() {
+            let mut at = 0;
+            assert_eq!(crumbeez_log_timestamp(read, i, &mut at), CrumbeezStatus::Ok);
+            assert_eq!(at, START_MS + i as u64 * 1000);
+            assert_eq!(take_string(crumbeez_log_event(read, i)), take_string(event));
+        }
+        let mut at = 0;
+        assert_eq!(
+            crumbeez_log_timestamp(read, len, &mut at),
+            CrumbeezStatus::OutOfRange
+        );
+        assert!(crumbeez_log_event(read, len).is_null());
+
+        let garbage = b"not a log";
+        let mut bad: *mut CrumbeezLog = ptr::null_mut();
+        assert_eq!(
+            crumbeez_log_deserialize(garbage.as_ptr(), garbage.len(), &mut bad),
+            CrumbeezStatus::DecodeFailed
+        );
+        assert!(bad.is_null());
+
+        crumbeez_log_free(read);
+        crumbeez_log_free(log);
+    }
+}
+
+#[test]
+fn header_declares_every_function() {
+    let header = include_str!("../include/crumbeez.h");
+    let source = include_str!("../src/lib.rs");
+    let exported: Vec<&str> = source
+        .lines()
+        .filter_map(|line| line.split("extern \"C\" fn ").nth(1))
+        .filter_map(|rest| rest.split('(').next())
+        .collect();
+    assert!(!exported.is_empty());
+    for name in exported {
+        assert!(
+            header.contains(&format!("{}(", name)),
+            "{} is missing from include/crumbeez.h; rerun cbindgen",
+            name
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Benchmarks for [`KeystrokeActivity::push_event`] and
+//! [`KeystrokeActivity::push_char`], one of which runs for every keystroke.
+//!
+//! Run natively (the default build target is wasm):
+//!
+//! ```sh
+//! cargo bench-native -p crumbeez-lib --bench keystroke_activity
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use crumbeez_lib::{
+    EditControlEvent, KeystrokeActivity, KeystrokeEvent, NavDirection, NavigationEvent,
+};
+
+fn typed(c: char) -> KeystrokeEvent {
+    KeystrokeEvent::TextTyped(c.to_string())
+}
+
+fn nav(direction: NavDirection) -> KeystrokeEvent {
+    KeystrokeEvent::Navigation(NavigationEvent {
+        direction,
+        count: 1,
+        with_shift: false,
+        with_ctrl: false,
+    })
+}
+
+/// A line of typing with a few corrections, then Enter.
+fn typing_session() -> Vec<KeystrokeEvent> {
+    let mut events: Vec<_> = "cargo bench --workspace".chars().map(typed).collect();
+    events.push(KeystrokeEvent::EditControl(EditControlEvent::Backspace {
+        count: 1,
+    }));
+    events.push(typed('e'));
+    events.extend(std::iter::repeat_n(nav(NavDirection::Left), 5));
+    events.push(typed('x'));
+    events.push(KeystrokeEvent::EditControl(EditControlEvent::Delete {
+        count: 1,
+    }));
+    events.push(nav(NavDirection::End));
+    events.push(KeystrokeEvent::EditControl(EditControlEvent::Enter));
+    events
+}
+
+fn bench_push_event(c: &mut Criterion) {
+    c.bench_function("push_event/append_char", |b| {
+        b.iter_batched_ref(
+            || {
+                let mut activity = KeystrokeActivity::new();
+                activity.push_event(typed('a'));
+                activity
+            },
+            |activity| activity.push_event(black_box(typed('b'))),
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("push_char/append_char", |b| {
+        b.iter_batched_ref(
+            || {
+                let mut activity = KeystrokeActivity::new();
+                activity.push_char('a');
+                activity
+            },
+            |activity| activity.push_char(black_box('b')),
+            BatchSize::SmallInput,
+        )
+    });
+
+    let session = typing_session();
+    c.bench_function("push_event/typing_session", |b| {
+        b.iter_batched(
+            || session.clone(),
+            |events| {
+                let mut activity = KeystrokeActivity::new();
+                for event in events {
+                    activity.push_event(event);
+                }
+                activity
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    // Steady state: the ring buffer is full, so every append also evicts.
+    c.bench_function("push_event/full_ring_buffer", |b| {
+        let mut activity = KeystrokeActivity::new();
+        for _ in 0..crumbeez_lib::KEYSTROKE_LOG_CAPACITY {
+            activity.push_event(KeystrokeEvent::Escape);
+        }
+        b.iter(|| activity.push_event(black_box(KeystrokeEvent::Escape)))
+    });
+}
+
+criterion_group!(benches, bench_push_event);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+//! Benchmarks for [`Summary::from_events`] and rendering it, over the
+//! canned [workday](crumbeez_lib::fixtures::workday).
+//!
+//! Run natively (the default build target is wasm):
+//!
+//! ```sh
+//! cargo bench-native -p crumbeez-lib --features fixtures --bench summary
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use crumbeez_lib::fixtures::{workday, FIXTURE_START_MS};
+use crumbeez_lib::Summary;
+
+fn bench_summary(c: &mut Criterion) {
+    let entries = workday(FIXTURE_START_MS);
+    c.bench_function("summary/from_events", |b| {
+        b.iter_batched(
+            || entries.clone(),
+            |entries| Summary::from_events(black_box(entries).into_iter()),
+            BatchSize::SmallInput,
+        )
+    });
+    let summary = Summary::from_events(entries.into_iter());
+    c.bench_function("summary/to_markdown", |b| {
+        b.iter(|| black_box(&summary).to_markdown())
+    });
+}
+
+criterion_group!(benches, bench_summary);
+criterion_main!(benches);
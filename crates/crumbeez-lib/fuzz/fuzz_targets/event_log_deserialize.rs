@@ -0,0 +1,21 @@
+//! Feed arbitrary bytes to `EventLog::deserialize`.  It must return an error
+//! rather than panic, and anything it accepts must survive a round trip.
+//!
+//! ```sh
+//! cd crates/crumbeez-lib && cargo +nightly fuzz run event_log_deserialize
+//! ```
+
+#![no_main]
+
+use crumbeez_lib::EventLog;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(log) = EventLog::deserialize(data) else {
+        return;
+    };
+    let bytes = log.serialize().expect("a deserialized log must serialize");
+    let again = EventLog::deserialize(&bytes).expect("a serialized log must deserialize");
+    assert_eq!(again.total_count(), log.total_count());
+    assert_eq!(again.unconsumed_count(), log.unconsumed_count());
+});
@@ -0,0 +1,213 @@
+//! Scrubbing an event log so it can be attached to a bug report.
+//!
+//! The shape of the log — event types, timings, counts, text lengths, and
+//! which pane was focused when — is kept, because that's what reproduces
+//! most bugs.  The content is not:
+//!
+//! - Typed text, bookmark names, and reasons given for risky commands
+//!   become lorem ipsum of the same length, with whitespace kept in
+//!   place.
+//! - Pane titles, tab names, and session names become stable pseudonyms
+//!   (`pane-1`, `tab-1`, `session-1`), numbered in order of first
+//!   appearance, so focus changes between the same panes still line up.
+//!   Titles a pane changes to while focused share the numbering.
+//! - Pane commands, commands imported from shell history, and risky
+//!   commands keep only the program's basename; each argument is replaced
+//!   by a hash, so repeated paths stay recognisable as repeats.
+//! - Pane and command working directories, ssh hosts, and containers
+//!   become pseudonyms too (`dir-1`, `host-1`, `container-1`).  Pane IDs,
+//!   tab positions, and process IDs say nothing about the user and are
+//!   kept.
+//! - Session context values, like the active virtualenv, become
+//!   pseudonyms (`value-1`) under their probe's key, as do the clusters and
+//!   profiles environment switches switch to, and the session's tags
+//!   (`tag-1`).
+//!
+//! Shortcuts and other keys carry no user content and pass through as-is.
+
+use std::collections::BTreeMap;
+
+use crate::{fnv1a, CommandOutcome, EventLog, KeystrokeEvent, LogEntry, PaneFocusedEvent};
+
+const LOREM: &str = "loremipsumdolorsitametconsecteturadipiscingelitseddoeiusmodtemporincididuntutlaboreetdoloremagnaaliqua";
+
+/// Anonymize a whole log, keeping its consumed position and sequence numbers.
+pub fn anonymize(log: &EventLog) -> EventLog {
+    let mut anonymizer = Anonymizer::new();
+    log.map_entries(|entry| anonymizer.entry(entry))
+}
+
+/// Rewrites events one at a time.  Pseudonyms are assigned per instance, so
+/// use one `Anonymizer` for every entry of a log.
+#[derive(Debug, Default)]
+pub struct Anonymizer {
+    pane_titles: BTreeMap<String, String>,
+    tab_names: BTreeMap<String, String>,
+    sessions: BTreeMap<String, String>,
+    cwds: BTreeMap<String, String>,
+    hosts: BTreeMap<String, String>,
+    containers: BTreeMap<String, String>,
+    context_values: BTreeMap<String, String>,
+    tags: BTreeMap<String, String>,
+    /// Position in [`LOREM`] where the next replacement text starts.
+    lorem_pos: usize,
+}
+
+impl Anonymizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entry(&mut self, entry: &LogEntry) -> LogEntry {
+        LogEntry {
+            event: self.event(&entry.event),
+            timestamp_ms: entry.timestamp_ms,
+            corrections: entry.corrections.clone(),
+        }
+    }
+
+    pub fn event(&mut self, event: &KeystrokeEvent) -> KeystrokeEvent {
+        match event {
+            KeystrokeEvent::TextTyped(text) => KeystrokeEvent::TextTyped(self.text(text)),
+            KeystrokeEvent::LongText {
+                preview,
+                chars,
+                file,
+            } => KeystrokeEvent::LongText {
+                preview: self.text(preview),
+                chars: *chars,
+                file: file.clone(),
+            },
+            KeystrokeEvent::CommandExecuted {
+                command: cmd,
+                shell,
+                outcome,
+            } => KeystrokeEvent::CommandExecuted {
+                command: command(cmd),
+                shell: shell.clone(),
+                outcome: outcome.as_ref().map(|outcome| CommandOutcome {
+                    cwd: outcome
+                        .cwd
+                        .as_deref()
+                        .map(|cwd| pseudonym(&mut self.cwds, "dir", cwd)),
+                    ..outcome.clone()
+                }),
+            },
+            KeystrokeEvent::RiskyCommand {
+                command: cmd,
+                pattern,
+                reason,
+            } => KeystrokeEvent::RiskyCommand {
+                command: command(cmd),
+                pattern: pattern.clone(),
+                reason: reason.as_deref().map(|reason| self.text(reason)),
+            },
+            KeystrokeEvent::Annotation(text) => KeystrokeEvent::Annotation(self.text(text)),
+            KeystrokeEvent::FuzzySelected { query, moves } => KeystrokeEvent::FuzzySelected {
+                query: self.text(query),
+                moves: *moves,
+            },
+            KeystrokeEvent::ReplInput { language, code } => KeystrokeEvent::ReplInput {
+                language: language.clone(),
+                code: self.text(code),
+            },
+            KeystrokeEvent::PromptToAgent(text) => KeystrokeEvent::PromptToAgent(self.text(text)),
+            KeystrokeEvent::AgentStatus(text) => KeystrokeEvent::AgentStatus(self.text(text)),
+            KeystrokeEvent::PaneTitleChanged(title) => {
+                KeystrokeEvent::PaneTitleChanged(pseudonym(&mut self.pane_titles, "pane", title))
+            }
+            KeystrokeEvent::TabClosed(name) => {
+                KeystrokeEvent::TabClosed(pseudonym(&mut self.tab_names, "tab", name))
+            }
+            KeystrokeEvent::SessionRenamed(name) => {
+                KeystrokeEvent::SessionRenamed(pseudonym(&mut self.sessions, "session", name))
+            }
+            KeystrokeEvent::PaneFocused(pane) => KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+                tab_name: pane
+                    .tab_name
+                    .as_deref()
+                    .map(|tab| pseudonym(&mut self.tab_names, "tab", tab)),
+                pane_title: pseudonym(&mut self.pane_titles, "pane", &pane.pane_title),
+                command: pane.command.as_deref().map(command),
+                is_plugin: pane.is_plugin,
+                role: pane.role,
+                pane_id: pane.pane_id,
+                tab_index: pane.tab_index,
+                cwd: pane
+                    .cwd
+                    .as_ref()
+                    .map(|cwd| pseudonym(&mut self.cwds, "dir", &cwd.to_string_lossy()).into()),
+                pid: pane.pid,
+                host: pane
+                    .host
+                    .as_deref()
+                    .map(|host| pseudonym(&mut self.hosts, "host", host)),
+                container: pane
+                    .container
+                    .as_deref()
+                    .map(|name| pseudonym(&mut self.containers, "container", name)),
+            }),
+            KeystrokeEvent::EnvironmentChanged { kind, target } => {
+                KeystrokeEvent::EnvironmentChanged {
+                    kind: *kind,
+                    target: pseudonym(&mut self.context_values, "value", target),
+                }
+            }
+            KeystrokeEvent::SessionContext(values) => KeystrokeEvent::SessionContext(
+                values
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = pseudonym(&mut self.context_values, "value", value);
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            ),
+            KeystrokeEvent::SessionTagged(tags) => KeystrokeEvent::SessionTagged(
+                tags.iter()
+                    .map(|tag| pseudonym(&mut self.tags, "tag", tag))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// Lorem ipsum with the same number of characters as `text`, and
+    /// whitespace in the same places.
+    fn text(&mut self, text: &str) -> String {
+        let lorem = LOREM.as_bytes();
+        text.chars()
+            .map(|c| {
+                if c.is_whitespace() {
+                    c
+                } else {
+                    let replacement = lorem[self.lorem_pos % lorem.len()] as char;
+                    self.lorem_pos += 1;
+                    replacement
+                }
+            })
+            .collect()
+    }
+}
+
+/// The pseudonym for `name`, assigning the next free one on first sight.
+fn pseudonym(names: &mut BTreeMap<String, String>, prefix: &str, name: &str) -> String {
+    let next = names.len() + 1;
+    names
+        .entry(name.to_string())
+        .or_insert_with(|| format!("{prefix}-{next}"))
+        .clone()
+}
+
+/// Keep the program's basename and hash every argument, e.g.
+/// `/usr/bin/nvim ~/notes.md` becomes `nvim #3f2a9c0e`.
+fn command(command: &str) -> String {
+    let mut words = command.split_whitespace();
+    let Some(program) = words.next() else {
+        return String::new();
+    };
+    let mut out = program.rsplit('/').next().unwrap_or(program).to_string();
+    for arg in words {
+        out.push_str(&format!(" #{:08x}", fnv1a(arg.as_bytes()) as u32));
+    }
+    out
+}
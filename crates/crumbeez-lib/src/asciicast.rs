@@ -0,0 +1,64 @@
+//! Typing sessions as [asciicast v2] recordings, to replay in a browser
+//! with asciinema-player.
+//!
+//! The recording is the [reconstructed](crate::replay) text appearing over
+//! time, not the terminal's real output: each focus change prints a dimmed
+//! banner naming the pane, then the pane's unsubmitted line, and typing,
+//! erasing, and Enter play back as they were logged.  Bookmarks show as
+//! their own highlighted line.
+//!
+//! [asciicast v2]: https://docs.asciinema.org/manual/asciicast/v2/
+
+use std::fmt::Write as _;
+
+use crate::broadcast::json_string;
+use crate::replay::{reconstruct, Frame, FrameChange};
+use crate::LogEntry;
+
+/// Terminal size the recording claims.  Lines wrap at the player's width.
+pub const CAST_WIDTH: u16 = 100;
+pub const CAST_HEIGHT: u16 = 30;
+
+/// The recording of `entries` (oldest first) from `start_ms` to `end_ms`,
+/// as asciicast v2: a header line and one line per output event.
+pub fn to_asciicast<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    start_ms: u64,
+    end_ms: u64,
+) -> String {
+    let frames = reconstruct(entries, start_ms, end_ms);
+    let origin_ms = frames.first().map_or(start_ms, |f| f.timestamp_ms);
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{{\"version\":2,\"width\":{},\"height\":{},\"timestamp\":{},\"title\":\"crumbeez replay\"}}",
+        CAST_WIDTH,
+        CAST_HEIGHT,
+        origin_ms / 1000
+    );
+    for Frame {
+        timestamp_ms,
+        change,
+    } in &frames
+    {
+        let output = match change {
+            FrameChange::Focus { pane, line } => {
+                format!("\r\n\x1b[2m── {} ──\x1b[0m\r\n$ {}", pane, crlf(line))
+            }
+            FrameChange::Char('\n') => "\r\n".to_string(),
+            FrameChange::Char(c) => c.to_string(),
+            FrameChange::Erase(n) => "\x08 \x08".repeat(*n),
+            FrameChange::Submit => "\r\n$ ".to_string(),
+            FrameChange::Bookmark(name) => format!("\r\n\x1b[1;33m📌 {}\x1b[0m\r\n$ ", name),
+        };
+        let seconds = timestamp_ms.saturating_sub(origin_ms) as f64 / 1000.0;
+        let _ = writeln!(out, "[{:.3}, \"o\", {}]", seconds, json_string(&output));
+    }
+    out
+}
+
+/// `text` with line breaks as the terminal needs them.
+fn crlf(text: &str) -> String {
+    text.replace('\n', "\r\n")
+}
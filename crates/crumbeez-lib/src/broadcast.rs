@@ -0,0 +1,127 @@
+//! The message other plugins receive for each breadcrumb.
+//!
+//! Subscribed plugins get every entry as it's logged, as a pipe message
+//! named [`BROADCAST_PIPE`] whose payload is one JSON object:
+//!
+//! ```json
+//! {"v":1,"seq":42,"timestamp_ms":1709284500000,"type":"TextTyped",
+//!  "description":"typed \"cargo test\"","text":"cargo test"}
+//! ```
+//!
+//! - `v` — [`ENVELOPE_VERSION`]; fields are only added within a version.
+//! - `seq` — the entry's sequence number in the log, increasing by one per
+//!   entry, so a gap means missed messages.
+//! - `timestamp_ms` — Unix time in milliseconds.
+//! - `type` — the [`KeystrokeEvent`] variant, e.g. `PaneFocused`.
+//! - `description` — a one-line human-readable rendering.
+//! - `text` — for events that carry text (typed text, prompts, REPL input,
+//!   fuzzy queries, bookmarks, agent statuses, pane titles, closed tabs'
+//!   and renamed sessions' names, commands from shell history, what an
+//!   environment switch switched to, risky commands), that text after
+//!   redaction.
+//! - `pane` — for `PaneFocused`, an object with `tab`, `title`, `command`
+//!   (each a string or `null`), `role`, and `id`, `tab_index`, `cwd`,
+//!   `pid`, `host` — the remote host of an ssh session — and `container`
+//!   (`null` where unknown).
+//! - `file` — for `SnapshotTaken`, the snapshot's path relative to the
+//!   `.crumbeez` directory.
+//! - `context` — for `SessionContext`, an object of the probes' values by
+//!   key, e.g. `{"VIRTUAL_ENV":"/home/me/app/.venv"}`.
+//!
+//! The JSON is written by hand; it's small and fixed, and not worth a
+//! serializer in the wasm module.
+
+use std::fmt::Write as _;
+
+use crate::summary::event_type_name;
+use crate::{KeystrokeEvent, LogEntry};
+
+/// Name of the pipe messages carrying breadcrumbs.
+pub const BROADCAST_PIPE: &str = "crumbeez-events";
+
+/// Version of the envelope format, the `v` field.
+pub const ENVELOPE_VERSION: u32 = 1;
+
+/// The JSON envelope for `entry`, which has sequence number `seq`.
+pub fn envelope_json(seq: u64, entry: &LogEntry) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "{{\"v\":{},\"seq\":{},\"timestamp_ms\":{},\"type\":{},\"description\":{}",
+        ENVELOPE_VERSION,
+        seq,
+        entry.timestamp_ms,
+        json_string(event_type_name(&entry.event)),
+        json_string(&entry.event.to_string()),
+    );
+    match &entry.event {
+        KeystrokeEvent::TextTyped(text)
+        | KeystrokeEvent::LongText { preview: text, .. }
+        | KeystrokeEvent::PromptToAgent(text)
+        | KeystrokeEvent::Annotation(text)
+        | KeystrokeEvent::AgentStatus(text)
+        | KeystrokeEvent::PaneTitleChanged(text)
+        | KeystrokeEvent::TabClosed(text)
+        | KeystrokeEvent::SessionRenamed(text)
+        | KeystrokeEvent::CommandExecuted { command: text, .. }
+        | KeystrokeEvent::EnvironmentChanged { target: text, .. }
+        | KeystrokeEvent::RiskyCommand { command: text, .. }
+        | KeystrokeEvent::FuzzySelected { query: text, .. }
+        | KeystrokeEvent::ReplInput { code: text, .. } => {
+            let _ = write!(out, ",\"text\":{}", json_string(text));
+        }
+        KeystrokeEvent::PaneFocused(pane) => {
+            let optional =
+                |value: &Option<String>| value.as_deref().map_or("null".into(), json_string);
+            let number = |value: Option<u64>| value.map_or("null".into(), |n| n.to_string());
+            let _ = write!(
+                out,
+                ",\"pane\":{{\"tab\":{},\"title\":{},\"command\":{},\"role\":{},\"id\":{},\"tab_index\":{},\"cwd\":{},\"pid\":{},\"host\":{},\"container\":{}}}",
+                optional(&pane.tab_name),
+                json_string(&pane.pane_title),
+                optional(&pane.command),
+                json_string(pane.role.name()),
+                number(pane.pane_id.map(u64::from)),
+                number(pane.tab_index.map(|i| i as u64)),
+                optional(&pane.cwd.as_ref().map(|cwd| cwd.to_string_lossy().into_owned())),
+                number(pane.pid.map(u64::from)),
+                optional(&pane.host),
+                optional(&pane.container),
+            );
+        }
+        KeystrokeEvent::SnapshotTaken(path) => {
+            let _ = write!(out, ",\"file\":{}", json_string(path));
+        }
+        KeystrokeEvent::SessionContext(values) => {
+            let values: Vec<String> = values
+                .iter()
+                .map(|(key, value)| format!("{}:{}", json_string(key), json_string(value)))
+                .collect();
+            let _ = write!(out, ",\"context\":{{{}}}", values.join(","));
+        }
+        _ => {}
+    }
+    out.push('}');
+    out
+}
+
+/// `text` as a JSON string literal.
+pub(crate) fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,123 @@
+//! A month of one project's activity as a calendar heatmap.
+//!
+//! Built from the day records in the shared [`SummaryIndex`], so past
+//! months show without reading any event log: a cell per day, Monday
+//! first, shaded by how many events the day had relative to the month's
+//! busiest.
+
+use std::path::Path;
+
+use crate::summary::{days_from_ymd, format_ymd};
+use crate::SummaryIndex;
+
+const MS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// Cell shades from an idle day to the busiest.
+const SHADES: [&str; 5] = ["··", "░░", "▒▒", "▓▓", "██"];
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthHeatmap {
+    /// `YYYY-MM`.
+    pub month: String,
+    /// Events on each day of the month, from the 1st.
+    pub events: Vec<usize>,
+    /// Days since the epoch of the 1st.
+    first_day: i64,
+}
+
+impl MonthHeatmap {
+    /// The (UTC) month containing `day_ms`, from `index`'s records for the
+    /// project at `root`.
+    pub fn from_index(index: &SummaryIndex, root: &Path, day_ms: u64) -> Self {
+        let month = format_ymd(day_ms)[..7].to_string();
+        let first_day = month_start(&month);
+        let next = format_ymd((first_day as u64 + 31) * MS_PER_DAY);
+        let mut events = vec![0; (month_start(&next[..7]) - first_day) as usize];
+        for record in index.records.iter().filter(|r| r.root == root) {
+            let Some(day) = days_from_ymd(&record.day) else {
+                continue;
+            };
+            if let Some(count) = events.get_mut((day - first_day) as usize) {
+                *count += record.events;
+            }
+        }
+        Self {
+            month,
+            events,
+            first_day,
+        }
+    }
+
+    /// Start of the 1st, in milliseconds.
+    pub fn start_ms(&self) -> u64 {
+        self.first_day as u64 * MS_PER_DAY
+    }
+
+    /// A time in the month before, or `None` for January 1970.
+    pub fn previous_ms(&self) -> Option<u64> {
+        self.start_ms().checked_sub(MS_PER_DAY)
+    }
+
+    /// A time in the month after.
+    pub fn next_ms(&self) -> u64 {
+        self.start_ms() + self.events.len() as u64 * MS_PER_DAY
+    }
+
+    /// Days with any events.
+    pub fn active_days(&self) -> usize {
+        self.events.iter().filter(|&&n| n > 0).count()
+    }
+
+    pub fn total_events(&self) -> usize {
+        self.events.iter().sum()
+    }
+
+    /// `March 2024`.
+    pub fn title(&self) -> String {
+        let month: usize = self.month[5..].parse().unwrap_or(1);
+        format!("{} {}", MONTH_NAMES[month - 1], &self.month[..4])
+    }
+
+    /// The calendar: a header of weekdays, a row per week, and a legend.
+    pub fn to_lines(&self) -> Vec<String> {
+        let max = self.events.iter().copied().max().unwrap_or(0);
+        let shade = |count: usize| match count {
+            0 => SHADES[0],
+            // Round up, so a day with a single event still shows.
+            _ => SHADES[(count * (SHADES.len() - 1)).div_ceil(max)],
+        };
+        // 1970-01-01 was a Thursday.
+        let offset = (self.first_day + 3).rem_euclid(7) as usize;
+        let mut cells: Vec<&str> = vec!["  "; offset];
+        cells.extend(self.events.iter().map(|&count| shade(count)));
+
+        let mut lines = vec!["Mo Tu We Th Fr Sa Su".to_string()];
+        lines.extend(
+            cells
+                .chunks(7)
+                .map(|week| week.join(" ").trim_end().to_string()),
+        );
+        lines.push(format!("less {} more", SHADES.join(" ")));
+        lines
+    }
+}
+
+/// Days since the epoch of the 1st of `month`, `YYYY-MM`.
+fn month_start(month: &str) -> i64 {
+    days_from_ymd(&format!("{}-01", month)).unwrap_or(0)
+}
@@ -0,0 +1,142 @@
+//! Folding multi-key sequences, like tmux's `Ctrl+B c` or Vim's `g g`, into
+//! one [`KeystrokeEvent::ChordSequence`].
+//!
+//! Sequences come from `chord` lines in the project config, each key
+//! written as a summary shows it: a character, `Esc`, `Tab`, `Enter`, `F5`,
+//! or a shortcut like `Ctrl+B`.  Letters after a modifier match either
+//! case, since terminals don't tell `Ctrl+b` from `Ctrl+B`.
+//!
+//! While the keys so far could still become a chord, [`ChordDetector`]
+//! holds them back.  Once they can't, or [`CHORD_TIMEOUT_MS`] passes
+//! between keys, they're released to be logged as usual.  A sequence that's
+//! both a chord and the start of a longer one is folded as the shorter.
+
+use crate::{AppRole, EditControlEvent, KeystrokeEvent};
+
+/// Longest pause between the keys of a chord.
+pub const CHORD_TIMEOUT_MS: u64 = 1000;
+
+/// A key sequence to fold, from a `chord` line in the config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChordBinding {
+    /// The keys, separated by single spaces.
+    pub keys: String,
+    /// Only fold in panes with this role; everywhere if `None`.
+    pub role: Option<AppRole>,
+}
+
+impl ChordBinding {
+    /// The binding for `keys`, or `None` unless it's at least two keys.
+    pub fn new(keys: &str, role: Option<AppRole>) -> Option<Self> {
+        let keys: Vec<&str> = keys.split_whitespace().collect();
+        (keys.len() >= 2).then(|| Self {
+            keys: keys.join(" "),
+            role,
+        })
+    }
+}
+
+/// Recognizes chords in a stream of classified keys.  Create a new one on
+/// each focus change.
+#[derive(Debug, Default)]
+pub struct ChordDetector {
+    /// Chords for the focused pane: their keys normalized, and as written.
+    chords: Vec<(Vec<String>, String)>,
+    /// Keys held back, with their normalized names.
+    pending: Vec<(KeystrokeEvent, String)>,
+    /// When the last key arrived.
+    last_ms: u64,
+}
+
+impl ChordDetector {
+    /// A detector for the `bindings` that apply to `role`.
+    pub fn new(bindings: &[ChordBinding], role: AppRole) -> Self {
+        Self {
+            chords: bindings
+                .iter()
+                .filter(|b| b.role.is_none_or(|r| r == role))
+                .map(|b| (b.keys.split(' ').map(normalize).collect(), b.keys.clone()))
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether any chords apply, so keys need to go through [`feed`].
+    ///
+    /// [`feed`]: Self::feed
+    pub fn is_active(&self) -> bool {
+        !self.chords.is_empty()
+    }
+
+    /// Take `event`, arriving at `now_ms`, and return the events to log
+    /// now, in order: none while a chord might be under way, the chord once
+    /// it's complete, or keys that turned out not to be one.
+    pub fn feed(&mut self, event: KeystrokeEvent, now_ms: u64) -> Vec<KeystrokeEvent> {
+        let mut ready = Vec::new();
+        if now_ms.saturating_sub(self.last_ms) > CHORD_TIMEOUT_MS {
+            ready.extend(self.flush());
+        }
+        self.last_ms = now_ms;
+        let Some(name) = key_name(&event) else {
+            ready.extend(self.flush());
+            ready.push(event);
+            return ready;
+        };
+
+        self.pending.push((event, normalize(&name)));
+        loop {
+            let keys: Vec<&str> = self.pending.iter().map(|(_, k)| k.as_str()).collect();
+            let starts = |chord: &[String]| {
+                chord.len() >= keys.len() && chord.iter().zip(&keys).all(|(a, b)| a == b)
+            };
+            if let Some((_, written)) = self
+                .chords
+                .iter()
+                .find(|(chord, _)| chord.len() == keys.len() && starts(chord))
+            {
+                let written = written.clone();
+                self.pending.clear();
+                ready.push(KeystrokeEvent::ChordSequence(written));
+                return ready;
+            }
+            if self.chords.iter().any(|(chord, _)| starts(chord)) {
+                return ready;
+            }
+            // Not a chord: release the oldest key and see whether the rest
+            // start one.
+            let (released, _) = self.pending.remove(0);
+            ready.push(released);
+            if self.pending.is_empty() {
+                return ready;
+            }
+        }
+    }
+
+    /// Release any held keys, e.g. before focus moves.
+    pub fn flush(&mut self) -> Vec<KeystrokeEvent> {
+        self.pending.drain(..).map(|(event, _)| event).collect()
+    }
+}
+
+/// The name of a key that can be part of a chord, as written in the config.
+fn key_name(event: &KeystrokeEvent) -> Option<String> {
+    match event {
+        KeystrokeEvent::Shortcut(shortcut) => Some(shortcut.to_string()),
+        KeystrokeEvent::TextTyped(text) if text.chars().count() == 1 => Some(text.clone()),
+        KeystrokeEvent::Escape => Some("Esc".to_string()),
+        KeystrokeEvent::FunctionKey(n) => Some(format!("F{}", n)),
+        KeystrokeEvent::EditControl(EditControlEvent::Tab) => Some("Tab".to_string()),
+        KeystrokeEvent::EditControl(EditControlEvent::Enter) => Some("Enter".to_string()),
+        _ => None,
+    }
+}
+
+/// `key` with a letter after a modifier in upper case.
+fn normalize(key: &str) -> String {
+    match key.rsplit_once('+') {
+        Some((modifiers, base)) if !modifiers.is_empty() && base.chars().count() == 1 => {
+            format!("{}+{}", modifiers, base.to_uppercase())
+        }
+        _ => key.to_string(),
+    }
+}
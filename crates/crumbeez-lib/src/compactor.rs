@@ -0,0 +1,115 @@
+//! Shrinking old parts of the event log.
+//!
+//! Once a day is over, most of its raw log is noise: every run of arrow
+//! keys, Caps Lock toggled on and off, text logged in pieces.  With
+//! `raw_days` set under `retention` in the config, the plugin keeps entries
+//! raw for that many days and then, once a day, passes the older ones
+//! through [`compact`] at the project's [`CompactionLevel`].
+//!
+//! At [`CompactionLevel::Keys`], what a [`Summary`](crate::Summary) of the
+//! entries says is unchanged apart from how many events it counts, since
+//! summaries add up navigation keys and skip system keys anyway.
+//! [`CompactionLevel::Text`] goes further and can change a summary: pieces
+//! of text it joins, like an editor command and the text typed after it,
+//! read as one.
+//!
+//! Merged entries keep the first one's timestamp, and only entries less
+//! than [`MAX_MERGE_GAP_MS`] apart are merged, so idle gaps stay where they
+//! were.
+
+use std::fmt;
+
+use crate::{CorrectionStats, KeystrokeEvent, LogEntry};
+
+/// Entries further apart than this are never merged.
+pub const MAX_MERGE_GAP_MS: u64 = 60 * 1000;
+
+/// How much [`compact`] throws away, each level including the one before
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum CompactionLevel {
+    /// Runs of the same navigation key become one entry with their count,
+    /// and system keys (Caps Lock, Num Lock, Print Screen, …) are dropped.
+    #[default]
+    Keys,
+    /// Text typed in pieces with nothing between them also becomes one
+    /// entry.
+    Text,
+}
+
+impl CompactionLevel {
+    pub const ALL: [Self; 2] = [Self::Keys, Self::Text];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Keys => "keys",
+            Self::Text => "text",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| level.name() == name)
+    }
+}
+
+impl fmt::Display for CompactionLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// `entries` (oldest first) with the noise `level` allows for merged or
+/// dropped.
+pub fn compact(
+    entries: impl IntoIterator<Item = LogEntry>,
+    level: CompactionLevel,
+) -> Vec<LogEntry> {
+    let mut out: Vec<LogEntry> = Vec::new();
+    for entry in entries {
+        if let KeystrokeEvent::SystemKey(_) = entry.event {
+            continue;
+        }
+        if let Some(last) = out.last_mut() {
+            if entry.timestamp_ms.saturating_sub(last.timestamp_ms) < MAX_MERGE_GAP_MS
+                && merge(last, &entry, level)
+            {
+                continue;
+            }
+        }
+        out.push(entry);
+    }
+    out
+}
+
+/// Fold `next` into `into` if `level` allows it.  Returns whether it did.
+fn merge(into: &mut LogEntry, next: &LogEntry, level: CompactionLevel) -> bool {
+    match (&mut into.event, &next.event) {
+        (KeystrokeEvent::Navigation(nav), KeystrokeEvent::Navigation(next_nav))
+            if nav.direction == next_nav.direction
+                && nav.with_shift == next_nav.with_shift
+                && nav.with_ctrl == next_nav.with_ctrl =>
+        {
+            nav.count += next_nav.count;
+            true
+        }
+        (KeystrokeEvent::TextTyped(text), KeystrokeEvent::TextTyped(next_text))
+            if level >= CompactionLevel::Text =>
+        {
+            if into.corrections.is_some() || next.corrections.is_some() {
+                let mut corrections = into
+                    .corrections
+                    .take()
+                    .map_or_else(|| CorrectionStats::uncorrected(text), |c| *c);
+                corrections.append(
+                    next.corrections
+                        .as_deref()
+                        .unwrap_or(&CorrectionStats::uncorrected(next_text)),
+                );
+                into.corrections = Some(Box::new(corrections));
+            }
+            text.push_str(next_text);
+            true
+        }
+        _ => false,
+    }
+}
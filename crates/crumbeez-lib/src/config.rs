@@ -0,0 +1,1211 @@
+//! Project-local configuration read from `.crumbeez/config.kdl`.
+//!
+//! The file lives next to the event log so a team can commit a shared policy
+//! for a repository while each user's Zellij layout stays generic.  Every
+//! setting is optional; a missing file is equivalent to an empty one.
+//!
+//! ```kdl
+//! // How much of the typed text is kept: "full", "hashed", or "metadata".
+//! privacy "full"
+//!
+//! // Keep breadcrumbs out of git: "local" writes `.crumbeez/.gitignore`
+//! // (config.kdl stays committable), "project" appends `.crumbeez/` to the
+//! // project's `.gitignore`, "none" leaves git alone.
+//! gitignore "local"
+//!
+//! // What a submodule's activity leaves in the superproject's `.crumbeez`
+//! // when both are in use.  The submodule's log always has the events;
+//! // "link" adds a line per summary to the superproject's
+//! // `summaries/submodule-links.kdl` pointing at them, "summary" also
+//! // appends the summary to its daily file, and "none" leaves it alone.
+//! superproject "link"
+//!
+//! // Panes whose command or title matches are never logged.
+//! exclude "ssh*" "*password*"
+//!
+//! // Nor are ssh sessions to hosts matching these.
+//! exclude_hosts "prod-*" "*.bank.internal"
+//!
+//! // Typed words matching these globs are replaced before reaching the log.
+//! redact "sk-*" "ghp_*"
+//!
+//! // Text matching a regular expression is replaced in everything that
+//! // leaves crumbeez — summaries, reports, LLM context, and live
+//! // breadcrumbs — even though the log keeps it.  The replacement defaults
+//! // to "[REDACTED]".
+//! redact_outbound "(?i)\\bacme corp\\b" "a customer"
+//! redact_outbound "[a-z0-9-]+\\.corp\\.internal"
+//!
+//! // How to read keystrokes in panes running these programs, on top of the
+//! // built-in profiles: "shell", "editor", "pager", "fuzzy-finder", "sql",
+//! // "repl", or "agent".
+//! profile "moar" "ov" role="pager"
+//!
+//! // Cursor key mode of panes running these programs: "normal" or
+//! // "application".  Keys are forwarded to editors and pagers in
+//! // application mode and to everything else in normal mode otherwise.
+//! cursor_keys "htop" "tig" mode="application"
+//!
+//! // Whether panes running these programs get pastes in bracketed-paste
+//! // markers.  Everything but pagers and fuzzy finders does otherwise.
+//! bracketed_paste "dash" "tclsh" enabled=false
+//!
+//! // Key sequences logged as one chord, e.g. tmux's prefix then a
+//! // command.  With role="...", only in panes with that role.
+//! chord "Ctrl+B c" "Ctrl+B %"
+//! chord "g g" "d d" role="editor"
+//!
+//! // Typed text shorter than this many characters (a stray key, a "y" to a
+//! // prompt) is counted as misc keys ("count") or left out ("drop").
+//! min_text_length 3 short="count"
+//!
+//! // Typed text longer than this many characters is logged as a preview
+//! // cut in the middle, with the full text in a scratchpad file.  The
+//! // plugin pane and summaries cut typed text to display= characters.
+//! long_text 1000 display=120
+//!
+//! // Commands submitted in a shell or SQL pane that are hard to undo,
+//! // flagged as risky and listed under "Notable actions" in summaries.
+//! // These add to the built-in "rm -rf", "git push --force",
+//! // "terraform apply", "DROP TABLE", and the like; defaults=false drops
+//! // those.  A pattern matches from any word of the command, ignoring
+//! // case, and `*` stands for anything.  annotate=true asks for a reason
+//! // each time.
+//! risky "pulumi up" "helm uninstall*" annotate=true
+//!
+//! // Kinds of key left out of the log, only counted: "navigation",
+//! // "edit-control" (except Enter), "shortcut", "escape", "function-key",
+//! // "system-key", "mode-change", "agent-status", "title-change", or
+//! // "session-context" (the toolchains and environment captured when
+//! // the project is found).
+//! disable_events "navigation" "edit-control"
+//!
+//! // Language of summaries and the plugin pane: "en", "de", or "es".
+//! locale "de"
+//!
+//! // Name shown for the project in summaries, the plugin pane, and
+//! // rollups.  Without it, the name comes from Cargo.toml, package.json,
+//! // or the git remote, and failing those, the root directory's name.
+//! project_name "Client X service"
+//!
+//! summary {
+//!     backend "local"        // "local", "cloud", or "none"
+//!     model "llama3"
+//!     endpoint "http://localhost:11434"
+//!     api_key_env "OPENAI_API_KEY"
+//! }
+//!
+//! retention {
+//!     max_events 10000
+//!     max_age_days 30
+//!     // Summarized entries older than this many days are compacted once
+//!     // a day: "keys" merges runs of navigation keys and drops system
+//!     // keys, "text" also joins text logged in pieces.
+//!     raw_days 7
+//!     compaction "keys"
+//! }
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::fmt::Write as _;
+
+use crate::chord::ChordBinding;
+use crate::event_log::EVENT_LOG_CAPACITY;
+use crate::fnv1a;
+use crate::glob::glob_match;
+use crate::kdl::{self, KdlError, KdlNode, KdlValue};
+use crate::locale::Locale;
+use crate::profile::{risky_pattern, AppProfile, AppRole, RISKY_COMMANDS};
+use crate::regex::Regex;
+use crate::{text_file, truncate_middle, CompactionLevel, CursorKeys, EventKind, KeystrokeEvent};
+
+/// Replacement text for words matched by a `redact` rule.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Per-project settings.  See the module docs for the file format.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ProjectConfig {
+    /// Glob patterns matched against the focused pane's command and title.
+    /// Keystrokes in a matching pane are not logged.
+    pub exclude: Vec<String>,
+    /// Glob patterns matched against the host of a pane's ssh session.
+    /// Keystrokes sent to a matching host are not logged.
+    pub exclude_hosts: Vec<String>,
+    /// Glob patterns matched against each whitespace-separated word of typed
+    /// text.  Matching words are replaced with [`REDACTED`].
+    pub redact: Vec<String>,
+    /// Replacements made in summaries and everything else sent out, in
+    /// order.
+    pub redact_outbound: Vec<OutboundRedaction>,
+    /// Per-application profiles, tried before the built-in ones.
+    pub profiles: Vec<AppProfile>,
+    /// Cursor key modes for panes, tried before the role's usual mode.
+    pub cursor_keys: Vec<CursorKeysRule>,
+    /// Bracketed paste for panes, tried before the role's usual setting.
+    pub bracketed_paste: Vec<BracketedPasteRule>,
+    /// Key sequences folded into one [`KeystrokeEvent::ChordSequence`].
+    pub chords: Vec<ChordBinding>,
+    pub privacy: PrivacyLevel,
+    pub min_text: MinTextLength,
+    pub long_text: LongTextConfig,
+    /// Kinds of event counted rather than logged.
+    pub disabled_events: Vec<EventKind>,
+    pub risky: RiskyConfig,
+    pub gitignore: GitignoreMode,
+    pub superproject: SuperprojectRouting,
+    pub locale: Locale,
+    /// The project's name, in place of one derived from its files.
+    pub project_name: Option<String>,
+    pub summary: SummaryConfig,
+    pub retention: RetentionConfig,
+}
+
+/// A `cursor_keys` rule: panes whose command or title matches any of
+/// `patterns` get keys encoded for `mode`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CursorKeysRule {
+    pub patterns: Vec<String>,
+    pub mode: CursorKeys,
+}
+
+/// A `bracketed_paste` rule: panes whose command or title matches any of
+/// `patterns` get pastes bracketed if `enabled`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BracketedPasteRule {
+    pub patterns: Vec<String>,
+    pub enabled: bool,
+}
+
+/// A `redact_outbound` rule: text matching `pattern` is replaced with
+/// `replacement` wherever it leaves crumbeez.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboundRedaction {
+    pub pattern: Regex,
+    pub replacement: String,
+}
+
+impl OutboundRedaction {
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self.pattern.replace_all(text, &self.replacement)
+    }
+}
+
+/// How much of the typed text reaches the event log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrivacyLevel {
+    /// Typed text is logged verbatim (after `redact` rules).
+    #[default]
+    Full,
+    /// Typed text is replaced by a stable hash, so repeated input can still be
+    /// correlated without revealing its content.
+    Hashed,
+    /// Only the length of typed text is logged.
+    Metadata,
+}
+
+impl PrivacyLevel {
+    pub const ALL: [Self; 3] = [Self::Full, Self::Hashed, Self::Metadata];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "full" => Some(Self::Full),
+            "hashed" => Some(Self::Hashed),
+            "metadata" => Some(Self::Metadata),
+            _ => None,
+        }
+    }
+
+    /// Reduce `text` to what this privacy level allows to be stored.
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Full => Cow::Borrowed(text),
+            Self::Hashed => Cow::Owned(format!("#{:016x}", fnv1a(text.as_bytes()))),
+            Self::Metadata => Cow::Owned(format!("<{} chars>", text.chars().count())),
+        }
+    }
+}
+
+impl fmt::Display for PrivacyLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full => write!(f, "full"),
+            Self::Hashed => write!(f, "hashed"),
+            Self::Metadata => write!(f, "metadata"),
+        }
+    }
+}
+
+/// Typed text too short to be worth logging as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MinTextLength {
+    /// Text with fewer characters than this (ignoring surrounding
+    /// whitespace) is short.  0 keeps everything.
+    pub chars: usize,
+    pub mode: ShortTextMode,
+}
+
+/// How much of long typed text is kept inline and shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LongTextConfig {
+    /// Text with more characters than this is logged as a
+    /// [`KeystrokeEvent::LongText`] preview of this length, its full text
+    /// stored in a file.
+    pub preview_chars: usize,
+    /// Typed text in the plugin pane and summaries is cut to this many
+    /// characters.
+    pub display_chars: usize,
+}
+
+impl Default for LongTextConfig {
+    fn default() -> Self {
+        Self {
+            preview_chars: 1000,
+            display_chars: 120,
+        }
+    }
+}
+
+/// Which submitted commands are flagged as
+/// [risky](KeystrokeEvent::RiskyCommand).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiskyConfig {
+    /// Patterns for [`risky_pattern`], on top of [`RISKY_COMMANDS`].
+    pub patterns: Vec<String>,
+    /// Whether [`RISKY_COMMANDS`] are flagged too.
+    pub defaults: bool,
+    /// Whether to ask for a reason each time a risky command is run.
+    pub annotate: bool,
+}
+
+impl Default for RiskyConfig {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+            defaults: true,
+            annotate: false,
+        }
+    }
+}
+
+impl RiskyConfig {
+    /// The pattern `command_line` matches, if it's risky.
+    pub fn matching(&self, command_line: &str) -> Option<&str> {
+        let defaults: &[&str] = if self.defaults { &RISKY_COMMANDS } else { &[] };
+        risky_pattern(
+            defaults
+                .iter()
+                .copied()
+                .chain(self.patterns.iter().map(String::as_str)),
+            command_line,
+        )
+    }
+}
+
+/// What happens to short typed text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShortTextMode {
+    /// Logged as a [`KeystrokeEvent::ShortText`], which summaries add up as
+    /// misc keys.
+    #[default]
+    Count,
+    /// Not logged at all.
+    Drop,
+}
+
+impl ShortTextMode {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(Self::Count),
+            "drop" => Some(Self::Drop),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ShortTextMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Count => write!(f, "count"),
+            Self::Drop => write!(f, "drop"),
+        }
+    }
+}
+
+/// How crumbeez keeps its data directory out of version control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GitignoreMode {
+    /// Write `.crumbeez/.gitignore` ignoring everything except the config
+    /// file, so a shared `config.kdl` can still be committed.
+    #[default]
+    Local,
+    /// Append `.crumbeez/` to the project's top-level `.gitignore`.
+    Project,
+    /// Don't touch any `.gitignore`.
+    None,
+}
+
+impl GitignoreMode {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "local" => Some(Self::Local),
+            "project" => Some(Self::Project),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GitignoreMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::Project => write!(f, "project"),
+            Self::None => write!(f, "none"),
+        }
+    }
+}
+
+/// What a submodule's activity leaves in the superproject's data dir.  The
+/// events themselves only go to the submodule's log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SuperprojectRouting {
+    /// A [`SubmoduleLink`](crate::SubmoduleLink) per summary, pointing at
+    /// the events in the submodule's log.
+    #[default]
+    Link,
+    /// The link, and the summary appended to the superproject's daily file.
+    Summary,
+    /// Nothing.
+    None,
+}
+
+impl SuperprojectRouting {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "link" => Some(Self::Link),
+            "summary" => Some(Self::Summary),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SuperprojectRouting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Link => write!(f, "link"),
+            Self::Summary => write!(f, "summary"),
+            Self::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Which summarization backend to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SummaryBackendKind {
+    /// No LLM — summaries are generated locally from event statistics.
+    #[default]
+    None,
+    /// A local model server such as Ollama.
+    Local,
+    /// A hosted provider such as OpenAI or Anthropic.
+    Cloud,
+}
+
+impl SummaryBackendKind {
+    pub const ALL: [Self; 3] = [Self::Local, Self::Cloud, Self::None];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::None),
+            "local" => Some(Self::Local),
+            "cloud" => Some(Self::Cloud),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SummaryBackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Local => write!(f, "local"),
+            Self::Cloud => write!(f, "cloud"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SummaryConfig {
+    pub backend: SummaryBackendKind,
+    pub model: Option<String>,
+    pub endpoint: Option<String>,
+    /// Name of the environment variable holding the API key.  The key itself
+    /// never belongs in a committed config file.
+    pub api_key_env: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetentionConfig {
+    /// Maximum number of entries kept in the event log.
+    pub max_events: usize,
+    /// Entries older than this many days are dropped when the log is saved.
+    pub max_age_days: Option<u64>,
+    /// Summarized entries older than this many days are
+    /// [compacted](crate::compact).  Without it, the log stays raw.
+    pub raw_days: Option<u64>,
+    pub compaction: CompactionLevel,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_events: EVENT_LOG_CAPACITY,
+            max_age_days: None,
+            raw_days: None,
+            compaction: CompactionLevel::default(),
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// Parse a config file's contents.
+    pub fn parse(input: &str) -> Result<Self, ConfigError> {
+        let nodes = kdl::parse(input).map_err(ConfigError::Syntax)?;
+        let mut config = Self::default();
+
+        for node in &nodes {
+            match node.name.as_str() {
+                "exclude" => config.exclude.extend(string_args(node)?),
+                "exclude_hosts" => config.exclude_hosts.extend(string_args(node)?),
+                "redact" => config.redact.extend(string_args(node)?),
+                "redact_outbound" => config.redact_outbound.push(parse_outbound(node)?),
+                "profile" => config.profiles.push(parse_profile(node)?),
+                "cursor_keys" => config.cursor_keys.push(parse_cursor_keys(node)?),
+                "bracketed_paste" => config.bracketed_paste.push(parse_bracketed_paste(node)?),
+                "chord" => config.chords.extend(parse_chords(node)?),
+                "privacy" => {
+                    let name = single_string(node)?;
+                    config.privacy = PrivacyLevel::from_name(&name).ok_or_else(|| {
+                        ConfigError::invalid(
+                            node,
+                            format!(
+                                "unknown privacy level '{name}' (expected \"full\", \"hashed\", or \"metadata\")"
+                            ),
+                        )
+                    })?;
+                }
+                "gitignore" => {
+                    let name = single_string(node)?;
+                    config.gitignore = GitignoreMode::from_name(&name).ok_or_else(|| {
+                        ConfigError::invalid(
+                            node,
+                            format!(
+                                "unknown gitignore mode '{name}' (expected \"local\", \"project\", or \"none\")"
+                            ),
+                        )
+                    })?;
+                }
+                "superproject" => {
+                    let name = single_string(node)?;
+                    config.superproject =
+                        SuperprojectRouting::from_name(&name).ok_or_else(|| {
+                            ConfigError::invalid(
+                                node,
+                                format!(
+                                    "unknown superproject routing '{name}' (expected \"link\", \"summary\", or \"none\")"
+                                ),
+                            )
+                        })?;
+                }
+                "min_text_length" => config.min_text = parse_min_text(node)?,
+                "long_text" => config.long_text = parse_long_text(node)?,
+                "risky" => parse_risky(node, &mut config.risky)?,
+                "locale" => {
+                    let code = single_string(node)?;
+                    config.locale = Locale::from_code(&code).ok_or_else(|| {
+                        let codes: Vec<String> =
+                            Locale::ALL.iter().map(|l| kdl::quote(l.code())).collect();
+                        ConfigError::invalid(
+                            node,
+                            format!(
+                                "unknown locale '{code}' (expected one of {})",
+                                codes.join(", ")
+                            ),
+                        )
+                    })?;
+                }
+                "project_name" => {
+                    let name = single_string(node)?;
+                    if name.trim().is_empty() {
+                        return Err(ConfigError::invalid(node, "the name is empty"));
+                    }
+                    config.project_name = Some(name);
+                }
+                "disable_events" => {
+                    for name in string_args(node)? {
+                        let kind = EventKind::from_name(&name).ok_or_else(|| {
+                            let names: Vec<String> = EventKind::ALL
+                                .iter()
+                                .map(|k| kdl::quote(k.name()))
+                                .collect();
+                            ConfigError::invalid(
+                                node,
+                                format!(
+                                    "unknown event kind '{name}' (expected one of {})",
+                                    names.join(", ")
+                                ),
+                            )
+                        })?;
+                        if !config.disabled_events.contains(&kind) {
+                            config.disabled_events.push(kind);
+                        }
+                    }
+                }
+                "summary" => config.summary = parse_summary(node)?,
+                "retention" => config.retention = parse_retention(node)?,
+                other => {
+                    return Err(ConfigError::invalid(
+                        node,
+                        format!("unknown setting '{other}'"),
+                    ))
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Render this config as a `config.kdl` document.  Settings left at their
+    /// defaults are omitted so the file stays short.
+    pub fn to_kdl(&self) -> String {
+        let mut out = String::new();
+        let quoted = |items: &[String]| {
+            items
+                .iter()
+                .map(|s| kdl::quote(s))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let _ = writeln!(out, "privacy {}", kdl::quote(&self.privacy.to_string()));
+        if self.gitignore != GitignoreMode::default() {
+            let _ = writeln!(out, "gitignore {}", kdl::quote(&self.gitignore.to_string()));
+        }
+        if self.superproject != SuperprojectRouting::default() {
+            let _ = writeln!(
+                out,
+                "superproject {}",
+                kdl::quote(&self.superproject.to_string())
+            );
+        }
+        if self.locale != Locale::default() {
+            let _ = writeln!(out, "locale {}", kdl::quote(self.locale.code()));
+        }
+        if let Some(name) = &self.project_name {
+            let _ = writeln!(out, "project_name {}", kdl::quote(name));
+        }
+        if !self.exclude.is_empty() {
+            let _ = writeln!(out, "exclude {}", quoted(&self.exclude));
+        }
+        if !self.exclude_hosts.is_empty() {
+            let _ = writeln!(out, "exclude_hosts {}", quoted(&self.exclude_hosts));
+        }
+        if !self.redact.is_empty() {
+            let _ = writeln!(out, "redact {}", quoted(&self.redact));
+        }
+        for rule in &self.redact_outbound {
+            let _ = write!(out, "redact_outbound {}", kdl::quote(rule.pattern.as_str()));
+            if rule.replacement != REDACTED {
+                let _ = write!(out, " {}", kdl::quote(&rule.replacement));
+            }
+            out.push('\n');
+        }
+        if self.min_text != MinTextLength::default() {
+            let _ = write!(out, "min_text_length {}", self.min_text.chars);
+            if self.min_text.mode != ShortTextMode::default() {
+                let _ = write!(
+                    out,
+                    " short={}",
+                    kdl::quote(&self.min_text.mode.to_string())
+                );
+            }
+            out.push('\n');
+        }
+        if self.long_text != LongTextConfig::default() {
+            let _ = write!(out, "long_text {}", self.long_text.preview_chars);
+            if self.long_text.display_chars != LongTextConfig::default().display_chars {
+                let _ = write!(out, " display={}", self.long_text.display_chars);
+            }
+            out.push('\n');
+        }
+        if self.risky != RiskyConfig::default() {
+            out.push_str("risky");
+            if !self.risky.patterns.is_empty() {
+                let _ = write!(out, " {}", quoted(&self.risky.patterns));
+            }
+            if !self.risky.defaults {
+                out.push_str(" defaults=false");
+            }
+            if self.risky.annotate {
+                out.push_str(" annotate=true");
+            }
+            out.push('\n');
+        }
+        if !self.disabled_events.is_empty() {
+            let names: Vec<String> = self
+                .disabled_events
+                .iter()
+                .map(|kind| kind.name().to_string())
+                .collect();
+            let _ = writeln!(out, "disable_events {}", quoted(&names));
+        }
+        for chord in &self.chords {
+            let _ = write!(out, "chord {}", kdl::quote(&chord.keys));
+            if let Some(role) = chord.role {
+                let _ = write!(out, " role={}", kdl::quote(role.name()));
+            }
+            out.push('\n');
+        }
+        for profile in &self.profiles {
+            let _ = writeln!(
+                out,
+                "profile {} role={}",
+                quoted(&profile.patterns),
+                kdl::quote(profile.role.name())
+            );
+        }
+        for rule in &self.cursor_keys {
+            let _ = writeln!(
+                out,
+                "cursor_keys {} mode={}",
+                quoted(&rule.patterns),
+                kdl::quote(rule.mode.name())
+            );
+        }
+        for rule in &self.bracketed_paste {
+            let _ = writeln!(
+                out,
+                "bracketed_paste {} enabled={}",
+                quoted(&rule.patterns),
+                rule.enabled
+            );
+        }
+
+        let summary = &self.summary;
+        out.push_str("\nsummary {\n");
+        let _ = writeln!(
+            out,
+            "    backend {}",
+            kdl::quote(&summary.backend.to_string())
+        );
+        for (key, value) in [
+            ("model", &summary.model),
+            ("endpoint", &summary.endpoint),
+            ("api_key_env", &summary.api_key_env),
+        ] {
+            if let Some(value) = value {
+                let _ = writeln!(out, "    {} {}", key, kdl::quote(value));
+            }
+        }
+        out.push_str("}\n");
+
+        if self.retention != RetentionConfig::default() {
+            out.push_str("\nretention {\n");
+            let _ = writeln!(out, "    max_events {}", self.retention.max_events);
+            if let Some(days) = self.retention.max_age_days {
+                let _ = writeln!(out, "    max_age_days {}", days);
+            }
+            if let Some(days) = self.retention.raw_days {
+                let _ = writeln!(out, "    raw_days {}", days);
+            }
+            if self.retention.compaction != CompactionLevel::default() {
+                let _ = writeln!(
+                    out,
+                    "    compaction {}",
+                    kdl::quote(self.retention.compaction.name())
+                );
+            }
+            out.push_str("}\n");
+        }
+
+        out
+    }
+
+    /// Whether a pane running `command` with the given `title` is excluded
+    /// from logging.
+    ///
+    /// Patterns are tried against the title, the full command line, and the
+    /// basename of the command's executable, so `exclude "ssh"` matches a pane
+    /// running `/usr/bin/ssh host`.
+    pub fn excludes_pane(&self, title: &str, command: Option<&str>) -> bool {
+        pane_matches(&self.exclude, title, command)
+    }
+
+    /// How to encode cursor keys for a pane: the first `cursor_keys` rule
+    /// matching it, or else the usual mode for its `role`.
+    pub fn cursor_keys_for(&self, title: &str, command: Option<&str>, role: AppRole) -> CursorKeys {
+        self.cursor_keys
+            .iter()
+            .find(|rule| pane_matches(&rule.patterns, title, command))
+            .map_or_else(|| role.cursor_keys(), |rule| rule.mode)
+    }
+
+    /// Whether pastes into a pane are bracketed: as the first
+    /// `bracketed_paste` rule matching it says, or else as usual for its
+    /// `role`.
+    pub fn bracketed_paste_for(&self, title: &str, command: Option<&str>, role: AppRole) -> bool {
+        self.bracketed_paste
+            .iter()
+            .find(|rule| pane_matches(&rule.patterns, title, command))
+            .map_or_else(|| role.bracketed_paste(), |rule| rule.enabled)
+    }
+
+    /// Whether keystrokes sent over ssh to `host` are excluded from logging.
+    pub fn excludes_host(&self, host: &str) -> bool {
+        self.exclude_hosts
+            .iter()
+            .any(|pattern| glob_match(pattern, host))
+    }
+
+    /// The kind of `event` and its key count, if `disable_events` leaves it
+    /// out of the log.
+    pub fn filtered(&self, event: &KeystrokeEvent) -> Option<(EventKind, usize)> {
+        EventKind::of(event).filter(|(kind, _)| self.disabled_events.contains(kind))
+    }
+
+    /// The event for a finished run of typed text: redacted and reduced to
+    /// the privacy level, or per `min_text_length` if it's short.  `None`
+    /// if nothing should be logged.
+    pub fn seal_text(&self, text: &str) -> Option<KeystrokeEvent> {
+        if text.is_empty() {
+            return None;
+        }
+        let chars = text.trim().chars().count();
+        if chars < self.min_text.chars {
+            return match self.min_text.mode {
+                ShortTextMode::Count => Some(KeystrokeEvent::ShortText(chars)),
+                ShortTextMode::Drop => None,
+            };
+        }
+        Some(KeystrokeEvent::TextTyped(self.scrub(text)))
+    }
+
+    /// The entry to log for a sealed [`KeystrokeEvent::TextTyped`] stamped
+    /// `timestamp_ms`.  Text longer than `long_text` becomes a
+    /// [`KeystrokeEvent::LongText`] preview, returned with the full text to
+    /// write to its [`text_file`].
+    pub fn split_long_text(
+        &self,
+        event: KeystrokeEvent,
+        timestamp_ms: u64,
+    ) -> (KeystrokeEvent, Option<String>) {
+        let KeystrokeEvent::TextTyped(text) = event else {
+            return (event, None);
+        };
+        let chars = text.chars().count();
+        if chars <= self.long_text.preview_chars {
+            return (KeystrokeEvent::TextTyped(text), None);
+        }
+        let preview = truncate_middle(&text, self.long_text.preview_chars).into_owned();
+        let event = KeystrokeEvent::LongText {
+            preview,
+            chars,
+            file: text_file(timestamp_ms),
+        };
+        (event, Some(text))
+    }
+
+    /// `text` with the `redact` rules and privacy level applied, as it may
+    /// be stored.
+    pub fn scrub(&self, text: &str) -> String {
+        self.privacy.apply(&self.redact(text)).into_owned()
+    }
+
+    /// Apply the `redact` rules to `text`, replacing each matching word with
+    /// [`REDACTED`].  Whitespace is preserved as-is.
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.redact.is_empty() {
+            return Cow::Borrowed(text);
+        }
+
+        let mut out = String::with_capacity(text.len());
+        let mut changed = false;
+        let mut word_start = None;
+
+        for (i, c) in text.char_indices() {
+            if c.is_whitespace() {
+                if let Some(start) = word_start.take() {
+                    changed |= self.push_word(&mut out, &text[start..i]);
+                }
+                out.push(c);
+            } else if word_start.is_none() {
+                word_start = Some(i);
+            }
+        }
+        if let Some(start) = word_start {
+            changed |= self.push_word(&mut out, &text[start..]);
+        }
+
+        if changed {
+            Cow::Owned(out)
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Apply the `redact_outbound` rules to `text`, in order, before it
+    /// leaves crumbeez.
+    pub fn redact_outbound<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for rule in &self.redact_outbound {
+            if let Cow::Owned(replaced) = rule.apply(&text) {
+                text = Cow::Owned(replaced);
+            }
+        }
+        text
+    }
+
+    /// Push `word` (or its redaction) onto `out`.  Returns `true` if redacted.
+    fn push_word(&self, out: &mut String, word: &str) -> bool {
+        if self.redact.iter().any(|pattern| glob_match(pattern, word)) {
+            out.push_str(REDACTED);
+            true
+        } else {
+            out.push_str(word);
+            false
+        }
+    }
+}
+
+// ── Node parsing ─────────────────────────────────────────────────
+
+/// Whether any of the glob `patterns` matches a pane's title, full command
+/// line, or the basename of its command's executable.
+pub fn pane_matches(patterns: &[String], title: &str, command: Option<&str>) -> bool {
+    let program = command
+        .and_then(|cmd| cmd.split_whitespace().next())
+        .map(|exe| exe.rsplit('/').next().unwrap_or(exe));
+
+    patterns.iter().any(|pattern| {
+        glob_match(pattern, title)
+            || command.is_some_and(|cmd| glob_match(pattern, cmd))
+            || program.is_some_and(|prog| glob_match(pattern, prog))
+    })
+}
+
+fn parse_profile(node: &KdlNode) -> Result<AppProfile, ConfigError> {
+    let patterns = string_args(node)?;
+    if patterns.is_empty() {
+        return Err(ConfigError::invalid(node, "expected at least one pattern"));
+    }
+    let role = node
+        .prop("role")
+        .ok_or_else(|| ConfigError::invalid(node, "missing role=\"...\""))?;
+    let name = role.as_str().ok_or_else(|| {
+        ConfigError::invalid(node, format!("expected a string role, found {role}"))
+    })?;
+    let role = AppRole::from_name(name).ok_or_else(|| {
+        let names: Vec<_> = AppRole::ALL.iter().map(|r| format!("\"{}\"", r)).collect();
+        ConfigError::invalid(
+            node,
+            format!(
+                "unknown role '{name}' (expected one of {})",
+                names.join(", ")
+            ),
+        )
+    })?;
+    Ok(AppProfile { patterns, role })
+}
+
+fn parse_cursor_keys(node: &KdlNode) -> Result<CursorKeysRule, ConfigError> {
+    let patterns = string_args(node)?;
+    if patterns.is_empty() {
+        return Err(ConfigError::invalid(node, "expected at least one pattern"));
+    }
+    let mode = node
+        .prop("mode")
+        .ok_or_else(|| ConfigError::invalid(node, "missing mode=\"...\""))?;
+    let mode = mode
+        .as_str()
+        .and_then(CursorKeys::from_name)
+        .ok_or_else(|| {
+            ConfigError::invalid(
+                node,
+                format!("unknown mode {mode} (expected \"normal\" or \"application\")"),
+            )
+        })?;
+    Ok(CursorKeysRule { patterns, mode })
+}
+
+fn parse_bracketed_paste(node: &KdlNode) -> Result<BracketedPasteRule, ConfigError> {
+    let patterns = string_args(node)?;
+    if patterns.is_empty() {
+        return Err(ConfigError::invalid(node, "expected at least one pattern"));
+    }
+    let enabled = node
+        .prop("enabled")
+        .ok_or_else(|| ConfigError::invalid(node, "missing enabled=true or enabled=false"))?;
+    let enabled = enabled.as_bool().ok_or_else(|| {
+        ConfigError::invalid(
+            node,
+            format!("expected enabled=true or false, found {enabled}"),
+        )
+    })?;
+    Ok(BracketedPasteRule { patterns, enabled })
+}
+
+fn parse_outbound(node: &KdlNode) -> Result<OutboundRedaction, ConfigError> {
+    let (pattern, replacement) = match string_args(node)?.as_slice() {
+        [pattern] => (pattern.clone(), REDACTED.to_string()),
+        [pattern, replacement] => (pattern.clone(), replacement.clone()),
+        _ => {
+            return Err(ConfigError::invalid(
+                node,
+                "expected a pattern and optionally a replacement",
+            ))
+        }
+    };
+    if pattern.is_empty() {
+        return Err(ConfigError::invalid(node, "the pattern is empty"));
+    }
+    let pattern = Regex::new(&pattern).map_err(|err| {
+        ConfigError::invalid(node, format!("bad pattern {}: {err}", kdl::quote(&pattern)))
+    })?;
+    Ok(OutboundRedaction {
+        pattern,
+        replacement,
+    })
+}
+
+fn parse_chords(node: &KdlNode) -> Result<Vec<ChordBinding>, ConfigError> {
+    let role = match node.prop("role") {
+        None => None,
+        Some(value) => Some(value.as_str().and_then(AppRole::from_name).ok_or_else(|| {
+            let names: Vec<_> = AppRole::ALL.iter().map(|r| format!("\"{}\"", r)).collect();
+            ConfigError::invalid(
+                node,
+                format!(
+                    "unknown role {value} (expected one of {})",
+                    names.join(", ")
+                ),
+            )
+        })?),
+    };
+    let keys = string_args(node)?;
+    if keys.is_empty() {
+        return Err(ConfigError::invalid(
+            node,
+            "expected at least one key sequence",
+        ));
+    }
+    keys.iter()
+        .map(|keys| {
+            ChordBinding::new(keys, role).ok_or_else(|| {
+                ConfigError::invalid(
+                    node,
+                    format!("chord '{keys}' needs at least two keys, separated by spaces"),
+                )
+            })
+        })
+        .collect()
+}
+
+fn parse_min_text(node: &KdlNode) -> Result<MinTextLength, ConfigError> {
+    let chars = usize::try_from(single_positive_int(node)?)
+        .map_err(|_| ConfigError::invalid(node, "value is too large"))?;
+    let mode = match node.prop("short") {
+        None => ShortTextMode::default(),
+        Some(value) => value
+            .as_str()
+            .and_then(ShortTextMode::from_name)
+            .ok_or_else(|| {
+                ConfigError::invalid(
+                    node,
+                    format!("unknown short=\"...\" mode {value} (expected \"count\" or \"drop\")"),
+                )
+            })?,
+    };
+    Ok(MinTextLength { chars, mode })
+}
+
+fn parse_long_text(node: &KdlNode) -> Result<LongTextConfig, ConfigError> {
+    let chars =
+        |n: u64| usize::try_from(n).map_err(|_| ConfigError::invalid(node, "value is too large"));
+    let preview_chars = chars(single_positive_int(node)?)?;
+    let display_chars = match node.prop("display") {
+        None => LongTextConfig::default().display_chars,
+        Some(value) => match value.as_i64() {
+            Some(n) if n > 0 => chars(n as u64)?,
+            _ => {
+                return Err(ConfigError::invalid(
+                    node,
+                    format!("display= needs a positive integer, found {value}"),
+                ))
+            }
+        },
+    };
+    Ok(LongTextConfig {
+        preview_chars,
+        display_chars,
+    })
+}
+
+/// A `risky` node: its patterns add to `risky`, and its properties, if
+/// given, replace what earlier ones said.
+fn parse_risky(node: &KdlNode, risky: &mut RiskyConfig) -> Result<(), ConfigError> {
+    let patterns = string_args(node)?;
+    if patterns.iter().any(|p| p.trim().is_empty()) {
+        return Err(ConfigError::invalid(node, "a pattern is empty"));
+    }
+    risky.patterns.extend(patterns);
+    for (name, field) in [
+        ("defaults", &mut risky.defaults),
+        ("annotate", &mut risky.annotate),
+    ] {
+        if let Some(value) = node.prop(name) {
+            *field = value.as_bool().ok_or_else(|| {
+                ConfigError::invalid(
+                    node,
+                    format!("expected {name}=true or false, found {value}"),
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_summary(node: &KdlNode) -> Result<SummaryConfig, ConfigError> {
+    let mut summary = SummaryConfig::default();
+    for child in &node.children {
+        match child.name.as_str() {
+            "backend" => {
+                let name = single_string(child)?;
+                summary.backend = SummaryBackendKind::from_name(&name).ok_or_else(|| {
+                    ConfigError::invalid(
+                        child,
+                        format!(
+                            "unknown backend '{name}' (expected \"local\", \"cloud\", or \"none\")"
+                        ),
+                    )
+                })?;
+            }
+            "model" => summary.model = Some(single_string(child)?),
+            "endpoint" => summary.endpoint = Some(single_string(child)?),
+            "api_key_env" => summary.api_key_env = Some(single_string(child)?),
+            other => {
+                return Err(ConfigError::invalid(
+                    child,
+                    format!("unknown summary setting '{other}'"),
+                ))
+            }
+        }
+    }
+    Ok(summary)
+}
+
+fn parse_retention(node: &KdlNode) -> Result<RetentionConfig, ConfigError> {
+    let mut retention = RetentionConfig::default();
+    for child in &node.children {
+        match child.name.as_str() {
+            "max_events" => {
+                let n = single_positive_int(child)?;
+                retention.max_events = usize::try_from(n)
+                    .map_err(|_| ConfigError::invalid(child, "value is too large"))?;
+            }
+            "max_age_days" => retention.max_age_days = Some(single_positive_int(child)?),
+            "raw_days" => retention.raw_days = Some(single_positive_int(child)?),
+            "compaction" => {
+                let name = single_string(child)?;
+                retention.compaction = CompactionLevel::from_name(&name).ok_or_else(|| {
+                    ConfigError::invalid(
+                        child,
+                        format!(
+                            "unknown compaction level '{name}' (expected \"keys\" or \"text\")"
+                        ),
+                    )
+                })?;
+            }
+            other => {
+                return Err(ConfigError::invalid(
+                    child,
+                    format!("unknown retention setting '{other}'"),
+                ))
+            }
+        }
+    }
+    Ok(retention)
+}
+
+fn string_args(node: &KdlNode) -> Result<Vec<String>, ConfigError> {
+    node.args
+        .iter()
+        .map(|v| match v {
+            KdlValue::String(s) => Ok(s.clone()),
+            other => Err(ConfigError::invalid(
+                node,
+                format!("expected a string, found {other}"),
+            )),
+        })
+        .collect()
+}
+
+fn single_arg(node: &KdlNode) -> Result<&KdlValue, ConfigError> {
+    match node.args.as_slice() {
+        [value] => Ok(value),
+        [] => Err(ConfigError::invalid(node, "missing value")),
+        _ => Err(ConfigError::invalid(node, "expected a single value")),
+    }
+}
+
+fn single_string(node: &KdlNode) -> Result<String, ConfigError> {
+    let value = single_arg(node)?;
+    value
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| ConfigError::invalid(node, format!("expected a string, found {value}")))
+}
+
+fn single_positive_int(node: &KdlNode) -> Result<u64, ConfigError> {
+    let value = single_arg(node)?;
+    match value.as_i64() {
+        Some(n) if n > 0 => Ok(n as u64),
+        _ => Err(ConfigError::invalid(
+            node,
+            format!("expected a positive integer, found {value}"),
+        )),
+    }
+}
+
+// ── Errors ───────────────────────────────────────────────────────
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file is not valid KDL.
+    Syntax(KdlError),
+    /// The file parsed but a setting has the wrong shape or value.
+    Invalid { line: usize, message: String },
+}
+
+impl ConfigError {
+    fn invalid(node: &KdlNode, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            line: node.line,
+            message: format!("'{}': {}", node.name, message.into()),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(err) => write!(f, "Syntax error at {}", err),
+            Self::Invalid { line, message } => {
+                write!(f, "Invalid config at line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,147 @@
+//! Recognising work done inside a container.
+//!
+//! `cargo test` on the host and `cargo test` in the app's container are
+//! different breadcrumbs.  A pane is in a container when its command is
+//! `docker exec`, `podman exec`, `docker compose exec` and the like, or a
+//! devcontainer CLI shell, or when its title is a shell prompt whose host
+//! is a container ID, as in a devcontainer's terminal.  Within a shell
+//! pane, the summary also follows an interactive `docker exec -it web bash`
+//! typed at the prompt into the container, and back out on `exit`.
+
+/// Programs whose `exec` and `run` subcommands take Docker's options.
+const ENGINES: [&str; 3] = ["docker", "podman", "nerdctl"];
+
+/// Short flags that take no value.
+const BOOLEAN_FLAGS: &str = "dtiPT";
+
+/// Long options that take no value.
+const BOOLEAN_OPTIONS: [&str; 11] = [
+    "interactive",
+    "tty",
+    "detach",
+    "rm",
+    "privileged",
+    "init",
+    "publish-all",
+    "read-only",
+    "no-deps",
+    "service-ports",
+    "no-TTY",
+];
+
+/// A command line that runs something in a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerCommand {
+    /// The container, compose service, or image name.
+    pub name: String,
+    /// Whether it opens a terminal in the container, rather than running
+    /// one command and returning.
+    pub interactive: bool,
+}
+
+impl ContainerCommand {
+    /// Parse `command_line` if it runs something in a container, e.g.
+    /// `docker exec -it web bash`.
+    pub fn parse(command_line: &str) -> Option<Self> {
+        let mut args = command_line.split_whitespace().peekable();
+        let program = args.next()?;
+        let program = program.rsplit('/').next().unwrap_or(program);
+        let compose = match program {
+            "docker-compose" | "podman-compose" => {
+                skip_options(&mut args);
+                true
+            }
+            "devcontainer" => {
+                return (args.next()? == "exec").then(|| Self {
+                    name: "devcontainer".to_string(),
+                    interactive: true,
+                });
+            }
+            _ if ENGINES.contains(&program) => {
+                skip_options(&mut args);
+                let compose = args.next_if_eq(&"compose").is_some();
+                if compose {
+                    skip_options(&mut args);
+                }
+                compose
+            }
+            _ => return None,
+        };
+        let subcommand = args.next()?;
+        if subcommand != "exec" && subcommand != "run" {
+            return None;
+        }
+
+        let mut interactive = compose;
+        let mut named = None;
+        while let Some(arg) = args.next() {
+            let Some(option) = arg.strip_prefix('-') else {
+                return Some(Self {
+                    name: named.unwrap_or_else(|| arg.to_string()),
+                    interactive,
+                });
+            };
+            match option.strip_prefix('-') {
+                Some(long) => {
+                    let (key, value) = match long.split_once('=') {
+                        Some((key, value)) => (key, Some(value.to_string())),
+                        None if BOOLEAN_OPTIONS.contains(&long) => (long, None),
+                        None => (long, args.next().map(str::to_string)),
+                    };
+                    match key {
+                        "interactive" | "tty" if !compose => interactive = true,
+                        "no-TTY" => interactive = false,
+                        "name" if subcommand == "run" => named = value,
+                        _ => {}
+                    }
+                }
+                None if option.chars().all(|c| BOOLEAN_FLAGS.contains(c)) => {
+                    if compose {
+                        interactive &= !option.contains('T');
+                    } else {
+                        interactive |= option.contains(['i', 't']);
+                    }
+                }
+                // A value attached, as in `-eFOO=1`, or the next argument.
+                None if option.len() > 1 => {}
+                None => {
+                    args.next();
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The container a pane works in, if any: from `command`, or failing that
+/// from `title`.
+pub fn pane_container(command: Option<&str>, title: &str) -> Option<String> {
+    command
+        .and_then(ContainerCommand::parse)
+        .or_else(|| ContainerCommand::parse(title))
+        .map(|command| command.name)
+        .or_else(|| prompt_container_id(title))
+}
+
+/// The container ID in a prompt title like `root@0f3a9c1b2d4e: /workspaces/app`:
+/// containers are named after the first 12 hex digits of their ID unless
+/// given a hostname.
+fn prompt_container_id(title: &str) -> Option<String> {
+    let (_, rest) = title.split_once('@')?;
+    let host = rest.split(':').next()?;
+    (host.len() == 12
+        && host
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()))
+    .then(|| host.to_string())
+}
+
+/// Skip the options before a subcommand, e.g. compose's `-f dev.yml` or
+/// docker's `--context staging`.
+fn skip_options<'a>(args: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>) {
+    while let Some(arg) = args.next_if(|arg| arg.starts_with('-')) {
+        if !arg.contains('=') && (arg.len() == 2 || arg.starts_with("--")) {
+            args.next();
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! A token-budgeted digest of recent activity, for injecting into an LLM
+//! prompt.
+//!
+//! [`context_blob`] renders as much of the log as fits in a token budget.
+//! The newest activity always stays in; older events are dropped until the
+//! text fits.  Tokens are estimated at [`CHARS_PER_TOKEN`] characters each,
+//! which is close for English and code with common tokenizers and
+//! overestimates for most other text, so the budget is rarely exceeded.
+
+use std::fmt::Write as _;
+
+use crate::summary::{describe, format_hm, format_ymd};
+use crate::{KeystrokeEvent, LogEntry, Summary};
+
+/// Budget used when a request doesn't give one.
+pub const DEFAULT_CONTEXT_TOKENS: usize = 2000;
+
+/// Characters per token assumed by [`estimate_tokens`].
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Roughly how many tokens `text` takes up.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// The most recent of `entries` (oldest first), rendered as plain text in at
+/// most `max_tokens` estimated tokens.
+pub fn context_blob(entries: &[LogEntry], max_tokens: usize) -> String {
+    if entries.is_empty() {
+        return "No recorded activity.\n".to_string();
+    }
+    let fits = |text: &str| estimate_tokens(text) <= max_tokens;
+
+    // Binary search for the earliest start that fits.  Text grows as
+    // events are added, so anything after a fitting start fits too.
+    let last = entries.len() - 1;
+    let (mut lo, mut hi) = (0, last);
+    let mut best = None;
+    while lo <= hi {
+        let mid = lo + (hi - lo) / 2;
+        let text = render(entries, mid);
+        if fits(&text) {
+            best = Some(text);
+            if mid == 0 {
+                break;
+            }
+            hi = mid - 1;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    // Not even the last event fits: cut it short rather than say nothing.
+    best.unwrap_or_else(|| truncate(&render(entries, last), max_tokens))
+}
+
+/// Render `entries[start..]`, carrying in the pane focused before `start`.
+fn render(entries: &[LogEntry], start: usize) -> String {
+    let (before, tail) = entries.split_at(start);
+    let pane = before.iter().rev().find_map(|entry| match &entry.event {
+        KeystrokeEvent::PaneFocused(pane) => Some(pane.clone()),
+        _ => None,
+    });
+    let summary = Summary::continuing(pane, tail.iter().cloned());
+
+    let mut out = String::from("Recent terminal activity");
+    if let Some((start_ms, end_ms)) = summary.span_ms {
+        let _ = write!(
+            out,
+            ", {} {}–{} UTC",
+            format_ymd(start_ms),
+            format_hm(start_ms),
+            format_hm(end_ms)
+        );
+    }
+    out.push_str(":\n");
+    if start > 0 {
+        let _ = writeln!(out, "({} earlier events omitted)", start);
+    }
+    for annotation in &summary.annotations {
+        let _ = writeln!(out, "📌 {}", annotation);
+    }
+    for switch in &summary.switches {
+        let _ = writeln!(
+            out,
+            "⇄ {}",
+            switch.describe(summary.locale.strings(), |s| format!("{:?}", s))
+        );
+    }
+    for action in &summary.notable {
+        let _ = writeln!(
+            out,
+            "⚠ {}",
+            action.describe(summary.locale.strings(), |s| format!("{:?}", s))
+        );
+    }
+    for segment in &summary.segments {
+        match &segment.pane {
+            Some(pane) => {
+                let _ = writeln!(out, "{}:", pane);
+            }
+            None => out.push_str("(unknown pane):\n"),
+        }
+        for activity in &segment.activities {
+            let _ = writeln!(
+                out,
+                "- {}",
+                describe(
+                    activity,
+                    segment.role(),
+                    summary.locale.strings(),
+                    |s| format!("{:?}", s)
+                )
+            );
+        }
+    }
+    out
+}
+
+/// `text` cut to `max_tokens`, with an ellipsis in place of the rest.
+fn truncate(text: &str, max_tokens: usize) -> String {
+    let keep = (max_tokens * CHARS_PER_TOKEN).saturating_sub(1);
+    let mut out: String = text.chars().take(keep).collect();
+    out.push('…');
+    out
+}
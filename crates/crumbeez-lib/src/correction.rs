@@ -0,0 +1,151 @@
+//! How much typed text was reworked before it was sealed.
+//!
+//! The log keeps text net of corrections (see [`KeystrokeActivity`]), which
+//! hides how it got that way.  [`CorrectionTracker`] follows the edits to a
+//! live buffer and, when the buffer is sealed, sums them up as
+//! [`CorrectionStats`] for the entry's [`LogEntry::corrections`].  Text typed
+//! straight through gets none, so the log only grows where there was
+//! rework to record.
+//!
+//! [`KeystrokeActivity`]: crate::KeystrokeActivity
+//! [`LogEntry::corrections`]: crate::LogEntry::corrections
+
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+/// Most [`HotSpot`]s kept per text.
+pub const HOT_SPOTS: usize = 3;
+
+/// The corrections made to one run of typed text.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct CorrectionStats {
+    /// Characters typed, including those deleted again.
+    pub typed: usize,
+    /// Characters in the text when it was sealed.
+    pub kept: usize,
+    /// Characters deleted with Backspace or Delete.
+    pub deleted: usize,
+    /// Times the cursor was moved within the text.
+    pub moves: usize,
+    /// Where the deleting happened, the most first; at most [`HOT_SPOTS`].
+    pub hot_spots: Vec<HotSpot>,
+}
+
+/// A stretch of text deleted in one go.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HotSpot {
+    /// Character offset into the text where the stretch began, as the text
+    /// was at the time.
+    pub offset: usize,
+    pub deleted: usize,
+}
+
+impl CorrectionStats {
+    /// Stats for `text` typed straight through.
+    pub fn uncorrected(text: &str) -> Self {
+        let chars = text.chars().count();
+        Self {
+            typed: chars,
+            kept: chars,
+            ..Self::default()
+        }
+    }
+
+    /// The share of typed characters deleted again, from 0 to 1.
+    pub fn rework(&self) -> f64 {
+        if self.typed == 0 {
+            0.0
+        } else {
+            self.deleted as f64 / self.typed as f64
+        }
+    }
+
+    /// Add `other`'s counts to these, for totals over many texts.  Hot
+    /// spots are offsets into one text, so they aren't carried over.
+    pub fn add(&mut self, other: &Self) {
+        self.typed += other.typed;
+        self.kept += other.kept;
+        self.deleted += other.deleted;
+        self.moves += other.moves;
+    }
+
+    /// Stats for this text with the text `next` describes typed right after
+    /// it, as if the two were one.
+    pub fn append(&mut self, next: &Self) {
+        let shift = self.kept;
+        self.add(next);
+        self.hot_spots
+            .extend(next.hot_spots.iter().map(|spot| HotSpot {
+                offset: spot.offset + shift,
+                deleted: spot.deleted,
+            }));
+        self.hot_spots.sort_by_key(|spot| Reverse(spot.deleted));
+        self.hot_spots.truncate(HOT_SPOTS);
+    }
+}
+
+/// Follows the edits to a live text buffer; see the [module docs](self).
+#[derive(Debug, Default)]
+pub struct CorrectionTracker {
+    typed: usize,
+    deleted: usize,
+    moves: usize,
+    hot_spots: Vec<HotSpot>,
+}
+
+impl CorrectionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `chars` characters were typed into the buffer.
+    pub fn typed(&mut self, chars: usize) {
+        self.typed += chars;
+    }
+
+    /// A character was deleted from `text` (as it was before the deletion)
+    /// starting at byte offset `at`, by Backspace if `backward`, otherwise
+    /// by Delete.  Deletions running on from the last one grow its hot spot.
+    pub fn deleted(&mut self, text: &str, at: usize, backward: bool) {
+        let offset = text[..at].chars().count();
+        self.deleted += 1;
+        match self.hot_spots.last_mut() {
+            Some(spot) if backward && offset + 1 == spot.offset => {
+                spot.offset = offset;
+                spot.deleted += 1;
+            }
+            Some(spot) if !backward && offset == spot.offset => spot.deleted += 1,
+            _ => self.hot_spots.push(HotSpot { offset, deleted: 1 }),
+        }
+    }
+
+    /// The cursor was moved within the buffer.
+    pub fn moved(&mut self) {
+        self.moves += 1;
+    }
+
+    /// The stats for the buffer, sealed as `text`, or `None` if it was typed
+    /// straight through.  Starts over for the next buffer either way.
+    pub fn finish(&mut self, text: &str) -> Option<CorrectionStats> {
+        let Self {
+            typed,
+            deleted,
+            moves,
+            mut hot_spots,
+        } = std::mem::take(self);
+        if deleted == 0 && moves == 0 {
+            return None;
+        }
+        // Stable, so equal spots stay in the order they were made.
+        hot_spots.sort_by_key(|spot| Reverse(spot.deleted));
+        hot_spots.truncate(HOT_SPOTS);
+        Some(CorrectionStats {
+            typed,
+            kept: text.chars().count(),
+            deleted,
+            moves,
+            hot_spots,
+        })
+    }
+}
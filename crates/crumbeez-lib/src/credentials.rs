@@ -0,0 +1,162 @@
+//! Keeping passwords out of the log.
+//!
+//! sudo, ssh, and the like read a password with echo turned off, so nothing
+//! on screen gives it away, but every key of it still reaches the plugin as
+//! typed text.  Output can't be seen from the plugin, so the prompt is
+//! inferred from the command before it: after a shell command that
+//! [prompts for a secret](prompts_for_secret), the next line typed is taken
+//! to be the answer if it comes within [`SECRET_PROMPT_WINDOW_MS`], and so
+//! are retries after a wrong one if they come within
+//! [`SECRET_RETRY_WINDOW_MS`].  [`CredentialGuard`] keeps track, and the
+//! plugin logs a bare [`KeystrokeEvent::CredentialEntered`] in place of
+//! what was typed, whatever the project's privacy settings say.
+//!
+//! The guess errs on the side of dropping too much: the first command typed
+//! after an ssh that logged in with a key, or a sudo that remembered the
+//! password, is dropped as if it were one.
+//!
+//! [`KeystrokeEvent::CredentialEntered`]: crate::KeystrokeEvent::CredentialEntered
+
+/// How long after a command that prompts for a secret the next line typed
+/// is taken to be the secret.
+pub const SECRET_PROMPT_WINDOW_MS: u64 = 60_000;
+
+/// How long after a secret the next line typed is taken to be another try,
+/// e.g. after sudo's "Sorry, try again."
+pub const SECRET_RETRY_WINDOW_MS: u64 = 10_000;
+
+/// Most lines taken as secrets after one command: sudo's three tries.
+pub const MAX_SECRET_ATTEMPTS: u32 = 3;
+
+/// Commands that prompt for a password or passphrase, by the words they
+/// start with.
+const SECRET_PROMPTS: &[&[&str]] = &[
+    &["sudo"],
+    &["su"],
+    &["doas"],
+    &["pkexec"],
+    &["passwd"],
+    &["ssh"],
+    &["scp"],
+    &["sftp"],
+    &["ssh-add"],
+    &["ssh-keygen"],
+    &["ssh-copy-id"],
+    &["kinit"],
+    &["gpg"],
+    &["docker", "login"],
+    &["podman", "login"],
+    &["npm", "login"],
+    &["npm", "adduser"],
+];
+
+/// Database clients that prompt for a password given a bare `-p` or
+/// `--password`.
+const PASSWORD_FLAG_CLIENTS: [&str; 2] = ["mysql", "mariadb"];
+
+/// Separate commands on one line; any of them can prompt.
+const COMMAND_SEPARATORS: [&str; 5] = ["&&", "||", ";", "|", "&"];
+
+/// Whether `command_line` runs a command that prompts for a password or
+/// passphrase: any of the commands on it, after leading `NAME=value`
+/// assignments.
+///
+/// ```
+/// use crumbeez_lib::prompts_for_secret;
+///
+/// assert!(prompts_for_secret("sudo apt upgrade"));
+/// assert!(prompts_for_secret("cd ~/infra && ssh deploy@prod-db-1"));
+/// assert!(prompts_for_secret("mysql -u root -p shop"));
+/// assert!(!prompts_for_secret("mysql -u root shop"));
+/// assert!(!prompts_for_secret("echo sudo"));
+/// ```
+pub fn prompts_for_secret(command_line: &str) -> bool {
+    let words: Vec<&str> = command_line.split_whitespace().collect();
+    words
+        .split(|word| COMMAND_SEPARATORS.contains(word))
+        .any(|command| {
+            let start = command
+                .iter()
+                .position(|word| !word.contains('=') || word.starts_with('-'))
+                .unwrap_or(command.len());
+            let command = &command[start..];
+            let Some(program) = command.first().and_then(|p| p.rsplit('/').next()) else {
+                return false;
+            };
+            if PASSWORD_FLAG_CLIENTS.contains(&program) {
+                return command[1..]
+                    .iter()
+                    .any(|arg| *arg == "-p" || *arg == "--password");
+            }
+            SECRET_PROMPTS.iter().any(|prompt| {
+                prompt[0] == program
+                    && command.len() >= prompt.len()
+                    && command[1..prompt.len()] == prompt[1..]
+            })
+        })
+}
+
+/// Whether the next line typed is a secret.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CredentialGuard {
+    /// The command prompting, with the time the answer is expected by and
+    /// how many more lines are taken as secrets.
+    awaiting: Option<(String, u64, u32)>,
+}
+
+impl CredentialGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `command` was submitted in a shell at `now_ms`: expect a secret next
+    /// if it prompts for one, and otherwise stop expecting one.
+    pub fn submitted(&mut self, command: &str, now_ms: u64) {
+        self.awaiting = prompts_for_secret(command).then(|| {
+            (
+                command.trim().to_string(),
+                now_ms + SECRET_PROMPT_WINDOW_MS,
+                MAX_SECRET_ATTEMPTS,
+            )
+        });
+    }
+
+    /// Whether text typed at `now_ms` would be a secret.
+    pub fn expects_secret(&self, now_ms: u64) -> bool {
+        self.awaiting
+            .as_ref()
+            .is_some_and(|(_, until_ms, _)| now_ms <= *until_ms)
+    }
+
+    /// Whether a line finished at `now_ms` is a secret.  If it is, another
+    /// try is expected within [`SECRET_RETRY_WINDOW_MS`], until
+    /// [`MAX_SECRET_ATTEMPTS`] are used up.
+    pub fn take_secret(&mut self, now_ms: u64) -> bool {
+        if !self.expects_secret(now_ms) {
+            self.awaiting = None;
+            return false;
+        }
+        if let Some((command, _, attempts)) = self.awaiting.take() {
+            if attempts > 1 {
+                self.awaiting = Some((command, now_ms + SECRET_RETRY_WINDOW_MS, attempts - 1));
+            }
+        }
+        true
+    }
+
+    /// `command` has finished, so it isn't prompting anymore.
+    pub fn finished(&mut self, command: &str) {
+        if self
+            .awaiting
+            .as_ref()
+            .is_some_and(|(awaited, ..)| awaited == command.trim())
+        {
+            self.awaiting = None;
+        }
+    }
+
+    /// Stop expecting a secret, e.g. when another pane is focused.
+    pub fn cancel(&mut self) {
+        self.awaiting = None;
+    }
+}
@@ -0,0 +1,61 @@
+//! Dropping keys reported twice.
+//!
+//! While the plugin's own pane is focused, Zellij delivers each key both as
+//! `Key` and as `InterceptedKeyPress`.  [`DuplicateFilter`] pairs a key from
+//! one source with the same key from the other when they arrive within
+//! [`DUPLICATE_WINDOW_MS`], and lets only the first through.  Repeats from a
+//! single source are never dropped, so holding a key down still logs every
+//! press.
+
+use std::collections::VecDeque;
+
+/// Longest gap between the two reports of one key press.
+pub const DUPLICATE_WINDOW_MS: u64 = 100;
+
+/// Most keys remembered while waiting for their other report.
+const MAX_UNPAIRED: usize = 16;
+
+/// Where a key was reported from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeySource {
+    /// `Event::Key`: the plugin's pane is focused.
+    Focused,
+    /// `Event::InterceptedKeyPress`: any pane.
+    Intercepted,
+}
+
+/// Recent keys not yet matched by a report from the other source.
+#[derive(Debug)]
+pub struct DuplicateFilter<K> {
+    unpaired: VecDeque<(KeySource, K, u64)>,
+}
+
+impl<K> Default for DuplicateFilter<K> {
+    fn default() -> Self {
+        Self {
+            unpaired: VecDeque::new(),
+        }
+    }
+}
+
+impl<K: PartialEq> DuplicateFilter<K> {
+    /// Whether `key`, reported by `source` at `now_ms`, should be logged:
+    /// `false` if it's the other report of a key already let through.
+    pub fn admit(&mut self, source: KeySource, key: K, now_ms: u64) -> bool {
+        self.unpaired
+            .retain(|(_, _, at)| now_ms.saturating_sub(*at) <= DUPLICATE_WINDOW_MS);
+        if let Some(index) = self
+            .unpaired
+            .iter()
+            .position(|(s, k, _)| *s != source && *k == key)
+        {
+            self.unpaired.remove(index);
+            return false;
+        }
+        if self.unpaired.len() >= MAX_UNPAIRED {
+            self.unpaired.pop_front();
+        }
+        self.unpaired.push_back((source, key, now_ms));
+        true
+    }
+}
@@ -0,0 +1,96 @@
+//! Choosing one recorder when crumbeez runs in several panes of a session.
+//!
+//! Every instance intercepts keys session-wide, so two of them would log
+//! each keystroke twice.  Instances find each other from the session's
+//! plugin panes and greet each one with a [`HELLO_PIPE`] message; an
+//! instance that gets a hello from one it didn't know greets it back, so
+//! both end up knowing each other.  Of the instances that have said hello,
+//! the one with the lowest plugin ID — the oldest — records, and the rest
+//! are viewers.  When the recorder's pane closes, the next oldest takes
+//! over.
+//!
+//! An instance without a pane (a background plugin) is never seen closing,
+//! so once it has said hello the others count it as running until they
+//! are reloaded themselves.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Pipe message one instance sends another to announce itself.
+pub const HELLO_PIPE: &str = "crumbeez::hello";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InstanceRole {
+    /// Intercepts keys and writes the log.
+    #[default]
+    Recorder,
+    /// Leaves both to the instance with this plugin ID.
+    Viewer { recorder: u32 },
+}
+
+impl fmt::Display for InstanceRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Recorder => write!(f, "recorder"),
+            Self::Viewer { recorder } => write!(f, "viewer (plugin {} records)", recorder),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Election {
+    own_id: u32,
+    /// Instances that said hello.
+    peers: BTreeSet<u32>,
+    /// Instances whose panes are open.
+    panes: BTreeSet<u32>,
+}
+
+impl Election {
+    /// An election for the instance with plugin ID `own_id`, which records
+    /// until it hears from an older one.
+    pub fn new(own_id: u32) -> Self {
+        Self {
+            own_id,
+            ..Self::default()
+        }
+    }
+
+    pub fn own_id(&self) -> u32 {
+        self.own_id
+    }
+
+    /// Note the plugin IDs of the crumbeez panes now open.  Returns the ones
+    /// newly opened, to greet; instances whose panes closed are forgotten.
+    pub fn set_panes(&mut self, open: impl IntoIterator<Item = u32>) -> Vec<u32> {
+        let open: BTreeSet<u32> = open.into_iter().filter(|id| *id != self.own_id).collect();
+        for closed in self.panes.difference(&open) {
+            self.peers.remove(closed);
+        }
+        let opened = open.difference(&self.panes).copied().collect();
+        self.panes = open;
+        opened
+    }
+
+    /// Note a hello from `peer`.  Returns whether it's new, and so should be
+    /// greeted back.
+    pub fn hello(&mut self, peer: u32) -> bool {
+        peer != self.own_id && self.peers.insert(peer)
+    }
+
+    pub fn role(&self) -> InstanceRole {
+        match self.peers.first() {
+            Some(&oldest) if oldest < self.own_id => InstanceRole::Viewer { recorder: oldest },
+            _ => InstanceRole::Recorder,
+        }
+    }
+
+    pub fn is_recorder(&self) -> bool {
+        self.role() == InstanceRole::Recorder
+    }
+
+    /// Instances that said hello, oldest first.
+    pub fn peers(&self) -> impl Iterator<Item = u32> + '_ {
+        self.peers.iter().copied()
+    }
+}
@@ -2,26 +2,74 @@ use std::collections::VecDeque;
 
 use serde::{Deserialize, Serialize};
 
-use crate::KeystrokeEvent;
+use crate::{compact, CompactionLevel, CorrectionStats, KeystrokeEvent};
 
-const EVENT_LOG_CAPACITY: usize = 10000;
+pub mod iter;
+
+pub(crate) const EVENT_LOG_CAPACITY: usize = 10000;
+
+/// Current on-disk format version.  Versions 1 (a header followed by bare
+/// entries), 2 (records without checksums), and 3 (append frames that don't
+/// say where they continue from) are still readable.
+const LOG_FORMAT_VERSION: u32 = 4;
+
+/// Start of a version 3+ file.  Older files start with a MessagePack header.
+const LOG_MAGIC: &[u8; 4] = b"CRBZ";
+
+/// Bytes before each frame's payload: its length and checksum, both `u32`
+/// little-endian.
+const FRAME_HEADER_LEN: usize = 8;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
     pub event: KeystrokeEvent,
     pub timestamp_ms: u64,
+    /// How a [`TextTyped`](KeystrokeEvent::TextTyped) entry's text was
+    /// corrected while it was typed, if it was.  Older logs have none.
+    /// Boxed, since most entries have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corrections: Option<Box<CorrectionStats>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct LogHeader {
     version: u32,
+    /// Number of entries at the start of the file already summarized.
     consumed_count: u64,
+    /// Sequence number of the first entry in the file (version 2+).
+    #[serde(default)]
+    first_seq: u64,
+}
+
+/// A record following the header in a version 2+ log.  Incremental saves
+/// append new entries plus a fresh consumed marker, so nothing already on
+/// disk has to be rewritten.
+#[derive(Debug, Deserialize)]
+enum LogRecord {
+    Entry(LogEntry),
+    /// Every entry with a lower sequence number has been consumed.
+    Consumed(u64),
+    /// Starts an append frame (version 4+): the sequence number of its
+    /// first entry, which the frames before it must end at.
+    Follows(u64),
+}
+
+/// Borrowing counterpart of [`LogRecord`] for serialization.
+#[derive(Debug, Serialize)]
+enum LogRecordRef<'a> {
+    Entry(&'a LogEntry),
+    Consumed(u64),
+    Follows(u64),
 }
 
 #[derive(Debug)]
 pub struct EventLog {
     events: VecDeque<LogEntry>,
     consumed_count: usize,
+    capacity: usize,
+    /// Sequence number of `events[0]`.  Every appended entry gets the next
+    /// number, so incremental saves can say "everything from here on".
+    first_seq: u64,
 }
 
 impl Default for EventLog {
@@ -35,25 +83,126 @@ impl EventLog {
         Self {
             events: VecDeque::with_capacity(EVENT_LOG_CAPACITY),
             consumed_count: 0,
+            capacity: EVENT_LOG_CAPACITY,
+            first_seq: 0,
+        }
+    }
+
+    /// Drop the oldest entry, keeping the sequence numbering intact.
+    fn pop_front(&mut self) {
+        if self.events.pop_front().is_some() {
+            self.first_seq += 1;
+        }
+    }
+
+    /// Change the maximum number of retained entries, dropping the oldest
+    /// entries immediately if the log is already over the new limit.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.events.len() > self.capacity {
+            self.pop_front();
+            self.consumed_count = self.consumed_count.saturating_sub(1);
+        }
+    }
+
+    /// Drop all entries with a timestamp before `cutoff_ms`.
+    pub fn prune_before(&mut self, cutoff_ms: u64) {
+        while self
+            .events
+            .front()
+            .is_some_and(|entry| entry.timestamp_ms < cutoff_ms)
+        {
+            self.pop_front();
+            self.consumed_count = self.consumed_count.saturating_sub(1);
+        }
+    }
+
+    /// [Compact](compact) the summarized entries with a timestamp before
+    /// `cutoff_ms` at `level`.  Entries after them keep their sequence
+    /// numbers, so the compacted ones move up to end just before them.
+    /// Returns how many entries fewer there are.
+    ///
+    /// Like [`backfill`](Self::backfill), this changes entries already
+    /// saved, so the log has to be saved in full afterwards.
+    pub fn compact_before(&mut self, cutoff_ms: u64, level: CompactionLevel) -> usize {
+        let old = self
+            .events
+            .iter()
+            .take(self.consumed_count)
+            .take_while(|entry| entry.timestamp_ms < cutoff_ms)
+            .count();
+        let compacted = compact(self.events.drain(..old), level);
+        let removed = old - compacted.len();
+        for entry in compacted.into_iter().rev() {
+            self.events.push_front(entry);
         }
+        self.first_seq += removed as u64;
+        self.consumed_count -= removed;
+        removed
     }
 
     pub fn append(&mut self, event: KeystrokeEvent, timestamp_ms: u64) {
-        if self.events.len() >= EVENT_LOG_CAPACITY {
+        self.append_entry(LogEntry {
+            event,
+            timestamp_ms,
+            corrections: None,
+        });
+    }
+
+    /// Like [`append`](Self::append), for an entry carrying more than its
+    /// event, e.g. [`corrections`](LogEntry::corrections).
+    pub fn append_entry(&mut self, entry: LogEntry) {
+        if self.events.len() >= self.capacity {
             if self.consumed_count > 0 {
                 let to_remove = self.consumed_count.min(self.events.len());
                 for _ in 0..to_remove {
-                    self.events.pop_front();
+                    self.pop_front();
                 }
                 self.consumed_count = 0;
             } else {
-                self.events.pop_front();
+                self.pop_front();
             }
         }
-        self.events.push_back(LogEntry {
-            event,
-            timestamp_ms,
-        });
+        self.events.push_back(entry);
+    }
+
+    /// Nothing has been summarized or dropped from the log yet, so nothing
+    /// refers to its entries' sequence numbers.
+    pub fn is_fresh(&self) -> bool {
+        self.first_seq == 0 && self.consumed_count == 0
+    }
+
+    /// Put `entries`, oldest first, in front of a [fresh](Self::is_fresh)
+    /// log, e.g. commands from [shell history](crate::history_entries).
+    /// They count as summarized, since they happened before crumbeez was
+    /// around to watch.  Only entries older than the log's oldest fit, and
+    /// only as many as its capacity leaves room for, the newest first.
+    /// Returns how many were added, none if the log isn't fresh.
+    ///
+    /// Entries already in the log move to later sequence numbers, so the
+    /// log has to be saved in full afterwards rather than appended to.
+    pub fn backfill(&mut self, entries: Vec<LogEntry>) -> usize {
+        if !self.is_fresh() {
+            return 0;
+        }
+        let oldest = self.events.front().map_or(u64::MAX, |e| e.timestamp_ms);
+        let room = self.capacity.saturating_sub(self.events.len());
+        let older: Vec<LogEntry> = entries
+            .into_iter()
+            .filter(|e| e.timestamp_ms < oldest)
+            .collect();
+        let skip = older.len().saturating_sub(room);
+        let added = older.len() - skip;
+        for entry in older.into_iter().skip(skip).rev() {
+            self.events.push_front(entry);
+        }
+        self.consumed_count = added;
+        added
+    }
+
+    /// Every entry still in memory, consumed or not, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &LogEntry> {
+        self.events.iter()
     }
 
     pub fn unconsumed(&self) -> impl Iterator<Item = &LogEntry> {
@@ -76,92 +225,408 @@ impl EventLog {
         if self.consumed_count > 0 {
             let to_remove = self.consumed_count.min(self.events.len());
             for _ in 0..to_remove {
-                self.events.pop_front();
+                self.pop_front();
             }
             self.consumed_count = 0;
         }
     }
 
+    /// A copy of the log with every entry passed through `f`, keeping the
+    /// consumed position and sequence numbers.
+    pub fn map_entries(&self, f: impl FnMut(&LogEntry) -> LogEntry) -> Self {
+        Self {
+            events: self.events.iter().map(f).collect(),
+            consumed_count: self.consumed_count,
+            capacity: self.capacity,
+            first_seq: self.first_seq,
+        }
+    }
+
+    /// Sequence number of the oldest entry still in memory.
+    pub fn first_seq(&self) -> u64 {
+        self.first_seq
+    }
+
+    /// Sequence number the next appended entry will get.
+    pub fn next_seq(&self) -> u64 {
+        self.first_seq + self.events.len() as u64
+    }
+
     pub fn serialize(&self) -> Result<Vec<u8>, EventLogError> {
-        let mut buf = Vec::new();
+        encode_records(false, self.first_seq, self.consumed_seq(), &self.events)
+    }
 
-        let header = LogHeader {
-            version: 1,
-            consumed_count: self.consumed_count as u64,
-        };
-        rmp_serde::encode::write(&mut buf, &header)
-            .map_err(|e| EventLogError::Serialization(e.to_string()))?;
+    /// Serialize only what changed since a save that ended at sequence number
+    /// `last_saved` (the [`next_seq`](Self::next_seq) at the time): entries
+    /// appended since, plus the current consumed position.  The result is
+    /// meant to be appended to a file written by [`serialize`](Self::serialize).
+    ///
+    /// Fails with [`EventLogError::MissingEntries`] if some of those entries
+    /// were already dropped from memory; the caller should fall back to a
+    /// full save.
+    pub fn serialize_since(&self, last_saved: u64) -> Result<Vec<u8>, EventLogError> {
+        let skip = self.offset_of(last_saved)?;
+        encode_records(
+            true,
+            last_saved,
+            self.consumed_seq(),
+            self.events.iter().skip(skip),
+        )
+    }
 
-        for entry in &self.events {
-            rmp_serde::encode::write(&mut buf, entry)
-                .map_err(|e| EventLogError::Serialization(e.to_string()))?;
-        }
+    /// Copy out what [`serialize`](Self::serialize) (with `since: None`) or
+    /// [`serialize_since`](Self::serialize_since) would encode, so the
+    /// encoding itself can happen elsewhere.
+    pub fn save_frame(&self, since: Option<u64>) -> Result<SaveFrame, EventLogError> {
+        let first_seq = since.unwrap_or(self.first_seq);
+        let skip = self.offset_of(first_seq)?;
+        Ok(SaveFrame {
+            append: since.is_some(),
+            first_seq,
+            consumed_seq: self.consumed_seq(),
+            entries: self.events.iter().skip(skip).cloned().collect(),
+        })
+    }
+
+    /// Sequence number of the oldest unconsumed entry, or of the next entry
+    /// if there are none.
+    pub fn consumed_seq(&self) -> u64 {
+        self.first_seq + self.consumed_count as u64
+    }
 
-        Ok(buf)
+    /// Index into `events` of sequence number `seq`.
+    fn offset_of(&self, seq: u64) -> Result<usize, EventLogError> {
+        if seq < self.first_seq || seq > self.next_seq() {
+            return Err(EventLogError::MissingEntries(format!(
+                "entries from {} are not in memory (have {}..{})",
+                seq,
+                self.first_seq,
+                self.next_seq()
+            )));
+        }
+        Ok((seq - self.first_seq) as usize)
     }
 
+    /// Load a log, failing on any damage.  See
+    /// [`deserialize_lossy`](Self::deserialize_lossy) to salvage what's
+    /// intact.
     pub fn deserialize(data: &[u8]) -> Result<Self, EventLogError> {
-        let mut cursor = std::io::Cursor::new(data);
+        match Self::deserialize_lossy(data)? {
+            (log, None) => Ok(log),
+            (_, Some(damage)) => Err(damage),
+        }
+    }
+
+    /// Load a log, keeping every frame up to the first damaged one.  The
+    /// error describing the damage, if any, is returned alongside the
+    /// recovered log.  Fails outright only if the header itself is
+    /// unreadable.
+    ///
+    /// Version 1 and 2 files have no checksums, so damage to them is only
+    /// caught where it breaks decoding.
+    pub fn deserialize_lossy(data: &[u8]) -> Result<(Self, Option<EventLogError>), EventLogError> {
+        if data.starts_with(LOG_MAGIC) {
+            Self::deserialize_frames(data)
+        } else {
+            Self::deserialize_unframed(data).map(|log| (log, None))
+        }
+    }
 
+    /// Version 3+: checksummed frames, the first holding the header.
+    fn deserialize_frames(data: &[u8]) -> Result<(Self, Option<EventLogError>), EventLogError> {
+        let mut frames = Frames {
+            data,
+            offset: LOG_MAGIC.len(),
+        };
+        let header_frame = frames
+            .next()
+            .unwrap_or_else(|| Err(EventLogError::Corrupted("missing header frame".to_string())))?;
+        let mut cursor = std::io::Cursor::new(header_frame);
         let header: LogHeader = rmp_serde::decode::from_read(&mut cursor)
             .map_err(|e| EventLogError::Deserialization(e.to_string()))?;
-
-        if header.version != 1 {
+        if header.version < 3 || header.version > LOG_FORMAT_VERSION {
             return Err(EventLogError::InvalidFormat(format!(
-                "unsupported version: {}",
+                "unsupported version in framed log: {}",
                 header.version
             )));
         }
 
+        let first_seq = header.first_seq;
+        let mut consumed_seq = first_seq + header.consumed_count;
+        let mut events = VecDeque::new();
+        let mut damage = decode_frame_records(
+            &mut cursor,
+            LOG_MAGIC.len(),
+            first_seq,
+            &mut events,
+            &mut consumed_seq,
+        )
+        .err();
+        while damage.is_none() {
+            let at = frames.offset;
+            let Some(frame) = frames.next() else {
+                break;
+            };
+            damage = frame
+                .and_then(|payload| {
+                    decode_frame_records(
+                        &mut std::io::Cursor::new(payload),
+                        at,
+                        first_seq,
+                        &mut events,
+                        &mut consumed_seq,
+                    )
+                })
+                .err();
+        }
+
+        let consumed_count = (consumed_seq.saturating_sub(first_seq) as usize).min(events.len());
+        let log = Self {
+            events,
+            consumed_count,
+            capacity: EVENT_LOG_CAPACITY,
+            first_seq,
+        };
+        Ok((log, damage))
+    }
+
+    /// Versions 1 and 2: a header followed by a bare record stream.
+    fn deserialize_unframed(data: &[u8]) -> Result<Self, EventLogError> {
+        let mut cursor = std::io::Cursor::new(data);
+
+        let header: LogHeader = rmp_serde::decode::from_read(&mut cursor)
+            .map_err(|e| EventLogError::Deserialization(e.to_string()))?;
+
+        let first_seq = header.first_seq;
+        let mut consumed_seq = first_seq + header.consumed_count;
         let mut events = VecDeque::new();
-        loop {
-            match rmp_serde::decode::from_read::<_, LogEntry>(&mut cursor) {
-                Ok(entry) => events.push_back(entry),
-                Err(e) if e.to_string().contains("unexpected EOF") => break,
-                Err(e) => return Err(EventLogError::Deserialization(e.to_string())),
+        match header.version {
+            1 => loop {
+                match rmp_serde::decode::from_read::<_, LogEntry>(&mut cursor) {
+                    Ok(entry) => events.push_back(entry),
+                    Err(e) if e.to_string().contains("unexpected EOF") => break,
+                    Err(e) => return Err(EventLogError::Deserialization(e.to_string())),
+                }
+            },
+            2 => loop {
+                match rmp_serde::decode::from_read::<_, LogRecord>(&mut cursor) {
+                    Ok(LogRecord::Entry(entry)) => events.push_back(entry),
+                    Ok(LogRecord::Consumed(seq)) => consumed_seq = seq,
+                    // Only version 4+ frames have these.
+                    Ok(LogRecord::Follows(_)) => {}
+                    Err(e) if e.to_string().contains("unexpected EOF") => break,
+                    Err(e) => return Err(EventLogError::Deserialization(e.to_string())),
+                }
+            },
+            version => {
+                return Err(EventLogError::InvalidFormat(format!(
+                    "unsupported version: {}",
+                    version
+                )))
             }
         }
 
-        let consumed_count = (header.consumed_count as usize).min(events.len());
+        let consumed_count = (consumed_seq.saturating_sub(first_seq) as usize).min(events.len());
 
         Ok(Self {
             events,
             consumed_count,
+            capacity: EVENT_LOG_CAPACITY,
+            first_seq,
         })
     }
 }
 
-#[derive(Debug)]
-pub struct Summary {
-    pub events_consumed: usize,
-    pub event_types: std::collections::HashMap<String, usize>,
-}
-
-impl Summary {
-    pub fn from_events(entries: impl Iterator<Item = LogEntry>) -> Self {
-        let mut events_consumed = 0;
-        let mut event_types = std::collections::HashMap::new();
-
-        for entry in entries {
-            events_consumed += 1;
-            let type_name = match entry.event {
-                KeystrokeEvent::TextTyped(_) => "TextTyped",
-                KeystrokeEvent::Shortcut(_) => "Shortcut",
-                KeystrokeEvent::Navigation(_) => "Navigation",
-                KeystrokeEvent::EditControl(_) => "EditControl",
-                KeystrokeEvent::Escape => "Escape",
-                KeystrokeEvent::FunctionKey(_) => "FunctionKey",
-                KeystrokeEvent::SystemKey(_) => "SystemKey",
-                KeystrokeEvent::PaneFocused(_) => "PaneFocused",
-            };
-            *event_types.entry(type_name.to_string()).or_insert(0) += 1;
+// ── Save frames ──────────────────────────────────────────────────
+
+/// A batch of log data to persist, detached from the [`EventLog`] so it can
+/// be handed to a plugin worker for encoding and writing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveFrame {
+    /// Append to a file written earlier rather than replacing it.
+    pub append: bool,
+    /// Sequence number of `entries[0]`.
+    pub first_seq: u64,
+    /// Sequence number of the first unconsumed entry.
+    pub consumed_seq: u64,
+    pub entries: Vec<LogEntry>,
+}
+
+impl SaveFrame {
+    /// Encode in the on-disk format, exactly as the corresponding
+    /// [`EventLog::serialize`] or [`EventLog::serialize_since`] call would.
+    pub fn encode(&self) -> Result<Vec<u8>, EventLogError> {
+        encode_records(
+            self.append,
+            self.first_seq,
+            self.consumed_seq,
+            &self.entries,
+        )
+    }
+}
+
+/// Encode a full file (header and entries) or, with `append`, the entries
+/// plus a consumed marker.
+fn encode_records<'a>(
+    append: bool,
+    first_seq: u64,
+    consumed_seq: u64,
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+) -> Result<Vec<u8>, EventLogError> {
+    let mut payload = Vec::new();
+    let buf = &mut payload;
+    let encode_err = |e: rmp_serde::encode::Error| EventLogError::Serialization(e.to_string());
+
+    if !append {
+        let header = LogHeader {
+            version: LOG_FORMAT_VERSION,
+            consumed_count: consumed_seq.saturating_sub(first_seq),
+            first_seq,
+        };
+        rmp_serde::encode::write(buf, &header).map_err(encode_err)?;
+    }
+    if append {
+        rmp_serde::encode::write(buf, &LogRecordRef::Follows(first_seq)).map_err(encode_err)?;
+    }
+    for entry in entries {
+        rmp_serde::encode::write(buf, &LogRecordRef::Entry(entry)).map_err(encode_err)?;
+    }
+    if append {
+        rmp_serde::encode::write(buf, &LogRecordRef::Consumed(consumed_seq)).map_err(encode_err)?;
+    }
+
+    let len = u32::try_from(payload.len())
+        .map_err(|_| EventLogError::Serialization("frame too large".to_string()))?;
+    let mut out = Vec::with_capacity(LOG_MAGIC.len() + FRAME_HEADER_LEN + payload.len());
+    if !append {
+        out.extend_from_slice(LOG_MAGIC);
+    }
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(&crc32(&payload).to_le_bytes());
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+// ── Frames ───────────────────────────────────────────────────────
+
+/// Every save writes one frame: a full save the header and all entries, an
+/// incremental one the new entries and a consumed marker.  Each carries its
+/// own checksum so an append can't invalidate what's already on disk, and a
+/// truncated or corrupted tail costs only the frames it touches.
+///
+/// An incremental frame also says which entry it continues from, so frames
+/// lost before it, say to a file cut short between two saves, are caught
+/// when it's loaded.  A file cut after its last frame is indistinguishable
+/// from the same log saved earlier; nothing in the file can tell.
+struct Frames<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for Frames<'a> {
+    /// A frame's payload, or what's wrong with it.  Iteration stops after
+    /// the first damaged frame, since its length can't be trusted.
+    type Item = Result<&'a [u8], EventLogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.data[self.offset..];
+        if rest.is_empty() {
+            return None;
         }
+        let at = self.offset;
+        // Whatever happens, this is the last frame unless it checks out.
+        self.offset = self.data.len();
 
-        Summary {
-            events_consumed,
-            event_types,
+        if rest.len() < FRAME_HEADER_LEN {
+            return Some(Err(EventLogError::Corrupted(format!(
+                "truncated frame header at byte {}",
+                at
+            ))));
         }
+        let len = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let checksum = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]);
+        let Some(payload) = rest[FRAME_HEADER_LEN..].get(..len) else {
+            return Some(Err(EventLogError::Corrupted(format!(
+                "frame at byte {} is truncated ({} of {} bytes)",
+                at,
+                rest.len() - FRAME_HEADER_LEN,
+                len
+            ))));
+        };
+        if crc32(payload) != checksum {
+            return Some(Err(EventLogError::Corrupted(format!(
+                "checksum mismatch in frame at byte {}",
+                at
+            ))));
+        }
+        self.offset = at + FRAME_HEADER_LEN + len;
+        Some(Ok(payload))
+    }
+}
+
+/// Decode the records in the checksummed frame at byte `at` of a log
+/// starting at `first_seq`.  Entries are only kept if the whole frame
+/// decodes and continues where `events` ends.
+fn decode_frame_records(
+    cursor: &mut std::io::Cursor<&[u8]>,
+    at: usize,
+    first_seq: u64,
+    events: &mut VecDeque<LogEntry>,
+    consumed_seq: &mut u64,
+) -> Result<(), EventLogError> {
+    let mut entries = Vec::new();
+    let mut consumed = None;
+    let len = cursor.get_ref().len() as u64;
+    while cursor.position() < len {
+        match rmp_serde::decode::from_read::<_, LogRecord>(&mut *cursor) {
+            Ok(LogRecord::Entry(entry)) => entries.push(entry),
+            Ok(LogRecord::Consumed(seq)) => consumed = Some(seq),
+            Ok(LogRecord::Follows(seq)) => {
+                let ends_at = first_seq + events.len() as u64;
+                if seq != ends_at {
+                    return Err(EventLogError::Corrupted(format!(
+                        "frame at byte {} continues from entry {} but the frames before it end at entry {}",
+                        at, seq, ends_at
+                    )));
+                }
+            }
+            Err(e) => return Err(EventLogError::Deserialization(e.to_string())),
+        }
+    }
+    events.extend(entries);
+    if let Some(seq) = consumed {
+        *consumed_seq = seq;
+    }
+    Ok(())
+}
+
+/// CRC-32 (IEEE 802.3, as in zlib and PNG).
+fn crc32(bytes: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 != 0 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc = TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
     }
+    !crc
 }
 
 #[derive(Debug)]
@@ -169,6 +634,9 @@ pub enum EventLogError {
     InvalidFormat(String),
     Serialization(String),
     Deserialization(String),
+    MissingEntries(String),
+    /// A checksum or length didn't match; the file was truncated or damaged.
+    Corrupted(String),
 }
 
 impl std::fmt::Display for EventLogError {
@@ -177,6 +645,8 @@ impl std::fmt::Display for EventLogError {
             Self::InvalidFormat(msg) => write!(f, "Invalid format: {}", msg),
             Self::Serialization(msg) => write!(f, "Serialization error: {}", msg),
             Self::Deserialization(msg) => write!(f, "Deserialization error: {}", msg),
+            Self::MissingEntries(msg) => write!(f, "Missing entries: {}", msg),
+            Self::Corrupted(msg) => write!(f, "Corrupted: {}", msg),
         }
     }
 }
@@ -0,0 +1,216 @@
+//! Filtered views of log entries.
+//!
+//! Summaries, reports, and exports each want some slice of the log: one
+//! day of it, the keys sent to each pane, typing without the cursor moves
+//! in between.  [`EntryIter`] adds those as adapters on any iterator of
+//! entries, borrowed or owned, so they compose and every consumer slices
+//! the log the same way, e.g.
+//! `log.entries().between(start, end).without_nav().coalesce_text()`.
+//! [`matching`](EntryIter::matching) finds entries for a search.
+
+use std::borrow::Borrow;
+use std::iter::Peekable;
+
+use super::LogEntry;
+use crate::{KeystrokeEvent, PaneFocusedEvent};
+
+/// Adapters for iterators of [`LogEntry`]s, oldest first.
+pub trait EntryIter: Iterator + Sized
+where
+    Self::Item: Borrow<LogEntry>,
+{
+    /// Entries from `start_ms` up to but not including `end_ms`.
+    fn between(self, start_ms: u64, end_ms: u64) -> Between<Self> {
+        Between {
+            iter: self,
+            start_ms,
+            end_ms,
+        }
+    }
+
+    /// The entries split into runs sent to one pane.  Focus entries start
+    /// the runs rather than appearing in them, and refocusing the pane
+    /// already focused continues its run.
+    fn by_pane(self) -> ByPane<Self> {
+        ByPane {
+            iter: self.peekable(),
+        }
+    }
+
+    /// Consecutive [`TextTyped`](KeystrokeEvent::TextTyped) entries joined
+    /// into one, at the first one's time.  Entries are recorded coalesced
+    /// already; this joins what other adapters, like
+    /// [`without_nav`](Self::without_nav), brought together.
+    fn coalesce_text(self) -> CoalesceText<Self> {
+        CoalesceText {
+            iter: self.peekable(),
+        }
+    }
+
+    /// Everything but [`Navigation`](KeystrokeEvent::Navigation) entries.
+    fn without_nav(self) -> WithoutNav<Self> {
+        WithoutNav { iter: self }
+    }
+
+    /// Entries whose event, as displayed, contains `query`, ignoring case
+    /// (see [`KeystrokeEvent::matches`]).
+    fn matching(self, query: &str) -> Matching<Self> {
+        Matching {
+            iter: self,
+            query: query.to_string(),
+        }
+    }
+}
+
+impl<I> EntryIter for I
+where
+    I: Iterator,
+    I::Item: Borrow<LogEntry>,
+{
+}
+
+/// See [`EntryIter::between`].
+#[derive(Debug, Clone)]
+pub struct Between<I> {
+    iter: I,
+    start_ms: u64,
+    end_ms: u64,
+}
+
+impl<I> Iterator for Between<I>
+where
+    I: Iterator,
+    I::Item: Borrow<LogEntry>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (start_ms, end_ms) = (self.start_ms, self.end_ms);
+        self.iter.find(|entry| {
+            let t = entry.borrow().timestamp_ms;
+            t >= start_ms && t < end_ms
+        })
+    }
+}
+
+/// Entries sent to one pane, from [`EntryIter::by_pane`].
+#[derive(Debug, Clone)]
+pub struct PaneRun<T> {
+    /// `None` for entries from before the log saw any focus.
+    pub pane: Option<PaneFocusedEvent>,
+    pub entries: Vec<T>,
+}
+
+/// See [`EntryIter::by_pane`].
+pub struct ByPane<I: Iterator> {
+    iter: Peekable<I>,
+}
+
+impl<I> Iterator for ByPane<I>
+where
+    I: Iterator,
+    I::Item: Borrow<LogEntry>,
+{
+    type Item = PaneRun<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut run: Option<PaneRun<I::Item>> = None;
+        while let Some(entry) = self.iter.peek() {
+            let focused = match &entry.borrow().event {
+                KeystrokeEvent::PaneFocused(pane) => Some(pane.clone()),
+                _ => None,
+            };
+            match (focused, &mut run) {
+                (Some(pane), None) => {
+                    run = Some(PaneRun {
+                        pane: Some(pane),
+                        entries: Vec::new(),
+                    });
+                }
+                (Some(pane), Some(run)) => {
+                    if run.pane.as_ref() != Some(&pane) {
+                        break;
+                    }
+                }
+                (None, run) => {
+                    let entry = self.iter.next()?;
+                    run.get_or_insert_with(|| PaneRun {
+                        pane: None,
+                        entries: Vec::new(),
+                    })
+                    .entries
+                    .push(entry);
+                    continue;
+                }
+            }
+            self.iter.next();
+        }
+        run
+    }
+}
+
+/// See [`EntryIter::coalesce_text`].
+pub struct CoalesceText<I: Iterator> {
+    iter: Peekable<I>,
+}
+
+impl<I> Iterator for CoalesceText<I>
+where
+    I: Iterator,
+    I::Item: Borrow<LogEntry>,
+{
+    type Item = LogEntry;
+
+    fn next(&mut self) -> Option<LogEntry> {
+        let mut entry = self.iter.next()?.borrow().clone();
+        if let KeystrokeEvent::TextTyped(ref mut text) = entry.event {
+            while let Some(next) = self.iter.peek() {
+                let KeystrokeEvent::TextTyped(more) = &next.borrow().event else {
+                    break;
+                };
+                text.push_str(more);
+                self.iter.next();
+            }
+        }
+        Some(entry)
+    }
+}
+
+/// See [`EntryIter::without_nav`].
+#[derive(Debug, Clone)]
+pub struct WithoutNav<I> {
+    iter: I,
+}
+
+impl<I> Iterator for WithoutNav<I>
+where
+    I: Iterator,
+    I::Item: Borrow<LogEntry>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .find(|entry| !matches!(entry.borrow().event, KeystrokeEvent::Navigation(_)))
+    }
+}
+
+/// See [`EntryIter::matching`].
+#[derive(Debug, Clone)]
+pub struct Matching<I> {
+    iter: I,
+    query: String,
+}
+
+impl<I> Iterator for Matching<I>
+where
+    I: Iterator,
+    I::Item: Borrow<LogEntry>,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let query = &self.query;
+        self.iter.find(|entry| entry.borrow().event.matches(query))
+    }
+}
@@ -0,0 +1,626 @@
+//! Building events without spelling out their structs, and reading them
+//! back from the text they display as.
+//!
+//! Tests, fixtures, and frontends other than the plugin make events by
+//! hand.  The constructors here save them assembling nested structs:
+//!
+//! ```
+//! use crumbeez_lib::{KeystrokeEvent, NavigationEvent};
+//!
+//! let save = KeystrokeEvent::shortcut("Ctrl+Shift+S");
+//! let back = KeystrokeEvent::from(NavigationEvent::left(3).ctrl());
+//! assert_eq!(back.to_string(), "nav Ctrl+← ×3");
+//! ```
+//!
+//! Every event type also parses from its `Display` form with [`FromStr`],
+//! so `"shortcut Ctrl+Shift+S".parse::<KeystrokeEvent>()` gives `save`
+//! back.  Parsing recovers what the text shows: a focused pane's tab,
+//! command name, title, role, host, and container, but not its IDs or the
+//! full command line, and a flood's duration to the tenth of a second.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{
+    is_tag, AppRole, CommandOutcome, EditControlEvent, EnvironmentKind, EventKind, KeystrokeEvent,
+    NavDirection, NavigationEvent, PaneFocusedEvent, ShortcutEvent, ShortcutKey, SuspendReason,
+    SystemKeyEvent,
+};
+
+/// Why text isn't the `Display` form of an event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventParseError {
+    text: String,
+    expected: &'static str,
+}
+
+impl EventParseError {
+    fn new(text: &str, expected: &'static str) -> Self {
+        Self {
+            text: text.to_string(),
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for EventParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' isn't {}", self.text, self.expected)
+    }
+}
+
+impl std::error::Error for EventParseError {}
+
+// ── Constructors ─────────────────────────────────────────────────
+
+impl KeystrokeEvent {
+    /// Text typed.
+    pub fn typed(text: impl Into<String>) -> Self {
+        Self::TextTyped(text.into())
+    }
+
+    /// A shortcut written as it displays, e.g. `"Ctrl+Shift+P"` or
+    /// `"Alt+←"`.
+    ///
+    /// # Panics
+    ///
+    /// If `chord` isn't a shortcut.  Parse untrusted text with
+    /// [`ShortcutEvent::from_str`] instead.
+    pub fn shortcut(chord: &str) -> Self {
+        match chord.parse() {
+            Ok(shortcut) => Self::Shortcut(shortcut),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// A bookmark.
+    pub fn annotation(text: impl Into<String>) -> Self {
+        Self::Annotation(text.into())
+    }
+}
+
+impl NavigationEvent {
+    /// `count` presses of the key for `direction`, without modifiers.
+    pub fn new(direction: NavDirection, count: usize) -> Self {
+        Self {
+            direction,
+            count,
+            with_shift: false,
+            with_ctrl: false,
+        }
+    }
+
+    pub fn left(count: usize) -> Self {
+        Self::new(NavDirection::Left, count)
+    }
+
+    pub fn right(count: usize) -> Self {
+        Self::new(NavDirection::Right, count)
+    }
+
+    pub fn up(count: usize) -> Self {
+        Self::new(NavDirection::Up, count)
+    }
+
+    pub fn down(count: usize) -> Self {
+        Self::new(NavDirection::Down, count)
+    }
+
+    pub fn home() -> Self {
+        Self::new(NavDirection::Home, 1)
+    }
+
+    pub fn end() -> Self {
+        Self::new(NavDirection::End, 1)
+    }
+
+    pub fn page_up(count: usize) -> Self {
+        Self::new(NavDirection::PageUp, count)
+    }
+
+    pub fn page_down(count: usize) -> Self {
+        Self::new(NavDirection::PageDown, count)
+    }
+
+    /// The same moves with Shift held, selecting.
+    pub fn shift(self) -> Self {
+        Self {
+            with_shift: true,
+            ..self
+        }
+    }
+
+    /// The same moves with Ctrl held, by word.
+    pub fn ctrl(self) -> Self {
+        Self {
+            with_ctrl: true,
+            ..self
+        }
+    }
+}
+
+impl EditControlEvent {
+    pub fn backspace(count: usize) -> Self {
+        Self::Backspace { count }
+    }
+
+    pub fn delete(count: usize) -> Self {
+        Self::Delete { count }
+    }
+}
+
+impl PaneFocusedEvent {
+    /// A terminal pane titled `title`, with nothing else known about it.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            pane_title: title.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn in_tab(self, tab: impl Into<String>) -> Self {
+        Self {
+            tab_name: Some(tab.into()),
+            ..self
+        }
+    }
+
+    pub fn running(self, command: impl Into<String>) -> Self {
+        Self {
+            command: Some(command.into()),
+            ..self
+        }
+    }
+
+    pub fn with_role(self, role: AppRole) -> Self {
+        Self { role, ..self }
+    }
+
+    pub fn with_id(self, pane_id: u32) -> Self {
+        Self {
+            pane_id: Some(pane_id),
+            ..self
+        }
+    }
+}
+
+impl From<ShortcutEvent> for KeystrokeEvent {
+    fn from(shortcut: ShortcutEvent) -> Self {
+        Self::Shortcut(shortcut)
+    }
+}
+
+impl From<NavigationEvent> for KeystrokeEvent {
+    fn from(nav: NavigationEvent) -> Self {
+        Self::Navigation(nav)
+    }
+}
+
+impl From<EditControlEvent> for KeystrokeEvent {
+    fn from(edit: EditControlEvent) -> Self {
+        Self::EditControl(edit)
+    }
+}
+
+impl From<SystemKeyEvent> for KeystrokeEvent {
+    fn from(key: SystemKeyEvent) -> Self {
+        Self::SystemKey(key)
+    }
+}
+
+impl From<PaneFocusedEvent> for KeystrokeEvent {
+    fn from(pane: PaneFocusedEvent) -> Self {
+        Self::PaneFocused(pane)
+    }
+}
+
+// ── Parsing ──────────────────────────────────────────────────────
+
+impl FromStr for ShortcutKey {
+    type Err = EventParseError;
+
+    /// A key as it displays, or by its name: `Left` as well as `←`,
+    /// `PageUp` as well as `PgUp`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Enter" => Self::Enter,
+            "Tab" => Self::Tab,
+            "Backspace" => Self::Backspace,
+            "Delete" => Self::Delete,
+            "Esc" => Self::Esc,
+            "Insert" => Self::Insert,
+            "←" | "Left" => Self::Left,
+            "→" | "Right" => Self::Right,
+            "↑" | "Up" => Self::Up,
+            "↓" | "Down" => Self::Down,
+            "Home" => Self::Home,
+            "End" => Self::End,
+            "PgUp" | "PageUp" => Self::PageUp,
+            "PgDn" | "PageDown" => Self::PageDown,
+            _ => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Self::Char(c),
+                    _ => match s.strip_prefix('F').and_then(|n| n.parse().ok()) {
+                        Some(n) => Self::F(n),
+                        None => return Err(EventParseError::new(s, "a key")),
+                    },
+                }
+            }
+        })
+    }
+}
+
+impl FromStr for ShortcutEvent {
+    type Err = EventParseError;
+
+    /// Modifiers joined to the key with `+`, e.g. `Ctrl+Shift+P`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut shortcut = ShortcutEvent {
+            key: ShortcutKey::Esc,
+            ctrl: false,
+            alt: false,
+            shift: false,
+            super_key: false,
+        };
+        let mut rest = s;
+        loop {
+            let flag = if let Some(r) = rest.strip_prefix("Ctrl+") {
+                rest = r;
+                &mut shortcut.ctrl
+            } else if let Some(r) = rest.strip_prefix("Alt+") {
+                rest = r;
+                &mut shortcut.alt
+            } else if let Some(r) = rest.strip_prefix("Shift+") {
+                rest = r;
+                &mut shortcut.shift
+            } else if let Some(r) = rest.strip_prefix("Super+") {
+                rest = r;
+                &mut shortcut.super_key
+            } else {
+                break;
+            };
+            *flag = true;
+        }
+        shortcut.key = rest
+            .parse()
+            .map_err(|_| EventParseError::new(s, "a shortcut"))?;
+        Ok(shortcut)
+    }
+}
+
+impl FromStr for NavDirection {
+    type Err = EventParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(ShortcutKey::Left) => Self::Left,
+            Ok(ShortcutKey::Right) => Self::Right,
+            Ok(ShortcutKey::Up) => Self::Up,
+            Ok(ShortcutKey::Down) => Self::Down,
+            Ok(ShortcutKey::Home) => Self::Home,
+            Ok(ShortcutKey::End) => Self::End,
+            Ok(ShortcutKey::PageUp) => Self::PageUp,
+            Ok(ShortcutKey::PageDown) => Self::PageDown,
+            _ => return Err(EventParseError::new(s, "a navigation key")),
+        })
+    }
+}
+
+impl FromStr for NavigationEvent {
+    type Err = EventParseError;
+
+    /// E.g. `Ctrl+Shift+← ×3`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || EventParseError::new(s, "a navigation key");
+        let (keys, count) = split_count(s).ok_or_else(err)?;
+        let (with_ctrl, keys) = strip_flag(keys, "Ctrl+");
+        let (with_shift, keys) = strip_flag(keys, "Shift+");
+        Ok(Self {
+            direction: keys.parse().map_err(|_| err())?,
+            count,
+            with_shift,
+            with_ctrl,
+        })
+    }
+}
+
+impl FromStr for EditControlEvent {
+    type Err = EventParseError;
+
+    /// E.g. `Enter` or `Backspace ×3`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || EventParseError::new(s, "an editing key");
+        let (key, count) = split_count(s).ok_or_else(err)?;
+        Ok(match (key, count) {
+            ("Backspace", count) => Self::Backspace { count },
+            ("Delete", count) => Self::Delete { count },
+            ("Enter", 1) => Self::Enter,
+            ("Tab", 1) => Self::Tab,
+            ("Insert", 1) => Self::Insert,
+            _ => return Err(err()),
+        })
+    }
+}
+
+impl FromStr for SystemKeyEvent {
+    type Err = EventParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "CapsLock" => Self::CapsLock,
+            "ScrollLock" => Self::ScrollLock,
+            "NumLock" => Self::NumLock,
+            "PrintScreen" => Self::PrintScreen,
+            "Pause" => Self::Pause,
+            "Menu" => Self::Menu,
+            _ => return Err(EventParseError::new(s, "a system key")),
+        })
+    }
+}
+
+impl FromStr for SuspendReason {
+    type Err = EventParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "detached" => Ok(Self::Detached),
+            "locked" => Ok(Self::Locked),
+            _ => Err(EventParseError::new(s, "a reason for suspending")),
+        }
+    }
+}
+
+impl FromStr for KeystrokeEvent {
+    type Err = EventParseError;
+
+    /// An event as it displays, e.g. `typed "ls"` or `nav ↓ ×3`.  A REPL
+    /// input whose language is the first word of another event's form,
+    /// like `prompt`, reads as that event.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_event(s).ok_or_else(|| EventParseError::new(s, "an event"))
+    }
+}
+
+/// Parses what follows an event's prefix.
+type ParseRest = fn(&str) -> Option<KeystrokeEvent>;
+
+fn parse_event(s: &str) -> Option<KeystrokeEvent> {
+    use KeystrokeEvent as E;
+
+    match s {
+        "Esc" => return Some(E::Escape),
+        "▶ session resumed" => return Some(E::SessionResumed),
+        "🏷 untagged" => return Some(E::SessionTagged(Vec::new())),
+        "🔑 credential entered" => return Some(E::CredentialEntered),
+        _ => {}
+    }
+    let prefixed: [(&str, ParseRest); 24] = [
+        ("typed ", |rest| match unquote(rest) {
+            Some((text, "")) => Some(E::TextTyped(text)),
+            Some((preview, long)) => {
+                let long = long.strip_prefix(" (")?.strip_suffix(')')?;
+                let (chars, file) = long.split_once(" chars in ")?;
+                Some(E::LongText {
+                    preview,
+                    chars: chars.parse().ok()?,
+                    file: file.to_string(),
+                })
+            }
+            None => Some(E::ShortText(rest.strip_suffix(" chars")?.parse().ok()?)),
+        }),
+        ("shortcut ", |rest| rest.parse().ok().map(E::Shortcut)),
+        ("nav ", |rest| rest.parse().ok().map(E::Navigation)),
+        ("edit-ctrl ", |rest| rest.parse().ok().map(E::EditControl)),
+        ("sys ", |rest| rest.parse().ok().map(E::SystemKey)),
+        ("focus → ", |rest| Some(E::PaneFocused(parse_pane(rest)))),
+        ("📌 ", |rest| Some(E::Annotation(rest.to_string()))),
+        ("fuzzy-selected ", |rest| {
+            let (query, rest) = unquote(rest)?;
+            let moves = rest.strip_prefix(" after ")?.strip_suffix(" moves")?;
+            Some(E::FuzzySelected {
+                query,
+                moves: moves.parse().ok()?,
+            })
+        }),
+        ("prompt ", |rest| quoted(rest).map(E::PromptToAgent)),
+        ("agent: ", |rest| Some(E::AgentStatus(rest.to_string()))),
+        ("⏸ session ", |rest| {
+            rest.parse().ok().map(E::SessionSuspended)
+        }),
+        ("mode ", |rest| Some(E::ModeChanged(rest.to_string()))),
+        ("📸 snapshot ", |rest| {
+            Some(E::SnapshotTaken(rest.to_string()))
+        }),
+        ("chord ", |rest| Some(E::ChordSequence(rest.to_string()))),
+        ("⚠ input flood: ", |rest| {
+            let (keys, secs) = rest.strip_suffix('s')?.split_once(" keys over ")?;
+            let secs: f64 = secs.parse().ok()?;
+            Some(E::FloodDetected {
+                keys: keys.parse().ok()?,
+                duration_ms: (secs * 1000.0).round() as u64,
+            })
+        }),
+        ("title → ", |rest| {
+            Some(E::PaneTitleChanged(rest.to_string()))
+        }),
+        ("✕ tab ", |rest| Some(E::TabClosed(rest.to_string()))),
+        ("session → ", |rest| {
+            Some(E::SessionRenamed(rest.to_string()))
+        }),
+        ("$ ", |rest| {
+            let (command, rest) = unquote(rest)?;
+            let rest = rest.strip_prefix(" (")?.strip_suffix(')')?;
+            if let Some(shell) = rest.strip_suffix(" history") {
+                return Some(E::CommandExecuted {
+                    command,
+                    shell: shell.to_string(),
+                    outcome: None,
+                });
+            }
+            let (shell, outcome) = rest.split_once(": exit ")?;
+            let (exit_code, rest) = outcome.split_once(" after ")?;
+            let (duration_ms, cwd) = rest.split_once(" ms")?;
+            let cwd = match cwd.strip_prefix(" in ") {
+                Some(cwd) => Some(quoted(cwd)?),
+                None if cwd.is_empty() => None,
+                None => return None,
+            };
+            Some(E::CommandExecuted {
+                command,
+                shell: shell.to_string(),
+                outcome: Some(CommandOutcome {
+                    exit_code: exit_code.parse().ok()?,
+                    duration_ms: duration_ms.parse().ok()?,
+                    cwd,
+                }),
+            })
+        }),
+        ("🧰 context", |mut rest| {
+            let mut values = BTreeMap::new();
+            while let Some(pair) = rest.strip_prefix(' ') {
+                let (key, value) = pair.split_once('=')?;
+                let (value, after) = unquote(value)?;
+                values.insert(key.to_string(), value);
+                rest = after;
+            }
+            rest.is_empty().then_some(E::SessionContext(values))
+        }),
+        ("🏷 tagged ", |rest| {
+            let tags: Vec<String> = rest.split(' ').map(str::to_string).collect();
+            tags.iter()
+                .all(|tag| is_tag(tag))
+                .then_some(E::SessionTagged(tags))
+        }),
+        ("⇄ ", |rest| {
+            let (kind, target) = rest.split_once(" → ")?;
+            Some(E::EnvironmentChanged {
+                kind: EnvironmentKind::from_name(kind)?,
+                target: target.to_string(),
+            })
+        }),
+        ("⚠ risky ", |rest| {
+            let (command, rest) = unquote(rest)?;
+            let (pattern, rest) = unquote(rest.strip_prefix(" (matches ")?)?;
+            let reason = match rest.strip_prefix(')')? {
+                "" => None,
+                reason => Some(reason.strip_prefix(": ")?.to_string()),
+            };
+            Some(E::RiskyCommand {
+                command,
+                pattern,
+                reason,
+            })
+        }),
+        ("F", |rest| rest.parse().ok().map(E::FunctionKey)),
+    ];
+    for (prefix, parse) in prefixed {
+        if let Some(rest) = s.strip_prefix(prefix) {
+            if let Some(event) = parse(rest) {
+                return Some(event);
+            }
+        }
+    }
+
+    let (first, rest) = s.split_once(' ')?;
+    if let Ok(count) = first.parse() {
+        let kind = EventKind::ALL.into_iter().find(|k| k.plural() == rest)?;
+        return Some(E::Filtered { kind, count });
+    }
+    Some(E::ReplInput {
+        language: first.to_string(),
+        code: quoted(rest)?,
+    })
+}
+
+/// A pane as [`PaneFocusedEvent`]'s `Display` shows it:
+/// `[tab (command)] title (role) on host in container name`.
+fn parse_pane(s: &str) -> PaneFocusedEvent {
+    let mut pane = PaneFocusedEvent::default();
+    let mut rest = s;
+    if let Some((label, after)) = s.strip_prefix('[').and_then(|r| r.split_once("] ")) {
+        let (tab, command) = match label.strip_suffix(')').and_then(|l| l.rsplit_once('(')) {
+            Some((tab, command)) => (tab.strip_suffix(' ').unwrap_or(tab), Some(command)),
+            None => (label, None),
+        };
+        pane.tab_name = (!tab.is_empty()).then(|| tab.to_string());
+        pane.command = command.map(str::to_string);
+        rest = after;
+    }
+    if let Some((before, container)) = rest.rsplit_once(" in container ") {
+        pane.container = Some(container.to_string());
+        rest = before;
+    }
+    if let Some((before, host)) = rest.rsplit_once(" on ") {
+        pane.host = Some(host.to_string());
+        rest = before;
+    }
+    if let Some((title, role)) = rest
+        .strip_suffix(')')
+        .and_then(|r| r.rsplit_once(" ("))
+        .and_then(|(title, role)| Some((title, AppRole::from_name(role)?)))
+    {
+        pane.role = role;
+        rest = title;
+    }
+    pane.pane_title = rest.to_string();
+    pane
+}
+
+/// Split `keys ×count` into its parts; a count of 1 isn't shown.
+fn split_count(s: &str) -> Option<(&str, usize)> {
+    match s.rsplit_once(" ×") {
+        Some((keys, count)) => Some((keys, count.parse().ok().filter(|n| *n > 1)?)),
+        None => Some((s, 1)),
+    }
+}
+
+fn strip_flag<'a>(s: &'a str, prefix: &str) -> (bool, &'a str) {
+    match s.strip_prefix(prefix) {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    }
+}
+
+/// The string `s` is the `Debug` form of, if it's nothing else.
+fn quoted(s: &str) -> Option<String> {
+    match unquote(s)? {
+        (text, "") => Some(text),
+        _ => None,
+    }
+}
+
+/// The string whose `Debug` form starts `s`, and the text after it.
+fn unquote(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut text = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((text, &s[i + 2..])),
+            '\\' => text.push(match chars.next()?.1 {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                'u' => {
+                    let (_, '{') = chars.next()? else {
+                        return None;
+                    };
+                    let mut hex = String::new();
+                    for (_, c) in chars.by_ref() {
+                        if c == '}' {
+                            break;
+                        }
+                        hex.push(c);
+                    }
+                    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                }
+                c @ ('\\' | '"' | '\'') => c,
+                _ => return None,
+            }),
+            c => text.push(c),
+        }
+    }
+    None
+}
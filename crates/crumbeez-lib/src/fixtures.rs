@@ -0,0 +1,275 @@
+//! Canned event streams, for exercising the pipeline deterministically.
+//!
+//! Each [`Fixture`] is a realistic stretch of work as the plugin would log
+//! it: keys already coalesced and reinterpreted for the focused pane's
+//! role, with the timing of someone typing at a normal pace.  Tests and
+//! benches feed them through summaries, work blocks, or exports; the
+//! plugin's demo mode replays them.  [`StreamBuilder`] composes new ones.
+//!
+//! Only built for tests, or with the `fixtures` feature.
+
+use crate::{
+    AppRole, EditControlEvent, EventLog, KeystrokeEvent, LogEntry, NavDirection, NavigationEvent,
+    PaneFocusedEvent, ProfileRegistry, Reinterpreter, Summary,
+};
+
+/// 2024-03-01 09:15:00 UTC, a default start for fixtures.
+pub const FIXTURE_START_MS: u64 = 1_709_284_500_000;
+
+/// Time between the keys of typed text, about 80 words a minute.
+const MS_PER_CHAR: u64 = 150;
+
+/// A canned stretch of work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixture {
+    /// Editing a Rust file in Neovim: moving around, inserting a function,
+    /// saving.
+    VimEdit,
+    /// Building with cargo, fixing, and rebuilding from shell history until
+    /// the tests pass.
+    CargoBuildLoop,
+    /// An interactive git rebase, squashing a fixup and resolving a
+    /// conflict.
+    GitRebase,
+}
+
+impl Fixture {
+    pub const ALL: [Self; 3] = [Self::VimEdit, Self::CargoBuildLoop, Self::GitRebase];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::VimEdit => "vim-edit",
+            Self::CargoBuildLoop => "cargo-build-loop",
+            Self::GitRebase => "git-rebase",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|fixture| fixture.name() == name)
+    }
+
+    /// The stream, starting at `start_ms`.
+    pub fn entries(self, start_ms: u64) -> Vec<LogEntry> {
+        let stream = StreamBuilder::new(start_ms);
+        match self {
+            Self::VimEdit => stream
+                .focus(1, "code", "nvim src/parser.rs", "nvim src/parser.rs")
+                .nav(NavDirection::Down, 42)
+                .typed("o")
+                .typed("fn parse_header(input: &str) -> Option<Header> {")
+                .enter()
+                .typed("    input.split_once(':').map(Header::from)")
+                .enter()
+                .typed("}")
+                .escape()
+                .typed(":w")
+                .enter()
+                .after_secs(20)
+                .nav(NavDirection::Up, 6)
+                .typed("cw")
+                .typed("parse_line")
+                .escape()
+                .typed(":w")
+                .enter()
+                .finish(),
+            Self::CargoBuildLoop => stream
+                .focus(2, "build", "zsh", "/bin/zsh")
+                .typed("cargo build")
+                .enter()
+                .after_secs(35)
+                .focus(1, "code", "nvim src/parser.rs", "nvim src/parser.rs")
+                .typed("ciw")
+                .typed("Header")
+                .escape()
+                .typed(":w")
+                .enter()
+                .focus(2, "build", "zsh", "/bin/zsh")
+                .nav(NavDirection::Up, 1)
+                .enter()
+                .after_secs(30)
+                .typed("cargo test parser")
+                .enter()
+                .after_secs(50)
+                .ctrl('l')
+                .nav(NavDirection::Up, 1)
+                .enter()
+                .after_secs(45)
+                .finish(),
+            Self::GitRebase => stream
+                .focus(2, "build", "zsh", "/bin/zsh")
+                .typed("git fetch origin")
+                .enter()
+                .after_secs(3)
+                .typed("git rebase -i origin/main")
+                .enter()
+                .title("nvim .git/rebase-merge/git-rebase-todo")
+                .nav(NavDirection::Down, 2)
+                .typed("cw")
+                .typed("fixup")
+                .escape()
+                .typed(":wq")
+                .enter()
+                .title("zsh")
+                .after_secs(2)
+                .typed("git status")
+                .enter()
+                .typed("nvim src/lib.rs")
+                .enter()
+                .title("nvim src/lib.rs")
+                .typed("/<<<<<<<")
+                .enter()
+                .typed("dd")
+                .nav(NavDirection::Down, 4)
+                .typed("3dd")
+                .typed(":wq")
+                .enter()
+                .title("zsh")
+                .typed("git add src/lib.rs")
+                .enter()
+                .typed("git rebase --continue")
+                .enter()
+                .after_secs(4)
+                .typed("git log --oneline -5")
+                .enter()
+                .finish(),
+        }
+    }
+}
+
+/// Every fixture in turn, each starting a few minutes after the last one
+/// ended, like a stretch of a working day.
+pub fn workday(start_ms: u64) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for fixture in Fixture::ALL {
+        let start = entries
+            .last()
+            .map_or(start_ms, |last| last.timestamp_ms + 4 * 60 * 1000);
+        entries.extend(fixture.entries(start));
+    }
+    entries
+}
+
+/// An [`EventLog`] holding `entries`, none of them consumed.
+pub fn event_log(entries: impl IntoIterator<Item = LogEntry>) -> EventLog {
+    let mut log = EventLog::new();
+    for entry in entries {
+        log.append_entry(entry);
+    }
+    log
+}
+
+/// Builds a timestamped stream the way the plugin logs one: keys are
+/// reinterpreted for the focused pane's role, and the clock advances with
+/// each entry.
+#[derive(Debug)]
+pub struct StreamBuilder {
+    entries: Vec<LogEntry>,
+    start_ms: u64,
+    now_ms: u64,
+    reinterpreter: Reinterpreter,
+}
+
+impl StreamBuilder {
+    pub fn new(start_ms: u64) -> Self {
+        Self {
+            entries: Vec::new(),
+            start_ms,
+            now_ms: start_ms,
+            reinterpreter: Reinterpreter::default(),
+        }
+    }
+
+    /// Let `secs` seconds pass.
+    pub fn after_secs(mut self, secs: u64) -> Self {
+        self.now_ms += secs * 1000;
+        self
+    }
+
+    /// Set the clock to `ms` after the start, for a next entry that has to
+    /// land at an exact time.
+    pub fn at_ms(mut self, ms: u64) -> Self {
+        self.now_ms = self.start_ms + ms;
+        self
+    }
+
+    /// Log `event` now, a second after the last one, or as long as it
+    /// takes to type if it's text.
+    pub fn event(mut self, event: KeystrokeEvent) -> Self {
+        let event = match event {
+            KeystrokeEvent::PaneFocused(pane) => {
+                self.reinterpreter = Reinterpreter::new(pane.role);
+                KeystrokeEvent::PaneFocused(pane)
+            }
+            event => self.reinterpreter.apply(event),
+        };
+        let elapsed = match event {
+            KeystrokeEvent::TextTyped(ref text) => text.chars().count() as u64 * MS_PER_CHAR,
+            _ => 1000,
+        };
+        self.entries.push(LogEntry {
+            event,
+            timestamp_ms: self.now_ms,
+            corrections: None,
+        });
+        self.now_ms += elapsed;
+        self
+    }
+
+    /// Focus the pane with ID `id` in `tab`, with its role looked up in the
+    /// default profiles.
+    pub fn focus(self, id: u32, tab: &str, title: &str, command: &str) -> Self {
+        let role = ProfileRegistry::default().role_for(title, Some(command));
+        self.event(
+            PaneFocusedEvent::new(title)
+                .in_tab(tab)
+                .running(command)
+                .with_role(role)
+                .with_id(id)
+                .into(),
+        )
+    }
+
+    pub fn typed(self, text: &str) -> Self {
+        self.event(KeystrokeEvent::typed(text))
+    }
+
+    pub fn enter(self) -> Self {
+        self.event(KeystrokeEvent::EditControl(EditControlEvent::Enter))
+    }
+
+    pub fn escape(self) -> Self {
+        self.event(KeystrokeEvent::Escape)
+    }
+
+    pub fn ctrl(self, c: char) -> Self {
+        self.event(KeystrokeEvent::shortcut(&format!("Ctrl+{c}")))
+    }
+
+    pub fn nav(self, direction: NavDirection, count: usize) -> Self {
+        self.event(NavigationEvent::new(direction, count).into())
+    }
+
+    /// The focused pane's program settled on a new title.
+    pub fn title(self, title: &str) -> Self {
+        self.event(KeystrokeEvent::PaneTitleChanged(title.to_string()))
+    }
+
+    /// The role keys are currently reinterpreted for.
+    pub fn role(&self) -> AppRole {
+        self.reinterpreter.role()
+    }
+
+    /// When the next entry will be logged.
+    pub fn now_ms(&self) -> u64 {
+        self.now_ms
+    }
+
+    /// A summary of the stream so far.
+    pub fn summary(self) -> Summary {
+        Summary::from_events(self.entries.into_iter())
+    }
+
+    pub fn finish(self) -> Vec<LogEntry> {
+        self.entries
+    }
+}
@@ -0,0 +1,105 @@
+//! Keeping a flood of input, like a stuck key repeating or a program
+//! pasting megabytes through the terminal, from swamping the log.
+//!
+//! [`FloodGuard`] counts keys per second.  Once a second brings more than
+//! the threshold, it stops letting keys through and only counts them, until
+//! a second passes at or under the threshold.  The flood is then logged as
+//! one [`KeystrokeEvent::FloodDetected`] in place of everything it held
+//! back.
+
+use crate::KeystrokeEvent;
+
+/// Default keys per second above which input counts as a flood.  Far past
+/// any typist, but well under what a key held down in a fast terminal
+/// repeats at over a few seconds.
+pub const DEFAULT_FLOOD_THRESHOLD: u32 = 100;
+
+const WINDOW_MS: u64 = 1000;
+
+#[derive(Debug)]
+pub struct FloodGuard {
+    threshold: u32,
+    /// The current second, and the keys seen in it.
+    window_start_ms: u64,
+    in_window: u32,
+    /// The last second with more keys than the threshold.
+    last_busy_ms: u64,
+    /// When the flood started and how many keys it held back so far.
+    flood: Option<(u64, usize)>,
+}
+
+impl Default for FloodGuard {
+    fn default() -> Self {
+        Self::new(DEFAULT_FLOOD_THRESHOLD)
+    }
+}
+
+impl FloodGuard {
+    /// A guard letting through at most `threshold` keys per second.
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            threshold,
+            window_start_ms: 0,
+            in_window: 0,
+            last_busy_ms: 0,
+            flood: None,
+        }
+    }
+
+    /// The most keys let through per second.
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// Whether the key arriving at `now_ms` should be logged: `false`
+    /// during a flood, when it's only counted.  Call [`finish`] first, so a
+    /// flood that has subsided is logged before the key after it.
+    ///
+    /// [`finish`]: Self::finish
+    pub fn admit(&mut self, now_ms: u64) -> bool {
+        let window = now_ms - now_ms % WINDOW_MS;
+        if window != self.window_start_ms {
+            self.window_start_ms = window;
+            self.in_window = 0;
+        }
+        self.in_window += 1;
+        if self.in_window > self.threshold {
+            self.last_busy_ms = window;
+            self.flood.get_or_insert((now_ms, 0));
+        }
+        match &mut self.flood {
+            Some((_, held)) => {
+                *held += 1;
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// The [`KeystrokeEvent::FloodDetected`] marker for a flood that has
+    /// subsided by `now_ms`: a whole second at or under the threshold has
+    /// passed since the last one over it.
+    pub fn finish(&mut self, now_ms: u64) -> Option<KeystrokeEvent> {
+        let (started_ms, held) = self.flood?;
+        let ended_ms = self.last_busy_ms + WINDOW_MS;
+        if now_ms < ended_ms + WINDOW_MS {
+            return None;
+        }
+        self.flood = None;
+        Some(KeystrokeEvent::FloodDetected {
+            keys: held,
+            duration_ms: ended_ms - started_ms,
+        })
+    }
+
+    /// Whether keys are being held back.
+    pub fn is_flooding(&self) -> bool {
+        self.flood.is_some()
+    }
+
+    /// When the current flood will have subsided unless more keys arrive,
+    /// so [`finish`](Self::finish) returns its marker.
+    pub fn subsides_at(&self) -> Option<u64> {
+        self.flood.map(|_| self.last_busy_ms + 2 * WINDOW_MS)
+    }
+}
@@ -0,0 +1,194 @@
+//! Ranked search over summaries.
+//!
+//! [`FullTextIndex`] is an inverted index: each word maps to the documents
+//! it's in and how often.  A query is split into words the same way, and
+//! documents are scored with BM25, so one that mentions `oauth` a lot in a
+//! short summary beats one that mentions it once among pages of other
+//! work, and a rare word counts for more than one in every summary.
+//!
+//! Words are runs of letters, digits, and `_`, compared ignoring case.  A
+//! query word also matches longer words it starts, counting for the share
+//! of the word it covers, so `auth` finds `authentication` as well as
+//! `auth`, with `auth` first.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// BM25's term frequency saturation: more mentions count for less and less.
+const K1: f64 = 1.2;
+/// BM25's length normalization: how much long documents are discounted.
+const B: f64 = 0.75;
+
+#[derive(Debug, Clone, Default)]
+pub struct FullTextIndex {
+    /// Each word's documents, by index, with how often it's in each.
+    postings: BTreeMap<String, Vec<(usize, u32)>>,
+    /// Each document's length in words.
+    lengths: Vec<u32>,
+}
+
+impl FullTextIndex {
+    /// Index `documents`, numbering them from 0 in order.
+    pub fn new<'a>(documents: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut index = Self::default();
+        for document in documents {
+            index.add(document);
+        }
+        index
+    }
+
+    /// Index `document` as the next number.
+    pub fn add(&mut self, document: &str) {
+        let id = self.lengths.len();
+        let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+        let mut length = 0;
+        for word in words(document) {
+            *counts.entry(word).or_insert(0) += 1;
+            length += 1;
+        }
+        for (word, count) in counts {
+            self.postings.entry(word).or_default().push((id, count));
+        }
+        self.lengths.push(length);
+    }
+
+    /// How many documents are indexed.
+    pub fn len(&self) -> usize {
+        self.lengths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lengths.is_empty()
+    }
+
+    /// The documents matching any word of `query`, with their scores, best
+    /// first.  Equal scores put the later document first, as summaries are
+    /// indexed oldest first.
+    pub fn search(&self, query: &str) -> Vec<(usize, f64)> {
+        let documents = self.len() as f64;
+        let average_length =
+            self.lengths.iter().map(|&l| f64::from(l)).sum::<f64>() / documents.max(1.0);
+        let mut scores: BTreeMap<usize, f64> = BTreeMap::new();
+        for query_word in words(query) {
+            let matching = self
+                .postings
+                .range(query_word.clone()..)
+                .take_while(|(word, _)| word.starts_with(&query_word));
+            for (word, postings) in matching {
+                let coverage = query_word.chars().count() as f64 / word.chars().count() as f64;
+                let found = postings.len() as f64;
+                let idf = (1.0 + (documents - found + 0.5) / (found + 0.5)).ln();
+                for &(id, count) in postings {
+                    let count = f64::from(count);
+                    let length = f64::from(self.lengths[id]) / average_length.max(1.0);
+                    let saturated = count * (K1 + 1.0) / (count + K1 * (1.0 - B + B * length));
+                    *scores.entry(id).or_insert(0.0) += coverage * idf * saturated;
+                }
+            }
+        }
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|(a_id, a), (b_id, b)| {
+            b.partial_cmp(a)
+                .unwrap_or(Ordering::Equal)
+                .then(b_id.cmp(a_id))
+        });
+        ranked
+    }
+}
+
+/// The words of `text`, lowercased.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{StreamBuilder, FIXTURE_START_MS};
+    use crate::{SummaryId, SummaryStore};
+
+    /// A daily file of summaries of typing each of `texts`, a minute apart.
+    fn store(texts: &[&str]) -> SummaryStore {
+        let file: String = texts
+            .iter()
+            .enumerate()
+            .map(|(n, text)| {
+                let at = FIXTURE_START_MS + n as u64 * 60_000;
+                let mut summary = StreamBuilder::new(at).typed(text).summary();
+                summary.id = Some(SummaryId::new(at, n as u128));
+                format!("{}\n", summary.to_markdown())
+            })
+            .collect();
+        SummaryStore::parse(&file)
+    }
+
+    #[test]
+    fn more_mentions_rank_higher() {
+        let index = FullTextIndex::new([
+            "fixed the oauth bug",
+            "lunch",
+            "oauth oauth oauth token refresh",
+        ]);
+        let ranked: Vec<usize> = index.search("oauth").into_iter().map(|(i, _)| i).collect();
+        assert_eq!(ranked, [2, 0]);
+    }
+
+    #[test]
+    fn matching_every_word_beats_matching_one() {
+        let index = FullTextIndex::new(["oauth bug", "oauth token", "parser bug"]);
+        let ranked = index.search("OAuth bug");
+        assert_eq!(ranked[0].0, 0);
+        assert_eq!(ranked.len(), 3);
+    }
+
+    #[test]
+    fn rare_words_count_for_more() {
+        let index = FullTextIndex::new([
+            "cargo test flood",
+            "cargo test parser",
+            "cargo build",
+            "cargo fmt",
+        ]);
+        let ranked: Vec<usize> = index
+            .search("cargo parser")
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(ranked[0], 1);
+    }
+
+    #[test]
+    fn words_match_longer_words_they_start() {
+        let index = FullTextIndex::new(["authentication flow", "auth header", "author list"]);
+        let ranked: Vec<usize> = index.search("auth").into_iter().map(|(i, _)| i).collect();
+        assert_eq!(ranked[0], 1);
+        assert_eq!(ranked.len(), 3);
+        assert!(index.search("authz").is_empty());
+    }
+
+    #[test]
+    fn ties_put_newer_first() {
+        let index = FullTextIndex::new(["deploy", "deploy"]);
+        let ranked: Vec<usize> = index.search("deploy").into_iter().map(|(i, _)| i).collect();
+        assert_eq!(ranked, [1, 0]);
+    }
+
+    #[test]
+    fn searching_a_store() {
+        let store = store(&[
+            "oauth callback",
+            "lunch break",
+            "oauth bug in oauth callback",
+        ]);
+        let found: Vec<_> = store
+            .search("oauth bug")
+            .into_iter()
+            .map(|s| s.id.unwrap().timestamp_ms())
+            .collect();
+        assert_eq!(found, [FIXTURE_START_MS + 2 * 60_000, FIXTURE_START_MS]);
+        assert!(store.search("kubernetes").is_empty());
+        assert!(store.search("").is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+/// Match `text` against a shell-style glob `pattern`.
+///
+/// - `?` matches any single character except `/`.
+/// - `*` matches any run of characters except `/`.
+/// - `**` matches any run of characters, including `/`.
+///
+/// Everything else matches literally.  There are no character classes or
+/// escapes; config patterns haven't needed them.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    matches_from(&pattern, &text)
+}
+
+/// Check that `pattern` means what it looks like it means: non-empty, and
+/// without the character classes or brace alternatives other glob dialects
+/// support, which [`glob_match`] would match literally.
+pub fn check_glob(pattern: &str) -> Result<(), String> {
+    if pattern.trim().is_empty() {
+        return Err("empty pattern".to_string());
+    }
+    if pattern.contains("***") {
+        return Err("use '*' or '**', not three or more '*' in a row".to_string());
+    }
+    if let Some(c) = pattern.chars().find(|c| matches!(c, '[' | ']' | '{' | '}')) {
+        return Err(format!(
+            "'{}' would match literally; character classes and {{a,b}} alternatives aren't supported",
+            c
+        ));
+    }
+    Ok(())
+}
+
+fn matches_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => {
+            let crosses_slash = pattern.get(1) == Some(&'*');
+            let rest = if crosses_slash {
+                &pattern[2..]
+            } else {
+                &pattern[1..]
+            };
+            // Try every possible split point, shortest first.
+            for i in 0..=text.len() {
+                if matches_from(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == '/' && !crosses_slash {
+                    return false;
+                }
+            }
+            false
+        }
+        Some('?') => match text.first() {
+            Some(&c) if c != '/' => matches_from(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        Some(&p) => match text.first() {
+            Some(&c) if c == p => matches_from(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal_patterns_match_exactly() {
+        assert!(glob_match("src/main.rs", "src/main.rs"));
+        assert!(!glob_match("src/main.rs", "src/main.rsx"));
+        assert!(!glob_match("src/main.rs", "src/main"));
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "a"));
+    }
+
+    #[test]
+    fn single_star_stays_within_a_component() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(glob_match("*.rs", ".rs"));
+        assert!(!glob_match("*.rs", "src/main.rs"));
+        assert!(glob_match("src/*/mod.rs", "src/kdl/mod.rs"));
+        assert!(!glob_match("src/*/mod.rs", "src/a/b/mod.rs"));
+        assert!(glob_match("*", "anything"));
+        assert!(!glob_match("*", "a/b"));
+    }
+
+    #[test]
+    fn double_star_crosses_slashes() {
+        assert!(glob_match("**", "a/b/c"));
+        assert!(glob_match("**", ""));
+        assert!(glob_match("**/*.rs", "src/bin/main.rs"));
+        assert!(glob_match("**/*.rs", "/main.rs"));
+        // `**/` needs the slash; it doesn't match zero directories.
+        assert!(!glob_match("**/*.rs", "main.rs"));
+        assert!(glob_match("target/**", "target/debug/build"));
+        assert!(glob_match("a/**/z", "a/b/c/z"));
+        assert!(glob_match("a/**/z", "a//z"));
+        assert!(!glob_match("a/**/z", "a/z"));
+        assert!(glob_match("**.log", "logs/today.log"));
+    }
+
+    #[test]
+    fn question_mark_is_one_non_slash_character() {
+        assert!(glob_match("?.rs", "a.rs"));
+        assert!(glob_match("?.rs", "é.rs"));
+        assert!(!glob_match("?.rs", "ab.rs"));
+        assert!(!glob_match("?.rs", ".rs"));
+        assert!(!glob_match("a?b", "a/b"));
+    }
+
+    #[test]
+    fn a_trailing_slash_must_match_a_trailing_slash() {
+        assert!(glob_match("target/", "target/"));
+        assert!(!glob_match("target/", "target"));
+        assert!(!glob_match("target/", "target/debug"));
+        assert!(glob_match("*/", "build/"));
+        assert!(glob_match("**/", "a/b/"));
+    }
+
+    #[test]
+    fn character_classes_match_literally_and_are_rejected() {
+        assert!(glob_match("[ab].rs", "[ab].rs"));
+        assert!(!glob_match("[ab].rs", "a.rs"));
+        assert!(!glob_match("{a,b}.rs", "a.rs"));
+        for pattern in ["[ab].rs", "file[0-9]", "*.{rs,toml}", "a]"] {
+            assert!(check_glob(pattern).is_err(), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn check_glob_accepts_what_glob_match_understands() {
+        for pattern in ["*.rs", "**/target/**", "src/?.rs", "target/", "a b"] {
+            assert_eq!(check_glob(pattern), Ok(()), "{pattern}");
+        }
+        assert!(check_glob("").is_err());
+        assert!(check_glob("   ").is_err());
+        assert!(check_glob("***").is_err());
+        assert!(check_glob("a/***/b").is_err());
+    }
+}
@@ -0,0 +1,54 @@
+//! A small status file for external monitoring.
+//!
+//! After every event log write the plugin replaces
+//! `.crumbeez/heartbeat.json`, so a script can tell whether it's still
+//! saving without decoding the log itself:
+//!
+//! ```json
+//! {"version":1,"written_ms":1709284545000,"last_flush_ms":1709284545000,"state":"recording","errors":0,"events":245}
+//! ```
+//!
+//! - `last_flush_ms` — when the event log last reached the disk, or `null`
+//!   if no write has succeeded yet.  A heartbeat much newer than this means
+//!   writes are failing.
+//! - `state` — `recording`, or why logging is suspended: `detached` or
+//!   `locked`.  While suspended nothing is saved, so the file going stale
+//!   is expected.
+//! - `errors` — event log writes that failed since the plugin started.
+//! - `events` — the sequence number the next event will get.
+
+use crate::broadcast::json_string;
+use crate::SuspendReason;
+
+/// Version of the heartbeat's fields.  Bumped when they change
+/// incompatibly.
+pub const HEARTBEAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heartbeat {
+    pub written_ms: u64,
+    pub last_flush_ms: Option<u64>,
+    /// Why logging is suspended, or `None` while recording.
+    pub suspended: Option<SuspendReason>,
+    pub errors: u32,
+    pub next_seq: u64,
+}
+
+impl Heartbeat {
+    /// The file's contents: one JSON object and a newline.
+    pub fn to_json(&self) -> String {
+        let state = self
+            .suspended
+            .map_or_else(|| "recording".to_string(), |reason| reason.to_string());
+        format!(
+            "{{\"version\":{},\"written_ms\":{},\"last_flush_ms\":{},\"state\":{},\"errors\":{},\"events\":{}}}\n",
+            HEARTBEAT_VERSION,
+            self.written_ms,
+            self.last_flush_ms
+                .map_or_else(|| "null".to_string(), |ms| ms.to_string()),
+            json_string(&state),
+            self.errors,
+            self.next_seq
+        )
+    }
+}
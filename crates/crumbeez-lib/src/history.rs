@@ -0,0 +1,309 @@
+//! Backfilling the log from shell history.
+//!
+//! A project's `.crumbeez` directory starts out empty, though its shells
+//! remember weeks of commands.  Those histories can be read back as
+//! [`CommandExecuted`](KeystrokeEvent::CommandExecuted) entries stamped with
+//! when each command ran, and put in front of a fresh log with
+//! [`EventLog::backfill`](crate::EventLog::backfill).  Only commands the
+//! history timestamps can be placed, so each format needs its timestamps:
+//!
+//! - bash: `HISTTIMEFORMAT` set, which writes a `#<seconds>` line before
+//!   each command.
+//! - zsh: `setopt extended_history`, for `: <seconds>:<duration>;<command>`
+//!   lines.
+//! - fish: always, as `when:` under each `- cmd:`.
+//! - atuin: always, read from its database with [`ATUIN_QUERY`].
+//!
+//! ```
+//! use crumbeez_lib::{history_entries, HistoryFormat, KeystrokeEvent};
+//!
+//! let zsh = b": 1709284500:0;cargo test\n: 1709284560:3;git push\n";
+//! let entries = history_entries(HistoryFormat::Zsh, zsh, 0, u64::MAX, 100);
+//! assert_eq!(entries[1].timestamp_ms, 1_709_284_560_000);
+//! assert_eq!(entries[1].event.to_string(), "$ \"git push\" (zsh history)");
+//! ```
+//!
+//! atuin also records how each command went.  Once a command typed in a
+//! shell pane has finished, [`atuin_lookup_query`] finds it again, and
+//! [`parse_atuin_lookup`] reads its [`CommandOutcome`].
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{KeystrokeEvent, LogEntry};
+
+/// How far back an import reaches by default.
+pub const HISTORY_IMPORT_DAYS: u64 = 28;
+
+/// Query for `sqlite3` on atuin's `history.db`: one `<nanoseconds>|<command>`
+/// line per command, with backslashes and newlines in the command escaped
+/// as `\\` and `\n`.
+pub const ATUIN_QUERY: &str = "SELECT timestamp, \
+     replace(replace(command, '\\', '\\\\'), char(10), '\\n') \
+     FROM history WHERE deleted_at IS NULL ORDER BY timestamp";
+
+/// Separates the columns of [`atuin_lookup_query`]'s output, since a
+/// directory or command can hold anything printable.
+const UNIT_SEPARATOR: char = '\u{1f}';
+
+/// How a command went, as atuin recorded it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandOutcome {
+    pub exit_code: i32,
+    pub duration_ms: u64,
+    /// Where it ran, if atuin knew.
+    pub cwd: Option<String>,
+}
+
+impl fmt::Display for CommandOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exit {} after {} ms", self.exit_code, self.duration_ms)?;
+        if let Some(ref cwd) = self.cwd {
+            write!(f, " in {:?}", cwd)?;
+        }
+        Ok(())
+    }
+}
+
+/// Query for `sqlite3` on atuin's `history.db`: the first command to have
+/// finished of those started from `since_ms` on, preferring one that reads
+/// `command`.  What was typed in a pane can differ from what ran, e.g.
+/// after tab completion, so another command started since stands in.  See
+/// [`parse_atuin_lookup`] for its output.
+pub fn atuin_lookup_query(command: &str, since_ms: u64) -> String {
+    format!(
+        "SELECT exit || char(31) || duration || char(31) || ifnull(cwd, '') || char(31) || \
+         replace(replace(command, '\\', '\\\\'), char(10), '\\n') \
+         FROM history WHERE deleted_at IS NULL AND timestamp >= {} AND duration >= 0 \
+         ORDER BY command = '{}' DESC, timestamp LIMIT 1",
+        since_ms.saturating_mul(1_000_000),
+        command.trim().replace('\'', "''")
+    )
+}
+
+/// The command [`atuin_lookup_query`] found and how it went, or `None` if
+/// it found nothing, as while the command is still running.
+pub fn parse_atuin_lookup(output: &str) -> Option<(String, CommandOutcome)> {
+    let line = output.lines().next()?;
+    let mut columns = line.splitn(4, UNIT_SEPARATOR);
+    let exit_code = columns.next()?.trim().parse().ok()?;
+    let duration_ns: u64 = columns.next()?.trim().parse().ok()?;
+    let cwd = columns.next()?;
+    let command = unescape(columns.next()?);
+    Some((
+        command,
+        CommandOutcome {
+            exit_code,
+            duration_ms: duration_ns / 1_000_000,
+            cwd: (!cwd.is_empty()).then(|| cwd.to_string()),
+        },
+    ))
+}
+
+/// The shell histories crumbeez can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    Bash,
+    Zsh,
+    Fish,
+    /// The output of [`ATUIN_QUERY`], not the database itself.
+    Atuin,
+}
+
+impl HistoryFormat {
+    pub const ALL: [Self; 4] = [Self::Bash, Self::Zsh, Self::Fish, Self::Atuin];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::Atuin => "atuin",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+}
+
+/// One command from a history, as it was run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryCommand {
+    pub timestamp_ms: u64,
+    pub command: String,
+}
+
+/// Every timestamped command in a history file's contents, in file order.
+/// Commands without a timestamp are left out.
+pub fn parse_history(format: HistoryFormat, contents: &[u8]) -> Vec<HistoryCommand> {
+    match format {
+        HistoryFormat::Bash => parse_bash(&String::from_utf8_lossy(contents)),
+        HistoryFormat::Zsh => parse_zsh(&String::from_utf8_lossy(&unmetafy(contents))),
+        HistoryFormat::Fish => parse_fish(&String::from_utf8_lossy(contents)),
+        HistoryFormat::Atuin => parse_atuin(&String::from_utf8_lossy(contents)),
+    }
+}
+
+/// Log entries for the commands in a history run from `since_ms` up to,
+/// not including, `before_ms`, oldest first.  If there are more than
+/// `max`, only the newest `max` are kept.
+pub fn history_entries(
+    format: HistoryFormat,
+    contents: &[u8],
+    since_ms: u64,
+    before_ms: u64,
+    max: usize,
+) -> Vec<LogEntry> {
+    let mut commands: Vec<HistoryCommand> = parse_history(format, contents)
+        .into_iter()
+        .filter(|c| (since_ms..before_ms).contains(&c.timestamp_ms))
+        .filter(|c| !c.command.trim().is_empty())
+        .collect();
+    // Histories written by several shells at once can interleave.
+    commands.sort_by_key(|c| c.timestamp_ms);
+    let skip = commands.len().saturating_sub(max);
+    commands
+        .into_iter()
+        .skip(skip)
+        .map(|c| LogEntry {
+            event: KeystrokeEvent::CommandExecuted {
+                command: c.command,
+                shell: format.name().to_string(),
+                outcome: None,
+            },
+            timestamp_ms: c.timestamp_ms,
+            corrections: None,
+        })
+        .collect()
+}
+
+/// Seconds since the epoch as milliseconds.
+fn secs_ms(secs: &str) -> Option<u64> {
+    secs.trim().parse::<u64>().ok()?.checked_mul(1000)
+}
+
+/// `#<seconds>` lines, each followed by a command that may span lines.
+fn parse_bash(text: &str) -> Vec<HistoryCommand> {
+    let mut commands: Vec<HistoryCommand> = Vec::new();
+    let mut open = false;
+    for line in text.lines() {
+        if let Some(timestamp_ms) = line.strip_prefix('#').and_then(secs_ms) {
+            commands.push(HistoryCommand {
+                timestamp_ms,
+                command: String::new(),
+            });
+            open = true;
+        } else if let (true, Some(last)) = (open, commands.last_mut()) {
+            if !last.command.is_empty() {
+                last.command.push('\n');
+            }
+            last.command.push_str(line);
+        }
+    }
+    commands
+}
+
+/// zsh writes bytes that clash with its own tokens as `0x83` followed by
+/// the byte xor `0x20`.
+fn unmetafy(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut bytes = bytes.iter();
+    while let Some(&b) = bytes.next() {
+        match b {
+            0x83 => out.extend(bytes.next().map(|next| next ^ 0x20)),
+            b => out.push(b),
+        }
+    }
+    out
+}
+
+/// `: <seconds>:<duration>;<command>` lines, with a trailing backslash
+/// continuing the command on the next line.
+fn parse_zsh(text: &str) -> Vec<HistoryCommand> {
+    let mut commands: Vec<HistoryCommand> = Vec::new();
+    let mut continued = false;
+    for line in text.lines() {
+        let (line, continues) = match line.strip_suffix('\\') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        if let (true, Some(last)) = (continued, commands.last_mut()) {
+            last.command.push('\n');
+            last.command.push_str(line);
+        } else if let Some((header, command)) = line
+            .strip_prefix(": ")
+            .and_then(|rest| rest.split_once(';'))
+        {
+            let Some(timestamp_ms) = header.split(':').next().and_then(secs_ms) else {
+                continued = false;
+                continue;
+            };
+            commands.push(HistoryCommand {
+                timestamp_ms,
+                command: command.to_string(),
+            });
+        } else {
+            // A line from before extended_history, with no timestamp.
+            continued = false;
+            continue;
+        }
+        continued = continues;
+    }
+    commands
+}
+
+/// `- cmd: <command>` entries with a `when: <seconds>` line under them.
+fn parse_fish(text: &str) -> Vec<HistoryCommand> {
+    let mut commands = Vec::new();
+    let mut command: Option<String> = None;
+    for line in text.lines() {
+        if let Some(cmd) = line.strip_prefix("- cmd: ") {
+            command = Some(unescape(cmd));
+        } else if let Some(timestamp_ms) =
+            line.trim_start().strip_prefix("when: ").and_then(secs_ms)
+        {
+            if let Some(command) = command.take() {
+                commands.push(HistoryCommand {
+                    timestamp_ms,
+                    command,
+                });
+            }
+        }
+    }
+    commands
+}
+
+/// `<nanoseconds>|<command>` lines from [`ATUIN_QUERY`].
+fn parse_atuin(text: &str) -> Vec<HistoryCommand> {
+    text.lines()
+        .filter_map(|line| {
+            let (ns, command) = line.split_once('|')?;
+            Some(HistoryCommand {
+                timestamp_ms: ns.trim().parse::<u64>().ok()? / 1_000_000,
+                command: unescape(command),
+            })
+        })
+        .collect()
+}
+
+/// Undo `\\` and `\n` escapes, as fish and [`ATUIN_QUERY`] write them.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                out.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                out.push('\\');
+                chars.next();
+            }
+            (c, _) => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,92 @@
+//! Durations, times, and sizes the way people read them.
+//!
+//! Everything crumbeez shows a person — the plugin pane, the doctor report,
+//! standup reports, work blocks, rollups — formats these through the
+//! wrappers here, so a wait reads the same everywhere: `7 s`, `45 min`,
+//! `2 h 05 min`, `3 d 4 h`, `7 s ago`, `1.2 MiB`.  Each is a `Display`, to
+//! use straight in `format!`:
+//!
+//! ```
+//! use crumbeez_lib::{HumanDuration, HumanSize, TimeAgo};
+//!
+//! assert_eq!(HumanDuration::from_ms(7_500).to_string(), "7 s");
+//! assert_eq!(TimeAgo::between(1_000, 61_000).to_string(), "1 min ago");
+//! assert_eq!(HumanSize(1_258_291).to_string(), "1.2 MiB");
+//! ```
+//!
+//! Durations keep only their two largest units and round down, since
+//! "2 h 05 min" says all a glance needs of 2:05:59.  Summaries name
+//! durations in their own locale instead.
+
+use std::fmt;
+use std::time::Duration;
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+/// A length of time: `<1 s`, `7 s`, `45 min`, `2 h 05 min`, or `3 d 4 h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HumanDuration(pub Duration);
+
+impl HumanDuration {
+    pub fn from_ms(ms: u64) -> Self {
+        Self(Duration::from_millis(ms))
+    }
+}
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+        match secs {
+            0 => f.write_str("<1 s"),
+            1..MINUTE => write!(f, "{} s", secs),
+            MINUTE..HOUR => write!(f, "{} min", secs / MINUTE),
+            HOUR..DAY => write!(f, "{} h {:02} min", secs / HOUR, secs % HOUR / MINUTE),
+            _ => write!(f, "{} d {} h", secs / DAY, secs % DAY / HOUR),
+        }
+    }
+}
+
+/// How long ago something happened: `just now` or `7 s ago`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeAgo(pub Duration);
+
+impl TimeAgo {
+    /// Something at `then_ms`, seen at `now_ms`.  A time in the future, as a
+    /// clock stepping back can make it, counts as just now.
+    pub fn between(then_ms: u64, now_ms: u64) -> Self {
+        Self(Duration::from_millis(now_ms.saturating_sub(then_ms)))
+    }
+}
+
+impl fmt::Display for TimeAgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 < Duration::from_secs(1) {
+            f.write_str("just now")
+        } else {
+            write!(f, "{} ago", HumanDuration(self.0))
+        }
+    }
+}
+
+/// A number of bytes in binary units: `512 B`, `3.4 KiB`, `1.2 MiB`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HumanSize(pub u64);
+
+impl fmt::Display for HumanSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = 0;
+        // Move up before rounding would show 1024.0 of a unit.
+        while value >= 1023.95 && unit + 1 < UNITS.len() {
+            value /= 1024.0;
+            unit += 1;
+        }
+        write!(f, "{:.1} {}", value, UNITS[unit])
+    }
+}
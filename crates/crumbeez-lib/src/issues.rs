@@ -0,0 +1,361 @@
+//! Issue-tracker references in what was typed.
+//!
+//! Work on a ticket leaves its number everywhere: in the branch
+//! (`feature/PROJ-142-login`), the commit message, a pasted link.  Each
+//! summary lists the references found in its text, commands, pane titles,
+//! and session context as `issues` in its front matter, and leaves a line
+//! per reference in `summaries/issue-index.kdl`:
+//!
+//! ```kdl
+//! issue "PROJ-142" summary="01HQT3Z5E8M2C4K7P9R1V3X5Z7" day="2024-03-01" headline="09:00–09:45 UTC · 12 events"
+//! ```
+//!
+//! so `zellij pipe --name crumbeez::issue -- PROJ-142` can list the work on
+//! it even after the daily files are rolled up.  Like the links file, the
+//! index is append-only.
+//!
+//! Three forms are recognized:
+//!
+//! - Jira-style keys, `PROJ-142`: an upper-case project key of two or more
+//!   letters and digits, a dash, and a number.  Keys that are really
+//!   standards or encodings, like `UTF-8` and `SHA-256`, are left out.
+//! - GitHub-style numbers, `#4567` and `owner/repo#4567`.
+//! - GitHub issue and pull request URLs, written as `owner/repo#4567`.
+//!
+//! ```
+//! use crumbeez_lib::issue_refs;
+//!
+//! let refs = issue_refs(
+//!     [
+//!         "git switch -c feature/PROJ-142-login",
+//!         "see https://github.com/acme/api/pull/87 and #12, UTF-8 only",
+//!     ],
+//!     8,
+//! );
+//! assert_eq!(refs, ["PROJ-142", "acme/api#87", "#12"]);
+//! ```
+
+use crate::kdl::{self, KdlNode};
+use crate::{StoredSummary, SummaryId};
+
+/// Most issues listed per summary.
+pub const MAX_ISSUES: usize = 16;
+
+/// Longest issue number recognized, in digits.
+const MAX_NUMBER_DIGITS: usize = 7;
+
+/// Upper-case words that look like project keys but name standards,
+/// encodings, and the like.
+const NOT_PROJECT_KEYS: [&str; 16] = [
+    "AES", "CVE", "GMT", "HTTP", "ISO", "MD5", "PEP", "RFC", "RSA", "SHA", "SSL", "TLS", "UTC",
+    "UTF", "X86", "IEEE",
+];
+
+/// The issue references in `texts`, at most `max`, each once, in the order
+/// they first appear.
+pub fn issue_refs<'a>(texts: impl IntoIterator<Item = &'a str>, max: usize) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    for text in texts {
+        for issue in refs_in(text) {
+            if found.len() >= max {
+                return found;
+            }
+            if !found.contains(&issue) {
+                found.push(issue);
+            }
+        }
+    }
+    found
+}
+
+/// The references in `text`, in order, repeats included.
+fn refs_in(text: &str) -> Vec<String> {
+    let mut refs: Vec<(usize, String)> = Vec::new();
+    refs.extend(project_keys(text));
+    refs.extend(hash_numbers(text));
+    refs.extend(github_urls(text));
+    refs.sort_by_key(|(at, _)| *at);
+    refs.into_iter().map(|(_, issue)| issue).collect()
+}
+
+/// `PROJ-142`, also inside longer words like `feature/PROJ-142-login`.
+fn project_keys(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    let offset = |part: &str| part.as_ptr() as usize - text.as_ptr() as usize;
+    text.split(|c: char| !c.is_alphanumeric() && c != '-')
+        .flat_map(move |word| {
+            let parts: Vec<&str> = word.split('-').collect();
+            parts
+                .windows(2)
+                .filter(|pair| is_project_key(pair[0]) && is_number(pair[1]))
+                .map(|pair| (offset(pair[0]), format!("{}-{}", pair[0], pair[1])))
+                .collect::<Vec<_>>()
+        })
+}
+
+fn is_project_key(word: &str) -> bool {
+    word.len() >= 2
+        && word.starts_with(|c: char| c.is_ascii_uppercase())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && !NOT_PROJECT_KEYS.contains(&word)
+}
+
+/// Digits, not starting with 0, at most [`MAX_NUMBER_DIGITS`] of them.
+fn is_number(word: &str) -> bool {
+    !word.is_empty()
+        && word.len() <= MAX_NUMBER_DIGITS
+        && !word.starts_with('0')
+        && word.chars().all(|c| c.is_ascii_digit())
+}
+
+/// `#4567` on its own, or `owner/repo#4567`.
+fn hash_numbers(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    text.match_indices('#').filter_map(move |(at, _)| {
+        let after = &text[at + 1..];
+        let digits = after
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after.len());
+        let number = &after[..digits];
+        if !is_number(number) || after[digits..].starts_with(|c: char| c.is_alphanumeric()) {
+            return None;
+        }
+        let before = &text[..at];
+        let repo_start = before.rfind(|c: char| !is_repo_char(c)).map_or(0, |i| {
+            i + before[i..].chars().next().map_or(1, char::len_utf8)
+        });
+        let repo = &before[repo_start..];
+        if repo.is_empty() {
+            // Not `&#123;` or a URL fragment.
+            if before.ends_with(['&', '/']) {
+                return None;
+            }
+            return Some((at, format!("#{}", number)));
+        }
+        let (owner, name) = repo.split_once('/')?;
+        (!owner.is_empty() && !name.is_empty() && !name.contains('/'))
+            .then(|| (repo_start, format!("{}#{}", repo, number)))
+    })
+}
+
+fn is_repo_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/')
+}
+
+/// `https://github.com/owner/repo/issues/4567`, or `/pull/4567`.
+fn github_urls(text: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+    text.match_indices("github.com/")
+        .filter_map(move |(at, host)| {
+            let mut path = text[at + host.len()..].split('/');
+            let owner = path.next()?;
+            let repo = path.next()?;
+            let kind = path.next()?;
+            let number = path.next()?;
+            let digits = number
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(number.len());
+            let valid = |part: &str| !part.is_empty() && part.chars().all(is_repo_char);
+            (valid(owner)
+                && valid(repo)
+                && matches!(kind, "issues" | "pull")
+                && is_number(&number[..digits])
+                && !number[digits..].starts_with(|c: char| c.is_alphanumeric()))
+            .then(|| (at, format!("{}/{}#{}", owner, repo, &number[..digits])))
+        })
+}
+
+/// One line of the issue index: a summary that mentions an issue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssueLink {
+    pub issue: String,
+    pub summary: SummaryId,
+    /// `YYYY-MM-DD`, the day the summary was made.
+    pub day: String,
+    /// The summary's headline, so the index alone says what was done.
+    pub headline: String,
+}
+
+impl IssueLink {
+    /// A line for each issue `summary` lists.  None if it has no ID.
+    pub fn all_in(summary: &StoredSummary) -> Vec<Self> {
+        let (Some(id), Some(day)) = (summary.id, summary.day()) else {
+            return Vec::new();
+        };
+        summary
+            .issues
+            .iter()
+            .map(|issue| Self {
+                issue: issue.clone(),
+                summary: id,
+                day: day.clone(),
+                headline: summary.headline().to_string(),
+            })
+            .collect()
+    }
+
+    /// Whether it's about `issue`, ignoring case.
+    pub fn is_about(&self, issue: &str) -> bool {
+        self.issue.eq_ignore_ascii_case(issue)
+    }
+
+    /// One line of the issue index, newline included.
+    pub fn to_kdl(&self) -> String {
+        format!(
+            "issue {} summary={} day={} headline={}\n",
+            kdl::quote(&self.issue),
+            kdl::quote(&self.summary.to_string()),
+            kdl::quote(&self.day),
+            kdl::quote(&self.headline)
+        )
+    }
+
+    fn from_node(node: &KdlNode) -> Option<Self> {
+        if node.name != "issue" {
+            return None;
+        }
+        Some(Self {
+            issue: node.args.first()?.as_str()?.to_string(),
+            summary: node.prop("summary")?.as_str()?.parse().ok()?,
+            day: node.prop("day")?.as_str()?.to_string(),
+            headline: node
+                .prop("headline")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+
+    /// Parse an issue index, skipping damaged lines.
+    pub fn parse_all(text: &str) -> Vec<Self> {
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .filter_map(|line| {
+                kdl::parse(line)
+                    .ok()
+                    .and_then(|nodes| nodes.first().and_then(Self::from_node))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{StreamBuilder, FIXTURE_START_MS};
+    use crate::{AppRole, KeystrokeEvent, PaneFocusedEvent, SummaryStore};
+
+    fn refs(text: &str) -> Vec<String> {
+        issue_refs([text], MAX_ISSUES)
+    }
+
+    #[test]
+    fn finds_project_keys() {
+        assert_eq!(refs("fix PROJ-142: login loops"), ["PROJ-142"]);
+        assert_eq!(refs("git switch -c feature/PROJ-142-login"), ["PROJ-142"]);
+        assert_eq!(refs("OPS2-7_hotfix"), ["OPS2-7"]);
+        assert_eq!(refs("https://acme.atlassian.net/browse/WEB-9"), ["WEB-9"]);
+    }
+
+    #[test]
+    fn skips_keys_that_are_not_issues() {
+        assert!(refs("charset UTF-8, SHA-256 sums, ISO-8601 dates").is_empty());
+        assert!(refs("proj-142 P-1 xPROJ-1 PROJ-1a PROJ-0 PROJ-").is_empty());
+    }
+
+    #[test]
+    fn finds_github_numbers() {
+        assert_eq!(refs("closes #4567"), ["#4567"]);
+        assert_eq!(refs("see acme/api#87."), ["acme/api#87"]);
+        assert!(refs("&#123; page#3 #12a #0 #").is_empty());
+    }
+
+    #[test]
+    fn finds_github_urls() {
+        assert_eq!(
+            refs("gh pr view https://github.com/acme/api/pull/87#discussion"),
+            ["acme/api#87"]
+        );
+        assert_eq!(refs("open github.com/acme/web/issues/5"), ["acme/web#5"]);
+        assert!(refs("https://github.com/acme/api/tree/main").is_empty());
+    }
+
+    #[test]
+    fn keeps_first_appearance_order_without_repeats() {
+        assert_eq!(
+            issue_refs(
+                ["#2 then PROJ-1", "PROJ-1 again, and #2, and #3"],
+                MAX_ISSUES
+            ),
+            ["#2", "PROJ-1", "#3"]
+        );
+        assert_eq!(issue_refs(["#1 #2 #3"], 2), ["#1", "#2"]);
+    }
+
+    #[test]
+    fn summary_lists_issues_in_front_matter() {
+        let pane = PaneFocusedEvent::new("nvim PROJ-142.md").with_role(AppRole::Editor);
+        let mut summary = StreamBuilder::new(FIXTURE_START_MS)
+            .event(pane.into())
+            .typed("see #12")
+            .event(KeystrokeEvent::annotation("WEB-3 review"))
+            .summary();
+        assert_eq!(summary.issues, ["WEB-3", "PROJ-142", "#12"]);
+
+        summary.id = Some(SummaryId::new(FIXTURE_START_MS, 7));
+        let stored = StoredSummary::new(&summary.to_markdown());
+        assert_eq!(stored.issues, summary.issues);
+        assert!(stored.has_issue("proj-142"));
+        assert!(!stored.has_issue("PROJ-14"));
+    }
+
+    #[test]
+    fn search_narrows_to_an_issue() {
+        let mut store = SummaryStore::default();
+        store.push_markdown("---\nissues: [\"PROJ-142\"]\n---\n## 09:00–09:30 UTC · login\n");
+        store.push_markdown("---\nissues: [\"PROJ-7\"]\n---\n## 10:00–10:30 UTC · login\n");
+        store
+            .push_markdown("---\nissues: [\"PROJ-142\", \"#3\"]\n---\n## 11:00–11:30 UTC · docs\n");
+
+        let headlines = |query: &str| -> Vec<String> {
+            store
+                .search(query)
+                .iter()
+                .map(|s| s.headline().to_string())
+                .collect()
+        };
+        assert_eq!(
+            headlines("issue:PROJ-142"),
+            ["11:00–11:30 UTC · docs", "09:00–09:30 UTC · login"]
+        );
+        assert_eq!(
+            headlines("issue:proj-142 login"),
+            ["09:00–09:30 UTC · login"]
+        );
+        assert_eq!(headlines("issue:#3 tag:docs"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn index_lines_round_trip() {
+        let id = SummaryId::new(FIXTURE_START_MS, 7);
+        let stored = StoredSummary::new(&format!(
+            "---\nid: {}\nissues: [\"PROJ-142\", \"acme/api#87\"]\n---\n## 09:15 UTC · \"quoted\" work\n",
+            id
+        ));
+        let links = IssueLink::all_in(&stored);
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[1].issue, "acme/api#87");
+        assert_eq!(links[1].day, "2024-03-01");
+        assert_eq!(links[1].headline, "09:15 UTC · \"quoted\" work");
+
+        let file: String = links.iter().map(IssueLink::to_kdl).collect();
+        let parsed = IssueLink::parse_all(&format!("{}damaged \"line\n", file));
+        assert_eq!(parsed, links);
+        assert!(parsed[0].is_about("proj-142"));
+    }
+
+    #[test]
+    fn summaries_without_an_id_are_not_indexed() {
+        let stored = StoredSummary::new("---\nissues: [\"PROJ-1\"]\n---\n## 09:15 UTC\n");
+        assert!(IssueLink::all_in(&stored).is_empty());
+    }
+}
@@ -0,0 +1,593 @@
+//! A minimal parser for the subset of [KDL](https://kdl.dev) used by
+//! `.crumbeez/config.kdl`.
+//!
+//! Supported syntax:
+//!
+//! - Nodes with positional arguments, `key=value` properties, and `{ … }`
+//!   child blocks, separated by newlines or `;`.
+//! - Quoted strings with the usual escapes (`\"`, `\\`, `\n`, `\t`, `\r`).
+//! - Bare identifiers as string values (KDL v2 style), integers, floats,
+//!   `true`/`false`/`null` and their `#`-prefixed v2 spellings.
+//! - `//` line comments, `/* … */` block comments, and `/-` slashdash
+//!   comments that drop the following node, argument, or property.
+//!
+//! Type annotations, raw strings, and multi-line strings are not supported —
+//! config files don't need them and rejecting them keeps the parser small.
+
+use std::fmt;
+
+/// A scalar value appearing as a node argument or property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KdlValue {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+}
+
+impl KdlValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for KdlValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(s) => write!(f, "{:?}", s),
+            Self::Integer(n) => write!(f, "{}", n),
+            Self::Float(x) => write!(f, "{:?}", x),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Null => write!(f, "null"),
+        }
+    }
+}
+
+/// A single KDL node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KdlNode {
+    pub name: String,
+    pub args: Vec<KdlValue>,
+    pub props: Vec<(String, KdlValue)>,
+    pub children: Vec<KdlNode>,
+    /// 1-based line number the node name appeared on, for error reporting.
+    pub line: usize,
+}
+
+impl KdlNode {
+    /// Return the last value given for property `key`, if any.
+    pub fn prop(&self, key: &str) -> Option<&KdlValue> {
+        self.props
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+}
+
+/// A syntax error, with the 1-based line it was detected on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KdlError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for KdlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for KdlError {}
+
+/// Parse a KDL document into its top-level nodes.
+pub fn parse(input: &str) -> Result<Vec<KdlNode>, KdlError> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let nodes = parser.nodes()?;
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("unexpected '}'"));
+    }
+    Ok(nodes)
+}
+
+/// Quote `s` as a KDL string literal.
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// ── Parser ───────────────────────────────────────────────────────
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+/// One item on a node line after the name: an argument, a property, or the
+/// start of a children block.
+enum Entry {
+    Arg(KdlValue),
+    Prop(String, KdlValue),
+    Children(Vec<KdlNode>),
+}
+
+impl Parser {
+    fn error(&self, message: impl Into<String>) -> KdlError {
+        KdlError {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    /// Parse nodes until end of input or a closing `}` (left unconsumed).
+    fn nodes(&mut self) -> Result<Vec<KdlNode>, KdlError> {
+        let mut nodes = Vec::new();
+        loop {
+            self.skip_whitespace_and_terminators()?;
+            match self.peek() {
+                None | Some('}') => return Ok(nodes),
+                _ => {}
+            }
+            let discard = self.take_slashdash()?;
+            let node = self.node()?;
+            if !discard {
+                nodes.push(node);
+            }
+        }
+    }
+
+    fn node(&mut self) -> Result<KdlNode, KdlError> {
+        let line = self.line;
+        let name = match self.peek() {
+            Some('"') => self.string()?,
+            _ => self.identifier()?,
+        };
+        let mut node = KdlNode {
+            name,
+            args: Vec::new(),
+            props: Vec::new(),
+            children: Vec::new(),
+            line,
+        };
+
+        loop {
+            self.skip_inline_whitespace()?;
+            match self.peek() {
+                None | Some('\n') | Some(';') | Some('}') => return Ok(node),
+                _ => {}
+            }
+            let discard = self.take_slashdash()?;
+            let entry = self.entry()?;
+            if discard {
+                continue;
+            }
+            match entry {
+                Entry::Arg(v) => node.args.push(v),
+                Entry::Prop(k, v) => node.props.push((k, v)),
+                Entry::Children(children) => {
+                    node.children = children;
+                    return Ok(node);
+                }
+            }
+        }
+    }
+
+    fn entry(&mut self) -> Result<Entry, KdlError> {
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                let children = self.nodes()?;
+                if self.bump() != Some('}') {
+                    return Err(self.error("unclosed '{'"));
+                }
+                Ok(Entry::Children(children))
+            }
+            Some('"') => {
+                let s = self.string()?;
+                if self.peek() == Some('=') {
+                    self.bump();
+                    Ok(Entry::Prop(s, self.value()?))
+                } else {
+                    Ok(Entry::Arg(KdlValue::String(s)))
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '+' || c == '#' => {
+                Ok(Entry::Arg(self.value()?))
+            }
+            Some(_) => {
+                let ident = self.identifier()?;
+                if self.peek() == Some('=') {
+                    self.bump();
+                    Ok(Entry::Prop(ident, self.value()?))
+                } else {
+                    Ok(Entry::Arg(keyword_or_string(ident)))
+                }
+            }
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn value(&mut self) -> Result<KdlValue, KdlError> {
+        match self.peek() {
+            Some('"') => Ok(KdlValue::String(self.string()?)),
+            Some('#') => {
+                self.bump();
+                match self.identifier()?.as_str() {
+                    "true" => Ok(KdlValue::Bool(true)),
+                    "false" => Ok(KdlValue::Bool(false)),
+                    "null" => Ok(KdlValue::Null),
+                    other => Err(self.error(format!("unknown keyword '#{other}'"))),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '+' => {
+                let word = self.identifier()?;
+                match parse_number(&word) {
+                    Some(v) => Ok(v),
+                    None if starts_numeric(&word) => {
+                        Err(self.error(format!("invalid number '{word}'")))
+                    }
+                    None => Ok(KdlValue::String(word)),
+                }
+            }
+            Some(_) => Ok(keyword_or_string(self.identifier()?)),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, KdlError> {
+        let start_line = self.line;
+        self.bump(); // opening quote
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None => {
+                    return Err(KdlError {
+                        line: start_line,
+                        message: "unterminated string".to_string(),
+                    })
+                }
+                Some('"') => return Ok(out),
+                Some('\\') => match self.bump() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('/') => out.push('/'),
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some(c) => return Err(self.error(format!("unknown escape '\\{c}'"))),
+                    None => return Err(self.error("unterminated string")),
+                },
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, KdlError> {
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || "{}();=\"\\/".contains(c) {
+                break;
+            }
+            out.push(c);
+            self.bump();
+        }
+        if out.is_empty() {
+            let found = self
+                .peek()
+                .map_or("end of input".to_string(), |c| format!("'{c}'"));
+            return Err(self.error(format!("expected an identifier, found {found}")));
+        }
+        Ok(out)
+    }
+
+    /// Consume a `/-` slashdash marker if present.
+    fn take_slashdash(&mut self) -> Result<bool, KdlError> {
+        if self.peek() == Some('/') && self.peek_at(1) == Some('-') {
+            self.bump();
+            self.bump();
+            self.skip_inline_whitespace()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Skip spaces, tabs, comments, and `\` line continuations — but not
+    /// newlines, which terminate a node.
+    fn skip_inline_whitespace(&mut self) -> Result<(), KdlError> {
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(c), _) if c != '\n' && c.is_whitespace() => {
+                    self.bump();
+                }
+                (Some('/'), Some('/')) => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                (Some('/'), Some('*')) => self.skip_block_comment()?,
+                (Some('\\'), _) => {
+                    self.bump();
+                    self.skip_inline_whitespace()?;
+                    if self.peek() == Some('\n') {
+                        self.bump();
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn skip_whitespace_and_terminators(&mut self) -> Result<(), KdlError> {
+        loop {
+            self.skip_inline_whitespace()?;
+            match self.peek() {
+                Some('\n') | Some(';') => {
+                    self.bump();
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn skip_block_comment(&mut self) -> Result<(), KdlError> {
+        let start_line = self.line;
+        self.bump();
+        self.bump();
+        let mut depth = 1;
+        while depth > 0 {
+            match (self.bump(), self.peek()) {
+                (Some('/'), Some('*')) => {
+                    self.bump();
+                    depth += 1;
+                }
+                (Some('*'), Some('/')) => {
+                    self.bump();
+                    depth -= 1;
+                }
+                (Some(_), _) => {}
+                (None, _) => {
+                    return Err(KdlError {
+                        line: start_line,
+                        message: "unterminated block comment".to_string(),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn keyword_or_string(ident: String) -> KdlValue {
+    match ident.as_str() {
+        "true" => KdlValue::Bool(true),
+        "false" => KdlValue::Bool(false),
+        "null" => KdlValue::Null,
+        _ => KdlValue::String(ident),
+    }
+}
+
+fn starts_numeric(word: &str) -> bool {
+    let digits = word.trim_start_matches(['-', '+']);
+    digits.starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn parse_number(word: &str) -> Option<KdlValue> {
+    let cleaned: String = word.chars().filter(|&c| c != '_').collect();
+    if let Ok(n) = cleaned.parse::<i64>() {
+        return Some(KdlValue::Integer(n));
+    }
+    if let Ok(x) = cleaned.parse::<f64>() {
+        return Some(KdlValue::Float(x));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn one(input: &str) -> KdlNode {
+        let mut nodes = parse(input).unwrap();
+        assert_eq!(nodes.len(), 1, "{:?}", nodes);
+        nodes.remove(0)
+    }
+
+    fn string(s: &str) -> KdlValue {
+        KdlValue::String(s.to_string())
+    }
+
+    #[test]
+    fn quoted_strings_unescape() {
+        let node = one(r#"say "a \"quoted\" word" "back\\slash" "tab\tnew\nline" "\/""#);
+        assert_eq!(
+            node.args,
+            [
+                string("a \"quoted\" word"),
+                string("back\\slash"),
+                string("tab\tnew\nline"),
+                string("/"),
+            ]
+        );
+    }
+
+    #[test]
+    fn quote_round_trips() {
+        let text = "C:\\Users\\me \"work\"\n\ttabbed\r";
+        assert_eq!(one(&format!("path {}", quote(text))).args, [string(text)]);
+    }
+
+    #[test]
+    fn quoted_names_and_property_keys() {
+        let node = one(r#""my node" "odd key"=1 plain=two"#);
+        assert_eq!(node.name, "my node");
+        assert_eq!(node.prop("odd key"), Some(&KdlValue::Integer(1)));
+        assert_eq!(node.prop("plain"), Some(&string("two")));
+    }
+
+    #[test]
+    fn values_of_every_kind() {
+        let node = one("n 1 -2 +3 1_000 2.5 true #false null #null bare");
+        assert_eq!(
+            node.args,
+            [
+                KdlValue::Integer(1),
+                KdlValue::Integer(-2),
+                KdlValue::Integer(3),
+                KdlValue::Integer(1000),
+                KdlValue::Float(2.5),
+                KdlValue::Bool(true),
+                KdlValue::Bool(false),
+                KdlValue::Null,
+                KdlValue::Null,
+                string("bare"),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_last_property_wins() {
+        let node = one("n key=1 key=2");
+        assert_eq!(node.prop("key"), Some(&KdlValue::Integer(2)));
+        assert_eq!(node.prop("missing"), None);
+    }
+
+    #[test]
+    fn nested_children() {
+        let nodes = parse(
+            "outer {\n    middle a=1 {\n        inner \"x\"\n    }\n    sibling\n}\nafter; last",
+        )
+        .unwrap();
+        let names: Vec<&str> = nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["outer", "after", "last"]);
+        let outer = &nodes[0];
+        assert_eq!(outer.children.len(), 2);
+        let middle = &outer.children[0];
+        assert_eq!(middle.prop("a"), Some(&KdlValue::Integer(1)));
+        assert_eq!(middle.children[0].name, "inner");
+        assert_eq!(middle.children[0].args, [string("x")]);
+        assert_eq!(middle.children[0].line, 3);
+        assert_eq!(outer.children[1].name, "sibling");
+        assert_eq!(nodes[1].line, 7);
+    }
+
+    #[test]
+    fn children_on_one_line() {
+        let node = one("a { b; c 1 }");
+        let names: Vec<&str> = node.children.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["b", "c"]);
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        let nodes = parse(
+            "// a line comment\n\
+             a 1 // trailing\n\
+             /* block\n   over lines */ b /* inline */ 2\n\
+             /* outer /* nested */ still comment */ c\n\
+             /-d \"dropped node\"\n\
+             e /-\"dropped arg\" kept /-gone=1 {\n}\n\
+             f \\\n  continued",
+        )
+        .unwrap();
+        let names: Vec<&str> = nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c", "e", "f"]);
+        assert_eq!(nodes[0].args, [KdlValue::Integer(1)]);
+        assert_eq!(nodes[1].args, [KdlValue::Integer(2)]);
+        assert_eq!(nodes[1].line, 4);
+        assert_eq!(nodes[3].args, [string("kept")]);
+        assert!(nodes[3].props.is_empty());
+        assert_eq!(nodes[4].args, [string("continued")]);
+    }
+
+    #[test]
+    fn an_empty_document_has_no_nodes() {
+        assert_eq!(parse("").unwrap(), []);
+        assert_eq!(parse("\n  ; // nothing\n/* here */").unwrap(), []);
+    }
+
+    #[test]
+    fn unterminated_strings_report_where_they_start() {
+        let err = parse("ok\nbad \"never closed\nmore").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.message, "unterminated string");
+        assert!(parse("bad \"ends in an escape\\").is_err());
+    }
+
+    #[test]
+    fn malformed_documents_are_errors() {
+        for (input, line, message) in [
+            ("a {\n  b\n", 3, "unclosed '{'"),
+            ("a\n}", 2, "unexpected '}'"),
+            ("/* never\nclosed", 1, "unterminated block comment"),
+            ("a \"\\q\"", 1, "unknown escape '\\q'"),
+            ("a #maybe", 1, "unknown keyword '#maybe'"),
+            ("a 12abc", 1, "invalid number '12abc'"),
+            ("a key=", 1, "expected a value"),
+        ] {
+            let err = parse(input).unwrap_err();
+            assert_eq!(
+                (err.line, err.message.as_str()),
+                (line, message),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn errors_display_their_line() {
+        let err = parse("a\n\n\"open").unwrap_err();
+        assert_eq!(err.to_string(), "line 3: unterminated string");
+    }
+}
@@ -1,12 +1,146 @@
-mod event_log;
-
-use std::collections::VecDeque;
+#[cfg(feature = "export")]
+pub mod anonymize;
+#[cfg(feature = "export")]
+mod asciicast;
+mod broadcast;
+#[cfg(feature = "analytics")]
+mod calendar;
+mod chord;
+mod compactor;
+mod config;
+mod container;
+mod context;
+mod correction;
+mod credentials;
+mod dedup;
+mod election;
+pub mod event_log;
+mod event_text;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
+mod flood;
+mod full_text;
+mod glob;
+mod heartbeat;
+mod history;
+mod humanize;
+mod issues;
+pub mod kdl;
+mod locale;
+mod long_text;
+mod onboarding;
+mod paste;
+mod power;
+mod profile;
+mod project;
+mod pull_request;
+mod recovery;
+mod regex;
+mod repl;
+mod replay;
+#[cfg(feature = "analytics")]
+mod rollup;
+mod search;
+mod session_context;
+mod sparkline;
+mod ssh;
+#[cfg(feature = "analytics")]
+mod standup;
+mod stats;
+mod submodule_link;
+mod summary;
+mod summary_id;
+mod summary_store;
+mod tags;
+mod title;
+#[cfg(feature = "analytics")]
+mod topics;
+mod vt_encode;
+#[cfg(feature = "analytics")]
+mod work_blocks;
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
 use std::fmt;
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-pub use event_log::{EventLog, EventLogError, LogEntry, Summary};
+#[cfg(feature = "export")]
+pub use asciicast::{to_asciicast, CAST_HEIGHT, CAST_WIDTH};
+pub use broadcast::{envelope_json, BROADCAST_PIPE, ENVELOPE_VERSION};
+#[cfg(feature = "analytics")]
+pub use calendar::MonthHeatmap;
+pub use chord::{ChordBinding, ChordDetector, CHORD_TIMEOUT_MS};
+pub use compactor::{compact, CompactionLevel, MAX_MERGE_GAP_MS};
+pub use config::{
+    pane_matches, BracketedPasteRule, ConfigError, CursorKeysRule, GitignoreMode, LongTextConfig,
+    MinTextLength, OutboundRedaction, PrivacyLevel, ProjectConfig, RetentionConfig, RiskyConfig,
+    ShortTextMode, SummaryBackendKind, SummaryConfig, SuperprojectRouting, REDACTED,
+};
+pub use container::{pane_container, ContainerCommand};
+pub use context::{context_blob, estimate_tokens, CHARS_PER_TOKEN, DEFAULT_CONTEXT_TOKENS};
+pub use correction::{CorrectionStats, CorrectionTracker, HotSpot, HOT_SPOTS};
+pub use credentials::{
+    prompts_for_secret, CredentialGuard, MAX_SECRET_ATTEMPTS, SECRET_PROMPT_WINDOW_MS,
+    SECRET_RETRY_WINDOW_MS,
+};
+pub use dedup::{DuplicateFilter, KeySource, DUPLICATE_WINDOW_MS};
+pub use election::{Election, InstanceRole, HELLO_PIPE};
+pub use event_log::iter::EntryIter;
+pub use event_log::{EventLog, EventLogError, LogEntry, SaveFrame};
+pub use event_text::EventParseError;
+pub use flood::{FloodGuard, DEFAULT_FLOOD_THRESHOLD};
+pub use full_text::FullTextIndex;
+pub use glob::{check_glob, glob_match};
+pub use heartbeat::{Heartbeat, HEARTBEAT_VERSION};
+pub use history::{
+    atuin_lookup_query, history_entries, parse_atuin_lookup, parse_history, CommandOutcome,
+    HistoryCommand, HistoryFormat, ATUIN_QUERY, HISTORY_IMPORT_DAYS,
+};
+pub use humanize::{HumanDuration, HumanSize, TimeAgo};
+pub use issues::{issue_refs, IssueLink, MAX_ISSUES};
+pub use locale::{fill, Locale, Strings};
+pub use long_text::{text_file, truncate_middle, ELLIPSIS};
+pub use onboarding::{Onboarding, OnboardingInput, OnboardingStep};
+pub use paste::{PasteDetector, PASTE_END, PASTE_GAP_MS, PASTE_START};
+pub use power::{on_battery, PowerMode, PowerProfile};
+pub use profile::{
+    agent_status, environment_change, risky_pattern, AppProfile, AppRole, EnvironmentKind,
+    ProfileRegistry, Reinterpreter, RISKY_COMMANDS,
+};
+pub use project::{directory_name, NameSource, ProjectName};
+pub use pull_request::{session_branch, PullRequestDraft, PR_MAX_ACTIVITIES};
+pub use recovery::Remnants;
+pub use regex::{Regex, RegexError};
+pub use repl::{needs_continuation, repl_language};
+pub use replay::{reconstruct, Frame, FrameChange, TYPING_INTERVAL_MS};
+#[cfg(feature = "analytics")]
+pub use rollup::{day_start_ms, DayRecord, ProjectWeek, SummaryIndex, WeeklyRollup};
+pub use search::find_matches;
+pub use session_context::{
+    parse_session_context, ContextProbe, ProbeSource, CONTEXT_PROBES, CONTEXT_SEPARATOR,
+    MAX_CONTEXT_VALUE_CHARS,
+};
+pub use sparkline::{events_per_minute, sparkline, SPARKLINE_MINUTES};
+pub use ssh::ssh_host;
+#[cfg(feature = "analytics")]
+pub use standup::{Standup, STANDUP_MAX_BULLETS, STANDUP_WINDOW_MS};
+pub use stats::LogStats;
+pub use submodule_link::SubmoduleLink;
+pub use summary::{
+    event_type_name, format_hm, format_ymd, Activity, EnvironmentSwitch, NotableAction,
+    PaneSegment, Summary, SummaryTrigger,
+};
+pub use summary_id::{SummaryId, SummaryIdError, SummaryLink};
+pub use summary_store::{StoredSummary, SummaryStore, SUMMARY_PAGE_SIZE};
+pub use tags::{is_tag, parse_tags, session_tags, MAX_TAGS, MAX_TAG_CHARS};
+pub use title::{TitleTracker, TITLE_SETTLE_MS};
+#[cfg(feature = "analytics")]
+pub use topics::{topics, MAX_TOPICS, MIN_TOPIC_CHARS};
+pub use vt_encode::{encode_key, write_key_bytes, CursorKeys, VtModifiers, MAX_KEY_SEQUENCE_LEN};
+#[cfg(feature = "analytics")]
+pub use work_blocks::{split_work_blocks, work_blocks_to_markdown, WorkBlock, DEFAULT_IDLE_GAP_MS};
 
 // ── Directory layout constants ───────────────────────────────────
 
@@ -19,9 +153,48 @@ pub const SCRATCH_DIR: &str = "scratchpad";
 /// Subdirectory for human-readable summary logs (Markdown).
 pub const SUMMARIES_SUBDIR: &str = "summaries";
 
+/// File mapping summary IDs to what they were made from (stored in the
+/// summaries directory).
+pub const SUMMARY_LINKS_FILE: &str = "summary-links.kdl";
+
+/// File pointing at the activity logged in the project's submodules (stored
+/// in the superproject's summaries directory).
+pub const SUBMODULE_LINKS_FILE: &str = "submodule-links.kdl";
+
+/// File of the issues each summary mentions (stored in the summaries
+/// directory).
+pub const ISSUE_INDEX_FILE: &str = "issue-index.kdl";
+
 /// Event log file name (stored in scratchpad directory).
 pub const EVENT_LOG_FILE: &str = "events.bin";
 
+/// Project-local config file name (stored directly in the `.crumbeez` directory).
+pub const CONFIG_FILE: &str = "config.kdl";
+
+/// Plugin diagnostic log file name (stored directly in the `.crumbeez`
+/// directory), written only when file logging is enabled.
+pub const PLUGIN_LOG_FILE: &str = "plugin.log";
+
+/// Monitoring status file name (stored directly in the `.crumbeez`
+/// directory), replaced after every event log write.
+pub const HEARTBEAT_FILE: &str = "heartbeat.json";
+
+/// Application directory under `$XDG_DATA_HOME` holding fallback storage for
+/// projects whose own directory can't be written to.
+pub const XDG_APP_DIR: &str = "crumbeez";
+
+/// Cross-project summary index file name (stored directly in the
+/// `$XDG_DATA_HOME` application directory).
+pub const SUMMARY_INDEX_FILE: &str = "summary-index.kdl";
+
+/// Subdirectory of the `$XDG_DATA_HOME` application directory for weekly
+/// rollups.
+pub const ROLLUPS_SUBDIR: &str = "rollups";
+
+/// Contents of the `.crumbeez/.gitignore` written in [`GitignoreMode::Local`]:
+/// ignore everything except the shareable config file.
+pub const LOCAL_GITIGNORE: &str = "*\n!.gitignore\n!config.kdl\n";
+
 // ── Directory layout helpers ─────────────────────────────────────
 
 /// Returns the `.crumbeez` directory path for a given project root.
@@ -44,14 +217,141 @@ pub fn event_log_path_from_crumbeez_dir(crumbeez_dir: &Path) -> PathBuf {
     crumbeez_dir.join(SCRATCH_DIR).join(EVENT_LOG_FILE)
 }
 
+/// Returns the project-local config file path for a given project root.
+pub fn config_path(root: &Path) -> PathBuf {
+    crumbeez_dir(root).join(CONFIG_FILE)
+}
+
+/// Returns the config file path given the `.crumbeez` directory directly.
+pub fn config_path_from_crumbeez_dir(crumbeez_dir: &Path) -> PathBuf {
+    crumbeez_dir.join(CONFIG_FILE)
+}
+
+/// Returns the plugin log file path given the `.crumbeez` directory directly.
+pub fn plugin_log_path_from_crumbeez_dir(crumbeez_dir: &Path) -> PathBuf {
+    crumbeez_dir.join(PLUGIN_LOG_FILE)
+}
+
 /// Returns the summaries subdirectory path for a given project root.
 pub fn summaries_dir(root: &Path) -> PathBuf {
     crumbeez_dir(root).join(SUMMARIES_SUBDIR)
 }
 
+/// Returns the summaries subdirectory path given the `.crumbeez` directory
+/// directly.
+pub fn summaries_dir_from_crumbeez_dir(crumbeez_dir: &Path) -> PathBuf {
+    crumbeez_dir.join(SUMMARIES_SUBDIR)
+}
+
+/// Returns the file each summary made on `day` (`YYYY-MM-DD`) is appended
+/// to, given the `.crumbeez` directory directly.
+pub fn daily_summary_path(crumbeez_dir: &Path, day: &str) -> PathBuf {
+    summaries_dir_from_crumbeez_dir(crumbeez_dir).join(format!("{}.md", day))
+}
+
+/// Returns the file linking each summary ID to its events and files, given
+/// the `.crumbeez` directory directly.
+pub fn summary_links_path(crumbeez_dir: &Path) -> PathBuf {
+    summaries_dir_from_crumbeez_dir(crumbeez_dir).join(SUMMARY_LINKS_FILE)
+}
+
+/// Returns the file of [`SubmoduleLink`]s to activity logged in a
+/// submodule, given the superproject's `.crumbeez` directory directly.
+pub fn submodule_links_path(crumbeez_dir: &Path) -> PathBuf {
+    summaries_dir_from_crumbeez_dir(crumbeez_dir).join(SUBMODULE_LINKS_FILE)
+}
+
+/// Returns the file of [`IssueLink`]s from issues to the summaries that
+/// mention them, given the `.crumbeez` directory directly.
+pub fn issue_index_path(crumbeez_dir: &Path) -> PathBuf {
+    summaries_dir_from_crumbeez_dir(crumbeez_dir).join(ISSUE_INDEX_FILE)
+}
+
+/// Where a screen snapshot taken at `timestamp_ms` goes, relative to the
+/// `.crumbeez` directory.  [`KeystrokeEvent::SnapshotTaken`] records it in
+/// this form so the log stays valid if the directory moves.
+pub fn snapshot_file(timestamp_ms: u64) -> String {
+    format!("{}/snapshot-{}.txt", SCRATCH_DIR, timestamp_ms)
+}
+
+/// Returns the monitoring status file, given the `.crumbeez` directory
+/// directly.  See [`Heartbeat`].
+pub fn heartbeat_path_from_crumbeez_dir(crumbeez_dir: &Path) -> PathBuf {
+    crumbeez_dir.join(HEARTBEAT_FILE)
+}
+
 /// Returns all directories that must exist for a given project root.
 pub fn required_dirs(root: &Path) -> Vec<PathBuf> {
-    vec![scratch_dir(root), summaries_dir(root)]
+    required_dirs_in(&crumbeez_dir(root))
+}
+
+/// Returns all directories that must exist inside a crumbeez data directory
+/// (either `<root>/.crumbeez` or a fallback location).
+pub fn required_dirs_in(crumbeez_dir: &Path) -> Vec<PathBuf> {
+    vec![
+        crumbeez_dir.join(SCRATCH_DIR),
+        crumbeez_dir.join(SUMMARIES_SUBDIR),
+    ]
+}
+
+// ── Cross-project storage ────────────────────────────────────────
+
+/// Returns the cross-project summary index path under `data_home`.
+pub fn summary_index_path(data_home: &Path) -> PathBuf {
+    data_home.join(XDG_APP_DIR).join(SUMMARY_INDEX_FILE)
+}
+
+/// Returns the weekly rollups directory under `data_home`.
+pub fn rollups_dir(data_home: &Path) -> PathBuf {
+    data_home.join(XDG_APP_DIR).join(ROLLUPS_SUBDIR)
+}
+
+// ── Fallback storage ─────────────────────────────────────────────
+
+/// Returns a stable, filesystem-safe directory name identifying a project
+/// root: its basename followed by a hash of the full path, e.g.
+/// `crumbeez-3f2a9c0e1b4d5a67`.
+pub fn project_key(root: &Path) -> String {
+    let name: String = root
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let hash = fnv1a(root.to_string_lossy().as_bytes());
+    if name.is_empty() {
+        format!("{hash:016x}")
+    } else {
+        format!("{name}-{hash:016x}")
+    }
+}
+
+/// Returns the fallback data directory standing in for `<root>/.crumbeez`
+/// when the project directory is read-only or on a network mount.
+pub fn fallback_crumbeez_dir(data_home: &Path, root: &Path) -> PathBuf {
+    data_home.join(XDG_APP_DIR).join(project_key(root))
+}
+
+/// Whether `dir` is a fallback data directory rather than `<root>/.crumbeez`.
+pub fn is_fallback_dir(dir: &Path) -> bool {
+    dir.file_name().is_none_or(|name| name != CRUMBEEZ_DIR_NAME)
+}
+
+/// 64-bit FNV-1a.  Stable across builds and platforms, unlike `DefaultHasher`.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
 }
 
 // ── Discovery phase ──────────────────────────────────────────────
@@ -62,12 +362,30 @@ pub enum DiscoveryPhase {
     /// Waiting for RunCommands permission to be granted.
     #[default]
     AwaitingPermissions,
-    /// Fired `git rev-parse --show-toplevel`, waiting for result.
-    FindingGitRoot,
-    /// Fired `git rev-parse --show-superproject-working-tree`, waiting for result.
-    FindingSuperproject,
-    /// Fired `mkdir -p` commands, waiting for them to complete.
-    CreatingDirs { pending: usize, dirs: Vec<PathBuf> },
+    /// Fired `git rev-parse --show-toplevel`, waiting for result.  `attempt`
+    /// counts from 1 and goes up each time the command times out and is
+    /// retried.
+    FindingGitRoot { attempt: u32 },
+    /// Fired `git rev-parse --show-superproject-working-tree`, waiting for
+    /// result.
+    FindingSuperproject { attempt: u32 },
+    /// Fired `test -d` on the primary root's `.crumbeez` dir, waiting for result.
+    CheckingExisting { roots: Vec<PathBuf> },
+    /// No `.crumbeez` dir exists yet; waiting for the user to finish onboarding.
+    AwaitingOnboarding { roots: Vec<PathBuf> },
+    /// Fired `mkdir -p` commands, waiting for them to complete.  `failed`
+    /// collects roots whose directory turned out not to be writable.
+    CreatingDirs {
+        pending: usize,
+        dirs: Vec<PathBuf>,
+        failed: Vec<PathBuf>,
+    },
+    /// Some roots weren't writable; fired a command to resolve
+    /// `$XDG_DATA_HOME` so their data can be stored there instead.
+    ResolvingFallback {
+        dirs: Vec<PathBuf>,
+        failed: Vec<PathBuf>,
+    },
     /// All .crumbeez directories have been created and are ready.
     Ready { dirs: Vec<PathBuf> },
     /// Discovery failed with an error message.
@@ -78,11 +396,26 @@ impl fmt::Display for DiscoveryPhase {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::AwaitingPermissions => write!(f, "⏳ Awaiting permissions..."),
-            Self::FindingGitRoot => write!(f, "🔍 Finding git root..."),
-            Self::FindingSuperproject => write!(f, "🔍 Checking for parent repo..."),
+            Self::FindingGitRoot { attempt } => {
+                write!(f, "🔍 Finding git root...")?;
+                write_attempt(f, *attempt)
+            }
+            Self::FindingSuperproject { attempt } => {
+                write!(f, "🔍 Checking for parent repo...")?;
+                write_attempt(f, *attempt)
+            }
+            Self::CheckingExisting { .. } => write!(f, "🔍 Checking for existing .crumbeez dir..."),
+            Self::AwaitingOnboarding { .. } => write!(f, "👋 Waiting for first-run setup..."),
             Self::CreatingDirs { pending, .. } => {
                 write!(f, "📁 Creating .crumbeez dirs ({pending} remaining)...")
             }
+            Self::ResolvingFallback { failed, .. } => {
+                write!(
+                    f,
+                    "📁 {} root(s) not writable, using fallback storage...",
+                    failed.len()
+                )
+            }
             Self::Ready { dirs } => {
                 let dirs: Vec<_> = dirs.iter().map(|d| d.to_string_lossy()).collect();
                 write!(f, "✅ Ready — {}", dirs.join(", "))
@@ -92,6 +425,14 @@ impl fmt::Display for DiscoveryPhase {
     }
 }
 
+/// Note a retry, e.g. " (attempt 2, git is slow to respond)".
+fn write_attempt(f: &mut fmt::Formatter<'_>, attempt: u32) -> fmt::Result {
+    if attempt > 1 {
+        write!(f, " (attempt {attempt}, git is slow to respond)")?;
+    }
+    Ok(())
+}
+
 // ── Keystroke activity ───────────────────────────────────────────
 
 /// Maximum number of recent keystroke events kept in the activity log.
@@ -134,6 +475,176 @@ pub enum KeystrokeEvent {
     /// startup).  This is a context boundary: subsequent keystrokes are being
     /// sent to a different program.
     PaneFocused(PaneFocusedEvent),
+
+    /// A bookmark the user dropped into the stream, e.g. "trying approach B".
+    /// It headlines the summary of the events that follow it.
+    Annotation(String),
+
+    /// A match accepted in a fuzzy finder, in place of the query keystrokes
+    /// and selection moves that led to it.  `moves` counts how often the
+    /// selection was moved before accepting.
+    FuzzySelected { query: String, moves: usize },
+
+    /// One submission to a REPL or database client, which may span several
+    /// lines, e.g. a whole function definition.
+    ReplInput { language: String, code: String },
+
+    /// A prompt submitted to an AI coding agent, kept whole so later
+    /// sessions can see what was already asked.
+    PromptToAgent(String),
+
+    /// An agent reported a new status through its pane title, e.g.
+    /// "Running tests".  This is the agent's activity, not the user's.
+    AgentStatus(String),
+
+    /// Typed text shorter than the project's `min_text_length`, kept only
+    /// as its length in characters.
+    ShortText(usize),
+
+    /// Logging stopped because the session was detached or locked.  The
+    /// time until the next [`SessionResumed`](Self::SessionResumed) is a
+    /// break, not idle time at the keyboard.
+    SessionSuspended(SuspendReason),
+
+    /// Logging picked up again after a [`SessionSuspended`](Self::SessionSuspended).
+    SessionResumed,
+
+    /// Zellij switched input mode, e.g. to `tab` after its prefix key, so
+    /// the keys that follow are commands to Zellij rather than to the pane.
+    /// The mode is Zellij's name for it in kebab case, e.g. `enter-search`.
+    ModeChanged(String),
+
+    /// The focused pane's screen was saved to a file, given relative to the
+    /// `.crumbeez` directory (see [`snapshot_file`]), to pin its output.
+    SnapshotTaken(String),
+
+    /// Keys of a kind the project's `disable_events` leaves out, counted
+    /// rather than logged.
+    Filtered { kind: EventKind, count: usize },
+
+    /// A configured multi-key sequence, e.g. `Ctrl+B c`, in place of its
+    /// keys.  Holds the keys as written in the config.
+    ChordSequence(String),
+
+    /// Input arrived faster than anyone types, e.g. a stuck key or a
+    /// program writing to the terminal, so `keys` keys over `duration_ms`
+    /// were counted rather than logged.  Stamped when the flood subsided.
+    FloodDetected { keys: usize, duration_ms: u64 },
+
+    /// The focused pane's program changed its title, e.g. nvim naming the
+    /// file it opened, and kept it long enough to count as more than a
+    /// flicker (see [`TitleTracker`]).
+    PaneTitleChanged(String),
+
+    /// The tab with this name was closed, ending whatever was done in it.
+    TabClosed(String),
+
+    /// The session was renamed to this, usually to say what it's now for.
+    SessionRenamed(String),
+
+    /// Typed text longer than the project's `long_text` length, kept as a
+    /// `preview` cut in the middle (see [`truncate_middle`]).  All `chars`
+    /// characters of it are in `file`, relative to the `.crumbeez`
+    /// directory (see [`text_file`]).
+    LongText {
+        preview: String,
+        chars: usize,
+        file: String,
+    },
+
+    /// A command run in a shell, as that `shell`'s history recorded it:
+    /// imported from before crumbeez was logging (see [`HistoryFormat`]),
+    /// stamped with when it ran, or looked up in atuin once a command typed
+    /// in a shell pane finished, with its `outcome`.
+    CommandExecuted {
+        command: String,
+        shell: String,
+        /// Logs from before outcomes were recorded have none.
+        #[serde(default)]
+        outcome: Option<CommandOutcome>,
+    },
+
+    /// Toolchain versions, the active virtualenv, the Kubernetes context,
+    /// and the like, captured by [`CONTEXT_PROBES`] when the project was
+    /// found, keyed by [`ContextProbe::key`].
+    SessionContext(BTreeMap<String, String>),
+
+    /// A command typed in a shell switched the Kubernetes context or
+    /// namespace, or logged in to an AWS profile (see
+    /// [`environment_change`]), to `target`.
+    EnvironmentChanged {
+        kind: EnvironmentKind,
+        target: String,
+    },
+
+    /// A command submitted in a shell or SQL pane matched the project's
+    /// `pattern` for commands that are hard to undo (see [`RiskyConfig`]),
+    /// with the `reason` given for it when the project asks for one.
+    RiskyCommand {
+        command: String,
+        pattern: String,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+
+    /// The session's [tags](crate::parse_tags) were set to these, replacing
+    /// any before.  None clears them.
+    SessionTagged(Vec<String>),
+
+    /// A password or passphrase was typed at a prompt that hides it, e.g.
+    /// sudo's (see [`CredentialGuard`]).  What was typed is dropped.
+    CredentialEntered,
+}
+
+impl KeystrokeEvent {
+    /// Pass the text the event carries through `redact`: what was typed or
+    /// shown, and the names of a focused pane.  Keys and paths are left
+    /// alone.
+    pub fn redact_text(&mut self, redact: &impl Fn(&str) -> Cow<'_, str>) {
+        match self {
+            Self::TextTyped(text)
+            | Self::LongText { preview: text, .. }
+            | Self::CommandExecuted { command: text, .. }
+            | Self::EnvironmentChanged { target: text, .. }
+            | Self::Annotation(text)
+            | Self::FuzzySelected { query: text, .. }
+            | Self::ReplInput { code: text, .. }
+            | Self::PromptToAgent(text)
+            | Self::AgentStatus(text)
+            | Self::PaneTitleChanged(text)
+            | Self::TabClosed(text)
+            | Self::SessionRenamed(text) => {
+                if let Cow::Owned(redacted) = redact(text) {
+                    *text = redacted;
+                }
+            }
+            Self::PaneFocused(pane) => pane.redact_text(redact),
+            Self::RiskyCommand {
+                command, reason, ..
+            } => {
+                for text in std::iter::once(command).chain(reason) {
+                    if let Cow::Owned(redacted) = redact(text) {
+                        *text = redacted;
+                    }
+                }
+            }
+            Self::SessionContext(values) => {
+                for value in values.values_mut() {
+                    if let Cow::Owned(redacted) = redact(value) {
+                        *value = redacted;
+                    }
+                }
+            }
+            Self::SessionTagged(tags) => {
+                for tag in tags {
+                    if let Cow::Owned(redacted) = redact(tag) {
+                        *tag = redacted;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 impl fmt::Display for KeystrokeEvent {
@@ -147,10 +658,188 @@ impl fmt::Display for KeystrokeEvent {
             Self::FunctionKey(n) => write!(f, "F{}", n),
             Self::SystemKey(k) => write!(f, "sys {}", k),
             Self::PaneFocused(p) => write!(f, "focus → {}", p),
+            Self::Annotation(s) => write!(f, "📌 {}", s),
+            Self::FuzzySelected { query, moves } => {
+                write!(f, "fuzzy-selected {:?} after {} moves", query, moves)
+            }
+            Self::ReplInput { language, code } => write!(f, "{} {:?}", language, code),
+            Self::PromptToAgent(s) => write!(f, "prompt {:?}", s),
+            Self::AgentStatus(s) => write!(f, "agent: {}", s),
+            Self::ShortText(n) => write!(f, "typed {} chars", n),
+            Self::SessionSuspended(reason) => write!(f, "⏸ session {}", reason),
+            Self::SessionResumed => write!(f, "▶ session resumed"),
+            Self::ModeChanged(mode) => write!(f, "mode {}", mode),
+            Self::SnapshotTaken(path) => write!(f, "📸 snapshot {}", path),
+            Self::Filtered { kind, count } => write!(f, "{} {}", count, kind.plural()),
+            Self::ChordSequence(keys) => write!(f, "chord {}", keys),
+            Self::FloodDetected { keys, duration_ms } => write!(
+                f,
+                "⚠ input flood: {} keys over {:.1}s",
+                keys,
+                *duration_ms as f64 / 1000.0
+            ),
+            Self::PaneTitleChanged(title) => write!(f, "title → {}", title),
+            Self::TabClosed(name) => write!(f, "✕ tab {}", name),
+            Self::SessionRenamed(name) => write!(f, "session → {}", name),
+            Self::LongText {
+                preview,
+                chars,
+                file,
+            } => write!(f, "typed {:?} ({} chars in {})", preview, chars, file),
+            Self::CommandExecuted {
+                command,
+                shell,
+                outcome: None,
+            } => write!(f, "$ {:?} ({} history)", command, shell),
+            Self::CommandExecuted {
+                command,
+                shell,
+                outcome: Some(outcome),
+            } => write!(f, "$ {:?} ({}: {})", command, shell, outcome),
+            Self::EnvironmentChanged { kind, target } => write!(f, "⇄ {} → {}", kind, target),
+            Self::RiskyCommand {
+                command,
+                pattern,
+                reason,
+            } => {
+                write!(f, "⚠ risky {:?} (matches {:?})", command, pattern)?;
+                match reason {
+                    Some(reason) => write!(f, ": {}", reason),
+                    None => Ok(()),
+                }
+            }
+            Self::SessionContext(values) => {
+                write!(f, "🧰 context")?;
+                for (key, value) in values {
+                    write!(f, " {}={:?}", key, value)?;
+                }
+                Ok(())
+            }
+            Self::SessionTagged(tags) if tags.is_empty() => write!(f, "🏷 untagged"),
+            Self::SessionTagged(tags) => write!(f, "🏷 tagged {}", tags.join(" ")),
+            Self::CredentialEntered => write!(f, "🔑 credential entered"),
+        }
+    }
+}
+
+/// Input modes for reading a pane's output rather than typing into it.
+pub const READING_MODES: [&str; 3] = ["scroll", "search", "enter-search"];
+
+/// Why logging was suspended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SuspendReason {
+    /// No client is attached to the session.
+    Detached,
+    /// The session is in locked input mode.
+    Locked,
+}
+
+impl fmt::Display for SuspendReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Detached => write!(f, "detached"),
+            Self::Locked => write!(f, "locked"),
         }
     }
 }
 
+/// Kinds of event a project can leave out of the log with
+/// `disable_events`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum EventKind {
+    Navigation,
+    /// Editing keys other than Enter, which is what marks text as run or
+    /// submitted and so is always logged.
+    EditControl,
+    Shortcut,
+    Escape,
+    FunctionKey,
+    SystemKey,
+    ModeChange,
+    AgentStatus,
+    TitleChange,
+    SessionContext,
+}
+
+impl EventKind {
+    pub const ALL: [Self; 10] = [
+        Self::Navigation,
+        Self::EditControl,
+        Self::Shortcut,
+        Self::Escape,
+        Self::FunctionKey,
+        Self::SystemKey,
+        Self::ModeChange,
+        Self::AgentStatus,
+        Self::TitleChange,
+        Self::SessionContext,
+    ];
+
+    /// The name used in config files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Navigation => "navigation",
+            Self::EditControl => "edit-control",
+            Self::Shortcut => "shortcut",
+            Self::Escape => "escape",
+            Self::FunctionKey => "function-key",
+            Self::SystemKey => "system-key",
+            Self::ModeChange => "mode-change",
+            Self::AgentStatus => "agent-status",
+            Self::TitleChange => "title-change",
+            Self::SessionContext => "session-context",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// How a count of these reads, e.g. "12 navigation keys".
+    pub fn plural(self) -> &'static str {
+        match self {
+            Self::Navigation => "navigation keys",
+            Self::EditControl => "editing keys",
+            Self::Shortcut => "shortcuts",
+            Self::Escape => "Esc presses",
+            Self::FunctionKey => "function keys",
+            Self::SystemKey => "system keys",
+            Self::ModeChange => "mode changes",
+            Self::AgentStatus => "agent statuses",
+            Self::TitleChange => "title changes",
+            Self::SessionContext => "session contexts",
+        }
+    }
+
+    /// The kind of `event` and how many keys it stands for, if it's one
+    /// that can be disabled.
+    pub fn of(event: &KeystrokeEvent) -> Option<(Self, usize)> {
+        Some(match event {
+            KeystrokeEvent::Navigation(nav) => (Self::Navigation, nav.count),
+            KeystrokeEvent::EditControl(EditControlEvent::Enter) => return None,
+            KeystrokeEvent::EditControl(
+                EditControlEvent::Backspace { count } | EditControlEvent::Delete { count },
+            ) => (Self::EditControl, *count),
+            KeystrokeEvent::EditControl(_) => (Self::EditControl, 1),
+            KeystrokeEvent::Shortcut(_) | KeystrokeEvent::ChordSequence(_) => (Self::Shortcut, 1),
+            KeystrokeEvent::Escape => (Self::Escape, 1),
+            KeystrokeEvent::FunctionKey(_) => (Self::FunctionKey, 1),
+            KeystrokeEvent::SystemKey(_) => (Self::SystemKey, 1),
+            KeystrokeEvent::ModeChanged(_) => (Self::ModeChange, 1),
+            KeystrokeEvent::AgentStatus(_) => (Self::AgentStatus, 1),
+            KeystrokeEvent::PaneTitleChanged(_) => (Self::TitleChange, 1),
+            KeystrokeEvent::SessionContext(_) => (Self::SessionContext, 1),
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 // ── ShortcutEvent ────────────────────────────────────────────────
 
 /// A keyboard shortcut — a chord involving Ctrl, Alt, or Super.
@@ -182,6 +871,52 @@ impl fmt::Display for ShortcutEvent {
     }
 }
 
+/// A word-wise cursor move or deletion, as shells and editors bind them to
+/// shortcuts in the style of readline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordEdit {
+    /// Alt+B, Alt+←, or Ctrl+←.
+    Left,
+    /// Alt+F, Alt+→, or Ctrl+→.
+    Right,
+    /// Ctrl+Backspace, Ctrl+W, or Alt+Backspace: delete the word before the
+    /// cursor.
+    DeleteLeft,
+    /// Alt+D or Ctrl+Delete: delete the word after the cursor.
+    DeleteRight,
+}
+
+impl WordEdit {
+    /// The word edit `shortcut` makes, if it makes one.  Shift is ignored;
+    /// Ctrl and Alt together, or Super, never make one.
+    pub fn of(shortcut: &ShortcutEvent) -> Option<Self> {
+        if shortcut.super_key || shortcut.ctrl == shortcut.alt {
+            return None;
+        }
+        let ctrl = shortcut.ctrl;
+        match shortcut.key {
+            ShortcutKey::Left => Some(Self::Left),
+            ShortcutKey::Right => Some(Self::Right),
+            ShortcutKey::Backspace => Some(Self::DeleteLeft),
+            ShortcutKey::Delete if ctrl => Some(Self::DeleteRight),
+            ShortcutKey::Char('w') if ctrl => Some(Self::DeleteLeft),
+            ShortcutKey::Char('b') if !ctrl => Some(Self::Left),
+            ShortcutKey::Char('f') if !ctrl => Some(Self::Right),
+            ShortcutKey::Char('d') if !ctrl => Some(Self::DeleteRight),
+            _ => None,
+        }
+    }
+
+    /// The byte offset in `text` the edit moves the cursor to from
+    /// `cursor`, or deletes up to.
+    pub fn target(self, text: &str, cursor: usize) -> usize {
+        match self {
+            Self::Left | Self::DeleteLeft => word_left(text, cursor),
+            Self::Right | Self::DeleteRight => word_right(text, cursor),
+        }
+    }
+}
+
 /// The base key of a shortcut chord.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ShortcutKey {
@@ -342,7 +1077,7 @@ impl fmt::Display for SystemKeyEvent {
 // ── PaneFocusedEvent ─────────────────────────────────────────────
 
 /// Describes the pane that just received keyboard focus.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct PaneFocusedEvent {
     /// The tab name, if known and non-empty.
     pub tab_name: Option<String>,
@@ -354,6 +1089,31 @@ pub struct PaneFocusedEvent {
     pub command: Option<String>,
     /// `true` when this is a plugin pane rather than a terminal pane.
     pub is_plugin: bool,
+    /// What kind of program the pane runs, from its [`AppProfile`].  Logs
+    /// written before profiles existed read as [`AppRole::Shell`].
+    #[serde(default)]
+    pub role: AppRole,
+    /// Zellij's ID for the pane, unique in the session among panes of its
+    /// kind: terminal and plugin panes are numbered separately.  Logs
+    /// written before IDs were recorded read as `None`.
+    #[serde(default)]
+    pub pane_id: Option<u32>,
+    /// Position of the pane's tab, from 0.
+    #[serde(default)]
+    pub tab_index: Option<usize>,
+    /// The working directory of the pane's program, when the host reports
+    /// it.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// The process ID of the pane's program, when the host reports it.
+    #[serde(default)]
+    pub pid: Option<u32>,
+    /// The remote host, when the pane is an ssh session (see [`ssh_host`]).
+    #[serde(default)]
+    pub host: Option<String>,
+    /// The container the pane works in (see [`pane_container`]).
+    #[serde(default)]
+    pub container: Option<String>,
 }
 
 impl fmt::Display for PaneFocusedEvent {
@@ -372,7 +1132,39 @@ impl fmt::Display for PaneFocusedEvent {
             (None, None) => {}
         }
 
-        write!(f, "{}", self.pane_title)
+        write!(f, "{}", self.pane_title)?;
+        if self.role != AppRole::Shell {
+            write!(f, " ({})", self.role)?;
+        }
+        if let Some(host) = &self.host {
+            write!(f, " on {}", host)?;
+        }
+        if let Some(container) = &self.container {
+            write!(f, " in container {}", container)?;
+        }
+        Ok(())
+    }
+}
+
+impl PaneFocusedEvent {
+    /// Pass the pane's names through `redact`: tab, title, command, cwd,
+    /// host, and container.
+    pub fn redact_text(&mut self, redact: &impl Fn(&str) -> Cow<'_, str>) {
+        let apply = |text: &mut String| {
+            if let Cow::Owned(redacted) = redact(text) {
+                *text = redacted;
+            }
+        };
+        apply(&mut self.pane_title);
+        self.tab_name.iter_mut().for_each(&apply);
+        self.command.iter_mut().for_each(&apply);
+        self.host.iter_mut().for_each(&apply);
+        self.container.iter_mut().for_each(&apply);
+        if let Some(ref mut cwd) = self.cwd {
+            if let Cow::Owned(redacted) = redact(&cwd.to_string_lossy()) {
+                *cwd = redacted.into();
+            }
+        }
     }
 }
 
@@ -388,9 +1180,9 @@ impl fmt::Display for PaneFocusedEvent {
 /// A `TextTyped` entry at the tail of the log is treated as a live buffer
 /// while cursor-movement and edit-control keys keep arriving.  A byte-level
 /// cursor tracks the insertion point inside that buffer.  Once a *sealing*
-/// event arrives (Enter, Esc, Tab, any shortcut, Up/Down/PageUp/PageDown, or
-/// any non-editing event) the buffer is frozen and subsequent keystrokes start
-/// a new entry.
+/// event arrives (Enter, Esc, Tab, any shortcut but a [`WordEdit`],
+/// Up/Down/PageUp/PageDown, or any non-editing event) the buffer is frozen
+/// and subsequent keystrokes start a new entry.
 ///
 /// Keys handled within the live buffer:
 ///
@@ -401,6 +1193,7 @@ impl fmt::Display for PaneFocusedEvent {
 /// | Delete | Delete char *at* cursor (if any) |
 /// | ← / → | Move cursor one Unicode scalar left / right |
 /// | Ctrl+← / Ctrl+→ | Move cursor one word left / right |
+/// | [Word-edit shortcuts](WordEdit) | Move cursor, or delete, one word left / right |
 /// | Home | Move cursor to start of buffer |
 /// | End | Move cursor to end of buffer |
 /// | Up / Down / PgUp / PgDn | Seal the buffer (left the line context) |
@@ -408,6 +1201,13 @@ impl fmt::Display for PaneFocusedEvent {
 /// If backspace/delete empties the buffer the `TextTyped` entry is removed
 /// rather than left as an empty string.  An empty buffer is never stored.
 ///
+/// Whenever a cursor is set, the tail entry is a non-empty `TextTyped` with
+/// the cursor on one of its character boundaries; see
+/// [`is_consistent`](Self::is_consistent).  Cursor state isn't saved, so a
+/// buffer that outlives the context it was typed in — the log it belongs to
+/// was reloaded, say — should be [sealed](Self::seal) rather than edited
+/// further.
+///
 /// This type lives in `crumbeez-lib` (no Zellij dependency) so it can be
 /// unit-tested on native targets.
 #[derive(Debug, Default)]
@@ -436,6 +1236,11 @@ impl KeystrokeActivity {
     /// separate entries.  Everything else either continues the live buffer or
     /// seals it and is appended as a new entry.
     pub fn push_event(&mut self, event: KeystrokeEvent) {
+        self.apply_event(event);
+        debug_assert!(self.is_consistent(), "inconsistent cursor: {:?}", self);
+    }
+
+    fn apply_event(&mut self, event: KeystrokeEvent) {
         match &event {
             // ── Text: insert into live buffer ────────────────────
             KeystrokeEvent::TextTyped(incoming) => {
@@ -550,7 +1355,7 @@ impl KeystrokeActivity {
                         self.coalesce_or_append(event);
                     }
                     NavDirection::End => {
-                        if let Some(_) = self.cursor {
+                        if self.cursor.is_some() {
                             if let Some(KeystrokeEvent::TextTyped(ref buf)) = self.events.back() {
                                 self.cursor = Some(buf.len());
                                 return;
@@ -571,9 +1376,36 @@ impl KeystrokeActivity {
                 }
             }
 
+            // ── Word edits: move or delete within the live buffer ─
+            KeystrokeEvent::Shortcut(shortcut) => {
+                if let (Some(cursor), Some(edit)) = (self.cursor, WordEdit::of(shortcut)) {
+                    if let Some(KeystrokeEvent::TextTyped(ref mut buf)) = self.events.back_mut() {
+                        let target = edit.target(buf, cursor);
+                        match edit {
+                            WordEdit::Left | WordEdit::Right => self.cursor = Some(target),
+                            WordEdit::DeleteLeft => {
+                                buf.drain(target..cursor);
+                                self.cursor = Some(target);
+                            }
+                            WordEdit::DeleteRight => {
+                                buf.drain(cursor..target);
+                            }
+                        }
+                        if buf.is_empty() {
+                            self.events.pop_back();
+                            self.cursor = None;
+                        }
+                        return;
+                    }
+                }
+                // Any other shortcut seals the buffer.
+                self.cursor = None;
+                self.coalesce_or_append(event);
+            }
+
             // ── Sealing events ───────────────────────────────────
-            // Enter, Tab, Esc, shortcuts, function keys, system keys — all
-            // seal the live buffer and are appended as their own entries.
+            // Enter, Tab, Esc, other shortcuts, function keys, system keys —
+            // all seal the live buffer and are appended as their own entries.
             _ => {
                 self.cursor = None;
                 self.coalesce_or_append(event);
@@ -581,6 +1413,49 @@ impl KeystrokeActivity {
         }
     }
 
+    /// Insert a single typed character.  Equivalent to pushing a one-char
+    /// [`KeystrokeEvent::TextTyped`], but inserts straight into the live
+    /// buffer without allocating an intermediate `String` — this is the path
+    /// nearly every keystroke takes.
+    pub fn push_char(&mut self, c: char) {
+        if let Some(cursor) = self.cursor {
+            if let Some(KeystrokeEvent::TextTyped(ref mut buf)) = self.events.back_mut() {
+                buf.insert(cursor, c);
+                self.cursor = Some(cursor + c.len_utf8());
+                return;
+            }
+        }
+        self.append(KeystrokeEvent::TextTyped(String::from(c)));
+        self.cursor = Some(c.len_utf8());
+    }
+
+    /// Freeze the live buffer, if there is one, so the next keys start a
+    /// new entry instead of editing it.
+    pub fn seal(&mut self) {
+        self.cursor = None;
+    }
+
+    /// Byte offset of the cursor in the live buffer, or `None` if there's no
+    /// live buffer.
+    pub fn cursor(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    /// Whether the cursor agrees with the events: with no cursor there's
+    /// nothing to check, and with one the tail entry is a non-empty
+    /// `TextTyped` with the cursor on one of its character boundaries.
+    pub fn is_consistent(&self) -> bool {
+        let Some(cursor) = self.cursor else {
+            return true;
+        };
+        match self.events.back() {
+            Some(KeystrokeEvent::TextTyped(buf)) => {
+                !buf.is_empty() && cursor <= buf.len() && buf.is_char_boundary(cursor)
+            }
+            _ => false,
+        }
+    }
+
     /// Clear all logged events and reset cursor state.
     pub fn clear(&mut self) {
         self.events.clear();
@@ -686,7 +1561,7 @@ fn word_left(s: &str, pos: usize) -> usize {
     // Skip trailing non-word chars, then skip the word.
     let mut iter = chars_before.iter().rev();
     // Skip leading whitespace/punctuation
-    while let Some(&(_, c)) = iter.next() {
+    for &(_, c) in iter.by_ref() {
         if c.is_alphanumeric() || c == '_' {
             break;
         }
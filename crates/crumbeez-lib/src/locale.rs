@@ -0,0 +1,384 @@
+//! Languages for summaries and the plugin pane.
+//!
+//! Everything a summary says in prose — its headline stats, the description
+//! of each activity, the Markdown table headers — and the plugin pane's
+//! section headers come from a [`Strings`] table for the project's
+//! [`Locale`], set with `locale "de"` in its config.  What the user typed,
+//! pane titles, and event type names stay as they are, so an LLM prompt
+//! built from a summary is in one language apart from the quoted input.
+//!
+//! Templates mark each value with `{}`, filled in order by [`fill`].  A
+//! translation can't reorder values, so phrase it to keep them in the
+//! English order.
+
+use std::fmt;
+
+use crate::{AppRole, EnvironmentKind, EventKind};
+
+/// A language summaries can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+    Spanish,
+}
+
+impl Locale {
+    pub const ALL: [Self; 3] = [Self::English, Self::German, Self::Spanish];
+
+    /// The ISO 639-1 code, as written in the config.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::German => "de",
+            Self::Spanish => "es",
+        }
+    }
+
+    /// The locale for a language code or tag, e.g. `de`, `de-AT`, or
+    /// `es_ES.UTF-8`, ignoring case and the region.
+    pub fn from_code(code: &str) -> Option<Self> {
+        let language = code.split(['-', '_', '.']).next()?;
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.code().eq_ignore_ascii_case(language))
+    }
+
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Self::English => &ENGLISH,
+            Self::German => &GERMAN,
+            Self::Spanish => &SPANISH,
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Every user-facing phrase in one language.
+#[derive(Debug)]
+pub struct Strings {
+    // ── Summary stats ──
+    pub events: &'static str,
+    pub misc_keys: &'static str,
+    /// A count, then the [kind's plural](Self::kind_plural).
+    pub filtered: &'static str,
+    pub reading_output: &'static str,
+    pub minutes: &'static str,
+    pub under_a_minute: &'static str,
+    pub unknown_pane: &'static str,
+    pub event_type_header: &'static str,
+    pub count_header: &'static str,
+    /// By [`EventKind`], in declaration order.
+    pub kind_plurals: [&'static str; EventKind::ALL.len()],
+
+    // ── Activities ──
+    pub typed: &'static str,
+    /// What submitting text does, by [`AppRole`] in declaration order.
+    pub submit_verbs: [&'static str; 7],
+    pub pressed: &'static str,
+    pub picked_first: &'static str,
+    pub picked_after_one: &'static str,
+    pub picked_after: &'static str,
+    pub ran_sql: &'static str,
+    pub evaluated: &'static str,
+    pub asked_agent: &'static str,
+    pub agent_status: &'static str,
+    pub title: &'static str,
+    pub normal_mode: &'static str,
+    pub entered_mode: &'static str,
+    pub snapshot: &'static str,
+    pub flood: &'static str,
+    pub tab_closed: &'static str,
+    pub session_renamed: &'static str,
+    pub entered_credential: &'static str,
+    pub ran_from_history: &'static str,
+    /// The command, its exit code, and how long it took.
+    pub ran_exit: &'static str,
+    /// The command, where it ran, its exit code, and how long it took.
+    pub ran_exit_in: &'static str,
+    /// What the session was working with, as `key value` pairs.
+    pub session_context: &'static str,
+    /// What was switched to and when, by [`EnvironmentKind`] in
+    /// declaration order.
+    pub switched_to: [&'static str; EnvironmentKind::ALL.len()],
+    /// Header of the section listing risky commands.
+    pub notable_actions: &'static str,
+    /// Headlines a summary of what a run left unsummarized when it stopped.
+    pub recovered: &'static str,
+    /// When the run stopped.
+    pub stopped_at: &'static str,
+    /// The last activity before it stopped.
+    pub in_flight: &'static str,
+    /// The command, when it ran, and the pattern it matched.
+    pub notable_action: &'static str,
+    pub one_other_key: &'static str,
+    pub other_keys: &'static str,
+
+    // ── Plugin pane ──
+    pub ui_event_log: &'static str,
+    pub ui_event_totals: &'static str,
+    pub ui_work_blocks: &'static str,
+    pub ui_summaries: &'static str,
+    pub ui_keystrokes: &'static str,
+    pub ui_no_keystrokes: &'static str,
+}
+
+impl Strings {
+    pub fn kind_plural(&self, kind: EventKind) -> &'static str {
+        self.kind_plurals[kind as usize]
+    }
+
+    pub fn switched_to(&self, kind: EnvironmentKind) -> &'static str {
+        self.switched_to[kind as usize]
+    }
+
+    pub fn submit_verb(&self, role: AppRole) -> &'static str {
+        self.submit_verbs[role as usize]
+    }
+}
+
+/// `template` with each `{}` replaced by the next of `values`.
+pub fn fill(template: &str, values: &[&dyn fmt::Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut values = values.iter();
+    let mut parts = template.split("{}");
+    out.push_str(parts.next().unwrap_or_default());
+    for part in parts {
+        if let Some(value) = values.next() {
+            out.push_str(&value.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+static ENGLISH: Strings = Strings {
+    events: "{} events",
+    misc_keys: "{} misc keys",
+    filtered: "plus ~{} {}",
+    reading_output: "{} reading output",
+    minutes: "{} min",
+    under_a_minute: "<1 min",
+    unknown_pane: "(unknown pane)",
+    event_type_header: "Event type",
+    count_header: "Count",
+    kind_plurals: [
+        "navigation keys",
+        "editing keys",
+        "shortcuts",
+        "Esc presses",
+        "function keys",
+        "system keys",
+        "mode changes",
+        "agent statuses",
+        "title changes",
+        "session contexts",
+    ],
+
+    typed: "typed {}",
+    submit_verbs: [
+        "ran",
+        "entered",
+        "searched for",
+        "picked with",
+        "ran SQL",
+        "evaluated",
+        "asked",
+    ],
+    pressed: "pressed {}",
+    picked_first: "picked the first match for {}",
+    picked_after_one: "picked a match for {} after 1 move",
+    picked_after: "picked a match for {} after {} moves",
+    ran_sql: "ran SQL {}",
+    evaluated: "evaluated {} {}",
+    asked_agent: "asked the agent {}",
+    agent_status: "agent: {}",
+    title: "on {}",
+    normal_mode: "back to normal mode",
+    entered_mode: "entered {} mode",
+    snapshot: "pinned the screen to {}",
+    flood: "flooded with {} keys too fast to log",
+    tab_closed: "closed the tab {}",
+    session_renamed: "renamed the session to {}",
+    entered_credential: "entered a password",
+    ran_from_history: "ran {} (from {} history)",
+    ran_exit: "ran {}, exit code {} after {}",
+    ran_exit_in: "ran {} in {}, exit code {} after {}",
+    session_context: "working with {}",
+    switched_to: [
+        "switched to {} cluster at {}",
+        "switched to {} namespace at {}",
+        "switched to {} AWS profile at {}",
+        "switched to {} branch at {}",
+    ],
+    notable_actions: "Notable actions",
+    recovered: "recovered",
+    stopped_at: "crumbeez stopped at {} before summarizing this",
+    in_flight: "last in progress: {}",
+    notable_action: "{} at {}, matching {}",
+    one_other_key: "1 other key",
+    other_keys: "{} other keys",
+
+    ui_event_log: "Event Log",
+    ui_event_totals: "Total: {} events, {} unconsumed",
+    ui_work_blocks: "Work Blocks (today, UTC)",
+    ui_summaries: "Summaries",
+    ui_keystrokes: "Keystroke Activity",
+    ui_no_keystrokes: "(no keystrokes yet)",
+};
+
+static GERMAN: Strings = Strings {
+    events: "{} Ereignisse",
+    misc_keys: "{} sonstige Tasten",
+    filtered: "dazu ~{} {}",
+    reading_output: "{} Ausgabe gelesen",
+    minutes: "{} Min.",
+    under_a_minute: "<1 Min.",
+    unknown_pane: "(unbekannter Bereich)",
+    event_type_header: "Ereignistyp",
+    count_header: "Anzahl",
+    kind_plurals: [
+        "Navigationstasten",
+        "Bearbeitungstasten",
+        "Tastenkürzel",
+        "Esc-Tastendrücke",
+        "Funktionstasten",
+        "Systemtasten",
+        "Moduswechsel",
+        "Agentenstatus",
+        "Titelwechsel",
+        "Sitzungskontexte",
+    ],
+
+    typed: "tippte {}",
+    submit_verbs: [
+        "führte aus:",
+        "gab ein:",
+        "suchte nach",
+        "wählte mit",
+        "führte SQL aus:",
+        "wertete aus:",
+        "fragte",
+    ],
+    pressed: "drückte {}",
+    picked_first: "wählte den ersten Treffer für {}",
+    picked_after_one: "wählte einen Treffer für {} nach 1 Schritt",
+    picked_after: "wählte einen Treffer für {} nach {} Schritten",
+    ran_sql: "führte SQL aus: {}",
+    evaluated: "wertete {} aus: {}",
+    asked_agent: "fragte den Agenten {}",
+    agent_status: "Agent: {}",
+    title: "bei {}",
+    normal_mode: "zurück im Normalmodus",
+    entered_mode: "wechselte in den Modus {}",
+    snapshot: "hielt den Bildschirm fest in {}",
+    flood: "bekam {} Tasten zu schnell zum Protokollieren",
+    tab_closed: "schloss den Tab {}",
+    session_renamed: "benannte die Sitzung in {} um",
+    entered_credential: "gab ein Passwort ein",
+    ran_from_history: "führte {} aus (aus dem {}-Verlauf)",
+    ran_exit: "führte {} aus, Exit-Code {} nach {}",
+    ran_exit_in: "führte {} in {} aus, Exit-Code {} nach {}",
+    session_context: "arbeitete mit {}",
+    switched_to: [
+        "wechselte zum Cluster {} um {}",
+        "wechselte zum Namespace {} um {}",
+        "wechselte zum AWS-Profil {} um {}",
+        "wechselte zum Branch {} um {}",
+    ],
+    notable_actions: "Bemerkenswerte Aktionen",
+    recovered: "wiederhergestellt",
+    stopped_at: "crumbeez endete um {}, bevor dies zusammengefasst wurde",
+    in_flight: "zuletzt in Arbeit: {}",
+    notable_action: "{} um {}, passend zu {}",
+    one_other_key: "1 weitere Taste",
+    other_keys: "{} weitere Tasten",
+
+    ui_event_log: "Ereignisprotokoll",
+    ui_event_totals: "Gesamt: {} Ereignisse, {} nicht zusammengefasst",
+    ui_work_blocks: "Arbeitsblöcke (heute, UTC)",
+    ui_summaries: "Zusammenfassungen",
+    ui_keystrokes: "Tastenaktivität",
+    ui_no_keystrokes: "(noch keine Tastendrücke)",
+};
+
+static SPANISH: Strings = Strings {
+    events: "{} eventos",
+    misc_keys: "{} teclas varias",
+    filtered: "más ~{} {}",
+    reading_output: "{} leyendo la salida",
+    minutes: "{} min",
+    under_a_minute: "<1 min",
+    unknown_pane: "(panel desconocido)",
+    event_type_header: "Tipo de evento",
+    count_header: "Cantidad",
+    kind_plurals: [
+        "teclas de navegación",
+        "teclas de edición",
+        "atajos",
+        "pulsaciones de Esc",
+        "teclas de función",
+        "teclas del sistema",
+        "cambios de modo",
+        "estados del agente",
+        "cambios de título",
+        "contextos de sesión",
+    ],
+
+    typed: "escribió {}",
+    submit_verbs: [
+        "ejecutó",
+        "introdujo",
+        "buscó",
+        "eligió con",
+        "ejecutó SQL",
+        "evaluó",
+        "preguntó",
+    ],
+    pressed: "pulsó {}",
+    picked_first: "eligió la primera coincidencia de {}",
+    picked_after_one: "eligió una coincidencia de {} tras 1 movimiento",
+    picked_after: "eligió una coincidencia de {} tras {} movimientos",
+    ran_sql: "ejecutó SQL {}",
+    evaluated: "evaluó en {} {}",
+    asked_agent: "preguntó al agente {}",
+    agent_status: "agente: {}",
+    title: "en {}",
+    normal_mode: "volvió al modo normal",
+    entered_mode: "entró en el modo {}",
+    snapshot: "fijó la pantalla en {}",
+    flood: "recibió {} teclas demasiado rápido para registrarlas",
+    tab_closed: "cerró la pestaña {}",
+    session_renamed: "renombró la sesión a {}",
+    entered_credential: "introdujo una contraseña",
+    ran_from_history: "ejecutó {} (del historial de {})",
+    ran_exit: "ejecutó {}, código de salida {} tras {}",
+    ran_exit_in: "ejecutó {} en {}, código de salida {} tras {}",
+    session_context: "trabajó con {}",
+    switched_to: [
+        "cambió al clúster {} a las {}",
+        "cambió al namespace {} a las {}",
+        "cambió al perfil de AWS {} a las {}",
+        "cambió a la rama {} a las {}",
+    ],
+    notable_actions: "Acciones destacadas",
+    recovered: "recuperado",
+    stopped_at: "crumbeez se detuvo a las {} antes de resumir esto",
+    in_flight: "lo último en curso: {}",
+    notable_action: "{} a las {}, coincide con {}",
+    one_other_key: "1 tecla más",
+    other_keys: "{} teclas más",
+
+    ui_event_log: "Registro de eventos",
+    ui_event_totals: "Total: {} eventos, {} sin resumir",
+    ui_work_blocks: "Bloques de trabajo (hoy, UTC)",
+    ui_summaries: "Resúmenes",
+    ui_keystrokes: "Actividad de teclado",
+    ui_no_keystrokes: "(aún no hay pulsaciones)",
+};
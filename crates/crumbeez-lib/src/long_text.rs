@@ -0,0 +1,75 @@
+//! Typed text too long to show or keep inline.
+//!
+//! Half a minute of steady typing is kilobytes of text: more than a line of
+//! the plugin pane holds, and more than a summary should quote.  Such text
+//! is shown cut in the middle, keeping how it starts and ends, which says
+//! more about a command or a paragraph than its start alone.
+//!
+//! Past the project's `long_text` length, the log itself keeps only a
+//! preview of this kind, as a [`KeystrokeEvent::LongText`], and the full
+//! text goes to a file in the scratchpad (see [`text_file`]).
+
+use std::borrow::Cow;
+
+use crate::{KeystrokeEvent, SCRATCH_DIR};
+
+/// Stands in for the middle of cut text.
+pub const ELLIPSIS: &str = " … ";
+
+/// `text` cut to at most `max_chars` characters by replacing its middle
+/// with [`ELLIPSIS`].  Text that fits, or a limit too small to keep
+/// anything either side, leaves it as it is.
+pub fn truncate_middle(text: &str, max_chars: usize) -> Cow<'_, str> {
+    let ellipsis_chars = ELLIPSIS.chars().count();
+    let chars = text.chars().count();
+    if chars <= max_chars || max_chars <= ellipsis_chars + 1 {
+        return Cow::Borrowed(text);
+    }
+    let kept = max_chars - ellipsis_chars;
+    // The start reads first, so it gets the odd character.
+    let head = kept.div_ceil(2);
+    let tail = kept - head;
+    let head_end = text.char_indices().nth(head).map_or(text.len(), |(i, _)| i);
+    let tail_start = text
+        .char_indices()
+        .nth(chars - tail)
+        .map_or(text.len(), |(i, _)| i);
+    Cow::Owned(format!(
+        "{}{}{}",
+        &text[..head_end],
+        ELLIPSIS,
+        &text[tail_start..]
+    ))
+}
+
+/// Where the full text of a [`KeystrokeEvent::LongText`] logged at
+/// `timestamp_ms` goes, relative to the `.crumbeez` directory.
+pub fn text_file(timestamp_ms: u64) -> String {
+    format!("{}/text-{}.txt", SCRATCH_DIR, timestamp_ms)
+}
+
+impl KeystrokeEvent {
+    /// The event with the text typed, submitted, or prompted cut to
+    /// `max_chars` with [`truncate_middle`], for showing on one line.
+    pub fn shortened(&self, max_chars: usize) -> Cow<'_, KeystrokeEvent> {
+        let text = match self {
+            Self::TextTyped(text)
+            | Self::LongText { preview: text, .. }
+            | Self::ReplInput { code: text, .. }
+            | Self::PromptToAgent(text) => text,
+            _ => return Cow::Borrowed(self),
+        };
+        let Cow::Owned(cut) = truncate_middle(text, max_chars) else {
+            return Cow::Borrowed(self);
+        };
+        let mut event = self.clone();
+        match &mut event {
+            Self::TextTyped(text)
+            | Self::LongText { preview: text, .. }
+            | Self::ReplInput { code: text, .. }
+            | Self::PromptToAgent(text) => *text = cut,
+            _ => {}
+        }
+        Cow::Owned(event)
+    }
+}
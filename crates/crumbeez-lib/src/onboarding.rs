@@ -0,0 +1,198 @@
+//! First-run setup, shown when a project has no `.crumbeez` directory yet.
+//!
+//! This is a small state machine layered over [`DiscoveryPhase`]: discovery
+//! pauses in [`DiscoveryPhase::AwaitingOnboarding`] while the user walks
+//! through the steps below, then resumes with the chosen roots.  The result
+//! is a [`ProjectConfig`] to be written to `.crumbeez/config.kdl`.
+//!
+//! [`DiscoveryPhase`]: crate::DiscoveryPhase
+//! [`DiscoveryPhase::AwaitingOnboarding`]: crate::DiscoveryPhase::AwaitingOnboarding
+
+use std::path::PathBuf;
+
+use crate::config::{PrivacyLevel, ProjectConfig, SummaryBackendKind};
+
+/// The steps of the onboarding flow, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnboardingStep {
+    ChooseRoot,
+    ChoosePrivacy,
+    ChooseBackend,
+    Confirm,
+    Done,
+}
+
+impl OnboardingStep {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::ChooseRoot => "Where should crumbeez keep its data?",
+            Self::ChoosePrivacy => "How much of what you type should be logged?",
+            Self::ChooseBackend => "How should summaries be generated?",
+            Self::Confirm => "Write .crumbeez/config.kdl with these settings?",
+            Self::Done => "Setup complete",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::ChooseRoot => Self::ChoosePrivacy,
+            Self::ChoosePrivacy => Self::ChooseBackend,
+            Self::ChooseBackend => Self::Confirm,
+            Self::Confirm | Self::Done => Self::Done,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            Self::ChooseRoot | Self::ChoosePrivacy => Self::ChooseRoot,
+            Self::ChooseBackend => Self::ChoosePrivacy,
+            Self::Confirm => Self::ChooseBackend,
+            Self::Done => Self::Done,
+        }
+    }
+}
+
+/// Abstract input for the onboarding flow, decoupled from Zellij key types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnboardingInput {
+    Up,
+    Down,
+    Select,
+    Back,
+}
+
+#[derive(Debug, Clone)]
+pub struct Onboarding {
+    pub step: OnboardingStep,
+    /// Index of the highlighted option in the current step.
+    pub selected: usize,
+    /// Each option is the full set of roots to create `.crumbeez` in.
+    root_options: Vec<Vec<PathBuf>>,
+    root_choice: usize,
+    privacy: PrivacyLevel,
+    backend: SummaryBackendKind,
+}
+
+impl Onboarding {
+    /// Start onboarding.  `discovered` are the roots found by discovery (the
+    /// git root plus any superproject); `initial_cwd` is offered as an
+    /// alternative when it differs.
+    pub fn new(discovered: Vec<PathBuf>, initial_cwd: PathBuf) -> Self {
+        let mut root_options = Vec::new();
+        if !discovered.is_empty() {
+            root_options.push(discovered);
+        }
+        if root_options.first().and_then(|r| r.first()) != Some(&initial_cwd) {
+            root_options.push(vec![initial_cwd]);
+        }
+
+        Self {
+            step: OnboardingStep::ChooseRoot,
+            selected: 0,
+            root_options,
+            root_choice: 0,
+            privacy: PrivacyLevel::default(),
+            backend: SummaryBackendKind::default(),
+        }
+    }
+
+    /// Labels for the options in the current step.
+    pub fn options(&self) -> Vec<String> {
+        match self.step {
+            OnboardingStep::ChooseRoot => self
+                .root_options
+                .iter()
+                .map(|roots| {
+                    let paths: Vec<_> = roots.iter().map(|r| r.to_string_lossy()).collect();
+                    paths.join(" + ")
+                })
+                .collect(),
+            OnboardingStep::ChoosePrivacy => PrivacyLevel::ALL
+                .iter()
+                .map(|p| match p {
+                    PrivacyLevel::Full => "Full text".to_string(),
+                    PrivacyLevel::Hashed => "Hashed (correlatable, not readable)".to_string(),
+                    PrivacyLevel::Metadata => "Metadata only (lengths, keys, panes)".to_string(),
+                })
+                .collect(),
+            OnboardingStep::ChooseBackend => SummaryBackendKind::ALL
+                .iter()
+                .map(|b| match b {
+                    SummaryBackendKind::Local => "Local LLM (e.g. Ollama)".to_string(),
+                    SummaryBackendKind::Cloud => "Cloud LLM (OpenAI, Anthropic, …)".to_string(),
+                    SummaryBackendKind::None => "None (statistics only)".to_string(),
+                })
+                .collect(),
+            OnboardingStep::Confirm => vec![
+                format!("root: {}", self.chosen_roots_label()),
+                format!("privacy: {}", self.privacy),
+                format!("summary backend: {}", self.backend),
+            ],
+            OnboardingStep::Done => Vec::new(),
+        }
+    }
+
+    /// Advance the state machine.  Returns `true` if anything changed.
+    pub fn handle(&mut self, input: OnboardingInput) -> bool {
+        let option_count = match self.step {
+            OnboardingStep::Confirm | OnboardingStep::Done => 0,
+            _ => self.options().len(),
+        };
+
+        match input {
+            OnboardingInput::Up if self.selected > 0 => self.selected -= 1,
+            OnboardingInput::Down if self.selected + 1 < option_count => self.selected += 1,
+            OnboardingInput::Select if self.step != OnboardingStep::Done => {
+                match self.step {
+                    OnboardingStep::ChooseRoot => self.root_choice = self.selected,
+                    OnboardingStep::ChoosePrivacy => {
+                        self.privacy = PrivacyLevel::ALL[self.selected]
+                    }
+                    OnboardingStep::ChooseBackend => {
+                        self.backend = SummaryBackendKind::ALL[self.selected]
+                    }
+                    OnboardingStep::Confirm | OnboardingStep::Done => {}
+                }
+                self.step = self.step.next();
+                self.selected = 0;
+            }
+            OnboardingInput::Back if self.step != OnboardingStep::ChooseRoot => {
+                self.step = self.step.prev();
+                self.selected = 0;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.step == OnboardingStep::Done
+    }
+
+    /// The roots chosen in the first step.
+    pub fn chosen_roots(&self) -> Vec<PathBuf> {
+        self.root_options
+            .get(self.root_choice)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The config resulting from the user's choices.
+    pub fn config(&self) -> ProjectConfig {
+        let mut config = ProjectConfig {
+            privacy: self.privacy,
+            ..ProjectConfig::default()
+        };
+        config.summary.backend = self.backend;
+        config
+    }
+
+    fn chosen_roots_label(&self) -> String {
+        let roots: Vec<_> = self
+            .chosen_roots()
+            .iter()
+            .map(|r| r.to_string_lossy().into_owned())
+            .collect();
+        roots.join(" + ")
+    }
+}
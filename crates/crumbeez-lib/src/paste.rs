@@ -0,0 +1,84 @@
+//! Telling a paste from typing among the keys forwarded to a pane, so it
+//! can be handed on in bracketed-paste markers.
+//!
+//! Zellij delivers a paste to an intercepting plugin as one key per
+//! character, which the plugin writes back to the pane one by one.  A
+//! program that turned on bracketed paste (`ESC [ ? 2004 h`) then sees
+//! typing: an editor auto-indents every pasted line again, a shell runs
+//! each line as it arrives.  [`PasteDetector`] spots the burst — keys far
+//! closer together than anyone types — and says where to put
+//! [`PASTE_START`] and [`PASTE_END`] around it.
+//!
+//! The burst is only recognized at its second key, so the first character
+//! of a paste goes ahead unbracketed.
+
+/// Written before pasted text: `ESC [ 200 ~`.
+pub const PASTE_START: &[u8] = b"\x1b[200~";
+
+/// Written after pasted text: `ESC [ 201 ~`.
+pub const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// Keys at most this far apart arrived together, as a paste.  Key repeat
+/// and the fastest typing are several times slower.
+pub const PASTE_GAP_MS: u64 = 2;
+
+#[derive(Debug, Default)]
+pub struct PasteDetector {
+    /// When the last key that could be part of a paste arrived.
+    last_key_ms: Option<u64>,
+    /// [`PASTE_START`] has been written without its [`PASTE_END`].
+    open: bool,
+}
+
+impl PasteDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append the markers due before forwarding the key arriving at
+    /// `now_ms`: the end of a paste it doesn't belong to, or the start of
+    /// one it continues.  Only `text` keys (characters, Enter, Tab) can be
+    /// pasted; any other key ends a paste.
+    pub fn before_key(&mut self, now_ms: u64, text: bool, out: &mut Vec<u8>) {
+        let in_burst = text
+            && self
+                .last_key_ms
+                .is_some_and(|last| now_ms.saturating_sub(last) <= PASTE_GAP_MS);
+        if self.open && !in_burst {
+            out.extend_from_slice(PASTE_END);
+            self.open = false;
+        } else if !self.open && in_burst {
+            out.extend_from_slice(PASTE_START);
+            self.open = true;
+        }
+        self.last_key_ms = text.then_some(now_ms);
+    }
+
+    /// Append [`PASTE_END`] if a paste is open and no key has arrived for
+    /// longer than [`PASTE_GAP_MS`] by `now_ms`.  Call when the timer fires.
+    pub fn finish(&mut self, now_ms: u64, out: &mut Vec<u8>) {
+        if self.ends_at().is_some_and(|end| now_ms >= end) {
+            out.extend_from_slice(PASTE_END);
+            self.open = false;
+        }
+    }
+
+    /// End an open paste without writing anything, as when the pane it
+    /// went to is gone.  Returns whether one was open, so the caller can
+    /// write [`PASTE_END`] elsewhere.
+    pub fn abandon(&mut self) -> bool {
+        self.last_key_ms = None;
+        std::mem::take(&mut self.open)
+    }
+
+    /// When an open paste will be over if no more keys arrive, to arm a
+    /// timer for [`finish`](Self::finish).
+    pub fn ends_at(&self) -> Option<u64> {
+        let last = self.last_key_ms.filter(|_| self.open)?;
+        Some(last + PASTE_GAP_MS + 1)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+}
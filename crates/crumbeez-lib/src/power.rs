@@ -0,0 +1,125 @@
+//! Doing less while the host runs on battery.
+//!
+//! Every timer the plugin arms wakes the host.  A [`PowerProfile`] says how
+//! eagerly it does its periodic work: how soon it writes the event log and
+//! summarizes after activity, whether it keeps work blocks and the rollup
+//! index up to date, and whether it draws a pane nobody can see.  The
+//! [`PowerMode`] chosen in the plugin's settings picks the profile, `Auto`
+//! following the power source (see [`on_battery`]).
+//!
+//! Nothing is lost in the low-power profile: events are written and
+//! summarized later and in bigger batches, and the skipped analytics catch
+//! up once the profile switches back.
+
+use std::fmt;
+use std::time::Duration;
+
+/// Which [`PowerProfile`] the plugin runs with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PowerMode {
+    #[default]
+    Normal,
+    Low,
+    /// Low on battery, normal otherwise.
+    Auto,
+}
+
+impl PowerMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(Self::Normal),
+            "low" => Some(Self::Low),
+            "auto" => Some(Self::Auto),
+            _ => None,
+        }
+    }
+
+    /// The profile to run with, given whether the host is on battery, if
+    /// that's known.  An unknown power source counts as mains.
+    pub fn profile(self, on_battery: Option<bool>) -> PowerProfile {
+        match self {
+            Self::Normal => PowerProfile::NORMAL,
+            Self::Low => PowerProfile::LOW,
+            Self::Auto if on_battery == Some(true) => PowerProfile::LOW,
+            Self::Auto => PowerProfile::NORMAL,
+        }
+    }
+}
+
+impl fmt::Display for PowerMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Normal => write!(f, "normal"),
+            Self::Low => write!(f, "low"),
+            Self::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+/// How eagerly the plugin does its periodic work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerProfile {
+    /// Least time between event log writes, before any backoff for failed
+    /// writes.
+    pub flush_interval: Duration,
+    /// Least quiet time before activity is summarized; a longer configured
+    /// inactivity timeout still applies.
+    pub summary_delay: Duration,
+    /// Keep work blocks and the rollup index up to date as summaries are
+    /// made.
+    pub analytics: bool,
+    /// Draw the plugin pane for commands, like a bookmark piped in, while
+    /// it isn't visible.  Background work like logging keys never draws a
+    /// hidden pane.
+    pub render_hidden: bool,
+}
+
+impl PowerProfile {
+    pub const NORMAL: Self = Self {
+        flush_interval: Duration::from_secs(5),
+        summary_delay: Duration::ZERO,
+        analytics: true,
+        render_hidden: true,
+    };
+
+    pub const LOW: Self = Self {
+        flush_interval: Duration::from_secs(60),
+        summary_delay: Duration::from_secs(2 * 60),
+        analytics: false,
+        render_hidden: false,
+    };
+
+    /// Quiet time before summarizing, for a configured `inactivity_timeout`.
+    pub fn inactivity_timeout(&self, inactivity_timeout: Duration) -> Duration {
+        inactivity_timeout.max(self.summary_delay)
+    }
+
+    pub fn is_low(&self) -> bool {
+        *self == Self::LOW
+    }
+}
+
+/// Whether the host is on battery, from the output of the plugin's power
+/// source check: macOS's `pmset -g batt` header (`Now drawing from 'Battery
+/// Power'`), the same wording from the Windows check, or the `online` flags
+/// of Linux's `/sys/class/power_supply` entries, one per line, where any
+/// mains supply online means it isn't.  `None` if the output says neither,
+/// as on a desktop without any of these.
+pub fn on_battery(output: &str) -> Option<bool> {
+    if output.contains("Battery Power") {
+        return Some(true);
+    }
+    if output.contains("AC Power") {
+        return Some(false);
+    }
+    let flags: Vec<&str> = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| *line == "0" || *line == "1")
+        .collect();
+    if flags.is_empty() {
+        None
+    } else {
+        Some(!flags.contains(&"1"))
+    }
+}
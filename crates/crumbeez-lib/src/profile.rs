@@ -0,0 +1,503 @@
+//! Per-application profiles: what keystrokes mean in a given kind of program.
+//!
+//! The classifier only sees keys, so by default every pane is read like a
+//! shell: typed characters are text and Enter runs a command.  That's wrong
+//! for much of what runs in a terminal — in an editor Enter is just a new
+//! line, in `less` the letters are
+//! scrolling, in `fzf` the typed text is a filter query, in `psql` Enter
+//! executes SQL.  A [`ProfileRegistry`] maps the focused pane's command to an
+//! [`AppRole`], which tags the pane's [`PaneFocusedEvent`](crate::PaneFocusedEvent)
+//! and, through a [`Reinterpreter`], rewrites events that mean something
+//! else in that role.
+//!
+//! Projects can add or override profiles in `config.kdl`:
+//!
+//! ```kdl
+//! profile "moar" "ov" role="pager"
+//! profile "python*" role="shell"   // don't treat Python as a REPL here
+//! ```
+//!
+//! Commands run in a shell are classified too: switching cluster,
+//! namespace, or AWS profile (see [`environment_change`]) is worth a
+//! breadcrumb of its own, and commands that are hard to undo (see
+//! [`RISKY_COMMANDS`] and [`risky_pattern`]) are flagged as risky.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::pane_matches;
+use crate::{
+    CursorKeys, EditControlEvent, KeystrokeEvent, NavDirection, NavigationEvent, ShortcutKey,
+};
+
+/// What kind of program a pane is running, as far as reading its keystrokes
+/// goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AppRole {
+    /// A shell or anything else without a profile: keys are taken at face
+    /// value.
+    #[default]
+    Shell,
+    /// A text editor: Enter starts a new line rather than submitting, so
+    /// text typed across several lines is kept together.
+    Editor,
+    /// `less`, `bat`, `man`: single letters scroll, `/` starts a search.
+    Pager,
+    /// `fzf` and friends: typed text is a filter query, Ctrl+N/Ctrl+P move
+    /// the selection.
+    FuzzyFinder,
+    /// A database client: submitted text is a query.
+    Sql,
+    /// A language REPL: submitted text is evaluated.
+    Repl,
+    /// An AI coding agent (`claude`, `aider`, `goose`): submitted text is a
+    /// prompt, and the pane title reports what the agent is doing.
+    Agent,
+}
+
+impl AppRole {
+    pub const ALL: [Self; 7] = [
+        Self::Shell,
+        Self::Editor,
+        Self::Pager,
+        Self::FuzzyFinder,
+        Self::Sql,
+        Self::Repl,
+        Self::Agent,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Shell => "shell",
+            Self::Editor => "editor",
+            Self::Pager => "pager",
+            Self::FuzzyFinder => "fuzzy-finder",
+            Self::Sql => "sql",
+            Self::Repl => "repl",
+            Self::Agent => "agent",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|role| role.name() == name)
+    }
+
+    /// How submitted text is described in summaries, e.g. "ran".
+    pub fn submit_verb(self) -> &'static str {
+        match self {
+            Self::Shell => "ran",
+            Self::Editor => "entered",
+            Self::Pager => "searched for",
+            Self::FuzzyFinder => "picked with",
+            Self::Sql => "ran SQL",
+            Self::Repl => "evaluated",
+            Self::Agent => "asked",
+        }
+    }
+
+    /// The cursor key mode programs in this role usually switch the
+    /// terminal to.  Editors and pagers turn on application mode when they
+    /// start (terminfo's `smkx`); the rest leave it normal.
+    pub fn cursor_keys(self) -> CursorKeys {
+        match self {
+            Self::Editor | Self::Pager => CursorKeys::Application,
+            _ => CursorKeys::Normal,
+        }
+    }
+
+    /// Whether programs in this role usually turn on bracketed paste.
+    /// Current shells, editors, and readline-based clients do; pagers and
+    /// fuzzy finders don't.
+    pub fn bracketed_paste(self) -> bool {
+        !matches!(self, Self::Pager | Self::FuzzyFinder)
+    }
+}
+
+impl fmt::Display for AppRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Panes whose command or title matches any of `patterns` have `role`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppProfile {
+    pub patterns: Vec<String>,
+    pub role: AppRole,
+}
+
+/// Built-in profiles, matched against the executable's basename (or, for
+/// agents that run under a generic interpreter, the title they set).
+const BUILTIN: &[(AppRole, &[&str])] = &[
+    (
+        AppRole::Editor,
+        &[
+            "vi",
+            "vim",
+            "nvim",
+            "hx",
+            "helix",
+            "kak",
+            "nano",
+            "micro",
+            "emacs",
+            "emacsclient",
+        ],
+    ),
+    (AppRole::Pager, &["less", "more", "most", "bat", "man"]),
+    (
+        AppRole::FuzzyFinder,
+        &["fzf", "fzf-tmux", "sk", "peco", "fzy"],
+    ),
+    (
+        AppRole::Sql,
+        &[
+            "psql", "pgcli", "mysql", "mariadb", "mycli", "sqlite3", "litecli", "duckdb", "usql",
+        ],
+    ),
+    (
+        AppRole::Repl,
+        &[
+            "python", "python3", "ipython", "node", "deno", "irb", "ghci", "iex", "julia",
+        ],
+    ),
+    (
+        AppRole::Agent,
+        &[
+            "claude",
+            "aider",
+            "goose",
+            "codex",
+            "gemini",
+            "opencode",
+            "*Claude Code*",
+        ],
+    ),
+];
+
+/// What an [environment switch](environment_change) switches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EnvironmentKind {
+    /// The Kubernetes cluster `kubectl` talks to.
+    KubeContext,
+    /// The namespace `kubectl` works in.
+    KubeNamespace,
+    /// The AWS profile logged in with.
+    AwsProfile,
+    /// The git branch checked out.
+    GitBranch,
+}
+
+impl EnvironmentKind {
+    pub const ALL: [Self; 4] = [
+        Self::KubeContext,
+        Self::KubeNamespace,
+        Self::AwsProfile,
+        Self::GitBranch,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::KubeContext => "kube-context",
+            Self::KubeNamespace => "kube-namespace",
+            Self::AwsProfile => "aws-profile",
+            Self::GitBranch => "git-branch",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+impl fmt::Display for EnvironmentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Commands that switch environment, by the words they start with.  The
+/// first argument after them names what's switched to, except for AWS,
+/// where it's the `--profile`.
+const ENVIRONMENT_SWITCHES: &[(EnvironmentKind, &[&str])] = &[
+    (
+        EnvironmentKind::KubeContext,
+        &["kubectl", "config", "use-context"],
+    ),
+    (EnvironmentKind::KubeContext, &["kubectl", "config", "use"]),
+    (EnvironmentKind::KubeContext, &["kubectx"]),
+    (EnvironmentKind::KubeNamespace, &["kubens"]),
+    (EnvironmentKind::AwsProfile, &["aws", "sso", "login"]),
+    (EnvironmentKind::GitBranch, &["git", "switch"]),
+    (EnvironmentKind::GitBranch, &["git", "checkout"]),
+];
+
+/// Separate commands on one line; only the first is looked at.
+const COMMAND_SEPARATORS: [&str; 5] = ["&&", "||", ";", "|", "&"];
+
+/// What `command_line` switches to, if it's one of the commands that
+/// switch cluster, namespace, AWS profile, or git branch.  Listing
+/// contexts, as a bare `kubectx` does, switches nothing, and neither does
+/// checking out files after `--`.
+///
+/// ```
+/// use crumbeez_lib::{environment_change, EnvironmentKind};
+///
+/// assert_eq!(
+///     environment_change("kubectl config use-context prod-eu"),
+///     Some((EnvironmentKind::KubeContext, "prod-eu".to_string()))
+/// );
+/// assert_eq!(
+///     environment_change("AWS_PROFILE=staging aws sso login"),
+///     Some((EnvironmentKind::AwsProfile, "staging".to_string()))
+/// );
+/// assert_eq!(
+///     environment_change("git switch -c feature/PROJ-142"),
+///     Some((EnvironmentKind::GitBranch, "feature/PROJ-142".to_string()))
+/// );
+/// assert_eq!(environment_change("kubens"), None);
+/// ```
+pub fn environment_change(command_line: &str) -> Option<(EnvironmentKind, String)> {
+    let mut words = command_line
+        .split_whitespace()
+        .take_while(|word| !COMMAND_SEPARATORS.contains(word))
+        .peekable();
+    let mut aws_profile = None;
+    while let Some((name, value)) = words.peek().and_then(|word| word.split_once('=')) {
+        if name == "AWS_PROFILE" {
+            aws_profile = Some(value.to_string());
+        }
+        words.next();
+    }
+    let words: Vec<&str> = words.collect();
+    let program = words.first()?.rsplit('/').next()?;
+    let (kind, switch) = ENVIRONMENT_SWITCHES.iter().find(|(_, switch)| {
+        switch[0] == program && words.len() >= switch.len() && words[1..switch.len()] == switch[1..]
+    })?;
+    let mut args = words[switch.len()..].iter();
+    if *kind == EnvironmentKind::AwsProfile {
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--profile") {
+                Some("") => aws_profile = args.next().map(|p| p.to_string()),
+                Some(profile) if profile.starts_with('=') => {
+                    aws_profile = Some(profile[1..].to_string())
+                }
+                _ => {}
+            }
+        }
+        return Some((*kind, aws_profile.unwrap_or_else(|| "default".to_string())));
+    }
+    while let Some(arg) = args.next() {
+        if *arg == "--" {
+            // Paths follow.
+            return None;
+        } else if arg.starts_with("--") && !arg.contains('=') {
+            // Its value, except git's flags, which take none before the
+            // branch.
+            if *kind != EnvironmentKind::GitBranch {
+                args.next();
+            }
+        } else if !arg.starts_with('-') {
+            // `kubectx new=old` renames a context, and `git checkout .`
+            // discards changes.
+            return (!arg.contains('=') && *arg != ".").then(|| (*kind, arg.to_string()));
+        }
+    }
+    None
+}
+
+/// Commands that are hard to undo, flagged as risky unless a project
+/// leaves them out with `risky defaults=false`.
+pub const RISKY_COMMANDS: [&str; 10] = [
+    "rm -rf",
+    "rm -fr",
+    "git push --force",
+    "git push -f",
+    "git reset --hard",
+    "terraform apply",
+    "terraform destroy",
+    "kubectl delete",
+    "DROP TABLE",
+    "DROP DATABASE",
+];
+
+/// The first of `patterns` that `command_line` matches, if any.  A pattern
+/// matches from the start of any word in the line, ignoring case and how
+/// much whitespace separates words, and `*` in it stands for anything.
+///
+/// ```
+/// use crumbeez_lib::risky_pattern;
+///
+/// let patterns = ["rm -rf", "git push*--force"];
+/// assert_eq!(risky_pattern(patterns, "sudo rm  -rf /tmp/x"), Some("rm -rf"));
+/// assert_eq!(
+///     risky_pattern(patterns, "git push origin main --force"),
+///     Some("git push*--force")
+/// );
+/// assert_eq!(risky_pattern(patterns, "confirm -rf"), None);
+/// ```
+pub fn risky_pattern<'a>(
+    patterns: impl IntoIterator<Item = &'a str>,
+    command_line: &str,
+) -> Option<&'a str> {
+    let normalize = |s: &str| {
+        s.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    };
+    let line = normalize(command_line);
+    let word_starts: Vec<usize> = std::iter::once(0)
+        .chain(line.match_indices(' ').map(|(i, _)| i + 1))
+        .collect();
+    patterns.into_iter().find(|pattern| {
+        let pattern = normalize(pattern);
+        let mut parts = pattern.split('*');
+        let first = parts.next().unwrap_or_default();
+        word_starts.iter().any(|&start| {
+            let Some(mut rest) = line[start..].strip_prefix(first) else {
+                return false;
+            };
+            parts.clone().all(|part| match rest.find(part) {
+                Some(i) => {
+                    rest = &rest[i + part.len()..];
+                    true
+                }
+                None => false,
+            })
+        })
+    })
+}
+
+/// Profiles to try in order: a project's own first, then the built-ins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileRegistry {
+    profiles: Vec<AppProfile>,
+}
+
+impl Default for ProfileRegistry {
+    fn default() -> Self {
+        Self::with_custom(&[])
+    }
+}
+
+impl ProfileRegistry {
+    /// The built-in profiles, after `custom` ones which take precedence.
+    pub fn with_custom(custom: &[AppProfile]) -> Self {
+        let builtin = BUILTIN.iter().map(|(role, patterns)| AppProfile {
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            role: *role,
+        });
+        Self {
+            profiles: custom.iter().cloned().chain(builtin).collect(),
+        }
+    }
+
+    /// The role of a pane with the given title and command; see
+    /// [`pane_matches`] for how patterns are matched.
+    pub fn role_for(&self, title: &str, command: Option<&str>) -> AppRole {
+        self.profiles
+            .iter()
+            .find(|profile| pane_matches(&profile.patterns, title, command))
+            .map_or(AppRole::Shell, |profile| profile.role)
+    }
+}
+
+/// Rewrites classified keystrokes to what they mean in the focused pane's
+/// role.  Create a new one on each focus change.
+#[derive(Debug, Default)]
+pub struct Reinterpreter {
+    role: AppRole,
+    /// A pager search prompt is open, so letters are text again.
+    searching: bool,
+}
+
+impl Reinterpreter {
+    pub fn new(role: AppRole) -> Self {
+        Self {
+            role,
+            searching: false,
+        }
+    }
+
+    pub fn role(&self) -> AppRole {
+        self.role
+    }
+
+    /// Whether typed characters are always plain text in this role, so
+    /// callers can skip [`apply`](Self::apply) for them.
+    pub fn passes_text(&self) -> bool {
+        self.role != AppRole::Pager || self.searching
+    }
+
+    pub fn apply(&mut self, event: KeystrokeEvent) -> KeystrokeEvent {
+        match self.role {
+            AppRole::Pager => self.apply_pager(event),
+            AppRole::FuzzyFinder => match &event {
+                KeystrokeEvent::Shortcut(s) if s.ctrl && !s.alt && !s.super_key => match s.key {
+                    ShortcutKey::Char('n' | 'j') => nav(NavDirection::Down),
+                    ShortcutKey::Char('p' | 'k') => nav(NavDirection::Up),
+                    _ => event,
+                },
+                _ => event,
+            },
+            AppRole::Shell | AppRole::Editor | AppRole::Sql | AppRole::Repl | AppRole::Agent => {
+                event
+            }
+        }
+    }
+
+    fn apply_pager(&mut self, event: KeystrokeEvent) -> KeystrokeEvent {
+        if self.searching {
+            if matches!(
+                event,
+                KeystrokeEvent::Escape | KeystrokeEvent::EditControl(EditControlEvent::Enter)
+            ) {
+                self.searching = false;
+            }
+            return event;
+        }
+        let KeystrokeEvent::TextTyped(ref text) = event else {
+            return event;
+        };
+        let mut chars = text.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return event;
+        };
+        match c {
+            '/' | '?' => {
+                self.searching = true;
+                event
+            }
+            'j' | 'e' => nav(NavDirection::Down),
+            'k' | 'y' => nav(NavDirection::Up),
+            ' ' | 'f' | 'd' => nav(NavDirection::PageDown),
+            'b' | 'u' => nav(NavDirection::PageUp),
+            'g' | '<' => nav(NavDirection::Home),
+            'G' | '>' => nav(NavDirection::End),
+            'q' => KeystrokeEvent::Escape,
+            _ => event,
+        }
+    }
+}
+
+/// What an agent's pane title says it's doing, with the spinner or status
+/// glyph most agents prefix it with removed, or `None` if nothing's left.
+/// Only the glyph changes while a spinner turns, so comparing the result is
+/// how repeated titles are told apart from new statuses.
+pub fn agent_status(title: &str) -> Option<&str> {
+    let status = title
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .trim_end();
+    (!status.is_empty()).then_some(status)
+}
+
+fn nav(direction: NavDirection) -> KeystrokeEvent {
+    KeystrokeEvent::Navigation(NavigationEvent {
+        direction,
+        count: 1,
+        with_shift: false,
+        with_ctrl: false,
+    })
+}
@@ -0,0 +1,237 @@
+//! Human-friendly project names.
+//!
+//! Paths like `/home/me/src/client-x/service-y` are noisy in summaries and
+//! rollups, so each project gets a name: `project_name` from its config if
+//! set, otherwise the package name from `Cargo.toml` or `package.json`, the
+//! repository name from the `origin` remote, and failing all of those, the
+//! root directory's name.  The plugin and anything else reading a project's
+//! files derive it the same way through [`ProjectName::resolve`].
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Where a [`ProjectName`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameSource {
+    /// `project_name` in `.crumbeez/config.kdl`.
+    Config,
+    /// `[package] name` in `Cargo.toml`.
+    CargoToml,
+    /// The top-level `name` in `package.json`.
+    PackageJson,
+    /// The repository in `.git/config`'s `origin` URL.
+    GitRemote,
+    /// The root directory's name.
+    Directory,
+}
+
+impl NameSource {
+    /// The sources read from a file, in order of preference.
+    pub const FILES: [Self; 3] = [Self::CargoToml, Self::PackageJson, Self::GitRemote];
+
+    /// The file this source reads, relative to the project root.
+    pub fn file(self) -> Option<&'static str> {
+        match self {
+            Self::CargoToml => Some("Cargo.toml"),
+            Self::PackageJson => Some("package.json"),
+            Self::GitRemote => Some(".git/config"),
+            Self::Config | Self::Directory => None,
+        }
+    }
+
+    /// The file's path under `root`.
+    pub fn path(self, root: &Path) -> Option<PathBuf> {
+        self.file().map(|file| root.join(file))
+    }
+
+    /// The name in the contents of this source's file, if it has one.
+    pub fn parse(self, text: &str) -> Option<String> {
+        let name = match self {
+            Self::CargoToml => cargo_package_name(text),
+            Self::PackageJson => package_json_name(text),
+            Self::GitRemote => git_origin_name(text),
+            Self::Config | Self::Directory => None,
+        }?;
+        let name = name.trim();
+        (!name.is_empty()).then(|| name.to_string())
+    }
+}
+
+impl fmt::Display for NameSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Config => "config",
+            Self::CargoToml => "Cargo.toml",
+            Self::PackageJson => "package.json",
+            Self::GitRemote => "git remote",
+            Self::Directory => "directory",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectName {
+    pub name: String,
+    pub source: NameSource,
+}
+
+impl ProjectName {
+    /// The name of the project at `root`, given its configured name and a
+    /// way to read the files under it (`None` if one doesn't exist).
+    pub fn resolve(
+        root: &Path,
+        configured: Option<&str>,
+        mut read: impl FnMut(&Path) -> Option<String>,
+    ) -> Self {
+        if let Some(name) = configured {
+            return Self {
+                name: name.to_string(),
+                source: NameSource::Config,
+            };
+        }
+        NameSource::FILES
+            .into_iter()
+            .find_map(|source| {
+                let text = read(&source.path(root)?)?;
+                Some(Self {
+                    name: source.parse(&text)?,
+                    source,
+                })
+            })
+            .unwrap_or_else(|| Self::directory(root))
+    }
+
+    /// The name when nothing better is known: the root directory's.
+    pub fn directory(root: &Path) -> Self {
+        Self {
+            name: directory_name(root),
+            source: NameSource::Directory,
+        }
+    }
+}
+
+impl fmt::Display for ProjectName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// A project's directory name, or its full path if it has none.
+pub fn directory_name(root: &Path) -> String {
+    root.file_name().map_or_else(
+        || root.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    )
+}
+
+// ── File formats ─────────────────────────────────────────────────
+
+/// `name = "..."` in the `[package]` table.  A workspace's root manifest
+/// has none.
+fn cargo_package_name(text: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() == "name" {
+            return quoted(value.trim());
+        }
+    }
+    None
+}
+
+/// A TOML or git config value in double or single quotes.
+fn quoted(value: &str) -> Option<String> {
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let rest = &value[1..];
+    Some(rest[..rest.find(quote)?].to_string())
+}
+
+/// The `name` key of the top-level object, skipping any nested ones (an
+/// `author` object has a `name` too).
+fn package_json_name(text: &str) -> Option<String> {
+    let mut chars = text.chars().peekable();
+    let mut depth = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth -= 1,
+            '"' => {
+                let key = json_string(&mut chars)?;
+                if depth != 1 || key != "name" {
+                    continue;
+                }
+                skip_whitespace(&mut chars);
+                if chars.next_if_eq(&':').is_none() {
+                    continue;
+                }
+                skip_whitespace(&mut chars);
+                chars.next_if_eq(&'"')?;
+                return json_string(&mut chars);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The rest of a JSON string whose opening quote was consumed.
+fn json_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let mut out = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(out),
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    out.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                }
+                other => out.push(other),
+            },
+            c => out.push(c),
+        }
+    }
+}
+
+fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+/// The last path segment of the `origin` remote's URL, without `.git`:
+/// `service-y` for `git@github.com:client-x/service-y.git`.
+fn git_origin_name(text: &str) -> Option<String> {
+    let mut in_origin = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_origin = line == "[remote \"origin\"]";
+            continue;
+        }
+        if !in_origin {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() != "url" {
+            continue;
+        }
+        let value = value.trim();
+        let url = quoted(value).unwrap_or_else(|| value.to_string());
+        let url = url.trim_end_matches('/');
+        let last = url.rsplit(['/', ':']).next()?;
+        return Some(last.strip_suffix(".git").unwrap_or(last).to_string());
+    }
+    None
+}
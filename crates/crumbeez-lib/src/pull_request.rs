@@ -0,0 +1,324 @@
+//! Drafting a pull request description from a branch's summaries.
+//!
+//! Every summary lists the git branches checked out while it was made (see
+//! [`Summary::branches`](crate::Summary::branches)), so the summaries of
+//! the work on a branch can be found again once it's ready for review.
+//! [`PullRequestDraft`] collates them into Markdown meant as a starting
+//! point for the description: a line on when the work happened and the
+//! issues it mentioned, then what was done, summary by summary, and the
+//! risky commands run along the way, which reviewers may want to know
+//! about.
+
+use std::fmt::Write as _;
+
+use crate::{EnvironmentKind, KeystrokeEvent, LogEntry, StoredSummary};
+
+/// Most activities listed per pane of each summary.
+pub const PR_MAX_ACTIVITIES: usize = 5;
+
+/// The git branch checked out after `entries`: the last one the
+/// [session context](crate::CONTEXT_PROBES) found or a command switched to.
+pub fn session_branch<'a>(entries: impl IntoIterator<Item = &'a LogEntry>) -> Option<String> {
+    entries
+        .into_iter()
+        .filter_map(|entry| match &entry.event {
+            KeystrokeEvent::SessionContext(values) => values.get("git-branch"),
+            KeystrokeEvent::EnvironmentChanged {
+                kind: EnvironmentKind::GitBranch,
+                target,
+            } => Some(target),
+            _ => None,
+        })
+        .last()
+        .cloned()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PullRequestDraft {
+    pub branch: String,
+    /// The first bookmark dropped on the branch, or the branch itself.
+    pub title: String,
+    /// The description, in Markdown.
+    pub body: String,
+}
+
+impl PullRequestDraft {
+    /// A draft from the `summaries` (oldest first) made on `branch`, if
+    /// there are any.
+    pub fn from_summaries<'a>(
+        branch: &str,
+        summaries: impl IntoIterator<Item = &'a StoredSummary>,
+    ) -> Option<Self> {
+        let summaries: Vec<&StoredSummary> = summaries
+            .into_iter()
+            .filter(|summary| summary.on_branch(branch))
+            .collect();
+        let first = summaries.first()?;
+
+        let title = summaries
+            .iter()
+            .find_map(|summary| summary.headline().strip_prefix("📌 "))
+            .and_then(|headline| headline.split(" · ").next())
+            .unwrap_or(branch)
+            .to_string();
+
+        let mut body = String::from("## Summary\n\n");
+        let days = (first.day(), summaries.last().and_then(|s| s.day()));
+        let _ = write!(
+            body,
+            "Work on `{}` over {} {}",
+            branch,
+            summaries.len(),
+            if summaries.len() == 1 {
+                "session"
+            } else {
+                "sessions"
+            }
+        );
+        match days {
+            (Some(start), Some(end)) if start != end => {
+                let _ = write!(body, ", {} to {}", start, end);
+            }
+            (Some(day), _) => {
+                let _ = write!(body, ", {}", day);
+            }
+            _ => {}
+        }
+        body.push_str(".\n");
+        let mut issues: Vec<&str> = Vec::new();
+        for issue in summaries.iter().flat_map(|summary| &summary.issues) {
+            if !issues.contains(&issue.as_str()) {
+                issues.push(issue);
+            }
+        }
+        if !issues.is_empty() {
+            let _ = write!(body, "\nRelated issues: {}\n", issues.join(", "));
+        }
+
+        body.push_str("\n## Changes\n");
+        for summary in &summaries {
+            let _ = write!(body, "\n### ");
+            if let Some(day) = summary.day() {
+                let _ = write!(body, "{} · ", day);
+            }
+            let _ = writeln!(body, "{}\n", summary.headline());
+            let mut listed = 0;
+            let mut skipped = 0;
+            // The risky commands get a section of their own below.
+            let mut notable = false;
+            for line in summary.outline().iter().skip(1) {
+                if let Some(activity) = line.strip_prefix("    ") {
+                    if notable {
+                        continue;
+                    } else if listed < PR_MAX_ACTIVITIES {
+                        let _ = writeln!(body, "  - {}", activity);
+                        listed += 1;
+                    } else {
+                        skipped += 1;
+                    }
+                } else {
+                    note_skipped(&mut body, skipped);
+                    (listed, skipped) = (0, 0);
+                    notable = line.trim().starts_with('⚠');
+                    if !notable {
+                        let _ = writeln!(body, "- **{}**", line.trim());
+                    }
+                }
+            }
+            note_skipped(&mut body, skipped);
+        }
+
+        let notable: Vec<(Option<String>, &str)> = summaries
+            .iter()
+            .flat_map(|summary| {
+                let day = summary.day();
+                summary
+                    .notable()
+                    .into_iter()
+                    .map(move |line| (day.clone(), line))
+            })
+            .collect();
+        if !notable.is_empty() {
+            body.push_str("\n## Notable commands\n\n");
+            for (day, line) in notable {
+                match day {
+                    Some(day) => {
+                        let _ = writeln!(body, "- {}: {}", day, line);
+                    }
+                    None => {
+                        let _ = writeln!(body, "- {}", line);
+                    }
+                }
+            }
+        }
+
+        Some(Self {
+            branch: branch.to_string(),
+            title,
+            body,
+        })
+    }
+
+    /// `pr-<branch>.md`, with any character of the branch but letters,
+    /// digits, `-`, `_`, and `.` made a `-`.
+    pub fn file_name(&self) -> String {
+        let branch: String = self
+            .branch
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        format!("pr-{}.md", branch)
+    }
+}
+
+fn note_skipped(body: &mut String, skipped: usize) {
+    if skipped > 0 {
+        let _ = writeln!(body, "  - … {} more", skipped);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::fixtures::{StreamBuilder, FIXTURE_START_MS};
+    use crate::{SummaryId, SummaryStore};
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    fn context(branch: &str) -> KeystrokeEvent {
+        KeystrokeEvent::SessionContext(BTreeMap::from([(
+            "git-branch".to_string(),
+            branch.to_string(),
+        )]))
+    }
+
+    fn switched(branch: &str) -> KeystrokeEvent {
+        KeystrokeEvent::EnvironmentChanged {
+            kind: EnvironmentKind::GitBranch,
+            target: branch.into(),
+        }
+    }
+
+    /// A summary made at `at_ms` of `events`, then typing and submitting
+    /// each of `texts`.
+    fn stored(
+        at_ms: u64,
+        branch: &str,
+        events: Vec<KeystrokeEvent>,
+        texts: &[&str],
+    ) -> StoredSummary {
+        let stream = events
+            .into_iter()
+            .fold(StreamBuilder::new(at_ms), StreamBuilder::event);
+        let mut summary = texts
+            .iter()
+            .fold(stream, |stream, text| stream.typed(text).enter())
+            .summary();
+        summary.carry_branch(Some(branch));
+        summary.id = Some(SummaryId::new(at_ms, 1));
+        StoredSummary::new(&summary.to_markdown())
+    }
+
+    #[test]
+    fn session_branch_is_the_last_seen() {
+        let entries = StreamBuilder::new(FIXTURE_START_MS)
+            .event(context("main"))
+            .typed("ls")
+            .event(switched("fix-login"))
+            .finish();
+        assert_eq!(session_branch(&entries[..2]).as_deref(), Some("main"));
+        assert_eq!(session_branch(&entries).as_deref(), Some("fix-login"));
+        assert_eq!(session_branch(&entries[1..2]), None);
+    }
+
+    #[test]
+    fn summaries_list_their_branches() {
+        let mut summary = StreamBuilder::new(FIXTURE_START_MS)
+            .event(context("main"))
+            .event(switched("fix-login"))
+            .event(switched("main"))
+            .summary();
+        assert_eq!(summary.branches, ["main", "fix-login"]);
+
+        // The branch checked out before the events comes first.
+        summary.carry_branch(Some("fix-login"));
+        assert_eq!(summary.branches, ["fix-login", "main"]);
+
+        let stored = StoredSummary::new(&summary.to_markdown());
+        assert!(stored
+            .markdown
+            .contains("\nbranches: [\"fix-login\", \"main\"]\n"));
+        assert!(stored.on_branch("main"));
+        assert!(!stored.on_branch("Main"));
+
+        let mut store = SummaryStore::default();
+        store.push_markdown(&stored.markdown);
+        store.push_markdown("## 10:00–10:30 UTC · 3 events\n");
+        assert_eq!(store.rank("branch:main"), [0]);
+        assert!(store.rank("branch:release").is_empty());
+    }
+
+    #[test]
+    fn draft_collates_the_branchs_summaries() {
+        let risky = KeystrokeEvent::RiskyCommand {
+            command: "git push --force".into(),
+            pattern: "git push --force".into(),
+            reason: None,
+        };
+        let summaries = [
+            stored(
+                FIXTURE_START_MS,
+                "fix-login",
+                vec![KeystrokeEvent::annotation("fix login loop")],
+                &["cargo test PROJ-142"],
+            ),
+            stored(
+                FIXTURE_START_MS + DAY_MS / 2,
+                "main",
+                Vec::new(),
+                &["git pull"],
+            ),
+            stored(
+                FIXTURE_START_MS + DAY_MS,
+                "fix-login",
+                vec![risky],
+                &["a", "b", "c", "d", "e", "f", "g"],
+            ),
+        ];
+
+        let draft = PullRequestDraft::from_summaries("fix-login", &summaries).unwrap();
+        assert_eq!(draft.title, "fix login loop");
+        assert_eq!(draft.file_name(), "pr-fix-login.md");
+        assert!(draft
+            .body
+            .starts_with("## Summary\n\nWork on `fix-login` over 2 sessions, 2024-03-01 to 2024-03-02.\n\nRelated issues: PROJ-142\n\n## Changes\n"));
+        assert!(!draft.body.contains("git pull"));
+        assert!(draft.body.contains("  - … 2 more\n"));
+        assert!(draft.body.ends_with(
+            "## Notable commands\n\n- 2024-03-02: `git push --force` at 09:15 UTC, matching `git push --force`\n"
+        ));
+
+        assert!(PullRequestDraft::from_summaries("release", &summaries).is_none());
+    }
+
+    #[test]
+    fn file_name_flattens_the_branch() {
+        let summaries = [stored(
+            FIXTURE_START_MS,
+            "feature/PROJ-142 x",
+            Vec::new(),
+            &["ls"],
+        )];
+        let draft = PullRequestDraft::from_summaries("feature/PROJ-142 x", &summaries).unwrap();
+        assert_eq!(draft.title, "feature/PROJ-142 x");
+        assert_eq!(draft.file_name(), "pr-feature-PROJ-142-x.md");
+    }
+}
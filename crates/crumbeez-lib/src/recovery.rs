@@ -0,0 +1,123 @@
+//! Noticing work a previous run left unsummarized.
+//!
+//! Events are summarized once activity stops, focus moves, and so on, and
+//! detaching or locking the session seals what's being typed and writes a
+//! suspension marker.  A run that was killed instead, by Zellij crashing,
+//! the machine losing power, or the plugin panicking, leaves whatever it
+//! logged since its last summary in the event log with no marker after it.
+//! On the next start the plugin summarizes these [`Remnants`] on their own,
+//! as a summary triggered by [`SummaryTrigger::Recovery`] that says when
+//! the run stopped and what was last being done, rather than folding them
+//! into the first summary of the new run.  Quitting Zellij without
+//! detaching leaves the log the same way, and is treated the same.
+//!
+//! What was typed after the last save is lost either way: only what
+//! reached the log can be recovered.
+//!
+//! [`SummaryTrigger::Recovery`]: crate::SummaryTrigger::Recovery
+
+use crate::{KeystrokeEvent, LogEntry};
+
+/// Events a run left unsummarized when it stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Remnants {
+    pub events: usize,
+    /// When the first and last of them were logged.
+    pub first_ms: u64,
+    pub last_ms: u64,
+}
+
+impl Remnants {
+    /// The remnants among the `unconsumed` entries of a log just loaded, if
+    /// there are any and the run that logged them didn't end suspended.
+    pub fn find<'a>(unconsumed: impl IntoIterator<Item = &'a LogEntry>) -> Option<Self> {
+        let mut remnants: Option<Self> = None;
+        let mut suspended = false;
+        for entry in unconsumed {
+            suspended = matches!(entry.event, KeystrokeEvent::SessionSuspended(_));
+            let remnants = remnants.get_or_insert(Self {
+                events: 0,
+                first_ms: entry.timestamp_ms,
+                last_ms: entry.timestamp_ms,
+            });
+            remnants.events += 1;
+            remnants.last_ms = entry.timestamp_ms;
+        }
+        remnants.filter(|_| !suspended)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{StreamBuilder, FIXTURE_START_MS};
+    use crate::{StoredSummary, SummaryTrigger, SuspendReason};
+
+    const MINUTE_MS: u64 = 60 * 1000;
+
+    /// Ran `cargo test`, then started a commit five minutes in.
+    fn stopped_mid_commit() -> StreamBuilder {
+        StreamBuilder::new(FIXTURE_START_MS)
+            .typed("cargo test")
+            .at_ms(MINUTE_MS)
+            .enter()
+            .at_ms(5 * MINUTE_MS)
+            .typed("git commit -m wip")
+    }
+
+    #[test]
+    fn a_run_that_stopped_mid_work_leaves_remnants() {
+        assert_eq!(
+            Remnants::find(&stopped_mid_commit().finish()),
+            Some(Remnants {
+                events: 3,
+                first_ms: FIXTURE_START_MS,
+                last_ms: FIXTURE_START_MS + 5 * MINUTE_MS,
+            })
+        );
+    }
+
+    #[test]
+    fn nothing_unsummarized_or_a_suspended_run_leaves_none() {
+        assert_eq!(Remnants::find(&[]), None);
+        let detached = StreamBuilder::new(FIXTURE_START_MS)
+            .typed("cargo test")
+            .event(KeystrokeEvent::SessionSuspended(SuspendReason::Detached))
+            .finish();
+        assert_eq!(Remnants::find(&detached), None);
+        // Typing after a resume that never got summarized is a remnant again.
+        let resumed = StreamBuilder::new(FIXTURE_START_MS)
+            .event(KeystrokeEvent::SessionSuspended(SuspendReason::Locked))
+            .event(KeystrokeEvent::SessionResumed)
+            .typed("ls")
+            .finish();
+        assert_eq!(Remnants::find(&resumed).map(|r| r.events), Some(3));
+    }
+
+    #[test]
+    fn recovered_summaries_say_when_the_run_stopped_and_what_was_in_flight() {
+        let mut summary = stopped_mid_commit().summary();
+        summary.trigger = Some(SummaryTrigger::Recovery);
+        let markdown = summary.to_markdown();
+        assert!(markdown.contains("trigger: recovery\n"), "{}", markdown);
+        assert!(
+            markdown.contains("\n## ⚠ recovered · 09:15–09:20 UTC · "),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains(
+                "**⚠ crumbeez stopped at 09:20 UTC before summarizing this; \
+                 last in progress: typed `git commit -m wip`**"
+            ),
+            "{}",
+            markdown
+        );
+        let stored = StoredSummary::new(&markdown);
+        assert_eq!(stored.trigger, Some(SummaryTrigger::Recovery));
+        assert!(stored.headline().starts_with("⚠ recovered · "));
+
+        summary.trigger = Some(SummaryTrigger::Inactivity);
+        assert!(!summary.to_markdown().contains('⚠'));
+    }
+}
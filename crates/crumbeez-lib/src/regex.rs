@@ -0,0 +1,506 @@
+//! A small backtracking regular expression matcher for config rules.
+//!
+//! Supports the everyday subset: literals, `.`, classes like `[a-z0-9_]`
+//! and `[^,]`, the escapes `\d`, `\w`, `\s` (and their negations) and `\b`,
+//! anchors `^` and `$`, groups `( )` and `(?: )` with `|` alternatives, and
+//! the quantifiers `*`, `+`, `?`, `{n}`, `{n,}` and `{n,m}`, each lazy with
+//! a trailing `?`.  A leading `(?i)` ignores case.  There are no captures,
+//! backreferences or lookaround; rules only need to find text to replace.
+//!
+//! Matching backtracks, so a pathological pattern can take a while on long
+//! text, but the text it runs on (one summary line, one context blob) is
+//! short.
+
+use std::borrow::Cow;
+use std::fmt;
+
+/// Most repetitions a `{n,m}` bound may give.
+const MAX_REPEAT: usize = 1000;
+
+/// A compiled pattern.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    source: String,
+    ignore_case: bool,
+    /// Alternatives, each a sequence of nodes.
+    alternatives: Vec<Vec<Node>>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    WordBoundary,
+    Group(Vec<Vec<Node>>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    /// `\d`, or `\D` when negated.
+    Digit {
+        negated: bool,
+    },
+    /// `\w`, or `\W` when negated.
+    Word {
+        negated: bool,
+    },
+    /// `\s`, or `\S` when negated.
+    Space {
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexError {
+    /// Character offset in the pattern.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at character {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, RegexError> {
+        let (ignore_case, body, offset) = match pattern.strip_prefix("(?i)") {
+            Some(body) => (true, body, 4),
+            None => (false, pattern, 0),
+        };
+        let mut parser = Parser {
+            chars: body.chars().collect(),
+            pos: 0,
+        };
+        let alternatives = parser.alternation().map_err(|mut err| {
+            err.position += offset;
+            err
+        })?;
+        if parser.pos < parser.chars.len() {
+            return Err(RegexError {
+                position: parser.pos + offset,
+                message: "unmatched ')'".to_string(),
+            });
+        }
+        Ok(Self {
+            source: pattern.to_string(),
+            ignore_case,
+            alternatives,
+        })
+    }
+
+    /// The pattern as written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        self.find_at(&chars, 0).is_some()
+    }
+
+    /// `text` with every match replaced by `replacement`, taken literally.
+    pub fn replace_all<'a>(&self, text: &'a str, replacement: &str) -> Cow<'a, str> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut out = String::new();
+        let mut changed = false;
+        let mut copied = 0;
+        let mut at = 0;
+        while let Some((start, end)) = self.find_at(&chars, at) {
+            changed = true;
+            out.extend(&chars[copied..start]);
+            out.push_str(replacement);
+            copied = end;
+            // An empty match still moves on.
+            at = if end > start { end } else { end + 1 };
+            if at > chars.len() {
+                break;
+            }
+        }
+        if !changed {
+            return Cow::Borrowed(text);
+        }
+        out.extend(&chars[copied..]);
+        Cow::Owned(out)
+    }
+
+    /// The leftmost match at or after `from`, as character offsets.
+    fn find_at(&self, text: &[char], from: usize) -> Option<(usize, usize)> {
+        (from..=text.len()).find_map(|start| {
+            let mut end = None;
+            self.alternatives.iter().any(|alternative| {
+                self.sequence(alternative, text, start, &mut |at| {
+                    end = Some(at);
+                    true
+                })
+            });
+            end.map(|end| (start, end))
+        })
+    }
+
+    // ── Matching ─────────────────────────────────────────────────
+
+    /// Match `nodes` at `at`, then hand the end to `next`, backtracking
+    /// until `next` accepts.
+    fn sequence(
+        &self,
+        nodes: &[Node],
+        text: &[char],
+        at: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        let Some((first, rest)) = nodes.split_first() else {
+            return next(at);
+        };
+        self.node(first, text, at, &mut |after| {
+            self.sequence(rest, text, after, next)
+        })
+    }
+
+    fn node(
+        &self,
+        node: &Node,
+        text: &[char],
+        at: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        let here = text.get(at).copied();
+        match node {
+            Node::Char(c) => here.is_some_and(|t| self.same(*c, t)) && next(at + 1),
+            Node::Any => here.is_some_and(|t| t != '\n') && next(at + 1),
+            Node::Class { items, negated } => {
+                here.is_some_and(|t| self.in_class(items, t) != *negated) && next(at + 1)
+            }
+            Node::Start => at == 0 && next(at),
+            Node::End => at == text.len() && next(at),
+            Node::WordBoundary => {
+                let before = at > 0 && is_word(text[at - 1]);
+                let after = here.is_some_and(is_word);
+                before != after && next(at)
+            }
+            Node::Group(alternatives) => alternatives
+                .iter()
+                .any(|alternative| self.sequence(alternative, text, at, next)),
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => self.repeat(node, (*min, *max, *greedy), 0, text, at, next),
+        }
+    }
+
+    /// Match `node` again after `count` repetitions, or stop.
+    fn repeat(
+        &self,
+        node: &Node,
+        bounds: (usize, Option<usize>, bool),
+        count: usize,
+        text: &[char],
+        at: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        let (min, max, greedy) = bounds;
+        let may_stop = count >= min;
+        let may_go_on = max.is_none_or(|max| count < max);
+        if !greedy && may_stop && next(at) {
+            return true;
+        }
+        if may_go_on
+            && self.node(node, text, at, &mut |after| {
+                // An empty repetition past the minimum would loop forever.
+                (after > at || count < min)
+                    && self.repeat(node, bounds, count + 1, text, after, next)
+            })
+        {
+            return true;
+        }
+        greedy && may_stop && next(at)
+    }
+
+    fn same(&self, a: char, b: char) -> bool {
+        a == b || self.ignore_case && a.to_lowercase().eq(b.to_lowercase())
+    }
+
+    fn in_class(&self, items: &[ClassItem], c: char) -> bool {
+        let folded = |c: char| {
+            let mut forms = vec![c];
+            if self.ignore_case {
+                forms.extend(c.to_lowercase());
+                forms.extend(c.to_uppercase());
+            }
+            forms
+        };
+        items.iter().any(|item| match *item {
+            ClassItem::Range(lo, hi) => folded(c).iter().any(|c| (lo..=hi).contains(c)),
+            ClassItem::Digit { negated } => c.is_ascii_digit() != negated,
+            ClassItem::Word { negated } => is_word(c) != negated,
+            ClassItem::Space { negated } => c.is_whitespace() != negated,
+        })
+    }
+}
+
+impl PartialEq for Regex {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// ── Parsing ──────────────────────────────────────────────────────
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, message: impl Into<String>) -> RegexError {
+        RegexError {
+            position: self.pos,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let matched = self.peek() == Some(c);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    /// `a|b|c`, up to an unmatched `)` or the end.
+    fn alternation(&mut self) -> Result<Vec<Vec<Node>>, RegexError> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, RegexError> {
+        let start = self.pos;
+        let Some(c) = self.peek() else {
+            return Err(self.error("unexpected end of pattern"));
+        };
+        self.pos += 1;
+        Ok(match c {
+            '(' => {
+                if self.eat('?') && !self.eat(':') {
+                    self.pos = start + 1;
+                    return Err(self.error("only (?: ) groups and a leading (?i) are supported"));
+                }
+                let alternatives = self.alternation()?;
+                if !self.eat(')') {
+                    return Err(RegexError {
+                        position: start,
+                        message: "unclosed '('".to_string(),
+                    });
+                }
+                Node::Group(alternatives)
+            }
+            '[' => self.class(start)?,
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '\\' => self.escape()?,
+            '*' | '+' | '?' => {
+                self.pos = start;
+                return Err(self.error(format!("nothing before '{}' to repeat", c)));
+            }
+            c => Node::Char(c),
+        })
+    }
+
+    /// `atom` with any quantifier after it applied.
+    fn quantified(&mut self, atom: Node) -> Result<Node, RegexError> {
+        let start = self.pos;
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.bounds() {
+                Some(bounds) => bounds?,
+                // Not a quantifier: a literal `{`.
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        if self.pos == start {
+            self.pos += 1;
+        }
+        if matches!(atom, Node::Start | Node::End | Node::WordBoundary) {
+            self.pos = start;
+            return Err(self.error("an anchor can't be repeated"));
+        }
+        let greedy = !self.eat('?');
+        if matches!(self.peek(), Some('*' | '+' | '?')) {
+            return Err(self.error("a quantifier can't be repeated"));
+        }
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// `{n}`, `{n,}` or `{n,m}` at the current position, consumed; `None`
+    /// (consuming nothing) if the `{` doesn't start one.
+    fn bounds(&mut self) -> Option<Result<(usize, Option<usize>), RegexError>> {
+        let start = self.pos;
+        let end = self.chars[start..].iter().position(|&c| c == '}')? + start;
+        let inner: String = self.chars[start + 1..end].iter().collect();
+        let (min, max) = match inner.split_once(',') {
+            Some((min, "")) => (min.parse().ok()?, None),
+            Some((min, max)) => (min.parse().ok()?, Some(max.parse().ok()?)),
+            None => {
+                let n = inner.parse().ok()?;
+                (n, Some(n))
+            }
+        };
+        self.pos = end + 1;
+        if max.is_some_and(|max| max < min) {
+            return Some(Err(RegexError {
+                position: start,
+                message: format!("{{{}}} has its bounds the wrong way round", inner),
+            }));
+        }
+        if max.unwrap_or(min) > MAX_REPEAT {
+            return Some(Err(RegexError {
+                position: start,
+                message: format!("repeat counts go up to {}", MAX_REPEAT),
+            }));
+        }
+        Some(Ok((min, max)))
+    }
+
+    /// After `\`.
+    fn escape(&mut self) -> Result<Node, RegexError> {
+        if self.eat('b') {
+            return Ok(Node::WordBoundary);
+        }
+        Ok(match self.class_escape()? {
+            Ok(item) => Node::Class {
+                items: vec![item],
+                negated: false,
+            },
+            Err(c) => Node::Char(c),
+        })
+    }
+
+    /// After `\`, outside a class or in one: a class like `\d`, or an
+    /// escaped character.
+    fn class_escape(&mut self) -> Result<Result<ClassItem, char>, RegexError> {
+        let Some(c) = self.peek() else {
+            return Err(self.error("'\\' at the end of the pattern"));
+        };
+        self.pos += 1;
+        Ok(match c {
+            'd' | 'D' => Ok(ClassItem::Digit { negated: c == 'D' }),
+            'w' | 'W' => Ok(ClassItem::Word { negated: c == 'W' }),
+            's' | 'S' => Ok(ClassItem::Space { negated: c == 'S' }),
+            'n' => Err('\n'),
+            't' => Err('\t'),
+            'r' => Err('\r'),
+            c if c.is_alphanumeric() => {
+                self.pos -= 1;
+                return Err(self.error(format!("unknown escape '\\{}'", c)));
+            }
+            c => Err(c),
+        })
+    }
+
+    /// After `[`, which is at `start`.
+    fn class(&mut self, start: usize) -> Result<Node, RegexError> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(RegexError {
+                    position: start,
+                    message: "unclosed '['".to_string(),
+                });
+            };
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let lo = if c == '\\' {
+                match self.class_escape()? {
+                    Ok(item) => {
+                        items.push(item);
+                        continue;
+                    }
+                    Err(c) => c,
+                }
+            } else {
+                c
+            };
+            let is_range =
+                self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']');
+            if !is_range {
+                items.push(ClassItem::Range(lo, lo));
+                continue;
+            }
+            self.pos += 1;
+            let mut hi = self.chars[self.pos];
+            self.pos += 1;
+            if hi == '\\' {
+                hi = match self.class_escape()? {
+                    Err(c) => c,
+                    Ok(_) => return Err(self.error("a range can't end in a class")),
+                };
+            }
+            if hi < lo {
+                return Err(self.error(format!("range {}-{} is backwards", lo, hi)));
+            }
+            items.push(ClassItem::Range(lo, hi));
+        }
+        Ok(Node::Class { items, negated })
+    }
+}
@@ -0,0 +1,91 @@
+//! Grouping REPL input into whole submissions.
+//!
+//! In a [`AppRole::Repl`] or [`AppRole::Sql`] pane, Enter doesn't always run
+//! anything: an open bracket, a trailing backslash, a Python block header,
+//! or a SQL statement without its `;` all make the REPL ask for another
+//! line.  [`needs_continuation`] makes the same call from the text so far,
+//! so a multi-line function definition is logged as one
+//! [`KeystrokeEvent::ReplInput`](crate::KeystrokeEvent::ReplInput) rather
+//! than a line at a time.
+
+use crate::AppRole;
+
+/// The language a REPL pane speaks, from its command's executable, or
+/// `None` if the role isn't a REPL.
+pub fn repl_language(role: AppRole, command: Option<&str>) -> Option<String> {
+    if !matches!(role, AppRole::Repl | AppRole::Sql) {
+        return None;
+    }
+    if role == AppRole::Sql {
+        return Some("sql".to_string());
+    }
+    let program = command
+        .and_then(|cmd| cmd.split_whitespace().next())
+        .map(|exe| exe.rsplit('/').next().unwrap_or(exe))
+        .unwrap_or("repl");
+    let language = match program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+        "python" | "ipython" | "bpython" | "ptpython" => "python",
+        "node" | "deno" | "bun" => "javascript",
+        "irb" | "pry" => "ruby",
+        "ghci" => "haskell",
+        "iex" => "elixir",
+        "julia" => "julia",
+        "lua" | "luajit" => "lua",
+        _ => program,
+    };
+    Some(language.to_string())
+}
+
+/// Whether a REPL for `language` would ask for another line after `code`.
+///
+/// Brackets are counted outside of string literals; a trailing backslash
+/// always continues.  Python also continues after a line ending in `:`
+/// until a blank line, and SQL until the statement ends in `;` (psql's
+/// backslash commands excepted).
+pub fn needs_continuation(language: &str, code: &str) -> bool {
+    let last_line = code.lines().last().unwrap_or("");
+    if last_line.ends_with('\\') && !last_line.ends_with("\\\\") {
+        return true;
+    }
+    if open_brackets(code) > 0 {
+        return true;
+    }
+    match language {
+        "python" => {
+            // An indented block ends with an empty line.
+            let in_block = code.lines().any(|l| l.trim_end().ends_with(':'));
+            in_block && !code.ends_with('\n') && !last_line.trim().is_empty()
+        }
+        "sql" => {
+            let trimmed = code.trim();
+            !trimmed.is_empty() && !trimmed.starts_with('\\') && !trimmed.ends_with(';')
+        }
+        _ => false,
+    }
+}
+
+/// Brackets opened and not yet closed, ignoring quoted text.
+fn open_brackets(code: &str) -> usize {
+    let mut depth: usize = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for c in code.chars() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' | '`' => quote = Some(c),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth
+}
@@ -0,0 +1,136 @@
+//! Reconstructing how text appeared on screen, for replaying a typing
+//! session.
+//!
+//! The log keeps typed text as whole runs, stamped when the run ended, so
+//! the moment each character was typed is gone.  [`reconstruct`] spreads a
+//! run's characters evenly over the time before its timestamp — back to the
+//! previous event, but no further than [`TYPING_INTERVAL_MS`] per character
+//! — so every run still finishes exactly when it was logged.
+//!
+//! Each pane keeps its own line in progress: focusing a pane again brings
+//! back what had been typed there and not yet submitted.
+
+use crate::{EditControlEvent, KeystrokeEvent, LogEntry, PaneFocusedEvent};
+
+/// Longest time assumed between two typed characters: a brisk 150 words
+/// per minute.
+pub const TYPING_INTERVAL_MS: u64 = 80;
+
+/// One change to the screen.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameChange {
+    /// Focus moved to a pane; `line` is its unsubmitted text.
+    Focus {
+        pane: PaneFocusedEvent,
+        line: String,
+    },
+    /// A character typed at the cursor.  A `'\n'` is a line break within
+    /// multi-line input, not a submission.
+    Char(char),
+    /// Characters removed before the cursor.
+    Erase(usize),
+    /// Enter: the line was run or submitted.
+    Submit,
+    /// A bookmark.
+    Bookmark(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub timestamp_ms: u64,
+    pub change: FrameChange,
+}
+
+/// The frames for `entries` (oldest first) from `start_ms` up to and
+/// including `end_ms`.
+pub fn reconstruct<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    start_ms: u64,
+    end_ms: u64,
+) -> Vec<Frame> {
+    let mut frames = Vec::new();
+    // Lines in progress by pane, with the focused pane's last.
+    let mut lines: Vec<(Option<PaneFocusedEvent>, String)> = vec![(None, String::new())];
+    let mut previous_ms: Option<u64> = None;
+
+    for entry in entries {
+        let at = entry.timestamp_ms;
+        if at > end_ms {
+            break;
+        }
+        // Events before the range still set up the panes' lines.
+        let shown = at >= start_ms;
+        let since = previous_ms.unwrap_or(at).max(start_ms);
+        previous_ms = Some(at);
+        let mut push = |timestamp_ms, change| {
+            if shown {
+                frames.push(Frame {
+                    timestamp_ms,
+                    change,
+                });
+            }
+        };
+        let line = &mut lines.last_mut().expect("never empty").1;
+
+        let (text, submitted) = match &entry.event {
+            KeystrokeEvent::PaneFocused(pane) => {
+                let index = lines.iter().position(|(p, _)| p.as_ref() == Some(pane));
+                let focused = match index {
+                    Some(index) => lines.remove(index),
+                    None => (Some(pane.clone()), String::new()),
+                };
+                push(
+                    at,
+                    FrameChange::Focus {
+                        pane: pane.clone(),
+                        line: focused.1.clone(),
+                    },
+                );
+                lines.push(focused);
+                continue;
+            }
+            KeystrokeEvent::TextTyped(text) | KeystrokeEvent::LongText { preview: text, .. } => {
+                (text.as_str(), false)
+            }
+            KeystrokeEvent::PromptToAgent(text)
+            | KeystrokeEvent::FuzzySelected { query: text, .. }
+            | KeystrokeEvent::ReplInput { code: text, .. } => (text.as_str(), true),
+            KeystrokeEvent::EditControl(EditControlEvent::Enter) => ("", true),
+            KeystrokeEvent::EditControl(EditControlEvent::Backspace { count }) => {
+                let erased = (*count).min(line.chars().count());
+                for _ in 0..erased {
+                    line.pop();
+                }
+                if erased > 0 {
+                    push(at, FrameChange::Erase(erased));
+                }
+                continue;
+            }
+            KeystrokeEvent::Annotation(name) => {
+                push(at, FrameChange::Bookmark(name.clone()));
+                continue;
+            }
+            _ => continue,
+        };
+
+        let chars = text.chars().count() as u64;
+        let first_ms = at
+            .saturating_sub(chars.saturating_sub(1) * TYPING_INTERVAL_MS)
+            .max(since)
+            .min(at);
+        for (i, c) in text.chars().enumerate() {
+            // Evenly from `first_ms`, with the last character at `at`.
+            let timestamp_ms = match chars {
+                1 => at,
+                _ => first_ms + (at - first_ms) * i as u64 / (chars - 1),
+            };
+            push(timestamp_ms, FrameChange::Char(c));
+            line.push(c);
+        }
+        if submitted {
+            push(at, FrameChange::Submit);
+            line.clear();
+        }
+    }
+    frames
+}
@@ -0,0 +1,360 @@
+//! Weekly rollups across every project the plugin has seen.
+//!
+//! Each plugin instance only knows its own project, so per-day totals are
+//! recorded in a shared [`SummaryIndex`] under `$XDG_DATA_HOME/crumbeez`.
+//! The index is append-only — one [`DayRecord`] per line, the latest record
+//! for a project and day superseding earlier ones — so concurrent sessions
+//! in different projects can't clobber each other's records.  A
+//! [`WeeklyRollup`] is then computed from the index alone, without reading
+//! any project's event log.
+
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crate::kdl::{self, KdlNode};
+use crate::project::directory_name;
+use crate::summary::{days_from_ymd, format_ymd};
+use crate::work_blocks::command_counts;
+use crate::{
+    daily_summary_path, split_work_blocks, EntryIter, HumanDuration, LogEntry, DEFAULT_IDLE_GAP_MS,
+};
+
+const MS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// Most commands kept per day record, and listed in a rollup.
+const TOP_COMMANDS: usize = 10;
+
+/// One project's activity on one (UTC) day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayRecord {
+    /// `YYYY-MM-DD`.
+    pub day: String,
+    pub root: PathBuf,
+    /// The project's [name](crate::ProjectName), if it was known when the
+    /// record was made.
+    pub name: Option<String>,
+    /// The project's data directory, `<root>/.crumbeez` or a fallback.
+    pub dir: PathBuf,
+    /// Total length of the day's work blocks.
+    pub active_ms: u64,
+    pub events: usize,
+    /// Most frequent commands and their counts.
+    pub commands: Vec<(String, usize)>,
+}
+
+impl DayRecord {
+    /// Record the entries (oldest first) falling on the UTC day containing
+    /// `day_ms`.
+    pub fn from_entries<'a>(
+        root: &Path,
+        dir: &Path,
+        entries: impl IntoIterator<Item = &'a LogEntry>,
+        day_ms: u64,
+    ) -> Self {
+        let start = day_ms - day_ms % MS_PER_DAY;
+        let blocks = split_work_blocks(
+            entries.into_iter().between(start, start + MS_PER_DAY),
+            DEFAULT_IDLE_GAP_MS,
+        );
+        Self {
+            day: format_ymd(day_ms),
+            root: root.to_path_buf(),
+            name: None,
+            dir: dir.to_path_buf(),
+            active_ms: blocks.iter().map(|b| b.duration_ms()).sum(),
+            events: blocks.iter().map(|b| b.summary.events_consumed).sum(),
+            commands: command_counts(&blocks)
+                .into_iter()
+                .take(TOP_COMMANDS)
+                .map(|(c, n)| (c.to_string(), n))
+                .collect(),
+        }
+    }
+
+    /// The project's name, or its directory's for records without one.
+    pub fn project_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| directory_name(&self.root))
+    }
+
+    /// The day's summary file, `summaries/YYYY-MM-DD.md`.
+    pub fn daily_file(&self) -> PathBuf {
+        daily_summary_path(&self.dir, &self.day)
+    }
+
+    /// One line of the index file, newline included.
+    pub fn to_kdl(&self) -> String {
+        let mut out = format!(
+            "day {} root={} dir={} active_ms={} events={}",
+            kdl::quote(&self.day),
+            kdl::quote(&self.root.to_string_lossy()),
+            kdl::quote(&self.dir.to_string_lossy()),
+            self.active_ms,
+            self.events
+        );
+        if let Some(name) = &self.name {
+            let _ = write!(out, " name={}", kdl::quote(name));
+        }
+        if !self.commands.is_empty() {
+            let commands: Vec<String> = self
+                .commands
+                .iter()
+                .map(|(c, n)| format!("command {} count={}", kdl::quote(c), n))
+                .collect();
+            let _ = write!(out, " {{ {} }}", commands.join("; "));
+        }
+        out.push('\n');
+        out
+    }
+
+    fn from_node(node: &KdlNode) -> Option<Self> {
+        if node.name != "day" {
+            return None;
+        }
+        let day = node.args.first()?.as_str()?.to_string();
+        days_from_ymd(&day)?;
+        let int = |key| node.prop(key).and_then(|v| v.as_i64()).map(|n| n.max(0));
+        Some(Self {
+            day,
+            root: node.prop("root")?.as_str()?.into(),
+            name: node
+                .prop("name")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            dir: node.prop("dir")?.as_str()?.into(),
+            active_ms: int("active_ms")? as u64,
+            events: int("events")? as usize,
+            commands: node
+                .children
+                .iter()
+                .filter(|c| c.name == "command")
+                .filter_map(|c| {
+                    let text = c.args.first()?.as_str()?.to_string();
+                    let count = c.prop("count")?.as_i64()?.max(0) as usize;
+                    Some((text, count))
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Start (UTC) of a `YYYY-MM-DD` day in milliseconds, or `None` if `date`
+/// isn't a date on or after the epoch.
+pub fn day_start_ms(date: &str) -> Option<u64> {
+    let days = u64::try_from(days_from_ymd(date)?).ok()?;
+    Some(days * MS_PER_DAY)
+}
+
+/// The cross-project summary index: the latest [`DayRecord`] for each
+/// project and day.
+#[derive(Debug, Default)]
+pub struct SummaryIndex {
+    pub records: Vec<DayRecord>,
+    /// Lines that couldn't be parsed, typically one cut short by a crash.
+    pub skipped_lines: usize,
+}
+
+impl SummaryIndex {
+    /// Parse an index file.  Lines are independent, so a damaged one is
+    /// skipped rather than failing the whole index.
+    pub fn parse(text: &str) -> Self {
+        let mut index = Self::default();
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let record = kdl::parse(line)
+                .ok()
+                .and_then(|nodes| nodes.first().and_then(DayRecord::from_node));
+            match record {
+                Some(record) => index.insert(record),
+                None => index.skipped_lines += 1,
+            }
+        }
+        index
+    }
+
+    /// Add `record`, replacing any earlier one for the same project and day.
+    pub fn insert(&mut self, record: DayRecord) {
+        match self
+            .records
+            .iter_mut()
+            .find(|r| r.day == record.day && r.root == record.root)
+        {
+            Some(existing) => *existing = record,
+            None => self.records.push(record),
+        }
+    }
+
+    /// The whole index, one line per record, for rewriting the file without
+    /// superseded records.
+    pub fn to_kdl(&self) -> String {
+        self.records.iter().map(DayRecord::to_kdl).collect()
+    }
+}
+
+/// One project's share of a week.
+#[derive(Debug)]
+pub struct ProjectWeek {
+    pub root: PathBuf,
+    /// The name from the project's latest record.
+    pub name: String,
+    pub active_ms: u64,
+    pub events: usize,
+    /// Days with any activity.
+    pub days: usize,
+}
+
+#[derive(Debug)]
+pub struct WeeklyRollup {
+    /// Monday the week starts on, `YYYY-MM-DD`.
+    pub week_start: String,
+    /// Projects by time spent, most first.
+    pub projects: Vec<ProjectWeek>,
+    /// Most frequent commands across all projects.
+    pub commands: Vec<(String, usize)>,
+    /// The week's records, oldest first, whose daily files the rollup
+    /// covers.
+    pub days: Vec<DayRecord>,
+}
+
+impl WeeklyRollup {
+    /// Roll up the Monday-to-Sunday week (UTC) containing `day_ms`.
+    pub fn from_index(index: &SummaryIndex, day_ms: u64) -> Self {
+        let day = (day_ms / MS_PER_DAY) as i64;
+        // 1970-01-01 was a Thursday.
+        let monday = day - (day + 3).rem_euclid(7);
+        let mut days: Vec<DayRecord> = index
+            .records
+            .iter()
+            .filter(|r| days_from_ymd(&r.day).is_some_and(|d| (monday..monday + 7).contains(&d)))
+            .cloned()
+            .collect();
+        days.sort_by(|a, b| a.day.cmp(&b.day).then_with(|| a.root.cmp(&b.root)));
+
+        let mut projects: Vec<ProjectWeek> = Vec::new();
+        let mut commands: Vec<(String, usize)> = Vec::new();
+        for record in &days {
+            match projects.iter_mut().find(|p| p.root == record.root) {
+                Some(project) => {
+                    project.active_ms += record.active_ms;
+                    project.events += record.events;
+                    project.days += 1;
+                    if record.name.is_some() {
+                        project.name = record.project_name();
+                    }
+                }
+                None => projects.push(ProjectWeek {
+                    root: record.root.clone(),
+                    name: record.project_name(),
+                    active_ms: record.active_ms,
+                    events: record.events,
+                    days: 1,
+                }),
+            }
+            for (command, count) in &record.commands {
+                match commands.iter_mut().find(|(c, _)| c == command) {
+                    Some((_, n)) => *n += count,
+                    None => commands.push((command.clone(), *count)),
+                }
+            }
+        }
+        projects.sort_by_key(|p| Reverse(p.active_ms));
+        commands.sort_by_key(|(_, n)| Reverse(*n));
+        commands.truncate(TOP_COMMANDS);
+
+        Self {
+            week_start: format_ymd(monday as u64 * MS_PER_DAY),
+            projects,
+            commands,
+            days,
+        }
+    }
+
+    /// Daily summary files the rollup covers from days before the one
+    /// containing `now_ms`; today's is still being written to.
+    pub fn past_daily_files(&self, now_ms: u64) -> Vec<PathBuf> {
+        let today = format_ymd(now_ms);
+        self.days
+            .iter()
+            .filter(|r| r.day < today)
+            .map(DayRecord::daily_file)
+            .collect()
+    }
+
+    /// `weekly-YYYY-MM-DD.md`, named for the week's Monday.
+    pub fn file_name(&self) -> String {
+        format!("weekly-{}.md", self.week_start)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Week of {}\n\n", self.week_start);
+        if self.days.is_empty() {
+            out.push_str("No recorded activity.\n");
+            return out;
+        }
+
+        let total_ms: u64 = self.projects.iter().map(|p| p.active_ms).sum();
+        out.push_str("## Projects\n\n| Project | Time | Share | Days | Events |\n|---|---:|---:|---:|---:|\n");
+        for project in &self.projects {
+            let share = (project.active_ms * 100).checked_div(total_ms).unwrap_or(0);
+            let _ = writeln!(
+                out,
+                "| {} | {} | {}% | {} | {} |",
+                project.name,
+                HumanDuration::from_ms(project.active_ms),
+                share,
+                project.days,
+                project.events
+            );
+        }
+        let _ = writeln!(out, "\nTotal: {}", HumanDuration::from_ms(total_ms));
+
+        if !self.commands.is_empty() {
+            out.push_str("\n## Top commands\n\n");
+            for (command, count) in &self.commands {
+                let _ = writeln!(out, "- `{}` ×{}", command, count);
+            }
+        }
+
+        out.push_str("\n## Days\n\n");
+        for record in &self.days {
+            let _ = writeln!(
+                out,
+                "- {} · {} · {} · {}",
+                record.day,
+                record.project_name(),
+                HumanDuration::from_ms(record.active_ms),
+                record.daily_file().display()
+            );
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollup_uses_the_latest_name() {
+        let index = SummaryIndex::parse(concat!(
+            "day \"2024-02-26\" root=\"/src/svc\" dir=\"/src/svc/.crumbeez\" active_ms=3600000 events=10\n",
+            "day \"2024-02-27\" root=\"/src/svc\" dir=\"/src/svc/.crumbeez\" active_ms=3600000 events=10 name=\"Billing API\"\n",
+        ));
+        assert_eq!(index.records[0].project_name(), "svc");
+        assert_eq!(
+            SummaryIndex::parse(&index.to_kdl()).records[1]
+                .name
+                .as_deref(),
+            Some("Billing API")
+        );
+
+        // 2024-02-28
+        let rollup = WeeklyRollup::from_index(&index, 1_709_078_400_000);
+        assert_eq!(rollup.projects.len(), 1);
+        assert_eq!(rollup.projects[0].name, "Billing API");
+        assert!(rollup
+            .to_markdown()
+            .contains("| Billing API | 2 h 00 min |"));
+    }
+}
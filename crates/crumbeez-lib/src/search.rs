@@ -0,0 +1,51 @@
+//! Finding text in events as they're displayed, for searching the log.
+//!
+//! A search matches an event's `Display` form, so what's found is what the
+//! plugin pane shows: `nav ←` matches left arrows, `focus → [code` panes
+//! focused in the tab `code`.  Case is ignored, as in the summary browser.
+
+use std::ops::Range;
+
+use crate::KeystrokeEvent;
+
+/// Byte ranges of the places `query` occurs in `text`, ignoring case, for
+/// highlighting.  Matches don't overlap; an empty query matches nowhere.
+pub fn find_matches(text: &str, query: &str) -> Vec<Range<usize>> {
+    let mut found = Vec::new();
+    if query.is_empty() {
+        return found;
+    }
+    let mut start = 0;
+    while start < text.len() {
+        match match_len(&text[start..], query) {
+            Some(len) => {
+                found.push(start..start + len);
+                start += len;
+            }
+            None => {
+                start += text[start..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+    found
+}
+
+/// How many bytes of `text` match `query` from its start, if it does.
+fn match_len(text: &str, query: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for q in query.chars() {
+        let (_, c) = chars.next()?;
+        if c != q && !c.to_lowercase().eq(q.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(text.len(), |(i, _)| i))
+}
+
+impl KeystrokeEvent {
+    /// Whether the event, as displayed, contains `query`, ignoring case.
+    /// Everything matches an empty query.
+    pub fn matches(&self, query: &str) -> bool {
+        query.is_empty() || !find_matches(&self.to_string(), query).is_empty()
+    }
+}
@@ -0,0 +1,99 @@
+//! What a session was working with: toolchains, the active Python
+//! environment, the Kubernetes context.
+//!
+//! Which cluster or virtualenv was active is rarely visible in keystrokes,
+//! yet it's the first thing anyone reading a summary asks.  When a project
+//! is found, the plugin runs each of the [`CONTEXT_PROBES`] in its root,
+//! through `direnv exec` if direnv is installed so a `.envrc` counts, and
+//! logs what they printed as one
+//! [`SessionContext`](crate::KeystrokeEvent::SessionContext) event.
+//!
+//! Only the probes' own output is kept, never the rest of the environment:
+//!
+//! ```
+//! use crumbeez_lib::parse_session_context;
+//!
+//! let output = "rust\u{1f}rustc 1.80.0\nVIRTUAL_ENV\u{1f}\nAWS_SECRET\u{1f}hunter2\n";
+//! let context = parse_session_context(output);
+//! assert_eq!(context.len(), 1);
+//! assert_eq!(context["rust"], "rustc 1.80.0");
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::truncate_middle;
+
+/// Where a probe's value comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeSource {
+    /// An environment variable.
+    Env(&'static str),
+    /// The first line a command prints, if it runs.
+    Command(&'static str),
+}
+
+/// One thing worth knowing about the session, logged under `key`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextProbe {
+    pub key: &'static str,
+    pub source: ProbeSource,
+}
+
+impl ContextProbe {
+    const fn env(name: &'static str) -> Self {
+        Self {
+            key: name,
+            source: ProbeSource::Env(name),
+        }
+    }
+
+    const fn command(key: &'static str, command: &'static str) -> Self {
+        Self {
+            key,
+            source: ProbeSource::Command(command),
+        }
+    }
+}
+
+/// Everything captured at the start of a session.
+pub const CONTEXT_PROBES: [ContextProbe; 13] = [
+    ContextProbe::command("rust", "rustc --version"),
+    ContextProbe::command("node", "node --version"),
+    ContextProbe::command("python", "python3 --version"),
+    ContextProbe::command("go", "go version"),
+    ContextProbe::command("kube-context", "kubectl config current-context"),
+    ContextProbe::command("git-branch", "git branch --show-current"),
+    ContextProbe::env("RUSTUP_TOOLCHAIN"),
+    ContextProbe::env("VIRTUAL_ENV"),
+    ContextProbe::env("CONDA_DEFAULT_ENV"),
+    ContextProbe::env("NODE_ENV"),
+    ContextProbe::env("KUBECONFIG"),
+    ContextProbe::env("AWS_PROFILE"),
+    ContextProbe::env("DIRENV_FILE"),
+];
+
+/// Longest value kept, cut in the middle beyond it.
+pub const MAX_CONTEXT_VALUE_CHARS: usize = 120;
+
+/// Separates a probe's key from its value in the probe script's output.
+pub const CONTEXT_SEPARATOR: char = '\u{1f}';
+
+/// The values in the output of a probe script, which prints one
+/// `<key>\x1f<value>` line per probe.  Keys that aren't one of the
+/// [`CONTEXT_PROBES`] and empty values are left out.
+pub fn parse_session_context(output: &str) -> BTreeMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(CONTEXT_SEPARATOR)?;
+            let probe = CONTEXT_PROBES.iter().find(|probe| probe.key == key)?;
+            let value = value.trim();
+            (!value.is_empty()).then(|| {
+                (
+                    probe.key.to_string(),
+                    truncate_middle(value, MAX_CONTEXT_VALUE_CHARS).into_owned(),
+                )
+            })
+        })
+        .collect()
+}
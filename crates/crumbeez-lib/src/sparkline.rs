@@ -0,0 +1,46 @@
+//! A one-line chart of how busy the log has been lately.
+//!
+//! The plugin draws one across the top of its pane, a bar per minute for
+//! the last hour, so a glance shows the recorder is alive and the rhythm
+//! of the session: bursts of typing, quiet stretches of reading.
+
+/// Bars from a single event up to the busiest minute.  Minutes without
+/// any are blank.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const MS_PER_MINUTE: u64 = 60 * 1000;
+
+/// Minutes the plugin's sparkline covers.
+pub const SPARKLINE_MINUTES: usize = 60;
+
+/// Events in each of the `minutes` minutes up to `now_ms`, oldest first.
+/// The last minute ends at `now_ms`; timestamps after it count towards it,
+/// in case the clock stepped back.
+pub fn events_per_minute(
+    timestamps: impl IntoIterator<Item = u64>,
+    now_ms: u64,
+    minutes: usize,
+) -> Vec<usize> {
+    let mut counts = vec![0; minutes];
+    for timestamp in timestamps {
+        let age = (now_ms.saturating_sub(timestamp) / MS_PER_MINUTE) as usize;
+        if age < minutes {
+            counts[minutes - 1 - age] += 1;
+        }
+    }
+    counts
+}
+
+/// A bar per minute of [`events_per_minute`], scaled to the busiest.
+pub fn sparkline(timestamps: impl IntoIterator<Item = u64>, now_ms: u64, minutes: usize) -> String {
+    let counts = events_per_minute(timestamps, now_ms, minutes);
+    let max = counts.iter().copied().max().unwrap_or(0);
+    counts
+        .into_iter()
+        .map(|count| match count {
+            0 => ' ',
+            // Round up, so one event still shows.
+            _ => BARS[(count * BARS.len()).div_ceil(max) - 1],
+        })
+        .collect()
+}
@@ -0,0 +1,68 @@
+//! Recognising panes logged into another machine.
+//!
+//! A pane running `ssh deploy@prod-db-1` types into `prod-db-1`, not the
+//! local machine, and "on prod-db-1: ran the migration" is the breadcrumb
+//! worth keeping.  [`ssh_host`] finds the host in the pane's command, or in
+//! its title when the command is the local shell that started ssh (shells
+//! set the title to the command they're running).  Projects can keep
+//! sensitive hosts out of the log entirely with `exclude_hosts`.
+
+/// Programs that open a remote shell, with the same `[user@]host` argument
+/// as ssh.
+const REMOTE_SHELLS: [&str; 3] = ["ssh", "mosh", "autossh"];
+
+/// ssh options that take a value as the next argument.
+const OPTIONS_WITH_VALUE: &str = "BbcDEeFIiJLlmOopQRSWw";
+
+/// The remote host a pane is logged into, if it runs ssh: from `command`,
+/// or failing that from `title`.
+pub fn ssh_host(command: Option<&str>, title: &str) -> Option<String> {
+    command
+        .and_then(host_in_command)
+        .or_else(|| host_in_command(title))
+}
+
+/// The host in an ssh command line, e.g. `prod-db-1` for
+/// `/usr/bin/ssh -p 2222 deploy@prod-db-1 uptime`.
+fn host_in_command(command: &str) -> Option<String> {
+    let mut args = command.split_whitespace();
+    let program = args.next()?;
+    let program = program.rsplit('/').next().unwrap_or(program);
+    if !REMOTE_SHELLS.contains(&program) {
+        return None;
+    }
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            return args.next().and_then(host_in_destination);
+        }
+        let Some(flags) = arg.strip_prefix('-') else {
+            return host_in_destination(arg);
+        };
+        if flags.starts_with('-') {
+            // Long options, like mosh's `--ssh=...`, come with `=`.
+            continue;
+        }
+        // Flags can be bundled, as in `-tp 2222`; a value is either the
+        // rest of the argument or the next one.
+        if let Some(at) = flags.find(|c| OPTIONS_WITH_VALUE.contains(c)) {
+            if at + 1 == flags.len() {
+                args.next();
+            }
+        }
+    }
+    None
+}
+
+/// The host in `[user@]host[:port]` or `ssh://[user@]host[:port][/path]`.
+fn host_in_destination(destination: &str) -> Option<String> {
+    let rest = destination.strip_prefix("ssh://").unwrap_or(destination);
+    let rest = rest.split('/').next().unwrap_or(rest);
+    let host = rest.rsplit('@').next().unwrap_or(rest);
+    let host = match host.strip_prefix('[') {
+        // An IPv6 address, `[::1]:22`.
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(bracketed),
+        None if destination.starts_with("ssh://") => host.split(':').next().unwrap_or(host),
+        None => host,
+    };
+    (!host.is_empty()).then(|| host.to_string())
+}
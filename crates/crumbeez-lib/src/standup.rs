@@ -0,0 +1,148 @@
+//! Condensing the last day of activity into a standup report.
+//!
+//! The window is split into [work blocks](crate::split_work_blocks), and the
+//! blocks are boiled down to at most [`STANDUP_MAX_BULLETS`] bullets:
+//! bookmarks first, then where the time went, then the commands run, then
+//! the totals.  The result is meant to be pasted into a standup channel
+//! as-is, so it names panes by title rather than in full.
+
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+
+use crate::summary::{format_hm, format_ymd};
+use crate::work_blocks::command_counts;
+use crate::{split_work_blocks, HumanDuration, KeystrokeEvent, LogEntry, DEFAULT_IDLE_GAP_MS};
+
+/// How far back a standup report looks.
+pub const STANDUP_WINDOW_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Most bullets in a report.
+pub const STANDUP_MAX_BULLETS: usize = 10;
+
+/// Most bookmarks and panes listed individually.
+const MAX_BOOKMARKS: usize = 4;
+const MAX_PANES: usize = 4;
+
+/// Most distinct commands named in the commands bullet.
+const MAX_COMMANDS: usize = 5;
+
+#[derive(Debug)]
+pub struct Standup {
+    /// End of the window the report covers.
+    pub end_ms: u64,
+    pub bullets: Vec<String>,
+}
+
+impl Standup {
+    /// Report on the entries (oldest first) from the
+    /// [`STANDUP_WINDOW_MS`] before `now_ms`.
+    pub fn from_entries<'a>(entries: impl IntoIterator<Item = &'a LogEntry>, now_ms: u64) -> Self {
+        let start_ms = now_ms.saturating_sub(STANDUP_WINDOW_MS);
+        // Focus carries into the window from before it.
+        let mut focused: Option<&str> = None;
+        let mut in_window: Vec<&LogEntry> = Vec::new();
+        for entry in entries {
+            if entry.timestamp_ms < start_ms {
+                if let KeystrokeEvent::PaneFocused(pane) = &entry.event {
+                    focused = Some(&pane.pane_title);
+                }
+            } else if entry.timestamp_ms <= now_ms {
+                in_window.push(entry);
+            }
+        }
+        let entries = in_window;
+        let blocks = split_work_blocks(entries.iter().copied(), DEFAULT_IDLE_GAP_MS);
+        let mut bullets = Vec::new();
+        if blocks.is_empty() {
+            bullets.push("No recorded activity.".to_string());
+            return Self {
+                end_ms: now_ms,
+                bullets,
+            };
+        }
+
+        // Bookmarks say what the user thought mattered; keep them verbatim.
+        let mut bookmarks: Vec<(u64, &str)> = Vec::new();
+        for block in &blocks {
+            for annotation in &block.summary.annotations {
+                if !bookmarks.iter().any(|(_, b)| *b == annotation) {
+                    bookmarks.push((block.start_ms, annotation));
+                }
+            }
+        }
+        for (at, bookmark) in bookmarks.iter().take(MAX_BOOKMARKS) {
+            bullets.push(format!("📌 {} (from {} UTC)", bookmark, format_hm(*at)));
+        }
+
+        // Time per pane: each gap between events within a block goes to the
+        // pane focused when it started.
+        let mut panes: Vec<(&str, u64)> = Vec::new();
+        for pair in entries.windows(2) {
+            if let KeystrokeEvent::PaneFocused(pane) = &pair[0].event {
+                focused = Some(&pane.pane_title);
+            }
+            let gap = pair[1].timestamp_ms.saturating_sub(pair[0].timestamp_ms);
+            let Some(focused) = focused.filter(|_| gap <= DEFAULT_IDLE_GAP_MS) else {
+                continue;
+            };
+            match panes.iter_mut().find(|(title, _)| *title == focused) {
+                Some((_, ms)) => *ms += gap,
+                None => panes.push((focused, gap)),
+            }
+        }
+        panes.sort_by_key(|(_, ms)| Reverse(*ms));
+        for (title, ms) in panes.iter().take(MAX_PANES) {
+            bullets.push(format!("{} — {}", title, HumanDuration::from_ms(*ms)));
+        }
+
+        let commands = command_counts(&blocks);
+        if !commands.is_empty() {
+            let named: Vec<String> = commands
+                .iter()
+                .take(MAX_COMMANDS)
+                .map(|(c, n)| match n {
+                    1 => format!("`{}`", c),
+                    n => format!("`{}` ×{}", c, n),
+                })
+                .collect();
+            let mut bullet = format!("Ran {}", named.join(", "));
+            if commands.len() > MAX_COMMANDS {
+                let _ = write!(bullet, " and {} more", commands.len() - MAX_COMMANDS);
+            }
+            bullets.push(bullet);
+        }
+
+        let active_ms: u64 = blocks.iter().map(|b| b.duration_ms()).sum();
+        let events: usize = blocks.iter().map(|b| b.summary.events_consumed).sum();
+        let first = blocks.first().map_or(0, |b| b.start_ms);
+        let last = blocks.last().map_or(0, |b| b.end_ms);
+        bullets.push(format!(
+            "{} active over {} work {} ({}–{} UTC), {} events",
+            HumanDuration::from_ms(active_ms),
+            blocks.len(),
+            if blocks.len() == 1 { "block" } else { "blocks" },
+            format_hm(first),
+            format_hm(last),
+            events
+        ));
+
+        bullets.truncate(STANDUP_MAX_BULLETS);
+        Self {
+            end_ms: now_ms,
+            bullets,
+        }
+    }
+
+    /// `standup-YYYY-MM-DD.md`, dated by the end of the window (UTC).
+    pub fn file_name(&self) -> String {
+        format!("standup-{}.md", format_ymd(self.end_ms))
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# Standup {}\n\n", format_ymd(self.end_ms));
+        for bullet in &self.bullets {
+            let _ = writeln!(out, "- {}", bullet);
+        }
+        out
+    }
+}
@@ -0,0 +1,117 @@
+//! Headline numbers about an event log.
+//!
+//! [`EventLog::stats`] is the one place these are computed, so the plugin
+//! pane and anything built on it report the same figures.  [`LogStats`] is
+//! serializable for handing to other tools as-is.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::summary::format_ymd;
+use crate::{CorrectionStats, EventLog, KeystrokeEvent, PaneFocusedEvent};
+
+const MS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+const MS_PER_HOUR: u64 = 60 * 60 * 1000;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct LogStats {
+    pub total_events: usize,
+    pub unconsumed_events: usize,
+    /// Events per UTC day (`YYYY-MM-DD`), for days with any.
+    pub events_per_day: BTreeMap<String, usize>,
+    /// Distinct panes focused, told apart by tab, title, and command.
+    pub unique_panes: usize,
+    /// The UTC hour of day (0–23) with the most events, the earliest on a
+    /// tie.  `None` for an empty log.
+    pub busiest_hour: Option<u8>,
+    /// Characters typed, counting submissions to REPLs, agents, and fuzzy
+    /// finders as well as plain text.
+    pub typed_chars: usize,
+    /// Timestamps of the oldest and newest entries.
+    pub span_ms: Option<(u64, u64)>,
+    /// Plain text typed into each program (the focused pane's command, or
+    /// its title), with the corrections made to it.  Text logged without
+    /// [`corrections`](crate::LogEntry::corrections) was typed straight
+    /// through.
+    pub rework_by_app: BTreeMap<String, CorrectionStats>,
+}
+
+impl EventLog {
+    pub fn stats(&self) -> LogStats {
+        let mut per_day: BTreeMap<u64, usize> = BTreeMap::new();
+        let mut per_hour = [0usize; 24];
+        let mut panes = BTreeSet::new();
+        let mut typed_chars = 0;
+        let mut rework_by_app: BTreeMap<String, CorrectionStats> = BTreeMap::new();
+        let mut app: Option<String> = None;
+
+        for entry in self.entries() {
+            *per_day.entry(entry.timestamp_ms / MS_PER_DAY).or_insert(0) += 1;
+            per_hour[((entry.timestamp_ms / MS_PER_HOUR) % 24) as usize] += 1;
+            typed_chars += match &entry.event {
+                KeystrokeEvent::PaneFocused(pane) => {
+                    panes.insert((&pane.tab_name, &pane.pane_title, &pane.command));
+                    app = Some(app_name(pane));
+                    0
+                }
+                KeystrokeEvent::TextTyped(text)
+                | KeystrokeEvent::PromptToAgent(text)
+                | KeystrokeEvent::FuzzySelected { query: text, .. }
+                | KeystrokeEvent::ReplInput { code: text, .. } => text.chars().count(),
+                KeystrokeEvent::ShortText(chars) | KeystrokeEvent::LongText { chars, .. } => *chars,
+                _ => 0,
+            };
+            if let KeystrokeEvent::TextTyped(text) = &entry.event {
+                let totals = rework_by_app
+                    .entry(app.clone().unwrap_or_default())
+                    .or_default();
+                match entry.corrections.as_deref() {
+                    Some(corrections) => totals.add(corrections),
+                    None => totals.add(&CorrectionStats::uncorrected(text)),
+                }
+            }
+        }
+
+        let busiest_hour = (self.total_count() > 0).then(|| {
+            // `max_by_key` keeps the last maximum; reversing keeps the first.
+            per_hour
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, count)| **count)
+                .map_or(0, |(hour, _)| hour as u8)
+        });
+        let span_ms = self
+            .entries()
+            .next()
+            .zip(self.entries().last())
+            .map(|(first, last)| (first.timestamp_ms, last.timestamp_ms));
+
+        LogStats {
+            total_events: self.total_count(),
+            unconsumed_events: self.unconsumed_count(),
+            events_per_day: per_day
+                .into_iter()
+                .map(|(day, count)| (format_ymd(day * MS_PER_DAY), count))
+                .collect(),
+            unique_panes: panes.len(),
+            busiest_hour,
+            typed_chars,
+            span_ms,
+            rework_by_app,
+        }
+    }
+}
+
+/// What [`LogStats::rework_by_app`] calls the program in `pane`: its
+/// command's file name, e.g. `nvim` for `/usr/bin/nvim README.md`, or the
+/// pane's title if it has no command.
+fn app_name(pane: &PaneFocusedEvent) -> String {
+    pane.command
+        .as_deref()
+        .and_then(|command| command.split_whitespace().next())
+        .and_then(|program| program.rsplit(['/', '\\']).next())
+        .unwrap_or(&pane.pane_title)
+        .to_string()
+}
@@ -0,0 +1,88 @@
+//! Pointers from a superproject to activity logged in its submodules.
+//!
+//! Working in a submodule, crumbeez finds two project roots: the submodule
+//! and the superproject around it.  The events go only to the submodule's
+//! log, since copying them into the superproject's would double the disk
+//! they take.  Instead, per the `superproject` setting, each summary of
+//! them leaves a line in the superproject's
+//! `summaries/submodule-links.kdl`:
+//!
+//! ```kdl
+//! submodule "libs/parser" summary="01HQT3Z5E8M2C4K7P9R1V3X5Z7" day="2024-03-01" first_seq=120 last_seq=245
+//! ```
+//!
+//! The summary ID and sequence numbers find the summary and its events in
+//! the submodule's own [links file](crate::SummaryLink).  Like that file,
+//! this one is append-only.
+
+use crate::kdl::{self, KdlNode};
+use crate::{SummaryId, SummaryLink};
+
+/// A summary of activity in a submodule, as the superproject records it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmoduleLink {
+    /// The submodule's path, relative to the superproject root.
+    pub submodule: String,
+    pub summary: SummaryId,
+    /// `YYYY-MM-DD`, the day of the summary's last event.
+    pub day: String,
+    /// Sequence numbers of the first and last summarized events in the
+    /// submodule's log.
+    pub first_seq: u64,
+    pub last_seq: u64,
+}
+
+impl SubmoduleLink {
+    /// The pointer to the summary `link` describes, made in `submodule`.
+    pub fn new(submodule: impl Into<String>, link: &SummaryLink) -> Self {
+        Self {
+            submodule: submodule.into(),
+            summary: link.id,
+            day: link.day.clone(),
+            first_seq: link.first_seq,
+            last_seq: link.last_seq,
+        }
+    }
+
+    /// One line of the submodule links file, newline included.
+    pub fn to_kdl(&self) -> String {
+        format!(
+            "submodule {} summary={} day={} first_seq={} last_seq={}\n",
+            kdl::quote(&self.submodule),
+            kdl::quote(&self.summary.to_string()),
+            kdl::quote(&self.day),
+            self.first_seq,
+            self.last_seq
+        )
+    }
+
+    fn from_node(node: &KdlNode) -> Option<Self> {
+        if node.name != "submodule" {
+            return None;
+        }
+        let seq = |key| {
+            node.prop(key)
+                .and_then(|v| v.as_i64())
+                .map(|n| n.max(0) as u64)
+        };
+        Some(Self {
+            submodule: node.args.first()?.as_str()?.to_string(),
+            summary: node.prop("summary")?.as_str()?.parse().ok()?,
+            day: node.prop("day")?.as_str()?.to_string(),
+            first_seq: seq("first_seq")?,
+            last_seq: seq("last_seq")?,
+        })
+    }
+
+    /// Parse a submodule links file, skipping damaged lines.
+    pub fn parse_all(text: &str) -> Vec<Self> {
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .filter_map(|line| {
+                kdl::parse(line)
+                    .ok()
+                    .and_then(|nodes| nodes.first().and_then(Self::from_node))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,1048 @@
+//! Turning a run of [`LogEntry`]s into a summary.
+//!
+//! A [`Summary`] groups activity by the pane it happened in and renders it
+//! two ways:
+//!
+//! - [`Summary::to_markdown`] — the human-readable form written to the
+//!   summaries directory.
+//! - [`Summary::to_narrative`] — a compact plain-text form shown in the
+//!   plugin pane and handed to an LLM as context.
+//!
+//! Bookmarks ([`KeystrokeEvent::Annotation`]) are pulled out of the activity
+//! and headline both forms.
+//!
+//! Both are written in the summary's [`Locale`].
+//!
+//! Both formats are locked down by the snapshot tests in
+//! `tests/summary_snapshots.rs`; update those deliberately when changing
+//! the output.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+
+use crate::broadcast::json_string;
+use crate::locale::{fill, Locale, Strings};
+use crate::summary_id::SummaryId;
+use crate::{
+    truncate_middle, AppRole, CommandOutcome, ContainerCommand, EditControlEvent, EnvironmentKind,
+    EventKind, HumanDuration, KeystrokeEvent, LogEntry, PaneFocusedEvent, READING_MODES,
+};
+
+/// One thing the user did within a pane.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Activity {
+    /// Text typed, net of corrections.  `submitted` is set when it was
+    /// followed by Enter — usually a shell command or a prompt answer.
+    Typed { text: String, submitted: bool },
+    /// A keyboard shortcut, e.g. `Ctrl+S`, or a chord like `Ctrl+B c`.
+    Shortcut(String),
+    /// A fuzzy finder match accepted for `query`, after moving the selection
+    /// `moves` times.
+    Picked { query: String, moves: usize },
+    /// A REPL submission, possibly several lines long.
+    Evaluated { language: String, code: String },
+    /// A prompt sent to an AI agent, verbatim.
+    Prompted(String),
+    /// A status the agent reported in its pane title.
+    AgentStatus(String),
+    /// The pane's program showed this title, e.g. the file open in an
+    /// editor.
+    Title(String),
+    /// Zellij switched to the named input mode.
+    Mode(String),
+    /// The screen was saved to the file at this path, relative to the
+    /// `.crumbeez` directory.
+    Snapshot(String),
+    /// A flood of this many keys, counted but not logged.
+    Flood(usize),
+    /// The tab with this name was closed.
+    TabClosed(String),
+    /// The session was renamed to this.
+    SessionRenamed(String),
+    /// A password was typed at a prompt that hides it, and left out.
+    Credential,
+    /// A command run in `shell`, imported from its history or, with its
+    /// `outcome`, looked up once it finished.
+    Ran {
+        command: String,
+        shell: String,
+        outcome: Option<CommandOutcome>,
+    },
+    /// What the session was working with, by probe key.
+    Context(BTreeMap<String, String>),
+    /// A run of navigation, editing, and other keys with no text of their
+    /// own, collapsed to a count.
+    Keys(usize),
+}
+
+/// Activity in a single pane, from when it gained focus until it lost it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaneSegment {
+    /// The pane, or `None` for activity before the first focus event.
+    pub pane: Option<PaneFocusedEvent>,
+    pub activities: Vec<Activity>,
+}
+
+impl PaneSegment {
+    /// The pane's role, [`AppRole::Shell`] if it's unknown.
+    pub fn role(&self) -> AppRole {
+        self.pane.as_ref().map_or(AppRole::Shell, |pane| pane.role)
+    }
+}
+
+#[derive(Debug)]
+pub struct Summary {
+    pub events_consumed: usize,
+    /// Event counts by type, sorted by type name.
+    pub event_types: BTreeMap<String, usize>,
+    /// Timestamps of the first and last summarized events.
+    pub span_ms: Option<(u64, u64)>,
+    /// Bookmarks dropped among the events, in order.  They headline the
+    /// rendered summary.
+    pub annotations: Vec<String>,
+    /// Clusters, namespaces, and AWS profiles switched to, in order.  They
+    /// follow the headline, since they change what every later command
+    /// touched.
+    pub switches: Vec<EnvironmentSwitch>,
+    /// Risky commands run, in order.  They get a section of their own.
+    pub notable: Vec<NotableAction>,
+    pub segments: Vec<PaneSegment>,
+    /// Characters of [short text](KeystrokeEvent::ShortText), across all
+    /// panes.
+    pub misc_keys: usize,
+    /// Time spent in scroll or search mode, reading output.
+    pub reading_ms: u64,
+    /// Keys of each [disabled kind](KeystrokeEvent::Filtered), across all
+    /// panes.
+    pub filtered: BTreeMap<EventKind, usize>,
+    /// Files the events refer to, relative to the `.crumbeez` directory.
+    pub files: Vec<String>,
+    /// Set once the summary is written out, and rendered as front matter.
+    pub id: Option<SummaryId>,
+    /// The [project's name](crate::ProjectName), rendered in the front
+    /// matter with the ID.
+    pub project: Option<String>,
+    /// What made the plugin summarize, rendered in the front matter.
+    pub trigger: Option<SummaryTrigger>,
+    /// The session's [tags](crate::parse_tags) at any point during the
+    /// events, rendered in the front matter.
+    pub tags: Vec<String>,
+    /// What the text typed was about, rendered in the front matter.  Empty
+    /// until [`find_topics`](Self::find_topics) is called.
+    pub topics: Vec<String>,
+    /// The [issues](crate::issue_refs) mentioned in what was typed and run,
+    /// in the pane titles, and in the session context, rendered in the
+    /// front matter.
+    pub issues: Vec<String>,
+    /// The git branches checked out at any point during the events, from
+    /// the session context and [switches](EnvironmentKind::GitBranch),
+    /// rendered in the front matter.
+    pub branches: Vec<String>,
+    /// The language it's rendered in.
+    pub locale: Locale,
+}
+
+/// A [`KeystrokeEvent::EnvironmentChanged`], with when it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvironmentSwitch {
+    pub kind: EnvironmentKind,
+    pub target: String,
+    pub at_ms: u64,
+}
+
+impl EnvironmentSwitch {
+    /// e.g. "switched to `prod` cluster at 14:02 UTC", quoting the target
+    /// with `quote`.
+    pub fn describe(&self, strings: &Strings, quote: impl Fn(&str) -> String) -> String {
+        let at = format!("{} UTC", format_hm(self.at_ms));
+        fill(strings.switched_to(self.kind), &[&quote(&self.target), &at])
+    }
+}
+
+/// A [`KeystrokeEvent::RiskyCommand`], with when it was run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotableAction {
+    pub command: String,
+    pub pattern: String,
+    pub reason: Option<String>,
+    pub at_ms: u64,
+}
+
+impl NotableAction {
+    /// e.g. "`rm -rf build` at 14:02 UTC, matching `rm -rf`: stale cache",
+    /// quoting the command and pattern with `quote`.
+    pub fn describe(&self, strings: &Strings, quote: impl Fn(&str) -> String) -> String {
+        let at = format!("{} UTC", format_hm(self.at_ms));
+        let mut out = fill(
+            strings.notable_action,
+            &[&quote(&self.command), &at, &quote(&self.pattern)],
+        );
+        if let Some(reason) = &self.reason {
+            let _ = write!(out, ": {}", reason);
+        }
+        out
+    }
+}
+
+/// Why a summary was made when it was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryTrigger {
+    /// Activity stopped for the inactivity timeout.
+    Inactivity,
+    /// Focus left a pane that had activity.
+    PaneSwitch,
+    /// A bookmark was dropped, to headline what follows.
+    Bookmark,
+    /// A report, like a rollup, needed everything summarized.
+    Report,
+    /// A tab was closed.
+    TabClose,
+    /// The session was renamed.
+    SessionRename,
+    /// The plugin started and found events a previous run left
+    /// unsummarized when it stopped (see [`Remnants`](crate::Remnants)).
+    Recovery,
+}
+
+impl SummaryTrigger {
+    pub const ALL: [Self; 7] = [
+        Self::Inactivity,
+        Self::PaneSwitch,
+        Self::Bookmark,
+        Self::Report,
+        Self::TabClose,
+        Self::SessionRename,
+        Self::Recovery,
+    ];
+
+    /// The name in front matter.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Inactivity => "inactivity",
+            Self::PaneSwitch => "pane-switch",
+            Self::Bookmark => "bookmark",
+            Self::Report => "report",
+            Self::TabClose => "tab-close",
+            Self::SessionRename => "session-rename",
+            Self::Recovery => "recovery",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|trigger| trigger.name() == name)
+    }
+}
+
+impl fmt::Display for SummaryTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Summary {
+    pub fn from_events(entries: impl Iterator<Item = LogEntry>) -> Self {
+        Self::continuing(None, entries)
+    }
+
+    /// Like [`from_events`](Self::from_events), for a stream that starts
+    /// mid-session with `pane` already focused.
+    pub fn continuing(
+        pane: Option<PaneFocusedEvent>,
+        entries: impl Iterator<Item = LogEntry>,
+    ) -> Self {
+        let mut events_consumed = 0;
+        let mut event_types = BTreeMap::new();
+        let mut span_ms: Option<(u64, u64)> = None;
+        let mut annotations = Vec::new();
+        let mut switches = Vec::new();
+        let mut notable = Vec::new();
+        let mut tags: Vec<String> = Vec::new();
+        let mut branches: Vec<String> = Vec::new();
+        let mut misc_keys = 0;
+        let mut filtered = BTreeMap::new();
+        let mut files = Vec::new();
+        let mut reading_ms = 0;
+        // When a reading mode was entered, while in one.
+        let mut reading_since: Option<u64> = None;
+        // The pane as focused, before any container entered from it.
+        let mut focused = pane.clone();
+        let mut segments: Vec<PaneSegment> = pane
+            .map(|pane| PaneSegment {
+                pane: Some(pane),
+                activities: Vec::new(),
+            })
+            .into_iter()
+            .collect();
+
+        for entry in entries {
+            events_consumed += 1;
+            *event_types
+                .entry(event_type_name(&entry.event).to_string())
+                .or_insert(0) += 1;
+            span_ms = Some(match span_ms {
+                Some((start, _)) => (start, entry.timestamp_ms),
+                None => (entry.timestamp_ms, entry.timestamp_ms),
+            });
+
+            // Leaving the mode, or the session, ends the reading.
+            if let KeystrokeEvent::ModeChanged(_) | KeystrokeEvent::SessionSuspended(_) =
+                &entry.event
+            {
+                if let Some(since) = reading_since.take() {
+                    reading_ms += entry.timestamp_ms.saturating_sub(since);
+                }
+            }
+            if let KeystrokeEvent::ModeChanged(mode) = &entry.event {
+                if READING_MODES.contains(&mode.as_str()) {
+                    reading_since = Some(entry.timestamp_ms);
+                }
+            }
+
+            if let KeystrokeEvent::SnapshotTaken(file) | KeystrokeEvent::LongText { file, .. } =
+                &entry.event
+            {
+                files.push(file.clone());
+            }
+
+            if let KeystrokeEvent::PaneFocused(pane) = entry.event {
+                focused = Some(pane.clone());
+                // Refocusing the same pane continues its segment.
+                if segments.last().and_then(|s| s.pane.as_ref()) != Some(&pane) {
+                    segments.push(PaneSegment {
+                        pane: Some(pane),
+                        activities: Vec::new(),
+                    });
+                }
+                continue;
+            }
+            if let KeystrokeEvent::Annotation(text) = entry.event {
+                annotations.push(text);
+                continue;
+            }
+            let branch = match &entry.event {
+                KeystrokeEvent::SessionContext(values) => values.get("git-branch"),
+                KeystrokeEvent::EnvironmentChanged {
+                    kind: EnvironmentKind::GitBranch,
+                    target,
+                } => Some(target),
+                _ => None,
+            };
+            if let Some(branch) = branch {
+                if !branches.contains(branch) {
+                    branches.push(branch.clone());
+                }
+            }
+            if let KeystrokeEvent::EnvironmentChanged { kind, target } = entry.event {
+                switches.push(EnvironmentSwitch {
+                    kind,
+                    target,
+                    at_ms: entry.timestamp_ms,
+                });
+                continue;
+            }
+            if let KeystrokeEvent::RiskyCommand {
+                command,
+                pattern,
+                reason,
+            } = entry.event
+            {
+                notable.push(NotableAction {
+                    command,
+                    pattern,
+                    reason,
+                    at_ms: entry.timestamp_ms,
+                });
+                continue;
+            }
+            if let KeystrokeEvent::SessionTagged(set) = entry.event {
+                for tag in set {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+                continue;
+            }
+            if let KeystrokeEvent::ShortText(chars) = entry.event {
+                misc_keys += chars;
+                continue;
+            }
+            if let KeystrokeEvent::Filtered { kind, count } = entry.event {
+                *filtered.entry(kind).or_insert(0) += count;
+                continue;
+            }
+            if matches!(
+                entry.event,
+                KeystrokeEvent::SessionSuspended(_) | KeystrokeEvent::SessionResumed
+            ) {
+                continue;
+            }
+
+            if segments.is_empty() {
+                segments.push(PaneSegment {
+                    pane: None,
+                    activities: Vec::new(),
+                });
+            }
+            let submitted = entry.event == KeystrokeEvent::EditControl(EditControlEvent::Enter);
+            let segment = segments.last_mut().expect("just ensured");
+            push_activity(&mut segment.activities, entry.event);
+            if submitted {
+                if let Some(pane) = container_switch(segment, focused.as_ref()) {
+                    segments.push(PaneSegment {
+                        pane: Some(pane),
+                        activities: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        if let (Some(since), Some((_, end))) = (reading_since, span_ms) {
+            reading_ms += end.saturating_sub(since);
+        }
+
+        // Drop panes that were only passed through.
+        segments.retain(|s| !s.activities.is_empty());
+        let issues = issue_refs_in(&annotations, &segments);
+
+        Summary {
+            events_consumed,
+            event_types,
+            span_ms,
+            annotations,
+            switches,
+            notable,
+            segments,
+            misc_keys,
+            reading_ms,
+            filtered,
+            files,
+            id: None,
+            project: None,
+            trigger: None,
+            tags,
+            topics: Vec::new(),
+            issues,
+            branches,
+            locale: Locale::default(),
+        }
+    }
+
+    /// Add `tags`, the session's tags as the events began, ahead of any
+    /// set among them.
+    pub fn carry_tags(&mut self, tags: &[String]) {
+        let set_since = std::mem::take(&mut self.tags);
+        self.tags = tags.to_vec();
+        for tag in set_since {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+    }
+
+    /// Add `branch`, the one checked out as the events began, ahead of any
+    /// switched to among them.
+    pub fn carry_branch(&mut self, branch: Option<&str>) {
+        if let Some(branch) = branch {
+            self.branches.retain(|b| b != branch);
+            self.branches.insert(0, branch.to_string());
+        }
+    }
+
+    /// Fill in [`topics`](Self::topics) from the text typed, evaluated,
+    /// prompted, and run.  Call it before
+    /// [`shorten_text`](Self::shorten_text), which cuts the middle out of
+    /// long text.
+    #[cfg(feature = "analytics")]
+    pub fn find_topics(&mut self) {
+        let texts = self
+            .segments
+            .iter()
+            .flat_map(|segment| &segment.activities)
+            .filter_map(|activity| match activity {
+                Activity::Typed { text, .. }
+                | Activity::Evaluated { code: text, .. }
+                | Activity::Prompted(text)
+                | Activity::Ran { command: text, .. } => Some(text.as_str()),
+                _ => None,
+            });
+        self.topics = crate::topics(texts, crate::MAX_TOPICS);
+    }
+
+    /// Cut text typed, evaluated, or prompted to `max_chars` with
+    /// [`truncate_middle`], so a long entry doesn't swamp the summary.
+    pub fn shorten_text(&mut self, max_chars: usize) {
+        for segment in &mut self.segments {
+            for activity in &mut segment.activities {
+                if let Activity::Typed { text, .. }
+                | Activity::Evaluated { code: text, .. }
+                | Activity::Prompted(text) = activity
+                {
+                    if let Cow::Owned(cut) = truncate_middle(text, max_chars) {
+                        *text = cut;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The (UTC) day of the last summarized event, `YYYY-MM-DD`.
+    pub fn day(&self) -> Option<String> {
+        self.span_ms.map(|(_, end)| format_ymd(end))
+    }
+
+    /// Pass every piece of recorded text through `redact`: bookmarks, the
+    /// project and pane names, and what was typed and shown.  Counts and
+    /// snapshot paths are left alone.
+    pub fn redact_text(&mut self, redact: &impl Fn(&str) -> Cow<'_, str>) {
+        let apply = |text: &mut String| {
+            if let Cow::Owned(redacted) = redact(text) {
+                *text = redacted;
+            }
+        };
+        self.annotations.iter_mut().for_each(&apply);
+        self.switches
+            .iter_mut()
+            .for_each(|switch| apply(&mut switch.target));
+        for action in &mut self.notable {
+            apply(&mut action.command);
+            action.reason.iter_mut().for_each(&apply);
+        }
+        self.project.iter_mut().for_each(&apply);
+        self.tags.iter_mut().for_each(&apply);
+        self.topics.iter_mut().for_each(&apply);
+        self.issues.iter_mut().for_each(&apply);
+        self.branches.iter_mut().for_each(&apply);
+        for segment in &mut self.segments {
+            if let Some(ref mut pane) = segment.pane {
+                pane.redact_text(redact);
+            }
+            for activity in &mut segment.activities {
+                match activity {
+                    Activity::Typed { text, .. }
+                    | Activity::Shortcut(text)
+                    | Activity::Picked { query: text, .. }
+                    | Activity::Evaluated { code: text, .. }
+                    | Activity::Prompted(text)
+                    | Activity::AgentStatus(text)
+                    | Activity::Title(text)
+                    | Activity::TabClosed(text)
+                    | Activity::SessionRenamed(text)
+                    | Activity::Ran { command: text, .. } => apply(text),
+                    Activity::Context(values) => values.values_mut().for_each(&apply),
+                    Activity::Mode(_)
+                    | Activity::Snapshot(_)
+                    | Activity::Flood(_)
+                    | Activity::Credential
+                    | Activity::Keys(_) => {}
+                }
+            }
+        }
+    }
+
+    /// Render as a Markdown section.
+    pub fn to_markdown(&self) -> String {
+        let strings = self.locale.strings();
+        let mut out = String::new();
+        if self.id.is_some()
+            || self.project.is_some()
+            || self.trigger.is_some()
+            || !self.tags.is_empty()
+            || !self.topics.is_empty()
+            || !self.issues.is_empty()
+            || !self.branches.is_empty()
+        {
+            out.push_str("---\n");
+            if let Some(id) = self.id {
+                let _ = writeln!(out, "id: {}", id);
+            }
+            if let Some(project) = &self.project {
+                let _ = writeln!(out, "project: {}", json_string(project));
+            }
+            if let Some(trigger) = self.trigger {
+                let _ = writeln!(out, "trigger: {}", trigger);
+            }
+            if !self.tags.is_empty() {
+                let tags: Vec<String> = self.tags.iter().map(|t| json_string(t)).collect();
+                let _ = writeln!(out, "tags: [{}]", tags.join(", "));
+            }
+            if !self.topics.is_empty() {
+                let topics: Vec<String> = self.topics.iter().map(|t| json_string(t)).collect();
+                let _ = writeln!(out, "topics: [{}]", topics.join(", "));
+            }
+            if !self.issues.is_empty() {
+                let issues: Vec<String> = self.issues.iter().map(|i| json_string(i)).collect();
+                let _ = writeln!(out, "issues: [{}]", issues.join(", "));
+            }
+            if !self.branches.is_empty() {
+                let branches: Vec<String> = self.branches.iter().map(|b| json_string(b)).collect();
+                let _ = writeln!(out, "branches: [{}]", branches.join(", "));
+            }
+            out.push_str("---\n");
+        }
+        let _ = write!(out, "## ");
+        for annotation in &self.annotations {
+            let _ = write!(out, "📌 {} · ", annotation);
+        }
+        if self.trigger == Some(SummaryTrigger::Recovery) {
+            let _ = write!(out, "⚠ {} · ", strings.recovered);
+        }
+        if let Some((start, end)) = self.span_ms {
+            let _ = write!(out, "{}–{} UTC · ", format_hm(start), format_hm(end));
+        }
+        out.push_str(&self.stats(strings).join(" · "));
+        out.push('\n');
+        if let Some(stopped) = self.stopped(strings, code_span) {
+            let _ = write!(out, "\n**⚠ {}**\n", stopped);
+        }
+        for switch in &self.switches {
+            let _ = write!(out, "\n**⇄ {}**\n", switch.describe(strings, code_span));
+        }
+
+        for segment in &self.segments {
+            out.push('\n');
+            match &segment.pane {
+                Some(pane) => {
+                    let _ = writeln!(out, "### {}", pane);
+                }
+                None => {
+                    let _ = writeln!(out, "### {}", strings.unknown_pane);
+                }
+            }
+            out.push('\n');
+            for activity in &segment.activities {
+                let _ = writeln!(
+                    out,
+                    "- {}",
+                    describe(activity, segment.role(), strings, code_span)
+                );
+            }
+        }
+
+        if !self.notable.is_empty() {
+            let _ = write!(out, "\n### ⚠ {}\n\n", strings.notable_actions);
+            for action in &self.notable {
+                let _ = writeln!(out, "- {}", action.describe(strings, code_span));
+            }
+        }
+
+        if !self.event_types.is_empty() {
+            let _ = write!(
+                out,
+                "\n| {} | {} |\n|---|---:|\n",
+                strings.event_type_header, strings.count_header
+            );
+            for (event_type, count) in &self.event_types {
+                let _ = writeln!(out, "| {} | {} |", event_type, count);
+            }
+        }
+        out
+    }
+
+    /// Render as compact plain text, one line per pane.
+    #[cfg(feature = "narrative")]
+    pub fn to_narrative(&self) -> String {
+        let strings = self.locale.strings();
+        let mut lines: Vec<String> = self
+            .annotations
+            .iter()
+            .map(|annotation| format!("📌 {}", annotation))
+            .collect();
+        lines.extend(
+            self.stopped(strings, |s| format!("{:?}", s))
+                .map(|stopped| format!("⚠ {}", stopped)),
+        );
+        lines.extend(
+            self.switches
+                .iter()
+                .map(|switch| format!("⇄ {}", switch.describe(strings, |s| format!("{:?}", s)))),
+        );
+        lines.extend(
+            self.notable
+                .iter()
+                .map(|action| format!("⚠ {}", action.describe(strings, |s| format!("{:?}", s)))),
+        );
+        lines.push(format!("📊 {}", self.stats(strings).join(", ")));
+        for segment in &self.segments {
+            let pane = segment
+                .pane
+                .as_ref()
+                .map_or_else(|| strings.unknown_pane.to_string(), |p| p.to_string());
+            let activities: Vec<_> = segment
+                .activities
+                .iter()
+                .map(|a| describe(a, segment.role(), strings, |s| format!("{:?}", s)))
+                .collect();
+            lines.push(format!("  {}: {}", pane, activities.join("; ")));
+        }
+        lines.join("\n")
+    }
+}
+
+impl Summary {
+    /// For a [recovery](SummaryTrigger::Recovery) summary, when the run
+    /// stopped and what was last being done, quoting typed text with
+    /// `quote`.
+    fn stopped(&self, strings: &Strings, quote: impl Fn(&str) -> String) -> Option<String> {
+        if self.trigger != Some(SummaryTrigger::Recovery) {
+            return None;
+        }
+        let (_, end) = self.span_ms?;
+        let mut out = fill(strings.stopped_at, &[&format!("{} UTC", format_hm(end))]);
+        let last = self
+            .segments
+            .last()
+            .and_then(|segment| Some((segment, segment.activities.last()?)));
+        if let Some((segment, activity)) = last {
+            let _ = write!(
+                out,
+                "; {}",
+                fill(
+                    strings.in_flight,
+                    &[&describe(activity, segment.role(), strings, quote)]
+                )
+            );
+        }
+        Some(out)
+    }
+
+    /// The headline counts: events, then whichever of the others apply.
+    fn stats(&self, strings: &Strings) -> Vec<String> {
+        let mut stats = vec![fill(strings.events, &[&self.events_consumed])];
+        if self.misc_keys > 0 {
+            stats.push(fill(strings.misc_keys, &[&self.misc_keys]));
+        }
+        for (kind, count) in &self.filtered {
+            stats.push(fill(
+                strings.filtered,
+                &[count, &strings.kind_plural(*kind)],
+            ));
+        }
+        if self.reading_ms > 0 {
+            let minutes = format_minutes(self.reading_ms, strings);
+            stats.push(fill(strings.reading_output, &[&minutes]));
+        }
+        stats
+    }
+}
+
+/// The name of `event`'s variant, as summaries count events and the
+/// breadcrumb envelope's `type` field gives it.
+pub fn event_type_name(event: &KeystrokeEvent) -> &'static str {
+    match event {
+        KeystrokeEvent::TextTyped(_) => "TextTyped",
+        KeystrokeEvent::Shortcut(_) => "Shortcut",
+        KeystrokeEvent::Navigation(_) => "Navigation",
+        KeystrokeEvent::EditControl(_) => "EditControl",
+        KeystrokeEvent::Escape => "Escape",
+        KeystrokeEvent::FunctionKey(_) => "FunctionKey",
+        KeystrokeEvent::SystemKey(_) => "SystemKey",
+        KeystrokeEvent::PaneFocused(_) => "PaneFocused",
+        KeystrokeEvent::Annotation(_) => "Annotation",
+        KeystrokeEvent::FuzzySelected { .. } => "FuzzySelected",
+        KeystrokeEvent::ReplInput { .. } => "ReplInput",
+        KeystrokeEvent::PromptToAgent(_) => "PromptToAgent",
+        KeystrokeEvent::AgentStatus(_) => "AgentStatus",
+        KeystrokeEvent::ShortText(_) => "ShortText",
+        KeystrokeEvent::SessionSuspended(_) => "SessionSuspended",
+        KeystrokeEvent::SessionResumed => "SessionResumed",
+        KeystrokeEvent::ModeChanged(_) => "ModeChanged",
+        KeystrokeEvent::SnapshotTaken(_) => "SnapshotTaken",
+        KeystrokeEvent::Filtered { .. } => "Filtered",
+        KeystrokeEvent::ChordSequence(_) => "ChordSequence",
+        KeystrokeEvent::FloodDetected { .. } => "FloodDetected",
+        KeystrokeEvent::PaneTitleChanged(_) => "PaneTitleChanged",
+        KeystrokeEvent::TabClosed(_) => "TabClosed",
+        KeystrokeEvent::SessionRenamed(_) => "SessionRenamed",
+        KeystrokeEvent::LongText { .. } => "LongText",
+        KeystrokeEvent::CommandExecuted { .. } => "CommandExecuted",
+        KeystrokeEvent::SessionContext(_) => "SessionContext",
+        KeystrokeEvent::EnvironmentChanged { .. } => "EnvironmentChanged",
+        KeystrokeEvent::RiskyCommand { .. } => "RiskyCommand",
+        KeystrokeEvent::SessionTagged(_) => "SessionTagged",
+        KeystrokeEvent::CredentialEntered => "CredentialEntered",
+    }
+}
+
+fn push_activity(activities: &mut Vec<Activity>, event: KeystrokeEvent) {
+    match event {
+        KeystrokeEvent::TextTyped(text) | KeystrokeEvent::LongText { preview: text, .. } => {
+            activities.push(Activity::Typed {
+                text,
+                submitted: false,
+            })
+        }
+        KeystrokeEvent::EditControl(EditControlEvent::Enter) => {
+            match activities.last_mut() {
+                Some(Activity::Typed { submitted, .. }) if !*submitted => *submitted = true,
+                // It's what submitted the password.
+                Some(Activity::Credential) => {}
+                _ => push_keys(activities, 1),
+            }
+        }
+        KeystrokeEvent::Shortcut(shortcut) => {
+            activities.push(Activity::Shortcut(shortcut.to_string()))
+        }
+        KeystrokeEvent::ChordSequence(keys) => activities.push(Activity::Shortcut(keys)),
+        KeystrokeEvent::FuzzySelected { query, moves } => {
+            activities.push(Activity::Picked { query, moves })
+        }
+        KeystrokeEvent::ReplInput { language, code } => {
+            activities.push(Activity::Evaluated { language, code })
+        }
+        KeystrokeEvent::PromptToAgent(text) => activities.push(Activity::Prompted(text)),
+        KeystrokeEvent::AgentStatus(status) => activities.push(Activity::AgentStatus(status)),
+        KeystrokeEvent::PaneTitleChanged(title) => activities.push(Activity::Title(title)),
+        KeystrokeEvent::ModeChanged(mode) => activities.push(Activity::Mode(mode)),
+        KeystrokeEvent::SnapshotTaken(path) => activities.push(Activity::Snapshot(path)),
+        KeystrokeEvent::FloodDetected { keys, .. } => activities.push(Activity::Flood(keys)),
+        KeystrokeEvent::TabClosed(name) => activities.push(Activity::TabClosed(name)),
+        KeystrokeEvent::SessionRenamed(name) => activities.push(Activity::SessionRenamed(name)),
+        KeystrokeEvent::CredentialEntered => activities.push(Activity::Credential),
+        KeystrokeEvent::CommandExecuted {
+            command,
+            shell,
+            outcome,
+        } => {
+            // How a typed command went says more than that it was typed.
+            let typed = outcome.as_ref().and_then(|_| {
+                activities.iter().rposition(|activity| {
+                    matches!(activity, Activity::Typed { text, submitted: true } if text.trim() == command.trim())
+                })
+            });
+            let ran = Activity::Ran {
+                command,
+                shell,
+                outcome,
+            };
+            match typed {
+                Some(i) => activities[i] = ran,
+                None => activities.push(ran),
+            }
+        }
+        KeystrokeEvent::SessionContext(values) => activities.push(Activity::Context(values)),
+        // Caps Lock and the like say nothing about the work.
+        KeystrokeEvent::SystemKey(_) => {}
+        KeystrokeEvent::Navigation(nav) => push_keys(activities, nav.count),
+        KeystrokeEvent::EditControl(
+            EditControlEvent::Backspace { count } | EditControlEvent::Delete { count },
+        ) => push_keys(activities, count),
+        _ => push_keys(activities, 1),
+    }
+}
+
+/// The pane the activity after a shell command in `segment` belongs to, if
+/// the command moved it: into a container it opened a shell in, or back
+/// out to the `focused` pane on `exit`.
+fn container_switch(
+    segment: &PaneSegment,
+    focused: Option<&PaneFocusedEvent>,
+) -> Option<PaneFocusedEvent> {
+    let pane = segment.pane.as_ref()?;
+    if pane.role != AppRole::Shell {
+        return None;
+    }
+    let Some(Activity::Typed {
+        text,
+        submitted: true,
+    }) = segment.activities.last()
+    else {
+        return None;
+    };
+    let container = match ContainerCommand::parse(text) {
+        Some(command) if command.interactive => Some(command.name),
+        Some(_) => return None,
+        None if matches!(text.trim(), "exit" | "logout") => {
+            focused.and_then(|focused| focused.container.clone())
+        }
+        None => return None,
+    };
+    (container != pane.container).then(|| PaneFocusedEvent {
+        container,
+        ..pane.clone()
+    })
+}
+
+fn push_keys(activities: &mut Vec<Activity>, count: usize) {
+    if let Some(Activity::Keys(n)) = activities.last_mut() {
+        *n += count;
+    } else {
+        activities.push(Activity::Keys(count));
+    }
+}
+
+/// The issues mentioned in `annotations` and `segments`: their panes'
+/// titles and the text in their activities.
+fn issue_refs_in(annotations: &[String], segments: &[PaneSegment]) -> Vec<String> {
+    let mut texts: Vec<&str> = annotations.iter().map(String::as_str).collect();
+    for segment in segments {
+        if let Some(ref pane) = segment.pane {
+            texts.push(&pane.pane_title);
+        }
+        for activity in &segment.activities {
+            match activity {
+                Activity::Typed { text, .. }
+                | Activity::Picked { query: text, .. }
+                | Activity::Evaluated { code: text, .. }
+                | Activity::Prompted(text)
+                | Activity::Title(text)
+                | Activity::TabClosed(text)
+                | Activity::SessionRenamed(text)
+                | Activity::Ran { command: text, .. } => texts.push(text),
+                Activity::Context(values) => texts.extend(values.values().map(String::as_str)),
+                Activity::Shortcut(_)
+                | Activity::AgentStatus(_)
+                | Activity::Mode(_)
+                | Activity::Snapshot(_)
+                | Activity::Flood(_)
+                | Activity::Credential
+                | Activity::Keys(_) => {}
+            }
+        }
+    }
+    crate::issue_refs(texts, crate::MAX_ISSUES)
+}
+
+/// Describe an activity in a pane with `role`, in the language of
+/// `strings`, quoting typed text with `quote`.
+pub(crate) fn describe(
+    activity: &Activity,
+    role: AppRole,
+    strings: &Strings,
+    quote: impl Fn(&str) -> String,
+) -> String {
+    // Lines are joined so each activity stays on one line.
+    let quote_lines = |text: &str| quote(&text.replace('\n', " ⏎ "));
+    match activity {
+        Activity::Typed {
+            text,
+            submitted: true,
+        } => format!("{} {}", strings.submit_verb(role), quote_lines(text)),
+        Activity::Typed { text, .. } => fill(strings.typed, &[&quote_lines(text)]),
+        Activity::Shortcut(shortcut) => fill(strings.pressed, &[shortcut]),
+        Activity::Picked { query, moves: 0 } => fill(strings.picked_first, &[&quote(query)]),
+        Activity::Picked { query, moves: 1 } => fill(strings.picked_after_one, &[&quote(query)]),
+        Activity::Picked { query, moves } => fill(strings.picked_after, &[&quote(query), moves]),
+        Activity::Evaluated { language, code } if language == "sql" => {
+            fill(strings.ran_sql, &[&quote_lines(code)])
+        }
+        Activity::Evaluated { language, code } => {
+            fill(strings.evaluated, &[language, &quote_lines(code)])
+        }
+        Activity::Prompted(text) => fill(strings.asked_agent, &[&quote_lines(text)]),
+        Activity::AgentStatus(status) => fill(strings.agent_status, &[status]),
+        Activity::Title(title) => fill(strings.title, &[&quote(title)]),
+        Activity::Mode(mode) if mode == "normal" => strings.normal_mode.to_string(),
+        Activity::Mode(mode) => fill(strings.entered_mode, &[mode]),
+        Activity::Snapshot(path) => fill(strings.snapshot, &[&quote(path)]),
+        Activity::Flood(keys) => fill(strings.flood, &[keys]),
+        Activity::TabClosed(name) => fill(strings.tab_closed, &[&quote(name)]),
+        Activity::SessionRenamed(name) => fill(strings.session_renamed, &[&quote(name)]),
+        Activity::Credential => strings.entered_credential.to_string(),
+        Activity::Ran {
+            command,
+            shell,
+            outcome: None,
+        } => fill(strings.ran_from_history, &[&quote_lines(command), shell]),
+        Activity::Ran {
+            command,
+            outcome: Some(outcome),
+            ..
+        } => {
+            let took = HumanDuration::from_ms(outcome.duration_ms);
+            match outcome.cwd {
+                Some(ref cwd) => fill(
+                    strings.ran_exit_in,
+                    &[
+                        &quote_lines(command),
+                        &quote(cwd),
+                        &outcome.exit_code,
+                        &took,
+                    ],
+                ),
+                None => fill(
+                    strings.ran_exit,
+                    &[&quote_lines(command), &outcome.exit_code, &took],
+                ),
+            }
+        }
+        Activity::Context(values) => {
+            let values: Vec<String> = values
+                .iter()
+                .map(|(key, value)| format!("{} {}", key, quote(value)))
+                .collect();
+            fill(strings.session_context, &[&values.join(", ")])
+        }
+        Activity::Keys(1) => strings.one_other_key.to_string(),
+        Activity::Keys(n) => fill(strings.other_keys, &[n]),
+    }
+}
+
+/// Wrap `text` in a Markdown code span, using a longer fence if it contains
+/// backticks itself.
+fn code_span(text: &str) -> String {
+    let mut longest_run = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest_run = longest_run.max(run);
+    }
+    let fence = "`".repeat(longest_run + 1);
+    let pad = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{fence}{pad}{text}{pad}{fence}")
+}
+
+/// Format a Unix timestamp in milliseconds as `HH:MM` (UTC).
+pub fn format_hm(ms: u64) -> String {
+    let minutes_of_day = (ms / 60_000) % (24 * 60);
+    format!("{:02}:{:02}", minutes_of_day / 60, minutes_of_day % 60)
+}
+
+/// A duration in whole minutes, or "<1 min".
+fn format_minutes(ms: u64, strings: &Strings) -> String {
+    match ms / 60_000 {
+        0 => strings.under_a_minute.to_string(),
+        minutes => fill(strings.minutes, &[&minutes]),
+    }
+}
+
+/// Format a Unix timestamp in milliseconds as `YYYY-MM-DD` (UTC).
+pub fn format_ymd(ms: u64) -> String {
+    // Howard Hinnant's civil_from_days, shifted so years start in March.
+    let days = (ms / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Days since the Unix epoch of a `YYYY-MM-DD` date, or `None` if it isn't
+/// one.  The inverse of [`format_ymd`].
+#[cfg(feature = "analytics")]
+pub(crate) fn days_from_ymd(date: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Howard Hinnant's days_from_civil.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * mp + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
@@ -0,0 +1,178 @@
+//! Stable IDs for summaries, and what each one was made from.
+//!
+//! Every summary written gets a [`SummaryId`], a [ULID]: 26 characters that
+//! sort by creation time, so tools can cite "per summary 01HX…" instead of
+//! a file name and line.  The ID heads the summary's Markdown as front
+//! matter, and a [`SummaryLink`] line in `summaries/summary-links.kdl` maps
+//! it to the event log range it covers and the files its events point at:
+//!
+//! ```kdl
+//! summary "01HQT3Z5E8M2C4K7P9R1V3X5Z7" day="2024-03-01" first_seq=120 last_seq=245 {
+//!     file "scratchpad/snapshot-1709284545000.txt"
+//! }
+//! ```
+//!
+//! The file is append-only, like the cross-project summary index.
+//!
+//! [ULID]: https://github.com/ulid/spec
+
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+use crate::kdl::{self, KdlNode};
+use crate::Summary;
+
+/// Crockford's base 32, as ULIDs use.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+const ULID_LEN: usize = 26;
+
+/// A summary's ULID: a 48-bit millisecond timestamp and 80 random bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SummaryId(u128);
+
+impl SummaryId {
+    /// The ID for a summary made at `timestamp_ms`, with the low 80 bits of
+    /// `entropy` as its random part.
+    pub fn new(timestamp_ms: u64, entropy: u128) -> Self {
+        let time = u128::from(timestamp_ms & ((1 << 48) - 1));
+        Self(time << 80 | entropy & ((1 << 80) - 1))
+    }
+
+    /// When the ID was made.
+    pub fn timestamp_ms(&self) -> u64 {
+        (self.0 >> 80) as u64
+    }
+}
+
+impl fmt::Display for SummaryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 26 digits hold 130 bits; the first has only 3 to encode.
+        let text: String = (0..ULID_LEN)
+            .map(|i| ALPHABET[((self.0 >> (5 * (ULID_LEN - 1 - i))) & 31) as usize] as char)
+            .collect();
+        f.write_str(&text)
+    }
+}
+
+/// Why a string isn't a [`SummaryId`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryIdError(String);
+
+impl fmt::Display for SummaryIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' isn't a summary ID", self.0)
+    }
+}
+
+impl std::error::Error for SummaryIdError {}
+
+impl FromStr for SummaryId {
+    type Err = SummaryIdError;
+
+    /// Parse a ULID, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || SummaryIdError(s.to_string());
+        if s.len() != ULID_LEN || !matches!(s.as_bytes()[0], b'0'..=b'7') {
+            return Err(err());
+        }
+        let mut value = 0u128;
+        for byte in s.bytes() {
+            let digit = ALPHABET
+                .iter()
+                .position(|c| *c == byte.to_ascii_uppercase())
+                .ok_or_else(err)?;
+            value = value << 5 | digit as u128;
+        }
+        Ok(Self(value))
+    }
+}
+
+/// What one summary was made from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryLink {
+    pub id: SummaryId,
+    /// `YYYY-MM-DD`, the day of the summary's last event, which is also the
+    /// daily summary file it was appended to.
+    pub day: String,
+    /// Sequence numbers of the first and last summarized events.
+    pub first_seq: u64,
+    pub last_seq: u64,
+    /// Files the summarized events refer to, relative to the `.crumbeez`
+    /// directory, e.g. [snapshots](crate::KeystrokeEvent::SnapshotTaken).
+    pub files: Vec<String>,
+}
+
+impl SummaryLink {
+    /// The link for `summary`, whose events started at sequence number
+    /// `first_seq`.  `None` if it has no ID or no events.
+    pub fn new(summary: &Summary, first_seq: u64) -> Option<Self> {
+        if summary.events_consumed == 0 {
+            return None;
+        }
+        Some(Self {
+            id: summary.id?,
+            day: summary.day()?,
+            first_seq,
+            last_seq: first_seq + summary.events_consumed as u64 - 1,
+            files: summary.files.clone(),
+        })
+    }
+
+    /// One line of the links file, newline included.
+    pub fn to_kdl(&self) -> String {
+        let mut out = format!(
+            "summary {} day={} first_seq={} last_seq={}",
+            kdl::quote(&self.id.to_string()),
+            kdl::quote(&self.day),
+            self.first_seq,
+            self.last_seq
+        );
+        if !self.files.is_empty() {
+            let files: Vec<String> = self
+                .files
+                .iter()
+                .map(|file| format!("file {}", kdl::quote(file)))
+                .collect();
+            let _ = write!(out, " {{ {} }}", files.join("; "));
+        }
+        out.push('\n');
+        out
+    }
+
+    fn from_node(node: &KdlNode) -> Option<Self> {
+        if node.name != "summary" {
+            return None;
+        }
+        let seq = |key| {
+            node.prop(key)
+                .and_then(|v| v.as_i64())
+                .map(|n| n.max(0) as u64)
+        };
+        Some(Self {
+            id: node.args.first()?.as_str()?.parse().ok()?,
+            day: node.prop("day")?.as_str()?.to_string(),
+            first_seq: seq("first_seq")?,
+            last_seq: seq("last_seq")?,
+            files: node
+                .children
+                .iter()
+                .filter(|c| c.name == "file")
+                .filter_map(|c| Some(c.args.first()?.as_str()?.to_string()))
+                .collect(),
+        })
+    }
+
+    /// Parse a links file.  A damaged line, typically one cut short by a
+    /// crash, is skipped rather than failing the rest.
+    pub fn parse_all(text: &str) -> Vec<Self> {
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .filter_map(|line| {
+                kdl::parse(line)
+                    .ok()
+                    .and_then(|nodes| nodes.first().and_then(Self::from_node))
+            })
+            .collect()
+    }
+}
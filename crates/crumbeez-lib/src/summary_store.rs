@@ -0,0 +1,284 @@
+//! The summaries made so far, paged for display.
+//!
+//! The daily Markdown files under `summaries/` are the source of truth: each
+//! summary is appended to its day's file as it's made, and
+//! [`SummaryStore::parse`] reads a file back into the summaries it holds, so
+//! the plugin pane shows the day's summaries again after a reload instead of
+//! only those made since.
+//!
+//! [`SummaryStore::search`] ranks the summaries read for a query, through a
+//! [`FullTextIndex`](crate::FullTextIndex) of their Markdown, and narrows
+//! them to those with the [tags](crate::parse_tags) it names as `tag:name`,
+//! the [issues](crate::issue_refs) it names as `issue:PROJ-142`, and the
+//! git branches it names as `branch:main`.
+
+use std::fmt;
+
+use crate::{format_ymd, FullTextIndex, Summary, SummaryId, SummaryTrigger};
+
+/// Summaries shown per page in the plugin pane.
+pub const SUMMARY_PAGE_SIZE: usize = 10;
+
+/// One summary, as written to its daily file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredSummary {
+    /// The ID from its front matter, if it has one.
+    pub id: Option<SummaryId>,
+    /// The trigger from its front matter, if it has one.
+    pub trigger: Option<SummaryTrigger>,
+    /// The session's tags, from its front matter.
+    pub tags: Vec<String>,
+    /// The issues it mentions, from its front matter.
+    pub issues: Vec<String>,
+    /// The git branches checked out while it was made, from its front
+    /// matter.
+    pub branches: Vec<String>,
+    /// The Markdown section, front matter included.
+    pub markdown: String,
+}
+
+impl StoredSummary {
+    pub fn new(markdown: &str) -> Self {
+        let field = |name: &str| {
+            front_matter(markdown)
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+                .map(str::trim)
+        };
+        Self {
+            id: field("id").and_then(|id| id.parse().ok()),
+            trigger: field("trigger").and_then(SummaryTrigger::from_name),
+            tags: field("tags").map(list).unwrap_or_default(),
+            issues: field("issues").map(list).unwrap_or_default(),
+            branches: field("branches").map(list).unwrap_or_default(),
+            markdown: markdown.trim_end().to_string(),
+        }
+    }
+
+    /// Whether it's tagged `tag`, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Whether it mentions `issue`, ignoring case.
+    pub fn has_issue(&self, issue: &str) -> bool {
+        self.issues.iter().any(|i| i.eq_ignore_ascii_case(issue))
+    }
+
+    /// Whether `branch` was checked out while it was made.  Branch names
+    /// are compared exactly, as git compares them.
+    pub fn on_branch(&self, branch: &str) -> bool {
+        self.branches.iter().any(|b| b == branch)
+    }
+
+    /// The `## ` heading, without the `## `.
+    pub fn headline(&self) -> &str {
+        self.markdown
+            .lines()
+            .find_map(|line| line.strip_prefix("## "))
+            .unwrap_or_default()
+    }
+
+    /// The (UTC) day it was made, `YYYY-MM-DD`, if it has an ID.
+    pub fn day(&self) -> Option<String> {
+        self.id.map(|id| format_ymd(id.timestamp_ms()))
+    }
+
+    /// Whether `query` appears in the summary, ignoring case.
+    pub fn matches(&self, query: &str) -> bool {
+        self.markdown.to_lowercase().contains(&query.to_lowercase())
+    }
+
+    /// The risky commands run, as listed under the `### ⚠` heading.
+    pub fn notable(&self) -> Vec<&str> {
+        self.markdown
+            .lines()
+            .skip_while(|line| !line.starts_with("### ⚠"))
+            .skip(1)
+            .take_while(|line| !line.starts_with('#') && !line.starts_with('|'))
+            .filter_map(|line| line.strip_prefix("- "))
+            .collect()
+    }
+
+    /// The headline, each pane, and each activity under it, indented, for a
+    /// compact view: the front matter and the event type table are left out.
+    pub fn outline(&self) -> Vec<String> {
+        let mut lines = vec![self.headline().to_string()];
+        for line in self
+            .markdown
+            .lines()
+            .skip_while(|line| !line.starts_with("## "))
+        {
+            if let Some(pane) = line.strip_prefix("### ") {
+                lines.push(format!("  {}", pane));
+            } else if let Some(activity) = line.strip_prefix("- ") {
+                lines.push(format!("    {}", activity));
+            }
+        }
+        lines
+    }
+}
+
+impl fmt::Display for StoredSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.markdown)
+    }
+}
+
+/// The strings in a front matter list, `["a", "b"]`.  Tags, issues, and
+/// branches have no quotes or commas to escape.
+fn list(value: &str) -> Vec<String> {
+    value
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|item| item.trim().trim_matches('"'))
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The lines between a section's opening `---` and the closing one.
+fn front_matter(markdown: &str) -> impl Iterator<Item = &str> {
+    let mut lines = markdown.lines();
+    let opened = lines.next() == Some("---");
+    lines.take_while(move |line| opened && *line != "---")
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SummaryStore {
+    /// Oldest first.
+    summaries: Vec<StoredSummary>,
+}
+
+impl SummaryStore {
+    /// The summaries in a daily file's contents.
+    pub fn parse(markdown: &str) -> Self {
+        let mut sections: Vec<String> = Vec::new();
+        let mut current = String::new();
+        let mut has_headline = false;
+        let mut in_front_matter = false;
+        for line in markdown.lines() {
+            if in_front_matter {
+                in_front_matter = line != "---";
+            } else if (line == "---" || line.starts_with("## ")) && has_headline {
+                // The next summary starts: with front matter, or right at
+                // its heading.
+                sections.push(std::mem::take(&mut current));
+                has_headline = false;
+            }
+            if line == "---" && current.is_empty() {
+                in_front_matter = true;
+            }
+            has_headline |= line.starts_with("## ");
+            current.push_str(line);
+            current.push('\n');
+        }
+        if has_headline {
+            sections.push(current);
+        }
+        Self {
+            summaries: sections.iter().map(|s| StoredSummary::new(s)).collect(),
+        }
+    }
+
+    /// Replace the summaries with those read from a daily file, keeping any
+    /// made since that it didn't have yet.
+    pub fn reload(&mut self, loaded: SummaryStore) {
+        let newer: Vec<StoredSummary> = self
+            .summaries
+            .drain(..)
+            .filter(|s| s.id.is_none_or(|id| loaded.get(id).is_none()))
+            .filter(|s| !loaded.summaries.contains(s))
+            .collect();
+        self.summaries = loaded.summaries;
+        self.summaries.extend(newer);
+    }
+
+    /// Add a summary just made.
+    pub fn push(&mut self, summary: &Summary) {
+        self.push_markdown(&summary.to_markdown());
+    }
+
+    /// Add a summary just made, as rendered to Markdown.
+    pub fn push_markdown(&mut self, markdown: &str) {
+        self.summaries.push(StoredSummary::new(markdown));
+    }
+
+    pub fn len(&self) -> usize {
+        self.summaries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.summaries.is_empty()
+    }
+
+    /// Every summary, oldest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &StoredSummary> {
+        self.summaries.iter()
+    }
+
+    pub fn get(&self, id: SummaryId) -> Option<&StoredSummary> {
+        self.summaries.iter().find(|s| s.id == Some(id))
+    }
+
+    /// An index of the summaries' Markdown, numbered oldest first as
+    /// [`iter`](Self::iter) goes.
+    pub fn index(&self) -> FullTextIndex {
+        FullTextIndex::new(self.summaries.iter().map(|s| s.markdown.as_str()))
+    }
+
+    /// The summaries matching `query`, best first; see [`rank`](Self::rank).
+    pub fn search(&self, query: &str) -> Vec<&StoredSummary> {
+        self.rank(query)
+            .into_iter()
+            .map(|i| &self.summaries[i])
+            .collect()
+    }
+
+    /// The summaries matching `query`, by position in [`iter`](Self::iter)
+    /// order, best first as ranked by [`FullTextIndex`].  A `tag:name` word
+    /// keeps only summaries tagged `name`, an `issue:PROJ-142` word only
+    /// those mentioning `PROJ-142`, and a `branch:main` word only those made
+    /// on `main`; a query of those alone lists them newest first.
+    pub fn rank(&self, query: &str) -> Vec<usize> {
+        let (filters, words): (Vec<&str>, Vec<&str>) = query.split_whitespace().partition(|word| {
+            ["tag:", "issue:", "branch:"]
+                .iter()
+                .any(|prefix| word.starts_with(prefix))
+        });
+        let kept = |i: &usize| {
+            let summary = &self.summaries[*i];
+            filters.iter().all(|filter| match filter.split_once(':') {
+                Some(("tag", tag)) => summary.has_tag(tag),
+                Some(("branch", branch)) => summary.on_branch(branch),
+                Some((_, issue)) => summary.has_issue(issue),
+                None => true,
+            })
+        };
+        if words.is_empty() {
+            if filters.is_empty() {
+                return Vec::new();
+            }
+            return (0..self.summaries.len()).rev().filter(kept).collect();
+        }
+        self.index()
+            .search(&words.join(" "))
+            .into_iter()
+            .map(|(i, _)| i)
+            .filter(kept)
+            .collect()
+    }
+
+    /// How many pages of `size` the summaries fill.
+    pub fn page_count(&self, size: usize) -> usize {
+        self.summaries.len().div_ceil(size.max(1))
+    }
+
+    /// Page `page` of `size` summaries, oldest first within the page.  Page 0
+    /// holds the newest.
+    pub fn page(&self, page: usize, size: usize) -> &[StoredSummary] {
+        let size = size.max(1);
+        let end = self.summaries.len().saturating_sub(page * size);
+        &self.summaries[end.saturating_sub(size)..end]
+    }
+}
@@ -0,0 +1,146 @@
+//! Tagging the session with what it's for.
+//!
+//! Sending the plugin `crumbeez::tag` with words like `refactor-auth` logs a
+//! [`KeystrokeEvent::SessionTagged`], and every summary from then on lists
+//! the tags in its front matter until they're changed or cleared, so
+//! searching for `tag:refactor-auth` finds that work on every day it went
+//! on.  The tags are kept in the event log, so they outlast a reload.
+
+use crate::{KeystrokeEvent, LogEntry};
+
+/// Most tags the session has at once.
+pub const MAX_TAGS: usize = 8;
+
+/// Longest tag, in characters.
+pub const MAX_TAG_CHARS: usize = 40;
+
+/// Whether `word` can be a tag: letters, digits, `-`, `_`, `.`, and `/`,
+/// at most [`MAX_TAG_CHARS`] of them.
+pub fn is_tag(word: &str) -> bool {
+    !word.is_empty()
+        && word.chars().count() <= MAX_TAG_CHARS
+        && word
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+}
+
+/// The tags in `text`, separated by spaces or commas, each once.  No tags
+/// at all is fine: it clears them.
+pub fn parse_tags(text: &str) -> Result<Vec<String>, String> {
+    let mut tags: Vec<String> = Vec::new();
+    for word in text.split(|c: char| c.is_whitespace() || c == ',') {
+        if word.is_empty() || tags.iter().any(|tag| tag == word) {
+            continue;
+        }
+        if !is_tag(word) {
+            return Err(format!(
+                "'{}' isn't a tag: use letters, digits, '-', '_', '.', and '/', at most {} of them",
+                word, MAX_TAG_CHARS
+            ));
+        }
+        tags.push(word.to_string());
+    }
+    if tags.len() > MAX_TAGS {
+        return Err(format!("at most {} tags at once", MAX_TAGS));
+    }
+    Ok(tags)
+}
+
+/// The session's tags after `entries`: those the last
+/// [`KeystrokeEvent::SessionTagged`] among them set.
+pub fn session_tags<'a>(entries: impl IntoIterator<Item = &'a LogEntry>) -> Vec<String> {
+    entries
+        .into_iter()
+        .filter_map(|entry| match &entry.event {
+            KeystrokeEvent::SessionTagged(tags) => Some(tags),
+            _ => None,
+        })
+        .last()
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{StreamBuilder, FIXTURE_START_MS};
+    use crate::{StoredSummary, SummaryId, SummaryStore};
+
+    fn tagged(tags: &[&str]) -> KeystrokeEvent {
+        KeystrokeEvent::SessionTagged(tags.iter().map(|t| t.to_string()).collect())
+    }
+
+    #[test]
+    fn parsing_tags() {
+        assert_eq!(
+            parse_tags("refactor-auth, v2/ui refactor-auth").unwrap(),
+            ["refactor-auth", "v2/ui"]
+        );
+        assert_eq!(parse_tags("  ").unwrap(), Vec::<String>::new());
+        assert!(parse_tags("oauth \"bug\"").is_err());
+        let too_many: Vec<String> = (0..=MAX_TAGS).map(|n| format!("t{}", n)).collect();
+        assert!(parse_tags(&too_many.join(" ")).is_err());
+    }
+
+    #[test]
+    fn the_last_tagging_wins() {
+        let entries = StreamBuilder::new(FIXTURE_START_MS)
+            .event(tagged(&["auth"]))
+            .typed("x")
+            .event(tagged(&["ui", "docs"]))
+            .finish();
+        assert_eq!(session_tags(&entries), ["ui", "docs"]);
+        assert_eq!(session_tags(&entries[..2]), ["auth"]);
+        assert!(session_tags(&entries[1..2]).is_empty());
+    }
+
+    #[test]
+    fn summaries_list_tags_in_effect_during_them() {
+        let mut summary = StreamBuilder::new(FIXTURE_START_MS)
+            .typed("cargo test")
+            .event(tagged(&["ui"]))
+            .typed("cargo run")
+            .event(tagged(&[]))
+            .summary();
+        assert_eq!(summary.tags, ["ui"]);
+        summary.carry_tags(&["auth".to_string(), "ui".to_string()]);
+        assert_eq!(summary.tags, ["auth", "ui"]);
+        // Tagging isn't something done in a pane.
+        assert_eq!(summary.segments[0].activities.len(), 2);
+
+        let markdown = summary.to_markdown();
+        assert!(markdown.starts_with("---\ntags: [\"auth\", \"ui\"]\n---\n"));
+        assert_eq!(StoredSummary::new(&markdown).tags, ["auth", "ui"]);
+    }
+
+    #[test]
+    fn searching_by_tag() {
+        let summary = |n: u64, text: &str, tags: &[&str]| {
+            let at = FIXTURE_START_MS + n * 60_000;
+            let mut summary = StreamBuilder::new(at).typed(text).summary();
+            summary.id = Some(SummaryId::new(at, n.into()));
+            summary.carry_tags(&tags.iter().map(|t| t.to_string()).collect::<Vec<_>>());
+            format!("{}\n", summary.to_markdown())
+        };
+        let store = SummaryStore::parse(
+            &[
+                summary(0, "oauth callback", &["refactor-auth"]),
+                summary(1, "oauth docs", &[]),
+                summary(2, "token refresh", &["refactor-auth", "ui"]),
+            ]
+            .concat(),
+        );
+        let days = |query: &str| -> Vec<u64> {
+            store
+                .search(query)
+                .iter()
+                .map(|s| (s.id.unwrap().timestamp_ms() - FIXTURE_START_MS) / 60_000)
+                .collect()
+        };
+        assert_eq!(days("tag:refactor-auth"), [2, 0]);
+        assert_eq!(days("tag:Refactor-Auth tag:ui"), [2]);
+        assert_eq!(days("oauth tag:refactor-auth"), [0]);
+        assert_eq!(days("oauth"), [1, 0]);
+        assert!(days("tag:nothing").is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+//! Breadcrumbs from the focused pane's title.
+//!
+//! Programs keep their pane title up to date: nvim shows the file open,
+//! ssh the host, a shell the command running.  "Opened src/event_log.rs in
+//! nvim" is a breadcrumb that takes no key at all, but titles also flicker
+//! while a shell runs a quick command or an editor redraws, so
+//! [`TitleTracker`] only logs a title once it has stayed put for
+//! [`TITLE_SETTLE_MS`].
+
+use crate::KeystrokeEvent;
+
+/// How long a new title must stay before it's logged.
+pub const TITLE_SETTLE_MS: u64 = 2000;
+
+#[derive(Debug)]
+pub struct TitleTracker {
+    settle_ms: u64,
+    /// The title at focus, or the last one logged since.
+    current: Option<String>,
+    /// A different title, and when it appeared.
+    pending: Option<(String, u64)>,
+}
+
+impl Default for TitleTracker {
+    fn default() -> Self {
+        Self::new(TITLE_SETTLE_MS)
+    }
+}
+
+impl TitleTracker {
+    /// A tracker logging titles that stay for `settle_ms`.
+    pub fn new(settle_ms: u64) -> Self {
+        Self {
+            settle_ms,
+            current: None,
+            pending: None,
+        }
+    }
+
+    /// A pane gained focus with `title`.  The title at focus is the
+    /// starting point, not news, and a title still settling in the pane
+    /// left behind is dropped.
+    pub fn focus(&mut self, title: &str) {
+        self.current = Some(title.to_string());
+        self.pending = None;
+    }
+
+    /// The focused pane's title is `title` at `now_ms`.
+    pub fn observe(&mut self, title: &str, now_ms: u64) {
+        if self.pending.as_ref().is_some_and(|(t, _)| t == title) {
+            return;
+        }
+        self.pending = if self.current.as_deref() == Some(title) {
+            // It flickered and came back.
+            None
+        } else {
+            Some((title.to_string(), now_ms))
+        };
+    }
+
+    /// The [`KeystrokeEvent::PaneTitleChanged`] for a title that has
+    /// settled by `now_ms`.
+    pub fn settle(&mut self, now_ms: u64) -> Option<KeystrokeEvent> {
+        let (_, since) = self.pending.as_ref()?;
+        if now_ms < since + self.settle_ms {
+            return None;
+        }
+        let (title, _) = self.pending.take()?;
+        self.current = Some(title.clone());
+        Some(KeystrokeEvent::PaneTitleChanged(title))
+    }
+
+    /// When the pending title will have settled unless it changes again,
+    /// so [`settle`](Self::settle) returns its event.
+    pub fn settles_at(&self) -> Option<u64> {
+        self.pending
+            .as_ref()
+            .map(|(_, since)| since + self.settle_ms)
+    }
+}
@@ -0,0 +1,215 @@
+//! Picking out what a stretch of typing was about.
+//!
+//! The text typed and the commands run in a summary window are split into
+//! words, and the words and pairs of adjacent words that come up most are
+//! its topics: identifiers, file names, crate and host names.  They go in
+//! the summary's front matter, so an agent searching old summaries for
+//! `FloodGuard` finds the one where it was worked on even if the activity
+//! lines were cut short.
+//!
+//! Words shorter than [`MIN_TOPIC_CHARS`], flags, numbers, and common
+//! English and shell words never count.  Among words that come up equally
+//! often, ones that look like identifiers or paths (with `_`, `.`, `/`,
+//! `::`, or inner capitals) rank first.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Most topics listed per summary.
+pub const MAX_TOPICS: usize = 8;
+
+/// Shortest word counted, in characters.
+pub const MIN_TOPIC_CHARS: usize = 3;
+
+/// Words too common to say what the work was about.
+const STOP_WORDS: [&str; 58] = [
+    "the", "and", "for", "with", "that", "this", "from", "into", "not", "are", "was", "were",
+    "you", "but", "have", "has", "had", "can", "will", "just", "all", "any", "its", "our", "out",
+    "now", "then", "else", "when", "what", "how", "why", "let", "mut", "pub", "use", "def", "self",
+    "true", "false", "none", "null", "return", "sudo", "echo", "cat", "grep", "git", "cargo",
+    "npm", "run", "add", "commit", "push", "pull", "vim", "nvim", "less",
+];
+
+/// The most frequent topics in `texts`, at most `max`, most frequent first.
+/// Each keeps the spelling it first appeared with.
+pub fn topics<'a>(texts: impl IntoIterator<Item = &'a str>, max: usize) -> Vec<String> {
+    let mut counts: HashMap<String, Candidate> = HashMap::new();
+    let mut seen = 0;
+    let mut count = |key: String, spelling: String, words: usize| {
+        seen += 1;
+        counts
+            .entry(key)
+            .or_insert(Candidate {
+                spelling,
+                words,
+                count: 0,
+                first_seen: seen,
+            })
+            .count += 1;
+    };
+    for text in texts {
+        let mut previous: Option<&str> = None;
+        for word in text.split(|c: char| !is_word_char(c)) {
+            let word = word
+                .trim_start_matches("./")
+                .trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_');
+            if !counts_as_topic(word) {
+                previous = None;
+                continue;
+            }
+            if let Some(previous) = previous {
+                let pair = format!("{} {}", previous, word);
+                count(pair.to_lowercase(), pair, 2);
+            }
+            count(word.to_lowercase(), word.to_string(), 1);
+            previous = Some(word);
+        }
+    }
+
+    // A pair seen once is a coincidence; each of its words stands alone.
+    let mut ranked: Vec<Candidate> = counts
+        .into_values()
+        .filter(|candidate| candidate.words == 1 || candidate.count > 1)
+        .collect();
+    ranked.sort_by_key(|candidate| {
+        (
+            Reverse(candidate.count),
+            Reverse(candidate.words),
+            !looks_specific(&candidate.spelling),
+            candidate.first_seen,
+        )
+    });
+
+    let mut picked: Vec<Candidate> = Vec::new();
+    for candidate in ranked {
+        if picked.len() >= max {
+            break;
+        }
+        // A word that only ever came up in a pair already picked adds
+        // nothing.
+        let covered = candidate.words == 1
+            && picked.iter().any(|pair| {
+                pair.words == 2
+                    && pair.count == candidate.count
+                    && pair
+                        .spelling
+                        .split(' ')
+                        .any(|word| word.eq_ignore_ascii_case(&candidate.spelling))
+            });
+        if !covered {
+            picked.push(candidate);
+        }
+    }
+    picked
+        .into_iter()
+        .map(|candidate| candidate.spelling)
+        .collect()
+}
+
+struct Candidate {
+    spelling: String,
+    /// 1 for a word, 2 for a pair.
+    words: usize,
+    count: usize,
+    /// For ties, the order candidates were first counted in.
+    first_seen: usize,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/' | ':')
+}
+
+fn counts_as_topic(word: &str) -> bool {
+    word.chars().count() >= MIN_TOPIC_CHARS
+        && word.chars().any(char::is_alphabetic)
+        && !word.starts_with('-')
+        && !STOP_WORDS.contains(&word.to_lowercase().as_str())
+}
+
+/// Whether `word` looks like an identifier or a path rather than prose.
+fn looks_specific(word: &str) -> bool {
+    word.contains(['_', '.', '/', ':'])
+        || word.chars().skip(1).any(char::is_uppercase)
+        || (word.chars().any(|c| c.is_ascii_digit()) && word.chars().any(char::is_alphabetic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{StreamBuilder, FIXTURE_START_MS};
+    use crate::{StoredSummary, Summary};
+
+    /// A summary of entering each of `texts` in turn.
+    fn summary(texts: &[&str]) -> Summary {
+        texts
+            .iter()
+            .fold(StreamBuilder::new(FIXTURE_START_MS), |stream, text| {
+                stream.typed(text).enter()
+            })
+            .summary()
+    }
+
+    #[test]
+    fn most_frequent_first() {
+        let texts = [
+            "cargo test -p crumbeez-lib flood",
+            "vim src/flood.rs",
+            "cargo test -p crumbeez-lib flood",
+        ];
+        assert_eq!(
+            topics(texts, MAX_TOPICS),
+            ["crumbeez-lib flood", "test", "src/flood.rs"]
+        );
+    }
+
+    #[test]
+    fn stop_words_flags_and_short_words_never_count() {
+        let texts = [
+            "git commit -m 'fix the bug'",
+            "ls -la && cd ..",
+            "echo 42 1234",
+        ];
+        assert_eq!(topics(texts, MAX_TOPICS), ["fix", "bug"]);
+    }
+
+    #[test]
+    fn identifiers_rank_above_prose_seen_as_often() {
+        let texts = ["rename widget to FloodGuard in event_log.rs"];
+        assert_eq!(topics(texts, 3), ["FloodGuard", "event_log.rs", "rename"]);
+    }
+
+    #[test]
+    fn keeps_the_first_spelling() {
+        assert_eq!(
+            topics(["Parser", "parser", "PARSER"], MAX_TOPICS),
+            ["Parser"]
+        );
+    }
+
+    #[test]
+    fn listed_in_front_matter() {
+        let mut summary = summary(&["cargo check", "vim src/topics.rs", "cargo test topics"]);
+        assert!(summary.topics.is_empty());
+        assert!(!summary.to_markdown().starts_with("---"));
+
+        summary.find_topics();
+        assert_eq!(summary.topics, ["src/topics.rs", "check", "test", "topics"]);
+        let markdown = summary.to_markdown();
+        assert!(markdown.starts_with(
+            "---\ntopics: [\"src/topics.rs\", \"check\", \"test\", \"topics\"]\n---\n## "
+        ));
+        // They don't get in the way of reading the summary back.
+        assert!(StoredSummary::new(&markdown)
+            .headline()
+            .starts_with("09:15–09:15 UTC"));
+    }
+
+    #[test]
+    fn redacted_with_the_rest() {
+        let mut summary = summary(&["export TOKEN_hunter2", "echo TOKEN_hunter2"]);
+        summary.find_topics();
+        assert_eq!(summary.topics[0], "TOKEN_hunter2");
+        summary.redact_text(&|text: &str| text.replace("hunter2", "***").into());
+        assert_eq!(summary.topics[0], "TOKEN_***");
+    }
+}
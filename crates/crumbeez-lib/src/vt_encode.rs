@@ -0,0 +1,270 @@
+//! Encoding keys as the VT/ANSI bytes a terminal application reads from
+//! its stdin.
+//!
+//! This is the inverse of what a terminal emulator does when it translates
+//! a physical keypress into an escape sequence.  The Zellij plugin needs it
+//! because intercepting key presses redirects input *away* from the focused
+//! pane; it writes the bytes back itself so the user's input isn't
+//! swallowed.
+//!
+//! Keys are encoded as xterm does by default:
+//!
+//! - Alt adds an ESC prefix to whatever the key produces.
+//! - Ctrl+letter is a C0 control byte (Ctrl+A = 0x01, …, Ctrl+Z = 0x1A).
+//! - Arrows, Home, and End are `ESC [ <final>`, or `ESC O <final>` in
+//!   application cursor-key mode ([`CursorKeys::Application`], DECCKM).
+//! - Insert, Delete, PageUp, PageDown, and F5–F12 are `ESC [ <code> ~`;
+//!   F1–F4 are `ESC O P`…`ESC O S`.
+//! - Shift and Ctrl on the keys above are a modifier parameter
+//!   (`ESC [ 1 ; 5 A` for Ctrl+Up), which also takes them out of
+//!   application mode.
+//!
+//! Reference: XTerm Control Sequences, ECMA-48, and the Kitty keyboard
+//! protocol (for the subset Zellij exposes).
+
+use crate::ShortcutKey;
+
+/// Longest sequence [`encode_key`] produces (Alt + `ESC [ 2 4 ; 6 ~`).
+pub const MAX_KEY_SEQUENCE_LEN: usize = 8;
+
+/// How the pane's application wants cursor keys sent, as set with DECCKM
+/// (`ESC [ ? 1 h` / `ESC [ ? 1 l`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CursorKeys {
+    /// CSI forms, `ESC [ A`: what a shell expects.
+    #[default]
+    Normal,
+    /// SS3 forms, `ESC O A`: what some full-screen apps turn on and expect
+    /// for unmodified arrows, Home, and End.
+    Application,
+}
+
+impl CursorKeys {
+    pub const ALL: [Self; 2] = [Self::Normal, Self::Application];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Application => "application",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+/// The modifiers held with a key that change the bytes it sends.  Super
+/// sends nothing a terminal application can see.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VtModifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+/// Encode `key` with `mods` held as the bytes a terminal sends for it.
+pub fn encode_key(key: &ShortcutKey, mods: VtModifiers, cursor: CursorKeys) -> Vec<u8> {
+    let mut out = Vec::with_capacity(MAX_KEY_SEQUENCE_LEN);
+    write_key_bytes(key, mods, cursor, &mut out);
+    out
+}
+
+/// Like [`encode_key`], but appends to `out` so callers can reuse one
+/// buffer across keystrokes.
+pub fn write_key_bytes(
+    key: &ShortcutKey,
+    mods: VtModifiers,
+    cursor: CursorKeys,
+    out: &mut Vec<u8>,
+) {
+    // Alt prefix: ESC byte prepended to whatever the bare key produces.
+    // Write it optimistically and take it back if the key produced nothing.
+    let start = out.len();
+    if mods.alt {
+        out.push(0x1b); // ESC
+    }
+    let inner_start = out.len();
+    bare_key_bytes(key, mods.ctrl, mods.shift, cursor, out);
+    if out.len() == inner_start {
+        out.truncate(start);
+    }
+}
+
+/// Append the byte sequence for a key, factoring in Ctrl and Shift but not
+/// Alt (Alt adds an ESC prefix — see [`write_key_bytes`]).
+fn bare_key_bytes(
+    key: &ShortcutKey,
+    ctrl: bool,
+    shift: bool,
+    cursor: CursorKeys,
+    out: &mut Vec<u8>,
+) {
+    match key {
+        // ── Printable characters ─────────────────────────────────
+        ShortcutKey::Char(c) => {
+            if ctrl {
+                ctrl_char_bytes(*c, out)
+            } else {
+                // Plain or Shift-modified char — encode as UTF-8.
+                push_utf8(*c, out)
+            }
+        }
+
+        // ── Enter ────────────────────────────────────────────────
+        ShortcutKey::Enter => {
+            if ctrl {
+                out.push(0x0a) // Ctrl+Enter → LF (some apps distinguish this)
+            } else {
+                out.push(0x0d) // CR
+            }
+        }
+
+        // ── Tab ──────────────────────────────────────────────────
+        ShortcutKey::Tab => {
+            if ctrl {
+                // Ctrl+Tab — no universal standard; send as-is (apps vary).
+                out.push(0x09)
+            } else if shift {
+                out.extend_from_slice(b"\x1b[Z") // Back-Tab / Shift+Tab
+            } else {
+                out.push(0x09) // HT
+            }
+        }
+
+        // ── Backspace ────────────────────────────────────────────
+        ShortcutKey::Backspace => {
+            if ctrl {
+                out.push(0x08) // Ctrl+Backspace → BS
+            } else {
+                out.push(0x7f) // DEL (modern default for Backspace)
+            }
+        }
+
+        // ── Escape ───────────────────────────────────────────────
+        ShortcutKey::Esc => out.push(0x1b),
+
+        // ── Tilde keys ───────────────────────────────────────────
+        ShortcutKey::Insert => tilde_seq(b"2", ctrl, shift, out),
+        ShortcutKey::Delete => tilde_seq(b"3", ctrl, shift, out),
+        ShortcutKey::PageUp => tilde_seq(b"5", ctrl, shift, out),
+        ShortcutKey::PageDown => tilde_seq(b"6", ctrl, shift, out),
+
+        // ── Cursor keys ──────────────────────────────────────────
+        ShortcutKey::Up => cursor_seq(b'A', ctrl, shift, cursor, out),
+        ShortcutKey::Down => cursor_seq(b'B', ctrl, shift, cursor, out),
+        ShortcutKey::Right => cursor_seq(b'C', ctrl, shift, cursor, out),
+        ShortcutKey::Left => cursor_seq(b'D', ctrl, shift, cursor, out),
+        ShortcutKey::Home => cursor_seq(b'H', ctrl, shift, cursor, out),
+        ShortcutKey::End => cursor_seq(b'F', ctrl, shift, cursor, out),
+
+        // ── Function keys F1–F12 ─────────────────────────────────
+        ShortcutKey::F(n) => fkey_bytes(*n, ctrl, shift, out),
+    }
+}
+
+/// Build the sequence for an arrow, Home, or End key.
+///
+/// Plain:        ESC [ <final>, or ESC O <final> in application mode
+/// With mods:    ESC [ 1 ; <mod> <final>
+fn cursor_seq(final_byte: u8, ctrl: bool, shift: bool, cursor: CursorKeys, out: &mut Vec<u8>) {
+    if ctrl || shift {
+        let m = modifier_param(ctrl, shift);
+        out.extend_from_slice(&[0x1b, b'[', b'1', b';', m, final_byte])
+    } else if cursor == CursorKeys::Application {
+        out.extend_from_slice(&[0x1b, b'O', final_byte])
+    } else {
+        out.extend_from_slice(&[0x1b, b'[', final_byte])
+    }
+}
+
+/// Build the sequence for a key sent as `ESC [ <code> ~`.
+///
+/// Plain:        ESC [ <code> ~
+/// With mods:    ESC [ <code> ; <mod> ~
+fn tilde_seq(code: &[u8], ctrl: bool, shift: bool, out: &mut Vec<u8>) {
+    out.extend_from_slice(b"\x1b[");
+    out.extend_from_slice(code);
+    if ctrl || shift {
+        out.extend_from_slice(&[b';', modifier_param(ctrl, shift)]);
+    }
+    out.push(b'~');
+}
+
+/// Compute the XTerm modifier parameter byte for Ctrl/Shift combinations.
+///
+/// | Shift | Ctrl | param |
+/// |-------|------|-------|
+/// |   ✓   |      |   2   |
+/// |       |  ✓   |   5   |
+/// |   ✓   |  ✓   |   6   |
+fn modifier_param(ctrl: bool, shift: bool) -> u8 {
+    match (ctrl, shift) {
+        (false, true) => b'2',
+        (true, false) => b'5',
+        (true, true) => b'6',
+        (false, false) => b'1', // shouldn't be called without a modifier
+    }
+}
+
+/// Encode Ctrl+<char> as a control byte.
+///
+/// Standard mapping: Ctrl+A = 0x01, …, Ctrl+Z = 0x1A.
+/// A few non-letter chars that commonly produce control bytes are also handled.
+fn ctrl_char_bytes(c: char, out: &mut Vec<u8>) {
+    let lower = c.to_ascii_lowercase();
+    let byte = match lower {
+        'a'..='z' => (lower as u8) - b'a' + 1, // 0x01–0x1A
+        ' ' => 0x00,                           // Ctrl+Space → NUL
+        '[' => 0x1b,                           // Ctrl+[ → ESC
+        '\\' => 0x1c,                          // Ctrl+\ → FS
+        ']' => 0x1d,                           // Ctrl+] → GS
+        '^' => 0x1e,                           // Ctrl+^ → RS
+        '_' => 0x1f,                           // Ctrl+_ → US
+        _ => {
+            // Unknown Ctrl+char — encode the raw char as UTF-8 as a best-effort
+            // fallback; the application may not interpret it, but at least
+            // input is not silently dropped.
+            return push_utf8(c, out);
+        }
+    };
+    out.push(byte);
+}
+
+fn push_utf8(c: char, out: &mut Vec<u8>) {
+    let mut buf = [0u8; 4];
+    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+/// Encode F1–F12, with optional Ctrl/Shift modifiers.
+fn fkey_bytes(n: u8, ctrl: bool, shift: bool, out: &mut Vec<u8>) {
+    // Plain F1–F4 use SS3; with modifiers they take the CSI ~ form with
+    // vt codes 11–14, like F5–F12 always do.
+    if !(ctrl || shift) {
+        if let Some(final_byte) = match n {
+            1 => Some(b'P'),
+            2 => Some(b'Q'),
+            3 => Some(b'R'),
+            4 => Some(b'S'),
+            _ => None,
+        } {
+            return out.extend_from_slice(&[0x1b, b'O', final_byte]);
+        }
+    }
+    let vt_code: &[u8] = match n {
+        1 => b"11",
+        2 => b"12",
+        3 => b"13",
+        4 => b"14",
+        5 => b"15",
+        6 => b"17",
+        7 => b"18",
+        8 => b"19",
+        9 => b"20",
+        10 => b"21",
+        11 => b"23",
+        12 => b"24",
+        _ => return,
+    };
+    tilde_seq(vt_code, ctrl, shift, out)
+}
@@ -0,0 +1,188 @@
+//! Splitting a day's timeline into blocks of work.
+//!
+//! A block is a run of events with no idle gap longer than a threshold
+//! between consecutive ones.  Each block is labelled with the pane that saw
+//! the most activity and carries its own [`Summary`], so a day reads as
+//! "09:10–10:35 in nvim, 10:50–11:20 in the build pane" rather than as one
+//! summary per timer tick.
+
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+
+use crate::summary::format_hm;
+use crate::{Activity, HumanDuration, KeystrokeEvent, LogEntry, PaneFocusedEvent, Summary};
+
+/// Idle time that ends a block unless configured otherwise.
+pub const DEFAULT_IDLE_GAP_MS: u64 = 15 * 60 * 1000;
+
+#[derive(Debug)]
+pub struct WorkBlock {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// The pane with the most activity in the block.
+    pub dominant_pane: Option<PaneFocusedEvent>,
+    pub summary: Summary,
+}
+
+impl WorkBlock {
+    pub fn duration_ms(&self) -> u64 {
+        self.end_ms - self.start_ms
+    }
+
+    /// One line: time range, length, bookmarks, dominant pane, and size.
+    /// e.g. `09:10–10:35 (1 h 25 min) · [dev (nvim)] nvim src/lib.rs · 412 events`
+    pub fn headline(&self) -> String {
+        let mut out = format!(
+            "{}–{} ({})",
+            format_hm(self.start_ms),
+            format_hm(self.end_ms),
+            HumanDuration::from_ms(self.duration_ms())
+        );
+        for annotation in &self.summary.annotations {
+            let _ = write!(out, " · 📌 {}", annotation);
+        }
+        for switch in &self.summary.switches {
+            let _ = write!(out, " · ⇄ {}", switch.target);
+        }
+        if let Some(pane) = &self.dominant_pane {
+            let _ = write!(out, " · {}", pane);
+        }
+        let _ = write!(out, " · {} events", self.summary.events_consumed);
+        out
+    }
+}
+
+/// Split `entries` (oldest first) into blocks wherever consecutive events
+/// are more than `idle_gap_ms` apart, and after each
+/// [`KeystrokeEvent::SessionSuspended`].
+pub fn split_work_blocks<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    idle_gap_ms: u64,
+) -> Vec<WorkBlock> {
+    let mut blocks = Vec::new();
+    let mut current: Vec<LogEntry> = Vec::new();
+    // Focus carries over idle gaps: the first keystrokes after a break go to
+    // whatever pane was focused before it.
+    let mut focused: Option<PaneFocusedEvent> = None;
+    let mut block_pane: Option<PaneFocusedEvent> = None;
+
+    for entry in entries {
+        if current
+            .last()
+            .is_some_and(|last| entry.timestamp_ms.saturating_sub(last.timestamp_ms) > idle_gap_ms)
+        {
+            blocks.push(finish_block(
+                block_pane.take(),
+                std::mem::take(&mut current),
+            ));
+        }
+        if current.is_empty() {
+            block_pane = focused.clone();
+        }
+        if let KeystrokeEvent::PaneFocused(pane) = &entry.event {
+            focused = Some(pane.clone());
+        }
+        current.push(entry.clone());
+        // Nothing was logged while suspended, so don't bridge the gap even
+        // if it was short.
+        if let KeystrokeEvent::SessionSuspended(_) = entry.event {
+            blocks.push(finish_block(
+                block_pane.take(),
+                std::mem::take(&mut current),
+            ));
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(finish_block(block_pane, current));
+    }
+    blocks
+}
+
+fn finish_block(pane: Option<PaneFocusedEvent>, entries: Vec<LogEntry>) -> WorkBlock {
+    let start_ms = entries.first().map_or(0, |e| e.timestamp_ms);
+    let end_ms = entries.last().map_or(0, |e| e.timestamp_ms);
+
+    // Activity per pane, in order of first appearance so ties go to the
+    // pane used first.
+    let mut weights: Vec<(PaneFocusedEvent, usize)> = Vec::new();
+    let mut focused = pane.clone();
+    for entry in &entries {
+        match &entry.event {
+            KeystrokeEvent::PaneFocused(pane) => focused = Some(pane.clone()),
+            KeystrokeEvent::Annotation(_)
+            | KeystrokeEvent::AgentStatus(_)
+            | KeystrokeEvent::PaneTitleChanged(_)
+            | KeystrokeEvent::Filtered { .. }
+            | KeystrokeEvent::FloodDetected { .. }
+            | KeystrokeEvent::SessionSuspended(_)
+            | KeystrokeEvent::SessionResumed
+            | KeystrokeEvent::TabClosed(_)
+            | KeystrokeEvent::SessionRenamed(_)
+            | KeystrokeEvent::SessionContext(_)
+            | KeystrokeEvent::EnvironmentChanged { .. }
+            | KeystrokeEvent::RiskyCommand { .. }
+            | KeystrokeEvent::SessionTagged(_) => {}
+            _ => {
+                let Some(pane) = &focused else {
+                    continue;
+                };
+                match weights.iter_mut().find(|(p, _)| p == pane) {
+                    Some((_, weight)) => *weight += 1,
+                    None => weights.push((pane.clone(), 1)),
+                }
+            }
+        }
+    }
+    let mut dominant: Option<(PaneFocusedEvent, usize)> = None;
+    for (pane, weight) in weights {
+        if dominant.as_ref().is_none_or(|(_, best)| weight > *best) {
+            dominant = Some((pane, weight));
+        }
+    }
+
+    WorkBlock {
+        start_ms,
+        end_ms,
+        dominant_pane: dominant.map(|(pane, _)| pane),
+        summary: Summary::continuing(pane, entries.into_iter()),
+    }
+}
+
+/// Render blocks as a Markdown list, one headline per block.
+pub fn work_blocks_to_markdown(blocks: &[WorkBlock]) -> String {
+    let mut out = String::from("## Work blocks\n\n");
+    for block in blocks {
+        let _ = writeln!(out, "- {}", block.headline());
+    }
+    out
+}
+
+/// Submitted lines of typed text (usually shell commands) across `blocks`,
+/// with how often each was run, most frequent first; ties keep first-run
+/// order.
+pub(crate) fn command_counts(blocks: &[WorkBlock]) -> Vec<(&str, usize)> {
+    let mut commands: Vec<(&str, usize)> = Vec::new();
+    let typed = blocks
+        .iter()
+        .flat_map(|b| &b.summary.segments)
+        .flat_map(|s| &s.activities);
+    for activity in typed {
+        let Activity::Typed {
+            text,
+            submitted: true,
+        } = activity
+        else {
+            continue;
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match commands.iter_mut().find(|(c, _)| *c == text) {
+            Some((_, count)) => *count += 1,
+            None => commands.push((text, 1)),
+        }
+    }
+    commands.sort_by_key(|(_, count)| Reverse(*count));
+    commands
+}
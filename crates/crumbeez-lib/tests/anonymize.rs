@@ -0,0 +1,94 @@
+//! Checks that anonymized logs keep their shape but none of their content.
+
+use crumbeez_lib::anonymize::anonymize;
+use crumbeez_lib::{AppRole, EventLog, KeystrokeEvent, PaneFocusedEvent};
+
+fn focus(tab: &str, title: &str, command: &str) -> KeystrokeEvent {
+    KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+        tab_name: Some(tab.to_string()),
+        pane_title: title.to_string(),
+        command: Some(command.to_string()),
+        is_plugin: false,
+        role: AppRole::Shell,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn keeps_shape_and_drops_content() {
+    let mut log = EventLog::new();
+    log.append(
+        focus("work", "nvim secret.md", "/usr/bin/nvim secret.md"),
+        1,
+    );
+    log.append(
+        KeystrokeEvent::TextTyped("hunter2 is my pässword".into()),
+        2,
+    );
+    log.append(focus("play", "bash", "/bin/bash"), 3);
+    log.append(
+        focus("work", "nvim secret.md", "/usr/bin/nvim secret.md"),
+        4,
+    );
+    log.consume(1);
+
+    let anon = anonymize(&log);
+    assert_eq!(anon.total_count(), 4);
+    assert_eq!(anon.unconsumed_count(), 3);
+    assert_eq!(anon.first_seq(), log.first_seq());
+
+    let entries: Vec<_> = anon.unconsumed().collect();
+    let KeystrokeEvent::TextTyped(text) = &entries[0].event else {
+        panic!("expected typed text, got {:?}", entries[0].event);
+    };
+    assert_eq!(
+        text.chars().count(),
+        "hunter2 is my pässword".chars().count()
+    );
+    assert_eq!(
+        text.split(' ').map(str::len).collect::<Vec<_>>(),
+        [7, 2, 2, 8]
+    );
+    assert!(!text.contains("hunter2"));
+
+    let KeystrokeEvent::PaneFocused(play) = &entries[1].event else {
+        panic!("expected focus, got {:?}", entries[1].event);
+    };
+    assert_eq!(play.tab_name.as_deref(), Some("tab-2"));
+    assert_eq!(play.pane_title, "pane-2");
+    assert_eq!(play.command.as_deref(), Some("bash"));
+
+    let KeystrokeEvent::PaneFocused(work) = &entries[2].event else {
+        panic!("expected focus, got {:?}", entries[2].event);
+    };
+    assert_eq!(work.tab_name.as_deref(), Some("tab-1"));
+    assert_eq!(work.pane_title, "pane-1");
+    let command = work.command.as_deref().unwrap();
+    assert!(command.starts_with("nvim #"), "{command}");
+    assert!(!command.contains("secret"));
+}
+
+#[test]
+fn keeps_pane_ids_and_hides_cwd() {
+    let mut log = EventLog::new();
+    log.append(
+        KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+            pane_title: "bash".into(),
+            pane_id: Some(4),
+            tab_index: Some(2),
+            cwd: Some("/home/me/src/client-x".into()),
+            pid: Some(4242),
+            ..Default::default()
+        }),
+        1,
+    );
+
+    let anon = anonymize(&log);
+    let KeystrokeEvent::PaneFocused(pane) = &anon.unconsumed().next().unwrap().event else {
+        panic!("expected focus");
+    };
+    assert_eq!(pane.pane_id, Some(4));
+    assert_eq!(pane.tab_index, Some(2));
+    assert_eq!(pane.pid, Some(4242));
+    assert_eq!(pane.cwd.as_deref(), Some("dir-1".as_ref()));
+}
@@ -0,0 +1,88 @@
+//! Locks down the JSON envelope other plugins parse.
+
+use crumbeez_lib::fixtures::{StreamBuilder, FIXTURE_START_MS};
+use crumbeez_lib::{
+    envelope_json, snapshot_file, AppRole, KeystrokeEvent, LogEntry, PaneFocusedEvent,
+};
+
+/// `event`, logged at [`FIXTURE_START_MS`].
+fn entry(event: KeystrokeEvent) -> LogEntry {
+    StreamBuilder::new(FIXTURE_START_MS)
+        .event(event)
+        .finish()
+        .remove(0)
+}
+
+#[test]
+fn text_is_escaped() {
+    let json = envelope_json(
+        42,
+        &entry(KeystrokeEvent::TextTyped("echo \"hi\"\n\tdone\\".into())),
+    );
+    assert_eq!(
+        json,
+        r#"{"v":1,"seq":42,"timestamp_ms":1709284500000,"type":"TextTyped","description":"typed \"echo \\\"hi\\\"\\n\\tdone\\\\\"","text":"echo \"hi\"\n\tdone\\"}"#
+    );
+}
+
+#[test]
+fn pane_focus_carries_the_pane() {
+    let json = envelope_json(
+        7,
+        &entry(KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+            tab_name: None,
+            pane_title: "psql".into(),
+            command: Some("psql app_dev".into()),
+            is_plugin: false,
+            role: AppRole::Sql,
+            pane_id: Some(3),
+            tab_index: Some(1),
+            cwd: Some("/src/app".into()),
+            pid: None,
+            host: Some("db-1".into()),
+            container: None,
+        })),
+    );
+    assert!(
+        json.ends_with(
+            r#","pane":{"tab":null,"title":"psql","command":"psql app_dev","role":"sql","id":3,"tab_index":1,"cwd":"/src/app","pid":null,"host":"db-1","container":null}}"#
+        ),
+        "{}",
+        json
+    );
+}
+
+#[test]
+fn control_characters_are_unicode_escapes() {
+    let json = envelope_json(0, &entry(KeystrokeEvent::Annotation("a\u{1b}b".into())));
+    assert!(json.contains(r#""text":"a\u001bb""#), "{}", json);
+}
+
+#[test]
+fn snapshot_carries_the_file() {
+    let json = envelope_json(
+        3,
+        &entry(KeystrokeEvent::SnapshotTaken(snapshot_file(
+            FIXTURE_START_MS,
+        ))),
+    );
+    assert!(
+        json.ends_with(r#","file":"scratchpad/snapshot-1709284500000.txt"}"#),
+        "{}",
+        json
+    );
+}
+
+#[test]
+fn session_context_carries_its_values() {
+    let values = [("NODE_ENV", "test"), ("node", "v20.11.0")]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    let json = envelope_json(3, &entry(KeystrokeEvent::SessionContext(values)));
+    assert!(
+        json.ends_with(r#","context":{"NODE_ENV":"test","node":"v20.11.0"}}"#),
+        "{}",
+        json
+    );
+}
@@ -0,0 +1,82 @@
+//! Checks the month heatmap built from the summary index.
+
+use std::path::{Path, PathBuf};
+
+use crumbeez_lib::{day_start_ms, DayRecord, MonthHeatmap, SummaryIndex};
+
+fn record(root: &str, day: &str, events: usize) -> DayRecord {
+    DayRecord {
+        day: day.to_string(),
+        root: PathBuf::from(root),
+        name: None,
+        dir: Path::new(root).join(".crumbeez"),
+        active_ms: 0,
+        events,
+        commands: Vec::new(),
+    }
+}
+
+fn index() -> SummaryIndex {
+    let mut index = SummaryIndex::default();
+    for record in [
+        record("/src/app", "2024-02-29", 50),
+        record("/src/app", "2024-03-01", 40),
+        record("/src/app", "2024-03-04", 10),
+        record("/src/app", "2024-03-31", 1),
+        record("/src/other", "2024-03-05", 1000),
+        record("/src/app", "2024-04-01", 70),
+    ] {
+        index.insert(record);
+    }
+    index
+}
+
+#[test]
+fn counts_the_projects_days_in_the_month() {
+    let march = MonthHeatmap::from_index(
+        &index(),
+        Path::new("/src/app"),
+        day_start_ms("2024-03-15").unwrap(),
+    );
+    assert_eq!(march.month, "2024-03");
+    assert_eq!(march.title(), "March 2024");
+    assert_eq!(march.events.len(), 31);
+    assert_eq!(march.active_days(), 3);
+    assert_eq!(march.total_events(), 51);
+    assert_eq!(march.start_ms(), day_start_ms("2024-03-01").unwrap());
+}
+
+#[test]
+fn steps_between_months() {
+    let index = index();
+    let root = Path::new("/src/app");
+    let march = MonthHeatmap::from_index(&index, root, day_start_ms("2024-03-15").unwrap());
+    let february = MonthHeatmap::from_index(&index, root, march.previous_ms().unwrap());
+    assert_eq!(february.month, "2024-02");
+    assert_eq!(february.events.len(), 29);
+    assert_eq!(february.total_events(), 50);
+    let april = MonthHeatmap::from_index(&index, root, march.next_ms());
+    assert_eq!(april.month, "2024-04");
+    assert_eq!(april.events[0], 70);
+    assert_eq!(
+        MonthHeatmap::from_index(&index, root, 0).previous_ms(),
+        None
+    );
+}
+
+#[test]
+fn draws_weeks_from_monday() {
+    let march = MonthHeatmap::from_index(
+        &index(),
+        Path::new("/src/app"),
+        day_start_ms("2024-03-01").unwrap(),
+    );
+    let lines = march.to_lines();
+    assert_eq!(lines[0], "Mo Tu We Th Fr Sa Su");
+    // 2024-03-01 was a Friday.
+    assert_eq!(lines[1], "            ██ ·· ··");
+    assert_eq!(lines[2], "░░ ·· ·· ·· ·· ·· ··");
+    assert_eq!(lines[5], "·· ·· ·· ·· ·· ·· ░░");
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines.last().unwrap(), "less ·· ░░ ▒▒ ▓▓ ██ more");
+}
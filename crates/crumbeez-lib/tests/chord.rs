@@ -0,0 +1,78 @@
+//! Folding configured key sequences into chords.
+
+use crumbeez_lib::{
+    AppRole, ChordDetector, KeystrokeEvent, ProjectConfig, ShortcutEvent, ShortcutKey,
+    CHORD_TIMEOUT_MS,
+};
+
+fn detector(config: &str, role: AppRole) -> ChordDetector {
+    let config = ProjectConfig::parse(config).expect("valid config");
+    ChordDetector::new(&config.chords, role)
+}
+
+fn ctrl(c: char) -> KeystrokeEvent {
+    KeystrokeEvent::Shortcut(ShortcutEvent {
+        key: ShortcutKey::Char(c),
+        ctrl: true,
+        alt: false,
+        shift: false,
+        super_key: false,
+    })
+}
+
+fn key(c: char) -> KeystrokeEvent {
+    KeystrokeEvent::TextTyped(c.to_string())
+}
+
+fn chord(keys: &str) -> KeystrokeEvent {
+    KeystrokeEvent::ChordSequence(keys.to_string())
+}
+
+#[test]
+fn prefix_and_command_fold_into_one_event() {
+    let mut chords = detector(r#"chord "Ctrl+B c" "Ctrl+B %""#, AppRole::Shell);
+    assert!(chords.is_active());
+    assert_eq!(chords.feed(ctrl('b'), 0), []);
+    assert_eq!(chords.feed(key('%'), 10), [chord("Ctrl+B %")]);
+    assert_eq!(chords.feed(key('c'), 20), [key('c')]);
+}
+
+#[test]
+fn keys_that_turn_out_not_to_be_a_chord_are_released_in_order() {
+    let mut chords = detector(r#"chord "g g" role="editor""#, AppRole::Editor);
+    assert_eq!(chords.feed(key('g'), 0), []);
+    assert_eq!(chords.feed(key('x'), 10), [key('g'), key('x')]);
+    // The released key can itself start the chord.
+    assert_eq!(chords.feed(key('g'), 20), []);
+    assert_eq!(chords.feed(key('g'), 30), [chord("g g")]);
+}
+
+#[test]
+fn a_pause_breaks_the_chord() {
+    let mut chords = detector(r#"chord "g g""#, AppRole::Shell);
+    assert_eq!(chords.feed(key('g'), 0), []);
+    assert_eq!(chords.feed(key('g'), CHORD_TIMEOUT_MS + 1), [key('g')]);
+    assert_eq!(chords.flush(), [key('g')]);
+}
+
+#[test]
+fn chords_for_other_roles_are_ignored() {
+    let chords = detector(r#"chord "g g" role="editor""#, AppRole::Shell);
+    assert!(!chords.is_active());
+}
+
+#[test]
+fn bad_chords_are_config_errors() {
+    for config in [r#"chord "Ctrl+B""#, r#"chord "g g" role="ide""#, "chord"] {
+        assert!(ProjectConfig::parse(config).is_err(), "{}", config);
+    }
+}
+
+#[test]
+fn chords_round_trip_through_kdl() {
+    let config = ProjectConfig::parse("chord \"Ctrl+B  c\" \"d d\" role=\"editor\"\n").unwrap();
+    assert!(config
+        .to_kdl()
+        .contains("chord \"Ctrl+B c\" role=\"editor\"\nchord \"d d\" role=\"editor\"\n"));
+    assert_eq!(ProjectConfig::parse(&config.to_kdl()).unwrap(), config);
+}
@@ -0,0 +1,175 @@
+//! Compacting old log entries shrinks the log without changing what its
+//! summaries say.
+
+use crumbeez_lib::event_log::LogEntry;
+use crumbeez_lib::fixtures::{self, Fixture, StreamBuilder, FIXTURE_START_MS};
+use crumbeez_lib::{
+    compact, CompactionLevel, CorrectionStats, HotSpot, KeystrokeEvent, NavDirection,
+    ProjectConfig, Summary, SystemKeyEvent, MAX_MERGE_GAP_MS,
+};
+
+/// A shell session heavy on arrow keys and Caps Lock.
+fn noisy(start_ms: u64) -> Vec<LogEntry> {
+    StreamBuilder::new(start_ms)
+        .focus(1, "dev", "zsh", "zsh")
+        .typed("git log")
+        .enter()
+        .nav(NavDirection::Up, 1)
+        .nav(NavDirection::Up, 1)
+        .nav(NavDirection::Up, 2)
+        .event(KeystrokeEvent::SystemKey(SystemKeyEvent::CapsLock))
+        .typed("LS")
+        .event(KeystrokeEvent::SystemKey(SystemKeyEvent::CapsLock))
+        .enter()
+        .focus(2, "dev", "nvim", "nvim")
+        .nav(NavDirection::Down, 3)
+        .nav(NavDirection::Down, 3)
+        .nav(NavDirection::Left, 1)
+        .typed("x")
+        .finish()
+}
+
+fn assert_same_summary(raw: &[LogEntry], compacted: &[LogEntry], name: &str) {
+    let raw = Summary::from_events(raw.iter().cloned());
+    let compacted = Summary::from_events(compacted.iter().cloned());
+    assert_eq!(compacted.segments, raw.segments, "{}", name);
+    assert_eq!(compacted.annotations, raw.annotations, "{}", name);
+    assert_eq!(compacted.misc_keys, raw.misc_keys, "{}", name);
+    assert_eq!(compacted.reading_ms, raw.reading_ms, "{}", name);
+    assert_eq!(compacted.filtered, raw.filtered, "{}", name);
+    assert_eq!(compacted.files, raw.files, "{}", name);
+    assert_eq!(
+        compacted.span_ms.map(|(start, _)| start),
+        raw.span_ms.map(|(start, _)| start),
+        "{}",
+        name
+    );
+}
+
+#[test]
+fn key_compaction_leaves_summaries_unchanged() {
+    let mut streams: Vec<(&str, Vec<LogEntry>)> = Fixture::ALL
+        .iter()
+        .map(|fixture| (fixture.name(), fixture.entries(FIXTURE_START_MS)))
+        .collect();
+    streams.push(("workday", fixtures::workday(FIXTURE_START_MS)));
+    streams.push(("noisy", noisy(FIXTURE_START_MS)));
+    for (name, raw) in streams {
+        let compacted = compact(raw.clone(), CompactionLevel::Keys);
+        assert_same_summary(&raw, &compacted, name);
+    }
+}
+
+#[test]
+fn key_compaction_merges_navigation_runs_and_drops_system_keys() {
+    let raw = noisy(FIXTURE_START_MS);
+    let compacted = compact(raw.clone(), CompactionLevel::Keys);
+    assert_eq!(compacted.len(), raw.len() - 5);
+    assert!(!compacted
+        .iter()
+        .any(|entry| matches!(entry.event, KeystrokeEvent::SystemKey(_))));
+    let navigation: Vec<_> = compacted
+        .iter()
+        .filter_map(|entry| match &entry.event {
+            KeystrokeEvent::Navigation(nav) => Some((nav.direction.clone(), nav.count)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        navigation,
+        [
+            (NavDirection::Up, 4),
+            (NavDirection::Down, 6),
+            (NavDirection::Left, 1)
+        ]
+    );
+}
+
+#[test]
+fn entries_far_apart_are_not_merged() {
+    let entries = StreamBuilder::new(FIXTURE_START_MS)
+        .nav(NavDirection::Down, 1)
+        .after_secs(MAX_MERGE_GAP_MS / 1000)
+        .nav(NavDirection::Down, 1)
+        .finish();
+    assert_eq!(compact(entries, CompactionLevel::Text).len(), 2);
+}
+
+#[test]
+fn text_compaction_joins_pieces_and_their_corrections() {
+    let piece = |text: &str, timestamp_ms, corrections| LogEntry {
+        event: KeystrokeEvent::typed(text),
+        timestamp_ms,
+        corrections,
+    };
+    let corrected = CorrectionStats {
+        typed: 5,
+        kept: 3,
+        deleted: 2,
+        moves: 0,
+        hot_spots: vec![HotSpot {
+            offset: 1,
+            deleted: 2,
+        }],
+    };
+    let entries = vec![
+        piece("git", FIXTURE_START_MS, None),
+        piece(" st", FIXTURE_START_MS + 1000, Some(Box::new(corrected))),
+        LogEntry {
+            event: KeystrokeEvent::Escape,
+            timestamp_ms: FIXTURE_START_MS + 2000,
+            corrections: None,
+        },
+    ];
+
+    assert_eq!(compact(entries.clone(), CompactionLevel::Keys).len(), 3);
+
+    let compacted = compact(entries, CompactionLevel::Text);
+    assert_eq!(compacted.len(), 2);
+    assert_eq!(compacted[0].event, KeystrokeEvent::typed("git st"));
+    assert_eq!(compacted[0].timestamp_ms, FIXTURE_START_MS);
+    let corrections = compacted[0].corrections.as_deref().unwrap();
+    assert_eq!(
+        (corrections.typed, corrections.kept, corrections.deleted),
+        (8, 6, 2)
+    );
+    assert_eq!(corrections.hot_spots[0].offset, 4);
+}
+
+#[test]
+fn only_old_summarized_entries_are_compacted() {
+    let raw = noisy(FIXTURE_START_MS);
+    let cutoff = raw[8].timestamp_ms;
+    let mut log = fixtures::event_log(raw.clone());
+    log.consume(6);
+    let next_seq = log.next_seq();
+
+    let removed = log.compact_before(cutoff, CompactionLevel::Keys);
+    // Only the Up run in the summarized part: the Caps Lock after it is
+    // unsummarized.
+    assert_eq!(removed, 2);
+    assert_eq!(log.total_count(), raw.len() - 2);
+    assert_eq!(log.unconsumed_count(), raw.len() - 6);
+    assert_eq!(log.next_seq(), next_seq);
+    assert_eq!(log.first_seq(), 2);
+    assert_eq!(log.compact_before(cutoff, CompactionLevel::Keys), 0);
+}
+
+#[test]
+fn compaction_settings_round_trip() {
+    let config = ProjectConfig::parse(
+        r#"
+retention {
+    raw_days 7
+    compaction "text"
+}
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.retention.raw_days, Some(7));
+    assert_eq!(config.retention.compaction, CompactionLevel::Text);
+    let reparsed = ProjectConfig::parse(&config.to_kdl()).unwrap();
+    assert_eq!(reparsed.retention, config.retention);
+
+    assert!(ProjectConfig::parse("retention {\n    compaction \"all\"\n}").is_err());
+}
@@ -0,0 +1,82 @@
+//! Panes and shell sessions inside containers are tagged with the
+//! container, so summaries tell host work from in-container work.
+
+use crumbeez_lib::{pane_container, ContainerCommand};
+
+fn parse(command: &str) -> Option<(String, bool)> {
+    ContainerCommand::parse(command).map(|c| (c.name, c.interactive))
+}
+
+#[test]
+fn exec_and_run() {
+    let cases = [
+        ("docker exec -it web bash", "web", true),
+        (
+            "/usr/bin/podman exec -u root -w /app -e RAILS_ENV=test web rake",
+            "web",
+            false,
+        ),
+        (
+            "docker exec --interactive --tty --env-file .env web sh",
+            "web",
+            true,
+        ),
+        (
+            "docker run --rm -it -v .:/src -p 3000:3000 node:20 bash",
+            "node:20",
+            true,
+        ),
+        (
+            "docker run --name scratch -eFOO=1 alpine true",
+            "scratch",
+            false,
+        ),
+        ("docker --context staging exec -ti api sh", "api", true),
+        ("docker compose -f dev.yml exec web bash", "web", true),
+        ("docker-compose exec -T db psql", "db", false),
+        (
+            "devcontainer exec --workspace-folder . bash",
+            "devcontainer",
+            true,
+        ),
+    ];
+    for (command, name, interactive) in cases {
+        assert_eq!(
+            parse(command),
+            Some((name.to_string(), interactive)),
+            "{}",
+            command
+        );
+    }
+}
+
+#[test]
+fn other_commands() {
+    for command in [
+        "docker ps",
+        "docker compose up -d",
+        "docker exec",
+        "cargo run",
+        "devcontainer up",
+    ] {
+        assert_eq!(parse(command), None, "{}", command);
+    }
+}
+
+#[test]
+fn pane_container_from_command_or_title() {
+    assert_eq!(
+        pane_container(Some("docker exec -it web bash"), "bash").as_deref(),
+        Some("web")
+    );
+    // A local shell running docker, or a devcontainer's prompt.
+    assert_eq!(
+        pane_container(Some("/bin/zsh"), "docker exec -it web bash").as_deref(),
+        Some("web")
+    );
+    assert_eq!(
+        pane_container(Some("/bin/bash"), "root@0f3a9c1b2d4e: /workspaces/app").as_deref(),
+        Some("0f3a9c1b2d4e")
+    );
+    assert_eq!(pane_container(Some("/bin/zsh"), "me@laptop: ~/src"), None);
+}
@@ -0,0 +1,62 @@
+//! Checks that the context blob stays within its token budget and keeps the
+//! newest activity.
+
+use crumbeez_lib::{
+    context_blob, estimate_tokens, AppRole, KeystrokeEvent, LogEntry, PaneFocusedEvent,
+};
+
+fn entries(commands: usize) -> Vec<LogEntry> {
+    let mut entries = vec![LogEntry {
+        event: KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+            tab_name: Some("dev".into()),
+            pane_title: "zsh".into(),
+            command: Some("/bin/zsh".into()),
+            is_plugin: false,
+            role: AppRole::Shell,
+            ..Default::default()
+        }),
+        timestamp_ms: 0,
+        corrections: None,
+    }];
+    for i in 0..commands {
+        entries.push(LogEntry {
+            event: KeystrokeEvent::TextTyped(format!("cargo test case_{}", i)),
+            timestamp_ms: 1000 * (i as u64 + 1),
+            corrections: None,
+        });
+    }
+    entries
+}
+
+#[test]
+fn everything_fits_in_a_large_budget() {
+    let blob = context_blob(&entries(3), 2000);
+    assert!(!blob.contains("omitted"), "{}", blob);
+    assert!(blob.contains("[dev (zsh)] zsh:"), "{}", blob);
+    for i in 0..3 {
+        assert!(blob.contains(&format!("case_{}", i)), "{}", blob);
+    }
+}
+
+#[test]
+fn oldest_events_are_dropped_to_fit() {
+    let blob = context_blob(&entries(200), 100);
+    assert!(estimate_tokens(&blob) <= 100, "{}", blob);
+    assert!(blob.contains("earlier events omitted"), "{}", blob);
+    assert!(blob.contains("case_199"), "{}", blob);
+    assert!(!blob.contains("case_0\""), "{}", blob);
+    // The pane focused before the cut still heads the activity.
+    assert!(blob.contains("[dev (zsh)] zsh:"), "{}", blob);
+}
+
+#[test]
+fn tiny_budget_is_truncated() {
+    let blob = context_blob(&entries(1), 5);
+    assert!(estimate_tokens(&blob) <= 5, "{}", blob);
+    assert!(blob.ends_with('…'), "{}", blob);
+}
+
+#[test]
+fn empty_log() {
+    assert_eq!(context_blob(&[], 2000), "No recorded activity.\n");
+}
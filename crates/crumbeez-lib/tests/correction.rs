@@ -0,0 +1,91 @@
+//! Correction stats: what a typed buffer went through before it was
+//! sealed, and the per-program totals built from them.
+
+use crumbeez_lib::{
+    CorrectionStats, CorrectionTracker, EventLog, HotSpot, KeystrokeEvent, LogEntry,
+    PaneFocusedEvent,
+};
+
+#[test]
+fn typed_straight_through_has_none() {
+    let mut tracker = CorrectionTracker::new();
+    tracker.typed(9);
+    assert_eq!(tracker.finish("cargo run"), None);
+}
+
+#[test]
+fn backspace_runs_make_hot_spots() {
+    let mut tracker = CorrectionTracker::new();
+    // "git comit", three backspaces, then "mmit": "git commit".
+    tracker.typed(9);
+    let mut text = "git comit".to_string();
+    for _ in 0..3 {
+        let at = text.len() - 1;
+        tracker.deleted(&text, at, true);
+        text.pop();
+    }
+    tracker.typed(4);
+    // Home, then Delete twice at the start.
+    tracker.moved();
+    let mut text = "git commit".to_string();
+    tracker.deleted(&text, 0, false);
+    text.remove(0);
+    tracker.deleted(&text, 0, false);
+
+    let stats = tracker.finish("t commit").unwrap();
+    assert_eq!(
+        stats,
+        CorrectionStats {
+            typed: 13,
+            kept: 8,
+            deleted: 5,
+            moves: 1,
+            hot_spots: vec![
+                HotSpot {
+                    offset: 6,
+                    deleted: 3
+                },
+                HotSpot {
+                    offset: 0,
+                    deleted: 2
+                },
+            ],
+        }
+    );
+    assert!((stats.rework() - 5.0 / 13.0).abs() < 1e-9);
+    // Finishing starts over.
+    assert_eq!(tracker.finish(""), None);
+}
+
+#[test]
+fn stats_total_rework_per_program() {
+    let focus = |command: &str, title: &str| {
+        KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+            pane_title: title.into(),
+            command: Some(command.into()),
+            ..Default::default()
+        })
+    };
+    let mut log = EventLog::new();
+    log.append(focus("/usr/bin/nvim notes.md", "notes"), 0);
+    log.append_entry(LogEntry {
+        event: KeystrokeEvent::TextTyped("hello".into()),
+        timestamp_ms: 1,
+        corrections: Some(Box::new(CorrectionStats {
+            typed: 10,
+            kept: 5,
+            deleted: 5,
+            ..Default::default()
+        })),
+    });
+    log.append(focus("zsh", "shell"), 2);
+    log.append(KeystrokeEvent::TextTyped("ls".into()), 3);
+
+    let stats = log.stats();
+    assert_eq!(stats.rework_by_app["nvim"].deleted, 5);
+    assert_eq!(stats.rework_by_app["nvim"].rework(), 0.5);
+    assert_eq!(
+        stats.rework_by_app["zsh"],
+        CorrectionStats::uncorrected("ls")
+    );
+}
@@ -0,0 +1,103 @@
+//! Lines typed at password prompts are recognized from the command before
+//! them and summarized without what was typed.
+
+use crumbeez_lib::fixtures::{StreamBuilder, FIXTURE_START_MS};
+use crumbeez_lib::{
+    prompts_for_secret, CredentialGuard, KeystrokeEvent, MAX_SECRET_ATTEMPTS,
+    SECRET_PROMPT_WINDOW_MS, SECRET_RETRY_WINDOW_MS,
+};
+
+#[test]
+fn commands_that_prompt_for_secrets() {
+    for command in [
+        "sudo systemctl restart nginx",
+        "/usr/bin/sudo -i",
+        "su - postgres",
+        "ssh -p 2222 deploy@prod-db-1",
+        "scp dump.sql backup:/srv",
+        "ssh-add ~/.ssh/id_ed25519",
+        "git pull && sudo make install",
+        "LC_ALL=C sudo apt upgrade",
+        "docker login ghcr.io",
+        "mysql -u root -p shop",
+        "mariadb --password -h db",
+        "passwd",
+    ] {
+        assert!(prompts_for_secret(command), "{:?}", command);
+    }
+    for command in [
+        "ls -la",
+        "echo sudo",
+        "man ssh",
+        "docker ps",
+        "mysql -u root shop",
+        "grep -r passwd /etc",
+        "",
+    ] {
+        assert!(!prompts_for_secret(command), "{:?}", command);
+    }
+}
+
+#[test]
+fn the_next_line_after_a_prompting_command_is_a_secret() {
+    let mut guard = CredentialGuard::new();
+    guard.submitted("cargo build", FIXTURE_START_MS);
+    assert!(!guard.expects_secret(FIXTURE_START_MS + 1000));
+
+    guard.submitted("sudo apt upgrade", FIXTURE_START_MS);
+    assert!(guard.expects_secret(FIXTURE_START_MS + 5000));
+    assert!(guard.take_secret(FIXTURE_START_MS + 5000));
+    // Too late for another try.
+    assert!(!guard.take_secret(FIXTURE_START_MS + 5000 + SECRET_RETRY_WINDOW_MS + 1));
+}
+
+#[test]
+fn nothing_typed_in_time_is_not_a_secret() {
+    let mut guard = CredentialGuard::new();
+    guard.submitted("ssh prod-db-1", FIXTURE_START_MS);
+    assert!(!guard.take_secret(FIXTURE_START_MS + SECRET_PROMPT_WINDOW_MS + 1));
+    assert!(!guard.expects_secret(FIXTURE_START_MS + 1));
+}
+
+#[test]
+fn retries_are_secrets_until_the_attempts_run_out() {
+    let mut guard = CredentialGuard::new();
+    guard.submitted("sudo -i", FIXTURE_START_MS);
+    let mut now = FIXTURE_START_MS;
+    for _ in 0..MAX_SECRET_ATTEMPTS {
+        now += 3000;
+        assert!(guard.take_secret(now));
+    }
+    assert!(!guard.take_secret(now + 3000));
+}
+
+#[test]
+fn finishing_or_leaving_the_command_stops_expecting_a_secret() {
+    let mut guard = CredentialGuard::new();
+    guard.submitted("sudo make install ", FIXTURE_START_MS);
+    guard.finished("make test");
+    assert!(guard.expects_secret(FIXTURE_START_MS + 1000));
+    guard.finished("sudo make install");
+    assert!(!guard.expects_secret(FIXTURE_START_MS + 1000));
+
+    guard.submitted("ssh prod-db-1", FIXTURE_START_MS);
+    guard.cancel();
+    assert!(!guard.expects_secret(FIXTURE_START_MS + 1000));
+}
+
+#[test]
+fn summaries_say_a_password_was_entered_and_nothing_more() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .typed("sudo apt upgrade")
+        .at_ms(1000)
+        .enter()
+        .at_ms(4000)
+        .event(KeystrokeEvent::CredentialEntered)
+        .enter()
+        .summary();
+    // The Enter goes with the password.
+    assert_eq!(summary.segments[0].activities.len(), 2);
+    let markdown = summary.to_markdown();
+    assert!(markdown.contains("entered a password"), "{}", markdown);
+    assert!(!markdown.contains("other key"), "{}", markdown);
+}
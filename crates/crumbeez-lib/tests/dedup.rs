@@ -0,0 +1,51 @@
+//! Keys reported both as `Key` and as `InterceptedKeyPress` while the
+//! plugin's pane is focused are logged once.
+
+use crumbeez_lib::{DuplicateFilter, KeySource, DUPLICATE_WINDOW_MS};
+
+use KeySource::{Focused, Intercepted};
+
+#[test]
+fn focused_plugin_key_is_logged_once() {
+    let mut filter = DuplicateFilter::default();
+    assert!(filter.admit(Intercepted, 'a', 1000));
+    assert!(!filter.admit(Focused, 'a', 1000));
+    // Either order.
+    assert!(filter.admit(Focused, 'b', 1010));
+    assert!(!filter.admit(Intercepted, 'b', 1011));
+}
+
+#[test]
+fn interleaved_reports_still_pair_up() {
+    let mut filter = DuplicateFilter::default();
+    assert!(filter.admit(Focused, 'l', 0));
+    assert!(filter.admit(Focused, 's', 1));
+    assert!(!filter.admit(Intercepted, 'l', 2));
+    assert!(!filter.admit(Intercepted, 's', 3));
+}
+
+#[test]
+fn repeats_from_one_source_are_kept() {
+    let mut filter = DuplicateFilter::default();
+    for at in 0..5 {
+        assert!(filter.admit(Intercepted, 'j', at));
+    }
+    // Each duplicate pairs with only one earlier press.
+    assert!(!filter.admit(Focused, 'j', 5));
+    assert!(!filter.admit(Focused, 'j', 5));
+}
+
+#[test]
+fn reports_far_apart_are_separate_presses() {
+    let mut filter = DuplicateFilter::default();
+    assert!(filter.admit(Intercepted, 'q', 0));
+    assert!(filter.admit(Focused, 'q', DUPLICATE_WINDOW_MS + 1));
+}
+
+#[test]
+fn unfocused_plugin_keys_are_all_logged() {
+    let mut filter = DuplicateFilter::default();
+    for (at, c) in "cargo test".chars().enumerate() {
+        assert!(filter.admit(Intercepted, c, at as u64));
+    }
+}
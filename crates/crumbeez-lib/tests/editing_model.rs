@@ -0,0 +1,321 @@
+//! Property tests for [`KeystrokeActivity`]'s editing model.
+//!
+//! Random sequences of typing, Backspace/Delete, cursor movement, and
+//! word-edit shortcuts are fed to `KeystrokeActivity`, and the
+//! reconstructed text is compared against a straightforward gap-buffer
+//! model of a single-line editor.
+
+use crumbeez_lib::{
+    EditControlEvent, KeystrokeActivity, KeystrokeEvent, NavDirection, NavigationEvent,
+    ShortcutEvent, ShortcutKey, WordEdit,
+};
+use proptest::prelude::*;
+
+// ── Reference model ──────────────────────────────────────────────
+
+/// A gap buffer: `before` holds the text left of the cursor in order,
+/// `after` holds the text right of the cursor in reverse order.
+#[derive(Debug, Default)]
+struct GapBuffer {
+    before: Vec<char>,
+    after: Vec<char>,
+}
+
+impl GapBuffer {
+    fn text(&self) -> String {
+        self.before.iter().chain(self.after.iter().rev()).collect()
+    }
+
+    fn left(&mut self) {
+        if let Some(c) = self.before.pop() {
+            self.after.push(c);
+        }
+    }
+
+    fn right(&mut self) {
+        if let Some(c) = self.after.pop() {
+            self.before.push(c);
+        }
+    }
+
+    fn word_left(&mut self) {
+        while self.before.last().is_some_and(|&c| !is_word(c)) {
+            self.left();
+        }
+        while self.before.last().is_some_and(|&c| is_word(c)) {
+            self.left();
+        }
+    }
+
+    fn word_right(&mut self) {
+        while self.after.last().is_some_and(|&c| !is_word(c)) {
+            self.right();
+        }
+        while self.after.last().is_some_and(|&c| is_word(c)) {
+            self.right();
+        }
+    }
+
+    fn delete_word_left(&mut self) {
+        while self.before.last().is_some_and(|&c| !is_word(c)) {
+            self.before.pop();
+        }
+        while self.before.last().is_some_and(|&c| is_word(c)) {
+            self.before.pop();
+        }
+    }
+
+    fn delete_word_right(&mut self) {
+        while self.after.last().is_some_and(|&c| !is_word(c)) {
+            self.after.pop();
+        }
+        while self.after.last().is_some_and(|&c| is_word(c)) {
+            self.after.pop();
+        }
+    }
+
+    fn apply(&mut self, op: &Op) {
+        match *op {
+            Op::Type(c) => self.before.push(c),
+            Op::Backspace => {
+                self.before.pop();
+            }
+            Op::Delete => {
+                self.after.pop();
+            }
+            Op::Left(n) => (0..n).for_each(|_| self.left()),
+            Op::Right(n) => (0..n).for_each(|_| self.right()),
+            Op::WordLeft | Op::AltB => self.word_left(),
+            Op::WordRight | Op::AltRight => self.word_right(),
+            Op::CtrlW | Op::CtrlBackspace => self.delete_word_left(),
+            Op::AltD => self.delete_word_right(),
+            Op::Home => (0..self.before.len()).for_each(|_| self.left()),
+            Op::End => (0..self.after.len()).for_each(|_| self.right()),
+        }
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// ── Operations ───────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+enum Op {
+    Type(char),
+    Backspace,
+    Delete,
+    Left(usize),
+    Right(usize),
+    WordLeft,
+    WordRight,
+    AltB,
+    AltRight,
+    CtrlW,
+    CtrlBackspace,
+    AltD,
+    Home,
+    End,
+}
+
+impl Op {
+    fn to_event(&self) -> KeystrokeEvent {
+        let nav = |direction, count, with_ctrl| {
+            KeystrokeEvent::Navigation(NavigationEvent {
+                direction,
+                count,
+                with_shift: false,
+                with_ctrl,
+            })
+        };
+        let shortcut = |key, ctrl| {
+            KeystrokeEvent::Shortcut(ShortcutEvent {
+                key,
+                ctrl,
+                alt: !ctrl,
+                shift: false,
+                super_key: false,
+            })
+        };
+        match *self {
+            Op::Type(c) => KeystrokeEvent::TextTyped(c.to_string()),
+            Op::Backspace => KeystrokeEvent::EditControl(EditControlEvent::Backspace { count: 1 }),
+            Op::Delete => KeystrokeEvent::EditControl(EditControlEvent::Delete { count: 1 }),
+            Op::Left(n) => nav(NavDirection::Left, n, false),
+            Op::Right(n) => nav(NavDirection::Right, n, false),
+            Op::WordLeft => nav(NavDirection::Left, 1, true),
+            Op::WordRight => nav(NavDirection::Right, 1, true),
+            Op::AltB => shortcut(ShortcutKey::Char('b'), false),
+            Op::AltRight => shortcut(ShortcutKey::Right, false),
+            Op::CtrlW => shortcut(ShortcutKey::Char('w'), true),
+            Op::CtrlBackspace => shortcut(ShortcutKey::Backspace, true),
+            Op::AltD => shortcut(ShortcutKey::Char('d'), false),
+            Op::Home => nav(NavDirection::Home, 1, false),
+            Op::End => nav(NavDirection::End, 1, false),
+        }
+    }
+}
+
+/// Mostly typing, with a mix of ASCII, word separators, and multi-byte
+/// characters so cursor moves have to respect UTF-8 boundaries.
+fn op() -> impl Strategy<Value = Op> {
+    let chars = prop_oneof![
+        4 => proptest::char::range('a', 'z'),
+        1 => Just(' '),
+        1 => Just('-'),
+        1 => Just('_'),
+        1 => Just('é'),
+        1 => Just('日'),
+        1 => Just('🦀'),
+    ];
+    prop_oneof![
+        8 => chars.prop_map(Op::Type),
+        2 => Just(Op::Backspace),
+        1 => Just(Op::Delete),
+        2 => (1..4usize).prop_map(Op::Left),
+        1 => (1..4usize).prop_map(Op::Right),
+        1 => Just(Op::WordLeft),
+        1 => Just(Op::WordRight),
+        1 => Just(Op::AltB),
+        1 => Just(Op::AltRight),
+        1 => Just(Op::CtrlW),
+        1 => Just(Op::CtrlBackspace),
+        1 => Just(Op::AltD),
+        1 => Just(Op::Home),
+        1 => Just(Op::End),
+    ]
+}
+
+/// The text of the live buffer, i.e. the tail `TextTyped` entry.
+fn live_text(activity: &KeystrokeActivity) -> String {
+    match activity.events().back() {
+        Some(KeystrokeEvent::TextTyped(s)) => s.clone(),
+        _ => String::new(),
+    }
+}
+
+// ── Properties ───────────────────────────────────────────────────
+
+proptest! {
+    #[test]
+    fn live_buffer_matches_gap_buffer(ops in proptest::collection::vec(op(), 0..64)) {
+        let mut activity = KeystrokeActivity::new();
+        let mut model = GapBuffer::default();
+        for op in &ops {
+            activity.push_event(op.to_event());
+            model.apply(op);
+            prop_assert_eq!(live_text(&activity), model.text(), "after {:?}", op);
+        }
+    }
+
+    #[test]
+    fn push_char_matches_push_event(ops in proptest::collection::vec(op(), 0..64)) {
+        let mut via_event = KeystrokeActivity::new();
+        let mut via_char = KeystrokeActivity::new();
+        for op in &ops {
+            via_event.push_event(op.to_event());
+            match *op {
+                Op::Type(c) => via_char.push_char(c),
+                _ => via_char.push_event(op.to_event()),
+            }
+        }
+        prop_assert_eq!(via_event.events(), via_char.events());
+    }
+
+    #[test]
+    fn cursor_stays_consistent(
+        ops in proptest::collection::vec(op(), 0..64),
+        seal_at in 0..64usize,
+    ) {
+        let mut activity = KeystrokeActivity::new();
+        for (i, op) in ops.iter().enumerate() {
+            if i == seal_at {
+                activity.seal();
+                prop_assert_eq!(activity.cursor(), None);
+            }
+            activity.push_event(op.to_event());
+            prop_assert!(activity.is_consistent(), "after {:?}: {:?}", op, activity);
+        }
+    }
+
+    #[test]
+    fn live_buffer_is_never_empty(ops in proptest::collection::vec(op(), 0..64)) {
+        let mut activity = KeystrokeActivity::new();
+        for op in &ops {
+            activity.push_event(op.to_event());
+            let has_empty_text = activity
+                .events()
+                .iter()
+                .any(|e| matches!(e, KeystrokeEvent::TextTyped(s) if s.is_empty()));
+            prop_assert!(!has_empty_text);
+        }
+    }
+}
+
+// ── Word-edit shortcuts ──────────────────────────────────────────
+
+#[test]
+fn other_shortcuts_seal_the_buffer() {
+    let mut activity = KeystrokeActivity::new();
+    for c in "git sw".chars() {
+        activity.push_char(c);
+    }
+    let ctrl_r = ShortcutEvent {
+        key: ShortcutKey::Char('r'),
+        ctrl: true,
+        alt: false,
+        shift: false,
+        super_key: false,
+    };
+    assert_eq!(WordEdit::of(&ctrl_r), None);
+    activity.push_event(KeystrokeEvent::Shortcut(ctrl_r.clone()));
+    activity.push_event(Op::CtrlW.to_event());
+    assert_eq!(
+        activity.events().iter().cloned().collect::<Vec<_>>(),
+        [
+            KeystrokeEvent::TextTyped("git sw".into()),
+            KeystrokeEvent::Shortcut(ctrl_r),
+            Op::CtrlW.to_event(),
+        ]
+    );
+}
+
+#[test]
+fn ctrl_and_alt_together_make_no_word_edit() {
+    let ctrl_alt_left = ShortcutEvent {
+        key: ShortcutKey::Left,
+        ctrl: true,
+        alt: true,
+        shift: false,
+        super_key: false,
+    };
+    assert_eq!(WordEdit::of(&ctrl_alt_left), None);
+}
+
+// ── Sealing ──────────────────────────────────────────────────────
+
+#[test]
+fn sealed_buffer_is_not_edited() {
+    let mut activity = KeystrokeActivity::new();
+    for c in "cargo tset".chars() {
+        activity.push_char(c);
+    }
+    activity.push_event(Op::Left(2).to_event());
+    activity.seal();
+    // Keys after the seal, as after a reload, don't reach into the old text.
+    for op in [Op::Backspace, Op::Delete, Op::End, Op::Type('x')] {
+        activity.push_event(op.to_event());
+    }
+    assert_eq!(
+        activity.events().iter().cloned().collect::<Vec<_>>(),
+        [
+            KeystrokeEvent::TextTyped("cargo tset".into()),
+            Op::Backspace.to_event(),
+            Op::Delete.to_event(),
+            Op::End.to_event(),
+            KeystrokeEvent::TextTyped("x".into()),
+        ]
+    );
+    assert_eq!(activity.cursor(), Some(1));
+}
@@ -0,0 +1,85 @@
+//! With crumbeez in several panes, the oldest instance that said hello
+//! records and the rest view, until its pane closes.
+
+use crumbeez_lib::{Election, InstanceRole};
+
+/// Deliver every hello in `outbox` (sender, recipient) until none are left,
+/// as the plugins would over pipes.
+fn exchange(instances: &mut [Election], mut outbox: Vec<(u32, u32)>) {
+    while let Some((from, to)) = outbox.pop() {
+        let Some(recipient) = instances.iter_mut().find(|e| e.own_id() == to) else {
+            continue;
+        };
+        if recipient.hello(from) {
+            outbox.push((to, from));
+        }
+    }
+}
+
+/// Show every instance the panes `open`, and deliver the hellos that
+/// follow.
+fn panes(instances: &mut [Election], open: &[u32]) {
+    let mut outbox = Vec::new();
+    for election in instances.iter_mut() {
+        let from = election.own_id();
+        outbox.extend(
+            election
+                .set_panes(open.iter().copied())
+                .into_iter()
+                .map(|to| (from, to)),
+        );
+    }
+    exchange(instances, outbox);
+}
+
+#[test]
+fn alone_records() {
+    let mut election = Election::new(7);
+    assert!(election.set_panes([7]).is_empty());
+    assert_eq!(election.role(), InstanceRole::Recorder);
+}
+
+#[test]
+fn oldest_records_and_others_view() {
+    let mut instances = vec![Election::new(3), Election::new(5), Election::new(9)];
+    panes(&mut instances, &[3, 5, 9]);
+    assert_eq!(instances[0].role(), InstanceRole::Recorder);
+    assert_eq!(instances[1].role(), InstanceRole::Viewer { recorder: 3 });
+    assert_eq!(instances[2].role(), InstanceRole::Viewer { recorder: 3 });
+    assert_eq!(instances[2].peers().collect::<Vec<_>>(), [3, 5]);
+
+    // A repeated pane update greets nobody again.
+    assert!(instances[1].set_panes([3, 5, 9]).is_empty());
+}
+
+#[test]
+fn next_oldest_takes_over_when_the_recorder_closes() {
+    let mut instances = vec![Election::new(3), Election::new(5), Election::new(9)];
+    panes(&mut instances, &[3, 5, 9]);
+    let mut instances = instances.split_off(1);
+    panes(&mut instances, &[5, 9]);
+    assert_eq!(instances[0].role(), InstanceRole::Recorder);
+    assert_eq!(instances[1].role(), InstanceRole::Viewer { recorder: 5 });
+}
+
+#[test]
+fn newer_instance_doesnt_take_over() {
+    let mut instances = vec![Election::new(4)];
+    panes(&mut instances, &[4]);
+    instances.push(Election::new(8));
+    panes(&mut instances, &[4, 8]);
+    assert_eq!(instances[0].role(), InstanceRole::Recorder);
+    assert_eq!(instances[1].role(), InstanceRole::Viewer { recorder: 4 });
+}
+
+#[test]
+fn instance_without_a_pane_is_kept() {
+    // A background instance greets the pane it sees, but is never seen
+    // itself, so later pane updates don't drop it.
+    let mut election = Election::new(6);
+    election.set_panes([6]);
+    assert!(election.hello(2));
+    assert!(!election.hello(2));
+    election.set_panes([6]);
+    assert_eq!(election.role(), InstanceRole::Viewer { recorder: 2 });
+}
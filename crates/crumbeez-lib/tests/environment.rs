@@ -0,0 +1,95 @@
+//! Commands that switch cluster, namespace, AWS profile, or git branch are
+//! recognised, and their switches headline summaries.
+
+use crumbeez_lib::{environment_change, EnvironmentKind, KeystrokeEvent, LogEntry, Summary};
+
+fn switch(command: &str) -> Option<(EnvironmentKind, String)> {
+    environment_change(command)
+}
+
+#[test]
+fn switches_and_their_targets() {
+    use EnvironmentKind::*;
+    let cases = [
+        ("kubectl config use-context prod-eu", KubeContext, "prod-eu"),
+        (
+            "kubectl config use-context --kubeconfig ~/.kube/work staging",
+            KubeContext,
+            "staging",
+        ),
+        (
+            "/usr/local/bin/kubectx dev && kubectl get pods",
+            KubeContext,
+            "dev",
+        ),
+        ("kubens payments", KubeNamespace, "payments"),
+        ("aws sso login --profile prod", AwsProfile, "prod"),
+        ("aws sso login --profile=sandbox", AwsProfile, "sandbox"),
+        ("AWS_PROFILE=staging aws sso login", AwsProfile, "staging"),
+        ("aws sso login", AwsProfile, "default"),
+        ("git switch main", GitBranch, "main"),
+        (
+            "git switch -c feature/PROJ-142",
+            GitBranch,
+            "feature/PROJ-142",
+        ),
+        ("git switch --create fix-login", GitBranch, "fix-login"),
+        ("git checkout -b spike origin/main", GitBranch, "spike"),
+    ];
+    for (command, kind, target) in cases {
+        assert_eq!(
+            switch(command),
+            Some((kind, target.to_string())),
+            "{}",
+            command
+        );
+    }
+}
+
+#[test]
+fn listing_renaming_and_other_commands_switch_nothing() {
+    for command in [
+        "kubectx",
+        "kubens",
+        "kubectx -",
+        "kubectx new=old",
+        "kubectl config get-contexts",
+        "kubectl get pods",
+        "aws s3 ls",
+        "git checkout -- src/lib.rs",
+        "git checkout .",
+        "git switch -",
+        "git status",
+        "echo kubectx prod",
+        "",
+    ] {
+        assert_eq!(switch(command), None, "{}", command);
+    }
+}
+
+#[test]
+fn switches_headline_summaries_with_their_time() {
+    let entry = |event, timestamp_ms| LogEntry {
+        event,
+        timestamp_ms,
+        corrections: None,
+    };
+    let entries = [
+        entry(KeystrokeEvent::typed("kubectx prod"), 50_000_000),
+        entry(
+            KeystrokeEvent::EnvironmentChanged {
+                kind: EnvironmentKind::KubeContext,
+                target: "prod".into(),
+            },
+            50_520_000,
+        ),
+    ];
+    let summary = Summary::from_events(entries.into_iter());
+    assert_eq!(summary.switches.len(), 1);
+    let markdown = summary.to_markdown();
+    assert!(
+        markdown.contains("\n**⇄ switched to `prod` cluster at 14:02 UTC**\n"),
+        "{}",
+        markdown
+    );
+}
@@ -0,0 +1,132 @@
+//! The [`EntryIter`] adapters: time windows, runs per pane, typing joined
+//! across the cursor moves dropped from it, and searches.
+
+use crumbeez_lib::{
+    find_matches, EntryIter, KeystrokeEvent, LogEntry, NavDirection, NavigationEvent,
+    PaneFocusedEvent,
+};
+
+fn focus(title: &str) -> KeystrokeEvent {
+    KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+        pane_title: title.into(),
+        ..Default::default()
+    })
+}
+
+fn typed(text: &str) -> KeystrokeEvent {
+    KeystrokeEvent::TextTyped(text.into())
+}
+
+fn left() -> KeystrokeEvent {
+    KeystrokeEvent::Navigation(NavigationEvent {
+        direction: NavDirection::Left,
+        count: 2,
+        with_shift: false,
+        with_ctrl: false,
+    })
+}
+
+fn entries(events: Vec<KeystrokeEvent>) -> Vec<LogEntry> {
+    events
+        .into_iter()
+        .enumerate()
+        .map(|(i, event)| LogEntry {
+            event,
+            timestamp_ms: i as u64 * 10,
+            corrections: None,
+        })
+        .collect()
+}
+
+fn texts(entries: impl Iterator<Item = LogEntry>) -> Vec<String> {
+    entries
+        .map(|entry| match entry.event {
+            KeystrokeEvent::TextTyped(text) => text,
+            other => format!("{:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn between_is_half_open() {
+    let log = entries(vec![typed("a"), typed("b"), typed("c"), typed("d")]);
+    let times: Vec<u64> = log.iter().between(10, 30).map(|e| e.timestamp_ms).collect();
+    assert_eq!(times, [10, 20]);
+    assert_eq!(log.iter().between(40, 100).count(), 0);
+}
+
+#[test]
+fn without_nav_then_coalesce_joins_typing() {
+    let log = entries(vec![
+        typed("git com"),
+        left(),
+        typed("mit"),
+        KeystrokeEvent::Escape,
+    ]);
+    assert_eq!(log.iter().coalesce_text().count(), 4);
+
+    let joined: Vec<LogEntry> = log.iter().without_nav().coalesce_text().collect();
+    assert_eq!(joined.len(), 2);
+    assert_eq!(joined[0].timestamp_ms, 0);
+    assert_eq!(texts(joined.into_iter())[0], "git commit");
+
+    // Owned entries go through the same adapters.
+    assert_eq!(log.into_iter().without_nav().count(), 3);
+}
+
+#[test]
+fn by_pane_splits_at_focus_changes() {
+    let log = entries(vec![
+        typed("before"),
+        focus("zsh"),
+        typed("ls"),
+        focus("zsh"),
+        typed("pwd"),
+        focus("nvim"),
+        focus("zsh"),
+        typed("exit"),
+    ]);
+    let runs: Vec<(Option<String>, usize)> = log
+        .iter()
+        .by_pane()
+        .map(|run| (run.pane.map(|p| p.pane_title), run.entries.len()))
+        .collect();
+    assert_eq!(
+        runs,
+        [
+            (None, 1),
+            (Some("zsh".to_string()), 2),
+            (Some("nvim".to_string()), 0),
+            (Some("zsh".to_string()), 1),
+        ]
+    );
+}
+
+#[test]
+fn matching_searches_what_events_display() {
+    let log = entries(vec![
+        focus("Cargo build"),
+        typed("cargo test"),
+        left(),
+        typed("echo done"),
+    ]);
+    let found: Vec<u64> = log
+        .iter()
+        .matching("CARGO")
+        .map(|e| e.timestamp_ms)
+        .collect();
+    assert_eq!(found, [0, 10]);
+    assert_eq!(log.iter().matching("nav ←").count(), 1);
+    assert_eq!(log.iter().matching("").count(), 4);
+    assert_eq!(log.iter().matching("rustc").count(), 0);
+}
+
+#[test]
+fn find_matches_gives_byte_ranges() {
+    assert_eq!(find_matches("Ab ab AB", "ab"), [0..2, 3..5, 6..8]);
+    assert_eq!(find_matches("aaaa", "aa"), [0..2, 2..4]);
+    assert_eq!(find_matches("über Über", "ÜBER"), [0..5, 6..11]);
+    assert_eq!(find_matches("a → zsh, b → zip", "→ Z"), [2..7, 13..18]);
+    assert!(find_matches("anything", "").is_empty());
+    assert!(find_matches("ab", "abc").is_empty());
+}
@@ -0,0 +1,88 @@
+//! Checks that damaged event logs are detected and their intact prefix
+//! recovered.
+
+use crumbeez_lib::{EventLog, EventLogError, KeystrokeEvent};
+
+/// A log saved in full with two entries, then appended to with two more.
+fn saved_log() -> (EventLog, Vec<u8>, usize) {
+    let mut log = EventLog::new();
+    log.append(KeystrokeEvent::TextTyped("first".into()), 1);
+    log.append(KeystrokeEvent::TextTyped("second".into()), 2);
+    let mut data = log.serialize().unwrap();
+    let first_frame_end = data.len();
+
+    let saved = log.next_seq();
+    log.append(KeystrokeEvent::TextTyped("third".into()), 3);
+    log.append(KeystrokeEvent::TextTyped("fourth".into()), 4);
+    log.consume(1);
+    data.extend(log.serialize_since(saved).unwrap());
+    (log, data, first_frame_end)
+}
+
+#[test]
+fn intact_log_round_trips() {
+    let (log, data, _) = saved_log();
+    let (loaded, damage) = EventLog::deserialize_lossy(&data).unwrap();
+    assert!(damage.is_none());
+    assert_eq!(loaded.total_count(), log.total_count());
+    assert_eq!(loaded.unconsumed_count(), log.unconsumed_count());
+}
+
+#[test]
+fn truncated_tail_keeps_earlier_frames() {
+    let (_, data, first_frame_end) = saved_log();
+    for cut in first_frame_end + 1..data.len() {
+        let truncated = &data[..cut];
+        assert!(matches!(
+            EventLog::deserialize(truncated),
+            Err(EventLogError::Corrupted(_))
+        ));
+        let (loaded, damage) = EventLog::deserialize_lossy(truncated).unwrap();
+        assert!(matches!(damage, Some(EventLogError::Corrupted(_))));
+        assert_eq!(loaded.total_count(), 2);
+    }
+}
+
+#[test]
+fn flipped_bit_is_detected() {
+    let (_, mut data, first_frame_end) = saved_log();
+    let last = data.len() - 1;
+    data[last] ^= 0x01;
+    let (loaded, damage) = EventLog::deserialize_lossy(&data).unwrap();
+    let Some(EventLogError::Corrupted(msg)) = damage else {
+        panic!("expected corruption, got {:?}", damage);
+    };
+    assert!(msg.contains("checksum"), "{}", msg);
+    assert!(msg.contains(&first_frame_end.to_string()), "{}", msg);
+    assert_eq!(loaded.total_count(), 2);
+}
+
+#[test]
+fn damaged_header_frame_fails() {
+    let (_, mut data, _) = saved_log();
+    // Just past the magic and frame header, inside the log header.
+    data[12] ^= 0xFF;
+    assert!(EventLog::deserialize_lossy(&data).is_err());
+}
+
+#[test]
+fn missing_frame_is_detected() {
+    let (mut log, mut data, first_frame_end) = saved_log();
+    let saved = log.next_seq();
+    log.append(KeystrokeEvent::TextTyped("fifth".into()), 5);
+    let third_frame = log.serialize_since(saved).unwrap();
+
+    // Cut at the end of the first save: still a valid log, until a later
+    // save says where it continues from.
+    data.truncate(first_frame_end);
+    assert!(EventLog::deserialize_lossy(&data).unwrap().1.is_none());
+    data.extend_from_slice(&third_frame);
+
+    let (loaded, damage) = EventLog::deserialize_lossy(&data).unwrap();
+    let Some(EventLogError::Corrupted(msg)) = damage else {
+        panic!("expected corruption, got {:?}", damage);
+    };
+    assert!(msg.contains("continues from entry 4"), "{}", msg);
+    assert!(msg.contains(&first_frame_end.to_string()), "{}", msg);
+    assert_eq!(loaded.total_count(), 2);
+}
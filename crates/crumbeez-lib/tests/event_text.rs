@@ -0,0 +1,244 @@
+//! Checks that events built with the constructors match the structs they
+//! stand for, and that every event parses back from its `Display` form.
+
+use crumbeez_lib::{
+    AppRole, CommandOutcome, EditControlEvent, EnvironmentKind, EventKind, KeystrokeEvent,
+    NavDirection, NavigationEvent, PaneFocusedEvent, ShortcutEvent, ShortcutKey, SuspendReason,
+    SystemKeyEvent,
+};
+
+/// One of each event, with the awkward cases: escapes in quoted text,
+/// modifiers, counts, and every part of a pane's label.
+fn every_event() -> Vec<KeystrokeEvent> {
+    use KeystrokeEvent as E;
+    vec![
+        E::typed("ls -la"),
+        E::typed("say \"hi\"\\\n\t\u{7}'"),
+        E::shortcut("Ctrl+C"),
+        E::shortcut("Ctrl+Alt+Shift+Super+F12"),
+        E::shortcut("Alt++"),
+        E::shortcut("Shift+PgDn"),
+        NavigationEvent::left(1).into(),
+        NavigationEvent::page_down(4).shift().into(),
+        NavigationEvent::home().ctrl().shift().into(),
+        EditControlEvent::Enter.into(),
+        EditControlEvent::Tab.into(),
+        EditControlEvent::Insert.into(),
+        EditControlEvent::backspace(1).into(),
+        EditControlEvent::delete(7).into(),
+        E::Escape,
+        E::FunctionKey(5),
+        SystemKeyEvent::CapsLock.into(),
+        SystemKeyEvent::Menu.into(),
+        PaneFocusedEvent::new("nvim main.rs (draft)")
+            .in_tab("code")
+            .running("nvim")
+            .with_role(AppRole::Editor)
+            .into(),
+        PaneFocusedEvent::new("bash").running("bash").into(),
+        PaneFocusedEvent::new("htop").in_tab("ops").into(),
+        PaneFocusedEvent {
+            host: Some("build-box".into()),
+            container: Some("dev".into()),
+            ..PaneFocusedEvent::new("zsh").running("zsh")
+        }
+        .into(),
+        E::annotation("fixed the flaky test"),
+        E::FuzzySelected {
+            query: "src \"lib\"".into(),
+            moves: 3,
+        },
+        E::ReplInput {
+            language: "python".into(),
+            code: "print(\"hi\")".into(),
+        },
+        E::PromptToAgent("why does this fail?".into()),
+        E::AgentStatus("thinking".into()),
+        E::ShortText(4),
+        E::SessionSuspended(SuspendReason::Detached),
+        E::SessionSuspended(SuspendReason::Locked),
+        E::SessionResumed,
+        E::ModeChanged("locked".into()),
+        E::SnapshotTaken("before refactor".into()),
+        E::Filtered {
+            kind: EventKind::Navigation,
+            count: 12,
+        },
+        E::ChordSequence("g g".into()),
+        E::FloodDetected {
+            keys: 500,
+            duration_ms: 1200,
+        },
+        E::PaneTitleChanged("vim notes.md".into()),
+        E::TabClosed("scratch".into()),
+        E::SessionRenamed("crumbeez".into()),
+        E::LongText {
+            preview: "fn main() { … }\n".into(),
+            chars: 4200,
+            file: "scratchpad/text-1700000000000.txt".into(),
+        },
+        E::CommandExecuted {
+            command: "git log --oneline | head -n \"5\"".into(),
+            shell: "zsh".into(),
+            outcome: None,
+        },
+        E::CommandExecuted {
+            command: "cargo test".into(),
+            shell: "atuin".into(),
+            outcome: Some(CommandOutcome {
+                exit_code: 101,
+                duration_ms: 4200,
+                cwd: Some("/home/me/my \"project\"".into()),
+            }),
+        },
+        E::SessionContext(
+            [
+                ("VIRTUAL_ENV", "/home/me/app/.venv"),
+                ("rust", "rustc 1.80.0 (\"stable\")"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        ),
+        E::SessionContext(Default::default()),
+        E::EnvironmentChanged {
+            kind: EnvironmentKind::AwsProfile,
+            target: "prod admin".into(),
+        },
+        E::RiskyCommand {
+            command: "rm -rf \"build\"".into(),
+            pattern: "rm -rf".into(),
+            reason: None,
+        },
+        E::RiskyCommand {
+            command: "terraform apply".into(),
+            pattern: "terraform apply".into(),
+            reason: Some("rolling out the (new) DNS: prod".into()),
+        },
+        E::SessionTagged(vec!["refactor-auth".into(), "v2.1/ui".into()]),
+        E::SessionTagged(Vec::new()),
+        E::CredentialEntered,
+    ]
+}
+
+#[test]
+fn events_parse_back_from_their_display() {
+    for event in every_event() {
+        let text = event.to_string();
+        let parsed: KeystrokeEvent = text
+            .parse()
+            .unwrap_or_else(|e| panic!("{:?}: {}", event, e));
+        assert_eq!(parsed, event, "{}", text);
+        assert_eq!(parsed.to_string(), text);
+    }
+}
+
+#[test]
+fn display_survives_parsing_where_events_dont() {
+    // Only the command's basename is shown, and no IDs.
+    let pane: KeystrokeEvent = PaneFocusedEvent::new("bash")
+        .running("/usr/bin/bash")
+        .with_id(3)
+        .into();
+    let parsed: KeystrokeEvent = pane.to_string().parse().unwrap();
+    assert_eq!(parsed.to_string(), pane.to_string());
+    assert_eq!(parsed, PaneFocusedEvent::new("bash").running("bash").into());
+
+    // A flood's duration is shown to the tenth of a second.
+    let flood = KeystrokeEvent::FloodDetected {
+        keys: 90,
+        duration_ms: 2345,
+    };
+    let parsed: KeystrokeEvent = flood.to_string().parse().unwrap();
+    assert_eq!(parsed.to_string(), flood.to_string());
+    assert_eq!(
+        parsed,
+        KeystrokeEvent::FloodDetected {
+            keys: 90,
+            duration_ms: 2300
+        }
+    );
+}
+
+#[test]
+fn constructors_build_the_structs() {
+    assert_eq!(
+        KeystrokeEvent::shortcut("Ctrl+Shift+P"),
+        KeystrokeEvent::Shortcut(ShortcutEvent {
+            key: ShortcutKey::Char('P'),
+            ctrl: true,
+            alt: false,
+            shift: true,
+            super_key: false,
+        })
+    );
+    assert_eq!(
+        NavigationEvent::left(3).ctrl(),
+        NavigationEvent {
+            direction: NavDirection::Left,
+            count: 3,
+            with_shift: false,
+            with_ctrl: true,
+        }
+    );
+    let pane = PaneFocusedEvent::new("psql")
+        .in_tab("db")
+        .running("/usr/bin/psql")
+        .with_role(AppRole::Sql)
+        .with_id(9);
+    assert_eq!(pane.tab_name.as_deref(), Some("db"));
+    assert_eq!(pane.command.as_deref(), Some("/usr/bin/psql"));
+    assert_eq!(pane.role, AppRole::Sql);
+    assert_eq!(pane.pane_id, Some(9));
+    assert!(!pane.is_plugin);
+}
+
+#[test]
+fn keys_parse_by_name_too() {
+    for (text, key) in [
+        ("Left", ShortcutKey::Left),
+        ("←", ShortcutKey::Left),
+        ("PageUp", ShortcutKey::PageUp),
+        ("PgUp", ShortcutKey::PageUp),
+        ("F10", ShortcutKey::F(10)),
+        ("F", ShortcutKey::Char('F')),
+        ("+", ShortcutKey::Char('+')),
+    ] {
+        assert_eq!(text.parse::<ShortcutKey>(), Ok(key), "{}", text);
+    }
+    assert_eq!(
+        "Ctrl+Right ×2".parse::<NavigationEvent>(),
+        Ok(NavigationEvent::right(2).ctrl())
+    );
+}
+
+#[test]
+fn text_that_isnt_an_event_is_an_error() {
+    for bad in [
+        "",
+        "shortcut Ctrl+",
+        "shortcut Hyper+x",
+        "nav PgUp ×1",
+        "nav Ctrl+x",
+        "edit-ctrl Enter ×2",
+        "sys Scroll",
+        "typed \"unterminated",
+        "typed \"bad \\q escape\"",
+        "typed some chars",
+        "fuzzy-selected \"q\" after many moves",
+        "⏸ session paused",
+        "3 kittens",
+        "python print(1)",
+        "Fx",
+    ] {
+        let err = bad.parse::<KeystrokeEvent>().unwrap_err();
+        assert!(err.to_string().contains("isn't an event"), "{}", err);
+    }
+    assert!("Ctrl+Hyper".parse::<ShortcutEvent>().is_err());
+}
+
+#[test]
+#[should_panic(expected = "isn't a shortcut")]
+fn shortcut_panics_on_nonsense() {
+    KeystrokeEvent::shortcut("Ctrl+Banana");
+}
@@ -0,0 +1,57 @@
+//! Checks that the canned streams in [`crumbeez_lib::fixtures`] are
+//! deterministic and hold up as input to the rest of the pipeline.
+
+use crumbeez_lib::fixtures::{self, Fixture, FIXTURE_START_MS};
+use crumbeez_lib::{AppRole, KeystrokeEvent, Summary};
+
+#[test]
+fn fixtures_are_deterministic_and_ordered() {
+    for fixture in Fixture::ALL {
+        let entries = fixture.entries(FIXTURE_START_MS);
+        assert!(!entries.is_empty(), "{} is empty", fixture.name());
+        let again = fixture.entries(FIXTURE_START_MS);
+        assert!(entries
+            .iter()
+            .zip(&again)
+            .all(|(a, b)| a.event == b.event && a.timestamp_ms == b.timestamp_ms));
+        assert_eq!(entries.len(), again.len());
+        assert_eq!(entries[0].timestamp_ms, FIXTURE_START_MS);
+        assert!(
+            entries
+                .windows(2)
+                .all(|w| w[0].timestamp_ms < w[1].timestamp_ms),
+            "{} goes back in time",
+            fixture.name()
+        );
+        assert_eq!(Fixture::from_name(fixture.name()), Some(fixture));
+    }
+    assert_eq!(Fixture::from_name("emacs-edit"), None);
+}
+
+#[test]
+fn focus_sets_the_role() {
+    let entries = Fixture::VimEdit.entries(FIXTURE_START_MS);
+    let KeystrokeEvent::PaneFocused(ref pane) = entries[0].event else {
+        panic!("expected a focus first, got {}", entries[0].event);
+    };
+    assert_eq!(pane.role, AppRole::Editor);
+}
+
+#[test]
+fn workday_summarizes_every_fixture() {
+    let entries = fixtures::workday(FIXTURE_START_MS);
+    let total: usize = Fixture::ALL
+        .iter()
+        .map(|fixture| fixture.entries(0).len())
+        .sum();
+    assert_eq!(entries.len(), total);
+
+    let stats = fixtures::event_log(entries.clone()).stats();
+    assert_eq!(stats.total_events, total);
+    assert_eq!(stats.unconsumed_events, total);
+
+    let markdown = Summary::from_events(entries.into_iter()).to_markdown();
+    for command in ["cargo build", "git rebase -i origin/main"] {
+        assert!(markdown.contains(command), "{command} missing:\n{markdown}");
+    }
+}
@@ -0,0 +1,54 @@
+//! Input faster than the flood threshold is counted, then logged as one
+//! `FloodDetected` marker once it subsides.
+
+use crumbeez_lib::{FloodGuard, KeystrokeEvent};
+
+#[test]
+fn typing_speed_is_never_a_flood() {
+    let mut guard = FloodGuard::new(10);
+    for at in (0..5000).step_by(100) {
+        assert!(guard.admit(at));
+    }
+    assert!(!guard.is_flooding());
+    assert_eq!(guard.finish(10_000), None);
+}
+
+#[test]
+fn keys_past_the_threshold_are_held_back() {
+    let mut guard = FloodGuard::new(10);
+    let admitted = (0..50).filter(|at| guard.admit(*at)).count();
+    assert_eq!(admitted, 10);
+    assert!(guard.is_flooding());
+    // Still flooding until a whole quiet second has passed.
+    assert_eq!(guard.finish(1500), None);
+    assert_eq!(
+        guard.finish(2000),
+        Some(KeystrokeEvent::FloodDetected {
+            keys: 40,
+            duration_ms: 990,
+        })
+    );
+    assert!(!guard.is_flooding());
+    assert!(guard.admit(2100));
+}
+
+#[test]
+fn flood_lasts_while_every_second_is_busy() {
+    let mut guard = FloodGuard::new(10);
+    for second in 0..3 {
+        for i in 0..20 {
+            guard.admit(second * 1000 + i);
+        }
+        assert_eq!(guard.finish(second * 1000 + 999), None);
+    }
+    assert_eq!(guard.subsides_at(), Some(4000));
+    // Keys in the quiet second are still held until it's over.
+    assert!(!guard.admit(3500));
+    assert_eq!(
+        guard.finish(4000),
+        Some(KeystrokeEvent::FloodDetected {
+            keys: 51,
+            duration_ms: 2990,
+        })
+    );
+}
@@ -0,0 +1,35 @@
+//! The heartbeat file's JSON, which external monitors parse.
+
+use crumbeez_lib::{Heartbeat, SuspendReason};
+
+#[test]
+fn recording_heartbeat() {
+    let heartbeat = Heartbeat {
+        written_ms: 1_709_284_545_123,
+        last_flush_ms: Some(1_709_284_545_000),
+        suspended: None,
+        errors: 0,
+        next_seq: 245,
+    };
+    assert_eq!(
+        heartbeat.to_json(),
+        "{\"version\":1,\"written_ms\":1709284545123,\"last_flush_ms\":1709284545000,\
+         \"state\":\"recording\",\"errors\":0,\"events\":245}\n"
+    );
+}
+
+#[test]
+fn suspended_before_any_write() {
+    let heartbeat = Heartbeat {
+        written_ms: 1000,
+        last_flush_ms: None,
+        suspended: Some(SuspendReason::Locked),
+        errors: 2,
+        next_seq: 0,
+    };
+    assert_eq!(
+        heartbeat.to_json(),
+        "{\"version\":1,\"written_ms\":1000,\"last_flush_ms\":null,\
+         \"state\":\"locked\",\"errors\":2,\"events\":0}\n"
+    );
+}
@@ -0,0 +1,197 @@
+//! Checks that bash, zsh, fish, and atuin histories are read with their
+//! timestamps and backfilled in front of a fresh log only, and that atuin
+//! lookups are read back.
+
+use crumbeez_lib::{
+    atuin_lookup_query, history_entries, parse_atuin_lookup, parse_history, Activity,
+    CommandOutcome, EditControlEvent, EventLog, HistoryCommand, HistoryFormat, KeystrokeEvent,
+    LogEntry, Summary,
+};
+
+fn command(timestamp_secs: u64, command: &str) -> HistoryCommand {
+    HistoryCommand {
+        timestamp_ms: timestamp_secs * 1000,
+        command: command.to_string(),
+    }
+}
+
+#[test]
+fn bash_needs_histtimeformat_timestamps() {
+    let history = "ls\n#1709284500\ncargo test\n#1709284560\nfor f in *; do\n  echo $f\ndone\n";
+    assert_eq!(
+        parse_history(HistoryFormat::Bash, history.as_bytes()),
+        [
+            command(1709284500, "cargo test"),
+            command(1709284560, "for f in *; do\n  echo $f\ndone"),
+        ]
+    );
+}
+
+#[test]
+fn zsh_extended_history_with_continuations_and_metafied_bytes() {
+    let mut history =
+        b"echo untimed\n: 1709284500:0;cargo test\n: 1709284560:2;printf 'a\\\nb'\n".to_vec();
+    // "ă" is 0xC4 0x83, and zsh writes its 0x83 as 0x83 0xA3.
+    history.extend(b": 1709284620:0;echo \xC4\x83\xA3\n");
+    assert_eq!(
+        parse_history(HistoryFormat::Zsh, &history),
+        [
+            command(1709284500, "cargo test"),
+            command(1709284560, "printf 'a\nb'"),
+            command(1709284620, "echo ă"),
+        ]
+    );
+}
+
+#[test]
+fn fish_history_unescapes_commands() {
+    let history = "\
+- cmd: cargo build
+  when: 1709284500
+- cmd: echo one\\ntwo \\\\
+  when: 1709284560
+  paths:
+    - two
+- cmd: no timestamp
+";
+    assert_eq!(
+        parse_history(HistoryFormat::Fish, history.as_bytes()),
+        [
+            command(1709284500, "cargo build"),
+            command(1709284560, "echo one\ntwo \\"),
+        ]
+    );
+}
+
+#[test]
+fn atuin_query_output_has_nanoseconds() {
+    let output = "1709284500123456789|git status\n1709284560000000000|a | b\\nc\n";
+    assert_eq!(
+        parse_history(HistoryFormat::Atuin, output.as_bytes()),
+        [
+            HistoryCommand {
+                timestamp_ms: 1709284500123,
+                command: "git status".to_string(),
+            },
+            command(1709284560, "a | b\nc"),
+        ]
+    );
+}
+
+#[test]
+fn formats_are_named() {
+    for format in HistoryFormat::ALL {
+        assert_eq!(HistoryFormat::from_name(format.name()), Some(format));
+    }
+    assert_eq!(HistoryFormat::from_name("csh"), None);
+}
+
+#[test]
+fn entries_are_windowed_sorted_and_capped() {
+    let history =
+        b": 1000:0;oldest\n: 4000:0;newest\n: 2000:0;middle\n: 3000:0;  \n: 2500:0;late\n";
+    let entries = history_entries(HistoryFormat::Zsh, history, 1_500_000, 4_000_000, 2);
+    let commands: Vec<(u64, String)> = entries
+        .iter()
+        .map(|e| (e.timestamp_ms, e.event.to_string()))
+        .collect();
+    assert_eq!(
+        commands,
+        [
+            (2_000_000, "$ \"middle\" (zsh history)".to_string()),
+            (2_500_000, "$ \"late\" (zsh history)".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn backfill_goes_in_front_of_a_fresh_log_as_summarized() {
+    let mut log = EventLog::new();
+    log.append(KeystrokeEvent::typed("hello"), 5_000_000);
+    let history = b": 1000:0;make\n: 2000:0;make test\n: 6000:0;after the log starts\n";
+    let entries = history_entries(HistoryFormat::Zsh, history, 0, u64::MAX, 100);
+    assert!(log.is_fresh());
+    assert_eq!(log.backfill(entries.clone()), 2);
+    assert_eq!(log.first_seq(), 0);
+    assert_eq!(log.total_count(), 3);
+    assert_eq!(log.unconsumed_count(), 1);
+    let times: Vec<u64> = log.entries().map(|e| e.timestamp_ms).collect();
+    assert_eq!(times, [1_000_000, 2_000_000, 5_000_000]);
+
+    // Summarized since, so sequence numbers are taken.
+    assert!(!log.is_fresh());
+    assert_eq!(log.backfill(entries), 0);
+}
+
+#[test]
+fn backfill_keeps_the_newest_that_fit() {
+    let mut log = EventLog::new();
+    log.set_capacity(3);
+    log.append(KeystrokeEvent::typed("hello"), 5_000_000);
+    let history = b": 1000:0;one\n: 2000:0;two\n: 3000:0;three\n";
+    let entries = history_entries(HistoryFormat::Zsh, history, 0, u64::MAX, 100);
+    assert_eq!(log.backfill(entries), 2);
+    let times: Vec<u64> = log.entries().map(|e| e.timestamp_ms).collect();
+    assert_eq!(times, [2_000_000, 3_000_000, 5_000_000]);
+}
+
+#[test]
+fn atuin_lookup_reads_outcome_columns() {
+    let output = "1\u{1f}2500000000\u{1f}/home/me/a|b\u{1f}grep -c x\\nfile\n";
+    assert_eq!(
+        parse_atuin_lookup(output),
+        Some((
+            "grep -c x\nfile".to_string(),
+            CommandOutcome {
+                exit_code: 1,
+                duration_ms: 2500,
+                cwd: Some("/home/me/a|b".to_string()),
+            }
+        ))
+    );
+    let no_cwd = parse_atuin_lookup("0\u{1f}1000000\u{1f}\u{1f}ls\n").unwrap();
+    assert_eq!(no_cwd.1.cwd, None);
+    // Still running, so not in the database yet.
+    assert_eq!(parse_atuin_lookup(""), None);
+}
+
+#[test]
+fn atuin_lookup_query_escapes_quotes() {
+    let query = atuin_lookup_query(" echo 'hi' ", 1500);
+    assert!(query.contains("timestamp >= 1500000000"));
+    assert!(query.contains("command = 'echo ''hi'''"));
+}
+
+#[test]
+fn outcomes_replace_the_typed_command_in_summaries() {
+    let entry = |event, timestamp_ms| LogEntry {
+        event,
+        timestamp_ms,
+        corrections: None,
+    };
+    let outcome = CommandOutcome {
+        exit_code: 0,
+        duration_ms: 1200,
+        cwd: None,
+    };
+    let entries = [
+        entry(KeystrokeEvent::typed("cargo test"), 1000),
+        entry(KeystrokeEvent::EditControl(EditControlEvent::Enter), 1100),
+        entry(KeystrokeEvent::typed("ls"), 2000),
+        entry(
+            KeystrokeEvent::CommandExecuted {
+                command: "cargo test".into(),
+                shell: "atuin".into(),
+                outcome: Some(outcome.clone()),
+            },
+            3000,
+        ),
+    ];
+    let summary = Summary::from_events(entries.into_iter());
+    let activities = &summary.segments[0].activities;
+    assert_eq!(activities.len(), 2);
+    assert!(matches!(
+        &activities[0],
+        Activity::Ran { command, outcome: Some(o), .. } if command == "cargo test" && *o == outcome
+    ));
+}
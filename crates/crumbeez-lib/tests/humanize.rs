@@ -0,0 +1,52 @@
+//! Durations, times, and sizes as the plugin pane and reports show them.
+
+use std::time::Duration;
+
+use crumbeez_lib::{HumanDuration, HumanSize, TimeAgo};
+
+#[test]
+fn durations_keep_their_two_largest_units() {
+    let cases = [
+        (0, "<1 s"),
+        (999, "<1 s"),
+        (7_500, "7 s"),
+        (59_999, "59 s"),
+        (60_000, "1 min"),
+        (45 * 60_000 + 59_000, "45 min"),
+        (2 * 3_600_000 + 5 * 60_000 + 59_000, "2 h 05 min"),
+        (24 * 3_600_000 - 1, "23 h 59 min"),
+        (3 * 86_400_000 + 4 * 3_600_000 + 59 * 60_000, "3 d 4 h"),
+    ];
+    for (ms, text) in cases {
+        assert_eq!(HumanDuration::from_ms(ms).to_string(), text, "{} ms", ms);
+    }
+    assert_eq!(HumanDuration(Duration::from_secs(300)).to_string(), "5 min");
+}
+
+#[test]
+fn times_ago() {
+    assert_eq!(TimeAgo::between(1_000, 1_500).to_string(), "just now");
+    assert_eq!(TimeAgo::between(1_000, 8_000).to_string(), "7 s ago");
+    assert_eq!(
+        TimeAgo::between(0, 90 * 60_000).to_string(),
+        "1 h 30 min ago"
+    );
+    // A clock that stepped back.
+    assert_eq!(TimeAgo::between(8_000, 1_000).to_string(), "just now");
+}
+
+#[test]
+fn sizes_use_binary_units() {
+    let cases = [
+        (0, "0 B"),
+        (1023, "1023 B"),
+        (1024, "1.0 KiB"),
+        (3_500, "3.4 KiB"),
+        (1_258_291, "1.2 MiB"),
+        (1024 * 1024 - 1, "1.0 MiB"),
+        (5 * 1024 * 1024 * 1024, "5.0 GiB"),
+    ];
+    for (bytes, text) in cases {
+        assert_eq!(HumanSize(bytes).to_string(), text, "{} bytes", bytes);
+    }
+}
@@ -0,0 +1,112 @@
+//! Checks that long typed text is cut in the middle for display, and
+//! logged as a preview with its full text split off past the project's
+//! `long_text` length.
+
+use crumbeez_lib::fixtures::{StreamBuilder, FIXTURE_START_MS};
+use crumbeez_lib::{
+    text_file, truncate_middle, Activity, KeystrokeEvent, LongTextConfig, ProjectConfig, ELLIPSIS,
+};
+
+#[test]
+fn truncate_middle_keeps_both_ends() {
+    assert_eq!(truncate_middle("short", 10), "short");
+    assert_eq!(truncate_middle("abcdefghijklmnop", 9), "abc … nop");
+    // The odd character goes to the start.
+    assert_eq!(truncate_middle("abcdefghijklmnop", 10), "abcd … nop");
+    // Characters, not bytes.
+    assert_eq!(truncate_middle("ééééééééééé", 7), "éé … éé");
+    // Too small a limit to keep anything either side.
+    assert_eq!(truncate_middle("abcdefghijklmnop", 4), "abcdefghijklmnop");
+}
+
+#[test]
+fn long_text_is_split_into_a_preview_and_a_file() {
+    let config = ProjectConfig::parse("long_text 20").unwrap();
+    let fits = KeystrokeEvent::typed("cargo test");
+    assert_eq!(config.split_long_text(fits.clone(), 5), (fits, None));
+
+    let text = "a".repeat(30) + &"z".repeat(30);
+    let (event, full) = config.split_long_text(KeystrokeEvent::typed(&text), 1_700_000_000_000);
+    assert_eq!(full.as_deref(), Some(text.as_str()));
+    let KeystrokeEvent::LongText {
+        preview,
+        chars,
+        file,
+    } = &event
+    else {
+        panic!("{:?}", event);
+    };
+    assert_eq!(preview.chars().count(), 20);
+    assert!(preview.starts_with("aaaa") && preview.ends_with("zzzz"));
+    assert!(preview.contains(ELLIPSIS));
+    assert_eq!(*chars, 60);
+    assert_eq!(*file, text_file(1_700_000_000_000));
+    assert_eq!(file, "scratchpad/text-1700000000000.txt");
+
+    // Other events pass through.
+    let enter = KeystrokeEvent::EditControl(crumbeez_lib::EditControlEvent::Enter);
+    assert_eq!(config.split_long_text(enter.clone(), 5), (enter, None));
+}
+
+#[test]
+fn long_text_config_parses_and_round_trips() {
+    let config = ProjectConfig::parse("long_text 500 display=80").unwrap();
+    assert_eq!(
+        config.long_text,
+        LongTextConfig {
+            preview_chars: 500,
+            display_chars: 80,
+        }
+    );
+    assert_eq!(ProjectConfig::parse(&config.to_kdl()).unwrap(), config);
+
+    let config = ProjectConfig::parse("long_text 500").unwrap();
+    assert_eq!(config.long_text.display_chars, 120);
+    assert!(config.to_kdl().contains("long_text 500\n"));
+
+    assert!(!ProjectConfig::default().to_kdl().contains("long_text"));
+    assert!(ProjectConfig::parse("long_text 0").is_err());
+    assert!(ProjectConfig::parse("long_text 500 display=0").is_err());
+    assert!(ProjectConfig::parse("long_text 500 display=\"wide\"").is_err());
+}
+
+#[test]
+fn shortened_events_cut_only_text() {
+    let event = KeystrokeEvent::typed("x".repeat(200));
+    let KeystrokeEvent::TextTyped(text) = event.shortened(50).into_owned() else {
+        panic!("not typed text");
+    };
+    assert_eq!(text.chars().count(), 50);
+
+    let prompt = KeystrokeEvent::PromptToAgent("why".into());
+    assert_eq!(*prompt.shortened(2), prompt);
+    let shortcut = KeystrokeEvent::shortcut("Ctrl+C");
+    assert_eq!(*shortcut.shortened(2), shortcut);
+}
+
+#[test]
+fn summaries_quote_long_text_cut_and_list_its_file() {
+    let file = text_file(FIXTURE_START_MS + 60_000);
+    let long = KeystrokeEvent::LongText {
+        preview: "b".repeat(100),
+        chars: 5000,
+        file: file.clone(),
+    };
+    let mut summary = StreamBuilder::new(FIXTURE_START_MS)
+        .typed(&"a".repeat(300))
+        .escape()
+        .at_ms(60_000)
+        .event(long)
+        .summary();
+    summary.shorten_text(40);
+    assert_eq!(summary.files, [file]);
+    let typed: Vec<usize> = summary.segments[0]
+        .activities
+        .iter()
+        .filter_map(|activity| match activity {
+            Activity::Typed { text, .. } => Some(text.chars().count()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(typed, [40, 40]);
+}
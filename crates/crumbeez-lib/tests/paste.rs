@@ -0,0 +1,99 @@
+//! Checks how [`crumbeez_lib::PasteDetector`] brackets bursts of forwarded
+//! keys, and which panes get bracketed pastes.
+
+use crumbeez_lib::{AppRole, PasteDetector, ProjectConfig, PASTE_END, PASTE_GAP_MS, PASTE_START};
+
+/// Forward `keys` as `(arrival ms, text, bytes)` and return what the pane
+/// is sent, with the detector's timer firing at `timer_ms`.
+fn forward(keys: &[(u64, bool, &str)], timer_ms: u64) -> String {
+    let mut detector = PasteDetector::new();
+    let mut out = Vec::new();
+    for (at, text, bytes) in keys {
+        detector.before_key(*at, *text, &mut out);
+        out.extend_from_slice(bytes.as_bytes());
+    }
+    detector.finish(timer_ms, &mut out);
+    String::from_utf8(out)
+        .unwrap()
+        .replace(std::str::from_utf8(PASTE_START).unwrap(), "<")
+        .replace(std::str::from_utf8(PASTE_END).unwrap(), ">")
+}
+
+#[test]
+fn typing_is_left_alone() {
+    let keys = [(0, true, "l"), (80, true, "s"), (150, true, "\r")];
+    assert_eq!(forward(&keys, 1000), "ls\r");
+}
+
+#[test]
+fn a_burst_is_bracketed_from_its_second_key() {
+    let keys = [
+        (1000, true, "f"),
+        (1000, true, "n"),
+        (1001, true, " "),
+        (1001, true, "\r"),
+        (1002, true, "}"),
+    ];
+    assert_eq!(forward(&keys, 1100), "f<n \r}>");
+}
+
+#[test]
+fn the_next_key_ends_a_paste() {
+    let keys = [
+        (0, true, "a"),
+        (0, true, "b"),
+        (0, true, "c"),
+        (200, true, "d"),
+        // A non-text key ends a paste even straight after it.
+        (400, true, "e"),
+        (400, true, "f"),
+        (400, false, "\x1b[A"),
+    ];
+    assert_eq!(forward(&keys, 1000), "a<bc>de<f>\x1b[A");
+}
+
+#[test]
+fn a_paste_ends_only_once_quiet() {
+    let mut detector = PasteDetector::new();
+    let mut out = Vec::new();
+    detector.before_key(0, true, &mut out);
+    detector.before_key(1, true, &mut out);
+    assert!(detector.is_open());
+    let end = detector.ends_at().unwrap();
+    assert!(end > 1 + PASTE_GAP_MS);
+
+    detector.finish(end - 1, &mut out);
+    assert!(detector.is_open());
+    detector.finish(end, &mut out);
+    assert!(!detector.is_open());
+    assert_eq!(out, [PASTE_START, PASTE_END].concat());
+    assert_eq!(detector.ends_at(), None);
+
+    // Abandoning an open paste writes nothing.
+    detector.before_key(10, true, &mut out);
+    detector.before_key(10, true, &mut out);
+    assert!(detector.abandon());
+    assert!(!detector.abandon());
+    detector.before_key(11, true, &mut out);
+    assert!(!detector.is_open());
+}
+
+#[test]
+fn pagers_and_fuzzy_finders_get_plain_pastes() {
+    let config = ProjectConfig::parse("bracketed_paste \"dash\" enabled=false\n").unwrap();
+    let bracketed = |command: &str, role| config.bracketed_paste_for(command, Some(command), role);
+    assert!(bracketed("bash", AppRole::Shell));
+    assert!(bracketed("nvim", AppRole::Editor));
+    assert!(!bracketed("less", AppRole::Pager));
+    assert!(!bracketed("fzf", AppRole::FuzzyFinder));
+    assert!(!bracketed("/bin/dash", AppRole::Shell));
+    assert_eq!(ProjectConfig::parse(&config.to_kdl()).unwrap(), config);
+
+    for bad in [
+        "bracketed_paste \"dash\"",
+        "bracketed_paste enabled=true",
+        "bracketed_paste \"dash\" enabled=\"no\"",
+    ] {
+        assert!(ProjectConfig::parse(bad).is_err(), "{}", bad);
+    }
+}
@@ -0,0 +1,58 @@
+//! Checks that the power mode picks the right profile and that the power
+//! source check's output is read on each platform.
+
+use std::time::Duration;
+
+use crumbeez_lib::{on_battery, PowerMode, PowerProfile};
+
+#[test]
+fn modes_pick_profiles() {
+    for on_battery in [None, Some(false), Some(true)] {
+        assert_eq!(PowerMode::Normal.profile(on_battery), PowerProfile::NORMAL);
+        assert_eq!(PowerMode::Low.profile(on_battery), PowerProfile::LOW);
+    }
+    assert_eq!(PowerMode::Auto.profile(Some(true)), PowerProfile::LOW);
+    assert_eq!(PowerMode::Auto.profile(Some(false)), PowerProfile::NORMAL);
+    // An unknown power source counts as mains.
+    assert_eq!(PowerMode::Auto.profile(None), PowerProfile::NORMAL);
+}
+
+#[test]
+fn modes_parse_and_display() {
+    for mode in [PowerMode::Normal, PowerMode::Low, PowerMode::Auto] {
+        assert_eq!(PowerMode::from_name(&mode.to_string()), Some(mode));
+    }
+    assert_eq!(PowerMode::from_name("battery"), None);
+    assert_eq!(PowerMode::default(), PowerMode::Normal);
+}
+
+#[test]
+fn low_power_lengthens_a_short_inactivity_timeout() {
+    let short = Duration::from_secs(10);
+    let long = Duration::from_secs(10 * 60);
+    assert_eq!(PowerProfile::NORMAL.inactivity_timeout(short), short);
+    assert_eq!(
+        PowerProfile::LOW.inactivity_timeout(short),
+        Duration::from_secs(120)
+    );
+    assert_eq!(PowerProfile::LOW.inactivity_timeout(long), long);
+    assert!(PowerProfile::LOW.flush_interval > PowerProfile::NORMAL.flush_interval);
+    assert!(PowerProfile::LOW.is_low() && !PowerProfile::NORMAL.is_low());
+}
+
+#[test]
+fn power_source_output_is_read() {
+    // macOS.
+    assert_eq!(on_battery("Now drawing from 'Battery Power'\n"), Some(true));
+    assert_eq!(on_battery("Now drawing from 'AC Power'\n"), Some(false));
+    // Windows.
+    assert_eq!(on_battery("Battery Power\r\n"), Some(true));
+    assert_eq!(on_battery("AC Power\r\n"), Some(false));
+    // Linux: a battery and mains, mains unplugged or plugged in.
+    assert_eq!(on_battery("0\n"), Some(true));
+    assert_eq!(on_battery("0\n1\n"), Some(false));
+    assert_eq!(on_battery("1\n0\n"), Some(false));
+    // A desktop without any of these.
+    assert_eq!(on_battery(""), None);
+    assert_eq!(on_battery("No battery\n"), None);
+}
@@ -0,0 +1,87 @@
+//! Project names come from the config, then Cargo.toml, package.json, and
+//! the git remote.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crumbeez_lib::{NameSource, ProjectConfig, ProjectName};
+
+const ROOT: &str = "/home/me/src/client-x/service-y";
+
+fn resolve(configured: Option<&str>, files: &[(&str, &str)]) -> ProjectName {
+    let root = Path::new(ROOT);
+    let files: HashMap<PathBuf, String> = files
+        .iter()
+        .map(|(file, text)| (root.join(file), text.to_string()))
+        .collect();
+    ProjectName::resolve(root, configured, |path| files.get(path).cloned())
+}
+
+#[test]
+fn config_name_wins() {
+    let config = ProjectConfig::parse("project_name \"Client X service\"").unwrap();
+    let name = resolve(
+        config.project_name.as_deref(),
+        &[("Cargo.toml", "[package]\nname = \"service-y\"\n")],
+    );
+    assert_eq!(name.name, "Client X service");
+    assert_eq!(name.source, NameSource::Config);
+    assert_eq!(
+        ProjectConfig::parse(&config.to_kdl()).unwrap().project_name,
+        config.project_name
+    );
+    assert!(ProjectConfig::parse("project_name \"  \"").is_err());
+}
+
+#[test]
+fn cargo_package_name() {
+    let name = resolve(
+        None,
+        &[(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"a\"]\n\n[package]\nversion = \"0.1.0\"\nname = 'billing-api' # the service\n\n[dependencies]\nname = \"x\"\n",
+        )],
+    );
+    assert_eq!(name.name, "billing-api");
+    assert_eq!(name.source, NameSource::CargoToml);
+}
+
+#[test]
+fn virtual_workspace_falls_through_to_package_json() {
+    let name = resolve(
+        None,
+        &[
+            ("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n"),
+            (
+                "package.json",
+                r#"{ "author": { "name": "Me" }, "keywords": ["name"], "name": "@client-x/web!" }"#,
+            ),
+        ],
+    );
+    assert_eq!(name.name, "@client-x/web!");
+    assert_eq!(name.source, NameSource::PackageJson);
+}
+
+#[test]
+fn git_remote_name() {
+    for url in [
+        "git@github.com:client-x/service-y.git",
+        "https://github.com/client-x/service-y/",
+        "\"ssh://git@host:2222/client-x/service-y.git\"",
+    ] {
+        let config = format!(
+            "[core]\n\tbare = false\n[remote \"upstream\"]\n\turl = git@github.com:other/fork.git\n[remote \"origin\"]\n\turl = {}\n\tfetch = +refs/heads/*:refs/remotes/origin/*\n",
+            url
+        );
+        let name = resolve(None, &[(".git/config", &config)]);
+        assert_eq!(name.name, "service-y", "{}", url);
+        assert_eq!(name.source, NameSource::GitRemote);
+    }
+}
+
+#[test]
+fn directory_name_last() {
+    let name = resolve(None, &[("package.json", "{ \"private\": true }")]);
+    assert_eq!(name.name, "service-y");
+    assert_eq!(name.source, NameSource::Directory);
+}
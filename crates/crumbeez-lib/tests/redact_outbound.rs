@@ -0,0 +1,107 @@
+//! `redact_outbound` rules rewrite text on its way out — summaries, context,
+//! live breadcrumbs — while the log keeps it as typed.
+
+use crumbeez_lib::{
+    envelope_json, Activity, KeystrokeEvent, LogEntry, PaneFocusedEvent, ProjectConfig, Regex,
+    Summary,
+};
+
+fn replace(pattern: &str, text: &str) -> String {
+    Regex::new(pattern)
+        .unwrap()
+        .replace_all(text, "X")
+        .into_owned()
+}
+
+#[test]
+fn regex_subset() {
+    assert_eq!(replace("a+", "caaab aab"), "cXb Xb");
+    assert_eq!(
+        replace("[0-9]{3}-[0-9]{4}", "call 555-1234 now"),
+        "call X now"
+    );
+    assert_eq!(replace(r"\bcat\b", "cat concat cat."), "X concat X.");
+    assert_eq!(replace("(?i)acme( corp)?", "ACME Corp and Acme"), "X and X");
+    assert_eq!(
+        replace(r"[^\s@]+@example\.com", "mail bo@example.com"),
+        "mail X"
+    );
+    assert_eq!(replace("^ab|cd$", "ab ab cd cd"), "X ab cd X");
+    assert_eq!(replace("(?:ab)+?", "ababab"), "XXX");
+    assert_eq!(replace(r"\d+\.\d+", "v1.2 and 10.20.3"), "vX and X.3");
+    assert_eq!(replace("x*", "ab"), "XaXbX");
+    assert_eq!(replace("{literal}", "a {literal} brace"), "a X brace");
+
+    for bad in ["(ab", "ab)", "[a-", "*a", r"\q", "a{3,1}", "(?=a)"] {
+        assert!(Regex::new(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn config_round_trip() {
+    let config = ProjectConfig::parse(
+        r#"
+        redact_outbound "(?i)\\bacme corp\\b" "a customer"
+        redact_outbound "[a-z0-9-]+\\.corp\\.internal"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.redact_outbound.len(), 2);
+    assert_eq!(config.redact_outbound[1].replacement, "[REDACTED]");
+    assert_eq!(
+        config.redact_outbound("ssh db-1.corp.internal for Acme Corp"),
+        "ssh [REDACTED] for a customer"
+    );
+    assert_eq!(ProjectConfig::parse(&config.to_kdl()).unwrap(), config);
+
+    let err = ProjectConfig::parse(r#"redact_outbound "(acme""#).unwrap_err();
+    assert!(err.to_string().contains("unclosed '('"), "{}", err);
+}
+
+#[test]
+fn summaries_and_broadcasts_are_redacted() {
+    let config = ProjectConfig::parse(r#"redact_outbound "acme" "client""#).unwrap();
+    let pane = KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+        pane_title: "acme-api".to_string(),
+        command: Some("ssh acme-db".to_string()),
+        host: Some("acme-db".to_string()),
+        ..Default::default()
+    });
+    let typed = KeystrokeEvent::TextTyped("deploy acme".to_string());
+    let entries = [pane, typed.clone()].map(|event| LogEntry {
+        event,
+        timestamp_ms: 0,
+        corrections: None,
+    });
+
+    let mut summary = Summary::from_events(entries.iter().cloned());
+    summary.redact_text(&|text| config.redact_outbound(text));
+    let segment = &summary.segments[0];
+    let pane = segment.pane.as_ref().unwrap();
+    assert_eq!(pane.pane_title, "client-api");
+    assert_eq!(pane.host.as_deref(), Some("client-db"));
+    assert_eq!(
+        segment.activities,
+        [Activity::Typed {
+            text: "deploy client".to_string(),
+            submitted: false
+        }]
+    );
+    assert!(!summary.to_markdown().contains("acme"));
+
+    let mut event = typed;
+    event.redact_text(&|text| config.redact_outbound(text));
+    let json = envelope_json(
+        1,
+        &LogEntry {
+            event,
+            timestamp_ms: 0,
+            corrections: None,
+        },
+    );
+    assert!(
+        json.contains("deploy client") && !json.contains("acme"),
+        "{}",
+        json
+    );
+}
@@ -0,0 +1,49 @@
+//! Checks that REPL input is grouped the way the REPLs themselves group it.
+
+use crumbeez_lib::{needs_continuation, repl_language, AppRole};
+
+#[test]
+fn languages() {
+    let language = |role, command| repl_language(role, Some(command));
+    assert_eq!(
+        language(AppRole::Repl, "/usr/bin/python3.12"),
+        Some("python".into())
+    );
+    assert_eq!(
+        language(AppRole::Repl, "node --inspect"),
+        Some("javascript".into())
+    );
+    assert_eq!(language(AppRole::Repl, "irb"), Some("ruby".into()));
+    assert_eq!(
+        language(AppRole::Repl, "/opt/bin/racket"),
+        Some("racket".into())
+    );
+    assert_eq!(language(AppRole::Sql, "psql app_dev"), Some("sql".into()));
+    assert_eq!(language(AppRole::Shell, "/bin/bash"), None);
+}
+
+#[test]
+fn brackets_and_backslashes() {
+    assert!(needs_continuation("javascript", "const xs = ["));
+    assert!(needs_continuation("javascript", "f(1,\n  g(2"));
+    assert!(!needs_continuation("javascript", "f(1,\n  g(2))"));
+    assert!(!needs_continuation("javascript", "console.log(\"(\")"));
+    assert!(!needs_continuation("ruby", "puts 'it''s ['"));
+    assert!(needs_continuation("ruby", "x = 1 + \\"));
+}
+
+#[test]
+fn python_blocks_end_with_a_blank_line() {
+    assert!(needs_continuation("python", "def f(x):"));
+    assert!(needs_continuation("python", "def f(x):\n    return x"));
+    assert!(!needs_continuation("python", "def f(x):\n    return x\n"));
+    assert!(!needs_continuation("python", "x = {'a': 1}"));
+}
+
+#[test]
+fn sql_statements_end_with_a_semicolon() {
+    assert!(needs_continuation("sql", "select *\n  from users"));
+    assert!(!needs_continuation("sql", "select *\n  from users;"));
+    assert!(!needs_continuation("sql", "\\dt"));
+    assert!(!needs_continuation("sql", ""));
+}
@@ -0,0 +1,105 @@
+//! Reconstructing typing over time, and the asciicast export built on it.
+
+use crumbeez_lib::fixtures::{StreamBuilder, FIXTURE_START_MS};
+use crumbeez_lib::{
+    reconstruct, to_asciicast, AppRole, EditControlEvent, Frame, FrameChange, PaneFocusedEvent,
+    TYPING_INTERVAL_MS,
+};
+
+fn pane(title: &str) -> PaneFocusedEvent {
+    PaneFocusedEvent::new(title)
+        .in_tab("dev")
+        .with_role(AppRole::Shell)
+}
+
+#[test]
+fn text_finishes_when_it_was_logged() {
+    let entries = StreamBuilder::new(FIXTURE_START_MS)
+        .typed("x")
+        .at_ms(10_000)
+        .typed("ls -l")
+        .finish();
+    let frames = reconstruct(&entries, FIXTURE_START_MS, u64::MAX);
+    let times: Vec<u64> = frames[1..].iter().map(|f| f.timestamp_ms).collect();
+    let end = FIXTURE_START_MS + 10_000;
+    let first = end - 4 * TYPING_INTERVAL_MS;
+    assert_eq!(
+        times,
+        [
+            first,
+            first + TYPING_INTERVAL_MS,
+            first + 2 * TYPING_INTERVAL_MS,
+            first + 3 * TYPING_INTERVAL_MS,
+            end
+        ]
+    );
+}
+
+#[test]
+fn fast_typing_starts_after_the_previous_event() {
+    let entries = StreamBuilder::new(FIXTURE_START_MS)
+        .typed("x")
+        .at_ms(100)
+        .typed("abc")
+        .finish();
+    let frames = reconstruct(&entries, FIXTURE_START_MS, u64::MAX);
+    let times: Vec<u64> = frames[1..].iter().map(|f| f.timestamp_ms).collect();
+    assert_eq!(
+        times,
+        [
+            FIXTURE_START_MS,
+            FIXTURE_START_MS + 50,
+            FIXTURE_START_MS + 100
+        ]
+    );
+}
+
+#[test]
+fn panes_keep_their_own_lines() {
+    let entries = StreamBuilder::new(FIXTURE_START_MS)
+        .event(pane("build").into())
+        .typed("cargo")
+        .event(pane("notes").into())
+        .typed("todo")
+        .enter()
+        .at_ms(5000)
+        .event(pane("build").into())
+        .finish();
+    let frames = reconstruct(&entries, FIXTURE_START_MS + 5000, u64::MAX);
+    assert_eq!(
+        frames,
+        [Frame {
+            timestamp_ms: FIXTURE_START_MS + 5000,
+            change: FrameChange::Focus {
+                pane: pane("build"),
+                line: "cargo".into(),
+            },
+        }]
+    );
+}
+
+#[test]
+fn asciicast_has_a_header_and_relative_times() {
+    let entries = StreamBuilder::new(FIXTURE_START_MS)
+        .event(pane("build").into())
+        .at_ms(2000)
+        .typed("ls")
+        .at_ms(2000)
+        .event(EditControlEvent::backspace(1).into())
+        .at_ms(3000)
+        .enter()
+        .finish();
+    let cast = to_asciicast(&entries, FIXTURE_START_MS, u64::MAX);
+    let lines: Vec<&str> = cast.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            r#"{"version":2,"width":100,"height":30,"timestamp":1709284500,"title":"crumbeez replay"}"#,
+            r#"[0.000, "o", "\r\n\u001b[2m── [dev] build ──\u001b[0m\r\n$ "]"#,
+            r#"[1.920, "o", "l"]"#,
+            r#"[2.000, "o", "s"]"#,
+            r#"[2.000, "o", "\u0008 \u0008"]"#,
+            r#"[3.000, "o", "\r\n$ "]"#,
+        ]
+    );
+}
@@ -0,0 +1,86 @@
+//! Commands that are hard to undo are flagged per the project's `risky`
+//! patterns, and listed in summaries under "Notable actions".
+
+use crumbeez_lib::{KeystrokeEvent, LogEntry, ProjectConfig, RiskyConfig, Summary};
+
+#[test]
+fn built_in_patterns_match_anywhere_in_the_line() {
+    let risky = RiskyConfig::default();
+    let cases = [
+        ("rm -rf target", "rm -rf"),
+        ("sudo rm   -rf /", "rm -rf"),
+        (
+            "cd infra && terraform apply -auto-approve",
+            "terraform apply",
+        ),
+        ("git push --force-with-lease", "git push --force"),
+        ("drop table users;", "DROP TABLE"),
+    ];
+    for (command, pattern) in cases {
+        assert_eq!(risky.matching(command), Some(pattern), "{}", command);
+    }
+    for command in [
+        "rm -i notes.txt",
+        "git push",
+        "terraform plan",
+        "inform -rf",
+        "",
+    ] {
+        assert_eq!(risky.matching(command), None, "{}", command);
+    }
+}
+
+#[test]
+fn config_adds_or_replaces_patterns() {
+    let config = ProjectConfig::parse(
+        r#"
+risky "pulumi up" "helm uninstall*prod"
+risky defaults=false annotate=true
+"#,
+    )
+    .unwrap();
+    let risky = &config.risky;
+    assert!(risky.annotate);
+    assert_eq!(risky.matching("pulumi up --yes"), Some("pulumi up"));
+    assert_eq!(
+        risky.matching("helm uninstall api -n prod"),
+        Some("helm uninstall*prod")
+    );
+    assert_eq!(risky.matching("rm -rf target"), None);
+
+    let reparsed = ProjectConfig::parse(&config.to_kdl()).unwrap();
+    assert_eq!(reparsed.risky, config.risky);
+
+    assert!(ProjectConfig::parse("risky annotate=\"yes\"").is_err());
+    assert!(ProjectConfig::parse("risky \"\"").is_err());
+}
+
+#[test]
+fn risky_commands_get_their_own_section() {
+    let entry = |event, timestamp_ms| LogEntry {
+        event,
+        timestamp_ms,
+        corrections: None,
+    };
+    let entries = [
+        entry(KeystrokeEvent::typed("rm -rf build"), 50_000_000),
+        entry(
+            KeystrokeEvent::RiskyCommand {
+                command: "rm -rf build".into(),
+                pattern: "rm -rf".into(),
+                reason: Some("stale cache".into()),
+            },
+            50_520_000,
+        ),
+    ];
+    let summary = Summary::from_events(entries.into_iter());
+    assert_eq!(summary.notable.len(), 1);
+    let markdown = summary.to_markdown();
+    assert!(
+        markdown.contains(
+            "\n### ⚠ Notable actions\n\n- `rm -rf build` at 14:02 UTC, matching `rm -rf`: stale cache\n"
+        ),
+        "{}",
+        markdown
+    );
+}
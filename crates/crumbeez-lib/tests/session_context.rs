@@ -0,0 +1,62 @@
+//! Checks that only the probes' own values are kept from a probe script's
+//! output, and how summaries show them.
+
+use crumbeez_lib::{
+    parse_session_context, KeystrokeEvent, LogEntry, Summary, CONTEXT_PROBES,
+    MAX_CONTEXT_VALUE_CHARS,
+};
+
+#[test]
+fn probe_keys_are_unique() {
+    for (i, probe) in CONTEXT_PROBES.iter().enumerate() {
+        assert!(
+            CONTEXT_PROBES[..i].iter().all(|p| p.key != probe.key),
+            "{} twice",
+            probe.key
+        );
+    }
+}
+
+#[test]
+fn unknown_keys_and_empty_values_are_dropped() {
+    let output = "node\u{1f} v20.11.0 \n\
+                  python\u{1f}\n\
+                  HOME\u{1f}/home/me\n\
+                  kube-context\u{1f}staging=eu\n\
+                  no separator\n";
+    let context = parse_session_context(output);
+    let pairs: Vec<(&str, &str)> = context
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+    assert_eq!(
+        pairs,
+        [("kube-context", "staging=eu"), ("node", "v20.11.0")]
+    );
+}
+
+#[test]
+fn long_values_are_cut() {
+    let output = format!("VIRTUAL_ENV\u{1f}/{}\n", "a".repeat(500));
+    let context = parse_session_context(&output);
+    assert_eq!(
+        context["VIRTUAL_ENV"].chars().count(),
+        MAX_CONTEXT_VALUE_CHARS
+    );
+}
+
+#[test]
+fn summaries_say_what_the_session_worked_with() {
+    let context = parse_session_context("VIRTUAL_ENV\u{1f}/app/.venv\nrust\u{1f}rustc 1.80.0\n");
+    let entries = [LogEntry {
+        event: KeystrokeEvent::SessionContext(context),
+        timestamp_ms: 1000,
+        corrections: None,
+    }];
+    let markdown = Summary::from_events(entries.into_iter()).to_markdown();
+    assert!(
+        markdown.contains("working with VIRTUAL_ENV `/app/.venv`, rust `rustc 1.80.0`"),
+        "{}",
+        markdown
+    );
+}
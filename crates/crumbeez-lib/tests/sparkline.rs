@@ -0,0 +1,40 @@
+//! Checks the per-minute activity chart from [`crumbeez_lib::sparkline`].
+
+use crumbeez_lib::fixtures::FIXTURE_START_MS;
+use crumbeez_lib::{events_per_minute, sparkline};
+
+const MINUTE_MS: u64 = 60 * 1000;
+
+#[test]
+fn counts_land_in_their_minute() {
+    let timestamps = [
+        FIXTURE_START_MS,
+        FIXTURE_START_MS - 1,
+        FIXTURE_START_MS - MINUTE_MS,
+        FIXTURE_START_MS - 3 * MINUTE_MS + 1,
+        // Too old, and from a clock that stepped back.
+        FIXTURE_START_MS - 4 * MINUTE_MS,
+        FIXTURE_START_MS + 5000,
+    ];
+    assert_eq!(
+        events_per_minute(timestamps, FIXTURE_START_MS, 4),
+        [0, 1, 1, 3]
+    );
+}
+
+#[test]
+fn bars_scale_to_the_busiest_minute() {
+    let mut timestamps = vec![FIXTURE_START_MS; 8];
+    timestamps.extend([FIXTURE_START_MS - MINUTE_MS; 4]);
+    timestamps.push(FIXTURE_START_MS - 3 * MINUTE_MS);
+    assert_eq!(sparkline(timestamps, FIXTURE_START_MS, 5), " ▁ ▄█");
+}
+
+#[test]
+fn an_idle_log_is_blank() {
+    assert_eq!(sparkline([], FIXTURE_START_MS, 3), "   ");
+    assert_eq!(
+        sparkline([FIXTURE_START_MS - 90 * MINUTE_MS], FIXTURE_START_MS, 3),
+        "   "
+    );
+}
@@ -0,0 +1,70 @@
+//! Panes running ssh are tagged with the remote host, which summaries show
+//! and `exclude_hosts` can keep out of the log.
+
+use crumbeez_lib::{ssh_host, PaneFocusedEvent, ProjectConfig};
+
+#[test]
+fn host_from_the_command() {
+    for command in [
+        "ssh prod-db-1",
+        "/usr/bin/ssh deploy@prod-db-1",
+        "ssh -p 2222 -i ~/.ssh/deploy deploy@prod-db-1 uptime",
+        "ssh -tp 2222 prod-db-1",
+        "ssh -A -o StrictHostKeyChecking=no prod-db-1",
+        "ssh ssh://deploy@prod-db-1:2222",
+        "ssh -- prod-db-1",
+        "mosh --ssh=ssh -p 2222 prod-db-1",
+    ] {
+        assert_eq!(
+            ssh_host(Some(command), "zsh").as_deref(),
+            Some("prod-db-1"),
+            "{}",
+            command
+        );
+    }
+    assert_eq!(
+        ssh_host(Some("ssh root@[::1]:22"), "").as_deref(),
+        Some("::1")
+    );
+}
+
+#[test]
+fn host_from_the_title_of_a_local_shell() {
+    // Shells set the title to the command they're running.
+    assert_eq!(
+        ssh_host(Some("/bin/zsh"), "ssh deploy@prod-db-1").as_deref(),
+        Some("prod-db-1")
+    );
+}
+
+#[test]
+fn other_programs_have_no_host() {
+    assert_eq!(ssh_host(Some("/bin/zsh"), "~/src/sshd-config"), None);
+    assert_eq!(ssh_host(Some("nvim ssh.rs"), "nvim"), None);
+    assert_eq!(ssh_host(Some("ssh -V"), "ssh -V"), None);
+    assert_eq!(ssh_host(None, "sshfs"), None);
+}
+
+#[test]
+fn summaries_show_the_host() {
+    let pane = PaneFocusedEvent {
+        tab_name: Some("ops".into()),
+        pane_title: "deploy@prod-db-1: ~".into(),
+        command: Some("ssh deploy@prod-db-1".into()),
+        host: Some("prod-db-1".into()),
+        ..Default::default()
+    };
+    assert_eq!(
+        pane.to_string(),
+        "[ops (ssh deploy@prod-db-1)] deploy@prod-db-1: ~ on prod-db-1"
+    );
+}
+
+#[test]
+fn sensitive_hosts_are_excluded() {
+    let config = ProjectConfig::parse("exclude_hosts \"prod-*\" \"*.bank.internal\"").unwrap();
+    assert!(config.excludes_host("prod-db-1"));
+    assert!(config.excludes_host("vault.bank.internal"));
+    assert!(!config.excludes_host("staging-db-1"));
+    assert_eq!(ProjectConfig::parse(&config.to_kdl()).unwrap(), config);
+}
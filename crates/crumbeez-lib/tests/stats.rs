@@ -0,0 +1,56 @@
+//! Checks the headline numbers from [`EventLog::stats`].
+
+use crumbeez_lib::{AppRole, EventLog, KeystrokeEvent, LogStats, PaneFocusedEvent};
+
+/// 2024-03-01 00:00:00 UTC
+const DAY_MS: u64 = 1_709_251_200_000;
+const HOUR_MS: u64 = 60 * 60 * 1000;
+
+fn focus(title: &str) -> KeystrokeEvent {
+    KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+        tab_name: Some("dev".into()),
+        pane_title: title.into(),
+        command: None,
+        is_plugin: false,
+        role: AppRole::Shell,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn empty_log() {
+    assert_eq!(EventLog::new().stats(), LogStats::default());
+}
+
+#[test]
+fn counts_days_panes_hours_and_text() {
+    let mut log = EventLog::new();
+    log.append(focus("zsh"), DAY_MS + 9 * HOUR_MS);
+    log.append(
+        KeystrokeEvent::TextTyped("cargo test".into()),
+        DAY_MS + 9 * HOUR_MS,
+    );
+    log.append(focus("nvim"), DAY_MS + 14 * HOUR_MS);
+    log.append(KeystrokeEvent::ShortText(2), DAY_MS + 14 * HOUR_MS);
+    log.append(focus("zsh"), DAY_MS + 24 * HOUR_MS + 14 * HOUR_MS);
+    log.append(
+        KeystrokeEvent::PromptToAgent("why?".into()),
+        DAY_MS + 24 * HOUR_MS + 14 * HOUR_MS,
+    );
+    log.consume(2);
+
+    let stats = log.stats();
+    assert_eq!(stats.total_events, 6);
+    assert_eq!(stats.unconsumed_events, 4);
+    assert_eq!(
+        stats.events_per_day.into_iter().collect::<Vec<_>>(),
+        [("2024-03-01".to_string(), 4), ("2024-03-02".to_string(), 2)]
+    );
+    assert_eq!(stats.unique_panes, 2);
+    assert_eq!(stats.busiest_hour, Some(14));
+    assert_eq!(stats.typed_chars, 10 + 2 + 4);
+    assert_eq!(
+        stats.span_ms,
+        Some((DAY_MS + 9 * HOUR_MS, DAY_MS + 38 * HOUR_MS))
+    );
+}
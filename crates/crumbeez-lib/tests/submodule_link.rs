@@ -0,0 +1,71 @@
+//! Links from a superproject to summaries made in its submodules, and the
+//! `superproject` setting choosing what it keeps.
+
+use std::path::Path;
+
+use crumbeez_lib::fixtures::FIXTURE_START_MS;
+use crumbeez_lib::{
+    submodule_links_path, ProjectConfig, SubmoduleLink, SummaryId, SummaryLink, SuperprojectRouting,
+};
+
+fn summary_link() -> SummaryLink {
+    SummaryLink {
+        id: SummaryId::new(FIXTURE_START_MS, 7),
+        day: "2024-03-01".into(),
+        first_seq: 120,
+        last_seq: 245,
+        files: vec!["scratchpad/snapshot-1709284545000.txt".into()],
+    }
+}
+
+#[test]
+fn links_point_at_the_submodule_summary() {
+    let link = SubmoduleLink::new("libs/parser", &summary_link());
+    assert_eq!(
+        link.to_kdl(),
+        "submodule \"libs/parser\" summary=\"01HQWMQ8H00000000000000007\" day=\"2024-03-01\" \
+         first_seq=120 last_seq=245\n"
+    );
+    assert_eq!(link.summary, summary_link().id);
+}
+
+#[test]
+fn links_file_round_trips_and_skips_damage() {
+    let first = SubmoduleLink::new("libs/parser", &summary_link());
+    let second = SubmoduleLink {
+        submodule: "vendor/\"odd\" name".into(),
+        first_seq: 246,
+        last_seq: 260,
+        ..first.clone()
+    };
+    let mut text = first.to_kdl() + &second.to_kdl();
+    text.push_str("submodule \"libs/parser\" summary=\"01HQ");
+    assert_eq!(SubmoduleLink::parse_all(&text), [first, second]);
+    assert!(SubmoduleLink::parse_all(&summary_link().to_kdl()).is_empty());
+}
+
+#[test]
+fn links_file_is_in_the_summaries_dir() {
+    assert_eq!(
+        submodule_links_path(Path::new("/work/app/.crumbeez")),
+        Path::new("/work/app/.crumbeez/summaries/submodule-links.kdl")
+    );
+}
+
+#[test]
+fn superproject_setting_parses_and_round_trips() {
+    assert_eq!(
+        ProjectConfig::default().superproject,
+        SuperprojectRouting::Link
+    );
+    assert!(!ProjectConfig::default().to_kdl().contains("superproject"));
+    for (name, routing) in [
+        ("summary", SuperprojectRouting::Summary),
+        ("none", SuperprojectRouting::None),
+    ] {
+        let config = ProjectConfig::parse(&format!("superproject \"{name}\"")).unwrap();
+        assert_eq!(config.superproject, routing);
+        assert_eq!(ProjectConfig::parse(&config.to_kdl()).unwrap(), config);
+    }
+    assert!(ProjectConfig::parse("superproject \"copy\"").is_err());
+}
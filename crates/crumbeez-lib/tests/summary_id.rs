@@ -0,0 +1,72 @@
+//! Summary IDs and the links file mapping them to events.
+
+use crumbeez_lib::fixtures::{StreamBuilder, FIXTURE_START_MS};
+use crumbeez_lib::{snapshot_file, KeystrokeEvent, SummaryId, SummaryLink};
+
+#[test]
+fn ids_are_ulids() {
+    let id = SummaryId::new(FIXTURE_START_MS, 0x1234);
+    assert_eq!(id.to_string(), "01HQWMQ8H000000000000004HM");
+    assert_eq!(id.timestamp_ms(), FIXTURE_START_MS);
+    assert_eq!("01hqwmq8h000000000000004hm".parse(), Ok(id));
+    assert_eq!(
+        SummaryId::new(u64::MAX, u128::MAX).to_string(),
+        "7ZZZZZZZZZZZZZZZZZZZZZZZZZ"
+    );
+}
+
+#[test]
+fn ids_sort_by_time() {
+    let earlier = SummaryId::new(FIXTURE_START_MS, u128::MAX);
+    let later = SummaryId::new(FIXTURE_START_MS + 1, 0);
+    assert!(earlier < later);
+    assert!(earlier.to_string() < later.to_string());
+}
+
+#[test]
+fn malformed_ids_are_rejected() {
+    for text in [
+        "",
+        "01HQWMQ8H",
+        "81HQWMQ8H000000000000004HM",
+        "01HQWMQ8H00000000000000UHM",
+    ] {
+        assert!(text.parse::<SummaryId>().is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn front_matter_carries_the_id() {
+    let mut summary = StreamBuilder::new(FIXTURE_START_MS).typed("ls").summary();
+    summary.id = Some(SummaryId::new(FIXTURE_START_MS, 0x1234));
+    assert!(
+        summary
+            .to_markdown()
+            .starts_with("---\nid: 01HQWMQ8H000000000000004HM\n---\n## "),
+        "{}",
+        summary.to_markdown()
+    );
+}
+
+#[test]
+fn links_round_trip() {
+    let mut summary = StreamBuilder::new(FIXTURE_START_MS)
+        .typed("cargo test")
+        .event(KeystrokeEvent::SnapshotTaken(snapshot_file(
+            FIXTURE_START_MS + 1000,
+        )))
+        .typed("cargo test -- --nocapture")
+        .summary();
+    assert_eq!(SummaryLink::new(&summary, 120), None);
+
+    summary.id = Some(SummaryId::new(FIXTURE_START_MS, 7));
+    let link = SummaryLink::new(&summary, 120).expect("has an ID and events");
+    assert_eq!(
+        link.to_kdl(),
+        "summary \"01HQWMQ8H00000000000000007\" day=\"2024-03-01\" first_seq=120 last_seq=122 \
+         { file \"scratchpad/snapshot-1709284501000.txt\" }\n"
+    );
+
+    let text = format!("{}summary \"01HQ\n{}", link.to_kdl(), link.to_kdl());
+    assert_eq!(SummaryLink::parse_all(&text), vec![link.clone(), link]);
+}
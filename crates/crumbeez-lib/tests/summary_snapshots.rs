@@ -0,0 +1,431 @@
+//! Snapshot tests for summary rendering.
+//!
+//! Each test feeds a canned event stream through [`Summary`] and locks both
+//! the Markdown and narrative output.  The narrative is what an LLM sees, so
+//! any change to it should be a deliberate one: review the diff with
+//! `cargo insta review` and commit the updated snapshots.
+
+use crumbeez_lib::fixtures::{StreamBuilder, FIXTURE_START_MS};
+use crumbeez_lib::{
+    snapshot_file, split_work_blocks, work_blocks_to_markdown, EditControlEvent, EventKind,
+    KeystrokeEvent, Locale, NavDirection, NavigationEvent, ProjectConfig, Standup, Summary,
+    SummaryIndex, SummaryTrigger, SuspendReason, WeeklyRollup, DEFAULT_IDLE_GAP_MS,
+};
+
+fn assert_summary_snapshots(name: &str, summary: &Summary) {
+    insta::assert_snapshot!(format!("{name}_markdown"), summary.to_markdown());
+    insta::assert_snapshot!(format!("{name}_narrative"), summary.to_narrative());
+}
+
+#[test]
+fn commit_message() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "dev", "~/src/crumbeez", "/bin/bash")
+        .typed("git add -p")
+        .enter()
+        .after_secs(40)
+        .typed("git commit -m \"Fix `word_left` at start of line\"")
+        .enter()
+        .summary();
+    assert_summary_snapshots("commit_message", &summary);
+}
+
+#[test]
+fn nvim_editing() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "dev", "nvim src/lib.rs", "/usr/bin/nvim")
+        .nav(NavDirection::Down, 12)
+        .typed("o")
+        .typed("// TODO: handle wide chars")
+        .escape()
+        .typed(":w")
+        .enter()
+        .nav(NavDirection::Up, 3)
+        .ctrl('r')
+        .summary();
+    assert_summary_snapshots("nvim_editing", &summary);
+}
+
+#[test]
+fn build_then_fix() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "build", "cargo", "/bin/zsh")
+        .typed("cargo build --release")
+        .enter()
+        .after_secs(90)
+        .focus(2, "dev", "nvim src/main.rs", "/usr/bin/nvim")
+        .typed("dd")
+        .ctrl('s')
+        .focus(1, "build", "cargo", "/bin/zsh")
+        .nav(NavDirection::Up, 1)
+        .enter()
+        .ctrl('c')
+        .summary();
+    assert_summary_snapshots("build_then_fix", &summary);
+}
+
+#[test]
+fn bookmarked_attempt() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .event(KeystrokeEvent::annotation("trying approach B"))
+        .focus(1, "dev", "~/src/crumbeez", "/bin/bash")
+        .typed("git stash")
+        .enter()
+        .typed("cargo test")
+        .enter()
+        .summary();
+    assert_summary_snapshots("bookmarked_attempt", &summary);
+}
+
+#[test]
+fn work_day_blocks() {
+    let stream = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "dev", "nvim src/lib.rs", "/usr/bin/nvim")
+        .typed("fn split()")
+        .nav(NavDirection::Down, 4)
+        .typed("todo!()")
+        .ctrl('s')
+        .focus(2, "build", "cargo", "/bin/zsh")
+        .typed("cargo test")
+        .enter()
+        // Lunch.
+        .after_secs(60 * 60)
+        .event(KeystrokeEvent::annotation("after lunch"))
+        .typed("cargo test")
+        .enter()
+        .focus(1, "dev", "nvim src/lib.rs", "/usr/bin/nvim")
+        .typed("x")
+        // Short interruption, same block.
+        .after_secs(5 * 60)
+        .typed("y")
+        .ctrl('s');
+    let blocks = split_work_blocks(&stream.finish(), DEFAULT_IDLE_GAP_MS);
+    insta::assert_snapshot!("work_day_blocks", work_blocks_to_markdown(&blocks));
+    insta::assert_snapshot!("work_day_last_block", blocks[1].summary.to_narrative());
+}
+
+#[test]
+fn standup_report() {
+    let stream = StreamBuilder::new(FIXTURE_START_MS)
+        // The day before, outside the window.
+        .focus(1, "dev", "nvim notes.md", "/usr/bin/nvim")
+        .typed("old news")
+        .after_secs(26 * 60 * 60)
+        .event(KeystrokeEvent::annotation("ship the parser"))
+        .focus(2, "dev", "nvim src/parse.rs", "/usr/bin/nvim")
+        .typed("fn parse()")
+        .nav(NavDirection::Down, 8)
+        .after_secs(10 * 60)
+        .typed("let tokens = lex(input);")
+        .nav(NavDirection::Up, 2)
+        .after_secs(10 * 60)
+        .typed("todo!()")
+        .ctrl('s')
+        .focus(3, "build", "cargo", "/bin/zsh")
+        .typed("cargo test")
+        .enter()
+        .after_secs(5 * 60)
+        .typed("cargo test")
+        .enter()
+        .typed("git commit -am parser")
+        .enter()
+        // Meeting.
+        .after_secs(90 * 60)
+        .focus(2, "dev", "nvim src/parse.rs", "/usr/bin/nvim")
+        .typed("// handle EOF")
+        .after_secs(12 * 60)
+        .ctrl('s');
+    let now_ms = stream.now_ms();
+    let report = Standup::from_entries(&stream.finish(), now_ms);
+    assert_eq!(report.file_name(), "standup-2024-03-02.md");
+    insta::assert_snapshot!("standup_report", report.to_markdown());
+}
+
+#[test]
+fn weekly_rollup() {
+    // Two projects across a week boundary, with a superseded record, a record
+    // from the following week, and a line cut short by a crash.
+    let index = SummaryIndex::parse(concat!(
+        "day \"2024-02-26\" root=\"/src/crumbeez\" dir=\"/src/crumbeez/.crumbeez\" active_ms=3600000 events=420 { command \"cargo test\" count=6; command \"git status\" count=2 }\n",
+        "day \"2024-02-26\" root=\"/src/site\" dir=\"/home/u/.local/share/crumbeez/projects/site-1a2b\" active_ms=1800000 events=90 { command \"npm run dev\" count=1 }\n",
+        "day \"2024-02-28\" root=\"/src/crumbeez\" dir=\"/src/crumbeez/.crumbeez\" active_ms=600000 events=40\n",
+        "day \"2024-02-28\" root=\"/src/crumbeez\" dir=\"/src/crumbeez/.crumbeez\" active_ms=5400000 events=610 { command \"cargo test\" count=3 }\n",
+        "day \"2024-03-04\" root=\"/src/site\" dir=\"/home/u/.local/share/crumbeez/projects/site-1a2b\" active_ms=60000 events=5\n",
+        "day \"2024-03-01\" root=\"/src/si",
+    ));
+    assert_eq!(index.records.len(), 4);
+    assert_eq!(index.skipped_lines, 1);
+
+    let rollup = WeeklyRollup::from_index(&index, FIXTURE_START_MS);
+    assert_eq!(rollup.file_name(), "weekly-2024-02-26.md");
+    assert_eq!(rollup.past_daily_files(FIXTURE_START_MS).len(), 3);
+    insta::assert_snapshot!("weekly_rollup", rollup.to_markdown());
+}
+
+#[test]
+fn app_profiles() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "db", "psql", "/usr/bin/psql app_dev")
+        .typed("select count(*) from users;")
+        .enter()
+        .focus(2, "docs", "less CHANGELOG.md", "/usr/bin/less CHANGELOG.md")
+        .typed(" ")
+        .typed(" ")
+        // The plugin seals the search into one entry.
+        .typed("/breaking")
+        .enter()
+        .typed("j")
+        .typed("q")
+        .focus(3, "dev", "~/src/crumbeez", "/bin/bash")
+        .typed("git log --oneline")
+        .enter()
+        .summary();
+    assert_summary_snapshots("app_profiles", &summary);
+}
+
+#[test]
+fn fuzzy_selection() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "dev", "~/src/crumbeez", "/bin/bash")
+        .ctrl('r')
+        .focus(2, "dev", "fzf", "/usr/bin/fzf")
+        .event(KeystrokeEvent::FuzzySelected {
+            query: "cargo test".to_string(),
+            moves: 2,
+        })
+        .focus(1, "dev", "~/src/crumbeez", "/bin/bash")
+        .enter()
+        .focus(2, "dev", "fzf", "/usr/bin/fzf")
+        .event(KeystrokeEvent::FuzzySelected {
+            query: "parse.rs".to_string(),
+            moves: 0,
+        })
+        .summary();
+    assert_summary_snapshots("fuzzy_selection", &summary);
+}
+
+#[test]
+fn repl_session() {
+    let repl = |language: &str, code: &str| KeystrokeEvent::ReplInput {
+        language: language.to_string(),
+        code: code.to_string(),
+    };
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "scratch", "python3", "/usr/bin/python3")
+        .event(repl("python", "import json"))
+        .event(repl(
+            "python",
+            "def load(path):\n    return json.load(open(path))",
+        ))
+        .event(repl("python", "load('fixtures/day.json')['events'][:3]"))
+        .focus(2, "db", "psql", "/usr/bin/psql app_dev")
+        .event(repl("sql", "select id, name\n  from users\n where active;"))
+        .summary();
+    assert_summary_snapshots("repl_session", &summary);
+}
+
+#[test]
+fn container_shell() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "app", "zsh", "/bin/zsh")
+        .typed("docker compose up -d db")
+        .enter()
+        .typed("docker exec -it app-web-1 bash")
+        .enter()
+        .typed("bin/rails db:migrate")
+        .enter()
+        .typed("exit")
+        .enter()
+        .typed("git status")
+        .enter()
+        .summary();
+    assert_summary_snapshots("container_shell", &summary);
+}
+
+#[test]
+fn agent_pair_pane() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "pair", "✳ Claude Code", "claude")
+        .event(KeystrokeEvent::PromptToAgent(
+            "the retention test fails on leap days, find out why".to_string(),
+        ))
+        .after_secs(20)
+        .event(KeystrokeEvent::AgentStatus(
+            "Reading retention.rs".to_string(),
+        ))
+        .after_secs(40)
+        .event(KeystrokeEvent::AgentStatus("Running tests".to_string()))
+        .after_secs(30)
+        .event(KeystrokeEvent::PromptToAgent(
+            "fix it, but keep the public API\nand add a regression test".to_string(),
+        ))
+        .focus(2, "shell", "zsh", "/bin/zsh")
+        .typed("git diff --stat")
+        .enter()
+        .summary();
+    assert_summary_snapshots("agent_pair_pane", &summary);
+}
+
+#[test]
+fn editor_title_changes() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "code", "nvim", "/usr/bin/nvim")
+        .after_secs(3)
+        .event(KeystrokeEvent::PaneTitleChanged(
+            "src/event_log.rs - NVIM".to_string(),
+        ))
+        .typed("/serialize_since")
+        .enter()
+        .after_secs(60)
+        .event(KeystrokeEvent::PaneTitleChanged(
+            "src/summary.rs - NVIM".to_string(),
+        ))
+        .summary();
+    assert_summary_snapshots("editor_title_changes", &summary);
+}
+
+#[test]
+fn short_text_counted() {
+    let config = ProjectConfig::parse("min_text_length 3").unwrap();
+    let sealed = |text: &str| config.seal_text(text).unwrap();
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "dev", "zsh", "/bin/zsh")
+        .event(sealed("rm -i *.orig"))
+        .enter()
+        .event(sealed("y"))
+        .enter()
+        .event(sealed("n"))
+        .enter()
+        .event(sealed("ls"))
+        .enter()
+        .summary();
+    assert_summary_snapshots("short_text_counted", &summary);
+
+    let config = ProjectConfig::parse(r#"min_text_length 3 short="drop""#).unwrap();
+    assert_eq!(config.seal_text("y"), None);
+    assert!(config.seal_text("yes").is_some());
+}
+
+#[test]
+fn editor_multiline() {
+    // The plugin keeps an editor's live text open across Enter, so a block
+    // typed in one go arrives as a single multi-line entry.
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "dev", "hx src/retry.rs", "hx")
+        .typed("fn backoff(attempt: u32) -> Duration {\n    Duration::from_millis(100 << attempt.min(6))\n}")
+        .escape()
+        .ctrl('s')
+        .summary();
+    assert_summary_snapshots("editor_multiline", &summary);
+}
+
+#[test]
+fn suspended_session_blocks() {
+    let stream = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "dev", "zsh", "/bin/zsh")
+        .typed("cargo build")
+        .enter()
+        // Locked for a couple of minutes: shorter than the idle gap, but
+        // nothing was logged in between, so it still ends the block.
+        .event(KeystrokeEvent::SessionSuspended(SuspendReason::Locked))
+        .after_secs(2 * 60)
+        .event(KeystrokeEvent::SessionResumed)
+        .typed("cargo test")
+        .enter();
+    let blocks = split_work_blocks(&stream.finish(), DEFAULT_IDLE_GAP_MS);
+    insta::assert_snapshot!("suspended_session_blocks", work_blocks_to_markdown(&blocks));
+}
+
+#[test]
+fn zellij_modes() {
+    let mode = |name: &str| KeystrokeEvent::ModeChanged(name.to_string());
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "build", "cargo", "/bin/zsh")
+        .typed("cargo test")
+        .enter()
+        // Ctrl+t then `n`: a new tab, not text for the shell.
+        .event(mode("tab"))
+        .typed("n")
+        .event(mode("normal"))
+        .event(mode("scroll"))
+        .nav(NavDirection::PageUp, 3)
+        .after_secs(150)
+        .event(mode("normal"))
+        .typed("cargo test -- --nocapture")
+        .enter()
+        .summary();
+    assert_summary_snapshots("zellij_modes", &summary);
+}
+
+#[test]
+fn pinned_snapshot() {
+    let summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "build", "cargo", "/bin/zsh")
+        .typed("cargo build --release")
+        .enter()
+        .after_secs(40)
+        .event(KeystrokeEvent::SnapshotTaken(snapshot_file(
+            FIXTURE_START_MS + 45_000,
+        )))
+        .typed("cargo build")
+        .enter()
+        .summary();
+    assert_summary_snapshots("pinned_snapshot", &summary);
+}
+
+#[test]
+fn work_boundaries() {
+    let mut summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "scratch", "zsh", "/bin/zsh")
+        .typed("cargo bench")
+        .enter()
+        .event(KeystrokeEvent::TabClosed("scratch".to_string()))
+        .event(KeystrokeEvent::SessionRenamed("release-1.2".to_string()))
+        .summary();
+    summary.trigger = Some(SummaryTrigger::TabClose);
+    assert_summary_snapshots("work_boundaries", &summary);
+}
+
+#[test]
+fn disabled_navigation_counted() {
+    let config = ProjectConfig::parse("disable_events \"navigation\" \"edit-control\"").unwrap();
+    let mut stream =
+        StreamBuilder::new(FIXTURE_START_MS).focus(1, "code", "nvim", "nvim src/lib.rs");
+    let mut counts = std::collections::BTreeMap::new();
+    for event in [
+        NavigationEvent::down(40).into(),
+        KeystrokeEvent::typed("fn parse()"),
+        EditControlEvent::backspace(3).into(),
+        KeystrokeEvent::EditControl(EditControlEvent::Enter),
+        NavigationEvent::page_down(2).into(),
+    ] {
+        match config.filtered(&event) {
+            Some((kind, count)) => *counts.entry(kind).or_insert(0) += count,
+            None => stream = stream.event(event),
+        }
+    }
+    for (kind, count) in counts {
+        stream = stream.event(KeystrokeEvent::Filtered { kind, count });
+    }
+    let summary = stream.summary();
+    assert_eq!(summary.filtered.get(&EventKind::Navigation), Some(&42));
+    assert_summary_snapshots("disabled_navigation_counted", &summary);
+}
+
+#[test]
+fn german_locale() {
+    let mut summary = StreamBuilder::new(FIXTURE_START_MS)
+        .focus(1, "dev", "nvim src/lib.rs", "/usr/bin/nvim")
+        .nav(NavDirection::Down, 12)
+        .typed("// TODO")
+        .ctrl('s')
+        .focus(2, "build", "cargo", "/bin/zsh")
+        .typed("cargo test")
+        .enter()
+        .event(KeystrokeEvent::ModeChanged("scroll".to_string()))
+        .after_secs(120)
+        .event(KeystrokeEvent::ModeChanged("normal".to_string()))
+        .summary();
+    summary.locale = Locale::German;
+    assert_summary_snapshots("german_locale", &summary);
+}
@@ -0,0 +1,119 @@
+//! The summary store reads a day's summaries back from its Markdown file
+//! and pages through them, newest first.
+
+use crumbeez_lib::fixtures::{StreamBuilder, FIXTURE_START_MS};
+use crumbeez_lib::{Summary, SummaryId, SummaryStore, SummaryTrigger};
+
+/// A summary of typing `text` `n` minutes after the start, with an ID.
+fn summary(n: u64, text: &str) -> Summary {
+    let at = FIXTURE_START_MS + n * 60_000;
+    let mut summary = StreamBuilder::new(at).typed(text).summary();
+    summary.id = Some(SummaryId::new(at, n.into()));
+    summary
+}
+
+/// The daily file the plugin appends `summaries` to.
+fn daily_file(summaries: &[Summary]) -> String {
+    summaries
+        .iter()
+        .map(|s| format!("{}\n", s.to_markdown()))
+        .collect()
+}
+
+#[test]
+fn reads_back_what_was_appended() {
+    let summaries: Vec<_> = (0..3).map(|n| summary(n, &format!("step {}", n))).collect();
+    let store = SummaryStore::parse(&daily_file(&summaries));
+    assert_eq!(store.len(), 3);
+
+    let mut made = SummaryStore::default();
+    for s in &summaries {
+        made.push(s);
+    }
+    assert_eq!(store, made);
+
+    let second = store.get(summaries[1].id.unwrap()).unwrap();
+    assert_eq!(second.headline(), "09:16–09:16 UTC · 1 events");
+    assert_eq!(
+        second.outline(),
+        [
+            "09:16–09:16 UTC · 1 events",
+            "  (unknown pane)",
+            "    typed `step 1`",
+        ]
+    );
+}
+
+#[test]
+fn summaries_without_front_matter() {
+    let text = "## 09:15–09:20 UTC · 4 events\n\n- typed `a`\n\n## 10:00–10:05 UTC · 2 events\n";
+    let store = SummaryStore::parse(text);
+    let headlines: Vec<_> = store.iter().map(|s| s.headline()).collect();
+    assert_eq!(
+        headlines,
+        ["09:15–09:20 UTC · 4 events", "10:00–10:05 UTC · 2 events"]
+    );
+    assert!(store.iter().all(|s| s.id.is_none()));
+    assert!(SummaryStore::parse("").is_empty());
+}
+
+#[test]
+fn pages_newest_first() {
+    let summaries: Vec<_> = (0..25).map(|n| summary(n, "x")).collect();
+    let store = SummaryStore::parse(&daily_file(&summaries));
+    assert_eq!(store.page_count(10), 3);
+    let ids = |page: usize| -> Vec<_> { store.page(page, 10).iter().map(|s| s.id).collect() };
+    assert_eq!(
+        ids(0),
+        summaries[15..].iter().map(|s| s.id).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        ids(2),
+        summaries[..5].iter().map(|s| s.id).collect::<Vec<_>>()
+    );
+    assert!(store.page(3, 10).is_empty());
+}
+
+#[test]
+fn reload_keeps_summaries_not_yet_written() {
+    let summaries: Vec<_> = (0..3).map(|n| summary(n, "x")).collect();
+    let mut store = SummaryStore::default();
+    store.push(&summaries[2]);
+    store.reload(SummaryStore::parse(&daily_file(&summaries[..2])));
+    let ids: Vec<_> = store.iter().map(|s| s.id).collect();
+    assert_eq!(ids, summaries.iter().map(|s| s.id).collect::<Vec<_>>());
+
+    // Once written, it isn't kept twice.
+    store.reload(SummaryStore::parse(&daily_file(&summaries)));
+    assert_eq!(store.len(), 3);
+}
+
+#[test]
+fn trigger_day_and_search() {
+    let mut first = summary(0, "cargo test");
+    first.trigger = Some(SummaryTrigger::Inactivity);
+    let mut second = summary(1, "git push");
+    second.trigger = Some(SummaryTrigger::Bookmark);
+    let untriggered = summary(2, "ls");
+    let store = SummaryStore::parse(&daily_file(&[first, second, untriggered]));
+
+    let triggers: Vec<_> = store.iter().map(|s| s.trigger).collect();
+    assert_eq!(
+        triggers,
+        [
+            Some(SummaryTrigger::Inactivity),
+            Some(SummaryTrigger::Bookmark),
+            None
+        ]
+    );
+    assert!(store
+        .iter()
+        .all(|s| s.day().as_deref() == Some("2024-03-01")));
+
+    let found: Vec<_> = store.iter().filter(|s| s.matches("GIT PUSH")).collect();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].trigger, Some(SummaryTrigger::Bookmark));
+    assert!(SummaryTrigger::ALL
+        .iter()
+        .all(|t| SummaryTrigger::from_name(t.name()) == Some(*t)));
+}
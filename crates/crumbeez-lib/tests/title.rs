@@ -0,0 +1,56 @@
+//! The focused pane's title is logged once it settles, not on every
+//! flicker.
+
+use crumbeez_lib::{KeystrokeEvent, TitleTracker};
+
+fn changed(title: &str) -> Option<KeystrokeEvent> {
+    Some(KeystrokeEvent::PaneTitleChanged(title.to_string()))
+}
+
+#[test]
+fn title_at_focus_is_not_news() {
+    let mut titles = TitleTracker::new(1000);
+    titles.focus("nvim");
+    titles.observe("nvim", 0);
+    assert_eq!(titles.settles_at(), None);
+    assert_eq!(titles.settle(5000), None);
+}
+
+#[test]
+fn settled_title_is_logged_once() {
+    let mut titles = TitleTracker::new(1000);
+    titles.focus("nvim");
+    titles.observe("src/event_log.rs - NVIM", 100);
+    titles.observe("src/event_log.rs - NVIM", 600);
+    assert_eq!(titles.settles_at(), Some(1100));
+    assert_eq!(titles.settle(1099), None);
+    assert_eq!(titles.settle(1100), changed("src/event_log.rs - NVIM"));
+    assert_eq!(titles.settle(9000), None);
+
+    titles.observe("src/event_log.rs - NVIM", 9000);
+    assert_eq!(titles.settles_at(), None);
+}
+
+#[test]
+fn flicker_is_dropped() {
+    let mut titles = TitleTracker::new(1000);
+    titles.focus("~/src/crumbeez");
+    titles.observe("cargo fmt", 0);
+    titles.observe("~/src/crumbeez", 300);
+    assert_eq!(titles.settle(2000), None);
+
+    // Only the last of a quick run of titles is logged.
+    titles.observe("ssh build-box", 3000);
+    titles.observe("me@build-box: ~", 3500);
+    assert_eq!(titles.settle(4000), None);
+    assert_eq!(titles.settle(4500), changed("me@build-box: ~"));
+}
+
+#[test]
+fn focus_drops_a_settling_title() {
+    let mut titles = TitleTracker::new(1000);
+    titles.focus("nvim");
+    titles.observe("src/lib.rs - NVIM", 0);
+    titles.focus("zsh");
+    assert_eq!(titles.settle(5000), None);
+}
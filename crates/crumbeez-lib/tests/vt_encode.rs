@@ -0,0 +1,231 @@
+//! Checks [`crumbeez_lib::encode_key`] against the sequences xterm sends,
+//! which kitty and other modern terminals match for these keys, and which
+//! cursor key mode panes are given.
+
+use crumbeez_lib::{
+    encode_key, write_key_bytes, AppRole, CursorKeys, ProfileRegistry, ProjectConfig, ShortcutKey,
+    VtModifiers,
+};
+use crumbeez_lib::{CursorKeys::Application, CursorKeys::Normal, MAX_KEY_SEQUENCE_LEN};
+
+const NONE: VtModifiers = VtModifiers {
+    ctrl: false,
+    alt: false,
+    shift: false,
+};
+const SHIFT: VtModifiers = VtModifiers {
+    shift: true,
+    ..NONE
+};
+const CTRL: VtModifiers = VtModifiers { ctrl: true, ..NONE };
+const ALT: VtModifiers = VtModifiers { alt: true, ..NONE };
+const CTRL_SHIFT: VtModifiers = VtModifiers {
+    ctrl: true,
+    shift: true,
+    ..NONE
+};
+const ALL: VtModifiers = VtModifiers {
+    ctrl: true,
+    alt: true,
+    shift: true,
+};
+
+fn check(table: &[(ShortcutKey, VtModifiers, CursorKeys, &[u8])]) {
+    for (key, mods, cursor, expected) in table {
+        assert_eq!(
+            encode_key(key, *mods, *cursor),
+            *expected,
+            "{:?} with {:?} in {:?} mode",
+            key,
+            mods,
+            cursor
+        );
+    }
+}
+
+#[test]
+fn characters() {
+    use ShortcutKey::Char;
+    check(&[
+        (Char('a'), NONE, Normal, b"a"),
+        (Char('A'), SHIFT, Normal, b"A"),
+        (Char('é'), NONE, Normal, "é".as_bytes()),
+        (Char('🐝'), NONE, Normal, "🐝".as_bytes()),
+        (Char('a'), CTRL, Normal, b"\x01"),
+        (Char('z'), CTRL, Normal, b"\x1a"),
+        (Char('C'), CTRL_SHIFT, Normal, b"\x03"),
+        (Char(' '), CTRL, Normal, b"\x00"),
+        (Char('['), CTRL, Normal, b"\x1b"),
+        (Char('\\'), CTRL, Normal, b"\x1c"),
+        (Char(']'), CTRL, Normal, b"\x1d"),
+        (Char('^'), CTRL, Normal, b"\x1e"),
+        (Char('_'), CTRL, Normal, b"\x1f"),
+        (Char('1'), CTRL, Normal, b"1"),
+        (Char('x'), ALT, Normal, b"\x1bx"),
+        (
+            Char('c'),
+            VtModifiers { alt: true, ..CTRL },
+            Normal,
+            b"\x1b\x03",
+        ),
+    ]);
+}
+
+#[test]
+fn editing_keys() {
+    use ShortcutKey::*;
+    check(&[
+        (Enter, NONE, Normal, b"\r"),
+        (Enter, CTRL, Normal, b"\n"),
+        (Enter, ALT, Normal, b"\x1b\r"),
+        (Tab, NONE, Normal, b"\t"),
+        (Tab, SHIFT, Normal, b"\x1b[Z"),
+        (Tab, CTRL, Normal, b"\t"),
+        (Backspace, NONE, Normal, b"\x7f"),
+        (Backspace, CTRL, Normal, b"\x08"),
+        (Backspace, ALT, Normal, b"\x1b\x7f"),
+        (Esc, NONE, Normal, b"\x1b"),
+        (Esc, ALT, Normal, b"\x1b\x1b"),
+        (Insert, NONE, Normal, b"\x1b[2~"),
+        (Insert, SHIFT, Normal, b"\x1b[2;2~"),
+        (Insert, CTRL, Normal, b"\x1b[2;5~"),
+        (Delete, NONE, Normal, b"\x1b[3~"),
+        (Delete, SHIFT, Normal, b"\x1b[3;2~"),
+        (Delete, CTRL, Normal, b"\x1b[3;5~"),
+        (Delete, CTRL_SHIFT, Normal, b"\x1b[3;6~"),
+        (PageUp, NONE, Normal, b"\x1b[5~"),
+        (PageUp, SHIFT, Normal, b"\x1b[5;2~"),
+        (PageDown, NONE, Normal, b"\x1b[6~"),
+        (PageDown, CTRL, Normal, b"\x1b[6;5~"),
+    ]);
+}
+
+#[test]
+fn cursor_keys_in_normal_mode() {
+    use ShortcutKey::*;
+    check(&[
+        (Up, NONE, Normal, b"\x1b[A"),
+        (Down, NONE, Normal, b"\x1b[B"),
+        (Right, NONE, Normal, b"\x1b[C"),
+        (Left, NONE, Normal, b"\x1b[D"),
+        (Home, NONE, Normal, b"\x1b[H"),
+        (End, NONE, Normal, b"\x1b[F"),
+        (Up, SHIFT, Normal, b"\x1b[1;2A"),
+        (Left, CTRL, Normal, b"\x1b[1;5D"),
+        (Right, CTRL_SHIFT, Normal, b"\x1b[1;6C"),
+        (Home, SHIFT, Normal, b"\x1b[1;2H"),
+        (End, CTRL, Normal, b"\x1b[1;5F"),
+        (Up, ALT, Normal, b"\x1b\x1b[A"),
+    ]);
+}
+
+#[test]
+fn cursor_keys_in_application_mode() {
+    use ShortcutKey::*;
+    check(&[
+        (Up, NONE, Application, b"\x1bOA"),
+        (Down, NONE, Application, b"\x1bOB"),
+        (Right, NONE, Application, b"\x1bOC"),
+        (Left, NONE, Application, b"\x1bOD"),
+        (Home, NONE, Application, b"\x1bOH"),
+        (End, NONE, Application, b"\x1bOF"),
+        (Up, ALT, Application, b"\x1b\x1bOA"),
+        // Modified cursor keys are CSI in either mode.
+        (Up, SHIFT, Application, b"\x1b[1;2A"),
+        (Left, CTRL, Application, b"\x1b[1;5D"),
+        (End, CTRL_SHIFT, Application, b"\x1b[1;6F"),
+        // Application mode covers only cursor keys.
+        (PageUp, NONE, Application, b"\x1b[5~"),
+        (Char('k'), NONE, Application, b"k"),
+        (F(1), NONE, Application, b"\x1bOP"),
+    ]);
+}
+
+#[test]
+fn function_keys() {
+    use ShortcutKey::F;
+    let plain: [&[u8]; 12] = [
+        b"\x1bOP",
+        b"\x1bOQ",
+        b"\x1bOR",
+        b"\x1bOS",
+        b"\x1b[15~",
+        b"\x1b[17~",
+        b"\x1b[18~",
+        b"\x1b[19~",
+        b"\x1b[20~",
+        b"\x1b[21~",
+        b"\x1b[23~",
+        b"\x1b[24~",
+    ];
+    for (n, expected) in (1..=12).zip(plain) {
+        check(&[(F(n), NONE, Normal, expected)]);
+    }
+    check(&[
+        (F(1), SHIFT, Normal, b"\x1b[11;2~"),
+        (F(4), CTRL, Normal, b"\x1b[14;5~"),
+        (F(5), SHIFT, Normal, b"\x1b[15;2~"),
+        (F(12), CTRL_SHIFT, Normal, b"\x1b[24;6~"),
+        (F(2), ALT, Normal, b"\x1b\x1bOQ"),
+        // Beyond F12 there's no standard sequence, and Alt alone isn't sent.
+        (F(13), NONE, Normal, b""),
+        (F(13), ALT, Normal, b""),
+    ]);
+}
+
+#[test]
+fn longest_sequence_fits_the_bound() {
+    let longest = encode_key(&ShortcutKey::F(12), ALL, Normal);
+    assert_eq!(longest, b"\x1b\x1b[24;6~");
+    assert_eq!(longest.len(), MAX_KEY_SEQUENCE_LEN);
+}
+
+#[test]
+fn writing_appends_to_the_buffer() {
+    let mut out = b"ls".to_vec();
+    write_key_bytes(&ShortcutKey::Enter, NONE, Normal, &mut out);
+    write_key_bytes(&ShortcutKey::F(20), ALT, Normal, &mut out);
+    write_key_bytes(&ShortcutKey::Up, NONE, Application, &mut out);
+    assert_eq!(out, b"ls\r\x1bOA");
+}
+
+#[test]
+fn editors_and_pagers_get_application_mode() {
+    let config = ProjectConfig::default();
+    let profiles = ProfileRegistry::default();
+    for (command, expected) in [
+        ("nvim", Application),
+        ("less", Application),
+        ("bash", Normal),
+        ("fzf", Normal),
+    ] {
+        let role = profiles.role_for(command, Some(command));
+        assert_eq!(
+            config.cursor_keys_for(command, Some(command), role),
+            expected,
+            "{}",
+            command
+        );
+    }
+}
+
+#[test]
+fn cursor_keys_rules_override_the_role() {
+    let config = ProjectConfig::parse(
+        "cursor_keys \"htop\" \"tig\" mode=\"application\"\ncursor_keys \"vi*\" mode=\"normal\"\n",
+    )
+    .unwrap();
+    let mode = |command: &str, role| config.cursor_keys_for(command, Some(command), role);
+    assert_eq!(mode("/usr/bin/htop", AppRole::Shell), Application);
+    assert_eq!(mode("vim", AppRole::Editor), Normal);
+    assert_eq!(mode("hx", AppRole::Editor), Application);
+    assert_eq!(ProjectConfig::parse(&config.to_kdl()).unwrap(), config);
+
+    for bad in [
+        "cursor_keys mode=\"normal\"",
+        "cursor_keys \"htop\"",
+        "cursor_keys \"htop\" mode=\"keypad\"",
+    ] {
+        assert!(ProjectConfig::parse(bad).is_err(), "{}", bad);
+    }
+}
@@ -0,0 +1,350 @@
+//! Python bindings for reading `crumbeez-lib` event logs, so breadcrumbs
+//! can be analyzed in a notebook without reimplementing the log format:
+//!
+//! ```python
+//! import crumbeez
+//!
+//! log = crumbeez.EventLog.load(".crumbeez/events.bin")
+//! for run in log.entries().between(start_ms, end_ms).without_nav().by_pane():
+//!     print(run.pane.title if run.pane else "-", len(run.entries))
+//! print(log.summarize().to_markdown())
+//! ```
+//!
+//! Built into a module with maturin; see `pyproject.toml`.  Everything is
+//! read-only: entries and summaries are copies, and query methods return new
+//! [`Entries`] rather than changing the one they're called on.
+
+#![cfg(not(target_family = "wasm"))]
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crumbeez_lib::{self as lib, event_type_name, EntryIter, KeystrokeEvent, LogEntry};
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+
+/// An event log, as the plugin writes it to `.crumbeez/events.bin`.
+#[pyclass(module = "crumbeez", frozen)]
+pub struct EventLog(lib::EventLog);
+
+#[pymethods]
+impl EventLog {
+    /// Read the log at `path`.  Raises `OSError` if it can't be read and
+    /// `ValueError` if it isn't an event log.
+    #[staticmethod]
+    fn load(path: PathBuf) -> PyResult<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Decode a log from the contents of an `events.bin` file.
+    #[staticmethod]
+    fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        lib::EventLog::deserialize(data)
+            .map(Self)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Every entry, oldest first.
+    fn entries(&self) -> Entries {
+        Entries(self.0.entries().cloned().collect())
+    }
+
+    /// The entries not yet summarized by the plugin.
+    fn unconsumed(&self) -> Entries {
+        Entries(self.0.unconsumed().cloned().collect())
+    }
+
+    /// A summary of every entry.
+    fn summarize(&self) -> Summary {
+        self.entries().summarize()
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.total_count()
+    }
+
+    fn __iter__(&self) -> EntryIterator {
+        self.entries().__iter__()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<EventLog of {} entries>", self.0.total_count())
+    }
+}
+
+/// A list of entries, oldest first, with the plugin's ways of slicing it.
+#[pyclass(module = "crumbeez", frozen)]
+#[derive(Clone)]
+pub struct Entries(Vec<LogEntry>);
+
+#[pymethods]
+impl Entries {
+    /// Entries from `start_ms` up to but not including `end_ms`.
+    fn between(&self, start_ms: u64, end_ms: u64) -> Self {
+        Self(self.0.iter().between(start_ms, end_ms).cloned().collect())
+    }
+
+    /// Everything but navigation keys.
+    fn without_nav(&self) -> Self {
+        Self(self.0.iter().without_nav().cloned().collect())
+    }
+
+    /// Consecutive typed text joined into one entry.
+    fn coalesce_text(&self) -> Self {
+        Self(self.0.iter().coalesce_text().collect())
+    }
+
+    /// The entries split into runs sent to one pane.  Focus entries start
+    /// the runs rather than appearing in them.
+    fn by_pane(&self) -> Vec<PaneRun> {
+        self.0
+            .iter()
+            .by_pane()
+            .map(|run| PaneRun {
+                pane: run.pane.map(Pane),
+                entries: Entries(run.entries.into_iter().cloned().collect()),
+            })
+            .collect()
+    }
+
+    /// A summary of these entries, as the plugin would write it.
+    fn summarize(&self) -> Summary {
+        Summary(lib::Summary::from_events(self.0.iter().cloned()))
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<Entry> {
+        let len = self.0.len() as isize;
+        let i = if index < 0 { index + len } else { index };
+        if !(0..len).contains(&i) {
+            return Err(PyIndexError::new_err("entry index out of range"));
+        }
+        Ok(Entry(self.0[i as usize].clone()))
+    }
+
+    fn __iter__(&self) -> EntryIterator {
+        EntryIterator(self.0.clone().into_iter())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Entries of {}>", self.0.len())
+    }
+}
+
+#[pyclass(module = "crumbeez")]
+pub struct EntryIterator(std::vec::IntoIter<LogEntry>);
+
+#[pymethods]
+impl EntryIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<Entry> {
+        self.0.next().map(Entry)
+    }
+}
+
+/// One logged event.
+#[pyclass(module = "crumbeez", frozen)]
+pub struct Entry(LogEntry);
+
+#[pymethods]
+impl Entry {
+    /// Milliseconds since the Unix epoch.
+    #[getter]
+    fn timestamp_ms(&self) -> u64 {
+        self.0.timestamp_ms
+    }
+
+    /// The kind of event, e.g. `"TextTyped"` or `"PaneFocused"`.
+    #[getter]
+    fn kind(&self) -> &'static str {
+        event_type_name(&self.0.event)
+    }
+
+    /// The event as the plugin logs it, e.g. `typed "ls"`.
+    #[getter]
+    fn description(&self) -> String {
+        self.0.event.to_string()
+    }
+
+    /// The text the event carries, if it carries any: what was typed or
+    /// prompted, a bookmark, a title.
+    #[getter]
+    fn text(&self) -> Option<&str> {
+        match &self.0.event {
+            KeystrokeEvent::TextTyped(text)
+            | KeystrokeEvent::LongText { preview: text, .. }
+            | KeystrokeEvent::PromptToAgent(text)
+            | KeystrokeEvent::Annotation(text)
+            | KeystrokeEvent::AgentStatus(text)
+            | KeystrokeEvent::PaneTitleChanged(text)
+            | KeystrokeEvent::TabClosed(text)
+            | KeystrokeEvent::SessionRenamed(text)
+            | KeystrokeEvent::CommandExecuted { command: text, .. }
+            | KeystrokeEvent::EnvironmentChanged { target: text, .. }
+            | KeystrokeEvent::RiskyCommand { command: text, .. }
+            | KeystrokeEvent::FuzzySelected { query: text, .. }
+            | KeystrokeEvent::ReplInput { code: text, .. } => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The pane focused, for a `PaneFocused` entry.
+    #[getter]
+    fn pane(&self) -> Option<Pane> {
+        match &self.0.event {
+            KeystrokeEvent::PaneFocused(pane) => Some(Pane(pane.clone())),
+            _ => None,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Entry {} at {}>",
+            self.0.event,
+            lib::format_ymd(self.0.timestamp_ms)
+        )
+    }
+}
+
+/// A pane that gained focus.
+#[pyclass(module = "crumbeez", frozen)]
+#[derive(Clone)]
+pub struct Pane(lib::PaneFocusedEvent);
+
+#[pymethods]
+impl Pane {
+    /// The tab's name, if known.
+    #[getter]
+    fn tab(&self) -> Option<&str> {
+        self.0.tab_name.as_deref()
+    }
+
+    #[getter]
+    fn title(&self) -> &str {
+        &self.0.pane_title
+    }
+
+    /// The command it runs, if it's a terminal pane and that's known.
+    #[getter]
+    fn command(&self) -> Option<&str> {
+        self.0.command.as_deref()
+    }
+
+    /// What kind of program it runs, e.g. `"editor"`.
+    #[getter]
+    fn role(&self) -> &'static str {
+        self.0.role.name()
+    }
+
+    /// Zellij's ID for the pane, if recorded.
+    #[getter]
+    fn id(&self) -> Option<u32> {
+        self.0.pane_id
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Pane {:?}>", self.0.pane_title)
+    }
+}
+
+/// Entries sent to one pane, from [`Entries::by_pane`].
+#[pyclass(module = "crumbeez", frozen)]
+pub struct PaneRun {
+    pane: Option<Pane>,
+    entries: Entries,
+}
+
+#[pymethods]
+impl PaneRun {
+    /// `None` for entries from before the log saw any focus.
+    #[getter]
+    fn pane(&self) -> Option<Pane> {
+        self.pane.clone()
+    }
+
+    #[getter]
+    fn entries(&self) -> Entries {
+        self.entries.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        let title = self.pane.as_ref().map(|pane| pane.title());
+        format!("<PaneRun {:?} of {}>", title, self.entries.0.len())
+    }
+}
+
+/// A summary of some entries.
+#[pyclass(module = "crumbeez", frozen)]
+pub struct Summary(lib::Summary);
+
+#[pymethods]
+impl Summary {
+    #[getter]
+    fn events_consumed(&self) -> usize {
+        self.0.events_consumed
+    }
+
+    /// How many events of each kind were summarized.
+    #[getter]
+    fn event_types(&self) -> BTreeMap<String, usize> {
+        self.0.event_types.clone()
+    }
+
+    /// The first and last summarized events' times, or `None` if there
+    /// were none.
+    #[getter]
+    fn span_ms(&self) -> Option<(u64, u64)> {
+        self.0.span_ms
+    }
+
+    /// The (UTC) day of the last summarized event, `YYYY-MM-DD`.
+    #[getter]
+    fn day(&self) -> Option<String> {
+        self.0.day()
+    }
+
+    /// Files the events refer to, such as screen snapshots, relative to the
+    /// `.crumbeez` directory.
+    #[getter]
+    fn files(&self) -> Vec<String> {
+        self.0.files.clone()
+    }
+
+    /// Time spent in scroll or search mode, reading output.
+    #[getter]
+    fn reading_ms(&self) -> u64 {
+        self.0.reading_ms
+    }
+
+    /// The summary as a Markdown section, as in the daily summary files.
+    fn to_markdown(&self) -> String {
+        self.0.to_markdown()
+    }
+
+    /// The summary as compact plain text, one line per pane.
+    fn to_narrative(&self) -> String {
+        self.0.to_narrative()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Summary of {} events>", self.0.events_consumed)
+    }
+}
+
+#[pymodule(name = "crumbeez")]
+pub fn crumbeez_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<EventLog>()?;
+    m.add_class::<Entries>()?;
+    m.add_class::<EntryIterator>()?;
+    m.add_class::<Entry>()?;
+    m.add_class::<Pane>()?;
+    m.add_class::<PaneRun>()?;
+    m.add_class::<Summary>()?;
+    Ok(())
+}
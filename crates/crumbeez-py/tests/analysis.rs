@@ -0,0 +1,179 @@
+//! Drives the module from Python, as a notebook would, and checks it reads
+//! logs and summarizes them the same as the library.
+//!
+//! Run natively (the default build target is wasm):
+//!
+//! ```sh
+//! cargo test-native -p crumbeez-py --test analysis
+//! ```
+
+#![cfg(not(target_family = "wasm"))]
+
+use std::ffi::CStr;
+
+use crumbeez_lib::fixtures::{self, Fixture, FIXTURE_START_MS};
+use crumbeez_lib::{EntryIter, Summary};
+use pyo3::ffi::c_str;
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{PyBytes, PyDict};
+
+/// The module, which can only be initialized once per process.
+static MODULE: GILOnceCell<Py<PyModule>> = GILOnceCell::new();
+
+/// Run `code` with `crumbeez` imported and `values` as its globals.
+fn run(code: &CStr, values: impl FnOnce(&Bound<'_, PyDict>)) {
+    Python::with_gil(|py| {
+        let module = MODULE.get_or_init(py, || {
+            pyo3::wrap_pymodule!(crumbeez_py::crumbeez_module)(py)
+        });
+        let globals = PyDict::new(py);
+        globals.set_item("crumbeez", module).unwrap();
+        values(&globals);
+        if let Err(e) = py.run(code, Some(&globals), None) {
+            e.print(py);
+            panic!("Python code failed");
+        }
+    });
+}
+
+fn workday_bytes() -> Vec<u8> {
+    let log = fixtures::event_log(fixtures::workday(FIXTURE_START_MS));
+    log.serialize().unwrap()
+}
+
+#[test]
+fn reads_entries() {
+    let entries = fixtures::workday(FIXTURE_START_MS);
+    let first = &entries[0];
+    run(
+        c_str!(
+            r#"
+log = crumbeez.EventLog.from_bytes(data)
+assert len(log) == count, (len(log), count)
+assert len(list(log)) == count
+first = log.entries()[0]
+assert first.timestamp_ms == first_ms
+assert first.kind == "PaneFocused"
+assert first.pane.title == "nvim src/parser.rs"
+assert first.pane.role == "editor"
+assert first.pane.tab == "code"
+assert first.text is None
+typed = [e for e in log if e.kind == "TextTyped"]
+assert typed[0].text == "o"
+assert typed[0].description == 'typed "o"'
+assert log.entries()[-1].timestamp_ms == last_ms
+try:
+    log.entries()[count]
+    raise AssertionError("indexed past the end")
+except IndexError:
+    pass
+"#
+        ),
+        |globals| {
+            let py = globals.py();
+            globals
+                .set_item("data", PyBytes::new(py, &workday_bytes()))
+                .unwrap();
+            globals.set_item("count", entries.len()).unwrap();
+            globals.set_item("first_ms", first.timestamp_ms).unwrap();
+            let last_ms = entries.last().unwrap().timestamp_ms;
+            globals.set_item("last_ms", last_ms).unwrap();
+        },
+    );
+}
+
+#[test]
+fn queries_match_the_library() {
+    let entries = fixtures::workday(FIXTURE_START_MS);
+    let start = Fixture::CargoBuildLoop.entries(FIXTURE_START_MS)[0].timestamp_ms;
+    let day_end = FIXTURE_START_MS + 24 * 60 * 60 * 1000;
+    let sliced: Vec<_> = entries
+        .iter()
+        .between(FIXTURE_START_MS, FIXTURE_START_MS + 60_000)
+        .without_nav()
+        .coalesce_text()
+        .collect();
+    let runs = entries.iter().by_pane().count();
+    let after: usize = entries.iter().between(start, day_end).count();
+    run(
+        c_str!(
+            r#"
+entries = crumbeez.EventLog.from_bytes(data).entries()
+sliced = entries.between(start_ms, start_ms + 60000).without_nav().coalesce_text()
+assert len(sliced) == sliced_count, (len(sliced), sliced_count)
+assert all(e.kind != "Navigation" for e in sliced)
+assert len(entries.between(later_ms, end_ms)) == after_count
+runs = entries.by_pane()
+assert len(runs) == run_count, (len(runs), run_count)
+assert runs[0].pane.title == "nvim src/parser.rs"
+assert all(e.kind != "PaneFocused" for run in runs for e in run.entries)
+"#
+        ),
+        |globals| {
+            let py = globals.py();
+            globals
+                .set_item("data", PyBytes::new(py, &workday_bytes()))
+                .unwrap();
+            globals.set_item("start_ms", FIXTURE_START_MS).unwrap();
+            globals.set_item("sliced_count", sliced.len()).unwrap();
+            globals.set_item("later_ms", start).unwrap();
+            globals.set_item("end_ms", day_end).unwrap();
+            globals.set_item("after_count", after).unwrap();
+            globals.set_item("run_count", runs).unwrap();
+        },
+    );
+}
+
+#[test]
+fn summarizes_like_the_library() {
+    let summary = Summary::from_events(fixtures::workday(FIXTURE_START_MS).into_iter());
+    run(
+        c_str!(
+            r#"
+summary = crumbeez.EventLog.from_bytes(data).summarize()
+assert summary.to_markdown() == markdown
+assert summary.to_narrative() == narrative
+assert summary.events_consumed == consumed
+assert summary.day == "2024-03-01"
+assert summary.span_ms[0] == start_ms
+assert summary.event_types["PaneFocused"] >= 3
+"#
+        ),
+        |globals| {
+            let py = globals.py();
+            globals
+                .set_item("data", PyBytes::new(py, &workday_bytes()))
+                .unwrap();
+            globals.set_item("markdown", summary.to_markdown()).unwrap();
+            globals
+                .set_item("narrative", summary.to_narrative())
+                .unwrap();
+            globals
+                .set_item("consumed", summary.events_consumed)
+                .unwrap();
+            globals.set_item("start_ms", FIXTURE_START_MS).unwrap();
+        },
+    );
+}
+
+#[test]
+fn bad_input_raises() {
+    run(
+        c_str!(
+            r#"
+try:
+    crumbeez.EventLog.from_bytes(b"not a log")
+    raise AssertionError("decoded garbage")
+except ValueError:
+    pass
+try:
+    crumbeez.EventLog.load("/nonexistent/events.bin")
+    raise AssertionError("loaded a missing file")
+except OSError:
+    pass
+"#
+        ),
+        |_| {},
+    );
+}
@@ -0,0 +1,81 @@
+//! Benchmarks for the per-keystroke hot path: every key pressed anywhere in
+//! the session goes through `plain_char` or `classify`, and `write_key_bytes`.
+//!
+//! Run natively (the default build target is wasm):
+//!
+//! ```sh
+//! cargo bench-native -p crumbeez --bench keystroke
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use zellij_tile::prelude::{BareKey, KeyWithModifier};
+
+// The plugin is a binary crate, so pull the module in directly.
+#[path = "../src/keystroke.rs"]
+#[allow(dead_code)]
+mod keystroke;
+
+use crumbeez_lib::CursorKeys;
+use keystroke::{classify, plain_char, write_key_bytes};
+
+fn sample_keys() -> Vec<KeyWithModifier> {
+    vec![
+        KeyWithModifier::new(BareKey::Char('a')),
+        KeyWithModifier::new(BareKey::Char('A')).with_shift_modifier(),
+        KeyWithModifier::new(BareKey::Char('é')),
+        KeyWithModifier::new(BareKey::Char('s')).with_ctrl_modifier(),
+        KeyWithModifier::new(BareKey::Left).with_ctrl_modifier(),
+        KeyWithModifier::new(BareKey::Backspace),
+        KeyWithModifier::new(BareKey::F(12))
+            .with_ctrl_modifier()
+            .with_shift_modifier()
+            .with_alt_modifier(),
+    ]
+}
+
+fn bench_classify(c: &mut Criterion) {
+    let plain = KeyWithModifier::new(BareKey::Char('a'));
+    c.bench_function("classify/plain_char", |b| {
+        b.iter(|| classify(black_box(&plain)))
+    });
+
+    c.bench_function("plain_char/plain_char", |b| {
+        b.iter(|| plain_char(black_box(&plain)))
+    });
+
+    let keys = sample_keys();
+    c.bench_function("classify/mixed", |b| {
+        b.iter(|| {
+            for key in &keys {
+                black_box(classify(black_box(key)));
+            }
+        })
+    });
+}
+
+fn bench_write_key_bytes(c: &mut Criterion) {
+    let plain = KeyWithModifier::new(BareKey::Char('a'));
+    c.bench_function("write_key_bytes/plain_char", |b| {
+        let mut buf = Vec::with_capacity(keystroke::MAX_KEY_SEQUENCE_LEN);
+        b.iter(|| {
+            buf.clear();
+            write_key_bytes(black_box(&plain), CursorKeys::Normal, &mut buf);
+            black_box(&buf);
+        })
+    });
+
+    let keys = sample_keys();
+    c.bench_function("write_key_bytes/mixed_reused_buffer", |b| {
+        let mut buf = Vec::with_capacity(keystroke::MAX_KEY_SEQUENCE_LEN);
+        b.iter(|| {
+            for key in &keys {
+                buf.clear();
+                write_key_bytes(black_box(key), CursorKeys::Normal, &mut buf);
+                black_box(&buf);
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_classify, bench_write_key_bytes);
+criterion_main!(benches);
@@ -0,0 +1,117 @@
+//! Live breadcrumbs for other plugins.
+//!
+//! A plugin subscribes by piping `crumbeez::subscribe` to crumbeez, e.g.
+//! from its `load`:
+//!
+//! ```rust,ignore
+//! pipe_message_to_plugin(MessageToPlugin::new("crumbeez::subscribe")
+//!     .with_plugin_url("file:/path/to/crumbeez.wasm"));
+//! ```
+//!
+//! From then on it receives a [`BROADCAST_PIPE`] message for every entry
+//! logged, with the [JSON envelope](crumbeez_lib::envelope_json) as
+//! payload, until it sends `crumbeez::unsubscribe`.
+//!
+//! A terminal can follow along too:
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::tail | jq .
+//! ```
+//!
+//! gets the same envelope, one line per entry, until it's interrupted.
+//! Its input is never unblocked, which is what keeps `zellij pipe` waiting
+//! for more output.
+
+use tracing::{debug, warn};
+use zellij_tile::prelude::*;
+
+use crumbeez_lib::{envelope_json, LogEntry, ProjectConfig, BROADCAST_PIPE};
+
+use crate::host::HostHandle;
+
+/// Pipe message names for (un)subscribing.
+pub const SUBSCRIBE_PIPE: &str = "crumbeez::subscribe";
+pub const UNSUBSCRIBE_PIPE: &str = "crumbeez::unsubscribe";
+
+/// Pipe message name for following the log from the CLI.
+pub const TAIL_PIPE: &str = "crumbeez::tail";
+
+/// Most subscribers of each kind at once.  Plugins that go away without
+/// unsubscribing and interrupted `zellij pipe`s can't be detected, so the
+/// oldest is dropped to make room.
+const MAX_SUBSCRIBERS: usize = 16;
+
+#[derive(Debug, Default)]
+pub struct Subscribers {
+    host: HostHandle,
+    plugin_ids: Vec<u32>,
+    /// IDs of the CLI pipes tailing the log.
+    tails: Vec<String>,
+}
+
+impl Subscribers {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    pub fn subscribe(&mut self, plugin_id: u32) {
+        if self.plugin_ids.contains(&plugin_id) {
+            return;
+        }
+        if self.plugin_ids.len() >= MAX_SUBSCRIBERS {
+            let dropped = self.plugin_ids.remove(0);
+            warn!(dropped, "Too many subscribers, dropping the oldest");
+        }
+        debug!(plugin_id, "Plugin subscribed to breadcrumbs");
+        self.plugin_ids.push(plugin_id);
+    }
+
+    /// Stream entries to the CLI pipe `pipe_id` from now on.
+    pub fn tail(&mut self, pipe_id: String) {
+        if self.tails.contains(&pipe_id) {
+            return;
+        }
+        if self.tails.len() >= MAX_SUBSCRIBERS {
+            let dropped = self.tails.remove(0);
+            warn!(%dropped, "Too many tails, dropping the oldest");
+            self.host
+                .cli_pipe_output(&dropped, "crumbeez: too many tails, this one was dropped\n");
+        }
+        debug!(%pipe_id, "CLI pipe tailing breadcrumbs");
+        self.tails.push(pipe_id);
+    }
+
+    pub fn unsubscribe(&mut self, plugin_id: u32) {
+        debug!(plugin_id, "Plugin unsubscribed from breadcrumbs");
+        self.plugin_ids.retain(|id| *id != plugin_id);
+    }
+
+    /// Send `entry`, numbered `seq`, to every subscriber and tail, with the
+    /// `config`'s outbound redactions applied.
+    pub fn publish(&self, seq: u64, entry: &LogEntry, config: &ProjectConfig) {
+        if self.plugin_ids.is_empty() && self.tails.is_empty() {
+            return;
+        }
+        let mut entry = entry.clone();
+        entry
+            .event
+            .redact_text(&|text| config.redact_outbound(text));
+        let payload = envelope_json(seq, &entry);
+        for plugin_id in &self.plugin_ids {
+            self.host.pipe_message_to_plugin(
+                MessageToPlugin::new(BROADCAST_PIPE)
+                    .with_destination_plugin_id(*plugin_id)
+                    .with_payload(payload.clone()),
+            );
+        }
+        if !self.tails.is_empty() {
+            let line = format!("{}\n", payload);
+            for pipe_id in &self.tails {
+                self.host.cli_pipe_output(pipe_id, &line);
+            }
+        }
+    }
+}
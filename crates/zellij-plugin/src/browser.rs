@@ -0,0 +1,204 @@
+//! Browsing past summaries in the plugin pane.
+//!
+//! The pane normally shows today's summaries; the browser lists every day's,
+//! newest first, read from the summary files:
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::browse
+//! ```
+//!
+//! or from a keybind, like [bookmarks](crate::mark).  Sending it again
+//! closes the browser.  Keys move through the list while the plugin pane
+//! is focused; keys typed in other panes meanwhile reach them as usual.  `y`
+//! copies the selected summary to the [clipboard](crate::clipboard), and `/`
+//! searches them, listing the best matches first.
+
+use crumbeez_lib::{StoredSummary, SummaryStore};
+use zellij_tile::prelude::*;
+
+use crate::keystroke::plain_char;
+
+/// Pipe message name that opens or closes the summary browser.
+pub const BROWSE_PIPE: &str = "crumbeez::browse";
+
+pub enum BrowserOutcome {
+    /// The browser is still open.
+    Browsing,
+    /// Copy this summary's Markdown, and stay open.
+    Copy(String),
+    Close,
+}
+
+/// The list of past summaries, with one expanded or a search being typed.
+#[derive(Default)]
+pub struct SummaryBrowser {
+    /// Every summary on disk, once read.
+    store: Option<SummaryStore>,
+    /// Index into the filtered list, 0 for the newest.
+    selected: usize,
+    /// The selected summary is shown in full, scrolled down this many lines.
+    expanded: Option<usize>,
+    /// A search being typed.
+    editing: Option<String>,
+    /// The search applied; only summaries matching it are listed.
+    query: String,
+    /// The summaries matching the query, as indexes into the store, best
+    /// first.
+    ranked: Vec<usize>,
+}
+
+impl SummaryBrowser {
+    pub fn load(&mut self, store: SummaryStore) {
+        self.store = Some(store);
+        self.rank();
+        self.selected = self.selected.min(self.listed().len().saturating_sub(1));
+    }
+
+    /// Search the summaries for the query.
+    fn rank(&mut self) {
+        self.ranked = match self.store {
+            Some(ref store) => store.rank(&self.query),
+            None => Vec::new(),
+        };
+    }
+
+    /// Every summary, newest first, or those matching the query, best
+    /// first.
+    fn listed(&self) -> Vec<&StoredSummary> {
+        let Some(ref store) = self.store else {
+            return Vec::new();
+        };
+        if self.query.is_empty() {
+            return store.iter().rev().collect();
+        }
+        let summaries: Vec<&StoredSummary> = store.iter().collect();
+        self.ranked.iter().map(|&i| summaries[i]).collect()
+    }
+
+    pub fn handle_key(&mut self, key: &KeyWithModifier) -> BrowserOutcome {
+        if key.bare_key == BareKey::Char('c') && key.key_modifiers.contains(&KeyModifier::Ctrl) {
+            return BrowserOutcome::Close;
+        }
+        if let Some(ref mut text) = self.editing {
+            if let Some(c) = plain_char(key) {
+                text.push(c);
+                return BrowserOutcome::Browsing;
+            }
+            match key.bare_key {
+                BareKey::Enter => {
+                    self.query = self.editing.take().unwrap_or_default().trim().to_string();
+                    self.rank();
+                    self.selected = 0;
+                }
+                BareKey::Esc => self.editing = None,
+                BareKey::Backspace => {
+                    text.pop();
+                }
+                _ => {}
+            }
+            return BrowserOutcome::Browsing;
+        }
+        if let Some(ref mut scroll) = self.expanded {
+            match key.bare_key {
+                BareKey::Up | BareKey::Char('k') => *scroll = scroll.saturating_sub(1),
+                BareKey::Down | BareKey::Char('j') => *scroll += 1,
+                BareKey::PageUp => *scroll = scroll.saturating_sub(10),
+                BareKey::PageDown => *scroll += 10,
+                BareKey::Esc | BareKey::Enter | BareKey::Char('q') => self.expanded = None,
+                BareKey::Char('y') => return self.copy_selected(),
+                _ => {}
+            }
+            return BrowserOutcome::Browsing;
+        }
+        let last = self.listed().len().saturating_sub(1);
+        match key.bare_key {
+            BareKey::Up | BareKey::Char('k') => self.selected = self.selected.saturating_sub(1),
+            BareKey::Down | BareKey::Char('j') => self.selected = (self.selected + 1).min(last),
+            BareKey::PageUp => self.selected = self.selected.saturating_sub(10),
+            BareKey::PageDown => self.selected = (self.selected + 10).min(last),
+            BareKey::Enter if !self.listed().is_empty() => self.expanded = Some(0),
+            BareKey::Char('/') => self.editing = Some(self.query.clone()),
+            BareKey::Char('y') => return self.copy_selected(),
+            BareKey::Esc if !self.query.is_empty() => {
+                self.query.clear();
+                self.selected = 0;
+            }
+            BareKey::Esc | BareKey::Char('q') => return BrowserOutcome::Close,
+            _ => {}
+        }
+        BrowserOutcome::Browsing
+    }
+
+    fn copy_selected(&self) -> BrowserOutcome {
+        match self.listed().get(self.selected) {
+            Some(summary) => BrowserOutcome::Copy(summary.markdown.clone()),
+            None => BrowserOutcome::Browsing,
+        }
+    }
+
+    /// The pane's contents, fitted to `rows` by `cols`.
+    pub fn render(&mut self, rows: usize, cols: usize) -> Vec<String> {
+        let body_rows = rows.saturating_sub(2).max(1);
+        let listed = self.listed();
+        let mut lines = Vec::new();
+        let hint;
+        match (self.expanded, listed.get(self.selected)) {
+            (Some(scroll), Some(summary)) => {
+                let markdown: Vec<&str> = summary.markdown.lines().collect();
+                let scroll = scroll.min(markdown.len().saturating_sub(body_rows));
+                lines.push(format!("\u{1b}[1m{}\u{1b}[0m", summary.headline()));
+                lines.extend(
+                    markdown
+                        .iter()
+                        .skip(scroll)
+                        .take(body_rows)
+                        .map(|line| line.to_string()),
+                );
+                hint = "↑/↓ scroll · y copy · Esc back";
+                self.expanded = Some(scroll);
+            }
+            _ => {
+                let title = match self.store {
+                    None => "Summaries (loading…)".to_string(),
+                    Some(_) if self.query.is_empty() => format!("Summaries ({})", listed.len()),
+                    Some(_) => format!("Summaries matching \"{}\" ({})", self.query, listed.len()),
+                };
+                lines.push(format!("\u{1b}[1m{}\u{1b}[0m", title));
+                // Keep the selection in view.
+                let first = self.selected.saturating_sub(body_rows - 1);
+                for (i, summary) in listed.iter().enumerate().skip(first).take(body_rows) {
+                    let mut line = format!(
+                        "{} {} · {}",
+                        if i == self.selected { ">" } else { " " },
+                        summary.day().as_deref().unwrap_or("----------"),
+                        summary.headline()
+                    );
+                    if let Some(trigger) = summary.trigger {
+                        line.push_str(&format!(" · {}", trigger));
+                    }
+                    lines.push(line);
+                }
+                hint = "↑/↓ select · Enter expand · y copy · / search · Esc close";
+            }
+        }
+        lines.push(match self.editing {
+            Some(ref text) => format!("/{}_", text),
+            None => format!("\u{1b}[2m{}\u{1b}[0m", hint),
+        });
+        lines.into_iter().map(|line| fit(&line, cols)).collect()
+    }
+}
+
+/// `line` cut to `cols` characters.  Styling escapes only wrap whole lines
+/// here, so cutting keeps the reset at the end.
+fn fit(line: &str, cols: usize) -> String {
+    if line.chars().count() <= cols {
+        return line.to_string();
+    }
+    let mut cut: String = line.chars().take(cols.saturating_sub(1)).collect();
+    cut.push('…');
+    if line.contains('\u{1b}') {
+        cut.push_str("\u{1b}[0m");
+    }
+    cut
+}
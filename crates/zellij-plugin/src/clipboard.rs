@@ -0,0 +1,38 @@
+//! Copying summaries to the clipboard, to paste as context into a chat
+//! window without going through files.
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::copy
+//! ```
+//!
+//! copies the newest summary on the page the plugin pane shows, as Markdown
+//! with `redact_outbound` applied.  Zellij puts it wherever its own copies
+//! go, the system clipboard or the primary selection.  From a keybind it
+//! works with any pane focused:
+//!
+//! ```kdl
+//! keybinds {
+//!     shared {
+//!         bind "Alt y" { MessagePlugin "file:crumbeez.wasm" { name "crumbeez::copy"; }; }
+//!     }
+//! }
+//! ```
+//!
+//! In the plugin pane `y` does the same, and in the summary browser it
+//! copies the selected summary.
+
+use crate::host::HostHandle;
+
+/// Pipe message name that copies the newest summary.
+pub const COPY_PIPE: &str = "crumbeez::copy";
+
+/// Put `text` on the clipboard, and say what was copied for the status
+/// line.
+pub fn copy(host: &HostHandle, text: &str, what: &str) -> String {
+    host.copy_to_clipboard(text);
+    format!(
+        "copied {} to the clipboard ({} chars)",
+        what,
+        text.chars().count()
+    )
+}
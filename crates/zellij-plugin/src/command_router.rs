@@ -0,0 +1,234 @@
+//! Request tracking for every `run_command` the plugin issues.
+//!
+//! Each command is tagged with its purpose (see [`ContextTag`]) and a request
+//! ID.  A result is only handed on if its ID is still pending, so a result
+//! that arrives after its command timed out, or one carrying someone else's
+//! context, can't be mistaken for a fresh one.  Commands that haven't
+//! answered by their deadline are re-run if their [`Retry`] policy allows
+//! it, and otherwise reported to their owner as failed.
+//!
+//! The router's state lives on the [`HostHandle`] the commands run on, so
+//! every clone of the handle sees the same commands, and a test's mock host
+//! starts with none.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use tracing::{debug, warn};
+
+use crate::context_tag::ContextTag;
+use crate::host::HostHandle;
+
+/// Context key holding the request ID.
+pub const CTX_REQUEST_ID: &str = "crumbeez_request_id";
+
+/// How long to wait for a command, and how many times to try it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Retry {
+    pub timeout: Duration,
+    pub attempts: u32,
+}
+
+impl Retry {
+    /// Run once.  The default, and the only safe choice for commands that
+    /// append to a file.
+    pub const ONCE: Self = Self {
+        timeout: Duration::from_secs(60),
+        attempts: 1,
+    };
+
+    /// Read-only commands, which can safely be repeated.
+    pub const IDEMPOTENT: Self = Self {
+        timeout: Duration::from_secs(20),
+        attempts: 3,
+    };
+}
+
+/// A command result, or a stand-in for a command given up on.
+#[cfg(not(feature = "minimal"))]
+pub struct CommandOutput {
+    /// `None` if the command couldn't run or timed out.
+    pub exit_code: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub context: BTreeMap<String, String>,
+}
+
+/// A command that missed its deadline.
+pub enum Expired {
+    /// Re-run; `attempt` counts from 1.
+    Retrying {
+        context: BTreeMap<String, String>,
+        attempt: u32,
+    },
+    /// Out of attempts.  Report it to the owner as a failure.
+    GaveUp(BTreeMap<String, String>),
+}
+
+struct Pending {
+    /// The purpose context, without the request ID.
+    context: BTreeMap<String, String>,
+    /// The command line, or `None` if it was run elsewhere (by a worker) and
+    /// can't be retried from here.
+    args: Option<Vec<String>>,
+    cwd: PathBuf,
+    deadline: SystemTime,
+    retry: Retry,
+    attempt: u32,
+}
+
+/// The commands running on a host; see [`HostHandle::commands`].
+#[derive(Default)]
+pub struct CommandRouter {
+    next_id: u64,
+    pending: BTreeMap<u64, Pending>,
+    /// Which purpose type owns each context key, to catch two modules
+    /// claiming the same one.
+    owners: BTreeMap<&'static str, &'static str>,
+}
+
+impl CommandRouter {
+    fn register<P: ContextTag>(&mut self) {
+        let owner = std::any::type_name::<P>();
+        let existing = *self.owners.entry(P::KEY).or_insert(owner);
+        if existing != owner {
+            warn!(key = P::KEY, existing, owner, "Context key claimed twice");
+        }
+    }
+
+    /// Record a pending request and return its full context.
+    fn track(&mut self, mut pending: Pending, now: SystemTime) -> BTreeMap<String, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut context = pending.context.clone();
+        context.insert(CTX_REQUEST_ID.to_string(), id.to_string());
+        pending.deadline = now + pending.retry.timeout;
+        self.pending.insert(id, pending);
+        context
+    }
+}
+
+fn purpose_context<P: ContextTag>(purpose: &P) -> BTreeMap<String, String> {
+    let mut context = BTreeMap::new();
+    context.insert(P::KEY.to_string(), purpose.to_tag());
+    context
+}
+
+fn spawn(host: &HostHandle, args: &[String], cwd: PathBuf, context: BTreeMap<String, String>) {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    host.run_command_with_env_variables_and_cwd(&args, BTreeMap::new(), cwd, context);
+}
+
+/// Run a command on `host` tagged with `purpose`, under the purpose's retry
+/// policy.
+pub fn run<P: ContextTag, S: AsRef<str>>(host: &HostHandle, purpose: &P, args: &[S], cwd: PathBuf) {
+    let args: Vec<String> = args.iter().map(|a| a.as_ref().to_string()).collect();
+    let context = {
+        let mut router = host.commands();
+        router.register::<P>();
+        router.track(
+            Pending {
+                context: purpose_context(purpose),
+                args: Some(args.clone()),
+                cwd: cwd.clone(),
+                deadline: SystemTime::UNIX_EPOCH,
+                retry: purpose.retry(),
+                attempt: 1,
+            },
+            host.now(),
+        )
+    };
+    spawn(host, &args, cwd, context);
+}
+
+/// Track a command that will be run elsewhere (by a worker) with the
+/// returned context.  It is never retried, since only the runner knows how.
+#[cfg(not(feature = "minimal"))]
+pub fn track<P: ContextTag>(host: &HostHandle, purpose: &P) -> BTreeMap<String, String> {
+    let mut router = host.commands();
+    router.register::<P>();
+    router.track(
+        Pending {
+            context: purpose_context(purpose),
+            args: None,
+            cwd: PathBuf::new(),
+            deadline: SystemTime::UNIX_EPOCH,
+            retry: Retry {
+                attempts: 1,
+                ..purpose.retry()
+            },
+            attempt: 1,
+        },
+        host.now(),
+    )
+}
+
+/// Claim a command result.  Returns `false` for results that aren't ours or
+/// that arrived after their command was given up on or retried; those must
+/// be dropped.
+pub fn accept(host: &HostHandle, context: &BTreeMap<String, String>) -> bool {
+    let Some(id) = context.get(CTX_REQUEST_ID).and_then(|id| id.parse().ok()) else {
+        return false;
+    };
+    if host.commands().pending.remove(&id).is_some() {
+        true
+    } else {
+        debug!(id, "Dropping result of an expired command");
+        false
+    }
+}
+
+/// The earliest deadline of any running command.
+#[cfg(not(feature = "minimal"))]
+pub fn next_deadline(host: &HostHandle) -> Option<SystemTime> {
+    host.commands().pending.values().map(|p| p.deadline).min()
+}
+
+/// Retry or give up on commands past their deadline.  Call when the timer
+/// fires; a command can't time out before the timer does.
+pub fn expire(host: &HostHandle) -> Vec<Expired> {
+    let now = host.now();
+    let mut retries = Vec::new();
+    let mut expired = Vec::new();
+    {
+        let mut router = host.commands();
+        let overdue: Vec<u64> = router
+            .pending
+            .iter()
+            .filter(|(_, p)| p.deadline <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in overdue {
+            let Some(mut pending) = router.pending.remove(&id) else {
+                continue;
+            };
+            match pending.args.clone() {
+                Some(args) if pending.attempt < pending.retry.attempts => {
+                    pending.attempt += 1;
+                    warn!(
+                        ?args,
+                        attempt = pending.attempt,
+                        of = pending.retry.attempts,
+                        "Command timed out, retrying"
+                    );
+                    let cwd = pending.cwd.clone();
+                    expired.push(Expired::Retrying {
+                        context: pending.context.clone(),
+                        attempt: pending.attempt,
+                    });
+                    let context = router.track(pending, now);
+                    retries.push((args, cwd, context));
+                }
+                _ => {
+                    warn!(context = ?pending.context, "Command timed out, giving up");
+                    expired.push(Expired::GaveUp(pending.context));
+                }
+            }
+        }
+    }
+    for (args, cwd, context) in retries {
+        spawn(host, &args, cwd, context);
+    }
+    expired
+}
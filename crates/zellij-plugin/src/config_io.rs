@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, error, info};
+
+use crate::command_router::{self, Retry};
+use crate::context_tag::{purpose_of, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+use crumbeez_lib::ProjectConfig;
+
+pub const CTX_PURPOSE: &str = "crumbeez_config_purpose";
+
+/// Where to look for the config of the project at `root` whose data dir is
+/// `dir`, first match wins.  A committed config in the project takes
+/// precedence over one in fallback storage.
+pub fn search_paths(root: Option<&Path>, dir: &Path) -> Vec<PathBuf> {
+    let config_path = crumbeez_lib::config_path_from_crumbeez_dir(dir);
+    let mut paths: Vec<_> = root
+        .map(crumbeez_lib::config_path)
+        .filter(|p| *p != config_path)
+        .into_iter()
+        .collect();
+    paths.push(config_path);
+    paths
+}
+
+#[derive(Debug)]
+enum ConfigCommand {
+    ReadConfig,
+    WriteConfig,
+}
+
+impl ContextTag for ConfigCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::ReadConfig => "ReadConfig",
+            Self::WriteConfig => "WriteConfig",
+        }
+        .to_string()
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "ReadConfig" => Some(Self::ReadConfig),
+            "WriteConfig" => Some(Self::WriteConfig),
+            _ => None,
+        }
+    }
+
+    fn retry(&self) -> Retry {
+        match self {
+            Self::ReadConfig | Self::WriteConfig => Retry::IDEMPOTENT,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ConfigIO {
+    host: HostHandle,
+    config_path: Option<PathBuf>,
+    /// The last parse error, shown in the UI until the file is fixed.
+    pub error: Option<String>,
+    /// Whether the initial read has completed (successfully or not), so
+    /// config-dependent setup can proceed.
+    pub loaded: bool,
+}
+
+impl ConfigIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Read the first of `paths` that exists.  A missing file is treated as
+    /// an empty config.
+    pub fn load(&mut self, paths: Vec<PathBuf>, cwd: PathBuf) {
+        debug!(paths = ?paths, "Loading project config");
+        let refs: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+        let cmd = shell::platform().read_first_existing(&refs);
+        self.config_path = paths.into_iter().next();
+        command_router::run(&self.host, &ConfigCommand::ReadConfig, &cmd, cwd);
+    }
+
+    /// Write `config` to `path`, replacing any existing file.
+    #[cfg(not(feature = "minimal"))]
+    pub fn save(&mut self, path: PathBuf, config: &ProjectConfig, cwd: PathBuf) {
+        info!(path = ?path, "Writing project config");
+        let cmd = shell::platform().write_text(&path, &config.to_kdl());
+        self.config_path = Some(path);
+        command_router::run(&self.host, &ConfigCommand::WriteConfig, &cmd, cwd);
+    }
+
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stdout: &[u8],
+        exit_code: Option<i32>,
+        config: &mut ProjectConfig,
+    ) -> bool {
+        let Some(purpose) = purpose_of::<ConfigCommand>(context) else {
+            return false;
+        };
+
+        match purpose {
+            ConfigCommand::ReadConfig => {
+                debug!(?exit_code, "ReadConfig result");
+                self.loaded = true;
+                if exit_code != Some(0) {
+                    return true;
+                }
+                let text = String::from_utf8_lossy(stdout);
+                match ProjectConfig::parse(&text) {
+                    Ok(parsed) => {
+                        info!(path = ?self.config_path, "Loaded project config");
+                        *config = parsed;
+                        self.error = None;
+                    }
+                    Err(e) => {
+                        error!(path = ?self.config_path, %e, "Failed to parse project config");
+                        self.error = Some(e.to_string());
+                    }
+                }
+                true
+            }
+            ConfigCommand::WriteConfig => {
+                debug!(?exit_code, "WriteConfig result");
+                if exit_code != Some(0) {
+                    error!(path = ?self.config_path, "Failed to write project config");
+                    self.error = Some("failed to write config file".to_string());
+                }
+                true
+            }
+        }
+    }
+}
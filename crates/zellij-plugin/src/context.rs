@@ -0,0 +1,46 @@
+//! Recent activity as LLM context, answered over the pipe that asked for it.
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::context -- --tokens 2000
+//! ```
+//!
+//! prints a [token-budgeted digest](crumbeez_lib::context_blob) of the event
+//! log, so a shell alias can put it straight into a prompt.  Other plugins
+//! get it back as a `crumbeez::context` message, and can give the budget as
+//! a `tokens` argument instead of in the payload.
+
+use std::collections::BTreeMap;
+
+use crumbeez_lib::DEFAULT_CONTEXT_TOKENS;
+
+/// Pipe message name that asks for context.
+pub const CONTEXT_PIPE: &str = "crumbeez::context";
+
+/// The token budget asked for: `--tokens N`, `--tokens=N`, or a bare `N` as
+/// the payload, or a `tokens` argument.  Defaults to
+/// [`DEFAULT_CONTEXT_TOKENS`].
+pub fn requested_tokens(
+    payload: Option<&str>,
+    args: &BTreeMap<String, String>,
+) -> Result<usize, String> {
+    let payload = payload.map(str::trim).unwrap_or("");
+    let value = match args.get("tokens") {
+        Some(tokens) => tokens.as_str(),
+        None if payload.is_empty() => return Ok(DEFAULT_CONTEXT_TOKENS),
+        None => {
+            let mut words = payload.split_whitespace();
+            match (words.next(), words.next(), words.next()) {
+                (Some("--tokens"), Some(n), None) => n,
+                (Some(word), None, None) => word.strip_prefix("--tokens=").unwrap_or(word),
+                _ => return Err(format!("expected --tokens N, got '{}'", payload)),
+            }
+        }
+    };
+    match value.parse() {
+        Ok(tokens) if tokens > 0 => Ok(tokens),
+        _ => Err(format!(
+            "token budget '{}' isn't a positive whole number",
+            value
+        )),
+    }
+}
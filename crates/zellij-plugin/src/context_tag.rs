@@ -0,0 +1,41 @@
+//! Tagging `run_command` requests so their results can be routed back.
+//!
+//! Each IO module tags its commands with a purpose enum stored under its own
+//! context key, and issues them through the
+//! [`command_router`](crate::command_router).  Tags are plain strings — the
+//! variant name, optionally followed by `:` and a single argument, e.g.
+//! `MkdirCrumbeez:/home/me/app` — so routing a result doesn't pull a JSON
+//! parser into the wasm module.
+
+use std::collections::BTreeMap;
+
+use crate::command_router::Retry;
+
+pub trait ContextTag: Sized {
+    /// Context key the tag is stored under.
+    const KEY: &'static str;
+
+    fn to_tag(&self) -> String;
+
+    /// Parse a tag produced by [`to_tag`](Self::to_tag).
+    fn from_tag(tag: &str) -> Option<Self>;
+
+    /// How long to wait for this command and whether to retry it.
+    fn retry(&self) -> Retry {
+        Retry::ONCE
+    }
+}
+
+/// The purpose a command result was tagged with, or `None` if it isn't one
+/// of `T`'s commands.
+pub fn purpose_of<T: ContextTag>(context: &BTreeMap<String, String>) -> Option<T> {
+    context.get(T::KEY).and_then(|tag| T::from_tag(tag))
+}
+
+/// Split a tag into its name and optional argument.
+pub fn split_tag(tag: &str) -> (&str, Option<&str>) {
+    match tag.split_once(':') {
+        Some((name, arg)) => (name, Some(arg)),
+        None => (tag, None),
+    }
+}
@@ -0,0 +1,114 @@
+//! Demo mode, for trying crumbeez out without logging real input.
+//!
+//! With `demo "true"` in its plugin configuration, an instance never
+//! intercepts keys.  [`DemoPlayer`] plays the canned [`Fixture`] streams in
+//! turn, forever, and the plugin feeds each key through the same pipeline
+//! as an intercepted one, so live text, summaries, and work blocks come out
+//! as they would for real work.  Nothing is read from or written to the
+//! project's `.crumbeez` directory.
+//!
+//! Long pauses in a fixture are cut short to keep things moving, and typed
+//! text arrives a character at a time.  Between fixtures the player waits
+//! a little longer than the inactivity timeout, so each one gets its own
+//! summary.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crumbeez_lib::fixtures::Fixture;
+use crumbeez_lib::KeystrokeEvent;
+
+/// Longest pause between two keys of a fixture.
+const MAX_GAP_MS: u64 = 3000;
+
+/// Time between typed characters.
+const CHAR_MS: u64 = 120;
+
+/// Extra wait after the inactivity timeout before the next fixture starts.
+const PAUSE_MARGIN: Duration = Duration::from_secs(2);
+
+/// Plays the fixtures; see the [module docs](self).
+#[derive(Debug)]
+pub struct DemoPlayer {
+    /// Keys of the fixture playing, with when each is due.
+    queue: VecDeque<(u64, KeystrokeEvent)>,
+    /// The fixture after the one playing.
+    next: usize,
+    /// When the last key was due.
+    last_ms: u64,
+    pause_ms: u64,
+}
+
+impl DemoPlayer {
+    /// A player starting at `now_ms`, pausing between fixtures until
+    /// `inactivity_timeout` has passed.
+    pub fn new(now_ms: u64, inactivity_timeout: Duration) -> Self {
+        let mut player = Self {
+            queue: VecDeque::new(),
+            next: 0,
+            last_ms: now_ms,
+            pause_ms: (inactivity_timeout + PAUSE_MARGIN).as_millis() as u64,
+        };
+        player.queue_next(now_ms);
+        player
+    }
+
+    /// When the next key is due, in ms since the epoch.
+    pub fn due_at(&self) -> Option<u64> {
+        self.queue.front().map(|(at, _)| *at)
+    }
+
+    /// The keys due by `now_ms`, in order.  Queues the next fixture once
+    /// the one playing runs out.
+    pub fn take_due(&mut self, now_ms: u64) -> Vec<KeystrokeEvent> {
+        let mut due = Vec::new();
+        while let Some((at, _)) = self.queue.front() {
+            if *at > now_ms {
+                break;
+            }
+            if let Some((at, event)) = self.queue.pop_front() {
+                self.last_ms = at;
+                due.push(event);
+            }
+        }
+        if self.queue.is_empty() {
+            self.queue_next(self.last_ms + self.pause_ms);
+        }
+        due
+    }
+
+    fn queue_next(&mut self, start_ms: u64) {
+        let fixture = Fixture::ALL[self.next];
+        self.next = (self.next + 1) % Fixture::ALL.len();
+        self.queue = steps(fixture, start_ms);
+    }
+}
+
+/// `fixture`'s keys starting at `start_ms`, with long pauses cut short
+/// and text split into characters.
+fn steps(fixture: Fixture, start_ms: u64) -> VecDeque<(u64, KeystrokeEvent)> {
+    let entries = fixture.entries(0);
+    let mut steps = VecDeque::new();
+    let mut at = start_ms;
+    for (i, entry) in entries.iter().enumerate() {
+        let gap = entries
+            .get(i + 1)
+            .map_or(0, |next| next.timestamp_ms - entry.timestamp_ms)
+            .min(MAX_GAP_MS);
+        match entry.event {
+            KeystrokeEvent::TextTyped(ref text) => {
+                let mut typing = 0;
+                for c in text.chars() {
+                    steps.push_back((at + typing, KeystrokeEvent::TextTyped(c.to_string())));
+                    typing += CHAR_MS;
+                }
+                at += gap.max(typing);
+            }
+            ref event => {
+                steps.push_back((at, event.clone()));
+                at += gap;
+            }
+        }
+    }
+    steps
+}
@@ -1,90 +1,405 @@
-use std::collections::BTreeMap;
-use std::path::PathBuf;
+//! Loading and saving the event log file.
+//!
+//! Encoding and writing happen in [`EventLogWorker`].  A write too big for
+//! one shell command (see [`Platform::write_binary_chunks`]) comes back to
+//! the plugin as a [`CHUNKED_WRITE`] message, and [`EventLogIO`] runs its
+//! commands one after another.  Reads go [`READ_CHUNK_BYTES`] at a time,
+//! assembled until a short chunk marks the end of the file, so a
+//! multi-megabyte log never has to pass through one command's output.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info};
 use zellij_tile::prelude::*;
 
-use crumbeez_lib::{EventLog, Summary};
+use crate::command_router::{self, Retry};
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+use crate::shell::{self, base64_decode, base64_encode, IoStrategy, Platform};
+use crumbeez_lib::{EventLog, PowerProfile, SaveFrame};
 
-const CTX_PURPOSE: &str = "crumbeez_event_log_purpose";
+pub const CTX_PURPOSE: &str = "crumbeez_event_log_purpose";
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Name the flush worker is registered under (see `register_worker!` in
+/// `main.rs`, which appends `_worker`).
+pub const WORKER_NAME: &str = "event_log";
+
+/// Message asking the worker to encode and write a [`FlushJob`].
+const FLUSH_MESSAGE: &str = "flush";
+
+/// Message the worker sends the plugin with a [`ChunkedWrite`] to run.
+pub const CHUNKED_WRITE: &str = "crumbeez_event_log_chunked_write";
+
+/// Context key the worker adds to a write: how many bytes it writes.
+const CTX_BYTES: &str = "crumbeez_event_log_bytes";
+
+/// Bytes of the log read per command.
+const READ_CHUNK_BYTES: usize = 1024 * 1024;
+
+/// The longest writes back off to while they keep failing.
+const MAX_FLUSH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Writes failing in a row before the plugin raises the alarm.
+pub const ALERT_AFTER_FAILURES: u32 = 3;
+
+#[derive(Debug)]
 enum EventLogCommand {
-    ReadEventLog,
-    WriteEventLog,
+    /// The file from `offset` on, up to [`READ_CHUNK_BYTES`].
+    ReadEventLog { offset: u64 },
+    /// Replace the file, or with `append`, add to the end of it.
+    WriteEventLog { append: bool },
+    /// One command of a [`ChunkedWrite`], with `remaining` still to run
+    /// after it.
+    WriteChunk { remaining: usize },
+}
+
+impl ContextTag for EventLogCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::ReadEventLog { offset } => format!("ReadEventLog:{}", offset),
+            Self::WriteEventLog { append } => format!("WriteEventLog:{}", append),
+            Self::WriteChunk { remaining } => format!("WriteChunk:{}", remaining),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match split_tag(tag) {
+            ("ReadEventLog", Some(offset)) => Some(Self::ReadEventLog {
+                offset: offset.parse().ok()?,
+            }),
+            ("WriteEventLog", Some(append)) => Some(Self::WriteEventLog {
+                append: append.parse().ok()?,
+            }),
+            ("WriteChunk", Some(remaining)) => Some(Self::WriteChunk {
+                remaining: remaining.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    fn retry(&self) -> Retry {
+        match self {
+            Self::ReadEventLog { .. } => Retry::IDEMPOTENT,
+            Self::WriteEventLog { .. } | Self::WriteChunk { .. } => Retry::ONCE,
+        }
+    }
 }
 
-fn purpose_context(purpose: EventLogCommand) -> BTreeMap<String, String> {
-    let mut ctx = BTreeMap::new();
-    ctx.insert(
-        CTX_PURPOSE.to_string(),
-        serde_json::to_string(&purpose).expect("EventLogCommand serialization is infallible"),
-    );
-    ctx
+/// The span of sequence numbers a log file on disk holds.
+#[derive(Debug, Clone, Copy)]
+struct SavedRange {
+    first_seq: u64,
+    next_seq: u64,
 }
 
 pub struct EventLogIO {
+    host: HostHandle,
     log_path: Option<PathBuf>,
-    pending_write: Option<Vec<u8>>,
+    /// What the file on disk holds, or `None` if the next save must rewrite
+    /// it from scratch.
+    saved: Option<SavedRange>,
+    /// What the file will hold once the running write succeeds.
+    in_flight: Option<SavedRange>,
+    /// The running write has the log as it was before a
+    /// [`rewrite`](Self::rewrite), so what it saves can't be appended to.
+    superseded: bool,
+    /// Commands of a chunked write still to run, in order.
+    chunks: VecDeque<Vec<String>>,
+    chunks_cwd: PathBuf,
+    /// Bytes the chunked write adds.
+    chunks_bytes: Option<u64>,
+    /// The running write appends rather than replacing the file.
+    appending: bool,
+    /// The file read so far, while a load is running.
+    read_buffer: Vec<u8>,
+    read_cwd: PathBuf,
+    /// The log has changes that haven't been handed to the worker yet.
+    dirty: bool,
+    last_flush: Option<SystemTime>,
+    /// Minimum time between writes, from the
+    /// [`PowerProfile`](crumbeez_lib::PowerProfile).  Saves requested
+    /// sooner only mark the log dirty; the next timer tick after the
+    /// interval writes them.
+    pub min_interval: Duration,
+    /// When a write last succeeded.
+    pub last_saved: Option<SystemTime>,
+    /// Size of the file on disk, as last read or written.
+    pub size: Option<u64>,
+    /// Writes that failed or timed out since the plugin started.
+    pub write_failures: u32,
+    /// Writes that failed since the last one that succeeded.  Each doubles
+    /// the time until the next attempt, up to [`MAX_FLUSH_INTERVAL`].
+    pub consecutive_failures: u32,
+    /// What was wrong with the file on disk when it was loaded, shown in the
+    /// plugin pane.
+    pub damage: Option<String>,
+    /// Never write the log, only load it; see [`crate::viewer`].
+    pub read_only: bool,
+    /// A load replaced the log in memory.  The plugin takes this to seal
+    /// text still being typed, which belongs to the log that was replaced.
+    pub reloaded: bool,
+    /// The last load has finished, whether or not there was anything to
+    /// load.  Events logged before then can be replaced by the loaded log.
+    pub loaded: bool,
 }
 
 impl Default for EventLogIO {
     fn default() -> Self {
-        Self::new()
+        Self::new(HostHandle::default())
     }
 }
 
 impl EventLogIO {
-    pub fn new() -> Self {
+    pub fn new(host: HostHandle) -> Self {
         Self {
+            host,
             log_path: None,
-            pending_write: None,
+            saved: None,
+            in_flight: None,
+            superseded: false,
+            chunks: VecDeque::new(),
+            chunks_cwd: PathBuf::new(),
+            chunks_bytes: None,
+            appending: false,
+            read_buffer: Vec::new(),
+            read_cwd: PathBuf::new(),
+            dirty: false,
+            last_flush: None,
+            min_interval: PowerProfile::NORMAL.flush_interval,
+            last_saved: None,
+            size: None,
+            write_failures: 0,
+            consecutive_failures: 0,
+            damage: None,
+            read_only: false,
+            reloaded: false,
+            loaded: false,
         }
     }
 
+    pub fn log_path(&self) -> Option<&Path> {
+        self.log_path.as_deref()
+    }
+
     pub fn set_log_path(&mut self, path: PathBuf) {
         debug!(path = ?path, "Event log path set");
         self.log_path = Some(path);
     }
 
+    /// Move the log to `path`: the next write replaces whatever is there
+    /// with everything in memory.
+    pub fn relocate(&mut self, path: PathBuf) {
+        info!(from = ?self.log_path, to = ?path, "Moving event log");
+        self.log_path = Some(path);
+        self.saved = None;
+        // A write still running targets the old path.
+        self.in_flight = None;
+        self.superseded = false;
+        self.chunks.clear();
+        self.size = None;
+        self.dirty = true;
+        // Don't hold a new location to the old one's backoff.
+        self.last_flush = None;
+    }
+
     pub fn load(&mut self, cwd: PathBuf) {
         let Some(log_path) = &self.log_path else {
             error!("No log path set for load");
             return;
         };
-        let path_str = log_path.to_string_lossy().into_owned();
-        debug!(path = %path_str, "Loading event log");
-        let base64_cmd = format!("if [ -f '{}' ]; then base64 '{}'; fi", path_str, path_str);
-        run_command_with_env_variables_and_cwd(
-            &["sh", "-c", &base64_cmd],
-            BTreeMap::new(),
-            cwd,
-            purpose_context(EventLogCommand::ReadEventLog),
+        debug!(path = ?log_path, "Loading event log");
+        self.loaded = false;
+        self.read_buffer.clear();
+        self.read_cwd = cwd;
+        self.read_from(0);
+    }
+
+    fn read_from(&self, offset: u64) {
+        let Some(log_path) = &self.log_path else {
+            return;
+        };
+        let cmd = shell::platform().read_binary_range(
+            log_path,
+            offset,
+            READ_CHUNK_BYTES,
+            shell::io_strategy().read,
+        );
+        command_router::run(
+            &self.host,
+            &EventLogCommand::ReadEventLog { offset },
+            &cmd,
+            self.read_cwd.clone(),
         );
     }
 
-    pub fn save(&mut self, cwd: PathBuf, data: Vec<u8>) {
+    /// Run the commands of a [`CHUNKED_WRITE`] message from the worker in
+    /// place of the write it was asked for.
+    pub fn handle_message(&mut self, payload: &str) {
+        let Some(write) = base64_decode(payload)
+            .and_then(|bytes| rmp_serde::from_slice::<ChunkedWrite>(&bytes).ok())
+        else {
+            error!("Malformed chunked write from the worker");
+            return;
+        };
+        // The write was given up on, or the log moved, while the worker
+        // encoded it.
+        if !command_router::accept(&self.host, &write.context) || self.in_flight.is_none() {
+            return;
+        }
+        info!(
+            commands = write.commands.len(),
+            "Writing event log in chunks"
+        );
+        self.chunks = write.commands.into();
+        self.chunks_cwd = write.cwd;
+        self.chunks_bytes = written_bytes(&write.context);
+        self.run_next_chunk();
+    }
+
+    fn run_next_chunk(&mut self) {
+        if let Some(cmd) = self.chunks.pop_front() {
+            let purpose = EventLogCommand::WriteChunk {
+                remaining: self.chunks.len(),
+            };
+            command_router::run(&self.host, &purpose, &cmd, self.chunks_cwd.clone());
+        }
+    }
+
+    /// Note that `event_log` has changes to persist and write them if the
+    /// last write was long enough ago.  Otherwise they're picked up by a
+    /// later [`flush_if_due`](Self::flush_if_due).
+    pub fn save(&mut self, cwd: PathBuf, event_log: &EventLog) {
+        self.dirty = true;
+        self.flush_if_due(cwd, event_log);
+    }
+
+    /// Like [`save`](Self::save), for a log changed other than at its end,
+    /// e.g. [backfilled](EventLog::backfill): the next write replaces the
+    /// file rather than appending to it.
+    pub fn rewrite(&mut self, cwd: PathBuf, event_log: &EventLog) {
+        self.saved = None;
+        self.superseded = self.in_flight.is_some();
+        self.save(cwd, event_log);
+    }
+
+    /// Write pending changes unless a write is already running or one
+    /// happened within [`flush_interval`](Self::flush_interval).  Call when
+    /// the timer fires.
+    pub fn flush_if_due(&mut self, cwd: PathBuf, event_log: &EventLog) {
+        // Nothing is written until the log has been loaded.
+        if !self.dirty || self.in_flight.is_some() || self.log_path.is_none() || self.read_only {
+            return;
+        }
+        let recently_flushed = self.last_flush.is_some_and(|last| {
+            self.host
+                .now()
+                .duration_since(last)
+                .is_ok_and(|elapsed| elapsed < self.flush_interval())
+        });
+        if recently_flushed {
+            debug!("Event log flushed recently, deferring");
+            return;
+        }
+        self.flush(cwd, event_log);
+    }
+
+    /// When [`flush_if_due`](Self::flush_if_due) will next write, if there's
+    /// anything to write.
+    pub fn flush_due(&self) -> Option<SystemTime> {
+        if !self.dirty || self.in_flight.is_some() || self.log_path.is_none() {
+            return None;
+        }
+        Some(
+            self.last_flush
+                .map_or_else(|| self.host.now(), |last| last + self.flush_interval()),
+        )
+    }
+
+    /// Minimum time between writes: [`min_interval`](Self::min_interval), doubled for
+    /// each write in a row that failed.
+    pub fn flush_interval(&self) -> Duration {
+        let doublings = self.consecutive_failures.min(16);
+        (self.min_interval * 2u32.pow(doublings)).min(MAX_FLUSH_INTERVAL)
+    }
+
+    /// Writes have failed [`ALERT_AFTER_FAILURES`] times in a row, so
+    /// events are only piling up in memory.
+    pub fn alerting(&self) -> bool {
+        self.consecutive_failures >= ALERT_AFTER_FAILURES
+    }
+
+    /// Hand everything not yet on disk to the worker.  Normally only entries
+    /// added since the last write are appended; the whole file is rewritten
+    /// on the first write after loading, after a failed write, or once more
+    /// than half of the file is entries since dropped from memory.
+    fn flush(&mut self, cwd: PathBuf, event_log: &EventLog) {
         let Some(log_path) = &self.log_path else {
             error!("No log path set for save");
             return;
         };
-        let path_str = log_path.to_string_lossy().into_owned();
-        let b64 = base64_encode(&data);
+
+        let first_seq = event_log.first_seq();
+        let append_from = self.saved.filter(|saved| {
+            let stale = first_seq.saturating_sub(saved.first_seq);
+            stale <= event_log.total_count() as u64
+        });
+        let frame = match event_log.save_frame(append_from.map(|saved| saved.next_seq)) {
+            Ok(frame) => frame,
+            Err(e) if append_from.is_some() => {
+                debug!(%e, "Can't append to event log, rewriting it");
+                self.saved = None;
+                return self.flush(cwd, event_log);
+            }
+            Err(e) => {
+                error!(%e, "Failed to collect event log for saving");
+                return;
+            }
+        };
+        let range = SavedRange {
+            first_seq: append_from.map_or(first_seq, |saved| saved.first_seq),
+            next_seq: event_log.next_seq(),
+        };
+
         info!(
-            bytes = data.len(),
-            b64_len = b64.len(),
-            path = %path_str,
-            "Saving event log"
+            entries = frame.entries.len(),
+            append = frame.append,
+            path = ?log_path,
+            "Flushing event log"
         );
-        let cmd = format!("printf '%s' '{}' | base64 -d > '{}'", b64, path_str);
-        self.pending_write = Some(data);
-        run_command_with_env_variables_and_cwd(
-            &["sh", "-c", &cmd],
-            BTreeMap::new(),
+        let job = FlushJob {
+            path: log_path.clone(),
             cwd,
-            purpose_context(EventLogCommand::WriteEventLog),
-        );
+            platform: shell::platform(),
+            io: shell::io_strategy(),
+            context: command_router::track(
+                &self.host,
+                &EventLogCommand::WriteEventLog {
+                    append: frame.append,
+                },
+            ),
+            frame,
+        };
+        let payload = match rmp_serde::to_vec(&job) {
+            Ok(bytes) => base64_encode(&bytes),
+            Err(e) => {
+                error!(%e, "Failed to serialize flush job");
+                return;
+            }
+        };
+        self.host.post_message_to(PluginMessage::new_to_worker(
+            WORKER_NAME,
+            FLUSH_MESSAGE,
+            &payload,
+        ));
+        self.in_flight = Some(range);
+        self.appending = job.frame.append;
+        self.dirty = false;
+        self.last_flush = Some(self.host.now());
     }
 
     pub fn handle_result(
@@ -94,116 +409,299 @@ impl EventLogIO {
         exit_code: Option<i32>,
         event_log: &mut EventLog,
     ) -> bool {
-        let purpose: EventLogCommand = match context.get(CTX_PURPOSE) {
-            Some(s) => match serde_json::from_str(s) {
-                Ok(p) => p,
-                Err(_) => return false,
-            },
-            None => return false,
+        let Some(purpose) = purpose_of::<EventLogCommand>(context) else {
+            return false;
         };
 
         match purpose {
-            EventLogCommand::ReadEventLog => {
-                debug!(?exit_code, "ReadEventLog result");
-                if exit_code == Some(0) && !stdout.is_empty() {
-                    let b64_str = String::from_utf8_lossy(stdout);
-                    if let Some(decoded) = base64_decode(&b64_str) {
-                        if let Ok(loaded_log) = EventLog::deserialize(&decoded) {
-                            info!(count = loaded_log.total_count(), "Loaded events from disk");
-                            *event_log = loaded_log;
-                        } else {
-                            error!("Failed to deserialize event log");
-                        }
-                    } else {
-                        error!("Failed to decode base64");
-                    }
+            EventLogCommand::ReadEventLog { offset } => {
+                debug!(?exit_code, offset, "ReadEventLog result");
+                if exit_code != Some(0) {
+                    error!(?exit_code, "Failed to read event log");
+                    self.read_buffer.clear();
+                    self.loaded = true;
+                    return true;
+                }
+                let Some(chunk) = shell::io_strategy().read.decode(stdout) else {
+                    error!(io = %shell::io_strategy(), "Failed to decode event log");
+                    self.read_buffer.clear();
+                    self.loaded = true;
+                    return true;
+                };
+                // A load started over; this belongs to the earlier one.
+                if offset != self.read_buffer.len() as u64 {
+                    return true;
+                }
+                self.read_buffer.extend_from_slice(&chunk);
+                if chunk.len() == READ_CHUNK_BYTES {
+                    self.read_from(self.read_buffer.len() as u64);
+                    return true;
                 }
+                let data = std::mem::take(&mut self.read_buffer);
+                self.size = Some(data.len() as u64);
+                if !data.is_empty() {
+                    self.load_bytes(&data, event_log);
+                }
+                self.loaded = true;
+                true
+            }
+            EventLogCommand::WriteChunk { remaining } if remaining > 0 && exit_code == Some(0) => {
+                debug!(remaining, "Event log chunk written");
+                self.run_next_chunk();
                 true
             }
-            EventLogCommand::WriteEventLog => {
-                debug!(?exit_code, "WriteEventLog result");
-                self.pending_write = None;
+            EventLogCommand::WriteEventLog { .. } | EventLogCommand::WriteChunk { .. } => {
+                debug!(?exit_code, ?purpose, "Event log write result");
+                self.chunks.clear();
+                let range = self.in_flight.take().filter(|_| !self.superseded);
+                self.superseded = false;
+                let bytes = match purpose {
+                    EventLogCommand::WriteChunk { .. } => self.chunks_bytes.take(),
+                    _ => written_bytes(context),
+                };
+                if exit_code == Some(0) {
+                    self.saved = range;
+                    self.size = match bytes {
+                        Some(bytes) if self.appending => self.size.map(|size| size + bytes),
+                        bytes => bytes,
+                    };
+                    self.last_saved = Some(self.host.now());
+                    self.consecutive_failures = 0;
+                } else {
+                    self.saved = None;
+                    self.size = None;
+                    self.dirty = true;
+                    self.write_failures += 1;
+                    self.consecutive_failures += 1;
+                    error!(
+                        ?exit_code,
+                        in_a_row = self.consecutive_failures,
+                        retry_in = ?self.flush_interval(),
+                        "Failed to write event log"
+                    );
+                }
                 true
             }
         }
     }
-}
 
-fn base64_encode(data: &[u8]) -> String {
-    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-    let mut result = String::new();
-    let mut padding = 0;
-
-    for chunk in data.chunks(3) {
-        let mut n = 0u32;
-        for (i, &byte) in chunk.iter().enumerate() {
-            n |= (byte as u32) << (16 - i * 8);
-        }
-        padding = 3 - chunk.len();
-        for i in 0..(4 - padding) {
-            let idx = ((n >> (18 - i * 6)) & 0x3F) as usize;
-            result.push(ALPHABET[idx] as char);
+    fn load_bytes(&mut self, data: &[u8], event_log: &mut EventLog) {
+        match EventLog::deserialize_lossy(data) {
+            Ok((loaded_log, damage)) => {
+                info!(count = loaded_log.total_count(), "Loaded events from disk");
+                if let Some(e) = damage {
+                    error!(%e, "Event log damaged, kept the intact entries");
+                    self.damage = Some(format!(
+                        "{} (recovered {} entries)",
+                        e,
+                        loaded_log.total_count()
+                    ));
+                }
+                *event_log = loaded_log;
+                self.reloaded = true;
+                // The file may be in an older format or have a damaged tail;
+                // rewrite it once before appending.
+                self.saved = None;
+            }
+            Err(e) => {
+                error!(%e, "Failed to deserialize event log");
+                self.damage = Some(e.to_string());
+            }
         }
     }
+}
 
-    for _ in 0..padding {
-        result.push('=');
-    }
+/// The bytes a write's context says it writes, if the worker added them.
+fn written_bytes(context: &BTreeMap<String, String>) -> Option<u64> {
+    context.get(CTX_BYTES).and_then(|bytes| bytes.parse().ok())
+}
+
+// ── Flush worker ─────────────────────────────────────────────────
+
+/// A write for the worker to perform.
+#[derive(Debug, Serialize, Deserialize)]
+struct FlushJob {
+    path: PathBuf,
+    cwd: PathBuf,
+    /// The worker doesn't see the plugin's configuration or probe results.
+    platform: Platform,
+    io: IoStrategy,
+    frame: SaveFrame,
+    /// Context for the write command, tracked by the plugin's router.
+    context: BTreeMap<String, String>,
+}
+
+/// Commands for the plugin to run in order, when a write is too big for one.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChunkedWrite {
+    commands: Vec<Vec<String>>,
+    cwd: PathBuf,
+    /// The [`FlushJob`]'s context, which the plugin claims in its place.
+    context: BTreeMap<String, String>,
+}
+
+/// Plugin worker that encodes [`SaveFrame`]s and runs the shell command to
+/// write them, keeping that work off the plugin's update path.  The command
+/// result is delivered to the plugin as usual and handled by
+/// [`EventLogIO::handle_result`].  A write needing several commands is sent
+/// back as a [`ChunkedWrite`] instead, since results of commands run here
+/// can arrive in any order.
+#[derive(Default, Serialize, Deserialize)]
+pub struct EventLogWorker {
+    #[serde(skip)]
+    host: HostHandle,
+}
 
-    result
+impl EventLogWorker {
+    pub fn new(host: HostHandle) -> Self {
+        Self { host }
+    }
 }
 
-fn base64_decode(s: &str) -> Option<Vec<u8>> {
-    const DECODE_TABLE: [i8; 128] = [
-        -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,
-        -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, 62, -1, -1,
-        -1, 63, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, -1, -1, -1, -1, -1, -1, -1, 0, 1, 2, 3, 4,
-        5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, -1, -1, -1,
-        -1, -1, -1, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45,
-        46, 47, 48, 49, 50, 51, -1, -1, -1, -1, -1,
-    ];
-
-    let s = s.trim();
-    let s = s.trim_end_matches('=');
-
-    let mut result = Vec::with_capacity(s.len() * 3 / 4);
-    let mut buffer = 0u32;
-    let mut bits = 0;
-
-    for c in s.chars() {
-        let val = if (c as usize) < 128 {
-            DECODE_TABLE[c as usize]
-        } else {
-            -1
+impl<'de> ZellijWorker<'de> for EventLogWorker {
+    fn on_message(&mut self, message: String, payload: String) {
+        if message != FLUSH_MESSAGE {
+            return;
+        }
+        // Workers run in their own instance with no subscriber installed.
+        crate::logging::init(&crate::logging::LogSettings::default());
+        let Some(bytes) = base64_decode(&payload) else {
+            error!("Malformed flush job: invalid base64");
+            return;
         };
-        if val < 0 {
-            continue;
+        let job: FlushJob = match rmp_serde::from_slice(&bytes) {
+            Ok(job) => job,
+            Err(e) => {
+                error!(%e, "Malformed flush job");
+                return;
+            }
+        };
+        let data = match job.frame.encode() {
+            Ok(data) => data,
+            Err(e) => {
+                error!(%e, "Failed to encode event log");
+                return;
+            }
+        };
+
+        let append = job.frame.append;
+        debug!(bytes = data.len(), append, path = ?job.path, "Writing event log");
+        let mut context = job.context;
+        context.insert(CTX_BYTES.to_string(), data.len().to_string());
+        let commands = job
+            .platform
+            .write_binary_chunks(&job.path, &data, append, job.io.write);
+        if commands.len() == 1 {
+            let cmd: Vec<&str> = commands[0].iter().map(String::as_str).collect();
+            self.host.run_command_with_env_variables_and_cwd(
+                &cmd,
+                BTreeMap::new(),
+                job.cwd,
+                context,
+            );
+            return;
         }
-        buffer = (buffer << 6) | (val as u32);
-        bits += 6;
-        if bits >= 8 {
-            bits -= 8;
-            result.push((buffer >> bits) as u8);
+        let write = ChunkedWrite {
+            commands,
+            cwd: job.cwd,
+            context,
+        };
+        match rmp_serde::to_vec(&write) {
+            Ok(bytes) => self
+                .host
+                .post_message_to_plugin(PluginMessage::new_to_plugin(
+                    CHUNKED_WRITE,
+                    &base64_encode(&bytes),
+                )),
+            Err(e) => error!(%e, "Failed to serialize chunked write"),
         }
     }
-
-    Some(result)
 }
 
-pub fn generate_summary(event_log: &mut EventLog) -> Option<String> {
-    let unconsumed: Vec<_> = event_log.unconsumed().cloned().collect();
-    if unconsumed.is_empty() {
-        return None;
+#[cfg(test)]
+mod tests {
+    use zellij_tile::prelude::PluginIds;
+
+    use super::*;
+    use crate::host::{HostCall, Mock};
+    use crumbeez_lib::KeystrokeEvent;
+
+    const LOG_PATH: &str = "/home/me/app/.crumbeez/events.log";
+
+    fn setup() -> (Mock, EventLogIO) {
+        let host = Mock::new(SystemTime::UNIX_EPOCH, PluginIds::default());
+        shell::set_platform(Platform::Unix);
+        let mut io = EventLogIO::new(host.handle());
+        io.set_log_path(PathBuf::from(LOG_PATH));
+        (host, io)
     }
 
-    let summary = Summary::from_events(unconsumed.into_iter());
-    let count = summary.events_consumed;
-    event_log.consume(count);
+    /// The write handed to the worker since calls were last taken, if any.
+    fn flushed(host: &Mock) -> Option<FlushJob> {
+        host.take_calls().into_iter().find_map(|call| match call {
+            HostCall::PostMessageTo(message) if message.name == FLUSH_MESSAGE => {
+                let bytes = base64_decode(&message.payload)?;
+                rmp_serde::from_slice(&bytes).ok()
+            }
+            _ => None,
+        })
+    }
 
-    let mut lines = vec![format!("📊 Summary: {} events processed", count)];
-    for (event_type, cnt) in &summary.event_types {
-        lines.push(format!("  {}: {}", event_type, cnt));
+    fn finish(io: &mut EventLogIO, job: &FlushJob, exit_code: i32, log: &mut EventLog) {
+        assert!(command_router::accept(&io.host, &job.context));
+        assert!(io.handle_result(&job.context, b"", Some(exit_code), log));
     }
 
-    Some(lines.join("\n"))
+    #[test]
+    fn a_failed_write_backs_off_then_rewrites_the_file() {
+        let (host, mut io) = setup();
+        let cwd = PathBuf::from("/home/me/app");
+        let mut log = EventLog::new();
+        log.append(KeystrokeEvent::TextTyped("ls".into()), 1);
+
+        io.save(cwd.clone(), &log);
+        let job = flushed(&host).expect("first save writes at once");
+        assert!(!job.frame.append);
+        finish(&mut io, &job, 0, &mut log);
+
+        log.append(KeystrokeEvent::TextTyped("cd src".into()), 2);
+        io.save(cwd.clone(), &log);
+        assert!(flushed(&host).is_none(), "wrote again within the interval");
+        host.advance(io.min_interval);
+        io.flush_if_due(cwd.clone(), &log);
+        let job = flushed(&host).expect("due after the interval");
+        assert!(job.frame.append);
+        finish(&mut io, &job, 1, &mut log);
+        assert_eq!(io.consecutive_failures, 1);
+
+        host.advance(io.min_interval);
+        io.flush_if_due(cwd.clone(), &log);
+        assert!(flushed(&host).is_none(), "didn't back off after failing");
+        host.advance(io.min_interval);
+        io.flush_if_due(cwd, &log);
+        let job = flushed(&host).expect("due after twice the interval");
+        assert!(!job.frame.append, "appended to a file in an unknown state");
+    }
+
+    #[test]
+    fn a_load_replaces_the_log_in_memory() {
+        let (host, mut io) = setup();
+        let mut saved = EventLog::new();
+        saved.append(KeystrokeEvent::TextTyped("make".into()), 1);
+        let data = saved.serialize().unwrap();
+        let mut log = EventLog::new();
+
+        io.load(PathBuf::from("/home/me/app"));
+        let calls = host.take_calls();
+        let [HostCall::RunCommand { context, .. }] = calls.as_slice() else {
+            panic!("expected one read: {:?}", calls);
+        };
+        assert!(command_router::accept(&io.host, context));
+        io.handle_result(context, base64_encode(&data).as_bytes(), Some(0), &mut log);
+
+        assert!(io.loaded && io.reloaded);
+        assert_eq!(io.size, Some(data.len() as u64));
+        assert_eq!(log.total_count(), 1);
+        assert!(host.take_calls().is_empty(), "read past a short chunk");
+    }
 }
@@ -0,0 +1,98 @@
+//! Writing `.crumbeez/heartbeat.json` after each event log write, for
+//! external tools to check the plugin is alive and saving.  See
+//! [`crumbeez_lib::Heartbeat`] for the fields.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use tracing::{debug, warn};
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+use crumbeez_lib::Heartbeat;
+
+pub const CTX_PURPOSE: &str = "crumbeez_heartbeat_purpose";
+
+#[derive(Debug)]
+enum HeartbeatCommand {
+    WriteHeartbeat,
+}
+
+impl ContextTag for HeartbeatCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::WriteHeartbeat => "WriteHeartbeat",
+        }
+        .to_string()
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "WriteHeartbeat" => Some(Self::WriteHeartbeat),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct HeartbeatIO {
+    host: HostHandle,
+    path: Option<PathBuf>,
+    /// A write is running.  A newer heartbeat waits here for it rather than
+    /// racing it to the file.
+    in_flight: bool,
+    queued: Option<Heartbeat>,
+}
+
+impl HeartbeatIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    pub fn set_path(&mut self, path: PathBuf) {
+        debug!(path = ?path, "Heartbeat path set");
+        self.path = Some(path);
+    }
+
+    /// Replace the heartbeat file with `heartbeat`.
+    pub fn write(&mut self, heartbeat: Heartbeat, cwd: PathBuf) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if self.in_flight {
+            self.queued = Some(heartbeat);
+            return;
+        }
+        let cmd = shell::platform().write_text(path, &heartbeat.to_json());
+        self.in_flight = true;
+        command_router::run(&self.host, &HeartbeatCommand::WriteHeartbeat, &cmd, cwd);
+    }
+
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stderr: &[u8],
+        exit_code: Option<i32>,
+        cwd: PathBuf,
+    ) -> bool {
+        let Some(HeartbeatCommand::WriteHeartbeat) = purpose_of::<HeartbeatCommand>(context) else {
+            return false;
+        };
+        self.in_flight = false;
+        if exit_code != Some(0) {
+            let stderr = String::from_utf8_lossy(stderr);
+            warn!(?exit_code, stderr = %stderr.trim(), "Failed to write heartbeat");
+        }
+        if let Some(heartbeat) = self.queued.take() {
+            self.write(heartbeat, cwd);
+        }
+        false
+    }
+}
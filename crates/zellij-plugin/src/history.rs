@@ -0,0 +1,123 @@
+//! Importing shell history into a fresh log.
+//!
+//! Run once after setting crumbeez up in a project, to start its trail
+//! with the last few weeks of commands rather than nothing:
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::import_history -- zsh
+//! zellij pipe --name crumbeez::import_history -- "bash $HOME/old/.bash_history"
+//! ```
+//!
+//! The payload names the shell (`bash`, `zsh`, `fish`, or `atuin`) and,
+//! optionally, the history file to read instead of the shell's usual one.
+//! Commands are read on the host (see
+//! [`Platform::read_shell_history`](crate::shell::Platform::read_shell_history))
+//! and backfilled by the caller.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crumbeez_lib::HistoryFormat;
+use tracing::{debug, error, info};
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+
+/// Pipe message name that imports a shell's history.
+pub const HISTORY_PIPE: &str = "crumbeez::import_history";
+
+pub const CTX_PURPOSE: &str = "crumbeez_history_purpose";
+
+#[derive(Debug)]
+enum HistoryCommand {
+    ReadHistory { format: HistoryFormat },
+}
+
+impl ContextTag for HistoryCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::ReadHistory { format } => format!("ReadHistory:{}", format.name()),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match split_tag(tag) {
+            ("ReadHistory", Some(name)) => {
+                HistoryFormat::from_name(name).map(|format| Self::ReadHistory { format })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The shell and history file a [`HISTORY_PIPE`] payload asks for.
+pub fn parse_request(payload: &str) -> Result<(HistoryFormat, Option<PathBuf>), String> {
+    let payload = payload.trim();
+    let (name, path) = match payload.split_once(char::is_whitespace) {
+        Some((name, path)) => (name, Some(PathBuf::from(path.trim()))),
+        None => (payload, None),
+    };
+    match HistoryFormat::from_name(name) {
+        Some(format) => Ok((format, path)),
+        None if name.is_empty() => Err("name a shell: bash, zsh, fish, or atuin".to_string()),
+        None => Err(format!(
+            "can't read {} history: try bash, zsh, fish, or atuin",
+            name
+        )),
+    }
+}
+
+#[derive(Default)]
+pub struct HistoryIO {
+    host: HostHandle,
+    /// How the last import went, shown in the plugin pane.
+    pub status: Option<String>,
+}
+
+impl HistoryIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Read `format`'s history from `path`, or from where the shell keeps
+    /// it.
+    pub fn read(&mut self, format: HistoryFormat, path: Option<PathBuf>, cwd: PathBuf) {
+        info!(shell = format.name(), ?path, "Reading shell history");
+        let cmd = shell::platform().read_shell_history(format, path.as_deref());
+        self.status = Some(format!("reading {} history…", format.name()));
+        command_router::run(
+            &self.host,
+            &HistoryCommand::ReadHistory { format },
+            &cmd,
+            cwd,
+        );
+    }
+
+    /// The shell and its history, if the result is a history that was
+    /// read.
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stdout: &[u8],
+        stderr: &[u8],
+        exit_code: Option<i32>,
+    ) -> Option<(HistoryFormat, Vec<u8>)> {
+        let HistoryCommand::ReadHistory { format } = purpose_of::<HistoryCommand>(context)?;
+        debug!(?exit_code, bytes = stdout.len(), "ReadHistory result");
+        if exit_code == Some(0) {
+            Some((format, stdout.to_vec()))
+        } else {
+            let err = String::from_utf8_lossy(stderr);
+            error!(%err, shell = format.name(), "Failed to read shell history");
+            self.status = Some(format!("couldn't read {} history", format.name()));
+            None
+        }
+    }
+}
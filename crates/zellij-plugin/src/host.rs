@@ -0,0 +1,413 @@
+//! The calls the plugin makes into Zellij, and the clock, behind a [`Host`].
+//!
+//! In the wasm build every call goes straight to `zellij_tile`.  Natively
+//! there is no Zellij to call, so tests build a [`Mock`] that notes each
+//! call and keeps a clock they move by hand.  With it they drive
+//! [`RootDiscovery`](crate::root_discovery::RootDiscovery) and
+//! [`EventLogIO`](crate::event_log_io::EventLogIO) on their own, or the
+//! whole update loop; see [`replay`](crate::replay).
+//!
+//! Every piece of state that calls into Zellij, runs commands, or reads the
+//! time keeps a [`HostHandle`], a clone of the one [`State`](crate::State)
+//! was made with.  The handle also carries the command router's state, so
+//! a test's mock and the commands run on it are its own.
+//!
+//! Each method on the handle has the signature of the `zellij_tile`
+//! function it stands in for.  Anything that reads the time for the update
+//! loop goes through [`HostHandle::now`], and anything that needs
+//! randomness through [`HostHandle::random`].
+
+use std::cell::{RefCell, RefMut};
+#[cfg(not(feature = "minimal"))]
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+#[cfg(not(feature = "minimal"))]
+use std::hash::BuildHasher;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use zellij_tile::prelude::{
+    EventType, MessageToPlugin, PaneId, PermissionType, PluginIds, PluginMessage,
+};
+
+use crate::command_router::CommandRouter;
+
+/// A call into Zellij that changes something, with owned arguments.
+// Natively only tests look inside, and only at what they check.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub enum HostCall {
+    Subscribe(Vec<EventType>),
+    RequestPermission(Vec<PermissionType>),
+    SetTimeout(f64),
+    RunCommand {
+        args: Vec<String>,
+        env: BTreeMap<String, String>,
+        cwd: PathBuf,
+        context: BTreeMap<String, String>,
+    },
+    PostMessageTo(PluginMessage),
+    PostMessageToPlugin(PluginMessage),
+    PipeMessageToPlugin(MessageToPlugin),
+    CliPipeOutput {
+        pipe: String,
+        output: String,
+    },
+    UnblockCliPipeInput(String),
+    Write(Vec<u8>),
+    WriteToPaneId(Vec<u8>, PaneId),
+    RenamePluginPane(u32, String),
+    InterceptKeyPresses,
+    ClearKeyPressesIntercepts,
+    CopyToClipboard(String),
+    WatchFilesystem,
+}
+
+/// Where the plugin's calls go and where its time comes from.
+pub trait Host {
+    fn now(&self) -> SystemTime;
+    #[cfg(not(feature = "minimal"))]
+    fn random(&self) -> u128;
+    fn plugin_ids(&self) -> PluginIds;
+    fn call(&self, call: HostCall);
+}
+
+/// The real thing.
+#[cfg(target_arch = "wasm32")]
+struct Zellij;
+
+#[cfg(target_arch = "wasm32")]
+impl Host for Zellij {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    fn random(&self) -> u128 {
+        entropy()
+    }
+
+    fn plugin_ids(&self) -> PluginIds {
+        zellij_tile::prelude::get_plugin_ids()
+    }
+
+    fn call(&self, call: HostCall) {
+        use zellij_tile::prelude as zellij;
+        match call {
+            HostCall::Subscribe(event_types) => zellij::subscribe(&event_types),
+            HostCall::RequestPermission(permissions) => zellij::request_permission(&permissions),
+            HostCall::SetTimeout(secs) => zellij::set_timeout(secs),
+            HostCall::RunCommand {
+                args,
+                env,
+                cwd,
+                context,
+            } => {
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                zellij::run_command_with_env_variables_and_cwd(&args, env, cwd, context);
+            }
+            HostCall::PostMessageTo(message) => zellij::post_message_to(message),
+            HostCall::PostMessageToPlugin(message) => zellij::post_message_to_plugin(message),
+            HostCall::PipeMessageToPlugin(message) => zellij::pipe_message_to_plugin(message),
+            HostCall::CliPipeOutput { pipe, output } => zellij::cli_pipe_output(&pipe, &output),
+            HostCall::UnblockCliPipeInput(pipe) => zellij::unblock_cli_pipe_input(&pipe),
+            HostCall::Write(bytes) => zellij::write(bytes),
+            HostCall::WriteToPaneId(bytes, pane_id) => zellij::write_to_pane_id(bytes, pane_id),
+            HostCall::RenamePluginPane(id, name) => zellij::rename_plugin_pane(id, name),
+            HostCall::InterceptKeyPresses => zellij::intercept_key_presses(),
+            HostCall::ClearKeyPressesIntercepts => zellij::clear_key_presses_intercepts(),
+            HostCall::CopyToClipboard(text) => zellij::copy_to_clipboard(text),
+            HostCall::WatchFilesystem => zellij::watch_filesystem(),
+        }
+    }
+}
+
+/// Natively there's no Zellij: a handle nobody gave a mock to drops its
+/// calls.
+#[cfg(not(target_arch = "wasm32"))]
+struct Absent;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Host for Absent {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    fn random(&self) -> u128 {
+        entropy()
+    }
+
+    fn plugin_ids(&self) -> PluginIds {
+        PluginIds::default()
+    }
+
+    fn call(&self, _call: HostCall) {}
+}
+
+/// Random bits from the system's randomness source.  Each `RandomState`
+/// gets new keys seeded from it, so hashing with two of them gives 128 bits.
+#[cfg(not(feature = "minimal"))]
+fn entropy() -> u128 {
+    let random = || u128::from(RandomState::new().hash_one(0u8));
+    random() << 64 | random()
+}
+
+/// A [`Host`], and the commands running on it.  Clones share both, so
+/// everything made from one handle sees the same commands.
+#[derive(Clone)]
+pub struct HostHandle {
+    host: Rc<dyn Host>,
+    commands: Rc<RefCell<CommandRouter>>,
+}
+
+impl std::fmt::Debug for HostHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HostHandle").finish_non_exhaustive()
+    }
+}
+
+/// Zellij in the wasm build; natively, a host that drops every call.
+impl Default for HostHandle {
+    fn default() -> Self {
+        #[cfg(target_arch = "wasm32")]
+        return Self::new(Zellij);
+        #[cfg(not(target_arch = "wasm32"))]
+        Self::new(Absent)
+    }
+}
+
+impl HostHandle {
+    pub fn new(host: impl Host + 'static) -> Self {
+        Self {
+            host: Rc::new(host),
+            commands: Rc::default(),
+        }
+    }
+
+    /// The commands running on this host; see [`command_router`](crate::command_router).
+    pub fn commands(&self) -> RefMut<'_, CommandRouter> {
+        self.commands.borrow_mut()
+    }
+
+    fn call(&self, call: HostCall) {
+        self.host.call(call);
+    }
+
+    /// The time, as far as the plugin is concerned.
+    pub fn now(&self) -> SystemTime {
+        self.host.now()
+    }
+
+    /// 128 random bits.
+    #[cfg(not(feature = "minimal"))]
+    pub fn random(&self) -> u128 {
+        self.host.random()
+    }
+
+    pub fn get_plugin_ids(&self) -> PluginIds {
+        self.host.plugin_ids()
+    }
+
+    pub fn subscribe(&self, event_types: &[EventType]) {
+        self.call(HostCall::Subscribe(event_types.to_vec()));
+    }
+
+    pub fn request_permission(&self, permissions: &[PermissionType]) {
+        self.call(HostCall::RequestPermission(permissions.to_vec()));
+    }
+
+    pub fn set_timeout(&self, secs: f64) {
+        self.call(HostCall::SetTimeout(secs));
+    }
+
+    pub fn run_command_with_env_variables_and_cwd(
+        &self,
+        cmd: &[&str],
+        env: BTreeMap<String, String>,
+        cwd: PathBuf,
+        context: BTreeMap<String, String>,
+    ) {
+        self.call(HostCall::RunCommand {
+            args: cmd.iter().map(|arg| arg.to_string()).collect(),
+            env,
+            cwd,
+            context,
+        });
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn post_message_to(&self, message: PluginMessage) {
+        self.call(HostCall::PostMessageTo(message));
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn post_message_to_plugin(&self, message: PluginMessage) {
+        self.call(HostCall::PostMessageToPlugin(message));
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn pipe_message_to_plugin(&self, message: MessageToPlugin) {
+        self.call(HostCall::PipeMessageToPlugin(message));
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn cli_pipe_output(&self, pipe_name: &str, output: &str) {
+        self.call(HostCall::CliPipeOutput {
+            pipe: pipe_name.to_string(),
+            output: output.to_string(),
+        });
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn unblock_cli_pipe_input(&self, pipe_name: &str) {
+        self.call(HostCall::UnblockCliPipeInput(pipe_name.to_string()));
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn write(&self, bytes: Vec<u8>) {
+        self.call(HostCall::Write(bytes));
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn write_to_pane_id(&self, bytes: Vec<u8>, pane_id: PaneId) {
+        self.call(HostCall::WriteToPaneId(bytes, pane_id));
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn rename_plugin_pane(&self, plugin_pane_id: u32, new_name: impl Into<String>) {
+        self.call(HostCall::RenamePluginPane(plugin_pane_id, new_name.into()));
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn intercept_key_presses(&self) {
+        self.call(HostCall::InterceptKeyPresses);
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn clear_key_presses_intercepts(&self) {
+        self.call(HostCall::ClearKeyPressesIntercepts);
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn copy_to_clipboard(&self, text: impl Into<String>) {
+        self.call(HostCall::CopyToClipboard(text.into()));
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn watch_filesystem(&self) {
+        self.call(HostCall::WatchFilesystem);
+    }
+}
+
+/// What a [`Mock`] has seen.
+#[cfg(test)]
+struct Tape {
+    now: SystemTime,
+    plugin_ids: PluginIds,
+    calls: Vec<HostCall>,
+    /// When the armed timer fires, and what it was armed with.
+    timer: Option<(SystemTime, f64)>,
+    /// Randomness is a counter, so summary IDs come out the same each run.
+    #[cfg(not(feature = "minimal"))]
+    draws: u128,
+}
+
+/// A host for tests.  It keeps every call made to it, and its clock only
+/// moves when told to.  Clones share one tape, so a test keeps a clone to
+/// look at after handing [`handle`](Self::handle) to the plugin.
+#[cfg(test)]
+#[derive(Clone)]
+pub struct Mock(Rc<RefCell<Tape>>);
+
+#[cfg(test)]
+impl Mock {
+    pub fn new(now: SystemTime, plugin_ids: PluginIds) -> Self {
+        Self(Rc::new(RefCell::new(Tape {
+            now,
+            plugin_ids,
+            calls: Vec::new(),
+            timer: None,
+            #[cfg(not(feature = "minimal"))]
+            draws: 0,
+        })))
+    }
+
+    fn tape(&self) -> RefMut<'_, Tape> {
+        self.0.borrow_mut()
+    }
+
+    /// A handle on a clone of this host, with no commands running.
+    pub fn handle(&self) -> HostHandle {
+        HostHandle::new(self.clone())
+    }
+
+    pub fn time(&self) -> SystemTime {
+        self.tape().now
+    }
+
+    /// Move the clock on by `by`.
+    #[cfg(not(feature = "minimal"))]
+    pub fn advance(&self, by: std::time::Duration) {
+        self.tape().now += by;
+    }
+
+    /// Move the clock to `to`, unless it's already past it.
+    #[cfg(not(feature = "minimal"))]
+    pub fn advance_to(&self, to: SystemTime) {
+        let mut tape = self.tape();
+        tape.now = tape.now.max(to);
+    }
+
+    /// When the armed timer fires, and what it was armed with.
+    #[cfg(not(feature = "minimal"))]
+    pub fn timer(&self) -> Option<(SystemTime, f64)> {
+        self.tape().timer
+    }
+
+    /// Disarm the timer, returning what [`timer`](Self::timer) would have.
+    #[cfg(not(feature = "minimal"))]
+    pub fn take_timer(&self) -> Option<(SystemTime, f64)> {
+        self.tape().timer.take()
+    }
+
+    /// Every call since calls were last taken.
+    #[cfg(not(feature = "minimal"))]
+    pub fn calls(&self) -> Vec<HostCall> {
+        self.tape().calls.clone()
+    }
+
+    pub fn take_calls(&self) -> Vec<HostCall> {
+        std::mem::take(&mut self.tape().calls)
+    }
+}
+
+#[cfg(test)]
+impl Host for Mock {
+    fn now(&self) -> SystemTime {
+        self.time()
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    fn random(&self) -> u128 {
+        let mut tape = self.tape();
+        tape.draws += 1;
+        tape.draws
+    }
+
+    fn plugin_ids(&self) -> PluginIds {
+        self.tape().plugin_ids.clone()
+    }
+
+    fn call(&self, call: HostCall) {
+        let mut tape = self.tape();
+        if let HostCall::SetTimeout(secs) = call {
+            // Zellij keeps every timer, but the plugin only arms another
+            // while one is pending if it's due sooner, and re-arms whatever
+            // is still due when one fires, so only the latest matters.
+            tape.timer = Some((tape.now + std::time::Duration::from_secs_f64(secs), secs));
+        }
+        tape.calls.push(call);
+    }
+}
@@ -0,0 +1,170 @@
+//! How shell commands went, from [atuin](https://atuin.sh).
+//!
+//! atuin records each command's exit code, duration, and directory once it
+//! finishes.  When Enter submits a command in a shell pane it's
+//! [tracked](AtuinIO::track), and atuin's database is queried for it (see
+//! [`Platform::atuin_lookup`](crate::shell::Platform::atuin_lookup)) after
+//! [`FIRST_DELAY`], then at doubling intervals until it has finished or
+//! [`MAX_ATTEMPTS`] have been made.  If the query fails, because `sqlite3`
+//! or the database isn't there, atuin is taken to be absent and nothing more
+//! is tracked.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crumbeez_lib::CommandOutcome;
+use tracing::{debug, info};
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+
+pub const CTX_PURPOSE: &str = "crumbeez_atuin_purpose";
+
+/// How long after Enter a command is first looked up.
+pub const FIRST_DELAY: Duration = Duration::from_secs(2);
+
+/// Lookups made for a command before giving up on it, e.g. an editor left
+/// open.  With the delay doubling each time the last is about half an hour
+/// after Enter.
+pub const MAX_ATTEMPTS: u32 = 10;
+
+/// Time allowed for atuin to record a command started just before Enter was
+/// seen.
+const CLOCK_SLACK_MS: u64 = 1000;
+
+#[derive(Debug)]
+enum AtuinCommand {
+    Lookup { id: u64 },
+}
+
+impl ContextTag for AtuinCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::Lookup { id } => format!("Lookup:{}", id),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match split_tag(tag) {
+            ("Lookup", Some(id)) => id.parse().ok().map(|id| Self::Lookup { id }),
+            _ => None,
+        }
+    }
+}
+
+/// A command waiting to be found in atuin's database.
+struct Lookup {
+    id: u64,
+    command: String,
+    since_ms: u64,
+    due_ms: u64,
+    attempts: u32,
+    in_flight: bool,
+}
+
+#[derive(Default)]
+pub struct AtuinIO {
+    host: HostHandle,
+    /// Whether atuin's database could be read, once it's been tried.
+    pub available: Option<bool>,
+    pending: Vec<Lookup>,
+    next_id: u64,
+}
+
+impl AtuinIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Look `command`, submitted at `now_ms`, up once it's had time to
+    /// finish.
+    pub fn track(&mut self, command: &str, now_ms: u64) {
+        if self.available == Some(false) || command.trim().is_empty() {
+            return;
+        }
+        self.next_id += 1;
+        self.pending.push(Lookup {
+            id: self.next_id,
+            command: command.to_string(),
+            since_ms: now_ms.saturating_sub(CLOCK_SLACK_MS),
+            due_ms: now_ms + FIRST_DELAY.as_millis() as u64,
+            attempts: 0,
+            in_flight: false,
+        });
+    }
+
+    /// When the next lookup is due, in milliseconds since the epoch.
+    pub fn due_at(&self) -> Option<u64> {
+        self.pending
+            .iter()
+            .filter(|lookup| !lookup.in_flight)
+            .map(|lookup| lookup.due_ms)
+            .min()
+    }
+
+    /// Run the lookups due by `now_ms`.
+    pub fn poll_if_due(&mut self, now_ms: u64, cwd: PathBuf) {
+        for lookup in &mut self.pending {
+            if lookup.in_flight || lookup.due_ms > now_ms {
+                continue;
+            }
+            lookup.in_flight = true;
+            lookup.attempts += 1;
+            let query = crumbeez_lib::atuin_lookup_query(&lookup.command, lookup.since_ms);
+            let cmd = shell::platform().atuin_lookup(&query);
+            command_router::run(
+                &self.host,
+                &AtuinCommand::Lookup { id: lookup.id },
+                &cmd,
+                cwd.clone(),
+            );
+        }
+    }
+
+    /// The command atuin ran and how it went, if the result is a lookup
+    /// that found it.  A lookup that didn't is tried again later.
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stdout: &[u8],
+        exit_code: Option<i32>,
+        now_ms: u64,
+    ) -> Option<(String, CommandOutcome)> {
+        let AtuinCommand::Lookup { id } = purpose_of::<AtuinCommand>(context)?;
+        debug!(id, ?exit_code, "atuin Lookup result");
+        let index = self.pending.iter().position(|lookup| lookup.id == id)?;
+        if exit_code.is_some_and(|code| code != 0) {
+            if self.available != Some(false) {
+                info!("atuin's database can't be read, not looking commands up");
+            }
+            self.available = Some(false);
+            self.pending.clear();
+            return None;
+        }
+        let found = match exit_code {
+            Some(_) => {
+                self.available = Some(true);
+                crumbeez_lib::parse_atuin_lookup(&String::from_utf8_lossy(stdout))
+            }
+            // Timed out; try again like any other miss.
+            None => None,
+        };
+        let lookup = &mut self.pending[index];
+        if found.is_none() && lookup.attempts < MAX_ATTEMPTS {
+            lookup.in_flight = false;
+            let delay = FIRST_DELAY * 2u32.pow(lookup.attempts);
+            lookup.due_ms = now_ms + delay.as_millis() as u64;
+            return None;
+        }
+        self.pending.remove(index);
+        found
+    }
+}
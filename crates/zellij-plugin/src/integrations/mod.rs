@@ -0,0 +1,5 @@
+//! Other tools on the host whose records fill in what keystrokes can't
+//! show.  Each is optional: a tool that isn't installed is noticed on first
+//! use and left alone from then on.
+
+pub mod atuin;
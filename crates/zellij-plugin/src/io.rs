@@ -0,0 +1,851 @@
+//! The IO modules, and routing the results of the commands they run.
+//!
+//! Each module tags the commands it runs with its own context key, and
+//! [`COMMAND_HANDLERS`] hands each result back to the module by that key.
+//! The [`Io`] owns the modules and the requests still waiting on a result.
+//! What a result means for the rest of the plugin comes back as an
+//! [`IoEvent`] for [`State`](crate::State) to pass on.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tracing::{debug, info, warn};
+use zellij_tile::prelude::*;
+
+use crumbeez_lib::{
+    CommandOutcome, EventLog, Heartbeat, HistoryFormat, KeystrokeEvent, ProjectConfig,
+    SubmoduleLink, SummaryLink, SummaryStore, SuperprojectRouting, SuspendReason,
+    HISTORY_IMPORT_DAYS,
+};
+
+use crate::command_router::CommandOutput;
+use crate::config_io::{self, ConfigIO};
+use crate::context::{requested_tokens, CONTEXT_PIPE};
+use crate::event_log_io::{self, EventLogIO};
+use crate::heartbeat::{self, HeartbeatIO};
+use crate::history::{self, HistoryIO};
+use crate::host::HostHandle;
+use crate::integrations::atuin::{self, AtuinIO};
+use crate::io_probe::{self, IoProbe};
+use crate::logging::{self, LogFileIO};
+use crate::long_text::{self, LongTextIO};
+use crate::power::{self, PowerIO};
+use crate::project_name::{self, ProjectNameIO};
+use crate::pull_request::{self, PullRequestIO};
+use crate::rollup::{self, RollupIO};
+use crate::root_discovery::RootDiscovery;
+use crate::session_context::{self, SessionContextIO};
+use crate::snapshot::{self, SnapshotIO};
+use crate::standup::{self, StandupIO};
+use crate::summaries::{
+    self, issue_report, search_report, Loaded, SummariesIO, ISSUE_PIPE, SEARCH_PIPE,
+};
+use crate::summary_worker::FinishedSummary;
+use crate::viewer;
+use crate::MS_PER_DAY;
+
+/// The IO modules, each running the commands for its own files.
+pub struct Io {
+    host: HostHandle,
+    pub probe: IoProbe,
+    pub event_log: EventLogIO,
+    pub config: ConfigIO,
+    pub log_file: LogFileIO,
+    pub standup: StandupIO,
+    pub pull_request: PullRequestIO,
+    pub rollup: RollupIO,
+    pub snapshot: SnapshotIO,
+    pub history: HistoryIO,
+    pub atuin: AtuinIO,
+    pub long_text: LongTextIO,
+    pub power: PowerIO,
+    pub heartbeat: HeartbeatIO,
+    pub project_name: ProjectNameIO,
+    pub session_context: SessionContextIO,
+    pub summaries: SummariesIO,
+    /// `crumbeez::search` queries waiting for every day's summaries to be
+    /// read, with the CLI pipes to answer them on.
+    pending_searches: Vec<(String, String)>,
+    /// `crumbeez::issue` lookups waiting for the issue index to be read,
+    /// with the CLI pipes to answer them on.
+    pending_issues: Vec<(String, String)>,
+}
+
+/// What the rest of the plugin lends the IO modules while they handle a
+/// result: the log and config to read results into, and where things are.
+pub struct Shared<'a> {
+    pub event_log: &'a mut EventLog,
+    pub config: &'a mut ProjectConfig,
+    /// The project's data dir, once discovery is done.
+    pub dir: Option<&'a Path>,
+    /// Where commands run.
+    pub cwd: &'a Path,
+    pub now_ms: u64,
+}
+
+/// What a command's result means outside the IO module that ran it.
+#[derive(Debug)]
+pub enum IoEvent {
+    /// Nothing more; whether the pane needs redrawing.
+    Done(bool),
+    /// The plugin's own log file was written.
+    LogFileWritten,
+    /// The event log was read or written.
+    EventLog {
+        /// It was read afresh, replacing the log in memory.
+        reloaded: bool,
+        /// A save finished or failed.
+        saved: bool,
+        /// Saves just started failing persistently.
+        alerting: bool,
+    },
+    /// The project config was read.
+    ConfigLoaded,
+    /// The IO probe ran a check; `finished` once it's found how to read
+    /// and write.
+    Probed { finished: bool },
+    /// The power source was checked.
+    PowerChecked(bool),
+    /// Today's summaries, read back.
+    Summaries(SummaryStore),
+    /// Every day's summaries, read for the browser.
+    History(SummaryStore),
+    /// Something to log, e.g. a snapshot that was saved.
+    Log(KeystrokeEvent),
+    /// atuin saw a command finish.
+    CommandFinished(String, CommandOutcome),
+    /// The session's context was captured.
+    SessionContext,
+    /// Shell history was put in front of the log.
+    Backfilled,
+}
+
+type CommandHandler = fn(&mut Io, &CommandOutput, &mut Shared) -> IoEvent;
+
+/// Result handlers by context key; each IO module tags its commands with
+/// its own key.  Root discovery's are the [`State`](crate::State)'s own.
+const COMMAND_HANDLERS: &[(&str, CommandHandler)] = &[
+    (logging::CTX_PURPOSE, Io::handle_log_file_result),
+    (event_log_io::CTX_PURPOSE, Io::handle_event_log_result),
+    (config_io::CTX_PURPOSE, Io::handle_config_result),
+    (io_probe::CTX_PURPOSE, Io::handle_io_probe_result),
+    (standup::CTX_PURPOSE, Io::handle_standup_result),
+    (pull_request::CTX_PURPOSE, Io::handle_pull_request_result),
+    (rollup::CTX_PURPOSE, Io::handle_rollup_result),
+    (snapshot::CTX_PURPOSE, Io::handle_snapshot_result),
+    (history::CTX_PURPOSE, Io::handle_history_result),
+    (atuin::CTX_PURPOSE, Io::handle_atuin_result),
+    (long_text::CTX_PURPOSE, Io::handle_long_text_result),
+    (power::CTX_PURPOSE, Io::handle_power_result),
+    (heartbeat::CTX_PURPOSE, Io::handle_heartbeat_result),
+    (project_name::CTX_PURPOSE, Io::handle_project_name_result),
+    (
+        session_context::CTX_PURPOSE,
+        Io::handle_session_context_result,
+    ),
+    (summaries::CTX_PURPOSE, Io::handle_summaries_result),
+];
+
+impl Io {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            probe: IoProbe::new(host.clone()),
+            event_log: EventLogIO::new(host.clone()),
+            config: ConfigIO::new(host.clone()),
+            log_file: LogFileIO::new(host.clone()),
+            standup: StandupIO::new(host.clone()),
+            pull_request: PullRequestIO::new(host.clone()),
+            rollup: RollupIO::new(host.clone()),
+            snapshot: SnapshotIO::new(host.clone()),
+            history: HistoryIO::new(host.clone()),
+            atuin: AtuinIO::new(host.clone()),
+            long_text: LongTextIO::new(host.clone()),
+            power: PowerIO::new(host.clone()),
+            heartbeat: HeartbeatIO::new(host.clone()),
+            project_name: ProjectNameIO::new(host.clone()),
+            session_context: SessionContextIO::new(host.clone()),
+            summaries: SummariesIO::new(host.clone()),
+            host,
+            pending_searches: Vec::new(),
+            pending_issues: Vec::new(),
+        }
+    }
+
+    /// The project's name, once it's known.
+    pub fn project_name(&self) -> Option<&str> {
+        self.project_name.name.as_ref().map(|p| p.name.as_str())
+    }
+
+    /// Write the full text of a [`KeystrokeEvent::LongText`] to its `file`
+    /// in the data dir `dir`.
+    pub fn save_long_text(&mut self, dir: Option<&Path>, file: String, text: &str, cwd: &Path) {
+        let Some(dir) = dir else {
+            self.long_text.status = Some(format!("{} not saved before setup finishes", file));
+            return;
+        };
+        self.long_text.write(dir, file, text, cwd.to_path_buf());
+    }
+
+    /// Load the event log from the data dir `dir` once the IO probe has
+    /// found how to read it, unless it's loaded already.
+    pub fn load_event_log_when_ready(&mut self, dir: Option<&Path>, cwd: &Path) {
+        if !self.probe.is_done() || self.event_log.log_path().is_some() {
+            return;
+        }
+        let Some(dir) = dir else {
+            return;
+        };
+        let log_path = crumbeez_lib::event_log_path_from_crumbeez_dir(dir);
+        debug!(path = ?log_path, "Log path");
+        self.event_log.set_log_path(log_path);
+        self.event_log.load(cwd.to_path_buf());
+    }
+
+    /// After rediscovery, write the log in memory to the data dir found,
+    /// replacing any log there.  Does nothing before the log is first
+    /// loaded.
+    pub fn move_event_log(&mut self, dir: Option<&Path>, event_log: &EventLog, cwd: &Path) {
+        if self.event_log.log_path().is_none() {
+            return;
+        }
+        let Some(dir) = dir else {
+            return;
+        };
+        self.event_log
+            .relocate(crumbeez_lib::event_log_path_from_crumbeez_dir(dir));
+        self.event_log.save(cwd.to_path_buf(), event_log);
+    }
+
+    pub fn handle_command_output(
+        &mut self,
+        output: &CommandOutput,
+        shared: &mut Shared,
+    ) -> IoEvent {
+        match COMMAND_HANDLERS
+            .iter()
+            .find(|(key, _)| output.context.contains_key(*key))
+        {
+            Some((_, handler)) => handler(self, output, shared),
+            None => {
+                warn!(context = ?output.context, "No handler for command result");
+                IoEvent::Done(false)
+            }
+        }
+    }
+
+    fn handle_log_file_result(&mut self, output: &CommandOutput, _: &mut Shared) -> IoEvent {
+        self.log_file
+            .handle_result(&output.context, &output.stderr, output.exit_code);
+        IoEvent::LogFileWritten
+    }
+
+    fn handle_event_log_result(&mut self, output: &CommandOutput, shared: &mut Shared) -> IoEvent {
+        let was_alerting = self.event_log.alerting();
+        let writes = (self.event_log.last_saved, self.event_log.write_failures);
+        self.event_log.handle_result(
+            &output.context,
+            &output.stdout,
+            output.exit_code,
+            shared.event_log,
+        );
+        IoEvent::EventLog {
+            reloaded: std::mem::take(&mut self.event_log.reloaded),
+            saved: writes != (self.event_log.last_saved, self.event_log.write_failures),
+            alerting: !was_alerting && self.event_log.alerting(),
+        }
+    }
+
+    /// Replace the heartbeat file with the plugin's current state.
+    pub fn write_heartbeat(
+        &mut self,
+        suspended: Option<SuspendReason>,
+        next_seq: u64,
+        now_ms: u64,
+        cwd: &Path,
+    ) {
+        let heartbeat = Heartbeat {
+            written_ms: now_ms,
+            last_flush_ms: self.event_log.last_saved.map(|saved| {
+                saved
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64
+            }),
+            suspended,
+            errors: self.event_log.write_failures,
+            next_seq,
+        };
+        self.heartbeat.write(heartbeat, cwd.to_path_buf());
+    }
+
+    fn handle_heartbeat_result(&mut self, output: &CommandOutput, shared: &mut Shared) -> IoEvent {
+        IoEvent::Done(self.heartbeat.handle_result(
+            &output.context,
+            &output.stderr,
+            output.exit_code,
+            shared.cwd.to_path_buf(),
+        ))
+    }
+
+    fn handle_project_name_result(
+        &mut self,
+        output: &CommandOutput,
+        shared: &mut Shared,
+    ) -> IoEvent {
+        IoEvent::Done(self.project_name.handle_result(
+            &output.context,
+            &output.stdout,
+            output.exit_code,
+            shared.cwd.to_path_buf(),
+        ))
+    }
+
+    fn handle_summaries_result(&mut self, output: &CommandOutput, shared: &mut Shared) -> IoEvent {
+        match self
+            .summaries
+            .handle_result(&output.context, &output.stdout, output.exit_code)
+        {
+            Some(Loaded::Today(loaded)) => IoEvent::Summaries(loaded),
+            Some(Loaded::History(history)) => {
+                for (pipe_id, query) in std::mem::take(&mut self.pending_searches) {
+                    self.host
+                        .cli_pipe_output(&pipe_id, &search_report(&history, &query));
+                    self.host.unblock_cli_pipe_input(SEARCH_PIPE);
+                }
+                self.draft_pull_requests(&history, shared);
+                IoEvent::History(history)
+            }
+            Some(Loaded::Issues(index)) => {
+                for (pipe_id, issue) in std::mem::take(&mut self.pending_issues) {
+                    self.host
+                        .cli_pipe_output(&pipe_id, &issue_report(&index, &issue));
+                    self.host.unblock_cli_pipe_input(ISSUE_PIPE);
+                }
+                IoEvent::Done(false)
+            }
+            None => IoEvent::Done(false),
+        }
+    }
+
+    fn handle_config_result(&mut self, output: &CommandOutput, shared: &mut Shared) -> IoEvent {
+        self.config.handle_result(
+            &output.context,
+            &output.stdout,
+            output.exit_code,
+            shared.config,
+        );
+        IoEvent::ConfigLoaded
+    }
+
+    fn handle_standup_result(&mut self, output: &CommandOutput, _: &mut Shared) -> IoEvent {
+        IoEvent::Done(
+            self.standup
+                .handle_result(&output.context, &output.stderr, output.exit_code),
+        )
+    }
+
+    fn handle_pull_request_result(
+        &mut self,
+        output: &CommandOutput,
+        shared: &mut Shared,
+    ) -> IoEvent {
+        IoEvent::Done(self.pull_request.handle_result(
+            &output.context,
+            &output.stdout,
+            &output.stderr,
+            output.exit_code,
+            shared.cwd.to_path_buf(),
+        ))
+    }
+
+    fn handle_rollup_result(&mut self, output: &CommandOutput, _: &mut Shared) -> IoEvent {
+        IoEvent::Done(self.rollup.handle_result(
+            &output.context,
+            &output.stdout,
+            &output.stderr,
+            output.exit_code,
+        ))
+    }
+
+    fn handle_snapshot_result(&mut self, output: &CommandOutput, _: &mut Shared) -> IoEvent {
+        match self
+            .snapshot
+            .handle_result(&output.context, &output.stderr, output.exit_code)
+        {
+            Some(file) => IoEvent::Log(KeystrokeEvent::SnapshotTaken(file)),
+            None => IoEvent::Done(true),
+        }
+    }
+
+    fn handle_history_result(&mut self, output: &CommandOutput, shared: &mut Shared) -> IoEvent {
+        let Some((format, contents)) = self.history.handle_result(
+            &output.context,
+            &output.stdout,
+            &output.stderr,
+            output.exit_code,
+        ) else {
+            return IoEvent::Done(true);
+        };
+        if self.backfill_history(format, &contents, shared) {
+            IoEvent::Backfilled
+        } else {
+            IoEvent::Done(true)
+        }
+    }
+
+    fn handle_atuin_result(&mut self, output: &CommandOutput, shared: &mut Shared) -> IoEvent {
+        match self.atuin.handle_result(
+            &output.context,
+            &output.stdout,
+            output.exit_code,
+            shared.now_ms,
+        ) {
+            Some((command, outcome)) => IoEvent::CommandFinished(command, outcome),
+            None => IoEvent::Done(false),
+        }
+    }
+
+    fn handle_session_context_result(&mut self, output: &CommandOutput, _: &mut Shared) -> IoEvent {
+        self.session_context
+            .handle_result(&output.context, &output.stdout, output.exit_code);
+        IoEvent::SessionContext
+    }
+
+    /// The session context captured and not yet logged, once the event log
+    /// has loaded to log it in.
+    pub fn take_session_context(&mut self) -> Option<BTreeMap<String, String>> {
+        if !self.event_log.loaded {
+            return None;
+        }
+        self.session_context.pending.take()
+    }
+
+    fn handle_long_text_result(&mut self, output: &CommandOutput, _: &mut Shared) -> IoEvent {
+        IoEvent::Done(self.long_text.handle_result(
+            &output.context,
+            &output.stderr,
+            output.exit_code,
+        ))
+    }
+
+    fn handle_power_result(&mut self, output: &CommandOutput, _: &mut Shared) -> IoEvent {
+        IoEvent::PowerChecked(self.power.handle_result(
+            &output.context,
+            &output.stdout,
+            output.exit_code,
+        ))
+    }
+
+    fn handle_io_probe_result(&mut self, output: &CommandOutput, _: &mut Shared) -> IoEvent {
+        IoEvent::Probed {
+            finished: self.probe.handle_result(
+                &output.context,
+                &output.stdout,
+                &output.stderr,
+                output.exit_code,
+            ),
+        }
+    }
+
+    /// In viewer mode, reload whichever of the event log and today's
+    /// summaries changed.
+    pub fn refresh_viewer(
+        &mut self,
+        changes: viewer::Changes,
+        dir: Option<&Path>,
+        now_ms: u64,
+        cwd: &Path,
+    ) {
+        let Some(dir) = dir else {
+            return;
+        };
+        if changes.summaries {
+            let today = crumbeez_lib::format_ymd(now_ms);
+            self.summaries.load(dir, &today, cwd.to_path_buf());
+        }
+        if changes.event_log && self.event_log.log_path().is_some() {
+            self.event_log.load(cwd.to_path_buf());
+        }
+    }
+
+    /// Append a summary back from the worker to its day's summary file in
+    /// the data dir, with its link, and leave what the `superproject`
+    /// setting asks for of it in the superproject's.  Returns whether it
+    /// was written.
+    pub fn file_summary(
+        &mut self,
+        summary: &FinishedSummary,
+        discovery: &RootDiscovery,
+        routing: SuperprojectRouting,
+    ) -> bool {
+        let Some(dir) = discovery.data_dir() else {
+            return false;
+        };
+        self.rollup
+            .append_daily(dir, &summary.day, &summary.markdown);
+        self.rollup.append_link(dir, &summary.link);
+        if !summary.issues.is_empty() {
+            self.rollup.append_issues(dir, &summary.issues);
+        }
+        self.route_to_superproject(summary, discovery, routing);
+        true
+    }
+
+    /// Leave what `routing` asks for of a summary made in a submodule in
+    /// the superproject's data dir.  Its events stay in the submodule's log
+    /// only.
+    fn route_to_superproject(
+        &mut self,
+        summary: &FinishedSummary,
+        discovery: &RootDiscovery,
+        routing: SuperprojectRouting,
+    ) {
+        let crumbeez_lib::DiscoveryPhase::Ready { ref dirs } = discovery.phase else {
+            return;
+        };
+        let (Some(parent), Some(root), Some(parent_dir)) = (
+            discovery.parent_git_root.as_ref(),
+            discovery.roots.first(),
+            dirs.get(1),
+        ) else {
+            return;
+        };
+        if discovery.roots.get(1) != Some(parent) || routing == SuperprojectRouting::None {
+            return;
+        }
+        let Some(link) = SummaryLink::parse_all(&summary.link).pop() else {
+            return;
+        };
+        let submodule = root.strip_prefix(parent).unwrap_or(root);
+        let link = SubmoduleLink::new(submodule.display().to_string(), &link);
+        self.rollup
+            .append_submodule_link(parent_dir, &link.to_kdl());
+        if routing == SuperprojectRouting::Summary {
+            self.rollup
+                .append_daily(parent_dir, &summary.day, &summary.markdown);
+        }
+    }
+
+    /// Note the day's totals so far in the cross-project index.
+    #[cfg(feature = "analytics")]
+    pub fn record_day(&mut self, discovery: &RootDiscovery, event_log: &EventLog, now_ms: u64) {
+        let (Some(root), Some(dir)) = (discovery.roots.first(), discovery.data_dir()) else {
+            return;
+        };
+        let mut record =
+            crumbeez_lib::DayRecord::from_entries(root, dir, event_log.entries(), now_ms);
+        record.name = self.project_name().map(str::to_string);
+        self.rollup.record_day(record);
+    }
+
+    /// Write a report on the last day's activity to the summaries dir.
+    #[cfg(feature = "analytics")]
+    pub fn write_standup(
+        &mut self,
+        dir: Option<&Path>,
+        event_log: &EventLog,
+        config: &ProjectConfig,
+        now_ms: u64,
+        cwd: &Path,
+    ) {
+        let Some(dir) = dir else {
+            self.standup.status = Some("no standup report before setup finishes".to_string());
+            return;
+        };
+        let report = crumbeez_lib::Standup::from_entries(event_log.entries(), now_ms);
+        let path = crumbeez_lib::summaries_dir_from_crumbeez_dir(dir).join(report.file_name());
+        self.standup.write(
+            path,
+            &config.redact_outbound(&report.to_markdown()),
+            cwd.to_path_buf(),
+        );
+    }
+
+    #[cfg(not(feature = "analytics"))]
+    pub fn write_standup(
+        &mut self,
+        _dir: Option<&Path>,
+        _event_log: &EventLog,
+        _config: &ProjectConfig,
+        _now_ms: u64,
+        _cwd: &Path,
+    ) {
+        warn!("Standup reports need the analytics feature");
+        self.standup.status = Some("standup reports need the analytics feature".to_string());
+    }
+
+    /// Roll up the week containing `payload` (`YYYY-MM-DD`), or the one
+    /// containing `now_ms`.
+    #[cfg(feature = "analytics")]
+    pub fn write_rollup(&mut self, payload: Option<&str>, now_ms: u64) {
+        let day_ms = match payload.map(str::trim).filter(|p| !p.is_empty()) {
+            Some(date) => match crumbeez_lib::day_start_ms(date) {
+                Some(ms) => ms,
+                None => {
+                    self.rollup.status = Some(format!("not a YYYY-MM-DD date: {}", date));
+                    return;
+                }
+            },
+            None => now_ms,
+        };
+        self.rollup.generate(day_ms);
+    }
+
+    #[cfg(not(feature = "analytics"))]
+    pub fn write_rollup(&mut self, _payload: Option<&str>, _now_ms: u64) {
+        warn!("Weekly rollups need the analytics feature");
+        self.rollup.status = Some("weekly rollups need the analytics feature".to_string());
+    }
+
+    /// Read the summary index for the calendar view.
+    #[cfg(feature = "analytics")]
+    pub fn load_calendar(&mut self) {
+        self.rollup.load_calendar();
+    }
+
+    #[cfg(not(feature = "analytics"))]
+    pub fn load_calendar(&mut self) {
+        warn!("The calendar needs the analytics feature");
+        self.rollup.status = Some("the calendar needs the analytics feature".to_string());
+    }
+
+    /// Answer a `crumbeez::context` request with recent activity from
+    /// `event_log`, through the CLI pipe or as a message back to the plugin
+    /// that sent it.
+    pub fn send_context(
+        &self,
+        request: &PipeMessage,
+        event_log: &EventLog,
+        config: &ProjectConfig,
+    ) {
+        let reply = match requested_tokens(request.payload.as_deref(), &request.args) {
+            Ok(tokens) => {
+                let entries: Vec<_> = event_log.entries().cloned().collect();
+                let blob = crumbeez_lib::context_blob(&entries, tokens);
+                debug!(tokens, len = blob.len(), "Sending context");
+                config.redact_outbound(&blob).into_owned()
+            }
+            Err(reason) => {
+                warn!(%reason, "Bad context request");
+                format!("error: {}\n", reason)
+            }
+        };
+        match &request.source {
+            PipeSource::Cli(pipe_id) => self.host.cli_pipe_output(pipe_id, &reply),
+            PipeSource::Plugin(plugin_id) => self.host.pipe_message_to_plugin(
+                MessageToPlugin::new(CONTEXT_PIPE)
+                    .with_destination_plugin_id(*plugin_id)
+                    .with_payload(reply),
+            ),
+            PipeSource::Keybind => warn!("Context requested from a keybind, nowhere to send it"),
+        }
+    }
+
+    /// Save the focused pane's screen to the scratchpad, unless it's
+    /// `excluded`.  It's logged once it's written.
+    pub fn take_snapshot(
+        &mut self,
+        excluded: bool,
+        session_name: Option<&str>,
+        dir: Option<&Path>,
+        now_ms: u64,
+        cwd: &Path,
+    ) {
+        if excluded {
+            self.snapshot.status = Some("the focused pane is excluded".to_string());
+            return;
+        }
+        let Some(dir) = dir else {
+            self.snapshot.status = Some("no snapshots before setup finishes".to_string());
+            return;
+        };
+        self.snapshot.take(
+            session_name,
+            dir,
+            crumbeez_lib::snapshot_file(now_ms),
+            cwd.to_path_buf(),
+        );
+    }
+
+    /// Read a shell's history, per a [`HISTORY_PIPE`] payload, to backfill
+    /// `event_log` with.
+    ///
+    /// [`HISTORY_PIPE`]: crate::history::HISTORY_PIPE
+    pub fn import_history(
+        &mut self,
+        payload: Option<&str>,
+        dir: Option<&Path>,
+        event_log: &EventLog,
+        cwd: &Path,
+    ) {
+        if dir.is_none() {
+            self.history.status = Some("no history import before setup finishes".to_string());
+            return;
+        }
+        if !event_log.is_fresh() {
+            self.history.status =
+                Some("history only goes into a log with nothing summarized yet".to_string());
+            return;
+        }
+        match history::parse_request(payload.unwrap_or_default()) {
+            Ok((format, path)) => self.history.read(format, path, cwd.to_path_buf()),
+            Err(problem) => self.history.status = Some(problem),
+        }
+    }
+
+    /// Put the commands from the last [`HISTORY_IMPORT_DAYS`] of a shell's
+    /// history in front of the log, within its retention.  Returns whether
+    /// any were.
+    fn backfill_history(
+        &mut self,
+        format: HistoryFormat,
+        contents: &[u8],
+        shared: &mut Shared,
+    ) -> bool {
+        let now = shared.now_ms;
+        let retention = &shared.config.retention;
+        let days = retention
+            .max_age_days
+            .map_or(HISTORY_IMPORT_DAYS, |max| max.min(HISTORY_IMPORT_DAYS));
+        let entries = crumbeez_lib::history_entries(
+            format,
+            contents,
+            now.saturating_sub(days * MS_PER_DAY),
+            now,
+            retention.max_events,
+        );
+        let found = entries.len();
+        let added = shared.event_log.backfill(entries);
+        info!(
+            shell = format.name(),
+            found, added, "Backfilled shell history"
+        );
+        self.history.status = Some(if added == 0 && found > 0 {
+            format!(
+                "no room before the log's first entry for {} history",
+                format.name()
+            )
+        } else {
+            format!("imported {} commands from {} history", added, format.name())
+        });
+        if added == 0 {
+            return false;
+        }
+        self.event_log
+            .rewrite(shared.cwd.to_path_buf(), shared.event_log);
+        true
+    }
+
+    /// Read every day's summaries to answer a `crumbeez::search` for
+    /// `query` on the CLI pipe `pipe_id`.  Returns whether the answer is
+    /// pending, and the pipe should stay blocked until it's sent.
+    pub fn search_summaries(
+        &mut self,
+        pipe_id: &str,
+        query: Option<&str>,
+        dir: Option<&Path>,
+        cwd: &Path,
+    ) -> bool {
+        let Some(query) = query.map(str::trim).filter(|query| !query.is_empty()) else {
+            self.host.cli_pipe_output(
+                pipe_id,
+                "usage: zellij pipe --name crumbeez::search -- <words>\n",
+            );
+            return false;
+        };
+        let Some(dir) = dir else {
+            self.host
+                .cli_pipe_output(pipe_id, "no summaries to search before setup finishes\n");
+            return false;
+        };
+        debug!(%query, "Searching summaries");
+        self.summaries.load_history(dir, cwd.to_path_buf());
+        self.pending_searches
+            .push((pipe_id.to_string(), query.to_string()));
+        true
+    }
+
+    /// Read every day's summaries to draft a pull request description for
+    /// the branch named in `payload`, or the one `event_log` last saw
+    /// checked out, answering on the CLI pipe `pipe_id` if there is one.
+    /// Returns whether the answer is pending, and the pipe should stay
+    /// blocked until it's sent.
+    #[allow(clippy::too_many_arguments)]
+    pub fn request_pull_request(
+        &mut self,
+        pipe_id: Option<&str>,
+        payload: Option<&str>,
+        create: bool,
+        dir: Option<&Path>,
+        event_log: &EventLog,
+        cwd: &Path,
+    ) -> bool {
+        let reply = |text: &str| {
+            if let Some(pipe_id) = pipe_id {
+                self.host.cli_pipe_output(pipe_id, &format!("{}\n", text));
+            }
+        };
+        let Some(dir) = dir else {
+            reply("no pull request draft before setup finishes");
+            return false;
+        };
+        let branch = payload
+            .map(str::trim)
+            .filter(|branch| !branch.is_empty())
+            .map(str::to_string)
+            .or_else(|| crumbeez_lib::session_branch(event_log.entries()));
+        let Some(branch) = branch else {
+            reply("no git branch seen yet: name one, e.g. zellij pipe --name crumbeez::pr -- main");
+            return false;
+        };
+        self.summaries.load_history(dir, cwd.to_path_buf());
+        self.pull_request
+            .request(branch, pipe_id.map(str::to_string), create);
+        pipe_id.is_some()
+    }
+
+    /// Draft the pull request descriptions asked for from `history`, every
+    /// day's summaries.
+    fn draft_pull_requests(&mut self, history: &SummaryStore, shared: &Shared) {
+        if !self.pull_request.has_pending() {
+            return;
+        }
+        let Some(dir) = shared.dir else {
+            return;
+        };
+        self.pull_request.draft(
+            history,
+            &dir.join(crumbeez_lib::SCRATCH_DIR),
+            shared.cwd.to_path_buf(),
+        );
+    }
+
+    /// Read the issue index to answer a `crumbeez::issue` for `issue` on
+    /// the CLI pipe `pipe_id`.  Returns whether the answer is pending, and
+    /// the pipe should stay blocked until it's sent.
+    pub fn look_up_issue(
+        &mut self,
+        pipe_id: &str,
+        issue: Option<&str>,
+        dir: Option<&Path>,
+        cwd: &Path,
+    ) -> bool {
+        let Some(issue) = issue.map(str::trim).filter(|issue| !issue.is_empty()) else {
+            self.host.cli_pipe_output(
+                pipe_id,
+                "usage: zellij pipe --name crumbeez::issue -- <issue, e.g. PROJ-142>\n",
+            );
+            return false;
+        };
+        let Some(dir) = dir else {
+            self.host
+                .cli_pipe_output(pipe_id, "no issue index to read before setup finishes\n");
+            return false;
+        };
+        debug!(%issue, "Looking up issue");
+        self.summaries.load_issues(dir, cwd.to_path_buf());
+        self.pending_issues
+            .push((pipe_id.to_string(), issue.to_string()));
+        true
+    }
+}
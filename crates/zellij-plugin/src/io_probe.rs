@@ -0,0 +1,171 @@
+//! Picking a binary IO strategy that works on this host.
+//!
+//! The event log is written and read back through shell tools, and minimal
+//! environments (BusyBox, distroless images) either lack those tools or
+//! ship versions with different edge cases.  At startup each
+//! [`IoStrategy`] is tried in order of preference by round-tripping every
+//! byte value through a temp file, and the first one that reproduces the
+//! data exactly is used for the event log.  The results are kept for doctor
+//! mode.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tracing::{error, info, warn};
+
+use crate::command_router::{self, Retry};
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+use crate::shell::{self, IoStrategy, Platform, ReadStrategy, WriteStrategy};
+
+pub const CTX_PURPOSE: &str = "crumbeez_io_probe_purpose";
+
+/// Strategies in order of preference: compact commands first.
+const CANDIDATES: [IoStrategy; 4] = [
+    IoStrategy {
+        write: WriteStrategy::Base64,
+        read: ReadStrategy::Base64,
+    },
+    IoStrategy {
+        write: WriteStrategy::Base64,
+        read: ReadStrategy::Od,
+    },
+    IoStrategy {
+        write: WriteStrategy::PrintfOctal,
+        read: ReadStrategy::Base64,
+    },
+    IoStrategy {
+        write: WriteStrategy::PrintfOctal,
+        read: ReadStrategy::Od,
+    },
+];
+
+#[derive(Debug)]
+struct RoundTrip(IoStrategy);
+
+impl ContextTag for RoundTrip {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        format!("RoundTrip:{}+{}", self.0.write.name(), self.0.read.name())
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        let ("RoundTrip", Some(arg)) = split_tag(tag) else {
+            return None;
+        };
+        let (write, read) = arg.split_once('+')?;
+        Some(Self(IoStrategy {
+            write: WriteStrategy::from_name(write)?,
+            read: ReadStrategy::from_name(read)?,
+        }))
+    }
+
+    fn retry(&self) -> Retry {
+        Retry {
+            timeout: Duration::from_secs(5),
+            attempts: 2,
+        }
+    }
+}
+
+/// How one candidate fared.
+#[derive(Debug, Clone)]
+pub enum ProbeResult {
+    Passed,
+    Failed(String),
+}
+
+#[derive(Debug, Default)]
+pub struct IoProbe {
+    host: HostHandle,
+    /// Candidates tried so far, in order.
+    pub results: Vec<(IoStrategy, ProbeResult)>,
+    /// Whether a strategy has been settled on, or every candidate failed.
+    done: bool,
+    cwd: PathBuf,
+}
+
+impl IoProbe {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Start probing.  Windows hosts use PowerShell's file API, which needs
+    /// no probe.
+    pub fn start(&mut self, cwd: PathBuf) {
+        self.cwd = cwd;
+        if shell::platform() == Platform::Windows {
+            self.done = true;
+            return;
+        }
+        self.try_candidate(0);
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn try_candidate(&mut self, index: usize) {
+        let Some(&io) = CANDIDATES.get(index) else {
+            error!("No binary IO strategy works on this host, keeping the default");
+            self.done = true;
+            return;
+        };
+        let data: Vec<u8> = (0..=u8::MAX).collect();
+        let cmd = Platform::Unix.probe_binary(&data, io);
+        command_router::run(&self.host, &RoundTrip(io), &cmd, self.cwd.clone());
+    }
+
+    /// Handle a probe result.  Returns true once the probe has finished with
+    /// this result.
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stdout: &[u8],
+        stderr: &[u8],
+        exit_code: Option<i32>,
+    ) -> bool {
+        let Some(RoundTrip(io)) = purpose_of::<RoundTrip>(context) else {
+            return false;
+        };
+        let expected: Vec<u8> = (0..=u8::MAX).collect();
+        let result = match exit_code {
+            Some(0) => match io.read.decode(stdout) {
+                Some(data) if data == expected => ProbeResult::Passed,
+                Some(data) => ProbeResult::Failed(format!(
+                    "data corrupted ({} bytes sent, {} read back)",
+                    expected.len(),
+                    data.len()
+                )),
+                None => ProbeResult::Failed("output could not be decoded".to_string()),
+            },
+            Some(code) => ProbeResult::Failed(format!(
+                "exit {}: {}",
+                code,
+                String::from_utf8_lossy(stderr).trim()
+            )),
+            None => ProbeResult::Failed("timed out".to_string()),
+        };
+
+        let passed = matches!(result, ProbeResult::Passed);
+        self.results.push((io, result));
+        if passed {
+            info!(%io, "Binary IO strategy selected");
+            shell::set_io_strategy(io);
+            self.done = true;
+            return true;
+        }
+        warn!(%io, "Binary IO strategy failed its probe");
+        let next = CANDIDATES
+            .iter()
+            .position(|c| *c == io)
+            .map_or(CANDIDATES.len(), |i| i + 1);
+        self.try_candidate(next);
+        self.done
+    }
+}
@@ -3,10 +3,12 @@
 //! Two public functions are provided:
 //!
 //! - [`classify`] — converts a `KeyWithModifier` into a semantic
-//!   [`KeystrokeEvent`] understood by `crumbeez-lib`.
-//! - [`key_to_bytes`] — converts a `KeyWithModifier` back into the raw VT/ANSI
+//!   [`KeystrokeEvent`] understood by `crumbeez-lib`.  [`plain_char`] is an
+//!   allocation-free shortcut for the common case of a typed character.
+//! - [`write_key_bytes`] — converts a `KeyWithModifier` back into the raw VT/ANSI
 //!   byte sequence that should be written to a terminal's stdin so the
-//!   keystroke reaches the application running in the pane.
+//!   keystroke reaches the application running in the pane.  The sequences
+//!   themselves come from `crumbeez-lib`'s encoder, which is tested there.
 //!
 //! Classification rules (in precedence order):
 //!
@@ -32,16 +34,27 @@
 use zellij_tile::prelude::{BareKey, KeyModifier, KeyWithModifier};
 
 use crumbeez_lib::{
-    EditControlEvent, KeystrokeEvent, NavDirection, NavigationEvent, ShortcutEvent, ShortcutKey,
-    SystemKeyEvent,
+    CursorKeys, EditControlEvent, KeystrokeEvent, NavDirection, NavigationEvent, ShortcutEvent,
+    ShortcutKey, SystemKeyEvent, VtModifiers,
 };
 
+/// Longest sequence [`write_key_bytes`] produces.
+pub use crumbeez_lib::MAX_KEY_SEQUENCE_LEN;
+
 /// Classify a single [`KeyWithModifier`] into a [`KeystrokeEvent`].
 pub fn classify(key: &KeyWithModifier) -> KeystrokeEvent {
-    let ctrl = key.key_modifiers.contains(&KeyModifier::Ctrl);
-    let alt = key.key_modifiers.contains(&KeyModifier::Alt);
-    let shift = key.key_modifiers.contains(&KeyModifier::Shift);
-    let super_key = key.key_modifiers.contains(&KeyModifier::Super);
+    // Fast path: the overwhelming majority of keys are printable
+    // characters, which skip the modifier lookups entirely.
+    if let Some(c) = plain_char(key) {
+        return KeystrokeEvent::TextTyped(c.to_string());
+    }
+
+    let Modifiers {
+        ctrl,
+        alt,
+        shift,
+        super_key,
+    } = Modifiers::of(key);
 
     let is_chord = ctrl || alt || super_key;
 
@@ -121,8 +134,56 @@ pub fn classify(key: &KeyWithModifier) -> KeystrokeEvent {
     })
 }
 
+/// Return the character for an unmodified (or Shift-only) printable key.
+///
+/// This is the common case for every keystroke; callers check it first so
+/// plain typing never builds a [`KeystrokeEvent`] (and its `String`).  For
+/// any key where this returns `Some(c)`, [`classify`] would return
+/// `TextTyped(c.to_string())`.
+pub fn plain_char(key: &KeyWithModifier) -> Option<char> {
+    let BareKey::Char(c) = key.bare_key else {
+        return None;
+    };
+    let shift_only = key.key_modifiers.iter().all(|m| *m == KeyModifier::Shift);
+    shift_only.then_some(c)
+}
+
+/// Whether `key` could be part of a paste: a character with no modifier
+/// but Shift, or a bare Enter or Tab.
+pub fn pastable(key: &KeyWithModifier) -> bool {
+    match key.bare_key {
+        BareKey::Char(_) => plain_char(key).is_some(),
+        BareKey::Enter | BareKey::Tab => key.key_modifiers.is_empty(),
+        _ => false,
+    }
+}
+
 // ── Helpers ──────────────────────────────────────────────────────
 
+/// The modifier state of a key, read in a single pass over the modifier set.
+#[derive(Default)]
+struct Modifiers {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    super_key: bool,
+}
+
+impl Modifiers {
+    fn of(key: &KeyWithModifier) -> Self {
+        let mut mods = Self::default();
+        for modifier in &key.key_modifiers {
+            match modifier {
+                KeyModifier::Ctrl => mods.ctrl = true,
+                KeyModifier::Alt => mods.alt = true,
+                KeyModifier::Shift => mods.shift = true,
+                KeyModifier::Super => mods.super_key = true,
+            }
+        }
+        mods
+    }
+}
+
 fn nav_direction(bare: &BareKey) -> Option<NavDirection> {
     match bare {
         BareKey::Left => Some(NavDirection::Left),
@@ -137,266 +198,42 @@ fn nav_direction(bare: &BareKey) -> Option<NavDirection> {
     }
 }
 
-// ── key_to_bytes ─────────────────────────────────────────────────
+// ── write_key_bytes ──────────────────────────────────────────────
 
-/// Encode a [`KeyWithModifier`] as the VT/ANSI byte sequence that a terminal
-/// application expects to receive on its stdin.
-///
-/// This is the inverse of what a terminal emulator does when it translates a
-/// physical keypress into an escape sequence.  We need it because
-/// `intercept_key_presses()` redirects input *away* from the focused pane; we
-/// must write the bytes back ourselves so the user's input is not swallowed.
+/// Append the VT/ANSI byte sequence that a terminal application expects to
+/// receive on its stdin for a [`KeyWithModifier`] to `out`.
 ///
-/// Reference: XTerm Control Sequences, ECMA-48, and the Kitty keyboard
-/// protocol (for the subset Zellij exposes).
-pub fn key_to_bytes(key: &KeyWithModifier) -> Vec<u8> {
-    let ctrl = key.key_modifiers.contains(&KeyModifier::Ctrl);
-    let alt = key.key_modifiers.contains(&KeyModifier::Alt);
-    let shift = key.key_modifiers.contains(&KeyModifier::Shift);
-
-    // Alt prefix: ESC byte prepended to whatever the bare key produces.
-    // We compute the inner sequence first and then wrap if Alt is set.
-    let inner = bare_key_to_bytes(&key.bare_key, ctrl, shift);
-
-    if alt && !inner.is_empty() {
-        let mut out = Vec::with_capacity(1 + inner.len());
-        out.push(0x1b); // ESC
-        out.extend_from_slice(&inner);
-        out
-    } else {
-        inner
-    }
+/// We need it because `intercept_key_presses()` redirects input *away* from
+/// the focused pane; we must write the bytes back ourselves so the user's
+/// input is not swallowed.  The encoding itself is
+/// [`crumbeez_lib::encode_key`].  Zellij doesn't tell plugins which cursor
+/// key mode a pane is in, so `cursor` is the caller's best guess; see
+/// [`crumbeez_lib::ProjectConfig::cursor_keys_for`].
+pub fn write_key_bytes(key: &KeyWithModifier, cursor: CursorKeys, out: &mut Vec<u8>) {
+    // System keys (CapsLock, NumLock, …) don't produce stdin bytes in
+    // normal terminal usage; the application won't miss them.
+    let Some(vt_key) = vt_key(&key.bare_key) else {
+        return;
+    };
+    let mods = Modifiers::of(key);
+    let mods = VtModifiers {
+        ctrl: mods.ctrl,
+        alt: mods.alt,
+        shift: mods.shift,
+    };
+    crumbeez_lib::write_key_bytes(&vt_key, mods, cursor, out);
 }
 
-/// Produce the byte sequence for a bare key, factoring in Ctrl and Shift but
-/// not Alt (Alt wraps the result with an ESC prefix — see `key_to_bytes`).
-fn bare_key_to_bytes(bare: &BareKey, ctrl: bool, shift: bool) -> Vec<u8> {
+/// The key to encode for `bare`, or `None` for keys that send nothing.
+fn vt_key(bare: &BareKey) -> Option<ShortcutKey> {
     match bare {
-        // ── Printable characters ─────────────────────────────────
-        BareKey::Char(c) => {
-            if ctrl {
-                // Ctrl+letter → control byte 0x01–0x1A (Ctrl+A = 1, …, Ctrl+Z = 26).
-                // Also handle a handful of common Ctrl+symbol combos.
-                ctrl_char_bytes(*c)
-            } else {
-                // Plain or Shift-modified char — encode as UTF-8.
-                let mut buf = [0u8; 4];
-                c.encode_utf8(&mut buf).as_bytes().to_vec()
-            }
-        }
-
-        // ── Enter ────────────────────────────────────────────────
-        BareKey::Enter => {
-            if ctrl {
-                vec![0x0a] // Ctrl+Enter → LF (some apps distinguish this)
-            } else {
-                vec![0x0d] // CR
-            }
-        }
-
-        // ── Tab ──────────────────────────────────────────────────
-        BareKey::Tab => {
-            if ctrl {
-                // Ctrl+Tab — no universal standard; send as-is (apps vary).
-                vec![0x09]
-            } else if shift {
-                vec![0x1b, b'[', b'Z'] // ESC [ Z  (Back-Tab / Shift+Tab)
-            } else {
-                vec![0x09] // HT
-            }
-        }
-
-        // ── Backspace ────────────────────────────────────────────
-        BareKey::Backspace => {
-            if ctrl {
-                vec![0x08] // Ctrl+Backspace → BS
-            } else {
-                vec![0x7f] // DEL (modern default for Backspace)
-            }
-        }
-
-        // ── Escape ───────────────────────────────────────────────
-        BareKey::Esc => vec![0x1b],
-
-        // ── Delete (forward-delete) ──────────────────────────────
-        BareKey::Delete => {
-            if ctrl {
-                vec![0x1b, b'[', b'3', b';', b'5', b'~'] // ESC [ 3 ; 5 ~
-            } else if shift {
-                vec![0x1b, b'[', b'3', b';', b'2', b'~'] // ESC [ 3 ; 2 ~
-            } else {
-                vec![0x1b, b'[', b'3', b'~'] // ESC [ 3 ~
-            }
-        }
-
-        // ── Insert ───────────────────────────────────────────────
-        BareKey::Insert => {
-            if shift {
-                vec![0x1b, b'[', b'2', b';', b'2', b'~']
-            } else {
-                vec![0x1b, b'[', b'2', b'~']
-            }
-        }
-
-        // ── Arrow keys ───────────────────────────────────────────
-        // With Ctrl or Shift the modifier is encoded as a parameter:
-        //   ESC [ <letter>          — plain
-        //   ESC [ 1 ; 2 <letter>   — Shift
-        //   ESC [ 1 ; 5 <letter>   — Ctrl
-        //   ESC [ 1 ; 6 <letter>   — Ctrl+Shift
-        BareKey::Up => arrow_seq(b'A', ctrl, shift),
-        BareKey::Down => arrow_seq(b'B', ctrl, shift),
-        BareKey::Right => arrow_seq(b'C', ctrl, shift),
-        BareKey::Left => arrow_seq(b'D', ctrl, shift),
-
-        // ── Home / End ───────────────────────────────────────────
-        BareKey::Home => {
-            if ctrl || shift {
-                let m = modifier_param(ctrl, shift);
-                vec![0x1b, b'[', b'1', b';', m, b'H']
-            } else {
-                vec![0x1b, b'[', b'H']
-            }
-        }
-        BareKey::End => {
-            if ctrl || shift {
-                let m = modifier_param(ctrl, shift);
-                vec![0x1b, b'[', b'1', b';', m, b'F']
-            } else {
-                vec![0x1b, b'[', b'F']
-            }
-        }
-
-        // ── Page Up / Page Down ──────────────────────────────────
-        BareKey::PageUp => {
-            if ctrl || shift {
-                let m = modifier_param(ctrl, shift);
-                vec![0x1b, b'[', b'5', b';', m, b'~']
-            } else {
-                vec![0x1b, b'[', b'5', b'~']
-            }
-        }
-        BareKey::PageDown => {
-            if ctrl || shift {
-                let m = modifier_param(ctrl, shift);
-                vec![0x1b, b'[', b'6', b';', m, b'~']
-            } else {
-                vec![0x1b, b'[', b'6', b'~']
-            }
-        }
-
-        // ── Function keys F1–F12 ─────────────────────────────────
-        // F1–F4 use SS3 sequences; F5–F12 use CSI ~ sequences.
-        BareKey::F(n) => fkey_bytes(*n, ctrl, shift),
-
-        // ── System keys (no meaningful stdin byte sequence) ──────
-        // CapsLock, NumLock, etc. do not produce stdin bytes in normal
-        // terminal usage.  Send nothing — the application won't miss them.
         BareKey::CapsLock
         | BareKey::ScrollLock
         | BareKey::NumLock
         | BareKey::PrintScreen
         | BareKey::Pause
-        | BareKey::Menu => vec![],
-    }
-}
-
-/// Build the CSI sequence for an arrow key, incorporating modifier state.
-///
-/// Plain:        ESC [ <final>
-/// With mods:    ESC [ 1 ; <mod> <final>
-fn arrow_seq(final_byte: u8, ctrl: bool, shift: bool) -> Vec<u8> {
-    if ctrl || shift {
-        let m = modifier_param(ctrl, shift);
-        vec![0x1b, b'[', b'1', b';', m, final_byte]
-    } else {
-        vec![0x1b, b'[', final_byte]
-    }
-}
-
-/// Compute the XTerm modifier parameter byte for Ctrl/Shift combinations.
-///
-/// | Shift | Ctrl | param |
-/// |-------|------|-------|
-/// |   ✓   |      |   2   |
-/// |       |  ✓   |   5   |
-/// |   ✓   |  ✓   |   6   |
-fn modifier_param(ctrl: bool, shift: bool) -> u8 {
-    match (ctrl, shift) {
-        (false, true) => b'2',
-        (true, false) => b'5',
-        (true, true) => b'6',
-        (false, false) => b'1', // shouldn't be called without a modifier
-    }
-}
-
-/// Encode Ctrl+<char> as a control byte.
-///
-/// Standard mapping: Ctrl+A = 0x01, …, Ctrl+Z = 0x1A.
-/// A few non-letter chars that commonly produce control bytes are also handled.
-fn ctrl_char_bytes(c: char) -> Vec<u8> {
-    let lower = c.to_ascii_lowercase();
-    let byte = match lower {
-        'a'..='z' => (lower as u8) - b'a' + 1, // 0x01–0x1A
-        ' ' => 0x00,                           // Ctrl+Space → NUL
-        '[' => 0x1b,                           // Ctrl+[ → ESC
-        '\\' => 0x1c,                          // Ctrl+\ → FS
-        ']' => 0x1d,                           // Ctrl+] → GS
-        '^' => 0x1e,                           // Ctrl+^ → RS
-        '_' => 0x1f,                           // Ctrl+_ → US
-        _ => {
-            // Unknown Ctrl+char — encode the raw char as UTF-8 as a best-effort
-            // fallback; the application may not interpret it, but at least
-            // input is not silently dropped.
-            let mut buf = [0u8; 4];
-            return c.encode_utf8(&mut buf).as_bytes().to_vec();
-        }
-    };
-    vec![byte]
-}
-
-/// Encode F1–F12, with optional Ctrl/Shift modifiers.
-fn fkey_bytes(n: u8, ctrl: bool, shift: bool) -> Vec<u8> {
-    if ctrl || shift {
-        // XTerm extended: ESC [ <vt_code> ; <mod> ~
-        // (F1–F4 get vt codes 11–14 in this form)
-        let vt_code: &[u8] = match n {
-            1 => b"11",
-            2 => b"12",
-            3 => b"13",
-            4 => b"14",
-            5 => b"15",
-            6 => b"17",
-            7 => b"18",
-            8 => b"19",
-            9 => b"20",
-            10 => b"21",
-            11 => b"23",
-            12 => b"24",
-            _ => return vec![],
-        };
-        let m = modifier_param(ctrl, shift);
-        let mut seq = vec![0x1b, b'['];
-        seq.extend_from_slice(vt_code);
-        seq.extend_from_slice(&[b';', m, b'~']);
-        seq
-    } else {
-        // Plain (no modifier): F1–F4 use SS3, F5–F12 use CSI ~.
-        match n {
-            1 => vec![0x1b, b'O', b'P'],
-            2 => vec![0x1b, b'O', b'Q'],
-            3 => vec![0x1b, b'O', b'R'],
-            4 => vec![0x1b, b'O', b'S'],
-            5 => vec![0x1b, b'[', b'1', b'5', b'~'],
-            6 => vec![0x1b, b'[', b'1', b'7', b'~'],
-            7 => vec![0x1b, b'[', b'1', b'8', b'~'],
-            8 => vec![0x1b, b'[', b'1', b'9', b'~'],
-            9 => vec![0x1b, b'[', b'2', b'0', b'~'],
-            10 => vec![0x1b, b'[', b'2', b'1', b'~'],
-            11 => vec![0x1b, b'[', b'2', b'3', b'~'],
-            12 => vec![0x1b, b'[', b'2', b'4', b'~'],
-            _ => vec![],
-        }
+        | BareKey::Menu => None,
+        _ => Some(bare_key_to_shortcut_key(bare)),
     }
 }
 
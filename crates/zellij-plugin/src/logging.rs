@@ -0,0 +1,247 @@
+//! Logging setup for the plugin.
+//!
+//! Everything is logged through `tracing`.  Formatted lines go to stderr,
+//! which Zellij captures in its own log, and are also kept in an in-memory
+//! buffer: the most recent lines back the debug panel, and — when file
+//! logging is enabled — lines not yet written are appended to
+//! `.crumbeez/plugin.log` on each timer tick.
+//!
+//! Settings come from the plugin's layout configuration (see
+//! [`crate::plugin_config`]), because the level must be known before the
+//! project (and its `config.kdl`) is discovered.
+
+#[cfg(not(feature = "minimal"))]
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+#[cfg(not(feature = "minimal"))]
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use tracing::level_filters::LevelFilter;
+#[cfg(not(feature = "minimal"))]
+use tracing::{debug, error};
+
+#[cfg(not(feature = "minimal"))]
+use crate::command_router;
+#[cfg(not(feature = "minimal"))]
+use crate::context_tag::{purpose_of, ContextTag};
+#[cfg(not(feature = "minimal"))]
+use crate::host::HostHandle;
+#[cfg(not(feature = "minimal"))]
+use crate::shell;
+
+#[cfg(not(feature = "minimal"))]
+pub const CTX_PURPOSE: &str = "crumbeez_log_purpose";
+
+/// Number of recent lines kept for the debug panel.
+pub const LOG_PANEL_CAPACITY: usize = 100;
+
+/// Upper bound on lines waiting to be appended to the log file, so a file
+/// that can't be written doesn't grow memory without bound.
+const MAX_PENDING_FILE_LINES: usize = 1000;
+
+#[cfg(not(feature = "minimal"))]
+#[derive(Debug)]
+enum LogCommand {
+    AppendLogFile,
+}
+
+#[cfg(not(feature = "minimal"))]
+impl ContextTag for LogCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::AppendLogFile => "AppendLogFile",
+        }
+        .to_string()
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "AppendLogFile" => Some(Self::AppendLogFile),
+            _ => None,
+        }
+    }
+}
+
+// ── Settings ─────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy)]
+pub struct LogSettings {
+    pub level: LevelFilter,
+    /// Mirror log lines into `.crumbeez/plugin.log`.
+    pub file: bool,
+    /// Show recent log lines in the plugin pane.
+    pub panel: bool,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::INFO,
+            file: false,
+            panel: false,
+        }
+    }
+}
+
+/// Install the global subscriber.  Later calls are no-ops.
+pub fn init(settings: &LogSettings) {
+    buffer().mirror_to_file = settings.file;
+    let _ = tracing_subscriber::fmt()
+        .with_writer(|| BufferWriter)
+        .with_max_level(settings.level)
+        .with_target(false)
+        .with_ansi(false)
+        .try_init();
+}
+
+/// The most recent `n` log lines, oldest first.
+#[cfg(not(feature = "minimal"))]
+pub fn recent_lines(n: usize) -> Vec<String> {
+    let buffer = buffer();
+    let skip = buffer.recent.len().saturating_sub(n);
+    buffer.recent.iter().skip(skip).cloned().collect()
+}
+
+// ── Buffer ───────────────────────────────────────────────────────
+
+struct LogBuffer {
+    recent: VecDeque<String>,
+    /// Lines not yet appended to the log file.
+    pending: VecDeque<String>,
+    /// Bytes of an incomplete line, kept until its newline arrives.
+    partial: String,
+    mirror_to_file: bool,
+}
+
+impl LogBuffer {
+    const fn new() -> Self {
+        Self {
+            recent: VecDeque::new(),
+            pending: VecDeque::new(),
+            partial: String::new(),
+            mirror_to_file: false,
+        }
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        self.partial.push_str(&String::from_utf8_lossy(bytes));
+        while let Some(newline) = self.partial.find('\n') {
+            let line: String = self.partial.drain(..=newline).collect();
+            self.push_line(line.trim_end().to_string());
+        }
+    }
+
+    fn push_line(&mut self, line: String) {
+        if self.mirror_to_file {
+            if self.pending.len() >= MAX_PENDING_FILE_LINES {
+                self.pending.pop_front();
+            }
+            self.pending.push_back(line.clone());
+        }
+        if self.recent.len() >= LOG_PANEL_CAPACITY {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(line);
+    }
+}
+
+static BUFFER: Mutex<LogBuffer> = Mutex::new(LogBuffer::new());
+
+fn buffer() -> MutexGuard<'static, LogBuffer> {
+    // A panic while holding the lock can't leave the buffer inconsistent in
+    // any way that matters for logging, so ignore poisoning.
+    BUFFER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Writes to stderr and records complete lines in [`BUFFER`].
+struct BufferWriter;
+
+impl Write for BufferWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write_all(buf)?;
+        buffer().push_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+// ── Log file ─────────────────────────────────────────────────────
+
+#[cfg(not(feature = "minimal"))]
+#[derive(Default)]
+pub struct LogFileIO {
+    host: HostHandle,
+    path: Option<PathBuf>,
+    /// An append is running; wait for it so lines stay in order.
+    in_flight: bool,
+}
+
+#[cfg(not(feature = "minimal"))]
+impl LogFileIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    pub fn set_path(&mut self, path: PathBuf) {
+        if buffer().mirror_to_file {
+            debug!(path = ?path, "Plugin log file set");
+            self.path = Some(path);
+        }
+    }
+
+    /// Append any buffered lines to the log file.
+    pub fn flush(&mut self, cwd: PathBuf) {
+        if self.in_flight {
+            return;
+        }
+        let Some(path) = &self.path else {
+            return;
+        };
+        let lines: Vec<String> = buffer().pending.drain(..).collect();
+        if lines.is_empty() {
+            return;
+        }
+        let mut content = lines.join("\n");
+        content.push('\n');
+        let cmd = shell::platform().append_text(path, &content);
+        self.in_flight = true;
+        command_router::run(&self.host, &LogCommand::AppendLogFile, &cmd, cwd);
+    }
+
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stderr: &[u8],
+        exit_code: Option<i32>,
+    ) -> bool {
+        let Some(purpose) = purpose_of::<LogCommand>(context) else {
+            return false;
+        };
+
+        match purpose {
+            LogCommand::AppendLogFile => {
+                self.in_flight = false;
+                if exit_code != Some(0) {
+                    // Stop mirroring rather than logging the same failure on
+                    // every tick.
+                    let stderr = String::from_utf8_lossy(stderr);
+                    buffer().mirror_to_file = false;
+                    buffer().pending.clear();
+                    self.path = None;
+                    error!(?exit_code, stderr = %stderr.trim(), "Failed to append to plugin log, disabling file logging");
+                }
+                true
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Keeping the full text of long typed entries out of the event log.
+//!
+//! Typed text longer than the project's `long_text` length is logged as a
+//! [`LongText`](crumbeez_lib::KeystrokeEvent::LongText) preview, and the
+//! full text is written to `scratchpad/text-<timestamp>.txt`, the file the
+//! entry names.  The entry is logged straight away; only a failed write is
+//! reported in the plugin pane.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, error, info};
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+
+pub const CTX_PURPOSE: &str = "crumbeez_long_text_purpose";
+
+#[derive(Debug)]
+enum LongTextCommand {
+    /// `file` is relative to the `.crumbeez` directory, as logged.
+    WriteText { file: String },
+}
+
+impl ContextTag for LongTextCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::WriteText { file } => format!("WriteText:{}", file),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match split_tag(tag) {
+            ("WriteText", Some(file)) => Some(Self::WriteText { file: file.into() }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LongTextIO {
+    host: HostHandle,
+    /// The last write that failed, shown in the plugin pane.
+    pub status: Option<String>,
+}
+
+impl LongTextIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Write `text` to `file` under `crumbeez_dir`.
+    pub fn write(&mut self, crumbeez_dir: &Path, file: String, text: &str, cwd: PathBuf) {
+        let path = crumbeez_dir.join(&file);
+        info!(
+            ?path,
+            chars = text.chars().count(),
+            "Writing long typed text"
+        );
+        let cmd = shell::platform().write_text(&path, text);
+        command_router::run(&self.host, &LongTextCommand::WriteText { file }, &cmd, cwd);
+    }
+
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stderr: &[u8],
+        exit_code: Option<i32>,
+    ) -> bool {
+        let Some(LongTextCommand::WriteText { file }) = purpose_of::<LongTextCommand>(context)
+        else {
+            return false;
+        };
+        debug!(?exit_code, "WriteText result");
+        if exit_code == Some(0) {
+            self.status = None;
+        } else {
+            let err = String::from_utf8_lossy(stderr);
+            error!(%err, %file, "Failed to write long typed text");
+            self.status = Some(format!("failed to save the full text to {}", file));
+        }
+        true
+    }
+}
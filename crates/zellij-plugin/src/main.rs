@@ -1,299 +1,1270 @@
+#[cfg(not(feature = "minimal"))]
+mod broadcast;
+#[cfg(not(feature = "minimal"))]
+mod browser;
+#[cfg(not(feature = "minimal"))]
+mod clipboard;
+mod command_router;
+mod config_io;
+#[cfg(not(feature = "minimal"))]
+mod context;
+mod context_tag;
+#[cfg(all(feature = "demo", not(feature = "minimal")))]
+mod demo;
+#[cfg(not(feature = "minimal"))]
 mod event_log_io;
+#[cfg(not(feature = "minimal"))]
+mod heartbeat;
+#[cfg(not(feature = "minimal"))]
+mod history;
+mod host;
+#[cfg(not(feature = "minimal"))]
+mod integrations;
+#[cfg(not(feature = "minimal"))]
+mod io;
+#[cfg(not(feature = "minimal"))]
+mod io_probe;
+#[cfg(not(feature = "minimal"))]
 mod keystroke;
+mod logging;
+#[cfg(not(feature = "minimal"))]
+mod long_text;
+#[cfg(not(feature = "minimal"))]
+mod mark;
+#[cfg(feature = "minimal")]
+mod minimal;
+mod plugin_config;
+#[cfg(not(feature = "minimal"))]
+mod power;
+#[cfg(not(feature = "minimal"))]
+mod project_name;
+#[cfg(not(feature = "minimal"))]
+mod pull_request;
+#[cfg(not(feature = "minimal"))]
+mod recorder;
+#[cfg(all(test, not(feature = "minimal")))]
+mod replay;
+#[cfg(not(feature = "minimal"))]
+mod rollup;
 mod root_discovery;
+#[cfg(not(feature = "minimal"))]
+mod search;
+#[cfg(not(feature = "minimal"))]
+mod session_context;
+mod shell;
+#[cfg(not(feature = "minimal"))]
+mod snapshot;
+#[cfg(not(feature = "minimal"))]
+mod standup;
+#[cfg(not(feature = "minimal"))]
+mod summaries;
+#[cfg(not(feature = "minimal"))]
+mod summarizer;
+#[cfg(not(feature = "minimal"))]
+mod summary_worker;
+mod toast;
+#[cfg(not(feature = "minimal"))]
+mod ui;
+#[cfg(not(feature = "minimal"))]
+mod viewer;
 
+#[cfg(not(feature = "minimal"))]
 use std::collections::{BTreeMap, HashMap};
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::{debug, error, info};
+#[cfg(not(feature = "minimal"))]
+use std::path::PathBuf;
+#[cfg(not(feature = "minimal"))]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(not(feature = "minimal"))]
+use tracing::{debug, error, info, warn};
 use zellij_tile::prelude::*;
 
+#[cfg(not(feature = "minimal"))]
+use broadcast::{Subscribers, SUBSCRIBE_PIPE, TAIL_PIPE, UNSUBSCRIBE_PIPE};
+#[cfg(not(feature = "minimal"))]
+use browser::BROWSE_PIPE;
+#[cfg(not(feature = "minimal"))]
+use clipboard::COPY_PIPE;
+#[cfg(not(feature = "minimal"))]
+use command_router::{CommandOutput, Expired};
+#[cfg(not(feature = "minimal"))]
+use context::CONTEXT_PIPE;
+#[cfg(not(feature = "minimal"))]
 use crumbeez_lib::{
-    EditControlEvent, EventLog, KeystrokeActivity, KeystrokeEvent, NavDirection, PaneFocusedEvent,
+    Election, EventLog, FloodGuard, InstanceRole, KeySource, KeystrokeEvent, PowerMode,
+    PowerProfile, ProjectConfig, SummaryTrigger, HELLO_PIPE, PASTE_START, SUMMARY_PAGE_SIZE,
 };
-use event_log_io::EventLogIO;
-use keystroke::{classify, key_to_bytes};
+#[cfg(not(feature = "minimal"))]
+use event_log_io::{EventLogWorker, CHUNKED_WRITE};
+#[cfg(not(feature = "minimal"))]
+use history::HISTORY_PIPE;
+#[cfg(not(feature = "minimal"))]
+use host::HostHandle;
+#[cfg(not(feature = "minimal"))]
+use io::{Io, IoEvent, Shared};
+#[cfg(not(feature = "minimal"))]
+use keystroke::{classify, pastable, plain_char, write_key_bytes, MAX_KEY_SEQUENCE_LEN};
+#[cfg(not(feature = "minimal"))]
+use mark::{clean_mark, MarkPrompt, MARK_PIPE};
+#[cfg(not(feature = "minimal"))]
+use plugin_config::{ConfigProblem, PluginConfig};
+#[cfg(not(feature = "minimal"))]
+use pull_request::PR_PIPE;
+#[cfg(not(feature = "minimal"))]
+use recorder::{Recorded, Recorder};
+#[cfg(not(feature = "minimal"))]
+use rollup::{CALENDAR_PIPE, ROLLUP_PIPE};
+#[cfg(not(feature = "minimal"))]
 use root_discovery::RootDiscovery;
+#[cfg(not(feature = "minimal"))]
+use snapshot::SNAPSHOT_PIPE;
+#[cfg(not(feature = "minimal"))]
+use standup::STANDUP_PIPE;
+#[cfg(not(feature = "minimal"))]
+use summaries::{ISSUE_PIPE, SEARCH_PIPE};
+#[cfg(not(feature = "minimal"))]
+use summarizer::Summarizer;
+#[cfg(not(feature = "minimal"))]
+use summary_worker::{FinishedSummary, SummaryWorker, SUMMARY_DONE};
+#[cfg(not(feature = "minimal"))]
+use toast::Severity;
+#[cfg(not(feature = "minimal"))]
+use ui::{KeyOutcome, Ui, View};
 
-#[derive(Default)]
+#[cfg(not(feature = "minimal"))]
 struct State {
+    /// Zellij, or a test's stand-in; every part below that calls into it
+    /// has a clone.
+    host: HostHandle,
     discovery: RootDiscovery,
     permissions_granted: bool,
-    keystroke_activity: KeystrokeActivity,
-    focused_pane: Option<FocusedPane>,
-    current_pane_has_activity: bool,
+    /// Turns keys into events for the log; see [`recorder`].
+    recorder: Recorder,
+    /// Decides when the log is summarized; see [`summarizer`].
+    summarizer: Summarizer,
+    /// What the plugin pane shows; see [`ui`].
+    ui: Ui,
+    /// The IO modules, each running its own commands; see [`io`].
+    io: Io,
+    /// Which of the session's crumbeez instances records.  The others only
+    /// show what's going on.
+    election: Election,
+    /// The election's outcome this instance last acted on.
+    role: InstanceRole,
+    /// The session's name, once it's been reported.
+    session_name: Option<String>,
     tab_names: HashMap<usize, String>,
+    /// Every tab's name, in order, to tell which one closed.
+    open_tabs: Vec<String>,
     event_log: EventLog,
-    event_log_io: EventLogIO,
-    pending_summaries: Vec<String>,
-    live_text: Option<String>,
-    live_cursor: usize,
-    last_activity_time: Option<SystemTime>,
-    last_summary_time: Option<SystemTime>,
+    /// Plugins receiving each entry as it's logged.
+    subscribers: Subscribers,
+    config: ProjectConfig,
+    plugin_config: PluginConfig,
+    /// Problems with the plugin configuration.  While there are any the
+    /// plugin shows them and does nothing else.
+    config_problems: Vec<ConfigProblem>,
+    /// Config chosen during onboarding, written once the dirs exist.
+    onboarding_config: Option<ProjectConfig>,
+    /// When the earliest armed timer fires, if one is; see
+    /// [`Self::schedule_wakeup`].  Later ones may still be armed too.
+    timer_due: Option<SystemTime>,
+    /// A fast timer is pending to time out discovery commands.  The regular
+    /// inactivity timer takes over once discovery settles.
+    discovery_tick_armed: bool,
+    /// When the data dir was last checked to still exist.
+    last_validated: Option<SystemTime>,
+    /// Plays canned activity in demo mode.
+    #[cfg(feature = "demo")]
+    demo: Option<demo::DemoPlayer>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-struct FocusedPane {
-    tab_index: usize,
-    pane_id: u32,
-    is_plugin: bool,
+/// Pipe name that toggles the doctor report.
+#[cfg(not(feature = "minimal"))]
+const DOCTOR_PIPE: &str = "crumbeez::doctor";
+
+/// Pipe name that reruns root discovery, from the payload's directory if
+/// there is one, and moves the log to wherever it finds.
+#[cfg(not(feature = "minimal"))]
+const REDISCOVER_PIPE: &str = "crumbeez::rediscover";
+
+/// Pipe name that asks for the plugin's permissions again, after they were
+/// denied or revoked.
+#[cfg(not(feature = "minimal"))]
+const PERMISSIONS_PIPE: &str = "crumbeez::permissions";
+
+/// Pipe name that sets the session's tags to the payload's words, or clears
+/// them without any.
+#[cfg(not(feature = "minimal"))]
+const TAG_PIPE: &str = "crumbeez::tag";
+
+/// How often, while there's activity, to check the data dir still exists.
+#[cfg(not(feature = "minimal"))]
+const REVALIDATE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The delay for a timer armed for something already overdue, so it
+/// doesn't spin the plugin.  Something due soon, like the end of a
+/// paste, gets a timer for exactly then.
+#[cfg(not(feature = "minimal"))]
+const MIN_TIMER_DELAY: Duration = Duration::from_millis(100);
+
+/// Timer interval while discovery commands are running.  Their timeouts are
+/// a few seconds, so the inactivity timer is too coarse.
+const DISCOVERY_TICK_SECS: f64 = 1.0;
+
+#[cfg(not(feature = "minimal"))]
+const MS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// Whether a plugin pane's URL is one of ours.
+#[cfg(not(feature = "minimal"))]
+fn is_crumbeez_url(url: Option<&str>) -> bool {
+    url.is_some_and(|url| url.contains("crumbeez"))
+}
+
+/// The tabs in `before` missing from `after`, when there are fewer of them.
+/// A tab renamed while the count stays the same isn't a closure.
+#[cfg(not(feature = "minimal"))]
+fn closed_tabs(before: &[String], after: &[String]) -> Vec<String> {
+    if after.len() >= before.len() {
+        return Vec::new();
+    }
+    let mut remaining: Vec<&String> = after.iter().collect();
+    before
+        .iter()
+        .filter(
+            |name| match remaining.iter().position(|open| open == name) {
+                Some(i) => {
+                    remaining.remove(i);
+                    false
+                }
+                None => true,
+            },
+        )
+        .cloned()
+        .collect()
+}
+
+/// Announce this instance to another one; see [`Election`].
+#[cfg(not(feature = "minimal"))]
+fn send_hello(host: &HostHandle, plugin_id: u32) {
+    debug!(plugin_id, "Greeting crumbeez instance");
+    host.pipe_message_to_plugin(
+        MessageToPlugin::new(HELLO_PIPE).with_destination_plugin_id(plugin_id),
+    );
 }
 
-const INACTIVITY_TIMER_SECS: f64 = 10.0;
+#[cfg(not(feature = "minimal"))]
+impl Default for State {
+    fn default() -> Self {
+        Self::new(HostHandle::default())
+    }
+}
 
+#[cfg(not(feature = "minimal"))]
 impl State {
-    fn log_event(&mut self, event: KeystrokeEvent) {
-        self.keystroke_activity.push_event(event.clone());
-        self.process_for_event_log(event);
-        // Mark that this pane has had activity (for summary triggering on pane switch)
-        self.current_pane_has_activity = true;
-    }
-
-    fn process_for_event_log(&mut self, event: KeystrokeEvent) {
-        match &event {
-            KeystrokeEvent::TextTyped(s) => {
-                if let Some(ref mut text) = self.live_text {
-                    text.insert_str(self.live_cursor, s);
-                    self.live_cursor += s.len();
+    fn new(host: HostHandle) -> Self {
+        Self {
+            discovery: RootDiscovery::new(host.clone()),
+            permissions_granted: false,
+            recorder: Recorder::new(host.clone()),
+            summarizer: Summarizer::new(host.clone()),
+            ui: Ui::new(host.clone()),
+            io: Io::new(host.clone()),
+            election: Election::default(),
+            role: InstanceRole::default(),
+            session_name: None,
+            tab_names: HashMap::new(),
+            open_tabs: Vec::new(),
+            event_log: EventLog::default(),
+            subscribers: Subscribers::new(host.clone()),
+            config: ProjectConfig::default(),
+            plugin_config: PluginConfig::default(),
+            config_problems: Vec::new(),
+            onboarding_config: None,
+            timer_due: None,
+            discovery_tick_armed: false,
+            last_validated: None,
+            #[cfg(feature = "demo")]
+            demo: None,
+            host,
+        }
+    }
+
+    /// Apply settings from the project config that affect already-loaded
+    /// state.  Called whenever the config or the event log is (re)loaded,
+    /// since both arrive asynchronously and in either order.
+    fn apply_config(&mut self) {
+        self.recorder
+            .apply_config(&self.config, self.current_time_ms());
+        self.dispatch_recorded();
+        self.event_log
+            .set_capacity(self.config.retention.max_events);
+        if self.io.config.loaded {
+            self.discovery.ensure_gitignore(self.config.gitignore);
+            self.resolve_project_name();
+        }
+    }
+
+    /// Work out the project's name, once its config is known.
+    fn resolve_project_name(&mut self) {
+        if let Some(root) = self.discovery.roots.first() {
+            self.io.project_name.resolve(
+                root,
+                self.config.project_name.as_deref(),
+                self.discovery.initial_cwd.clone(),
+            );
+        }
+    }
+
+    fn current_time_ms(&self) -> u64 {
+        self.host
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64
+    }
+
+    fn handle_discovery_ready(&mut self) {
+        debug!(
+            phase = ?self.discovery.phase,
+            "handle_discovery_ready called"
+        );
+        if std::mem::take(&mut self.discovery.recreated) {
+            // Carry the settings over to the new data dir.
+            self.onboarding_config = Some(self.config.clone());
+        }
+        self.io.move_event_log(
+            self.discovery.data_dir(),
+            &self.event_log,
+            &self.discovery.initial_cwd,
+        );
+        self.load_event_log_when_ready();
+        if let crumbeez_lib::DiscoveryPhase::Ready { ref dirs } = self.discovery.phase {
+            if let Some(dir) = dirs.first() {
+                self.io
+                    .log_file
+                    .set_path(crumbeez_lib::plugin_log_path_from_crumbeez_dir(dir));
+                self.io.log_file.flush(self.discovery.initial_cwd.clone());
+                self.io
+                    .heartbeat
+                    .set_path(crumbeez_lib::heartbeat_path_from_crumbeez_dir(dir));
+                if !self.is_demo() {
+                    let today = crumbeez_lib::format_ymd(self.current_time_ms());
+                    self.io
+                        .summaries
+                        .load(dir, &today, self.discovery.initial_cwd.clone());
+                }
+                if let Some(config) = self.onboarding_config.take() {
+                    let config_path = crumbeez_lib::config_path_from_crumbeez_dir(dir);
+                    self.io
+                        .config
+                        .save(config_path, &config, self.discovery.initial_cwd.clone());
+                    self.config = config;
+                    self.apply_config();
+                    self.discovery.ensure_gitignore(self.config.gitignore);
+                    self.resolve_project_name();
                 } else {
-                    self.live_text = Some(s.clone());
-                    self.live_cursor = s.len();
-                }
-            }
-            KeystrokeEvent::EditControl(EditControlEvent::Backspace { .. }) => {
-                if let Some(ref mut text) = self.live_text {
-                    if self.live_cursor > 0 {
-                        let prev = prev_char_boundary(text, self.live_cursor);
-                        text.drain(prev..self.live_cursor);
-                        self.live_cursor = prev;
-                        if text.is_empty() {
-                            self.live_text = None;
-                        }
+                    let paths = config_io::search_paths(
+                        self.discovery.roots.first().map(PathBuf::as_path),
+                        dir,
+                    );
+                    self.io
+                        .config
+                        .load(paths, self.discovery.initial_cwd.clone());
+                }
+                self.io.rollup.start(self.discovery.initial_cwd.clone());
+                // Start capturing only once the project (and, on first run, the
+                // user's privacy choice) is settled.
+                if self.is_recorder() && !self.is_demo() {
+                    self.host.intercept_key_presses();
+                    if let Some(root) = self.discovery.roots.first() {
+                        self.io.session_context.capture(root);
                     }
                 }
             }
-            KeystrokeEvent::EditControl(EditControlEvent::Delete { .. }) => {
-                if let Some(ref mut text) = self.live_text {
-                    if self.live_cursor < text.len() {
-                        let next = next_char_boundary(text, self.live_cursor);
-                        text.drain(self.live_cursor..next);
-                        if text.is_empty() {
-                            self.live_text = None;
-                        }
-                    }
+        }
+    }
+
+    /// Run root discovery again from `cwd`, or from where it ran last time.
+    fn rediscover(&mut self, cwd: Option<PathBuf>) {
+        if !self.permissions_granted || self.discovery.in_progress() {
+            warn!(phase = ?self.discovery.phase, "Can't rediscover now");
+            return;
+        }
+        let cwd = cwd.unwrap_or_else(|| self.discovery.initial_cwd.clone());
+        self.discovery.rediscover(cwd);
+        self.ensure_discovery_tick();
+    }
+
+    /// The permissions this instance asks for.  Viewers and demos don't
+    /// intercept keys, so don't need to forward them either.
+    fn required_permissions(&self) -> Vec<PermissionType> {
+        let mut permissions = vec![
+            PermissionType::ReadApplicationState,
+            PermissionType::RunCommands,
+            // ReadCliPipes: accept `zellij pipe --name crumbeez::mark`.
+            PermissionType::ReadCliPipes,
+            // MessageAndLaunchOtherPlugins: answer `crumbeez::context`
+            // requests from other plugins.
+            PermissionType::MessageAndLaunchOtherPlugins,
+            // WriteToClipboard: copy summaries with `crumbeez::copy`.
+            PermissionType::WriteToClipboard,
+            // ChangeApplicationState: show toasts in the pane title.
+            PermissionType::ChangeApplicationState,
+        ];
+        if !self.plugin_config.viewer && !self.is_demo() {
+            permissions.extend([
+                // InterceptInput: receive every keystroke session-wide via
+                // InterceptedKeyPress.  We immediately re-forward each key back
+                // to the focused pane so the user's input is not swallowed.
+                PermissionType::InterceptInput,
+                // WriteToStdin: needed to forward the intercepted keys back.
+                PermissionType::WriteToStdin,
+            ]);
+        }
+        permissions
+    }
+
+    /// Ask for the permissions again, so ones denied at first or revoked
+    /// since can be granted without restarting the plugin.
+    fn rerequest_permissions(&self) {
+        info!("Requesting permissions again");
+        self.host.request_permission(&self.required_permissions());
+    }
+
+    /// Pick up from wherever permissions were needed.  Zellij answers a
+    /// repeated request straight away, so this runs again for permissions
+    /// already granted and must leave running work alone.
+    fn handle_permissions_granted(&mut self) {
+        let first = !std::mem::replace(&mut self.permissions_granted, true);
+        let cwd = self.host.get_plugin_ids().initial_cwd;
+        info!(?cwd, first, "Permissions granted");
+        if first && !self.io.probe.is_done() {
+            self.io.probe.start(cwd.clone());
+        }
+        self.check_power_if_due(cwd.clone());
+        match self.discovery.phase {
+            crumbeez_lib::DiscoveryPhase::AwaitingPermissions => self.discovery.start(cwd),
+            // A session that had a data dir finds it again; one denied
+            // from the start goes on to onboarding as usual.
+            crumbeez_lib::DiscoveryPhase::Failed(_) if self.io.event_log.log_path().is_some() => {
+                self.discovery.rediscover(cwd)
+            }
+            crumbeez_lib::DiscoveryPhase::Failed(_) => {
+                self.discovery = RootDiscovery::default();
+                self.discovery.start(cwd);
+            }
+            crumbeez_lib::DiscoveryPhase::Ready { .. } => {
+                // Intercepting again is harmless if it never stopped.
+                if self.is_recorder() && !self.is_demo() {
+                    self.host.intercept_key_presses();
                 }
+                return;
             }
-            KeystrokeEvent::Navigation(nav) => match nav.direction {
-                NavDirection::Left => {
-                    if let Some(ref text) = self.live_text {
-                        let new_pos = if nav.with_ctrl {
-                            word_left(text, self.live_cursor)
-                        } else {
-                            prev_char_boundary(text, self.live_cursor)
-                        };
-                        self.live_cursor = new_pos;
-                    }
+            // Discovery or onboarding is under way already.
+            _ => return,
+        }
+        self.ensure_discovery_tick();
+    }
+
+    /// Check whether the host is on battery, if the power mode depends on it
+    /// and it's been a while.
+    fn check_power_if_due(&mut self, cwd: PathBuf) {
+        if self.plugin_config.power == PowerMode::Auto {
+            self.io.power.check_if_due(cwd);
+        }
+    }
+
+    /// How eagerly to do periodic work, per the `power` setting and the
+    /// power source.
+    fn power_profile(&self) -> PowerProfile {
+        self.plugin_config.power.profile(self.io.power.on_battery)
+    }
+
+    /// Pass the power profile's settings on to the IO modules it affects.
+    fn apply_power_profile(&mut self) {
+        self.io.event_log.min_interval = self.power_profile().flush_interval;
+    }
+
+    /// Check the data dir still exists, if it's been a while.
+    fn validate_dir_if_due(&mut self) {
+        let due = self.last_validated.is_none_or(|last| {
+            self.host
+                .now()
+                .duration_since(last)
+                .is_ok_and(|e| e >= REVALIDATE_INTERVAL)
+        });
+        if due {
+            self.last_validated = Some(self.host.now());
+            self.discovery.validate();
+        }
+    }
+
+    /// Keep a fast timer running while discovery waits on commands.
+    fn ensure_discovery_tick(&mut self) {
+        if self.discovery.in_progress() && !self.discovery_tick_armed {
+            self.host.set_timeout(DISCOVERY_TICK_SECS);
+            self.discovery_tick_armed = true;
+        }
+    }
+
+    /// Arm a timer for whatever is due first: a summary once activity has
+    /// been quiet for the inactivity timeout, an event log write, a
+    /// command deadline, and so on.  A timer already armed is left alone
+    /// unless something is now due before it, in which case another is
+    /// armed for that.  Zellij can't cancel the first, but when it fires
+    /// each handler finds its own deadline not yet reached.  With nothing
+    /// due no timer is armed, so an idle session doesn't wake the plugin
+    /// until the next event.
+    fn schedule_wakeup(&mut self) {
+        if self.discovery_tick_armed || self.recorder.suspended.is_some() {
+            return;
+        }
+        let flood_due = self
+            .recorder
+            .flood
+            .subsides_at()
+            .map(|ms| UNIX_EPOCH + Duration::from_millis(ms));
+        let title_due = self
+            .recorder
+            .titles
+            .settles_at()
+            .map(|ms| UNIX_EPOCH + Duration::from_millis(ms));
+        let paste_due = self
+            .recorder
+            .paste
+            .ends_at()
+            .map(|ms| UNIX_EPOCH + Duration::from_millis(ms));
+        let atuin_due = self
+            .io
+            .atuin
+            .due_at()
+            .map(|ms| UNIX_EPOCH + Duration::from_millis(ms));
+        let toast_due = self
+            .ui
+            .toasts
+            .expires_at()
+            .map(|ms| UNIX_EPOCH + Duration::from_millis(ms));
+        let summary_due = self.summarizer.due_at(self.inactivity_timeout());
+        let Some(due) = [
+            summary_due,
+            flood_due,
+            title_due,
+            paste_due,
+            atuin_due,
+            toast_due,
+            self.demo_due(),
+            self.io.event_log.flush_due(),
+            command_router::next_deadline(&self.host),
+        ]
+        .into_iter()
+        .flatten()
+        .min() else {
+            return;
+        };
+        let now = self.host.now();
+        let delay = due
+            .duration_since(now)
+            .ok()
+            .filter(|delay| !delay.is_zero())
+            .unwrap_or(MIN_TIMER_DELAY);
+        if self.timer_due.is_some_and(|armed| armed <= now + delay) {
+            return;
+        }
+        debug!(secs = delay.as_secs_f64(), "Scheduling wakeup");
+        self.host.set_timeout(delay.as_secs_f64());
+        self.timer_due = Some(now + delay);
+    }
+
+    /// When the next demo key is due.
+    #[cfg(feature = "demo")]
+    fn demo_due(&self) -> Option<SystemTime> {
+        self.demo
+            .as_ref()
+            .and_then(demo::DemoPlayer::due_at)
+            .map(|ms| UNIX_EPOCH + Duration::from_millis(ms))
+    }
+
+    #[cfg(not(feature = "demo"))]
+    fn demo_due(&self) -> Option<SystemTime> {
+        None
+    }
+
+    /// Quiet time before activity is summarized, lengthened in the
+    /// low-power profile.
+    fn inactivity_timeout(&self) -> Duration {
+        self.power_profile()
+            .inactivity_timeout(self.plugin_config.inactivity_timeout)
+    }
+
+    /// Whether this instance intercepts keys and writes the log: it isn't a
+    /// [viewer](viewer) and won the [`Election`].
+    fn is_recorder(&self) -> bool {
+        !self.plugin_config.viewer && self.role == InstanceRole::Recorder
+    }
+
+    /// Whether canned activity is played in place of intercepted keys.
+    /// Nothing is read from or written to the data dir, and the instance
+    /// stays out of the election like a viewer.
+    fn is_demo(&self) -> bool {
+        self.plugin_config.demo
+    }
+
+    /// Start or stop recording if the election's outcome changed.
+    fn apply_role(&mut self) {
+        let role = self.election.role();
+        if role == self.role {
+            return;
+        }
+        info!(%role, "Instance role changed");
+        let now = self.current_time_ms();
+        if self.role == InstanceRole::Recorder {
+            // Keep what was caught so far, then leave it to the recorder.
+            self.recorder.seal_pending_text(&self.config, now);
+            self.recorder.record_filtered(&self.config, now);
+            self.dispatch_recorded();
+            self.io
+                .event_log
+                .save(self.discovery.initial_cwd.clone(), &self.event_log);
+        }
+        self.role = role;
+        self.recorder.recording = self.is_recorder();
+        match role {
+            InstanceRole::Viewer { .. } => self.host.clear_key_presses_intercepts(),
+            InstanceRole::Recorder => {
+                // Whatever was typed while watching is in the old
+                // recorder's log, not this one's.
+                self.recorder.take_pending_text(&self.config, now);
+                self.recorder.keystroke_activity.seal();
+                self.dispatch_recorded();
+                // The log on disk has moved on while this instance watched.
+                if self.io.event_log.log_path().is_some() {
+                    self.io.event_log.load(self.discovery.initial_cwd.clone());
+                } else {
+                    self.load_event_log_when_ready();
                 }
-                NavDirection::Right => {
-                    if let Some(ref text) = self.live_text {
-                        let new_pos = if nav.with_ctrl {
-                            word_right(text, self.live_cursor)
-                        } else {
-                            next_char_boundary(text, self.live_cursor)
-                        };
-                        self.live_cursor = new_pos;
+                if matches!(
+                    self.discovery.phase,
+                    crumbeez_lib::DiscoveryPhase::Ready { .. }
+                ) {
+                    self.host.intercept_key_presses();
+                }
+            }
+        }
+    }
+
+    /// Pass on what the recorder did, in the order it did it: entries to
+    /// the log and its subscribers, activity to the summarizer, and so on.
+    fn dispatch_recorded(&mut self) {
+        while let Some(recorded) = self.recorder.next_output() {
+            let now = self.current_time_ms();
+            match recorded {
+                Recorded::Entry(entry) => {
+                    let seq = self.event_log.next_seq();
+                    self.event_log.append_entry(entry);
+                    if let Some(entry) = self.event_log.entries().last() {
+                        self.subscribers.publish(seq, entry, &self.config);
                     }
                 }
-                NavDirection::Home => {
-                    self.live_cursor = 0;
+                Recorded::Activity => self.summarizer.note_activity(),
+                Recorded::Submitted(command) => self.io.atuin.track(&command, now),
+                Recorded::LongText { file, text } => {
+                    if self.is_recorder() {
+                        self.io.save_long_text(
+                            self.discovery.data_dir(),
+                            file,
+                            &text,
+                            &self.discovery.initial_cwd,
+                        );
+                    }
                 }
-                NavDirection::End => {
-                    if let Some(ref text) = self.live_text {
-                        self.live_cursor = text.len();
+                Recorded::Risky(risky) => {
+                    if let Err(risky) = self.ui.ask_why(risky) {
+                        self.recorder
+                            .record(risky.into_event(None), &self.config, now);
                     }
                 }
-                NavDirection::Up
-                | NavDirection::Down
-                | NavDirection::PageUp
-                | NavDirection::PageDown => {
-                    self.seal_and_log(event);
+                Recorded::Boundary(trigger) => self.summarize_logged(trigger),
+                Recorded::Suspended => {
+                    self.io
+                        .event_log
+                        .save(self.discovery.initial_cwd.clone(), &self.event_log);
+                    self.io.log_file.flush(self.discovery.initial_cwd.clone());
                 }
-            },
-            _ => {
-                self.seal_and_log(event);
+                Recorded::Resumed => {
+                    self.write_heartbeat();
+                    // The break isn't inactivity worth a summary of its own.
+                    self.summarizer.forget_activity();
+                }
+                Recorded::Toast(severity, text) => self.toast(severity, text),
             }
         }
-
-        self.last_activity_time = Some(SystemTime::now());
     }
 
-    fn seal_and_log(&mut self, event: KeystrokeEvent) {
-        if let Some(text) = self.live_text.take() {
-            if !text.is_empty() {
-                self.event_log
-                    .append(KeystrokeEvent::TextTyped(text), Self::current_time_ms());
+    /// Act on what a key the pane took asks for.  Returns whether the pane
+    /// needs redrawing.
+    fn handle_key_outcome(&mut self, outcome: KeyOutcome) -> bool {
+        let changed = outcome.changed();
+        match outcome {
+            KeyOutcome::Ignored | KeyOutcome::Handled => {}
+            KeyOutcome::Bookmark(name) => self.add_annotation(name),
+            KeyOutcome::Risky(event) => {
+                self.recorder
+                    .record(event, &self.config, self.current_time_ms());
+                self.dispatch_recorded();
+            }
+            KeyOutcome::Onboarded(roots, config) => {
+                self.onboarding_config = Some(*config);
+                self.discovery.finish_onboarding(roots);
+                self.ensure_discovery_tick();
             }
         }
-        self.live_cursor = 0;
-        self.event_log.append(event, Self::current_time_ms());
+        changed
     }
 
-    fn seal_pending_text(&mut self) {
-        if let Some(text) = self.live_text.take() {
-            if !text.is_empty() {
-                self.event_log
-                    .append(KeystrokeEvent::TextTyped(text), Self::current_time_ms());
-            }
+    /// Flash `text` in the pane title, if the `toasts` setting shows
+    /// `severity`.
+    fn toast(&mut self, severity: Severity, text: &str) {
+        self.ui.toasts.show(
+            &self.plugin_config.toasts,
+            severity,
+            text,
+            self.current_time_ms(),
+        );
+    }
+
+    /// Load the event log once discovery has found where it lives and the IO
+    /// probe has found how to read it.
+    fn load_event_log_when_ready(&mut self) {
+        if !(self.is_recorder() || self.plugin_config.viewer) || self.is_demo() {
+            return;
         }
-        self.live_cursor = 0;
+        self.io
+            .load_event_log_when_ready(self.discovery.data_dir(), &self.discovery.initial_cwd);
     }
 
-    fn current_time_ms() -> u64 {
-        use std::time::SystemTime;
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64
+    /// Replace the heartbeat file with the plugin's current state.
+    fn write_heartbeat(&mut self) {
+        if !self.is_recorder() || self.is_demo() {
+            return;
+        }
+        self.io.write_heartbeat(
+            self.recorder.suspended,
+            self.event_log.next_seq(),
+            self.current_time_ms(),
+            &self.discovery.initial_cwd,
+        );
     }
 
-    fn handle_discovery_ready(&mut self) {
-        debug!(
-            phase = ?self.discovery.phase,
-            "handle_discovery_ready called"
+    /// Writes just started failing persistently.  With `save_fallback`,
+    /// move the data dir to fallback storage; discovery finishing there
+    /// relocates the log, which writes it straight away.
+    fn handle_save_alert(&mut self) {
+        error!(
+            failures = self.io.event_log.consecutive_failures,
+            "Event log writes keep failing"
         );
-        if let crumbeez_lib::DiscoveryPhase::Ready { ref dirs } = self.discovery.phase {
-            if let Some(dir) = dirs.first() {
-                let log_path = crumbeez_lib::event_log_path_from_crumbeez_dir(dir);
-                debug!(path = ?log_path, "Log path");
-                self.event_log_io.set_log_path(log_path.clone());
-                self.event_log_io.load(self.discovery.initial_cwd.clone());
-                self.reset_inactivity_timer();
-            }
+        self.toast(Severity::Error, "event log writes keep failing");
+        if self.plugin_config.save_fallback && !self.discovery.in_progress() {
+            self.discovery.fall_back();
+            self.ensure_discovery_tick();
         }
     }
 
-    fn reset_inactivity_timer(&mut self) {
-        debug!(secs = INACTIVITY_TIMER_SECS, "Resetting inactivity timer");
-        set_timeout(INACTIVITY_TIMER_SECS);
+    /// Log the captured session context, once the event log has loaded.
+    fn record_session_context(&mut self) {
+        if let Some(values) = self.io.take_session_context() {
+            self.recorder.record(
+                KeystrokeEvent::SessionContext(values),
+                &self.config,
+                self.current_time_ms(),
+            );
+            self.dispatch_recorded();
+        }
     }
 
-    fn handle_pane_update(&mut self, manifest: PaneManifest) {
-        let my_plugin_id = get_plugin_ids().plugin_id;
-        let mut new_focus: Option<(usize, PaneInfo)> = None;
-        let mut focused_tab_name: Option<String> = None;
-
-        for (tab_index, panes) in &manifest.panes {
-            for pane in panes {
-                if !pane.is_selectable || pane.is_suppressed {
-                    continue;
-                }
-                if pane.is_plugin {
-                    if let Some(ref url) = pane.plugin_url {
-                        if url.contains("crumbeez") {
-                            continue;
-                        }
-                    }
-                    if pane.id == my_plugin_id {
-                        continue;
-                    }
+    fn handle_command_output(&mut self, output: CommandOutput) -> bool {
+        if output.context.contains_key(root_discovery::CTX_PURPOSE) {
+            return self.handle_discovery_result(&output);
+        }
+        let now_ms = self.current_time_ms();
+        let event = self.io.handle_command_output(
+            &output,
+            &mut Shared {
+                event_log: &mut self.event_log,
+                config: &mut self.config,
+                dir: self.discovery.data_dir(),
+                cwd: &self.discovery.initial_cwd,
+                now_ms,
+            },
+        );
+        self.handle_io_event(event)
+    }
+
+    /// Act on what a command's result means outside the IO module that ran
+    /// it.  Returns whether the pane needs redrawing.
+    fn handle_io_event(&mut self, event: IoEvent) -> bool {
+        let now = self.current_time_ms();
+        match event {
+            IoEvent::Done(changed) => changed,
+            IoEvent::LogFileWritten => self.plugin_config.log.panel,
+            IoEvent::EventLog {
+                reloaded,
+                saved,
+                alerting,
+            } => {
+                if reloaded {
+                    // The session's tags are kept in the log.
+                    self.summarizer.reload_tags(&self.event_log);
+                    self.recover();
+                    // Edits from here on shouldn't land in a buffer begun
+                    // against the log that was replaced.
+                    self.recorder.seal_pending_text(&self.config, now);
+                    self.recorder.keystroke_activity.seal();
+                    self.dispatch_recorded();
                 }
-                if pane.is_focused {
-                    new_focus = Some((*tab_index, pane.clone()));
-                    focused_tab_name = self
-                        .tab_names
-                        .get(tab_index)
-                        .cloned()
-                        .or_else(|| Some(format!("tab {}", tab_index + 1)));
-                    break;
+                self.summarizer.note_loaded(self.io.event_log.loaded);
+                self.record_session_context();
+                if saved {
+                    self.write_heartbeat();
+                }
+                if alerting {
+                    self.handle_save_alert();
+                }
+                self.apply_config();
+                self.refresh_work_blocks();
+                true
+            }
+            IoEvent::ConfigLoaded => {
+                self.apply_config();
+                true
+            }
+            IoEvent::Probed { finished } => {
+                if finished {
+                    self.load_event_log_when_ready();
                 }
+                self.ui.doctor
+            }
+            IoEvent::PowerChecked(handled) => {
+                self.apply_power_profile();
+                handled
+            }
+            IoEvent::Summaries(loaded) => {
+                self.summarizer.reload(loaded);
+                self.ui
+                    .fit_page(self.summarizer.summaries.page_count(SUMMARY_PAGE_SIZE));
+                true
+            }
+            IoEvent::History(history) => self.ui.load_history(history),
+            IoEvent::Log(event) => {
+                self.recorder.log_event(event, &self.config, now);
+                self.dispatch_recorded();
+                true
+            }
+            IoEvent::CommandFinished(command, outcome) => {
+                self.recorder
+                    .command_finished(command, outcome, &self.config, now);
+                self.dispatch_recorded();
+                true
+            }
+            IoEvent::SessionContext => {
+                self.record_session_context();
+                false
+            }
+            IoEvent::Backfilled => {
+                self.refresh_work_blocks();
+                true
             }
-            if new_focus.is_some() {
-                break;
+        }
+    }
+
+    fn handle_discovery_result(&mut self, output: &CommandOutput) -> bool {
+        let was_ready = matches!(
+            self.discovery.phase,
+            crumbeez_lib::DiscoveryPhase::Ready { .. }
+        );
+        let handled = self.discovery.handle_command_result(
+            output.exit_code,
+            &output.stdout,
+            &output.stderr,
+            &output.context,
+        );
+        if !was_ready
+            && matches!(
+                self.discovery.phase,
+                crumbeez_lib::DiscoveryPhase::Ready { .. }
+            )
+        {
+            self.handle_discovery_ready();
+        }
+        if std::mem::take(&mut self.discovery.stale) {
+            self.rediscover(None);
+        }
+        // A viewer leaves setting up the project to whoever records.
+        if !self.plugin_config.viewer {
+            self.ui.maybe_start_onboarding(&self.discovery);
+        }
+        self.ensure_discovery_tick();
+        handled
+    }
+
+    fn expire_commands(&mut self) {
+        for expired in command_router::expire(&self.host) {
+            match expired {
+                Expired::Retrying { context, attempt } => {
+                    self.discovery.handle_retry(&context, attempt);
+                }
+                Expired::GaveUp(context) => {
+                    self.handle_command_output(CommandOutput {
+                        exit_code: None,
+                        stdout: Vec::new(),
+                        stderr: b"timed out".to_vec(),
+                        context,
+                    });
+                }
             }
         }
+    }
 
-        let Some((tab_index, pane)) = new_focus else {
+    /// Summarize what the previous run left unsummarized when it stopped,
+    /// if it did, before anything from this run joins it.
+    fn recover(&mut self) {
+        if !self.is_recorder() || self.is_demo() {
+            return;
+        }
+        let Some(remnants) = self.summarizer.remnants(&self.event_log) else {
             return;
         };
+        warn!(
+            events = remnants.events,
+            last_ms = remnants.last_ms,
+            "Previous run stopped without summarizing, recovering"
+        );
+        self.start_summary(SummaryTrigger::Recovery);
+        self.toast(Severity::Warn, "recovered an unsummarized session");
+    }
 
-        let new_fp = FocusedPane {
-            tab_index,
-            pane_id: pane.id,
-            is_plugin: pane.is_plugin,
-        };
+    /// Summarize everything not yet summarized, at a context boundary such
+    /// as a bookmark.
+    fn summarize_unconsumed(&mut self, trigger: SummaryTrigger) {
+        debug!("summarize_unconsumed called");
+        if !self.is_recorder() {
+            return;
+        }
+        self.recorder.flush(&self.config, self.current_time_ms());
+        self.dispatch_recorded();
+        self.summarize_logged(trigger);
+    }
+
+    /// Summarize what's in the log and not yet summarized, once the
+    /// recorder has logged everything it held back.
+    fn summarize_logged(&mut self, trigger: SummaryTrigger) {
+        if !self.is_recorder() {
+            return;
+        }
+        let unconsumed = self.event_log.unconsumed_count();
+        if unconsumed > 0 {
+            info!(count = unconsumed, "Context boundary, summarizing events");
+            self.start_summary(trigger);
+            self.prune_expired_events();
+            self.refresh_work_blocks();
+            self.io
+                .event_log
+                .save(self.discovery.initial_cwd.clone(), &self.event_log);
+        }
+    }
 
-        if self.focused_pane.as_ref() == Some(&new_fp) {
+    /// Summarize activity that's been quiet for the inactivity timeout,
+    /// if there's been any since the last summary.
+    fn summarize_if_quiet(&mut self) {
+        if !self.is_recorder() || !self.summarizer.is_quiet(self.inactivity_timeout()) {
+            debug!("Skipping summary - no new activity since last summary");
             return;
         }
+        let now = self.current_time_ms();
+        self.recorder.seal_pending_text(&self.config, now);
+        self.recorder.record_filtered(&self.config, now);
+        self.dispatch_recorded();
+        if self.event_log.unconsumed_count() > 0 {
+            self.start_summary(SummaryTrigger::Inactivity);
+            self.prune_expired_events();
+            self.refresh_work_blocks();
+            self.io
+                .event_log
+                .save(self.discovery.initial_cwd.clone(), &self.event_log);
+        }
+        self.summarizer.note_summarized();
+    }
 
-        debug!(
-            from = ?self.focused_pane,
-            to = ?new_fp,
-            "Pane focus changed"
+    /// Drop and compact old entries per the retention config, rewriting
+    /// the log if that shrank it.
+    fn prune_expired_events(&mut self) {
+        let now = self.current_time_ms();
+        let shrunk = self
+            .summarizer
+            .prune(&mut self.event_log, &self.config.retention, now);
+        if shrunk {
+            self.io
+                .event_log
+                .rewrite(self.discovery.initial_cwd.clone(), &self.event_log);
+        }
+    }
+
+    /// Recompute today's work blocks, unless the power profile skips
+    /// analytics.
+    fn refresh_work_blocks(&mut self) {
+        if self.power_profile().analytics {
+            self.summarizer
+                .refresh_work_blocks(&self.event_log, self.current_time_ms());
+        }
+    }
+
+    /// Hand the unconsumed events to the summary worker, and note the
+    /// day's totals in the cross-project index.
+    fn start_summary(&mut self, trigger: SummaryTrigger) {
+        let now = self.current_time_ms();
+        let summarized = self.summarizer.summarize(
+            &mut self.event_log,
+            now,
+            &self.config,
+            self.io.project_name.name.as_ref(),
+            trigger,
         );
+        if summarized && !self.is_demo() {
+            self.record_day();
+        }
+    }
 
-        // Trigger summary when switching away from a pane that had activity
-        if self.current_pane_has_activity {
-            self.trigger_summary_for_pane_switch();
+    #[cfg(feature = "analytics")]
+    fn record_day(&mut self) {
+        if self.power_profile().analytics {
+            self.io
+                .record_day(&self.discovery, &self.event_log, self.current_time_ms());
         }
+    }
 
-        // Switch to new pane and reset activity flag
-        self.focused_pane = Some(new_fp);
-        self.current_pane_has_activity = false;
+    #[cfg(not(feature = "analytics"))]
+    fn record_day(&mut self) {}
 
-        let event = KeystrokeEvent::PaneFocused(PaneFocusedEvent {
-            tab_name: focused_tab_name,
-            pane_title: pane.title.clone(),
-            command: pane.terminal_command.clone(),
-            is_plugin: pane.is_plugin,
-        });
-        info!(%event);
-        self.log_event(event);
+    /// Show a summary back from the worker, and file it in the data dir.
+    fn push_summary(&mut self, summary: FinishedSummary) {
+        self.summarizer.add(&summary);
+        if self.is_demo() {
+            return;
+        }
+        if self
+            .io
+            .file_summary(&summary, &self.discovery, self.config.superproject)
+        {
+            self.toast(Severity::Info, "summary written");
+        }
     }
 
-    fn trigger_summary_for_pane_switch(&mut self) {
-        debug!("trigger_summary_for_pane_switch called");
-        self.seal_pending_text();
-        let unconsumed = self.event_log.unconsumed_count();
-        if unconsumed > 0 {
-            info!(
-                count = unconsumed,
-                "Pane switch trigger, summarizing events"
-            );
-            if let Some(summary) = event_log_io::generate_summary(&mut self.event_log) {
-                self.pending_summaries.push(summary);
-                if self.pending_summaries.len() > 10 {
-                    self.pending_summaries.remove(0);
-                }
-            }
-            if let Ok(data) = self.event_log.serialize() {
-                self.event_log_io
-                    .save(self.discovery.initial_cwd.clone(), data);
-            } else {
-                error!("Failed to serialize event log");
+    /// Log `marker` for a tab closing or the session being renamed, and
+    /// summarize the work it ends, unless `boundary_summaries` is off.
+    fn work_boundary(&mut self, marker: KeystrokeEvent, trigger: SummaryTrigger) {
+        if !self.plugin_config.boundary_summaries
+            || self.recorder.suspended.is_some()
+            || self.is_demo()
+        {
+            return;
+        }
+        info!(%marker, "Work boundary");
+        let had_activity = self.event_log.unconsumed_count() > 0 || self.recorder.live.is_some();
+        self.recorder
+            .seal_and_log(marker, &self.config, self.current_time_ms());
+        self.dispatch_recorded();
+        // A marker alone isn't worth a summary; it heads the next one.
+        if had_activity {
+            self.summarize_unconsumed(trigger);
+        }
+    }
+
+    /// Write a report on the last day's activity to the summaries dir.
+    fn write_standup(&mut self) {
+        let now = self.current_time_ms();
+        self.recorder.seal_pending_text(&self.config, now);
+        self.dispatch_recorded();
+        self.io.write_standup(
+            self.discovery.data_dir(),
+            &self.event_log,
+            &self.config,
+            now,
+            &self.discovery.initial_cwd,
+        );
+    }
+
+    /// Roll up the week containing `payload` (`YYYY-MM-DD`), or this week,
+    /// with everything so far summarized.
+    fn write_rollup(&mut self, payload: Option<&str>) {
+        #[cfg(feature = "analytics")]
+        self.summarize_unconsumed(SummaryTrigger::Report);
+        self.io.write_rollup(payload, self.current_time_ms());
+    }
+
+    /// Answer a `crumbeez::context` request with recent activity, the text
+    /// being typed included.
+    fn send_context(&mut self, request: &PipeMessage) {
+        self.recorder
+            .seal_pending_text(&self.config, self.current_time_ms());
+        self.dispatch_recorded();
+        self.io.send_context(request, &self.event_log, &self.config);
+    }
+
+    /// Open the summary browser and read every day's summaries into it, or
+    /// close it if it's open.
+    fn toggle_browser(&mut self) {
+        let Some(dir) = self.discovery.data_dir() else {
+            warn!("No data dir yet to browse summaries in");
+            self.ui.browser = None;
+            return;
+        };
+        if self.ui.toggle_browser() {
+            self.io
+                .summaries
+                .load_history(dir, self.discovery.initial_cwd.clone());
+        }
+    }
+
+    /// Drop a bookmark.  Activity so far is summarized first, so the bookmark
+    /// headlines the summary of whatever comes after it.
+    fn add_annotation(&mut self, name: String) {
+        info!(%name, "Bookmark");
+        self.summarize_unconsumed(SummaryTrigger::Bookmark);
+        // Keep the timer from summarizing the bookmark on its own before any
+        // new activity arrives.
+        self.summarizer.note_summarized();
+        self.recorder
+            .log_bookmark(name, &self.config, self.current_time_ms());
+        self.dispatch_recorded();
+        self.io
+            .event_log
+            .save(self.discovery.initial_cwd.clone(), &self.event_log);
+    }
+
+    /// Set the session's tags to those in `text`, or clear them if there
+    /// are none.
+    fn tag_session(&mut self, text: Option<&str>) -> Result<(), String> {
+        let tags = crumbeez_lib::parse_tags(text.unwrap_or_default())?;
+        if !self.summarizer.set_tags(tags.clone()) {
+            return Ok(());
+        }
+        info!(?tags, "Session tagged");
+        self.recorder.record(
+            KeystrokeEvent::SessionTagged(tags),
+            &self.config,
+            self.current_time_ms(),
+        );
+        self.dispatch_recorded();
+        self.io
+            .event_log
+            .save(self.discovery.initial_cwd.clone(), &self.event_log);
+        Ok(())
+    }
+
+    /// Feed the demo keys due by now through the recorder, the way
+    /// intercepted ones are.  Returns whether any were.
+    #[cfg(feature = "demo")]
+    fn play_demo(&mut self) -> bool {
+        let Some(ref mut player) = self.demo else {
+            return false;
+        };
+        let now = self.current_time_ms();
+        let due = player.take_due(now);
+        let played = !due.is_empty();
+        for event in due {
+            self.recorder.play_demo(event, &self.config, now);
+            self.dispatch_recorded();
+        }
+        played
+    }
+
+    #[cfg(not(feature = "demo"))]
+    fn play_demo(&mut self) -> bool {
+        false
+    }
+
+    fn handle_pane_update(&mut self, manifest: PaneManifest) {
+        let my_plugin_id = self.host.get_plugin_ids().plugin_id;
+        self.ui.follow_own_pane(&manifest, my_plugin_id);
+        if !self.plugin_config.viewer && !self.is_demo() {
+            let instances = manifest
+                .panes
+                .values()
+                .flatten()
+                .filter(|pane| pane.is_plugin && is_crumbeez_url(pane.plugin_url.as_deref()))
+                .map(|pane| pane.id);
+            for peer in self.election.set_panes(instances) {
+                send_hello(&self.host, peer);
             }
+            self.apply_role();
         }
+        // The demo's panes stand in for the real ones.
+        if self.is_demo() {
+            return;
+        }
+        self.recorder.handle_pane_update(
+            &manifest,
+            my_plugin_id,
+            &self.tab_names,
+            &self.plugin_config.exclude,
+            &self.config,
+            self.current_time_ms(),
+        );
+        self.dispatch_recorded();
     }
 }
 
+#[cfg(not(feature = "minimal"))]
 impl ZellijPlugin for State {
-    fn load(&mut self, _configuration: BTreeMap<String, String>) {
-        let _ = tracing_subscriber::fmt()
-            .with_writer(std::io::stderr)
-            .with_target(false)
-            .try_init();
+    fn load(&mut self, configuration: BTreeMap<String, String>) {
+        match PluginConfig::validate(&configuration) {
+            Ok(config) => {
+                self.recorder.flood = FloodGuard::new(config.flood_threshold);
+                self.plugin_config = config;
+                self.recorder.recording = self.is_recorder();
+                self.apply_power_profile();
+            }
+            Err(problems) => {
+                // Log at the default level, and don't start: running on
+                // defaults would hide the mistake until it mattered.
+                logging::init(&self.plugin_config.log);
+                for problem in &problems {
+                    error!(%problem, "Invalid plugin configuration");
+                }
+                self.config_problems = problems;
+                return;
+            }
+        }
+        logging::init(&self.plugin_config.log);
 
-        request_permission(&[
-            PermissionType::ReadApplicationState,
-            PermissionType::RunCommands,
-            // InterceptInput: receive every keystroke session-wide via
-            // InterceptedKeyPress.  We immediately re-forward each key back to
-            // the focused pane so the user's input is not swallowed.
-            PermissionType::InterceptInput,
-            // WriteToStdin: needed to forward the intercepted keys back.
-            PermissionType::WriteToStdin,
-        ]);
+        let platform = shell::Platform::detect(
+            self.plugin_config.platform,
+            &self.host.get_plugin_ids().initial_cwd,
+        );
+        debug!(?platform, "Host platform");
+        shell::set_platform(platform);
+        self.election = Election::new(self.host.get_plugin_ids().plugin_id);
+        self.io.rollup.prune_daily = self.plugin_config.prune_daily_summaries;
+
+        if self.plugin_config.viewer {
+            // Nothing is written, so the files are only watched for changes
+            // made by whoever is recording.
+            self.io.event_log.read_only = true;
+            self.host.watch_filesystem();
+        } else if self.is_demo() {
+            // Keys are played rather than intercepted, so nothing more is
+            // needed.
+            #[cfg(feature = "demo")]
+            {
+                self.demo = Some(demo::DemoPlayer::new(
+                    self.current_time_ms(),
+                    self.plugin_config.inactivity_timeout,
+                ));
+            }
+        }
+        self.host.request_permission(&self.required_permissions());
 
-        subscribe(&[
+        self.host.subscribe(&[
             // Key fires only when the plugin pane itself has focus.
             EventType::Key,
             // InterceptedKeyPress fires for every keystroke in any pane once
@@ -305,66 +1276,164 @@ impl ZellijPlugin for State {
             EventType::Timer,
             EventType::RunCommandResult,
             EventType::PermissionRequestResult,
+            // CustomMessage brings summaries back from the summary worker,
+            // and large writes from the event log worker.
+            EventType::CustomMessage,
+            // ModeUpdate and SessionUpdate: suspend logging while the
+            // session is locked or has no client attached.
+            EventType::ModeUpdate,
+            EventType::SessionUpdate,
+            // Visible: skip drawing a pane that can't be seen.
+            EventType::Visible,
         ]);
     }
 
     fn update(&mut self, event: Event) -> bool {
+        // Events that only feed the log, or that come whether or not anyone
+        // is looking, don't redraw a pane that can't be seen.  Keys the pane
+        // gets itself mean it's focused, and permission answers follow a
+        // prompt.
+        let background = !matches!(
+            event,
+            Event::Key(_) | Event::PermissionRequestResult(_) | Event::Visible(_)
+        );
+        let was_hidden = self.ui.hidden;
         let result = match event {
             Event::PermissionRequestResult(PermissionStatus::Granted) => {
-                self.permissions_granted = true;
-                let cwd = get_plugin_ids().initial_cwd;
-                info!(?cwd, "Permissions granted");
-                self.discovery.start(cwd);
-                intercept_key_presses();
+                self.handle_permissions_granted();
                 true
             }
             Event::PermissionRequestResult(PermissionStatus::Denied) => {
                 error!("Permissions denied");
+                self.permissions_granted = false;
                 self.discovery.phase =
                     root_discovery::DiscoveryPhase::Failed("Permissions denied".to_string());
                 true
             }
             Event::RunCommandResult(exit_code, stdout, stderr, context) => {
-                if self.event_log_io.handle_result(
-                    &context,
-                    &stdout,
+                if !command_router::accept(&self.host, &context) {
+                    return false;
+                }
+                self.handle_command_output(CommandOutput {
                     exit_code,
-                    &mut self.event_log,
-                ) {
-                    return true;
+                    stdout,
+                    stderr,
+                    context,
+                })
+            }
+            Event::InterceptedKeyPress(key) if self.ui.mark_prompt.is_some() => {
+                let outcome = self.ui.handle_mark_key(&key);
+                self.handle_key_outcome(outcome)
+            }
+            Event::InterceptedKeyPress(key) => {
+                let now = self.current_time_ms();
+                let mut bytes = Vec::with_capacity(PASTE_START.len() + MAX_KEY_SEQUENCE_LEN);
+                if self.recorder.bracketed_paste {
+                    self.recorder
+                        .paste
+                        .before_key(now, pastable(&key), &mut bytes);
                 }
-                let was_creating = matches!(
-                    self.discovery.phase,
-                    crumbeez_lib::DiscoveryPhase::CreatingDirs { .. }
-                );
-                let handled = self
-                    .discovery
-                    .handle_command_result(exit_code, &stdout, &stderr, &context);
-                if was_creating
-                    && matches!(
-                        self.discovery.phase,
-                        crumbeez_lib::DiscoveryPhase::Ready { .. }
-                    )
-                {
-                    self.handle_discovery_ready();
+                write_key_bytes(&key, self.recorder.cursor_keys, &mut bytes);
+                self.host.write(bytes);
+                let admitted = self.recorder.admit_key(KeySource::Intercepted, &key, now)
+                    && self.recorder.admit_flood(&self.config, now);
+                self.dispatch_recorded();
+                if !admitted {
+                    return false;
                 }
-                handled
+                match plain_char(&key) {
+                    Some(c)
+                        if self.recorder.reinterpreter.passes_text()
+                            && !self.recorder.chords.is_active() =>
+                    {
+                        debug!(?c, "key event");
+                        self.recorder.log_char(c, now);
+                    }
+                    _ => {
+                        let event = self.recorder.reinterpreter.apply(classify(&key));
+                        debug!(%event, "key event");
+                        if self.recorder.chords.is_active() {
+                            for event in self.recorder.chords.feed(event, now) {
+                                self.recorder.log_key(event, &self.config, now);
+                            }
+                        } else {
+                            self.recorder.log_event(event, &self.config, now);
+                        }
+                    }
+                }
+                self.dispatch_recorded();
+                true
             }
-            Event::InterceptedKeyPress(key) => {
-                let bytes = key_to_bytes(&key);
-                write(bytes);
-                let event = classify(&key);
-                debug!(%event, "key event");
-                self.log_event(event);
+            Event::Key(key) if self.ui.onboarding.is_some() => {
+                let outcome = self.ui.handle_onboarding_key(&key);
+                self.handle_key_outcome(outcome)
+            }
+            Event::Key(key) if self.ui.mark_prompt.is_some() => {
+                let outcome = self.ui.handle_mark_key(&key);
+                self.handle_key_outcome(outcome)
+            }
+            Event::Key(key) if self.ui.browser.is_some() => {
+                self.ui.handle_browser_key(&key, &self.config)
+            }
+            Event::Key(key) if self.ui.calendar.is_some() => {
+                self.ui
+                    .handle_calendar_key(&key, &self.io.rollup, self.discovery.roots.first())
+            }
+            Event::Key(key) if self.ui.search.is_some() => {
+                self.ui.handle_search_key(&key, &self.event_log)
+            }
+            Event::Key(key) if self.ui.doctor && key.bare_key == BareKey::Esc => {
+                self.ui.doctor = false;
+                true
+            }
+            Event::Key(key)
+                if (self.ui.doctor || !self.permissions_granted)
+                    && key.key_modifiers.is_empty()
+                    && key.bare_key == BareKey::Char('p') =>
+            {
+                self.rerequest_permissions();
+                true
+            }
+            Event::Key(key)
+                if key.key_modifiers.is_empty()
+                    && matches!(key.bare_key, BareKey::PageUp | BareKey::PageDown)
+                    && self.summarizer.summaries.page_count(SUMMARY_PAGE_SIZE) > 1 =>
+            {
+                // Page through the summaries rather than log the key.
+                self.ui.turn_page(
+                    key.bare_key,
+                    self.summarizer.summaries.page_count(SUMMARY_PAGE_SIZE),
+                );
+                true
+            }
+            Event::Key(key) if plain_char(&key) == Some('/') => {
+                self.ui.open_search();
                 true
             }
+            Event::Key(key) if plain_char(&key) == Some('y') => {
+                self.ui
+                    .copy_summary(&self.summarizer.summaries, &self.config);
+                true
+            }
+            Event::Key(_) if self.is_demo() => false,
             Event::Key(key) => {
+                let now = self.current_time_ms();
+                if !self.recorder.admit_key(KeySource::Focused, &key, now) {
+                    return false;
+                }
                 let event = classify(&key);
                 debug!(%event, "key event (plugin focused)");
-                self.log_event(event);
+                self.recorder.log_event(event, &self.config, now);
+                self.dispatch_recorded();
                 true
             }
             Event::TabUpdate(tabs) => {
+                let open: Vec<String> = tabs.iter().map(|t| t.name.clone()).collect();
+                for name in closed_tabs(&self.open_tabs, &open) {
+                    self.work_boundary(KeystrokeEvent::TabClosed(name), SummaryTrigger::TabClose);
+                }
+                self.open_tabs = open;
+                self.ui.follow_tabs(&tabs);
                 self.tab_names = tabs
                     .into_iter()
                     .filter(|t| !t.name.is_empty())
@@ -376,176 +1445,300 @@ impl ZellijPlugin for State {
                 self.handle_pane_update(manifest);
                 true
             }
+            Event::Timer(elapsed) if self.discovery_tick_armed => {
+                // Either timer may have fired; whichever is still due gets
+                // armed again at the end of the update.
+                self.discovery_tick_armed = false;
+                self.timer_due = None;
+                self.expire_commands();
+                self.ensure_discovery_tick();
+                if !self.discovery_tick_armed
+                    && matches!(
+                        self.discovery.phase,
+                        crumbeez_lib::DiscoveryPhase::Ready { .. }
+                    )
+                {
+                    debug!(elapsed_secs = ?elapsed, "Discovery settled, starting inactivity timer");
+                }
+                true
+            }
+            Event::Timer(elapsed) if self.recorder.suspended.is_some() => {
+                // Let the timer lapse; resuming re-arms it.
+                debug!(elapsed_secs = ?elapsed, "Timer fired while suspended");
+                self.timer_due = None;
+                self.expire_commands();
+                false
+            }
             Event::Timer(elapsed) => {
                 debug!(elapsed_secs = ?elapsed, "Timer fired");
+                self.timer_due = None;
+                let now = self.current_time_ms();
+                self.recorder.finish_paste(now);
+                self.recorder.record_flood(&self.config, now);
+                self.recorder.record_title(&self.config, now);
+                self.dispatch_recorded();
+                self.ui.toasts.expire(now);
+                self.io
+                    .atuin
+                    .poll_if_due(now, self.discovery.initial_cwd.clone());
 
-                // Check if we've been inactive for the threshold AND there's new activity since last summary
-                let should_summarize = self.last_activity_time.is_some_and(|last| {
-                    let inactive_duration = SystemTime::now().duration_since(last);
-                    inactive_duration
-                        .map(|d| d.as_secs_f64() >= INACTIVITY_TIMER_SECS)
-                        .unwrap_or(false)
-                }) && self.last_summary_time.is_none_or(|last_summary| {
-                    self.last_activity_time
-                        .is_some_and(|last_activity| last_activity > last_summary)
-                });
-
-                if should_summarize {
-                    self.seal_pending_text();
-                    let unconsumed = self.event_log.unconsumed_count();
-                    if unconsumed > 0 {
-                        if let Some(summary) = event_log_io::generate_summary(&mut self.event_log) {
-                            self.pending_summaries.push(summary);
-                            if self.pending_summaries.len() > 10 {
-                                self.pending_summaries.remove(0);
-                            }
-                        }
-                        if let Ok(data) = self.event_log.serialize() {
-                            self.event_log_io
-                                .save(self.discovery.initial_cwd.clone(), data);
-                        } else {
-                            error!("Failed to serialize event log");
-                        }
-                        self.last_summary_time = Some(SystemTime::now());
+                self.summarize_if_quiet();
+                self.io
+                    .event_log
+                    .flush_if_due(self.discovery.initial_cwd.clone(), &self.event_log);
+                self.io.log_file.flush(self.discovery.initial_cwd.clone());
+                self.validate_dir_if_due();
+                self.check_power_if_due(self.discovery.initial_cwd.clone());
+                self.expire_commands();
+                true
+            }
+            Event::CustomMessage(message, payload) if message == CHUNKED_WRITE => {
+                self.io.event_log.handle_message(&payload);
+                false
+            }
+            Event::CustomMessage(message, payload) if message == SUMMARY_DONE => {
+                match self.summarizer.worker.handle_message(&payload) {
+                    Some(summary) => {
+                        self.push_summary(summary);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Event::ModeUpdate(mode_info) => {
+                if let Some(name) = mode_info.session_name {
+                    let renamed = self.session_name.as_ref().is_some_and(|old| *old != name);
+                    self.session_name = Some(name.clone());
+                    if renamed {
+                        self.work_boundary(
+                            KeystrokeEvent::SessionRenamed(name),
+                            SummaryTrigger::SessionRename,
+                        );
                     }
-                } else {
-                    debug!("Skipping summary - no new activity since last summary");
                 }
-                self.reset_inactivity_timer();
+                self.recorder.handle_mode_update(
+                    mode_info.mode,
+                    !self.is_demo(),
+                    &self.config,
+                    self.current_time_ms(),
+                );
+                self.dispatch_recorded();
+                true
+            }
+            Event::SessionUpdate(sessions, _) => {
+                if let Some(session) = sessions.iter().find(|s| s.is_current_session) {
+                    self.recorder.set_detached(
+                        session.connected_clients == 0,
+                        &self.config,
+                        self.current_time_ms(),
+                    );
+                    self.dispatch_recorded();
+                }
                 true
             }
+            Event::FileSystemUpdate(paths) if self.plugin_config.viewer => {
+                let paths: Vec<PathBuf> = paths.into_iter().map(|(path, _)| path).collect();
+                self.io.refresh_viewer(
+                    viewer::Changes::of(&paths),
+                    self.discovery.data_dir(),
+                    self.current_time_ms(),
+                    &self.discovery.initial_cwd,
+                );
+                false
+            }
             Event::FileSystemUpdate(_) => true,
+            Event::Visible(visible) => {
+                self.ui.hidden = !visible;
+                visible
+            }
             _ => false,
         };
 
-        result
+        let played = self.play_demo();
+        self.schedule_wakeup();
+        // Coming into view draws whatever changed while hidden.
+        let appeared = was_hidden && !self.ui.hidden;
+        appeared
+            || self.ui.worth_rendering(
+                result || played,
+                background,
+                self.power_profile().render_hidden,
+            )
     }
 
-    fn render(&mut self, rows: usize, cols: usize) {
-        println!("crumbeez — breadcrumb logger");
-        println!();
-        println!("Root discovery: {}", self.discovery.phase);
-
-        if let Some(ref git_root) = self.discovery.git_root {
-            println!("  git root: {}", git_root.display());
+    fn pipe(&mut self, pipe_message: PipeMessage) -> bool {
+        if pipe_message.name == HELLO_PIPE {
+            let PipeSource::Plugin(peer) = pipe_message.source else {
+                return false;
+            };
+            // Viewers and demos stay out of the election.
+            if self.plugin_config.viewer || self.is_demo() {
+                return false;
+            }
+            if self.election.hello(peer) {
+                send_hello(&self.host, peer);
+            }
+            self.apply_role();
+            return true;
         }
-        if let Some(ref parent) = self.discovery.parent_git_root {
-            println!("  parent repo: {}", parent.display());
+        // Each instance has its own doctor report and permissions.
+        let own = [DOCTOR_PIPE, PERMISSIONS_PIPE].contains(&pipe_message.name.as_str());
+        if !self.is_recorder() && !own {
+            // Every instance gets these; the recorder answers them.
+            if let PipeSource::Cli(_) = pipe_message.source {
+                self.host.unblock_cli_pipe_input(&pipe_message.name);
+            }
+            return false;
         }
-
-        println!();
-        println!("─── Event Log ─────────────────────────────────────────");
-        println!(
-            "  Total: {} events, {} unconsumed",
-            self.event_log.total_count(),
-            self.event_log.unconsumed_count()
-        );
-
-        if !self.pending_summaries.is_empty() {
-            println!();
-            println!("─── Summaries ─────────────────────────────────────────");
-            for summary in &self.pending_summaries {
-                for line in summary.lines() {
-                    let truncated = if cols > 4 && line.chars().count() > cols {
-                        let mut s: String = line.chars().take(cols - 1).collect();
-                        s.push('…');
-                        s
-                    } else {
-                        line.to_string()
-                    };
-                    println!("{}", truncated);
-                }
-            }
-        }
-
-        println!();
-        println!("─── Keystroke Activity ───────────────────────────────");
-
-        let events = self.keystroke_activity.events();
-        if events.is_empty() {
-            println!("  (no keystrokes yet)");
-        } else {
-            let available_lines = rows.saturating_sub(15).max(1);
-            let skip = events.len().saturating_sub(available_lines);
-            for event in events.iter().skip(skip) {
-                let line = format!("  {}", event);
-                let truncated = if cols > 4 && line.chars().count() > cols {
-                    let mut s: String = line.chars().take(cols - 1).collect();
-                    s.push('…');
-                    s
-                } else {
-                    line
+        match pipe_message.name.as_str() {
+            MARK_PIPE => match pipe_message.payload.as_deref().and_then(clean_mark) {
+                Some(name) => self.add_annotation(name),
+                None => {
+                    debug!("Opening bookmark prompt");
+                    self.ui.mark_prompt = Some(MarkPrompt::default());
+                }
+            },
+            DOCTOR_PIPE => self.ui.doctor = !self.ui.doctor,
+            PERMISSIONS_PIPE => self.rerequest_permissions(),
+            BROWSE_PIPE => self.toggle_browser(),
+            CALENDAR_PIPE => {
+                if self.ui.toggle_calendar(self.current_time_ms()) {
+                    self.io.load_calendar();
+                }
+            }
+            REDISCOVER_PIPE => self.rediscover(
+                pipe_message
+                    .payload
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|cwd| !cwd.is_empty())
+                    .map(PathBuf::from),
+            ),
+            STANDUP_PIPE => self.write_standup(),
+            PR_PIPE => {
+                let pipe_id = match pipe_message.source {
+                    PipeSource::Cli(ref pipe_id) => Some(pipe_id.as_str()),
+                    _ => None,
                 };
-                println!("{}", truncated);
+                let create = pipe_message.args.get("create").map(String::as_str) == Some("true");
+                if self.io.request_pull_request(
+                    pipe_id,
+                    pipe_message.payload.as_deref(),
+                    create,
+                    self.discovery.data_dir(),
+                    &self.event_log,
+                    &self.discovery.initial_cwd,
+                ) {
+                    return false;
+                }
             }
+            SNAPSHOT_PIPE => self.io.take_snapshot(
+                self.recorder.current_pane_excluded,
+                self.session_name.as_deref(),
+                self.discovery.data_dir(),
+                self.current_time_ms(),
+                &self.discovery.initial_cwd,
+            ),
+            HISTORY_PIPE => self.io.import_history(
+                pipe_message.payload.as_deref(),
+                self.discovery.data_dir(),
+                &self.event_log,
+                &self.discovery.initial_cwd,
+            ),
+            ROLLUP_PIPE => self.write_rollup(pipe_message.payload.as_deref()),
+            CONTEXT_PIPE => self.send_context(&pipe_message),
+            COPY_PIPE => self
+                .ui
+                .copy_summary(&self.summarizer.summaries, &self.config),
+            TAG_PIPE => {
+                if let Err(problem) = self.tag_session(pipe_message.payload.as_deref()) {
+                    warn!(%problem, "Bad tags");
+                    if let PipeSource::Cli(ref pipe_id) = pipe_message.source {
+                        self.host
+                            .cli_pipe_output(pipe_id, &format!("error: {}\n", problem));
+                    }
+                }
+            }
+            SUBSCRIBE_PIPE | UNSUBSCRIBE_PIPE => match pipe_message.source {
+                PipeSource::Plugin(plugin_id) if pipe_message.name == SUBSCRIBE_PIPE => {
+                    self.subscribers.subscribe(plugin_id)
+                }
+                PipeSource::Plugin(plugin_id) => self.subscribers.unsubscribe(plugin_id),
+                _ => warn!(name = %pipe_message.name, "Only plugins can subscribe"),
+            },
+            SEARCH_PIPE => match pipe_message.source {
+                PipeSource::Cli(ref pipe_id) => {
+                    if self.io.search_summaries(
+                        pipe_id,
+                        pipe_message.payload.as_deref(),
+                        self.discovery.data_dir(),
+                        &self.discovery.initial_cwd,
+                    ) {
+                        return false;
+                    }
+                }
+                _ => warn!("Only `zellij pipe` can search summaries"),
+            },
+            ISSUE_PIPE => match pipe_message.source {
+                PipeSource::Cli(ref pipe_id) => {
+                    if self.io.look_up_issue(
+                        pipe_id,
+                        pipe_message.payload.as_deref(),
+                        self.discovery.data_dir(),
+                        &self.discovery.initial_cwd,
+                    ) {
+                        return false;
+                    }
+                }
+                _ => warn!("Only `zellij pipe` can look up issues"),
+            },
+            TAIL_PIPE => match pipe_message.source {
+                // Left blocked, so `zellij pipe` keeps printing what's sent.
+                PipeSource::Cli(pipe_id) => {
+                    self.subscribers.tail(pipe_id);
+                    return false;
+                }
+                _ => warn!("Only `zellij pipe` can tail"),
+            },
+            _ => return false,
         }
-    }
-}
-
-fn prev_char_boundary(s: &str, pos: usize) -> usize {
-    if pos == 0 {
-        return 0;
-    }
-    let mut p = pos - 1;
-    while p > 0 && !s.is_char_boundary(p) {
-        p -= 1;
-    }
-    p
-}
-
-fn next_char_boundary(s: &str, pos: usize) -> usize {
-    if pos >= s.len() {
-        return s.len();
-    }
-    let mut p = pos + 1;
-    while p < s.len() && !s.is_char_boundary(p) {
-        p += 1;
-    }
-    p
-}
-
-fn word_left(s: &str, pos: usize) -> usize {
-    let chars_before: Vec<(usize, char)> = s[..pos].char_indices().collect();
-    if chars_before.is_empty() {
-        return 0;
-    }
-    let mut iter = chars_before.iter().rev();
-    for &(_, c) in iter.by_ref() {
-        if c.is_alphanumeric() || c == '_' {
-            break;
-        }
-    }
-    for &(i, c) in iter {
-        if !c.is_alphanumeric() && c != '_' {
-            return next_char_boundary(s, i);
+        if let PipeSource::Cli(_) = pipe_message.source {
+            self.host.unblock_cli_pipe_input(&pipe_message.name);
         }
+        self.schedule_wakeup();
+        self.ui
+            .worth_rendering(true, false, self.power_profile().render_hidden)
     }
-    0
-}
 
-fn word_right(s: &str, pos: usize) -> usize {
-    let chars_after: Vec<(usize, char)> =
-        s[pos..].char_indices().map(|(i, c)| (pos + i, c)).collect();
-    if chars_after.is_empty() {
-        return s.len();
-    }
-    let mut iter = chars_after.iter();
-    let mut found_word = false;
-    for &(_i, c) in iter.by_ref() {
-        if c.is_alphanumeric() || c == '_' {
-            found_word = true;
-            break;
-        }
-    }
-    if !found_word {
-        return s.len();
-    }
-    for &(byte_i, c) in iter.by_ref() {
-        if !c.is_alphanumeric() && c != '_' {
-            return byte_i;
+    fn render(&mut self, rows: usize, cols: usize) {
+        if !self.config_problems.is_empty() {
+            ui::render_config_problems(&self.config_problems);
+            return;
         }
+        let view = View {
+            discovery: &self.discovery,
+            permissions_granted: self.permissions_granted,
+            role: self.role,
+            demo: self.is_demo(),
+            event_log: &self.event_log,
+            keystrokes: &self.recorder.keystroke_activity,
+            summarizer: &self.summarizer,
+            io: &self.io,
+            config: &self.config,
+            plugin_config: &self.plugin_config,
+            power: self.power_profile(),
+            inactivity_timeout: self.inactivity_timeout(),
+            now_ms: self.current_time_ms(),
+        };
+        self.ui.render(&view, rows, cols);
     }
-    s.len()
 }
 
+#[cfg(not(feature = "minimal"))]
 register_plugin!(State);
+#[cfg(not(feature = "minimal"))]
+register_worker!(EventLogWorker, event_log_worker, EVENT_LOG_WORKER);
+#[cfg(not(feature = "minimal"))]
+register_worker!(SummaryWorker, summary_worker, SUMMARY_WORKER);
+#[cfg(feature = "minimal")]
+register_plugin!(minimal::Minimal);
@@ -0,0 +1,105 @@
+//! Bookmarks: named annotations the user drops into the event stream.
+//!
+//! A bookmark arrives either as a pipe message with the name as payload:
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::mark -- "trying approach B"
+//! ```
+//!
+//! or, with no payload (typically from a keybind), opens a one-line prompt in
+//! the plugin pane.  While the prompt is open, keystrokes edit it instead of
+//! reaching the focused pane, and none of them are logged.
+//!
+//! The same prompt asks for the reason behind a risky command when the
+//! project's config has `risky annotate=true`.
+//!
+//! ```kdl
+//! keybinds {
+//!     shared {
+//!         bind "Alt b" { MessagePlugin "file:crumbeez.wasm" { name "crumbeez::mark"; }; }
+//!     }
+//! }
+//! ```
+
+use crumbeez_lib::KeystrokeEvent;
+use zellij_tile::prelude::*;
+
+use crate::keystroke::plain_char;
+
+/// Pipe message name that drops a bookmark.
+pub const MARK_PIPE: &str = "crumbeez::mark";
+
+/// Longest bookmark name kept, in characters.
+const MAX_MARK_LEN: usize = 120;
+
+/// Normalize a bookmark name: whitespace runs (including newlines) collapse
+/// to single spaces and overlong names are cut.  `None` if nothing is left.
+pub fn clean_mark(name: &str) -> Option<String> {
+    let name: String = name
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_MARK_LEN)
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+pub enum PromptOutcome {
+    /// The prompt is still open.
+    Editing,
+    Submit(String),
+    Cancel,
+}
+
+/// The one-line bookmark prompt.
+#[derive(Default)]
+pub struct MarkPrompt {
+    pub text: String,
+    /// The risky command the prompt asks the reason for, if it's not for a
+    /// bookmark.
+    pub risky: Option<RiskyCommand>,
+}
+
+/// A risky command waiting to be logged with its reason.
+#[derive(Debug)]
+pub struct RiskyCommand {
+    pub command: String,
+    pub pattern: String,
+}
+
+impl RiskyCommand {
+    pub fn into_event(self, reason: Option<String>) -> KeystrokeEvent {
+        KeystrokeEvent::RiskyCommand {
+            command: self.command,
+            pattern: self.pattern,
+            reason,
+        }
+    }
+}
+
+impl MarkPrompt {
+    pub fn handle_key(&mut self, key: &KeyWithModifier) -> PromptOutcome {
+        if let Some(c) = plain_char(key) {
+            if self.text.chars().count() < MAX_MARK_LEN {
+                self.text.push(c);
+            }
+            return PromptOutcome::Editing;
+        }
+        match key.bare_key {
+            BareKey::Enter => match clean_mark(&self.text) {
+                Some(name) => PromptOutcome::Submit(name),
+                None => PromptOutcome::Cancel,
+            },
+            BareKey::Esc => PromptOutcome::Cancel,
+            BareKey::Char('c') if key.key_modifiers.contains(&KeyModifier::Ctrl) => {
+                PromptOutcome::Cancel
+            }
+            BareKey::Backspace => {
+                self.text.pop();
+                PromptOutcome::Editing
+            }
+            _ => PromptOutcome::Editing,
+        }
+    }
+}
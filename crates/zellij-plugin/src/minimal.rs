@@ -0,0 +1,183 @@
+//! The discovery-only plugin, built with the `minimal` feature.
+//!
+//! It finds the project roots and creates their `.crumbeez` dirs the same
+//! way the full plugin does, using the same [`RootDiscovery`], and shows
+//! where they ended up.  Nothing is intercepted, logged, or summarized, so
+//! it's a quick way to check where a session's data would go, or to set up
+//! the data dirs ahead of time.  With no one to ask, first-run onboarding
+//! takes every root discovery found, and the project config is only read
+//! for where the `.gitignore` goes.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use tracing::{error, info};
+use zellij_tile::prelude::*;
+
+use crumbeez_lib::{DiscoveryPhase, ProjectConfig};
+
+use crate::command_router::{self, Expired};
+use crate::config_io::{self, ConfigIO};
+use crate::host::HostHandle;
+use crate::logging;
+use crate::plugin_config::{ConfigProblem, PluginConfig};
+use crate::root_discovery::RootDiscovery;
+use crate::shell;
+use crate::DISCOVERY_TICK_SECS;
+
+pub struct Minimal {
+    host: HostHandle,
+    discovery: RootDiscovery,
+    config_problems: Vec<ConfigProblem>,
+    config_io: ConfigIO,
+    /// The project config, once [`ConfigIO`] has read it.
+    config: ProjectConfig,
+    /// The project config has been asked for.
+    config_requested: bool,
+    /// A timer is pending to time out discovery commands.
+    tick_armed: bool,
+}
+
+impl Default for Minimal {
+    fn default() -> Self {
+        let host = HostHandle::default();
+        Self {
+            discovery: RootDiscovery::new(host.clone()),
+            config_problems: Vec::new(),
+            config_io: ConfigIO::new(host.clone()),
+            config: ProjectConfig::default(),
+            config_requested: false,
+            tick_armed: false,
+            host,
+        }
+    }
+}
+
+impl Minimal {
+    /// Move discovery along after a command result or a timeout: past
+    /// onboarding, on to the project config once it's ready, and to the
+    /// `.gitignore` once that's read.
+    fn settle(&mut self) {
+        if let DiscoveryPhase::AwaitingOnboarding { ref roots } = self.discovery.phase {
+            let roots = roots.clone();
+            info!(?roots, "No one to onboard, taking every root");
+            self.discovery.finish_onboarding(roots);
+        }
+        if let Some(dir) = self.discovery.data_dir() {
+            if !self.config_requested {
+                let paths = config_io::search_paths(
+                    self.discovery.roots.first().map(PathBuf::as_path),
+                    dir,
+                );
+                let cwd = self.discovery.initial_cwd.clone();
+                self.config_io.load(paths, cwd);
+                self.config_requested = true;
+            }
+        }
+        if self.config_io.loaded {
+            self.discovery.ensure_gitignore(self.config.gitignore);
+        }
+        if self.discovery.in_progress() && !self.tick_armed {
+            self.host.set_timeout(DISCOVERY_TICK_SECS);
+            self.tick_armed = true;
+        }
+    }
+}
+
+impl ZellijPlugin for Minimal {
+    fn load(&mut self, configuration: BTreeMap<String, String>) {
+        let config = match PluginConfig::validate(&configuration) {
+            Ok(config) => config,
+            Err(problems) => {
+                let config = PluginConfig::default();
+                logging::init(&config.log);
+                for problem in &problems {
+                    error!(%problem, "Invalid plugin configuration");
+                }
+                self.config_problems = problems;
+                return;
+            }
+        };
+        logging::init(&config.log);
+        shell::set_platform(shell::Platform::detect(
+            config.platform,
+            &self.host.get_plugin_ids().initial_cwd,
+        ));
+        self.host.request_permission(&[PermissionType::RunCommands]);
+        self.host.subscribe(&[
+            EventType::PermissionRequestResult,
+            EventType::RunCommandResult,
+            EventType::Timer,
+        ]);
+    }
+
+    fn update(&mut self, event: Event) -> bool {
+        match event {
+            Event::PermissionRequestResult(PermissionStatus::Granted) => {
+                if self.discovery.phase == DiscoveryPhase::AwaitingPermissions {
+                    self.discovery.start(self.host.get_plugin_ids().initial_cwd);
+                    self.settle();
+                }
+                true
+            }
+            Event::PermissionRequestResult(PermissionStatus::Denied) => {
+                error!("Permissions denied");
+                self.discovery.phase = DiscoveryPhase::Failed("Permissions denied".to_string());
+                true
+            }
+            Event::RunCommandResult(exit_code, stdout, stderr, context) => {
+                if !command_router::accept(&self.host, &context) {
+                    return false;
+                }
+                let handled =
+                    self.config_io
+                        .handle_result(&context, &stdout, exit_code, &mut self.config)
+                        || self
+                            .discovery
+                            .handle_command_result(exit_code, &stdout, &stderr, &context);
+                self.settle();
+                handled
+            }
+            Event::Timer(_) => {
+                self.tick_armed = false;
+                for expired in command_router::expire(&self.host) {
+                    match expired {
+                        Expired::Retrying { context, attempt } => {
+                            self.discovery.handle_retry(&context, attempt);
+                        }
+                        Expired::GaveUp(context) => {
+                            self.discovery
+                                .handle_command_result(None, &[], b"timed out", &context);
+                        }
+                    }
+                }
+                self.settle();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn render(&mut self, _rows: usize, _cols: usize) {
+        if !self.config_problems.is_empty() {
+            println!("crumbeez — configuration error");
+            println!();
+            for problem in &self.config_problems {
+                println!("  ✗ {}", problem);
+            }
+            return;
+        }
+        println!("{}", self.discovery.phase);
+        if let DiscoveryPhase::Ready { ref dirs } = self.discovery.phase {
+            for dir in dirs {
+                println!("  {}", dir.display());
+            }
+        }
+        if let Some(ref err) = self.config_io.error {
+            println!("⚠ config: {}", err);
+        }
+        for timeout in &self.discovery.timeouts {
+            println!("⚠ {}", timeout);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! The plugin's own settings, from its block in the Zellij layout or config.
+//!
+//! These are the settings needed before a project is discovered, or that
+//! belong to the user rather than to any one project (project policy lives in
+//! `.crumbeez/config.kdl`, see [`crumbeez_lib::ProjectConfig`]):
+//!
+//! ```kdl
+//! plugin location="file:crumbeez.wasm" {
+//!     log_level "debug"                // error, warn, info (default), debug, trace, off
+//!     log_file "true"                  // mirror to .crumbeez/plugin.log
+//!     debug_panel "true"               // defaults to on at debug/trace level
+//!     platform "auto"                  // unix, windows, or auto
+//!     inactivity_timeout "10s"         // quiet time before activity is summarized
+//!     boundary_summaries "true"        // summarize when a tab closes or the session is renamed
+//!     exclude "ssh* *password*"        // panes never logged, in every project
+//!     prune_daily_summaries "false"    // move past days' summaries into weekly rollups
+//!     flood_threshold "100"            // keys per second above which input is only counted
+//!     save_fallback "false"            // move to $XDG_DATA_HOME when saves keep failing
+//!     power "normal"                   // normal, low, or auto (low while on battery)
+//!     viewer "false"                   // only show a log another instance writes
+//!     demo "false"                     // replay canned activity instead of recording
+//!     toasts "warn error"              // severities flashed in the pane title, or "off"
+//! }
+//! ```
+//!
+//! Zellij hands the block over as a flat string map.  A typo there would
+//! otherwise go unnoticed until the setting turned out not to apply, so
+//! [`PluginConfig::validate`] rejects the whole map with every problem found,
+//! and the plugin shows them instead of running on defaults.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use crumbeez_lib::PowerMode;
+use tracing::level_filters::LevelFilter;
+
+use crate::logging::LogSettings;
+use crate::shell::Platform;
+use crate::toast::Severity;
+
+/// Every key the plugin understands.
+const KEYS: [&str; 14] = [
+    "log_level",
+    "log_file",
+    "debug_panel",
+    "platform",
+    "inactivity_timeout",
+    "boundary_summaries",
+    "exclude",
+    "prune_daily_summaries",
+    "flood_threshold",
+    "save_fallback",
+    "power",
+    "viewer",
+    "demo",
+    "toasts",
+];
+
+/// Default quiet time before activity is summarized.
+const DEFAULT_INACTIVITY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct PluginConfig {
+    pub log: LogSettings,
+    /// The host platform, or `None` to guess from the session's cwd.
+    pub platform: Option<Platform>,
+    pub inactivity_timeout: Duration,
+    /// Summarize when a tab closes or the session is renamed, as well as on
+    /// pane switches and inactivity.
+    pub boundary_summaries: bool,
+    /// Glob patterns for panes never logged, on top of each project's own
+    /// `exclude` list.
+    pub exclude: Vec<String>,
+    /// Move past days' summary files into the weekly rollup.
+    pub prune_daily_summaries: bool,
+    /// Keys per second above which intercepted input is counted rather
+    /// than logged.
+    pub flood_threshold: u32,
+    /// Move the project's data to fallback storage, as for a read-only
+    /// project, once saving the event log keeps failing.
+    pub save_fallback: bool,
+    /// How eagerly to do periodic work; see [`crumbeez_lib::PowerProfile`].
+    pub power: PowerMode,
+    /// Run as a read-only viewer; see [`crate::viewer`].
+    pub viewer: bool,
+    /// Replay canned activity instead of recording; see `crate::demo`.
+    /// Only builds with the `demo` feature accept it.
+    pub demo: bool,
+    /// Which notices to flash in the pane title; see [`crate::toast`].
+    pub toasts: Vec<Severity>,
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            log: LogSettings::default(),
+            platform: None,
+            inactivity_timeout: DEFAULT_INACTIVITY_TIMEOUT,
+            boundary_summaries: true,
+            exclude: Vec::new(),
+            prune_daily_summaries: false,
+            flood_threshold: crumbeez_lib::DEFAULT_FLOOD_THRESHOLD,
+            save_fallback: false,
+            power: PowerMode::default(),
+            viewer: false,
+            demo: false,
+            toasts: vec![Severity::Warn, Severity::Error],
+        }
+    }
+}
+
+/// One problem with the plugin configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigProblem {
+    /// A key the plugin doesn't use, with the closest known key if there's
+    /// a plausible one.
+    UnknownKey {
+        key: String,
+        suggestion: Option<&'static str>,
+    },
+    /// A value that doesn't parse as the key's type.
+    InvalidValue {
+        key: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+    /// An `exclude` pattern that wouldn't match what it looks like it should.
+    InvalidGlob { pattern: String, reason: String },
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownKey {
+                key,
+                suggestion: Some(suggestion),
+            } => write!(
+                f,
+                "unknown setting '{}' (did you mean '{}'?)",
+                key, suggestion
+            ),
+            Self::UnknownKey {
+                key,
+                suggestion: None,
+            } => write!(
+                f,
+                "unknown setting '{}' (known settings: {})",
+                key,
+                KEYS.join(", ")
+            ),
+            Self::InvalidValue {
+                key,
+                value,
+                expected,
+            } => write!(f, "{} is '{}', expected {}", key, value, expected),
+            Self::InvalidGlob { pattern, reason } => {
+                write!(f, "exclude pattern '{}': {}", pattern, reason)
+            }
+        }
+    }
+}
+
+impl PluginConfig {
+    /// Parse the configuration map.  Every problem is collected, so they can
+    /// all be fixed in one go.
+    pub fn validate(configuration: &BTreeMap<String, String>) -> Result<Self, Vec<ConfigProblem>> {
+        let mut config = Self::default();
+        let mut problems = Vec::new();
+        let mut invalid = |key, value: &str, expected| {
+            problems.push(ConfigProblem::InvalidValue {
+                key,
+                value: value.to_string(),
+                expected,
+            })
+        };
+
+        if let Some(level) = configuration.get("log_level") {
+            match level.parse() {
+                Ok(level) => config.log.level = level,
+                Err(_) => invalid(
+                    "log_level",
+                    level,
+                    "one of error, warn, info, debug, trace, or off",
+                ),
+            }
+        }
+        config.log.panel = config.log.level >= LevelFilter::DEBUG;
+        for (key, flag) in [
+            ("log_file", &mut config.log.file),
+            ("debug_panel", &mut config.log.panel),
+            ("boundary_summaries", &mut config.boundary_summaries),
+            ("prune_daily_summaries", &mut config.prune_daily_summaries),
+            ("save_fallback", &mut config.save_fallback),
+            ("viewer", &mut config.viewer),
+            ("demo", &mut config.demo),
+        ] {
+            match configuration.get(key).map(String::as_str) {
+                None => {}
+                Some("true") => *flag = true,
+                Some("false") => *flag = false,
+                Some(other) => invalid(key, other, "true or false"),
+            }
+        }
+        if config.demo && !cfg!(feature = "demo") {
+            invalid("demo", "true", "false, as this build has no demo mode");
+        } else if config.demo && config.viewer {
+            invalid("demo", "true", "false for a viewer");
+        }
+        match configuration.get("platform").map(String::as_str) {
+            None | Some("auto") => {}
+            Some("unix") => config.platform = Some(Platform::Unix),
+            Some("windows") => config.platform = Some(Platform::Windows),
+            Some(other) => invalid("platform", other, "unix, windows, or auto"),
+        }
+        if let Some(timeout) = configuration.get("inactivity_timeout") {
+            match parse_duration(timeout) {
+                Some(timeout) if !timeout.is_zero() => config.inactivity_timeout = timeout,
+                _ => invalid(
+                    "inactivity_timeout",
+                    timeout,
+                    "a duration such as 500ms, 10s, 2m, or 1h",
+                ),
+            }
+        }
+        if let Some(mode) = configuration.get("power") {
+            match PowerMode::from_name(mode) {
+                Some(mode) => config.power = mode,
+                None => invalid("power", mode, "normal, low, or auto"),
+            }
+        }
+        if let Some(threshold) = configuration.get("flood_threshold") {
+            match threshold.parse() {
+                Ok(threshold) if threshold > 0 => config.flood_threshold = threshold,
+                _ => invalid("flood_threshold", threshold, "a positive whole number"),
+            }
+        }
+        if let Some(severities) = configuration.get("toasts") {
+            let parsed: Option<Vec<Severity>> = match severities.trim() {
+                "off" => Some(Vec::new()),
+                severities => severities
+                    .split_whitespace()
+                    .map(Severity::from_name)
+                    .collect(),
+            };
+            match parsed {
+                Some(severities) => config.toasts = severities,
+                None => invalid(
+                    "toasts",
+                    severities,
+                    "info, warn, and error separated by spaces, or off",
+                ),
+            }
+        }
+
+        if let Some(patterns) = configuration.get("exclude") {
+            for pattern in patterns.split_whitespace() {
+                match crumbeez_lib::check_glob(pattern) {
+                    Ok(()) => config.exclude.push(pattern.to_string()),
+                    Err(reason) => problems.push(ConfigProblem::InvalidGlob {
+                        pattern: pattern.to_string(),
+                        reason,
+                    }),
+                }
+            }
+        }
+
+        for key in configuration.keys() {
+            if !KEYS.contains(&key.as_str()) {
+                problems.push(ConfigProblem::UnknownKey {
+                    key: key.clone(),
+                    suggestion: closest_key(key),
+                });
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// `500ms`, `10s`, `2m`, or `1h`.  A bare number is seconds.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().ok()?;
+    let ms_per_unit = match unit.trim() {
+        "ms" => 1,
+        "" | "s" => 1000,
+        "m" | "min" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        _ => return None,
+    };
+    Some(Duration::from_millis(number.checked_mul(ms_per_unit)?))
+}
+
+/// The known key within a few edits of `key`, for catching typos.
+fn closest_key(key: &str) -> Option<&'static str> {
+    KEYS.iter()
+        .map(|known| (edit_distance(key, known), *known))
+        .filter(|(distance, known)| *distance <= 2.max(known.len() / 4))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known)
+}
+
+/// Levenshtein distance between `a` and `b`, by character.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,96 @@
+//! Checking whether the host is on battery, for `power "auto"`.
+//!
+//! The power source is read with a shell command (see
+//! [`Platform::power_source`](crate::shell::Platform::power_source)) when
+//! permissions are granted, then at most every [`CHECK_INTERVAL`] while
+//! there's activity, so the check itself doesn't keep an idle laptop awake.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use tracing::{debug, info};
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+
+pub const CTX_PURPOSE: &str = "crumbeez_power_purpose";
+
+/// How often the power source is checked again.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug)]
+enum PowerCommand {
+    CheckPowerSource,
+}
+
+impl ContextTag for PowerCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::CheckPowerSource => "CheckPowerSource".to_string(),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match split_tag(tag) {
+            ("CheckPowerSource", None) => Some(Self::CheckPowerSource),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PowerIO {
+    host: HostHandle,
+    /// Whether the host was on battery at the last check, if it could
+    /// tell.
+    pub on_battery: Option<bool>,
+    last_check: Option<SystemTime>,
+}
+
+impl PowerIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Check the power source if it hasn't been within [`CHECK_INTERVAL`].
+    pub fn check_if_due(&mut self, cwd: PathBuf) {
+        let due = self.last_check.is_none_or(|last| {
+            self.host
+                .now()
+                .duration_since(last)
+                .is_ok_and(|e| e >= CHECK_INTERVAL)
+        });
+        if due {
+            self.last_check = Some(self.host.now());
+            let cmd = shell::platform().power_source();
+            command_router::run(&self.host, &PowerCommand::CheckPowerSource, &cmd, cwd);
+        }
+    }
+
+    /// Returns whether the result was a power source check.
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stdout: &[u8],
+        exit_code: Option<i32>,
+    ) -> bool {
+        let Some(PowerCommand::CheckPowerSource) = purpose_of::<PowerCommand>(context) else {
+            return false;
+        };
+        debug!(?exit_code, "CheckPowerSource result");
+        let on_battery = crumbeez_lib::on_battery(&String::from_utf8_lossy(stdout));
+        if on_battery != self.on_battery {
+            info!(?on_battery, "Power source changed");
+        }
+        self.on_battery = on_battery;
+        true
+    }
+}
@@ -0,0 +1,120 @@
+//! Working out the project's name from its config and files.  See
+//! [`crumbeez_lib::ProjectName`] for the order they're tried in.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, info};
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+use crumbeez_lib::{NameSource, ProjectName};
+
+pub const CTX_PURPOSE: &str = "crumbeez_project_name_purpose";
+
+#[derive(Debug)]
+enum ProjectNameCommand {
+    /// Read the file of [`NameSource::FILES`]`[index]`.
+    ReadFile { index: usize },
+}
+
+impl ContextTag for ProjectNameCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::ReadFile { index } => format!("ReadFile:{}", index),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        let index = tag.strip_prefix("ReadFile:")?.parse().ok()?;
+        Some(Self::ReadFile { index })
+    }
+}
+
+#[derive(Default)]
+pub struct ProjectNameIO {
+    host: HostHandle,
+    /// The root and configured name being resolved for.
+    resolving: Option<(PathBuf, Option<String>)>,
+    pub name: Option<ProjectName>,
+}
+
+impl ProjectNameIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Work out the name of the project at `root`.  Does nothing if it's
+    /// already known for this root and `configured` name.
+    pub fn resolve(&mut self, root: &Path, configured: Option<&str>, cwd: PathBuf) {
+        let key = (root.to_path_buf(), configured.map(str::to_string));
+        if self.resolving.as_ref() == Some(&key) {
+            return;
+        }
+        self.resolving = Some(key);
+        if configured.is_some() {
+            self.set(ProjectName::resolve(root, configured, |_| None));
+            return;
+        }
+        self.read_file(0, cwd);
+    }
+
+    fn read_file(&mut self, index: usize, cwd: PathBuf) {
+        let Some((root, _)) = &self.resolving else {
+            return;
+        };
+        let Some(path) = NameSource::FILES.get(index).and_then(|s| s.path(root)) else {
+            self.set(ProjectName::directory(root));
+            return;
+        };
+        let cmd = shell::platform().read_first_existing(&[&path]);
+        command_router::run(
+            &self.host,
+            &ProjectNameCommand::ReadFile { index },
+            &cmd,
+            cwd,
+        );
+    }
+
+    fn set(&mut self, name: ProjectName) {
+        info!(name = %name, source = %name.source, "Project name");
+        self.name = Some(name);
+    }
+
+    /// Returns `true` once the name is known and worth a rerender.
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stdout: &[u8],
+        exit_code: Option<i32>,
+        cwd: PathBuf,
+    ) -> bool {
+        let Some(ProjectNameCommand::ReadFile { index }) =
+            purpose_of::<ProjectNameCommand>(context)
+        else {
+            return false;
+        };
+        let Some(&source) = NameSource::FILES.get(index) else {
+            return false;
+        };
+        let text = String::from_utf8_lossy(stdout);
+        match source.parse(&text).filter(|_| exit_code == Some(0)) {
+            Some(name) => {
+                self.set(ProjectName { name, source });
+                true
+            }
+            None => {
+                debug!(%source, "No project name");
+                self.read_file(index + 1, cwd);
+                self.name.is_some()
+            }
+        }
+    }
+}
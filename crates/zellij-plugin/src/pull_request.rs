@@ -0,0 +1,210 @@
+//! Drafting pull request descriptions from a branch's summaries.
+//!
+//! The draft itself is built by [`crumbeez_lib::PullRequestDraft`] from
+//! every day's summaries; this module writes it to
+//! `scratchpad/pr-<branch>.md` and, if asked, opens a draft pull request
+//! with it through the GitHub CLI:
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::pr                        # the branch checked out
+//! zellij pipe --name crumbeez::pr -- feature/PROJ-142
+//! zellij pipe --name crumbeez::pr --args create=true     # and `gh pr create --draft`
+//! ```
+//!
+//! From `zellij pipe`, the path written and the new pull request's URL are
+//! printed; from a keybind, they're shown in the plugin pane.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, error, info};
+
+use crumbeez_lib::{PullRequestDraft, SummaryStore};
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+
+/// Pipe message name that drafts a pull request description.
+pub const PR_PIPE: &str = "crumbeez::pr";
+
+pub const CTX_PURPOSE: &str = "crumbeez_pr_purpose";
+
+#[derive(Debug)]
+enum PullRequestCommand {
+    WriteDraft { path: PathBuf },
+    CreatePr { path: PathBuf },
+}
+
+impl PullRequestCommand {
+    fn path(&self) -> &Path {
+        match self {
+            Self::WriteDraft { path } | Self::CreatePr { path } => path,
+        }
+    }
+}
+
+impl ContextTag for PullRequestCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::WriteDraft { path } => format!("WriteDraft:{}", path.display()),
+            Self::CreatePr { path } => format!("CreatePr:{}", path.display()),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match split_tag(tag) {
+            ("WriteDraft", Some(path)) => Some(Self::WriteDraft { path: path.into() }),
+            ("CreatePr", Some(path)) => Some(Self::CreatePr { path: path.into() }),
+            _ => None,
+        }
+    }
+}
+
+/// A draft asked for.
+#[derive(Debug)]
+struct DraftRequest {
+    branch: String,
+    /// The CLI pipe to answer on, if it came from `zellij pipe`.
+    pipe_id: Option<String>,
+    /// Open a draft pull request with `gh` once it's written.
+    create: bool,
+}
+
+#[derive(Default)]
+pub struct PullRequestIO {
+    host: HostHandle,
+    /// How the last draft went, shown in the plugin pane.
+    pub status: Option<String>,
+    /// Drafts waiting for every day's summaries to be read.
+    pending: Vec<DraftRequest>,
+    /// Drafts being written or opened, with where and their titles.
+    in_progress: Vec<(PathBuf, String, DraftRequest)>,
+}
+
+impl PullRequestIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Ask for a draft of `branch`, answered on the CLI pipe `pipe_id` if
+    /// there is one, once [`draft`](Self::draft) has the summaries.
+    pub fn request(&mut self, branch: String, pipe_id: Option<String>, create: bool) {
+        debug!(%branch, create, "Pull request draft requested");
+        self.status = Some(format!("drafting a pull request for {}…", branch));
+        self.pending.push(DraftRequest {
+            branch,
+            pipe_id,
+            create,
+        });
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Draft each pending request from `history`, every day's summaries,
+    /// into `scratch_dir`.
+    pub fn draft(&mut self, history: &SummaryStore, scratch_dir: &Path, cwd: PathBuf) {
+        for request in std::mem::take(&mut self.pending) {
+            let Some(draft) = PullRequestDraft::from_summaries(&request.branch, history.iter())
+            else {
+                self.finish(&request, format!("no summaries on {}", request.branch));
+                continue;
+            };
+            let path = scratch_dir.join(draft.file_name());
+            info!(?path, "Writing pull request draft");
+            let cmd = shell::platform().write_text(&path, &draft.body);
+            command_router::run(
+                &self.host,
+                &PullRequestCommand::WriteDraft { path: path.clone() },
+                &cmd,
+                cwd.clone(),
+            );
+            self.in_progress.push((path, draft.title, request));
+        }
+    }
+
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stdout: &[u8],
+        stderr: &[u8],
+        exit_code: Option<i32>,
+        cwd: PathBuf,
+    ) -> bool {
+        let Some(command) = purpose_of::<PullRequestCommand>(context) else {
+            return false;
+        };
+        let Some(i) = self
+            .in_progress
+            .iter()
+            .position(|(path, ..)| path == command.path())
+        else {
+            return false;
+        };
+        debug!(?command, ?exit_code, "Pull request result");
+        let (path, title, request) = self.in_progress.remove(i);
+        if exit_code != Some(0) {
+            let err = String::from_utf8_lossy(stderr);
+            error!(%err, ?command, "Pull request draft failed");
+            let failed = match command {
+                PullRequestCommand::WriteDraft { .. } => {
+                    format!("failed to write {}", path.display())
+                }
+                PullRequestCommand::CreatePr { .. } => {
+                    format!("gh pr create failed: {}", err.trim())
+                }
+            };
+            self.finish(&request, failed);
+            return true;
+        }
+        match command {
+            PullRequestCommand::WriteDraft { .. } if request.create => {
+                reply(
+                    &self.host,
+                    &request,
+                    &format!("draft written to {}\n", path.display()),
+                );
+                let cmd = shell::platform().gh_pr_create(&request.branch, &title, &path);
+                command_router::run(
+                    &self.host,
+                    &PullRequestCommand::CreatePr { path: path.clone() },
+                    &cmd,
+                    cwd,
+                );
+                self.status = Some(format!("opening a pull request for {}…", request.branch));
+                self.in_progress.push((path, title, request));
+            }
+            PullRequestCommand::WriteDraft { .. } => {
+                self.finish(&request, format!("draft written to {}", path.display()));
+            }
+            PullRequestCommand::CreatePr { .. } => {
+                let url = String::from_utf8_lossy(stdout);
+                self.finish(&request, format!("opened {}", url.trim()));
+            }
+        }
+        true
+    }
+
+    /// Report how `request` went, and let its CLI pipe go.
+    fn finish(&mut self, request: &DraftRequest, status: String) {
+        reply(&self.host, request, &format!("{}\n", status));
+        if request.pipe_id.is_some() {
+            self.host.unblock_cli_pipe_input(PR_PIPE);
+        }
+        self.status = Some(status);
+    }
+}
+
+fn reply(host: &HostHandle, request: &DraftRequest, text: &str) {
+    if let Some(ref pipe_id) = request.pipe_id {
+        host.cli_pipe_output(pipe_id, text);
+    }
+}
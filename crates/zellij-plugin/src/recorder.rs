@@ -0,0 +1,1184 @@
+//! Recording: turning intercepted keys into the events in the log.
+//!
+//! Every key is forwarded to the focused pane first, then passes the
+//! duplicate, flood, and chord detectors and the pane's profile on its way
+//! to the log.  Typed text gathers in the [`LiveText`] until something seals
+//! it into one event.  The [`Recorder`] owns all of that.  What it logs, and
+//! anything else the rest of the plugin acts on, it queues as [`Recorded`]
+//! outputs, which [`State`](crate::State) passes on in order: entries to the
+//! log, activity to the [`Summarizer`](crate::summarizer::Summarizer), which
+//! decides when that's summarized, and so on.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use tracing::{debug, info, warn};
+use zellij_tile::prelude::*;
+
+use crumbeez_lib::{
+    agent_status, needs_continuation, pane_container, repl_language, ssh_host, AppRole,
+    ChordDetector, CommandOutcome, CorrectionStats, CorrectionTracker, CredentialGuard, CursorKeys,
+    DuplicateFilter, EditControlEvent, EventKind, FloodGuard, KeySource, KeystrokeActivity,
+    KeystrokeEvent, LogEntry, NavDirection, PaneFocusedEvent, PasteDetector, ProfileRegistry,
+    ProjectConfig, Reinterpreter, SummaryTrigger, SuspendReason, TitleTracker, WordEdit, PASTE_END,
+};
+
+use crate::host::HostHandle;
+use crate::is_crumbeez_url;
+use crate::mark::RiskyCommand;
+use crate::toast::Severity;
+
+/// Everything between a key being intercepted and its event reaching the
+/// log: the focused pane and how it wants keys forwarded, the detectors
+/// keys pass through, and the text being typed.
+#[derive(Default)]
+pub struct Recorder {
+    host: HostHandle,
+    pub keystroke_activity: KeystrokeActivity,
+    focused_pane: Option<FocusedPane>,
+    current_pane_has_activity: bool,
+    /// Whether the focused pane matches an `exclude` pattern in the project
+    /// config.  Keystrokes in excluded panes are forwarded but never logged.
+    pub current_pane_excluded: bool,
+    /// Built-in and project profiles, and the focused pane's.
+    pub profiles: ProfileRegistry,
+    pub reinterpreter: Reinterpreter,
+    /// How the focused pane's program wants cursor keys forwarded.
+    pub cursor_keys: CursorKeys,
+    /// Whether the focused pane's program expects pastes bracketed.
+    pub bracketed_paste: bool,
+    /// Finds pastes among forwarded keys to bracket them.
+    pub paste: PasteDetector,
+    /// Folds configured key sequences into chords.
+    pub chords: ChordDetector,
+    /// Keys seen recently, to log those reported twice only once.
+    duplicates: DuplicateFilter<KeyWithModifier>,
+    /// Counts intercepted keys, to stop logging them during a flood.
+    pub flood: FloodGuard,
+    /// Selection moves in a fuzzy finder since its last accepted match.
+    fuzzy_moves: usize,
+    /// The focused REPL's language, if it is one.
+    repl_language: Option<String>,
+    /// The focused agent's last status, from its pane title.
+    agent_status: Option<String>,
+    /// The focused pane's title, settling before a change is logged.
+    pub titles: TitleTracker,
+    /// Keys of kinds the config disables, counted since they were last
+    /// logged as [`KeystrokeEvent::Filtered`].
+    filtered: BTreeMap<EventKind, usize>,
+    /// The line being typed, until it's sealed into the log.
+    pub live: LiveText,
+    /// Whether the live text answers a password prompt, and so is dropped.
+    pub credentials: CredentialGuard,
+    /// Whether this instance writes the log.  One that doesn't still
+    /// follows focus and shows what's typed.
+    pub recording: bool,
+    /// Why logging is suspended, if it is.  Nothing is logged and the
+    /// inactivity timer isn't re-armed until it resumes.
+    pub suspended: Option<SuspendReason>,
+    /// No client is attached to the session.
+    detached: bool,
+    /// The session is in locked input mode.
+    locked: bool,
+    /// Zellij's current input mode, once it's been reported.
+    input_mode: Option<InputMode>,
+    /// What's been done since it was last passed on, oldest first.
+    output: VecDeque<Recorded>,
+}
+
+/// Something the [`Recorder`] did that the rest of the plugin acts on.
+#[derive(Debug)]
+pub enum Recorded {
+    /// An event for the log.
+    Entry(LogEntry),
+    /// Something was logged or typed: a summary is due once it's been
+    /// quiet for a while.
+    Activity,
+    /// A shell command was just submitted.
+    Submitted(String),
+    /// The full text of a [`KeystrokeEvent::LongText`], for its `file`.
+    LongText { file: String, text: String },
+    /// A risky command the project wants a reason for before it's logged.
+    Risky(RiskyCommand),
+    /// Focus left a pane with activity, and what was logged in it should be
+    /// summarized before anything from the next one.
+    Boundary(SummaryTrigger),
+    /// Logging was suspended; what's logged so far should be saved.
+    Suspended,
+    /// Logging resumed.
+    Resumed,
+    /// A notice to flash in the pane title.
+    Toast(Severity, &'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FocusedPane {
+    tab_index: usize,
+    pane_id: u32,
+    is_plugin: bool,
+}
+
+/// Text typed in the focused pane and not yet logged, with the cursor in
+/// it and the edits made to it.
+#[derive(Debug, Default)]
+pub struct LiveText {
+    text: Option<String>,
+    /// Byte offset into `text`.
+    cursor: usize,
+    corrections: CorrectionTracker,
+}
+
+impl LiveText {
+    pub fn is_some(&self) -> bool {
+        self.text.is_some()
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// Type `c` at the cursor.
+    pub fn type_char(&mut self, c: char) {
+        self.corrections.typed(1);
+        if let Some(ref mut text) = self.text {
+            text.insert(self.cursor, c);
+        } else {
+            self.text = Some(String::from(c));
+            self.cursor = 0;
+        }
+        self.cursor += c.len_utf8();
+    }
+
+    /// Type `s` at the cursor.
+    pub fn type_str(&mut self, s: &str) {
+        self.corrections.typed(s.chars().count());
+        if let Some(ref mut text) = self.text {
+            text.insert_str(self.cursor, s);
+            self.cursor += s.len();
+        } else {
+            self.text = Some(s.to_string());
+            self.cursor = s.len();
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(ref mut text) = self.text {
+            if self.cursor > 0 {
+                let prev = prev_char_boundary(text, self.cursor);
+                self.corrections.deleted(text, prev, true);
+                text.drain(prev..self.cursor);
+                self.cursor = prev;
+                self.clear_if_empty();
+            }
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if let Some(ref mut text) = self.text {
+            if self.cursor < text.len() {
+                let next = next_char_boundary(text, self.cursor);
+                self.corrections.deleted(text, self.cursor, false);
+                text.drain(self.cursor..next);
+                self.clear_if_empty();
+            }
+        }
+    }
+
+    /// Move the cursor a character, or a word `by_word`, to the left.
+    pub fn left(&mut self, by_word: bool) {
+        if let Some(ref text) = self.text {
+            self.cursor = if by_word {
+                word_left(text, self.cursor)
+            } else {
+                prev_char_boundary(text, self.cursor)
+            };
+            self.corrections.moved();
+        }
+    }
+
+    /// Like [`left`](Self::left), to the right.
+    pub fn right(&mut self, by_word: bool) {
+        if let Some(ref text) = self.text {
+            self.cursor = if by_word {
+                word_right(text, self.cursor)
+            } else {
+                next_char_boundary(text, self.cursor)
+            };
+            self.corrections.moved();
+        }
+    }
+
+    pub fn home(&mut self) {
+        if self.text.is_some() {
+            self.corrections.moved();
+        }
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        if let Some(ref text) = self.text {
+            self.cursor = text.len();
+            self.corrections.moved();
+        }
+    }
+
+    /// Make a word-wise move or deletion.  Returns whether there was text
+    /// to make it in.
+    pub fn word_edit(&mut self, edit: WordEdit) -> bool {
+        let Some(ref mut text) = self.text else {
+            return false;
+        };
+        let target = edit.target(text, self.cursor);
+        match edit {
+            WordEdit::Left | WordEdit::Right => {
+                self.cursor = target;
+                self.corrections.moved();
+            }
+            WordEdit::DeleteLeft => {
+                let mut at = self.cursor;
+                while at > target {
+                    at = prev_char_boundary(text, at);
+                    self.corrections.deleted(text, at, true);
+                }
+                text.drain(target..self.cursor);
+                self.cursor = target;
+            }
+            WordEdit::DeleteRight => {
+                let deleted = text[self.cursor..target].chars().count();
+                for _ in 0..deleted {
+                    self.corrections.deleted(text, self.cursor, false);
+                }
+                text.drain(self.cursor..target);
+            }
+        }
+        self.clear_if_empty();
+        true
+    }
+
+    /// Break the line at the cursor.  Returns whether there was text to
+    /// break.
+    pub fn insert_newline(&mut self) -> bool {
+        let Some(ref mut text) = self.text else {
+            return false;
+        };
+        text.insert(self.cursor, '\n');
+        self.cursor += 1;
+        true
+    }
+
+    /// Start another line at the end, for input that continues.
+    pub fn continue_line(&mut self) {
+        if let Some(ref mut text) = self.text {
+            text.push('\n');
+            self.cursor = text.len();
+        }
+    }
+
+    /// Move the cursor `count` lines up or down.  Returns whether it
+    /// could, staying within the text.
+    pub fn move_lines(&mut self, direction: &NavDirection, count: usize) -> bool {
+        let Some(ref text) = self.text else {
+            return false;
+        };
+        let step = match direction {
+            NavDirection::Up => line_up,
+            _ => line_down,
+        };
+        let mut cursor = self.cursor;
+        for _ in 0..count {
+            match step(text, cursor) {
+                Some(pos) => cursor = pos,
+                None => return false,
+            }
+        }
+        self.cursor = cursor;
+        true
+    }
+
+    /// Take the text, with the stats of the edits made to it, leaving
+    /// nothing typed.
+    pub fn take(&mut self) -> Option<(String, Option<CorrectionStats>)> {
+        self.cursor = 0;
+        let text = self.text.take();
+        let corrections = match text {
+            Some(ref text) => self.corrections.finish(text),
+            None => None,
+        };
+        text.map(|text| (text, corrections))
+    }
+
+    /// Nothing is left to attach the rework to once the text is gone.
+    fn clear_if_empty(&mut self) {
+        if self.text.as_deref() == Some("") {
+            self.text = None;
+            self.corrections = CorrectionTracker::new();
+        }
+    }
+}
+
+impl Recorder {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// The next thing done that the rest of the plugin acts on, oldest
+    /// first.
+    pub fn next_output(&mut self) -> Option<Recorded> {
+        self.output.pop_front()
+    }
+
+    /// Whether anything is being logged right now: nothing is in an
+    /// excluded pane or while logging is suspended.
+    fn logging(&self) -> bool {
+        !self.current_pane_excluded && self.suspended.is_none()
+    }
+
+    pub fn log_event(&mut self, event: KeystrokeEvent, config: &ProjectConfig, now_ms: u64) {
+        if !self.logging() {
+            return;
+        }
+        // A password isn't shown, even in the plugin pane.
+        if !matches!(event, KeystrokeEvent::TextTyped(_))
+            || !self.credentials.expects_secret(now_ms)
+        {
+            self.keystroke_activity.push_event(event.clone());
+        }
+        self.process_for_event_log(event, config, now_ms);
+        // Mark that this pane has had activity (for summary triggering on pane switch)
+        self.current_pane_has_activity = true;
+    }
+
+    /// Fast path for [`Self::log_event`] with a single typed character: no
+    /// intermediate `KeystrokeEvent` or `String` is built.
+    pub fn log_char(&mut self, c: char, now_ms: u64) {
+        if !self.logging() {
+            return;
+        }
+        if !self.credentials.expects_secret(now_ms) {
+            self.keystroke_activity.push_char(c);
+        }
+        self.live.type_char(c);
+        self.output.push_back(Recorded::Activity);
+        self.current_pane_has_activity = true;
+    }
+
+    /// Whether `key` from `source` is new, rather than the second report of
+    /// a key already seen while the plugin's pane is focused.
+    pub fn admit_key(&mut self, source: KeySource, key: &KeyWithModifier, now_ms: u64) -> bool {
+        let admitted = self.duplicates.admit(source, key.clone(), now_ms);
+        if !admitted {
+            debug!(?source, ?key, "Skipping duplicate key");
+        }
+        admitted
+    }
+
+    /// Log a key released by the chord detector, typed characters through
+    /// the same path as when there are no chords.
+    pub fn log_key(&mut self, event: KeystrokeEvent, config: &ProjectConfig, now_ms: u64) {
+        if let KeystrokeEvent::TextTyped(ref text) = event {
+            let mut chars = text.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                if self.reinterpreter.passes_text() {
+                    self.log_char(c, now_ms);
+                    return;
+                }
+            }
+        }
+        self.log_event(event, config, now_ms);
+    }
+
+    /// Log any keys held back as the possible start of a chord.
+    pub fn release_chord_keys(&mut self, config: &ProjectConfig, now_ms: u64) {
+        for event in self.chords.flush() {
+            self.log_key(event, config, now_ms);
+        }
+    }
+
+    /// Take up the profiles and chords of a newly loaded project config.
+    pub fn apply_config(&mut self, config: &ProjectConfig, now_ms: u64) {
+        self.profiles = ProfileRegistry::with_custom(&config.profiles);
+        self.release_chord_keys(config, now_ms);
+        self.chords = ChordDetector::new(&config.chords, self.reinterpreter.role());
+    }
+
+    /// Log everything held back for now: keys that might start a chord, a
+    /// flood's marker, a title still settling, the text being typed, and
+    /// the counts of disabled events.
+    pub fn flush(&mut self, config: &ProjectConfig, now_ms: u64) {
+        self.release_chord_keys(config, now_ms);
+        self.record_flood(config, now_ms);
+        self.record_title(config, now_ms);
+        self.seal_pending_text(config, now_ms);
+        self.record_filtered(config, now_ms);
+    }
+
+    fn process_for_event_log(
+        &mut self,
+        event: KeystrokeEvent,
+        config: &ProjectConfig,
+        now_ms: u64,
+    ) {
+        // The answer to a password prompt, whatever the pane's profile would
+        // make of the line.
+        if event == KeystrokeEvent::EditControl(EditControlEvent::Enter)
+            && self.live.is_some()
+            && self.credentials.expects_secret(now_ms)
+        {
+            self.seal_and_log(event, config, now_ms);
+            self.output.push_back(Recorded::Activity);
+            return;
+        }
+        let consumed = match self.reinterpreter.role() {
+            AppRole::FuzzyFinder => self.process_fuzzy_key(&event, config, now_ms),
+            AppRole::Repl | AppRole::Sql => self.process_repl_key(&event, config, now_ms),
+            AppRole::Agent => self.process_agent_key(&event, config, now_ms),
+            AppRole::Editor => self.process_editor_key(&event),
+            _ => false,
+        };
+        if consumed {
+            self.output.push_back(Recorded::Activity);
+            return;
+        }
+        match &event {
+            KeystrokeEvent::TextTyped(s) => self.live.type_str(s),
+            KeystrokeEvent::EditControl(EditControlEvent::Backspace { .. }) => {
+                self.live.backspace()
+            }
+            KeystrokeEvent::EditControl(EditControlEvent::Delete { .. }) => self.live.delete(),
+            KeystrokeEvent::Navigation(nav) => match nav.direction {
+                NavDirection::Left => self.live.left(nav.with_ctrl),
+                NavDirection::Right => self.live.right(nav.with_ctrl),
+                NavDirection::Home => self.live.home(),
+                NavDirection::End => self.live.end(),
+                NavDirection::Up
+                | NavDirection::Down
+                | NavDirection::PageUp
+                | NavDirection::PageDown => {
+                    self.seal_and_log(event, config, now_ms);
+                }
+            },
+            KeystrokeEvent::Shortcut(shortcut) => {
+                let edited = WordEdit::of(shortcut).is_some_and(|edit| self.live.word_edit(edit));
+                if !edited {
+                    self.seal_and_log(event, config, now_ms);
+                }
+            }
+            KeystrokeEvent::EditControl(EditControlEvent::Enter)
+                if self.reinterpreter.role() == AppRole::Shell =>
+            {
+                let command = self.live.text().map(str::to_string);
+                self.seal_and_log(event, config, now_ms);
+                if let Some(command) = command {
+                    self.output.push_back(Recorded::Submitted(command.clone()));
+                    if let Some((kind, target)) = crumbeez_lib::environment_change(&command) {
+                        let target = config.scrub(&target);
+                        info!(%kind, %target, "Environment switched");
+                        self.record(
+                            KeystrokeEvent::EnvironmentChanged { kind, target },
+                            config,
+                            now_ms,
+                        );
+                    }
+                    self.flag_risky(&command, config, now_ms);
+                    self.credentials.submitted(&command, now_ms);
+                }
+            }
+            _ => {
+                self.seal_and_log(event, config, now_ms);
+            }
+        }
+
+        self.output.push_back(Recorded::Activity);
+    }
+
+    /// Log `event`, timestamped `now_ms`, if this instance is recording.
+    /// Events of a disabled kind are only counted; the live text has
+    /// already seen them.
+    pub fn record(&mut self, event: KeystrokeEvent, config: &ProjectConfig, now_ms: u64) {
+        self.record_corrected(event, None, config, now_ms);
+    }
+
+    /// [`record`](Self::record), with the corrections made to typed text.
+    fn record_corrected(
+        &mut self,
+        event: KeystrokeEvent,
+        corrections: Option<CorrectionStats>,
+        config: &ProjectConfig,
+        now_ms: u64,
+    ) {
+        if !self.recording {
+            return;
+        }
+        if let Some((kind, count)) = config.filtered(&event) {
+            *self.filtered.entry(kind).or_insert(0) += count;
+            return;
+        }
+        self.output.push_back(Recorded::Entry(LogEntry {
+            event,
+            timestamp_ms: now_ms,
+            corrections: corrections.map(Box::new),
+        }));
+    }
+
+    /// Log the counts of disabled events since the last call, one
+    /// [`KeystrokeEvent::Filtered`] per kind.
+    pub fn record_filtered(&mut self, config: &ProjectConfig, now_ms: u64) {
+        for (kind, count) in std::mem::take(&mut self.filtered) {
+            self.record(KeystrokeEvent::Filtered { kind, count }, config, now_ms);
+        }
+    }
+
+    /// Whether an intercepted key should be logged rather than only
+    /// counted as part of a flood.  A flood that has subsided is logged
+    /// first.
+    pub fn admit_flood(&mut self, config: &ProjectConfig, now_ms: u64) -> bool {
+        self.record_flood(config, now_ms);
+        let was_flooding = self.flood.is_flooding();
+        let admitted = self.flood.admit(now_ms);
+        if !admitted && !was_flooding {
+            warn!(
+                threshold = self.flood.threshold(),
+                "Input flood, counting keys instead of logging them"
+            );
+            self.output.push_back(Recorded::Toast(
+                Severity::Warn,
+                "input flood, counting keys",
+            ));
+        }
+        admitted
+    }
+
+    /// Log the [`KeystrokeEvent::FloodDetected`] marker if a flood has
+    /// subsided by `now_ms`.
+    pub fn record_flood(&mut self, config: &ProjectConfig, now_ms: u64) {
+        if let Some(marker) = self.flood.finish(now_ms) {
+            info!(%marker, "Input flood subsided");
+            self.seal_and_log(marker, config, now_ms);
+            self.output.push_back(Recorded::Activity);
+        }
+    }
+
+    /// Log the focused pane's new title once it has settled.
+    pub fn record_title(&mut self, config: &ProjectConfig, now_ms: u64) {
+        let Some(event) = self.titles.settle(now_ms) else {
+            return;
+        };
+        if !self.logging() {
+            return;
+        }
+        debug!(%event, "Pane title settled");
+        self.keystroke_activity.push_event(event.clone());
+        self.seal_and_log(event, config, now_ms);
+        self.output.push_back(Recorded::Activity);
+    }
+
+    pub fn seal_and_log(&mut self, event: KeystrokeEvent, config: &ProjectConfig, now_ms: u64) {
+        self.seal_pending_text(config, now_ms);
+        self.record(event, config, now_ms);
+    }
+
+    /// In a fuzzy finder the typed text is the query, so selection moves
+    /// leave it open and Enter turns the lot into one
+    /// [`KeystrokeEvent::FuzzySelected`].  Returns whether `event` was
+    /// consumed.
+    fn process_fuzzy_key(
+        &mut self,
+        event: &KeystrokeEvent,
+        config: &ProjectConfig,
+        now_ms: u64,
+    ) -> bool {
+        match event {
+            KeystrokeEvent::Navigation(nav)
+                if matches!(
+                    nav.direction,
+                    NavDirection::Up
+                        | NavDirection::Down
+                        | NavDirection::PageUp
+                        | NavDirection::PageDown
+                ) =>
+            {
+                self.fuzzy_moves += nav.count;
+                true
+            }
+            KeystrokeEvent::EditControl(EditControlEvent::Enter) => {
+                let query = self.take_pending_text(config, now_ms).unwrap_or_default();
+                let moves = std::mem::take(&mut self.fuzzy_moves);
+                self.record(
+                    KeystrokeEvent::FuzzySelected { query, moves },
+                    config,
+                    now_ms,
+                );
+                true
+            }
+            KeystrokeEvent::Escape | KeystrokeEvent::Shortcut(_) => {
+                // Abandoned: the query is logged like any other text.
+                self.fuzzy_moves = 0;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// In a REPL, Enter continues the input while it's incomplete (see
+    /// [`needs_continuation`]) and otherwise logs it as one
+    /// [`KeystrokeEvent::ReplInput`].  Returns whether `event` was consumed.
+    fn process_repl_key(
+        &mut self,
+        event: &KeystrokeEvent,
+        config: &ProjectConfig,
+        now_ms: u64,
+    ) -> bool {
+        if *event != KeystrokeEvent::EditControl(EditControlEvent::Enter) {
+            return false;
+        }
+        let Some(language) = self.repl_language.clone() else {
+            return false;
+        };
+        let Some(code) = self.live.text() else {
+            return false;
+        };
+        if needs_continuation(&language, code) {
+            self.live.continue_line();
+            return true;
+        }
+        let submitted = code.to_string();
+        let Some(code) = self.take_pending_text(config, now_ms) else {
+            return false;
+        };
+        let code = code.trim_end_matches('\n').to_string();
+        self.record(KeystrokeEvent::ReplInput { language, code }, config, now_ms);
+        if self.reinterpreter.role() == AppRole::Sql {
+            self.flag_risky(&submitted, config, now_ms);
+        }
+        true
+    }
+
+    /// Log `command`, just submitted, as risky if it matches the project's
+    /// `risky` patterns, or pass it on to ask why first if the project
+    /// says to.
+    fn flag_risky(&mut self, command: &str, config: &ProjectConfig, now_ms: u64) {
+        let Some(pattern) = config.risky.matching(command) else {
+            return;
+        };
+        let risky = RiskyCommand {
+            command: config.scrub(command.trim()),
+            pattern: pattern.to_string(),
+        };
+        info!(command = %risky.command, pattern = %risky.pattern, "Risky command");
+        if config.risky.annotate {
+            self.output.push_back(Recorded::Risky(risky));
+        } else {
+            self.record(risky.into_event(None), config, now_ms);
+        }
+    }
+
+    /// In an editor, Enter inserts a newline into the live text and Up/Down
+    /// move between its lines, so only leaving it (focus change, Esc, a
+    /// shortcut, or moving off its first or last line) seals it.  Returns
+    /// whether `event` was consumed.
+    fn process_editor_key(&mut self, event: &KeystrokeEvent) -> bool {
+        match event {
+            KeystrokeEvent::EditControl(EditControlEvent::Enter) => self.live.insert_newline(),
+            KeystrokeEvent::Navigation(nav)
+                if matches!(nav.direction, NavDirection::Up | NavDirection::Down) =>
+            {
+                self.live.move_lines(&nav.direction, nav.count)
+            }
+            _ => false,
+        }
+    }
+
+    /// In an agent pane, Enter sends the typed text as one
+    /// [`KeystrokeEvent::PromptToAgent`].  Returns whether `event` was
+    /// consumed.
+    fn process_agent_key(
+        &mut self,
+        event: &KeystrokeEvent,
+        config: &ProjectConfig,
+        now_ms: u64,
+    ) -> bool {
+        if *event != KeystrokeEvent::EditControl(EditControlEvent::Enter) {
+            return false;
+        }
+        // A bare Enter answers one of the agent's own prompts.
+        let Some(prompt) = self.take_pending_text(config, now_ms) else {
+            return false;
+        };
+        self.record(KeystrokeEvent::PromptToAgent(prompt), config, now_ms);
+        true
+    }
+
+    /// Guess how the focused pane wants keys forwarded again from its
+    /// title.
+    fn update_forwarding(&mut self, title: &str, command: Option<&str>, config: &ProjectConfig) {
+        let role = self.profiles.role_for(title, command);
+        let mode = config.cursor_keys_for(title, command, role);
+        if mode != self.cursor_keys {
+            debug!(?mode, title, "Cursor key mode changed");
+            self.cursor_keys = mode;
+        }
+        let bracketed = config.bracketed_paste_for(title, command, role);
+        if bracketed != self.bracketed_paste {
+            debug!(bracketed, title, "Bracketed paste changed");
+            self.close_paste();
+            self.bracketed_paste = bracketed;
+        }
+    }
+
+    /// End a paste still open in the focused pane, wherever focus is now.
+    fn close_paste(&mut self) {
+        if !self.paste.abandon() {
+            return;
+        }
+        if let Some(ref pane) = self.focused_pane {
+            let pane_id = if pane.is_plugin {
+                PaneId::Plugin(pane.pane_id)
+            } else {
+                PaneId::Terminal(pane.pane_id)
+            };
+            self.host.write_to_pane_id(PASTE_END.to_vec(), pane_id);
+        }
+    }
+
+    /// End a paste once no more of it has arrived for a moment.
+    pub fn finish_paste(&mut self, now_ms: u64) {
+        let mut bytes = Vec::new();
+        self.paste.finish(now_ms, &mut bytes);
+        if !bytes.is_empty() {
+            self.host.write(bytes);
+        }
+    }
+
+    /// Log the focused agent's new status when its title changes.
+    fn update_agent_status(&mut self, title: &str, config: &ProjectConfig, now_ms: u64) {
+        if self.reinterpreter.role() != AppRole::Agent || self.current_pane_excluded {
+            return;
+        }
+        let Some(status) = agent_status(title) else {
+            return;
+        };
+        if self.agent_status.as_deref() == Some(status) {
+            return;
+        }
+        debug!(%status, "Agent status changed");
+        self.agent_status = Some(status.to_string());
+        let event = KeystrokeEvent::AgentStatus(status.to_string());
+        self.keystroke_activity.push_event(event.clone());
+        self.record(event, config, now_ms);
+    }
+
+    pub fn seal_pending_text(&mut self, config: &ProjectConfig, now_ms: u64) {
+        let Some((text, corrections)) = self.live.take() else {
+            return;
+        };
+        if self.drop_secret(config, now_ms) {
+            return;
+        }
+        match config.seal_text(&text) {
+            Some(event @ KeystrokeEvent::TextTyped(_)) => {
+                let (event, full_text) = config.split_long_text(event, now_ms);
+                if let (KeystrokeEvent::LongText { file, .. }, Some(text)) = (&event, full_text) {
+                    self.output.push_back(Recorded::LongText {
+                        file: file.clone(),
+                        text,
+                    });
+                }
+                self.record_corrected(event, corrections, config, now_ms)
+            }
+            Some(event) => self.record(event, config, now_ms),
+            None => {}
+        }
+    }
+
+    /// Log a [`KeystrokeEvent::CredentialEntered`] in place of the live
+    /// text just taken if it answered a password prompt (see
+    /// [`CredentialGuard`]), whatever the privacy settings.  Returns
+    /// whether it did.
+    fn drop_secret(&mut self, config: &ProjectConfig, now_ms: u64) -> bool {
+        if !self.credentials.take_secret(now_ms) {
+            return false;
+        }
+        info!("Password prompt answered, dropping what was typed");
+        let event = KeystrokeEvent::CredentialEntered;
+        self.keystroke_activity.push_event(event.clone());
+        self.record(event, config, now_ms);
+        true
+    }
+
+    /// The live text buffer, redacted and reduced to the privacy level, or
+    /// `None` if there's nothing typed.
+    pub fn take_pending_text(&mut self, config: &ProjectConfig, now_ms: u64) -> Option<String> {
+        let (text, _) = self.live.take().filter(|(t, _)| !t.is_empty())?;
+        if self.drop_secret(config, now_ms) {
+            return None;
+        }
+        let redacted = config.redact(&text);
+        if let Cow::Owned(_) = redacted {
+            self.output
+                .push_back(Recorded::Toast(Severity::Warn, "redacted typed text"));
+        }
+        Some(config.privacy.apply(&redacted).into_owned())
+    }
+
+    /// Log a command atuin saw finish, with how it went.
+    pub fn command_finished(
+        &mut self,
+        command: String,
+        outcome: CommandOutcome,
+        config: &ProjectConfig,
+        now_ms: u64,
+    ) {
+        // It's no longer asking for a password.
+        self.credentials.finished(&command);
+        let command = config.scrub(&command);
+        self.record(
+            KeystrokeEvent::CommandExecuted {
+                command,
+                shell: "atuin".to_string(),
+                outcome: Some(outcome),
+            },
+            config,
+            now_ms,
+        );
+    }
+
+    /// Log a bookmark, which starts the work after it afresh.
+    pub fn log_bookmark(&mut self, name: String, config: &ProjectConfig, now_ms: u64) {
+        self.current_pane_has_activity = false;
+        let event = KeystrokeEvent::Annotation(name);
+        self.keystroke_activity.push_event(event.clone());
+        self.record(event, config, now_ms);
+    }
+
+    /// Log a switch of input mode, if `log_switch`.  Locked mode suspends
+    /// logging instead.
+    pub fn handle_mode_update(
+        &mut self,
+        mode: InputMode,
+        log_switch: bool,
+        config: &ProjectConfig,
+        now_ms: u64,
+    ) {
+        // ModeUpdate also fires for changes other than the mode.
+        let Some(previous) = self.input_mode.replace(mode) else {
+            // The mode at startup is where things begin, not a change.
+            self.locked = mode == InputMode::Locked;
+            self.update_suspension(config, now_ms);
+            return;
+        };
+        if previous == mode {
+            return;
+        }
+        self.locked = mode == InputMode::Locked;
+        self.update_suspension(config, now_ms);
+        if !self.locked && log_switch {
+            self.log_event(KeystrokeEvent::ModeChanged(mode_name(mode)), config, now_ms);
+        }
+    }
+
+    /// Note whether any client is attached to the session.
+    pub fn set_detached(&mut self, detached: bool, config: &ProjectConfig, now_ms: u64) {
+        self.detached = detached;
+        self.update_suspension(config, now_ms);
+    }
+
+    /// Suspend or resume logging to match the detached and locked state.
+    fn update_suspension(&mut self, config: &ProjectConfig, now_ms: u64) {
+        let reason = if self.detached {
+            Some(SuspendReason::Detached)
+        } else if self.locked {
+            Some(SuspendReason::Locked)
+        } else {
+            None
+        };
+        match (self.suspended, reason) {
+            (None, Some(reason)) => {
+                info!(%reason, "Suspending logging");
+                self.seal_pending_text(config, now_ms);
+                self.record(KeystrokeEvent::SessionSuspended(reason), config, now_ms);
+                self.suspended = Some(reason);
+                self.output.push_back(Recorded::Suspended);
+            }
+            (Some(_), None) => {
+                info!("Resuming logging");
+                self.suspended = None;
+                self.output.push_back(Recorded::Resumed);
+                self.record(KeystrokeEvent::SessionResumed, config, now_ms);
+            }
+            // Locked, then detached: still suspended, for the first reason.
+            _ => {}
+        }
+    }
+
+    /// Feed a demo key through the pipeline, the way an intercepted one
+    /// is.
+    #[cfg(feature = "demo")]
+    pub fn play_demo(&mut self, event: KeystrokeEvent, config: &ProjectConfig, now_ms: u64) {
+        match event {
+            KeystrokeEvent::PaneFocused(pane) => self.focus_demo_pane(pane, config, now_ms),
+            KeystrokeEvent::PaneTitleChanged(_) => {
+                self.keystroke_activity.push_event(event.clone());
+                self.seal_and_log(event, config, now_ms);
+                self.output.push_back(Recorded::Activity);
+            }
+            event => self.log_key(event, config, now_ms),
+        }
+    }
+
+    /// Leave the pane focused for another, passing on what was done in it
+    /// to be summarized if anything was.
+    fn leave_pane(&mut self, config: &ProjectConfig, now_ms: u64) {
+        if self.current_pane_has_activity && self.recording {
+            self.flush(config, now_ms);
+            self.output
+                .push_back(Recorded::Boundary(SummaryTrigger::PaneSwitch));
+        }
+        self.current_pane_has_activity = false;
+    }
+
+    /// Switch to a demo pane, as [`Self::handle_pane_update`] does to a
+    /// real one.
+    #[cfg(feature = "demo")]
+    fn focus_demo_pane(&mut self, pane: PaneFocusedEvent, config: &ProjectConfig, now_ms: u64) {
+        self.release_chord_keys(config, now_ms);
+        self.leave_pane(config, now_ms);
+        self.reinterpreter = Reinterpreter::new(pane.role);
+        self.cursor_keys =
+            config.cursor_keys_for(&pane.pane_title, pane.command.as_deref(), pane.role);
+        self.chords = ChordDetector::new(&config.chords, pane.role);
+        self.fuzzy_moves = 0;
+        self.repl_language = repl_language(pane.role, pane.command.as_deref());
+        self.agent_status = agent_status(&pane.pane_title).map(str::to_string);
+        self.titles.focus(&pane.pane_title);
+        let event = KeystrokeEvent::PaneFocused(pane);
+        info!(%event, "Demo");
+        self.log_event(event, config, now_ms);
+    }
+
+    /// Follow focus to the pane focused in `manifest`, other than
+    /// crumbeez's own, or pick up a change to the focused pane's title.
+    /// Panes matching `exclude`, from the plugin config, are never logged.
+    #[allow(clippy::too_many_arguments)]
+    pub fn handle_pane_update(
+        &mut self,
+        manifest: &PaneManifest,
+        own_plugin_id: u32,
+        tab_names: &HashMap<usize, String>,
+        exclude: &[String],
+        config: &ProjectConfig,
+        now_ms: u64,
+    ) {
+        let mut new_focus: Option<(usize, PaneInfo)> = None;
+        let mut focused_tab_name: Option<String> = None;
+
+        for (tab_index, panes) in &manifest.panes {
+            for pane in panes {
+                if !pane.is_selectable || pane.is_suppressed {
+                    continue;
+                }
+                if pane.is_plugin {
+                    if is_crumbeez_url(pane.plugin_url.as_deref()) {
+                        continue;
+                    }
+                    if pane.id == own_plugin_id {
+                        continue;
+                    }
+                }
+                if pane.is_focused {
+                    new_focus = Some((*tab_index, pane.clone()));
+                    focused_tab_name = tab_names
+                        .get(tab_index)
+                        .cloned()
+                        .or_else(|| Some(format!("tab {}", tab_index + 1)));
+                    break;
+                }
+            }
+            if new_focus.is_some() {
+                break;
+            }
+        }
+
+        let Some((tab_index, pane)) = new_focus else {
+            return;
+        };
+
+        let new_fp = FocusedPane {
+            tab_index,
+            pane_id: pane.id,
+            is_plugin: pane.is_plugin,
+        };
+
+        if self.focused_pane.as_ref() == Some(&new_fp) {
+            // A shell's title changes to the program run in it, which may
+            // switch the terminal's cursor key mode or bracketed paste.
+            self.update_forwarding(&pane.title, pane.terminal_command.as_deref(), config);
+            self.update_agent_status(&pane.title, config, now_ms);
+            // An agent's title is its status, logged above.
+            if self.reinterpreter.role() != AppRole::Agent {
+                self.titles.observe(&pane.title, now_ms);
+            }
+            return;
+        }
+
+        debug!(
+            from = ?self.focused_pane,
+            to = ?new_fp,
+            "Pane focus changed"
+        );
+        // Keys held for a chord belong to the pane being left, as does the
+        // end of a paste.
+        self.release_chord_keys(config, now_ms);
+        self.close_paste();
+        self.leave_pane(config, now_ms);
+
+        self.focused_pane = Some(new_fp);
+        let command = pane.terminal_command.as_deref();
+        let host = (!pane.is_plugin)
+            .then(|| ssh_host(command, &pane.title))
+            .flatten();
+        let container = (!pane.is_plugin)
+            .then(|| pane_container(command, &pane.title))
+            .flatten();
+        self.current_pane_excluded = config.excludes_pane(&pane.title, command)
+            || crumbeez_lib::pane_matches(exclude, &pane.title, command)
+            || host
+                .as_deref()
+                .is_some_and(|host| config.excludes_host(host));
+        let role = self.profiles.role_for(&pane.title, command);
+        self.reinterpreter = Reinterpreter::new(role);
+        self.cursor_keys = config.cursor_keys_for(&pane.title, command, role);
+        self.bracketed_paste = config.bracketed_paste_for(&pane.title, command, role);
+        self.chords = ChordDetector::new(&config.chords, role);
+        self.fuzzy_moves = 0;
+        self.repl_language = repl_language(role, command);
+        // The title at focus is the starting point, not news.
+        self.agent_status = agent_status(&pane.title).map(str::to_string);
+        self.titles.focus(&pane.title);
+
+        let event = KeystrokeEvent::PaneFocused(PaneFocusedEvent {
+            tab_name: focused_tab_name,
+            pane_title: pane.title.clone(),
+            command: pane.terminal_command.clone(),
+            is_plugin: pane.is_plugin,
+            role,
+            pane_id: Some(pane.id),
+            tab_index: Some(tab_index),
+            // Zellij's pane manifest reports neither.
+            cwd: None,
+            pid: None,
+            host,
+            container,
+        });
+        info!(%event);
+        self.log_event(event, config, now_ms);
+        // A password prompt in the pane left isn't answered in this one.
+        self.credentials.cancel();
+    }
+}
+
+fn prev_char_boundary(s: &str, pos: usize) -> usize {
+    if pos == 0 {
+        return 0;
+    }
+    let mut p = pos - 1;
+    while p > 0 && !s.is_char_boundary(p) {
+        p -= 1;
+    }
+    p
+}
+
+fn next_char_boundary(s: &str, pos: usize) -> usize {
+    if pos >= s.len() {
+        return s.len();
+    }
+    let mut p = pos + 1;
+    while p < s.len() && !s.is_char_boundary(p) {
+        p += 1;
+    }
+    p
+}
+
+fn word_left(s: &str, pos: usize) -> usize {
+    let chars_before: Vec<(usize, char)> = s[..pos].char_indices().collect();
+    if chars_before.is_empty() {
+        return 0;
+    }
+    let mut iter = chars_before.iter().rev();
+    for &(_, c) in iter.by_ref() {
+        if c.is_alphanumeric() || c == '_' {
+            break;
+        }
+    }
+    for &(i, c) in iter {
+        if !c.is_alphanumeric() && c != '_' {
+            return next_char_boundary(s, i);
+        }
+    }
+    0
+}
+
+fn word_right(s: &str, pos: usize) -> usize {
+    let chars_after: Vec<(usize, char)> =
+        s[pos..].char_indices().map(|(i, c)| (pos + i, c)).collect();
+    if chars_after.is_empty() {
+        return s.len();
+    }
+    let mut iter = chars_after.iter();
+    let mut found_word = false;
+    for &(_i, c) in iter.by_ref() {
+        if c.is_alphanumeric() || c == '_' {
+            found_word = true;
+            break;
+        }
+    }
+    if !found_word {
+        return s.len();
+    }
+    for &(byte_i, c) in iter.by_ref() {
+        if !c.is_alphanumeric() && c != '_' {
+            return byte_i;
+        }
+    }
+    s.len()
+}
+
+/// Zellij's name for `mode` in kebab case, e.g. `enter-search`.
+fn mode_name(mode: InputMode) -> String {
+    let mut name = String::new();
+    for c in format!("{:?}", mode).chars() {
+        if c.is_ascii_uppercase() && !name.is_empty() {
+            name.push('-');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+/// The position in the line above `pos` at the same column, or that line's
+/// end if it's shorter.  `None` on the first line.
+fn line_up(s: &str, pos: usize) -> Option<usize> {
+    let line_start = s[..pos].rfind('\n')? + 1;
+    let column = s[line_start..pos].chars().count();
+    let prev_start = s[..line_start - 1].rfind('\n').map_or(0, |i| i + 1);
+    Some(column_offset(s, prev_start, column))
+}
+
+/// Like [`line_up`], for the line below.  `None` on the last line.
+fn line_down(s: &str, pos: usize) -> Option<usize> {
+    let line_start = s[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let column = s[line_start..pos].chars().count();
+    let next_start = pos + s[pos..].find('\n')? + 1;
+    Some(column_offset(s, next_start, column))
+}
+
+/// The position `column` characters into the line starting at `start`,
+/// clamped to the line's end.
+fn column_offset(s: &str, start: usize, column: usize) -> usize {
+    let line_end = s[start..].find('\n').map_or(s.len(), |i| start + i);
+    s[start..line_end]
+        .char_indices()
+        .nth(column)
+        .map_or(line_end, |(i, _)| start + i)
+}
@@ -0,0 +1,563 @@
+//! Replaying events through the plugin's update loop, natively.
+//!
+//! A [`Replay`] stands in for Zellij.  It hands the plugin a [`Mock`] host, which
+//! keeps every call the plugin makes and a clock that only moves when told
+//! to, answers the plugin's commands from a script of [`Reply`]s by purpose, and
+//! runs the workers in line, so feeding it the events Zellij would send
+//! drives [`State::update`] end to end, the same way every time.
+//!
+//! Run natively (the default build target is wasm):
+//!
+//! ```sh
+//! cargo test-native -p crumbeez replay
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use zellij_tile::prelude::*;
+
+use crumbeez_lib::{DiscoveryPhase, PASTE_END, PASTE_START};
+
+use crate::command_router::CTX_REQUEST_ID;
+use crate::event_log_io::{self, EventLogWorker};
+use crate::host::{HostCall, HostHandle, Mock};
+use crate::shell::base64_encode;
+use crate::summary_worker::{self, SummaryWorker};
+use crate::State;
+
+/// 2024-03-01 09:15:00 UTC
+const START_MS: u64 = 1_709_284_500_000;
+
+/// The session's cwd, a git checkout with a `.crumbeez` dir already.
+const PROJECT: &str = "/home/me/app";
+
+/// Time between keys typed with [`Replay::type_line`].
+const KEYSTROKE_GAP: Duration = Duration::from_millis(150);
+
+/// A command's result, as Zellij would report it.
+#[derive(Debug, Clone, Default)]
+pub struct Reply {
+    pub exit_code: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl Reply {
+    pub fn ok(stdout: &str) -> Self {
+        Self {
+            exit_code: Some(0),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: Vec::new(),
+        }
+    }
+
+    pub fn failed(code: i32, stderr: &str) -> Self {
+        Self {
+            exit_code: Some(code),
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// A command the plugin ran that hasn't been answered.
+#[derive(Debug, Clone)]
+pub struct Command {
+    /// The name of the purpose it was tagged with, e.g. `GitToplevel`.
+    pub purpose: String,
+    pub args: Vec<String>,
+    context: BTreeMap<String, String>,
+}
+
+/// The plugin, with a stand-in for Zellij around it.
+pub struct Replay {
+    pub state: State,
+    host: Mock,
+    /// Replies to commands, by purpose name.  A purpose without one is
+    /// left running, as if it hung, until [`answer`](Self::answer)ed.
+    script: BTreeMap<String, Reply>,
+    /// Commands with no reply scripted yet, oldest first.
+    unanswered: Vec<Command>,
+    /// Calls already looked at for commands and worker messages.
+    seen: usize,
+}
+
+impl Replay {
+    /// A fresh plugin at [`START_MS`] in [`PROJECT`], answering `script`,
+    /// loaded with `config` and granted its permissions.
+    pub fn start(config: &[(&str, &str)], script: &[(&str, Reply)]) -> Self {
+        let mut replay = Self::load(config, script);
+        replay.send(Event::PermissionRequestResult(PermissionStatus::Granted));
+        replay
+    }
+
+    /// A fresh plugin, loaded but still waiting for its permissions.
+    pub fn load(config: &[(&str, &str)], script: &[(&str, Reply)]) -> Self {
+        let host = Mock::new(
+            SystemTime::UNIX_EPOCH + Duration::from_millis(START_MS),
+            PluginIds {
+                plugin_id: 1,
+                zellij_pid: 1000,
+                initial_cwd: PathBuf::from(PROJECT),
+                client_id: 1,
+            },
+        );
+        let mut replay = Self {
+            state: State::new(host.handle()),
+            host,
+            script: script
+                .iter()
+                .map(|(purpose, reply)| (purpose.to_string(), reply.clone()))
+                .collect(),
+            unanswered: Vec::new(),
+            seen: 0,
+        };
+        let config = config
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        replay.state.load(config);
+        replay.settle();
+        replay
+    }
+
+    /// Feed `event` to the plugin, then everything it leads to: scripted
+    /// replies to the commands it runs and the workers' answers.  Returns
+    /// whether the plugin asked to render after `event` itself.
+    pub fn send(&mut self, event: Event) -> bool {
+        let render = self.state.update(event);
+        self.settle();
+        render
+    }
+
+    /// Type `line` into the focused pane and press Enter.
+    pub fn type_line(&mut self, line: &str) {
+        self.type_text(line);
+        self.press(BareKey::Enter);
+    }
+
+    /// Type `text` into the focused pane, a key every [`KEYSTROKE_GAP`] as a
+    /// person would; keys all at once look pasted.  Timers due meanwhile
+    /// fire between keys.
+    pub fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.press(BareKey::Char(c));
+        }
+    }
+
+    /// Press `key` in the focused pane, then wait [`KEYSTROKE_GAP`].
+    pub fn press(&mut self, key: BareKey) {
+        self.send(Event::InterceptedKeyPress(KeyWithModifier::new(key)));
+        self.wait(KEYSTROKE_GAP);
+    }
+
+    /// Send a pipe message from the CLI to the plugin.
+    pub fn pipe(&mut self, name: &str, payload: Option<&str>) -> bool {
+        let render = self.state.pipe(PipeMessage {
+            source: PipeSource::Cli(format!("cli-{}", name)),
+            name: name.to_string(),
+            payload: payload.map(str::to_string),
+            args: BTreeMap::new(),
+            is_private: false,
+        });
+        self.settle();
+        render
+    }
+
+    /// Move the clock to when the armed timer fires, and fire it.  Returns
+    /// `false` if no timer is armed.
+    pub fn fire_timer(&mut self) -> bool {
+        let Some((due, secs)) = self.host.take_timer() else {
+            return false;
+        };
+        self.host.advance_to(due);
+        self.send(Event::Timer(secs));
+        true
+    }
+
+    /// Keep firing the timer until `by` has passed, then move the clock the
+    /// rest of the way.
+    pub fn wait(&mut self, by: Duration) {
+        let until = self.host.time() + by;
+        while self.host.timer().is_some_and(|(due, _)| due <= until) {
+            self.fire_timer();
+        }
+        self.host.advance_to(until);
+    }
+
+    /// Answer the oldest unanswered command tagged with `purpose`.  Returns
+    /// `false` if there is none.
+    pub fn answer(&mut self, purpose: &str, reply: Reply) -> bool {
+        let Some(i) = self.unanswered.iter().position(|c| c.purpose == purpose) else {
+            return false;
+        };
+        let command = self.unanswered.remove(i);
+        self.deliver(command, reply);
+        true
+    }
+
+    /// The commands with no reply yet.
+    pub fn unanswered(&self) -> &[Command] {
+        &self.unanswered
+    }
+
+    /// Every call the plugin has made since the last time this was asked.
+    pub fn take_calls(&mut self) -> Vec<HostCall> {
+        let calls = self.host.take_calls();
+        self.seen = 0;
+        calls
+    }
+
+    /// The bytes written to the focused pane since calls were last taken.
+    pub fn written(&self) -> Vec<u8> {
+        self.host
+            .calls()
+            .iter()
+            .filter_map(|call| match call {
+                HostCall::Write(bytes) => Some(bytes.as_slice()),
+                _ => None,
+            })
+            .flatten()
+            .copied()
+            .collect()
+    }
+
+    /// Answer commands and run the workers until the plugin has nothing
+    /// more to do without a new event.
+    fn settle(&mut self) {
+        let mut queue: VecDeque<Event> = VecDeque::new();
+        loop {
+            let new = self.host.calls().split_off(self.seen);
+            self.seen += new.len();
+            for call in new {
+                match call {
+                    HostCall::RunCommand { args, context, .. } => {
+                        let command = Command {
+                            purpose: purpose_name(&context),
+                            args,
+                            context,
+                        };
+                        match self.script.get(&command.purpose) {
+                            Some(reply) => queue.push_back(result_event(&command, reply.clone())),
+                            None => self.unanswered.push(command),
+                        }
+                    }
+                    HostCall::PostMessageTo(message) => run_worker(self.host.handle(), message),
+                    HostCall::PostMessageToPlugin(message) => {
+                        queue.push_back(Event::CustomMessage(message.name, message.payload));
+                    }
+                    _ => {}
+                }
+            }
+            let Some(event) = queue.pop_front() else {
+                // The workers' answers come in as calls, so look again.
+                if self.host.calls().len() == self.seen {
+                    return;
+                }
+                continue;
+            };
+            self.state.update(event);
+        }
+    }
+
+    fn deliver(&mut self, command: Command, reply: Reply) {
+        self.state.update(result_event(&command, reply));
+        self.settle();
+    }
+}
+
+/// The purpose a command's context names, without its argument.
+fn purpose_name(context: &BTreeMap<String, String>) -> String {
+    context
+        .iter()
+        .find(|(key, _)| *key != CTX_REQUEST_ID)
+        .map(|(_, tag)| tag.split(':').next().unwrap_or_default().to_string())
+        .unwrap_or_default()
+}
+
+fn result_event(command: &Command, reply: Reply) -> Event {
+    Event::RunCommandResult(
+        reply.exit_code,
+        reply.stdout,
+        reply.stderr,
+        command.context.clone(),
+    )
+}
+
+/// Run a worker in line; whatever it sends back is a call like any other.
+/// It gets a handle of its own, as a real worker shares nothing with the
+/// plugin but the host.
+fn run_worker(host: HostHandle, message: PluginMessage) {
+    match message.worker_name.as_deref() {
+        Some(summary_worker::WORKER_NAME) => {
+            SummaryWorker::new(host).on_message(message.name, message.payload)
+        }
+        Some(event_log_io::WORKER_NAME) => {
+            EventLogWorker::new(host).on_message(message.name, message.payload)
+        }
+        _ => {}
+    }
+}
+
+// ── Scenarios ────────────────────────────────────────────────────
+
+/// Replies for a git checkout at [`PROJECT`] that has a `.crumbeez` dir,
+/// no config, and nothing logged yet, on a host where everything works.
+fn existing_project() -> Vec<(&'static str, Reply)> {
+    let probe_data: Vec<u8> = (0..=u8::MAX).collect();
+    vec![
+        ("GitToplevel", Reply::ok("/home/me/app\n")),
+        ("GitSuperproject", Reply::ok("")),
+        ("CheckCrumbeezDir", Reply::ok("")),
+        ("MkdirCrumbeez", Reply::ok("")),
+        ("WriteLocalGitignore", Reply::ok("")),
+        ("DataHome", Reply::ok("/home/me/.local/share")),
+        ("MkdirShared", Reply::ok("")),
+        ("Probe", Reply::ok("")),
+        ("RoundTrip", Reply::ok(&base64_encode(&probe_data))),
+        ("ReadConfig", Reply::ok("")),
+        ("ReadDaily", Reply::ok("")),
+        ("ReadFile", Reply::failed(1, "")),
+        ("ReadEventLog", Reply::ok("")),
+        ("WriteEventLog", Reply::ok("")),
+        ("AppendDaily", Reply::ok("")),
+        ("AppendLink", Reply::ok("")),
+    ]
+}
+
+/// A shell in the first tab, focused.
+fn shell_focused() -> Event {
+    let shell = PaneInfo {
+        id: 1,
+        is_focused: true,
+        is_selectable: true,
+        title: "zsh".to_string(),
+        terminal_command: Some("zsh".to_string()),
+        ..Default::default()
+    };
+    Event::PaneUpdate(PaneManifest {
+        panes: [(0, vec![shell])].into_iter().collect(),
+    })
+}
+
+#[test]
+fn discovery_settles_on_the_checkout_and_starts_intercepting() {
+    let mut replay = Replay::load(&[], &existing_project());
+    let calls = replay.take_calls();
+    assert!(
+        calls
+            .iter()
+            .any(|call| matches!(call, HostCall::RequestPermission(p) if p.contains(&PermissionType::InterceptInput))),
+        "{:?}",
+        calls
+    );
+    assert!(replay.unanswered().is_empty());
+
+    replay.send(Event::PermissionRequestResult(PermissionStatus::Granted));
+    assert_eq!(
+        replay.state.discovery.phase,
+        DiscoveryPhase::Ready {
+            dirs: vec![PathBuf::from("/home/me/app/.crumbeez")]
+        }
+    );
+    let calls = replay.take_calls();
+    let git = calls.iter().find_map(|call| match call {
+        HostCall::RunCommand { args, cwd, .. } if args.first().is_some_and(|a| a == "git") => {
+            Some((args.clone(), cwd.clone()))
+        }
+        _ => None,
+    });
+    assert_eq!(
+        git,
+        Some((
+            vec!["git".into(), "rev-parse".into(), "--show-toplevel".into()],
+            PathBuf::from(PROJECT)
+        ))
+    );
+    assert!(calls
+        .iter()
+        .any(|call| matches!(call, HostCall::InterceptKeyPresses)));
+    assert!(replay.unanswered().is_empty(), "{:?}", replay.unanswered());
+}
+
+#[test]
+fn intercepted_keys_reach_the_pane_and_the_log() {
+    let mut replay = Replay::start(&[], &existing_project());
+    replay.send(shell_focused());
+    replay.take_calls();
+
+    replay.type_line("ls");
+    assert_eq!(replay.written(), b"ls\r");
+    let typed: Vec<String> = replay
+        .state
+        .event_log
+        .entries()
+        .map(|entry| entry.event.to_string())
+        .collect();
+    assert!(
+        typed.iter().any(|event| event.contains("ls")),
+        "{:?}",
+        typed
+    );
+}
+
+#[test]
+fn the_browser_leaves_keys_in_other_panes_alone() {
+    let mut replay = Replay::start(&[], &existing_project());
+    replay.send(shell_focused());
+    replay.pipe(crate::browser::BROWSE_PIPE, None);
+    assert!(replay.state.ui.browser.is_some());
+    replay.take_calls();
+
+    replay.type_line("ls");
+    assert_eq!(replay.written(), b"ls\r");
+    assert!(replay.state.ui.browser.is_some());
+    assert!(replay
+        .state
+        .event_log
+        .entries()
+        .any(|entry| entry.event.to_string().contains("ls")));
+}
+
+#[test]
+fn quiet_time_summarizes_what_was_typed() {
+    let mut replay = Replay::start(&[("inactivity_timeout", "10s")], &existing_project());
+    replay.send(shell_focused());
+    replay.type_line("cargo test");
+    assert!(replay.state.summarizer.summaries.is_empty());
+
+    replay.wait(Duration::from_secs(5));
+    assert!(replay.state.summarizer.summaries.is_empty());
+    replay.wait(Duration::from_secs(10));
+    assert_eq!(replay.state.summarizer.summaries.len(), 1);
+    assert_eq!(replay.state.event_log.unconsumed_count(), 0);
+    let appended = replay.take_calls().into_iter().any(|call| {
+        matches!(call, HostCall::RunCommand { args, .. }
+            if args.iter().any(|arg| arg.contains("summaries/2024-03-01.md")))
+    });
+    assert!(appended);
+}
+
+#[test]
+fn typing_past_the_timeout_stays_one_run() {
+    let mut replay = Replay::start(&[("inactivity_timeout", "10s")], &existing_project());
+    replay.send(shell_focused());
+    replay.press(BareKey::Left);
+    // 100 keys 150ms apart: 15s with no pause as long as the timeout.
+    let word = "x".repeat(100);
+    replay.type_text(&word);
+    assert!(replay.state.summarizer.summaries.is_empty());
+
+    replay.press(BareKey::Enter);
+    let typed: Vec<String> = replay
+        .state
+        .event_log
+        .entries()
+        .map(|entry| entry.event.to_string())
+        .filter(|event| event.contains('x'))
+        .collect();
+    assert_eq!(typed.len(), 1, "{:?}", typed);
+    assert!(typed[0].contains(&word), "{:?}", typed);
+}
+
+#[test]
+fn tags_piped_in_go_in_the_next_summary() {
+    let mut replay = Replay::start(&[("inactivity_timeout", "10s")], &existing_project());
+    replay.send(shell_focused());
+    replay.pipe("crumbeez::tag", Some("auth, login-bug"));
+    replay.type_line("cargo test auth");
+    replay.wait(Duration::from_secs(30));
+    let summary = replay.state.summarizer.summaries.iter().next_back();
+    assert_eq!(
+        summary.map(|summary| summary.tags.clone()),
+        Some(vec!["auth".to_string(), "login-bug".to_string()])
+    );
+
+    replay.take_calls();
+    replay.pipe("crumbeez::tag", Some("no:colons"));
+    let reported = replay.take_calls().into_iter().any(|call| {
+        matches!(call, HostCall::CliPipeOutput { output, .. } if output.starts_with("error: "))
+    });
+    assert!(reported);
+}
+
+#[test]
+fn a_hung_git_is_retried_then_given_up_on() {
+    let script: Vec<_> = existing_project()
+        .into_iter()
+        .filter(|(purpose, _)| *purpose != "GitToplevel")
+        .collect();
+    let mut replay = Replay::start(&[], &script);
+    assert_eq!(
+        replay.state.discovery.phase,
+        DiscoveryPhase::FindingGitRoot { attempt: 1 }
+    );
+    assert_eq!(
+        replay.unanswered()[0].args,
+        ["git", "rev-parse", "--show-toplevel"]
+    );
+    replay.wait(Duration::from_secs(6));
+    assert_eq!(
+        replay.state.discovery.phase,
+        DiscoveryPhase::FindingGitRoot { attempt: 2 }
+    );
+    // A reply to the first attempt comes too late to count.
+    let late = replay.answer("GitToplevel", Reply::ok("/home/me/app\n"));
+    assert!(late);
+    assert_eq!(
+        replay.state.discovery.phase,
+        DiscoveryPhase::FindingGitRoot { attempt: 2 }
+    );
+    replay.wait(Duration::from_secs(12));
+    assert!(
+        matches!(replay.state.discovery.phase, DiscoveryPhase::Ready { .. }),
+        "{:?}",
+        replay.state.discovery.phase
+    );
+    assert!(!replay.state.discovery.timeouts.is_empty());
+}
+
+#[test]
+fn a_paste_is_closed_without_waiting_for_the_summary_timer() {
+    let mut replay = Replay::start(&[], &existing_project());
+    replay.send(shell_focused());
+    replay.type_line("ls");
+    // Past discovery's tick, to the summary timer far off.
+    replay.wait(Duration::from_secs(2));
+    let (summary_due, _) = replay.host.timer().expect("a timer for the summary");
+    assert!(summary_due > replay.host.time() + Duration::from_secs(1));
+    replay.take_calls();
+
+    for c in "cargo build".chars() {
+        replay.send(Event::InterceptedKeyPress(KeyWithModifier::new(
+            BareKey::Char(c),
+        )));
+    }
+    let (due, _) = replay.host.timer().expect("a timer for the paste's end");
+    assert!(due <= replay.host.time() + Duration::from_millis(5));
+
+    replay.fire_timer();
+    let written = replay.written();
+    assert!(written.starts_with(b"c") && written.ends_with(PASTE_END));
+    assert_eq!(
+        written
+            .windows(PASTE_START.len())
+            .filter(|w| *w == PASTE_START)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn the_same_events_make_the_same_calls() {
+    let session = || {
+        let mut replay = Replay::start(&[], &existing_project());
+        replay.send(shell_focused());
+        replay.type_line("git status");
+        replay.wait(Duration::from_secs(60));
+        format!("{:?}", replay.take_calls())
+    };
+    assert_eq!(session(), session());
+}
@@ -0,0 +1,427 @@
+//! Daily summary files, the cross-project summary index, and weekly rollups.
+//!
+//! Every summary is appended to the project's `summaries/YYYY-MM-DD.md`,
+//! with a line linking its ID to its events in `summaries/summary-links.kdl`
+//! (see [`crumbeez_lib::SummaryLink`]), a line for each issue it mentions
+//! in `summaries/issue-index.kdl` (see [`crumbeez_lib::IssueLink`]), and
+//! the day's totals to the shared index under `$XDG_DATA_HOME/crumbeez`
+//! (see [`crumbeez_lib::SummaryIndex`]).  A rollup of the current week
+//! across all projects is written on request:
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::rollup
+//! zellij pipe --name crumbeez::rollup -- 2024-03-01   # the week containing a date
+//! ```
+//!
+//! With `prune_daily_summaries true` in the plugin configuration, the daily
+//! files of past days are moved into the rollup once it's written.
+//!
+//! The index also backs the calendar view, a month of the project's daily
+//! event counts as a heatmap:
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::calendar
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "analytics")]
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "analytics")]
+use tracing::info;
+use tracing::{debug, error};
+
+use crate::command_router::{self, Retry};
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+
+/// Pipe message name that writes a weekly rollup.
+pub const ROLLUP_PIPE: &str = "crumbeez::rollup";
+
+/// Pipe message name that opens or closes the calendar view.
+pub const CALENDAR_PIPE: &str = "crumbeez::calendar";
+
+pub const CTX_PURPOSE: &str = "crumbeez_rollup_purpose";
+
+/// Minimum time between index records for the same day, so the index
+/// doesn't gain a line for every summary.
+#[cfg(feature = "analytics")]
+const INDEX_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug)]
+enum RollupCommand {
+    DataHome,
+    MkdirShared,
+    AppendDaily,
+    AppendLink,
+    AppendSubmoduleLink,
+    AppendIssues,
+    AppendIndex,
+    /// Read the index to roll up the week containing the given time.
+    ReadIndex {
+        day_ms: u64,
+    },
+    WriteRollup,
+    RewriteIndex,
+    PruneDaily,
+    /// Read the index for the calendar view.
+    ReadCalendar,
+}
+
+impl ContextTag for RollupCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::DataHome => "DataHome".to_string(),
+            Self::MkdirShared => "MkdirShared".to_string(),
+            Self::AppendDaily => "AppendDaily".to_string(),
+            Self::AppendLink => "AppendLink".to_string(),
+            Self::AppendSubmoduleLink => "AppendSubmoduleLink".to_string(),
+            Self::AppendIssues => "AppendIssues".to_string(),
+            Self::AppendIndex => "AppendIndex".to_string(),
+            Self::ReadIndex { day_ms } => format!("ReadIndex:{}", day_ms),
+            Self::WriteRollup => "WriteRollup".to_string(),
+            Self::RewriteIndex => "RewriteIndex".to_string(),
+            Self::PruneDaily => "PruneDaily".to_string(),
+            Self::ReadCalendar => "ReadCalendar".to_string(),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        Some(match split_tag(tag) {
+            ("DataHome", None) => Self::DataHome,
+            ("MkdirShared", None) => Self::MkdirShared,
+            ("AppendDaily", None) => Self::AppendDaily,
+            ("AppendLink", None) => Self::AppendLink,
+            ("AppendSubmoduleLink", None) => Self::AppendSubmoduleLink,
+            ("AppendIssues", None) => Self::AppendIssues,
+            ("AppendIndex", None) => Self::AppendIndex,
+            ("ReadIndex", Some(day_ms)) => Self::ReadIndex {
+                day_ms: day_ms.parse().ok()?,
+            },
+            ("WriteRollup", None) => Self::WriteRollup,
+            ("RewriteIndex", None) => Self::RewriteIndex,
+            ("PruneDaily", None) => Self::PruneDaily,
+            ("ReadCalendar", None) => Self::ReadCalendar,
+            _ => return None,
+        })
+    }
+
+    fn retry(&self) -> Retry {
+        match self {
+            Self::DataHome | Self::MkdirShared | Self::ReadIndex { .. } | Self::ReadCalendar => {
+                Retry::IDEMPOTENT
+            }
+            _ => Retry::ONCE,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct RollupIO {
+    host: HostHandle,
+    cwd: PathBuf,
+    /// `$XDG_DATA_HOME`, once the shell has told us.
+    data_home: Option<PathBuf>,
+    /// Move past days' summary files into the rollup.
+    pub prune_daily: bool,
+    /// Latest index record not yet appended, and when one last was.
+    #[cfg(feature = "analytics")]
+    pending_record: Option<crumbeez_lib::DayRecord>,
+    #[cfg(feature = "analytics")]
+    last_record: Option<(String, SystemTime)>,
+    /// Rollup being written and the daily files to move into it after.
+    #[cfg(feature = "analytics")]
+    pending_prune: Option<(PathBuf, Vec<PathBuf>)>,
+    /// How the last rollup went, shown in the plugin pane.
+    pub status: Option<String>,
+    /// The index as last read for the calendar view.
+    #[cfg(feature = "analytics")]
+    pub calendar_index: Option<crumbeez_lib::SummaryIndex>,
+}
+
+impl RollupIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Find the shared data directory.  Call once discovery is ready.
+    pub fn start(&mut self, cwd: PathBuf) {
+        self.cwd = cwd.clone();
+        command_router::run(
+            &self.host,
+            &RollupCommand::DataHome,
+            &shell::platform().data_home(),
+            cwd,
+        );
+    }
+
+    /// Append a summary's `markdown` to `dir`'s file for `day`, the day it
+    /// was made.
+    pub fn append_daily(&mut self, dir: &Path, day: &str, markdown: &str) {
+        let path = crumbeez_lib::daily_summary_path(dir, day);
+        let cmd = shell::platform().append_text(&path, &format!("{}\n", markdown));
+        command_router::run(
+            &self.host,
+            &RollupCommand::AppendDaily,
+            &cmd,
+            self.cwd.clone(),
+        );
+    }
+
+    /// Record what a summary in `dir` was made from: its
+    /// [`SummaryLink`](crumbeez_lib::SummaryLink) line.
+    pub fn append_link(&mut self, dir: &Path, link: &str) {
+        let path = crumbeez_lib::summary_links_path(dir);
+        let cmd = shell::platform().append_text(&path, link);
+        command_router::run(
+            &self.host,
+            &RollupCommand::AppendLink,
+            &cmd,
+            self.cwd.clone(),
+        );
+    }
+
+    /// Point the superproject data dir `dir` at a summary made in one of
+    /// its submodules: its [`SubmoduleLink`](crumbeez_lib::SubmoduleLink)
+    /// line.
+    pub fn append_submodule_link(&mut self, dir: &Path, link: &str) {
+        let path = crumbeez_lib::submodule_links_path(dir);
+        let cmd = shell::platform().append_text(&path, link);
+        command_router::run(
+            &self.host,
+            &RollupCommand::AppendSubmoduleLink,
+            &cmd,
+            self.cwd.clone(),
+        );
+    }
+
+    /// Record the issues a summary in `dir` mentions: its
+    /// [`IssueLink`](crumbeez_lib::IssueLink) lines.
+    pub fn append_issues(&mut self, dir: &Path, lines: &str) {
+        let path = crumbeez_lib::issue_index_path(dir);
+        let cmd = shell::platform().append_text(&path, lines);
+        command_router::run(
+            &self.host,
+            &RollupCommand::AppendIssues,
+            &cmd,
+            self.cwd.clone(),
+        );
+    }
+
+    /// Note the day's totals in the shared index, at most every
+    /// [`INDEX_INTERVAL`] per day.
+    #[cfg(feature = "analytics")]
+    pub fn record_day(&mut self, record: crumbeez_lib::DayRecord) {
+        let due = self.last_record.as_ref().is_none_or(|(day, at)| {
+            *day != record.day
+                || self
+                    .host
+                    .now()
+                    .duration_since(*at)
+                    .is_ok_and(|elapsed| elapsed >= INDEX_INTERVAL)
+        });
+        self.pending_record = Some(record);
+        if due {
+            self.flush_record();
+        }
+    }
+
+    #[cfg(feature = "analytics")]
+    fn flush_record(&mut self) {
+        let Some(data_home) = &self.data_home else {
+            return;
+        };
+        let Some(record) = self.pending_record.take() else {
+            return;
+        };
+        let path = crumbeez_lib::summary_index_path(data_home);
+        let cmd = shell::platform().append_text(&path, &record.to_kdl());
+        command_router::run(
+            &self.host,
+            &RollupCommand::AppendIndex,
+            &cmd,
+            self.cwd.clone(),
+        );
+        self.last_record = Some((record.day, self.host.now()));
+    }
+
+    /// Roll up the week containing `day_ms`.
+    #[cfg(feature = "analytics")]
+    pub fn generate(&mut self, day_ms: u64) {
+        // Make sure this session's latest totals are included.
+        self.flush_record();
+        let Some(data_home) = &self.data_home else {
+            self.status = Some("no rollup before the data directory is known".to_string());
+            return;
+        };
+        let path = crumbeez_lib::summary_index_path(data_home);
+        let cmd = shell::platform().read_first_existing(&[&path]);
+        self.status = Some("reading the summary index…".to_string());
+        command_router::run(
+            &self.host,
+            &RollupCommand::ReadIndex { day_ms },
+            &cmd,
+            self.cwd.clone(),
+        );
+    }
+
+    /// Read the index for the calendar view into
+    /// [`calendar_index`](Self::calendar_index).
+    #[cfg(feature = "analytics")]
+    pub fn load_calendar(&mut self) {
+        // Show today's activity too.
+        self.flush_record();
+        let Some(data_home) = &self.data_home else {
+            self.status = Some("no calendar before the data directory is known".to_string());
+            return;
+        };
+        let path = crumbeez_lib::summary_index_path(data_home);
+        let cmd = shell::platform().read_first_existing(&[&path]);
+        command_router::run(
+            &self.host,
+            &RollupCommand::ReadCalendar,
+            &cmd,
+            self.cwd.clone(),
+        );
+    }
+
+    #[cfg(feature = "analytics")]
+    fn write_rollup(&mut self, index_text: &str, day_ms: u64) {
+        let Some(data_home) = self.data_home.clone() else {
+            return;
+        };
+        let index = crumbeez_lib::SummaryIndex::parse(index_text);
+        if index.skipped_lines > 0 {
+            error!(
+                skipped = index.skipped_lines,
+                "Skipped damaged summary index lines"
+            );
+        }
+        let rollup = crumbeez_lib::WeeklyRollup::from_index(&index, day_ms);
+        let path = crumbeez_lib::rollups_dir(&data_home).join(rollup.file_name());
+        info!(?path, days = rollup.days.len(), "Writing weekly rollup");
+
+        let cwd = self.cwd.clone();
+        let cmd = shell::platform().write_text(&path, &rollup.to_markdown());
+        command_router::run(&self.host, &RollupCommand::WriteRollup, &cmd, cwd.clone());
+        // Drop superseded records while we have the whole index in hand.
+        let index_path = crumbeez_lib::summary_index_path(&data_home);
+        let cmd = shell::platform().write_text(&index_path, &index.to_kdl());
+        command_router::run(&self.host, &RollupCommand::RewriteIndex, &cmd, cwd);
+
+        let daily = rollup.past_daily_files(now_ms(&self.host));
+        self.pending_prune = self.prune_daily.then_some((path.clone(), daily));
+        self.status = Some(format!("writing {}…", path.display()));
+    }
+
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stdout: &[u8],
+        stderr: &[u8],
+        exit_code: Option<i32>,
+    ) -> bool {
+        let Some(purpose) = purpose_of::<RollupCommand>(context) else {
+            return false;
+        };
+        debug!(?exit_code, ?purpose, "Rollup command result");
+        let reading = matches!(
+            purpose,
+            RollupCommand::ReadIndex { .. } | RollupCommand::ReadCalendar
+        );
+        if exit_code != Some(0) && !reading {
+            let err = String::from_utf8_lossy(stderr);
+            error!(%err, ?purpose, "Rollup command failed");
+        }
+
+        match purpose {
+            RollupCommand::DataHome => {
+                let data_home = String::from_utf8_lossy(stdout).trim().to_string();
+                if exit_code == Some(0) && !data_home.is_empty() {
+                    let data_home = PathBuf::from(data_home);
+                    let cmd =
+                        shell::platform().create_dirs(&[&crumbeez_lib::rollups_dir(&data_home)]);
+                    command_router::run(
+                        &self.host,
+                        &RollupCommand::MkdirShared,
+                        &cmd,
+                        self.cwd.clone(),
+                    );
+                    self.data_home = Some(data_home);
+                }
+            }
+            RollupCommand::MkdirShared => {
+                #[cfg(feature = "analytics")]
+                self.flush_record();
+            }
+            RollupCommand::AppendDaily
+            | RollupCommand::AppendLink
+            | RollupCommand::AppendSubmoduleLink
+            | RollupCommand::AppendIssues
+            | RollupCommand::AppendIndex
+            | RollupCommand::RewriteIndex => {}
+            RollupCommand::ReadIndex { day_ms } => {
+                // A missing index just means an empty week.
+                #[cfg(feature = "analytics")]
+                self.write_rollup(&String::from_utf8_lossy(stdout), day_ms);
+                #[cfg(not(feature = "analytics"))]
+                let _ = day_ms;
+            }
+            RollupCommand::ReadCalendar => {
+                // A missing index is an empty calendar.
+                #[cfg(feature = "analytics")]
+                {
+                    self.calendar_index = Some(crumbeez_lib::SummaryIndex::parse(
+                        &String::from_utf8_lossy(stdout),
+                    ));
+                }
+            }
+            RollupCommand::WriteRollup => {
+                if exit_code != Some(0) {
+                    self.status = Some("failed to write the weekly rollup".to_string());
+                    #[cfg(feature = "analytics")]
+                    {
+                        self.pending_prune = None;
+                    }
+                    return true;
+                }
+                self.status = Some("weekly rollup written".to_string());
+                #[cfg(feature = "analytics")]
+                if let Some((rollup, daily)) = self.pending_prune.take() {
+                    if !daily.is_empty() {
+                        let daily: Vec<&Path> = daily.iter().map(PathBuf::as_path).collect();
+                        let cmd = shell::platform().append_files_and_remove(&rollup, &daily);
+                        command_router::run(
+                            &self.host,
+                            &RollupCommand::PruneDaily,
+                            &cmd,
+                            self.cwd.clone(),
+                        );
+                    }
+                }
+            }
+            RollupCommand::PruneDaily => {
+                if exit_code == Some(0) {
+                    self.status =
+                        Some("weekly rollup written, daily files moved into it".to_string());
+                }
+            }
+        }
+        true
+    }
+}
+
+#[cfg(feature = "analytics")]
+fn now_ms(host: &HostHandle) -> u64 {
+    host.now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
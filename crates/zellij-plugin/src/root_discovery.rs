@@ -1,36 +1,105 @@
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "minimal"))]
+use tracing::warn;
 use tracing::{debug, error, info};
-use zellij_tile::prelude::*;
 
+use crate::command_router::{self, Retry};
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+use crate::shell::{self, EXIT_NETWORK_FS};
 pub use crumbeez_lib::DiscoveryPhase;
+use crumbeez_lib::GitignoreMode;
 
 /// Context key used to tag run_command requests for root discovery.
-const CTX_PURPOSE: &str = "crumbeez_purpose";
+pub const CTX_PURPOSE: &str = "crumbeez_purpose";
 
 /// Identifies which async command produced a given `RunCommandResult`.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug)]
 enum CommandPurpose {
     GitToplevel,
     GitSuperproject,
-    MkdirCrumbeez,
+    CheckCrumbeezDir,
+    MkdirCrumbeez {
+        root: PathBuf,
+    },
+    XdgDataHome,
+    MkdirFallback,
+    /// `grep` for an existing `.crumbeez` entry in the root's `.gitignore`.
+    CheckProjectGitignore {
+        root: PathBuf,
+    },
+    AppendProjectGitignore,
+    WriteLocalGitignore,
+    /// `test -d` on the primary data dir, checking it's still there.
+    ValidateDir,
 }
 
-/// Build a context map tagged with the given purpose.
-fn purpose_context(purpose: CommandPurpose) -> BTreeMap<String, String> {
-    let mut ctx = BTreeMap::new();
-    ctx.insert(
-        CTX_PURPOSE.to_string(),
-        serde_json::to_string(&purpose).expect("CommandPurpose serialization is infallible"),
-    );
-    ctx
+impl ContextTag for CommandPurpose {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::GitToplevel => "GitToplevel".to_string(),
+            Self::GitSuperproject => "GitSuperproject".to_string(),
+            Self::CheckCrumbeezDir => "CheckCrumbeezDir".to_string(),
+            Self::MkdirCrumbeez { root } => format!("MkdirCrumbeez:{}", root.display()),
+            Self::XdgDataHome => "XdgDataHome".to_string(),
+            Self::MkdirFallback => "MkdirFallback".to_string(),
+            Self::CheckProjectGitignore { root } => {
+                format!("CheckProjectGitignore:{}", root.display())
+            }
+            Self::AppendProjectGitignore => "AppendProjectGitignore".to_string(),
+            Self::WriteLocalGitignore => "WriteLocalGitignore".to_string(),
+            Self::ValidateDir => "ValidateDir".to_string(),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        Some(match split_tag(tag) {
+            ("GitToplevel", None) => Self::GitToplevel,
+            ("GitSuperproject", None) => Self::GitSuperproject,
+            ("CheckCrumbeezDir", None) => Self::CheckCrumbeezDir,
+            ("MkdirCrumbeez", Some(root)) => Self::MkdirCrumbeez { root: root.into() },
+            ("XdgDataHome", None) => Self::XdgDataHome,
+            ("MkdirFallback", None) => Self::MkdirFallback,
+            ("CheckProjectGitignore", Some(root)) => {
+                Self::CheckProjectGitignore { root: root.into() }
+            }
+            ("AppendProjectGitignore", None) => Self::AppendProjectGitignore,
+            ("WriteLocalGitignore", None) => Self::WriteLocalGitignore,
+            ("ValidateDir", None) => Self::ValidateDir,
+            _ => return None,
+        })
+    }
+
+    /// Git can hang on a network mount or a credential prompt, so the
+    /// discovery probes get short timeouts and a couple of retries before
+    /// discovery falls back to a default.
+    fn retry(&self) -> Retry {
+        let probe = |attempts| Retry {
+            timeout: Duration::from_secs(5),
+            attempts,
+        };
+        match self {
+            Self::GitToplevel | Self::GitSuperproject => probe(3),
+            Self::CheckCrumbeezDir | Self::XdgDataHome => probe(2),
+            Self::CheckProjectGitignore { .. } | Self::ValidateDir => Retry::IDEMPOTENT,
+            Self::MkdirCrumbeez { .. } | Self::MkdirFallback => Retry {
+                timeout: Duration::from_secs(10),
+                attempts: 1,
+            },
+            Self::AppendProjectGitignore | Self::WriteLocalGitignore => Retry::ONCE,
+        }
+    }
 }
 
 /// State for the root discovery process.
 #[derive(Default, Debug)]
 pub struct RootDiscovery {
+    host: HostHandle,
     /// The cwd where the Zellij session was started.
     pub initial_cwd: PathBuf,
     /// The git root for the repo containing initial_cwd (if any).
@@ -39,23 +108,101 @@ pub struct RootDiscovery {
     pub parent_git_root: Option<PathBuf>,
     /// Current phase of the discovery state machine.
     pub phase: DiscoveryPhase,
+    /// The project roots chosen for this session (each gets a data dir).
+    pub roots: Vec<PathBuf>,
+    /// Roots whose data lives under `$XDG_DATA_HOME` because their own
+    /// directory was read-only or on a network mount.
+    pub fallback_roots: Vec<PathBuf>,
+    /// Whether `.gitignore` handling has already been kicked off.
+    gitignore_started: bool,
+    /// Probes that timed out and what discovery did instead, shown in the
+    /// plugin pane.
+    pub timeouts: Vec<String>,
+    /// Discovery is running again for a session that already had a data
+    /// dir, so a missing one is recreated instead of starting onboarding.
+    rediscovering: bool,
+    /// Rediscovery had to create the data dir, so the plugin's settings
+    /// should be written to it rather than read from it.
+    pub recreated: bool,
+    /// The data dir was found missing; discovery should run again.
+    pub stale: bool,
 }
 
 impl RootDiscovery {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
     /// Initialize with the plugin's initial_cwd and kick off discovery.
     /// Call this once permissions have been granted.
     pub fn start(&mut self, initial_cwd: PathBuf) {
         self.initial_cwd = initial_cwd.clone();
-        self.phase = DiscoveryPhase::FindingGitRoot;
+        self.phase = DiscoveryPhase::FindingGitRoot { attempt: 1 };
 
-        run_command_with_env_variables_and_cwd(
+        command_router::run(
+            &self.host,
+            &CommandPurpose::GitToplevel,
             &["git", "rev-parse", "--show-toplevel"],
-            BTreeMap::new(),
             initial_cwd,
-            purpose_context(CommandPurpose::GitToplevel),
         );
     }
 
+    /// Run discovery again from `cwd`, after the data dir went missing or
+    /// the project moved.  Everything found last time is forgotten.
+    #[cfg(any(test, not(feature = "minimal")))]
+    pub fn rediscover(&mut self, cwd: PathBuf) {
+        info!(?cwd, "Rediscovering project roots");
+        *self = Self {
+            rediscovering: true,
+            ..Self::new(self.host.clone())
+        };
+        self.start(cwd);
+    }
+
+    /// Check the primary data dir still exists.  If it doesn't, [`stale`]
+    /// is set once the result arrives.
+    ///
+    /// [`stale`]: Self::stale
+    #[cfg(not(feature = "minimal"))]
+    pub fn validate(&mut self) {
+        let DiscoveryPhase::Ready { ref dirs } = self.phase else {
+            return;
+        };
+        let Some(dir) = dirs.first() else {
+            return;
+        };
+        debug!(?dir, "Validating data dir");
+        let cmd = shell::platform().dir_exists(dir);
+        command_router::run(
+            &self.host,
+            &CommandPurpose::ValidateDir,
+            &cmd,
+            self.initial_cwd.clone(),
+        );
+    }
+
+    /// Move the primary data dir to fallback storage after writes to it
+    /// kept failing.  Does nothing unless discovery is done and the primary
+    /// dir is still the project's own.
+    #[cfg(not(feature = "minimal"))]
+    pub fn fall_back(&mut self) {
+        let DiscoveryPhase::Ready { ref dirs } = self.phase else {
+            return;
+        };
+        let (Some(dir), Some(root)) = (dirs.first(), self.roots.first()) else {
+            return;
+        };
+        if crumbeez_lib::is_fallback_dir(dir) {
+            return;
+        }
+        warn!(?root, "Project dir not writable any more, falling back");
+        let failed = vec![root.clone()];
+        self.resolve_fallback(dirs.clone(), failed);
+    }
+
     /// Handle a RunCommandResult event. Returns true if this event was consumed
     /// by the discovery process (i.e. it was tagged with our context key).
     pub fn handle_command_result(
@@ -65,12 +212,8 @@ impl RootDiscovery {
         stderr: &[u8],
         context: &BTreeMap<String, String>,
     ) -> bool {
-        let purpose: CommandPurpose = match context.get(CTX_PURPOSE) {
-            Some(s) => match serde_json::from_str(s) {
-                Ok(p) => p,
-                Err(_) => return false,
-            },
-            None => return false, // Not our command
+        let Some(purpose) = purpose_of::<CommandPurpose>(context) else {
+            return false;
         };
 
         match purpose {
@@ -78,10 +221,133 @@ impl RootDiscovery {
             CommandPurpose::GitSuperproject => {
                 self.handle_git_superproject(exit_code, stdout, stderr)
             }
-            CommandPurpose::MkdirCrumbeez => self.handle_mkdir_result(exit_code, stderr),
+            CommandPurpose::CheckCrumbeezDir => self.handle_check_existing(exit_code),
+            CommandPurpose::MkdirCrumbeez { root } => {
+                self.handle_mkdir_result(exit_code, stderr, root)
+            }
+            CommandPurpose::XdgDataHome => self.handle_xdg_data_home(exit_code, stdout),
+            CommandPurpose::MkdirFallback => self.handle_mkdir_fallback(exit_code, stderr),
+            CommandPurpose::CheckProjectGitignore { root } => {
+                self.handle_check_project_gitignore(exit_code, root)
+            }
+            CommandPurpose::ValidateDir => {
+                // A timeout says nothing about the dir; try again next time.
+                if exit_code.is_some_and(|code| code != 0) {
+                    error!(phase = ?self.phase, "Data dir has gone missing");
+                    self.stale = true;
+                }
+                true
+            }
+            CommandPurpose::AppendProjectGitignore | CommandPurpose::WriteLocalGitignore => {
+                if exit_code != Some(0) {
+                    let err = String::from_utf8_lossy(stderr);
+                    error!(%err, "Failed to update .gitignore");
+                }
+                true
+            }
+        }
+    }
+
+    /// Whether discovery is waiting on a command (as opposed to the user,
+    /// or being finished).
+    pub fn in_progress(&self) -> bool {
+        matches!(
+            self.phase,
+            DiscoveryPhase::FindingGitRoot { .. }
+                | DiscoveryPhase::FindingSuperproject { .. }
+                | DiscoveryPhase::CheckingExisting { .. }
+                | DiscoveryPhase::CreatingDirs { .. }
+                | DiscoveryPhase::ResolvingFallback { .. }
+        )
+    }
+
+    /// The project's data dir, once discovery is done.
+    pub fn data_dir(&self) -> Option<&Path> {
+        match self.phase {
+            DiscoveryPhase::Ready { ref dirs } => dirs.first().map(PathBuf::as_path),
+            _ => None,
+        }
+    }
+
+    /// Note that a timed-out discovery command is being retried.
+    pub fn handle_retry(&mut self, context: &BTreeMap<String, String>, attempt: u32) {
+        let Some(purpose) = purpose_of::<CommandPurpose>(context) else {
+            return;
+        };
+        match (purpose, &mut self.phase) {
+            (CommandPurpose::GitToplevel, DiscoveryPhase::FindingGitRoot { attempt: a })
+            | (
+                CommandPurpose::GitSuperproject,
+                DiscoveryPhase::FindingSuperproject { attempt: a },
+            ) => *a = attempt,
+            _ => {}
+        }
+    }
+
+    /// Keep the `.crumbeez` dirs out of version control according to `mode`.
+    /// Runs at most once, after discovery is ready and the project config is
+    /// known.
+    pub fn ensure_gitignore(&mut self, mode: GitignoreMode) {
+        if self.gitignore_started {
+            return;
+        }
+        let DiscoveryPhase::Ready { ref dirs } = self.phase else {
+            return;
+        };
+        self.gitignore_started = true;
+
+        for dir in dirs {
+            if crumbeez_lib::is_fallback_dir(dir) {
+                continue;
+            }
+            match mode {
+                GitignoreMode::None => {}
+                GitignoreMode::Local => {
+                    let cmd = shell::platform().write_text_if_missing(
+                        &dir.join(".gitignore"),
+                        crumbeez_lib::LOCAL_GITIGNORE,
+                    );
+                    command_router::run(
+                        &self.host,
+                        &CommandPurpose::WriteLocalGitignore,
+                        &cmd,
+                        self.initial_cwd.clone(),
+                    );
+                }
+                GitignoreMode::Project => {
+                    let Some(root) = dir.parent() else {
+                        continue;
+                    };
+                    command_router::run(
+                        &self.host,
+                        &CommandPurpose::CheckProjectGitignore {
+                            root: root.to_path_buf(),
+                        },
+                        &shell::platform()
+                            .contains_line(Path::new(".gitignore"), r"/?\.crumbeez/?"),
+                        root.to_path_buf(),
+                    );
+                }
+            }
         }
     }
 
+    fn handle_check_project_gitignore(&mut self, exit_code: Option<i32>, root: PathBuf) -> bool {
+        if exit_code == Some(0) {
+            debug!(?root, ".crumbeez already in .gitignore");
+            return true;
+        }
+        info!(?root, "Adding .crumbeez/ to .gitignore");
+        let cmd = shell::platform().append_line(Path::new(".gitignore"), ".crumbeez/");
+        command_router::run(
+            &self.host,
+            &CommandPurpose::AppendProjectGitignore,
+            &cmd,
+            root,
+        );
+        true
+    }
+
     fn handle_git_toplevel(
         &mut self,
         exit_code: Option<i32>,
@@ -93,25 +359,32 @@ impl RootDiscovery {
             if !root.is_empty() {
                 let root_path = PathBuf::from(&root);
                 self.git_root = Some(root_path.clone());
-                self.phase = DiscoveryPhase::FindingSuperproject;
+                self.phase = DiscoveryPhase::FindingSuperproject { attempt: 1 };
 
                 // Check if this is a submodule
-                run_command_with_env_variables_and_cwd(
+                command_router::run(
+                    &self.host,
+                    &CommandPurpose::GitSuperproject,
                     &["git", "rev-parse", "--show-superproject-working-tree"],
-                    BTreeMap::new(),
                     root_path,
-                    purpose_context(CommandPurpose::GitSuperproject),
                 );
                 return true;
             }
         }
 
+        if exit_code.is_none() {
+            self.timeouts.push(format!(
+                "git rev-parse timed out, using {} as the project root",
+                self.initial_cwd.display()
+            ));
+        }
+
         // Not a git repo — use initial_cwd as root
         debug!(
             path = ?self.initial_cwd,
             "Not a git repo, using initial_cwd"
         );
-        self.create_crumbeez_dirs(vec![self.initial_cwd.clone()]);
+        self.check_existing(vec![self.initial_cwd.clone()]);
         true
     }
 
@@ -128,6 +401,11 @@ impl RootDiscovery {
             roots.push(git_root.clone());
         }
 
+        if exit_code.is_none() {
+            self.timeouts
+                .push("git timed out checking for a parent repo, assuming none".to_string());
+        }
+
         // If superproject found, also include it
         if exit_code == Some(0) {
             let superproject = String::from_utf8_lossy(stdout).trim().to_string();
@@ -142,25 +420,175 @@ impl RootDiscovery {
             }
         }
 
-        self.create_crumbeez_dirs(roots);
+        self.check_existing(roots);
+        true
+    }
+
+    /// Resume discovery after first-run onboarding with the roots the user
+    /// chose.  No-op unless discovery is waiting for onboarding.
+    pub fn finish_onboarding(&mut self, roots: Vec<PathBuf>) {
+        if matches!(self.phase, DiscoveryPhase::AwaitingOnboarding { .. }) {
+            self.create_crumbeez_dirs(roots);
+        }
+    }
+
+    /// Check whether the primary root already has a `.crumbeez` dir.  If not,
+    /// this is a first run and discovery pauses for onboarding.
+    fn check_existing(&mut self, roots: Vec<PathBuf>) {
+        let Some(primary) = roots.first() else {
+            self.phase = DiscoveryPhase::Failed("No project root found".to_string());
+            return;
+        };
+        let cmd = shell::platform().dir_exists(&crumbeez_lib::crumbeez_dir(primary));
+        command_router::run(
+            &self.host,
+            &CommandPurpose::CheckCrumbeezDir,
+            &cmd,
+            self.initial_cwd.clone(),
+        );
+
+        self.phase = DiscoveryPhase::CheckingExisting { roots };
+    }
+
+    fn handle_check_existing(&mut self, exit_code: Option<i32>) -> bool {
+        if let DiscoveryPhase::CheckingExisting { ref roots } = self.phase {
+            let roots = roots.clone();
+            if exit_code == Some(0) {
+                self.create_crumbeez_dirs(roots);
+            } else if self.rediscovering {
+                info!(?roots, "No .crumbeez dir found, recreating it");
+                self.recreated = true;
+                self.create_crumbeez_dirs(roots);
+            } else {
+                if exit_code.is_none() {
+                    self.timeouts.push(
+                        "Checking for an existing .crumbeez dir timed out, assuming none"
+                            .to_string(),
+                    );
+                }
+                info!(?roots, "No .crumbeez dir found, starting onboarding");
+                self.phase = DiscoveryPhase::AwaitingOnboarding { roots };
+            }
+        }
         true
     }
 
-    fn handle_mkdir_result(&mut self, exit_code: Option<i32>, stderr: &[u8]) -> bool {
+    fn handle_mkdir_result(
+        &mut self,
+        exit_code: Option<i32>,
+        stderr: &[u8],
+        root: PathBuf,
+    ) -> bool {
         if let DiscoveryPhase::CreatingDirs {
             ref mut pending,
             ref dirs,
+            ref mut failed,
+        } = self.phase
+        {
+            match exit_code {
+                Some(0) => {}
+                Some(EXIT_NETWORK_FS) => {
+                    info!(?root, "Project is on a network mount");
+                    failed.push(root);
+                }
+                _ => {
+                    let err = String::from_utf8_lossy(stderr);
+                    error!(%err, ?root, "Project dir not writable");
+                    failed.push(root);
+                }
+            }
+
+            *pending = pending.saturating_sub(1);
+            if *pending == 0 {
+                let dirs = dirs.clone();
+                if failed.is_empty() {
+                    info!(?dirs, "Root discovery complete");
+                    self.phase = DiscoveryPhase::Ready { dirs };
+                } else {
+                    let failed = std::mem::take(failed);
+                    self.resolve_fallback(dirs, failed);
+                }
+            }
+        }
+        true
+    }
+
+    /// Ask the shell where `$XDG_DATA_HOME` (`%LOCALAPPDATA%` on Windows)
+    /// is, so roots that couldn't be written to can store their data there
+    /// instead.
+    fn resolve_fallback(&mut self, dirs: Vec<PathBuf>, failed: Vec<PathBuf>) {
+        command_router::run(
+            &self.host,
+            &CommandPurpose::XdgDataHome,
+            &shell::platform().data_home(),
+            self.initial_cwd.clone(),
+        );
+        self.phase = DiscoveryPhase::ResolvingFallback { dirs, failed };
+    }
+
+    fn handle_xdg_data_home(&mut self, exit_code: Option<i32>, stdout: &[u8]) -> bool {
+        let DiscoveryPhase::ResolvingFallback {
+            ref dirs,
+            ref failed,
+        } = self.phase
+        else {
+            return true;
+        };
+        let mut dirs = dirs.clone();
+        let failed = failed.clone();
+
+        let data_home = String::from_utf8_lossy(stdout).trim().to_string();
+        if exit_code != Some(0) || data_home.is_empty() {
+            error!("Could not resolve $XDG_DATA_HOME, keeping project dirs");
+            self.phase = DiscoveryPhase::Ready { dirs };
+            return true;
+        }
+        let data_home = PathBuf::from(data_home);
+
+        for root in &failed {
+            let fallback = crumbeez_lib::fallback_crumbeez_dir(&data_home, root);
+            let original = crumbeez_lib::crumbeez_dir(root);
+            for dir in dirs.iter_mut().filter(|d| **d == original) {
+                *dir = fallback.clone();
+            }
+
+            let required = crumbeez_lib::required_dirs_in(&fallback);
+            let refs: Vec<&Path> = required.iter().map(PathBuf::as_path).collect();
+            let cmd = shell::platform().create_dirs(&refs);
+            command_router::run(
+                &self.host,
+                &CommandPurpose::MkdirFallback,
+                &cmd,
+                self.initial_cwd.clone(),
+            );
+
+            info!(?root, ?fallback, "Using fallback storage");
+        }
+
+        self.fallback_roots.extend(failed.iter().cloned());
+        self.phase = DiscoveryPhase::CreatingDirs {
+            pending: failed.len(),
+            dirs,
+            failed: Vec::new(),
+        };
+        true
+    }
+
+    fn handle_mkdir_fallback(&mut self, exit_code: Option<i32>, stderr: &[u8]) -> bool {
+        if let DiscoveryPhase::CreatingDirs {
+            ref mut pending,
+            ref dirs,
+            ..
         } = self.phase
         {
             if exit_code != Some(0) {
                 let err = String::from_utf8_lossy(stderr);
-                error!(%err, "mkdir failed");
+                error!(%err, "mkdir failed for fallback storage");
             }
 
             *pending = pending.saturating_sub(1);
             if *pending == 0 {
                 info!(?dirs, "Root discovery complete");
-                // Move dirs out of CreatingDirs into Ready
                 let dirs = dirs.clone();
                 self.phase = DiscoveryPhase::Ready { dirs };
             }
@@ -176,20 +604,22 @@ impl RootDiscovery {
             .collect();
 
         for root in &roots {
-            let mkdir_args: Vec<String> = crumbeez_lib::required_dirs(root)
-                .into_iter()
-                .map(|d| d.to_string_lossy().into_owned())
-                .collect();
-            let mkdir_strs: Vec<&str> = mkdir_args.iter().map(|s| s.as_str()).collect();
-
-            let mut cmd: Vec<&str> = vec!["mkdir", "-p"];
-            cmd.extend_from_slice(&mkdir_strs);
+            // Create the dirs, then confirm we can actually write there and
+            // that it isn't a network mount; either failure triggers the
+            // $XDG_DATA_HOME fallback.
+            let required = crumbeez_lib::required_dirs(root);
+            let refs: Vec<&Path> = required.iter().map(PathBuf::as_path).collect();
+            let cmd = shell::platform().create_project_dirs(
+                &refs,
+                &crumbeez_lib::scratch_dir(root),
+                &crumbeez_lib::crumbeez_dir(root),
+            );
 
-            run_command_with_env_variables_and_cwd(
+            command_router::run(
+                &self.host,
+                &CommandPurpose::MkdirCrumbeez { root: root.clone() },
                 &cmd,
-                BTreeMap::new(),
                 self.initial_cwd.clone(),
-                purpose_context(CommandPurpose::MkdirCrumbeez),
             );
 
             debug!(
@@ -198,9 +628,114 @@ impl RootDiscovery {
             );
         }
 
+        self.roots = roots;
         self.phase = DiscoveryPhase::CreatingDirs {
             pending: count,
             dirs,
+            failed: Vec::new(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use zellij_tile::prelude::PluginIds;
+
+    use super::*;
+    use crate::host::{HostCall, Mock};
+    use crate::shell::Platform;
+
+    fn setup() -> (Mock, RootDiscovery) {
+        let host = Mock::new(SystemTime::UNIX_EPOCH, PluginIds::default());
+        shell::set_platform(Platform::Unix);
+        let discovery = RootDiscovery::new(host.handle());
+        (host, discovery)
+    }
+
+    /// Answer the one command discovery is waiting on, returning what it ran.
+    fn reply(
+        discovery: &mut RootDiscovery,
+        host: &Mock,
+        exit_code: Option<i32>,
+        stdout: &str,
+    ) -> (Vec<String>, PathBuf) {
+        let mut calls = host.take_calls();
+        assert_eq!(calls.len(), 1, "{:?}", calls);
+        let HostCall::RunCommand {
+            args, cwd, context, ..
+        } = calls.remove(0)
+        else {
+            panic!("not a command: {:?}", calls);
         };
+        assert!(command_router::accept(&discovery.host, &context));
+        assert!(discovery.handle_command_result(exit_code, stdout.as_bytes(), b"", &context));
+        (args, cwd)
+    }
+
+    #[test]
+    fn a_submodule_adds_its_parent_as_a_root() {
+        let (host, mut discovery) = setup();
+        discovery.start(PathBuf::from("/home/me/app/vendor/lib/src"));
+
+        let (args, _) = reply(&mut discovery, &host, Some(0), "/home/me/app/vendor/lib\n");
+        assert_eq!(args, ["git", "rev-parse", "--show-toplevel"]);
+        let (args, cwd) = reply(&mut discovery, &host, Some(0), "/home/me/app\n");
+        assert_eq!(
+            args,
+            ["git", "rev-parse", "--show-superproject-working-tree"]
+        );
+        assert_eq!(cwd, Path::new("/home/me/app/vendor/lib"));
+        reply(&mut discovery, &host, Some(1), "");
+
+        assert_eq!(
+            discovery.phase,
+            DiscoveryPhase::AwaitingOnboarding {
+                roots: vec![
+                    PathBuf::from("/home/me/app/vendor/lib"),
+                    PathBuf::from("/home/me/app"),
+                ],
+            }
+        );
+        assert_eq!(
+            discovery.parent_git_root,
+            Some(PathBuf::from("/home/me/app"))
+        );
+    }
+
+    #[test]
+    fn a_timed_out_git_falls_back_to_the_cwd() {
+        let (host, mut discovery) = setup();
+        discovery.start(PathBuf::from("/tmp/scratch"));
+
+        reply(&mut discovery, &host, None, "");
+
+        assert_eq!(
+            discovery.phase,
+            DiscoveryPhase::CheckingExisting {
+                roots: vec![PathBuf::from("/tmp/scratch")],
+            }
+        );
+        assert_eq!(discovery.timeouts.len(), 1);
+    }
+
+    #[test]
+    fn rediscovery_recreates_a_missing_data_dir() {
+        let (host, mut discovery) = setup();
+        discovery.rediscover(PathBuf::from("/home/me/app"));
+
+        reply(&mut discovery, &host, Some(0), "/home/me/app\n");
+        reply(&mut discovery, &host, Some(0), "");
+        reply(&mut discovery, &host, Some(1), "");
+        assert!(discovery.recreated);
+        reply(&mut discovery, &host, Some(0), "");
+
+        assert_eq!(
+            discovery.phase,
+            DiscoveryPhase::Ready {
+                dirs: vec![crumbeez_lib::crumbeez_dir(Path::new("/home/me/app"))],
+            }
+        );
     }
 }
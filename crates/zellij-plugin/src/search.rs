@@ -0,0 +1,103 @@
+//! Searching the activity shown in the plugin pane.
+//!
+//! Typing `/` with the plugin pane focused opens a search prompt.  As the
+//! pattern is typed, the keystroke list shows only the events containing it,
+//! with the matches highlighted, and the event log's matching entries are
+//! listed with their times and a count.  Enter keeps the search and `n`/`N`
+//! step to the next older or newer match; Esc closes it.  While a search is
+//! open, keys in the plugin pane go to it and none of them are logged.
+
+use std::ops::Range;
+
+use crumbeez_lib::find_matches;
+use zellij_tile::prelude::*;
+
+use crate::keystroke::plain_char;
+
+pub enum SearchOutcome {
+    /// The search is still open.
+    Searching,
+    Close,
+}
+
+/// The search pattern and which of the log's matches is selected.
+#[derive(Default)]
+pub struct Search {
+    pub query: String,
+    /// The pattern is being typed.
+    pub editing: bool,
+    /// The selected match, counting back from the newest.
+    pub selected: usize,
+}
+
+impl Search {
+    pub fn open() -> Self {
+        Self {
+            editing: true,
+            ..Self::default()
+        }
+    }
+
+    /// Handle a key, given how many entries in the log match.
+    pub fn handle_key(&mut self, key: &KeyWithModifier, matches: usize) -> SearchOutcome {
+        if key.bare_key == BareKey::Char('c') && key.key_modifiers.contains(&KeyModifier::Ctrl) {
+            return SearchOutcome::Close;
+        }
+        if self.editing {
+            if let Some(c) = plain_char(key) {
+                self.query.push(c);
+                self.selected = 0;
+                return SearchOutcome::Searching;
+            }
+            match key.bare_key {
+                BareKey::Enter if self.query.is_empty() => return SearchOutcome::Close,
+                BareKey::Enter => self.editing = false,
+                BareKey::Esc => return SearchOutcome::Close,
+                BareKey::Backspace => {
+                    self.query.pop();
+                    self.selected = 0;
+                }
+                _ => {}
+            }
+            return SearchOutcome::Searching;
+        }
+        match plain_char(key) {
+            Some('n') if matches > 0 => self.selected = (self.selected + 1) % matches,
+            Some('N') if matches > 0 => self.selected = (self.selected + matches - 1) % matches,
+            Some('/') => self.editing = true,
+            Some('q') => return SearchOutcome::Close,
+            _ if key.bare_key == BareKey::Esc => return SearchOutcome::Close,
+            _ => {}
+        }
+        SearchOutcome::Searching
+    }
+
+    /// The prompt line, with a count of the log's matches and the keys
+    /// that apply.
+    pub fn status(&self, matches: usize) -> String {
+        let count = match matches {
+            0 => "no matches".to_string(),
+            n => format!("{}/{} matches", self.selected.min(n - 1) + 1, n),
+        };
+        if self.editing {
+            format!("🔍 /{}▏ {} · Enter keep · Esc cancel", self.query, count)
+        } else {
+            format!("🔍 /{} {} · n/N older/newer · Esc close", self.query, count)
+        }
+    }
+}
+
+/// `text` with the places `query` occurs in reverse video.
+pub fn highlight(text: &str, query: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for Range { start, end } in find_matches(text, query) {
+        out.push_str(&text[last..start]);
+        out.push_str("\u{1b}[7m");
+        out.push_str(&text[start..end]);
+        out.push_str("\u{1b}[27m");
+        last = end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
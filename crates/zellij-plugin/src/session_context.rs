@@ -0,0 +1,101 @@
+//! Capturing what the session works with when the project is found.  See
+//! [`crumbeez_lib::CONTEXT_PROBES`] for what's probed.
+//!
+//! The probes run in the project root, so a `.envrc` there and tools that
+//! read the directory (e.g. `rustup` overrides) report what applies to the
+//! project rather than to wherever Zellij was started.  What they print is
+//! held until the event log has loaded, so the
+//! [`SessionContext`](crumbeez_lib::KeystrokeEvent::SessionContext) event
+//! isn't written into a log about to be replaced.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, info};
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+
+pub const CTX_PURPOSE: &str = "crumbeez_session_context_purpose";
+
+#[derive(Debug)]
+enum SessionContextCommand {
+    Probe,
+}
+
+impl ContextTag for SessionContextCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::Probe => "Probe".to_string(),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "Probe" => Some(Self::Probe),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SessionContextIO {
+    host: HostHandle,
+    /// The project root last probed, so rediscovering the same project
+    /// doesn't log its context again.
+    probed: Option<PathBuf>,
+    /// Values captured but not logged yet.
+    pub pending: Option<BTreeMap<String, String>>,
+    /// The last values captured, shown in the doctor report.
+    pub values: Option<BTreeMap<String, String>>,
+}
+
+impl SessionContextIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Run the probes in `root`, unless it's the project last probed.
+    pub fn capture(&mut self, root: &Path) {
+        if self.probed.as_deref() == Some(root) {
+            return;
+        }
+        self.probed = Some(root.to_path_buf());
+        debug!(?root, "Probing session context");
+        let cmd = shell::platform().session_context(&crumbeez_lib::CONTEXT_PROBES);
+        command_router::run(
+            &self.host,
+            &SessionContextCommand::Probe,
+            &cmd,
+            root.to_path_buf(),
+        );
+    }
+
+    /// Returns whether the result was the probes'.  Their values, if any,
+    /// are left in [`pending`](Self::pending).
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stdout: &[u8],
+        exit_code: Option<i32>,
+    ) -> bool {
+        let Some(SessionContextCommand::Probe) = purpose_of::<SessionContextCommand>(context)
+        else {
+            return false;
+        };
+        let values = crumbeez_lib::parse_session_context(&String::from_utf8_lossy(stdout));
+        info!(?exit_code, probes = values.len(), "Session context");
+        if !values.is_empty() {
+            self.pending = Some(values.clone());
+        }
+        self.values = Some(values);
+        true
+    }
+}
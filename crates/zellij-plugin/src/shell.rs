@@ -0,0 +1,727 @@
+//! Shell commands for the plugin's file IO, per host platform.
+//!
+//! Zellij plugins can't touch the host filesystem directly, so every read
+//! and write is a command run on the host.  On Unix those are `sh -c`
+//! one-liners; on Windows they are PowerShell.  Each function here returns
+//! the full argv for one operation, with paths converted to the host's
+//! separators and quoted for the host's shell.
+//!
+//! The platform is chosen once at load, from the `platform` plugin setting
+//! or, by default, guessed from the shape of the session's cwd.
+
+use std::path::Path;
+#[cfg(not(feature = "minimal"))]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(feature = "minimal"))]
+use std::sync::Mutex;
+
+#[cfg(not(feature = "minimal"))]
+use base64::engine::general_purpose::STANDARD;
+#[cfg(not(feature = "minimal"))]
+use base64::Engine as _;
+#[cfg(not(feature = "minimal"))]
+use crumbeez_lib::{ContextProbe, HistoryFormat, ProbeSource};
+use serde::{Deserialize, Serialize};
+
+/// `stat -f -c %T` filesystem types treated as network mounts, as a `case`
+/// pattern.  The event log is rewritten often, so these fall back to local
+/// storage.
+const NETWORK_FS_TYPES: &str = "nfs*|cifs|smb*|afs|fuse.sshfs";
+
+/// Exit code the create-dirs script uses to report a network mount.
+pub const EXIT_NETWORK_FS: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Platform {
+    #[default]
+    Unix,
+    Windows,
+}
+
+static WINDOWS: AtomicBool = AtomicBool::new(false);
+
+/// The host platform set at load.
+pub fn platform() -> Platform {
+    if WINDOWS.load(Ordering::Relaxed) {
+        Platform::Windows
+    } else {
+        Platform::Unix
+    }
+}
+
+pub fn set_platform(platform: Platform) {
+    WINDOWS.store(platform == Platform::Windows, Ordering::Relaxed);
+}
+
+impl Platform {
+    /// The `platform` setting if there is one, otherwise a guess from `cwd`.
+    pub fn detect(configured: Option<Self>, cwd: &Path) -> Self {
+        configured.unwrap_or_else(|| Self::guess(cwd))
+    }
+
+    /// Windows cwds look like `C:\...` or `\\server\share`.
+    fn guess(cwd: &Path) -> Self {
+        let cwd = cwd.to_string_lossy();
+        let bytes = cwd.as_bytes();
+        let drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+        if drive || cwd.starts_with("\\\\") {
+            Self::Windows
+        } else {
+            Self::Unix
+        }
+    }
+
+    /// `path` with the host's separators, quoted for the host's shell.
+    pub fn quote_path(self, path: &Path) -> String {
+        let path = path.to_string_lossy();
+        match self {
+            Self::Unix => self.quote(&path),
+            Self::Windows => self.quote(&path.replace('/', "\\")),
+        }
+    }
+
+    /// Single-quote `s` for the host's shell.
+    pub fn quote(self, s: &str) -> String {
+        match self {
+            Self::Unix => format!("'{}'", s.replace('\'', "'\\''")),
+            Self::Windows => format!("'{}'", s.replace('\'', "''")),
+        }
+    }
+
+    fn script(self, script: String) -> Vec<String> {
+        match self {
+            Self::Unix => vec!["sh".into(), "-c".into(), script],
+            Self::Windows => vec![
+                "powershell".into(),
+                "-NoProfile".into(),
+                "-NonInteractive".into(),
+                "-Command".into(),
+                script,
+            ],
+        }
+    }
+
+    fn quote_paths(self, paths: &[&Path]) -> Vec<String> {
+        paths.iter().map(|p| self.quote_path(p)).collect()
+    }
+
+    // ── Directories ──────────────────────────────────────────────
+
+    /// Exit 0 if `path` is a directory.
+    pub fn dir_exists(self, path: &Path) -> Vec<String> {
+        let path = self.quote_path(path);
+        self.script(match self {
+            Self::Unix => format!("test -d {}", path),
+            Self::Windows => format!(
+                "if (Test-Path -LiteralPath {} -PathType Container) {{ exit 0 }} else {{ exit 1 }}",
+                path
+            ),
+        })
+    }
+
+    pub fn create_dirs(self, paths: &[&Path]) -> Vec<String> {
+        let paths = self.quote_paths(paths);
+        self.script(match self {
+            Self::Unix => format!("mkdir -p {}", paths.join(" ")),
+            Self::Windows => format!(
+                "New-Item -ItemType Directory -Force -Path {} | Out-Null",
+                paths.join(",")
+            ),
+        })
+    }
+
+    /// Create `paths`, then confirm `writable` can actually be written to and
+    /// that `dir` isn't on a network mount, exiting with [`EXIT_NETWORK_FS`]
+    /// if it is.
+    pub fn create_project_dirs(self, paths: &[&Path], writable: &Path, dir: &Path) -> Vec<String> {
+        let paths = self.quote_paths(paths);
+        let writable = self.quote_path(writable);
+        let dir = self.quote_path(dir);
+        self.script(match self {
+            Self::Unix => format!(
+                "mkdir -p {} && [ -w {} ] && \
+                 case \"$(stat -f -c %T {} 2>/dev/null)\" in {}) exit {};; esac",
+                paths.join(" "),
+                writable,
+                dir,
+                NETWORK_FS_TYPES,
+                EXIT_NETWORK_FS,
+            ),
+            Self::Windows => format!(
+                "$ErrorActionPreference = 'Stop'; \
+                 New-Item -ItemType Directory -Force -Path {} | Out-Null; \
+                 $probe = Join-Path {} '.crumbeez-probe'; \
+                 [IO.File]::WriteAllText($probe, ''); Remove-Item -LiteralPath $probe; \
+                 $root = [IO.Path]::GetPathRoot((Resolve-Path -LiteralPath {}).ProviderPath); \
+                 if ($root.StartsWith('\\\\') -or ([IO.DriveInfo]$root).DriveType -eq 'Network') {{ exit {} }}",
+                paths.join(","),
+                writable,
+                dir,
+                EXIT_NETWORK_FS,
+            ),
+        })
+    }
+
+    /// Print the per-user data directory: `$XDG_DATA_HOME` (or its default)
+    /// on Unix, `%LOCALAPPDATA%` on Windows.
+    pub fn data_home(self) -> Vec<String> {
+        self.script(match self {
+            Self::Unix => "printf '%s' \"${XDG_DATA_HOME:-$HOME/.local/share}\"".to_string(),
+            Self::Windows => "[Console]::Out.Write($env:LOCALAPPDATA)".to_string(),
+        })
+    }
+
+    /// Print what the host runs on, for [`crumbeez_lib::on_battery`]: the
+    /// `online` flag of each Linux power supply and the source `pmset`
+    /// reports on macOS, or `Battery Power` or `AC Power` on Windows.
+    #[cfg(not(feature = "minimal"))]
+    pub fn power_source(self) -> Vec<String> {
+        self.script(match self {
+            Self::Unix => "cat /sys/class/power_supply/*/online 2>/dev/null; \
+                 pmset -g batt 2>/dev/null | head -n 1; true"
+                .to_string(),
+            Self::Windows => "if ((Get-CimInstance Win32_Battery).BatteryStatus -eq 1) \
+                 { 'Battery Power' } else { 'AC Power' }"
+                .to_string(),
+        })
+    }
+
+    /// Print a `<key>\x1f<value>` line per probe, for
+    /// [`crumbeez_lib::parse_session_context`].  On Unix the probes run
+    /// under `direnv exec` when direnv is installed and allows the
+    /// directory, so an `.envrc` is taken into account.
+    #[cfg(not(feature = "minimal"))]
+    pub fn session_context(self, probes: &[ContextProbe]) -> Vec<String> {
+        let lines: Vec<String> = probes
+            .iter()
+            .map(|probe| match (self, probe.source) {
+                (Self::Unix, ProbeSource::Env(name)) => {
+                    format!("printf '{}\\037%s\\n' \"${}\"", probe.key, name)
+                }
+                (Self::Unix, ProbeSource::Command(command)) => format!(
+                    "printf '{}\\037%s\\n' \"$({} 2>/dev/null | head -n 1)\"",
+                    probe.key, command
+                ),
+                (Self::Windows, ProbeSource::Env(name)) => {
+                    format!("'{}' + [char]31 + $env:{}", probe.key, name)
+                }
+                (Self::Windows, ProbeSource::Command(command)) => format!(
+                    "'{}' + [char]31 + (({} 2>$null) | Select-Object -First 1)",
+                    probe.key, command
+                ),
+            })
+            .collect();
+        self.script(match self {
+            Self::Unix => format!(
+                "probe={}; if command -v direnv >/dev/null 2>&1 && direnv exec . true 2>/dev/null; \
+                 then direnv exec . sh -c \"$probe\" 2>/dev/null; else sh -c \"$probe\"; fi",
+                self.quote(&lines.join("; "))
+            ),
+            Self::Windows => format!(
+                "$ErrorActionPreference = 'SilentlyContinue'; {}",
+                lines.join("; ")
+            ),
+        })
+    }
+
+    // ── Text files ───────────────────────────────────────────────
+
+    /// Print a shell's history for [`crumbeez_lib::parse_history`]: the
+    /// file at `path`, or the shell's usual one.  atuin's database is read
+    /// with `sqlite3`, which has to be installed.
+    #[cfg(not(feature = "minimal"))]
+    pub fn read_shell_history(self, format: HistoryFormat, path: Option<&Path>) -> Vec<String> {
+        let path = match (path, self, format) {
+            (Some(path), ..) => self.quote_path(path),
+            (None, Self::Unix, HistoryFormat::Bash) => "\"$HOME/.bash_history\"".to_string(),
+            (None, Self::Unix, HistoryFormat::Zsh) => {
+                "\"${HISTFILE:-$HOME/.zsh_history}\"".to_string()
+            }
+            (None, Self::Unix, HistoryFormat::Fish) => {
+                "\"${XDG_DATA_HOME:-$HOME/.local/share}/fish/fish_history\"".to_string()
+            }
+            (None, _, HistoryFormat::Atuin) => self.atuin_db().to_string(),
+            (None, Self::Windows, HistoryFormat::Bash) => "\"$HOME\\.bash_history\"".to_string(),
+            (None, Self::Windows, HistoryFormat::Zsh) => "\"$HOME\\.zsh_history\"".to_string(),
+            (None, Self::Windows, HistoryFormat::Fish) => {
+                "\"$HOME\\.local\\share\\fish\\fish_history\"".to_string()
+            }
+        };
+        self.script(match (self, format) {
+            (_, HistoryFormat::Atuin) => format!(
+                "sqlite3 -readonly {} {}",
+                path,
+                self.quote(crumbeez_lib::ATUIN_QUERY)
+            ),
+            (Self::Unix, _) => format!("cat {}", path),
+            (Self::Windows, _) => {
+                format!("[Console]::Out.Write([IO.File]::ReadAllText({}))", path)
+            }
+        })
+    }
+
+    /// Run `query` on atuin's database, failing if it or `sqlite3` isn't
+    /// there.
+    #[cfg(not(feature = "minimal"))]
+    pub fn atuin_lookup(self, query: &str) -> Vec<String> {
+        self.script(format!(
+            "sqlite3 -readonly {} {}",
+            self.atuin_db(),
+            self.quote(query)
+        ))
+    }
+
+    /// Where atuin keeps its history, quoted for the script.
+    #[cfg(not(feature = "minimal"))]
+    fn atuin_db(self) -> &'static str {
+        match self {
+            Self::Unix => "\"${XDG_DATA_HOME:-$HOME/.local/share}/atuin/history.db\"",
+            Self::Windows => "\"$HOME\\.local\\share\\atuin\\history.db\"",
+        }
+    }
+
+    /// Print the first of `paths` that exists; print nothing if none do.
+    pub fn read_first_existing(self, paths: &[&Path]) -> Vec<String> {
+        let paths = self.quote_paths(paths);
+        self.script(match self {
+            Self::Unix => format!(
+                "for f in {}; do if [ -f \"$f\" ]; then cat \"$f\"; break; fi; done",
+                paths.join(" ")
+            ),
+            Self::Windows => format!(
+                "foreach ($f in @({})) {{ if (Test-Path -LiteralPath $f -PathType Leaf) \
+                 {{ [Console]::Out.Write([IO.File]::ReadAllText($f)); break }} }}",
+                paths.join(",")
+            ),
+        })
+    }
+
+    /// Print every file in `dir` whose name matches the glob `pattern`, in
+    /// name order; print nothing if none do.
+    #[cfg(not(feature = "minimal"))]
+    pub fn read_matching(self, dir: &Path, pattern: &str) -> Vec<String> {
+        let dir = self.quote_path(dir);
+        self.script(match self {
+            Self::Unix => format!(
+                "for f in {}/{}; do if [ -f \"$f\" ]; then cat \"$f\"; fi; done",
+                dir, pattern
+            ),
+            Self::Windows => format!(
+                "Get-ChildItem -LiteralPath {} -Filter {} -File -ErrorAction SilentlyContinue \
+                 | Sort-Object Name | ForEach-Object {{ [Console]::Out.Write([IO.File]::ReadAllText($_.FullName)) }}",
+                dir,
+                self.quote(pattern)
+            ),
+        })
+    }
+
+    /// Replace `path` with `content`.
+    #[cfg(not(feature = "minimal"))]
+    pub fn write_text(self, path: &Path, content: &str) -> Vec<String> {
+        let path = self.quote_path(path);
+        let content = self.quote(content);
+        self.script(match self {
+            Self::Unix => format!("printf '%s' {} > {}", content, path),
+            Self::Windows => format!("[IO.File]::WriteAllText({}, {})", path, content),
+        })
+    }
+
+    /// Open a draft pull request from `branch` titled `title`, its
+    /// description read from `body_file`, with the GitHub CLI.  It prints
+    /// the pull request's URL.
+    #[cfg(not(feature = "minimal"))]
+    pub fn gh_pr_create(self, branch: &str, title: &str, body_file: &Path) -> Vec<String> {
+        self.script(format!(
+            "gh pr create --draft --head {} --title {} --body-file {}",
+            self.quote(branch),
+            self.quote(title),
+            self.quote_path(body_file)
+        ))
+    }
+
+    /// Write `content` to `path` unless it already exists.
+    pub fn write_text_if_missing(self, path: &Path, content: &str) -> Vec<String> {
+        let path = self.quote_path(path);
+        let content = self.quote(content);
+        self.script(match self {
+            Self::Unix => format!("[ -f {} ] || printf '%s' {} > {}", path, content, path),
+            Self::Windows => format!(
+                "if (-not (Test-Path -LiteralPath {})) {{ [IO.File]::WriteAllText({}, {}) }}",
+                path, path, content
+            ),
+        })
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn append_text(self, path: &Path, content: &str) -> Vec<String> {
+        let path = self.quote_path(path);
+        let content = self.quote(content);
+        self.script(match self {
+            Self::Unix => format!("printf '%s' {} >> {}", content, path),
+            Self::Windows => format!("[IO.File]::AppendAllText({}, {})", path, content),
+        })
+    }
+
+    /// Exit 0 if some line of `path` matches the extended regex `pattern` in
+    /// full.
+    pub fn contains_line(self, path: &Path, pattern: &str) -> Vec<String> {
+        match self {
+            Self::Unix => vec![
+                "grep".into(),
+                "-qsxE".into(),
+                pattern.into(),
+                path.to_string_lossy().into_owned(),
+            ],
+            Self::Windows => self.script(format!(
+                "if (Select-String -LiteralPath {} -Pattern {} -Quiet -ErrorAction SilentlyContinue) \
+                 {{ exit 0 }} else {{ exit 1 }}",
+                self.quote_path(path),
+                self.quote(&format!("^(?:{})$", pattern)),
+            )),
+        }
+    }
+
+    /// Append `line` to `path`, first ending the last line if the file lacks
+    /// a trailing newline.
+    pub fn append_line(self, path: &Path, line: &str) -> Vec<String> {
+        let path = self.quote_path(path);
+        let line = self.quote(line);
+        self.script(match self {
+            Self::Unix => format!(
+                "if [ -s {p} ] && [ -n \"$(tail -c1 {p})\" ]; then echo >> {p}; fi; echo {l} >> {p}",
+                p = path,
+                l = line
+            ),
+            Self::Windows => format!(
+                "$p = {p}; $sep = ''; \
+                 if (Test-Path -LiteralPath $p) {{ $t = [IO.File]::ReadAllText($p); \
+                 if ($t.Length -gt 0 -and -not $t.EndsWith(\"`n\")) {{ $sep = \"`n\" }} }}; \
+                 [IO.File]::AppendAllText($p, $sep + {l} + \"`n\")",
+                p = path,
+                l = line
+            ),
+        })
+    }
+
+    /// Move each of `sources` that exists onto the end of `target`, under a
+    /// `---` separator, deleting it once copied.
+    #[cfg(all(feature = "analytics", not(feature = "minimal")))]
+    pub fn append_files_and_remove(self, target: &Path, sources: &[&Path]) -> Vec<String> {
+        let target = self.quote_path(target);
+        let sources = self.quote_paths(sources);
+        self.script(match self {
+            Self::Unix => format!(
+                "for f in {}; do if [ -f \"$f\" ]; then \
+                 {{ printf '\\n---\\n\\n'; cat \"$f\"; }} >> {} && rm -f \"$f\" || exit 1; fi; done",
+                sources.join(" "),
+                target
+            ),
+            Self::Windows => format!(
+                "$ErrorActionPreference = 'Stop'; foreach ($f in @({})) {{ \
+                 if (Test-Path -LiteralPath $f) {{ \
+                 [IO.File]::AppendAllText({}, \"`n---`n`n\" + [IO.File]::ReadAllText($f)); \
+                 Remove-Item -LiteralPath $f }} }}",
+                sources.join(","),
+                target
+            ),
+        })
+    }
+
+    // ── Binary files ─────────────────────────────────────────────
+
+    /// Print up to `len` bytes of `path` from `offset` on, in `read`'s
+    /// encoding, or nothing if it doesn't exist or ends before `offset`.
+    /// Windows always prints base64.
+    #[cfg(not(feature = "minimal"))]
+    pub fn read_binary_range(
+        self,
+        path: &Path,
+        offset: u64,
+        len: usize,
+        read: ReadStrategy,
+    ) -> Vec<String> {
+        let path = self.quote_path(path);
+        self.script(match self {
+            Self::Unix => format!(
+                "if [ -f {p} ]; then tail -c +{} {p} | head -c {} | {}; fi",
+                offset + 1,
+                len,
+                read.command(),
+                p = path
+            ),
+            Self::Windows => format!(
+                "if (Test-Path -LiteralPath {p}) {{ \
+                 $s = [IO.File]::OpenRead({p}); [void]$s.Seek({}, 'Begin'); \
+                 $b = New-Object byte[] {l}; $n = 0; \
+                 while ($n -lt {l}) {{ $r = $s.Read($b, $n, {l} - $n); if ($r -le 0) {{ break }}; $n += $r }}; \
+                 $s.Close(); [Console]::Out.Write([Convert]::ToBase64String($b, 0, $n)) }}",
+                offset,
+                p = path,
+                l = len
+            ),
+        })
+    }
+
+    /// Write `data` to `path` with `write`, replacing it or with `append`
+    /// adding to the end.
+    #[cfg(not(feature = "minimal"))]
+    pub fn write_binary(
+        self,
+        path: &Path,
+        data: &[u8],
+        append: bool,
+        write: WriteStrategy,
+    ) -> Vec<String> {
+        let path = self.quote_path(path);
+        self.script(match self {
+            Self::Unix => write.script(data, if append { ">>" } else { ">" }, &path),
+            Self::Windows => format!(
+                "$b = [Convert]::FromBase64String('{}'); \
+                 $s = [IO.File]::Open({}, [IO.FileMode]::{}); \
+                 $s.Write($b, 0, $b.Length); $s.Close()",
+                base64_encode(data),
+                path,
+                if append { "Append" } else { "Create" }
+            ),
+        })
+    }
+
+    /// Most bytes one [`write_binary`](Self::write_binary) command should
+    /// carry.  The data is spelled out in the script, which Linux caps at
+    /// 128 KiB as a single argument and Windows at 32K characters for the
+    /// whole command line.
+    #[cfg(not(feature = "minimal"))]
+    fn write_chunk_bytes(self, write: WriteStrategy) -> usize {
+        match (self, write) {
+            (Self::Windows, _) => 16 * 1024,
+            (Self::Unix, WriteStrategy::Base64) => 64 * 1024,
+            (Self::Unix, WriteStrategy::PrintfOctal) => 24 * 1024,
+        }
+    }
+
+    /// Commands writing `data` like [`write_binary`](Self::write_binary),
+    /// split so none is too long to run.  Run them in order, each after the
+    /// last succeeded.  Data too big for one command is built up in a
+    /// `.part` file beside `path` and only moved or appended onto it by the
+    /// last command, so a write failing partway leaves `path` as it was.
+    #[cfg(not(feature = "minimal"))]
+    pub fn write_binary_chunks(
+        self,
+        path: &Path,
+        data: &[u8],
+        append: bool,
+        write: WriteStrategy,
+    ) -> Vec<Vec<String>> {
+        let chunk_bytes = self.write_chunk_bytes(write);
+        if data.len() <= chunk_bytes {
+            return vec![self.write_binary(path, data, append, write)];
+        }
+        let mut part = path.as_os_str().to_owned();
+        part.push(".part");
+        let part = PathBuf::from(part);
+        let mut commands: Vec<Vec<String>> = data
+            .chunks(chunk_bytes)
+            .enumerate()
+            .map(|(i, chunk)| self.write_binary(&part, chunk, i > 0, write))
+            .collect();
+        let (part, path) = (self.quote_path(&part), self.quote_path(path));
+        commands.push(self.script(match (self, append) {
+            (Self::Unix, true) => format!("cat {} >> {} && rm -f {}", part, path, part),
+            (Self::Unix, false) => format!("mv -f {} {}", part, path),
+            (Self::Windows, true) => format!(
+                "$ErrorActionPreference = 'Stop'; $b = [IO.File]::ReadAllBytes({}); \
+                 $s = [IO.File]::Open({}, [IO.FileMode]::Append); \
+                 $s.Write($b, 0, $b.Length); $s.Close(); Remove-Item -LiteralPath {}",
+                part, path, part
+            ),
+            (Self::Windows, false) => format!(
+                "$ErrorActionPreference = 'Stop'; Move-Item -LiteralPath {} -Destination {} -Force",
+                part, path
+            ),
+        }));
+        commands
+    }
+
+    /// Round-trip `data` through a temp file with `io`, printing it back in
+    /// `io.read`'s encoding.  Unix only.
+    #[cfg(not(feature = "minimal"))]
+    pub fn probe_binary(self, data: &[u8], io: IoStrategy) -> Vec<String> {
+        self.script(format!(
+            "f=$(mktemp) || exit 1\n{}\ns=$?\n[ $s -eq 0 ] && {{ {}; s=$?; }}\nrm -f \"$f\"\nexit $s",
+            io.write.script(data, ">", "\"$f\""),
+            io.read.script("\"$f\""),
+        ))
+    }
+}
+
+// ── Binary IO strategies ─────────────────────────────────────────
+
+/// How binary files are written on Unix hosts.  Minimal environments like
+/// BusyBox lack or mangle some tools, so the plugin probes for one that
+/// works (see `io_probe`).
+#[cfg(not(feature = "minimal"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WriteStrategy {
+    /// Pipe a base64 heredoc through `base64 -d`.
+    #[default]
+    Base64,
+    /// Spell every byte as an octal escape for the shell's `printf`; needs no
+    /// external tools but makes commands about four times the data size.
+    PrintfOctal,
+}
+
+/// How binary files are read back on Unix hosts.
+#[cfg(not(feature = "minimal"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReadStrategy {
+    /// `base64`, decoded by [`base64_decode`].
+    #[default]
+    Base64,
+    /// `od` hex dump, for hosts without `base64`.
+    Od,
+}
+
+#[cfg(not(feature = "minimal"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct IoStrategy {
+    pub write: WriteStrategy,
+    pub read: ReadStrategy,
+}
+
+/// Bytes per line in generated scripts, to stay clear of line length limits
+/// in minimal shells.
+#[cfg(not(feature = "minimal"))]
+const SCRIPT_LINE_BYTES: usize = 57;
+
+#[cfg(not(feature = "minimal"))]
+impl WriteStrategy {
+    #[cfg(not(feature = "minimal"))]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Base64 => "base64",
+            Self::PrintfOctal => "printf",
+        }
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Base64, Self::PrintfOctal]
+            .into_iter()
+            .find(|s| s.name() == name)
+    }
+
+    /// Shell commands writing `data` through `redirect` (`>` or `>>`) to the
+    /// already-quoted `target`.
+    #[cfg(not(feature = "minimal"))]
+    fn script(self, data: &[u8], redirect: &str, target: &str) -> String {
+        match self {
+            Self::Base64 => {
+                let mut script = format!("base64 -d {} {} <<'CRUMBEEZ_EOF'\n", redirect, target);
+                for chunk in data.chunks(SCRIPT_LINE_BYTES) {
+                    script.push_str(&base64_encode(chunk));
+                    script.push('\n');
+                }
+                script.push_str("CRUMBEEZ_EOF");
+                script
+            }
+            Self::PrintfOctal => {
+                let mut script = "{ :\n".to_string();
+                for chunk in data.chunks(SCRIPT_LINE_BYTES) {
+                    script.push_str("printf '");
+                    for byte in chunk {
+                        script.push_str(&format!("\\{:03o}", byte));
+                    }
+                    script.push_str("'\n");
+                }
+                script.push_str(&format!("}} {} {}", redirect, target));
+                script
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "minimal"))]
+impl ReadStrategy {
+    #[cfg(not(feature = "minimal"))]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Base64 => "base64",
+            Self::Od => "od",
+        }
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Base64, Self::Od]
+            .into_iter()
+            .find(|s| s.name() == name)
+    }
+
+    /// Shell command printing its standard input.
+    #[cfg(not(feature = "minimal"))]
+    fn command(self) -> &'static str {
+        match self {
+            Self::Base64 => "base64",
+            Self::Od => "od -An -v -tx1",
+        }
+    }
+
+    /// Shell command printing the already-quoted `source`.
+    #[cfg(not(feature = "minimal"))]
+    fn script(self, source: &str) -> String {
+        format!("{} {}", self.command(), source)
+    }
+
+    /// Decode what [`Platform::read_binary_range`] printed.
+    #[cfg(not(feature = "minimal"))]
+    pub fn decode(self, stdout: &[u8]) -> Option<Vec<u8>> {
+        let text = String::from_utf8_lossy(stdout);
+        match self {
+            Self::Base64 => base64_decode(&text),
+            Self::Od => text
+                .split_whitespace()
+                .map(|byte| u8::from_str_radix(byte, 16).ok())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(not(feature = "minimal"))]
+impl std::fmt::Display for IoStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "write {}, read {}", self.write.name(), self.read.name())
+    }
+}
+
+#[cfg(not(feature = "minimal"))]
+static IO_STRATEGY: Mutex<IoStrategy> = Mutex::new(IoStrategy {
+    write: WriteStrategy::Base64,
+    read: ReadStrategy::Base64,
+});
+
+/// The binary IO strategy chosen by the probe.
+#[cfg(not(feature = "minimal"))]
+pub fn io_strategy() -> IoStrategy {
+    *IO_STRATEGY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(not(feature = "minimal"))]
+pub fn set_io_strategy(io: IoStrategy) {
+    *IO_STRATEGY.lock().unwrap_or_else(|e| e.into_inner()) = io;
+}
+
+// ── Base64 ───────────────────────────────────────────────────────
+
+#[cfg(not(feature = "minimal"))]
+pub(crate) fn base64_encode(data: &[u8]) -> String {
+    STANDARD.encode(data)
+}
+
+/// Decode standard base64, ignoring the line breaks tools like `base64`
+/// wrap their output with.
+#[cfg(not(feature = "minimal"))]
+pub(crate) fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let s: String = s.split_whitespace().collect();
+    STANDARD.decode(s).ok()
+}
@@ -0,0 +1,108 @@
+//! Pinning the focused pane's screen into the breadcrumb trail.
+//!
+//! The screen is saved with Zellij's `dump-screen` action to
+//! `scratchpad/snapshot-<timestamp>.txt`, and a
+//! [`SnapshotTaken`](crumbeez_lib::KeystrokeEvent::SnapshotTaken) event
+//! pointing at the file is logged once it's written.  Bind it to a key:
+//!
+//! ```kdl
+//! keybinds {
+//!     shared {
+//!         bind "Alt p" { MessagePlugin "file:crumbeez.wasm" { name "crumbeez::snapshot"; }; }
+//!     }
+//! }
+//! ```
+//!
+//! Only what's visible is saved, not the scrollback.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, error, info};
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+
+/// Pipe message name that snapshots the focused pane.
+pub const SNAPSHOT_PIPE: &str = "crumbeez::snapshot";
+
+pub const CTX_PURPOSE: &str = "crumbeez_snapshot_purpose";
+
+#[derive(Debug)]
+enum SnapshotCommand {
+    /// `file` is relative to the `.crumbeez` directory, as logged.
+    DumpScreen { file: String },
+}
+
+impl ContextTag for SnapshotCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::DumpScreen { file } => format!("DumpScreen:{}", file),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match split_tag(tag) {
+            ("DumpScreen", Some(file)) => Some(Self::DumpScreen { file: file.into() }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SnapshotIO {
+    host: HostHandle,
+    /// How the last snapshot went, shown in the plugin pane.
+    pub status: Option<String>,
+}
+
+impl SnapshotIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Dump the focused pane of `session` (the current one if `None`) to
+    /// `file` under `crumbeez_dir`.
+    pub fn take(&mut self, session: Option<&str>, crumbeez_dir: &Path, file: String, cwd: PathBuf) {
+        let path = crumbeez_dir.join(&file);
+        info!(?path, "Taking screen snapshot");
+        let mut cmd = vec!["zellij".to_string()];
+        if let Some(session) = session {
+            cmd.extend(["--session".to_string(), session.to_string()]);
+        }
+        cmd.extend([
+            "action".to_string(),
+            "dump-screen".to_string(),
+            path.display().to_string(),
+        ]);
+        self.status = Some(format!("saving {}…", file));
+        command_router::run(&self.host, &SnapshotCommand::DumpScreen { file }, &cmd, cwd);
+    }
+
+    /// The snapshot's file, relative to the `.crumbeez` directory, if the
+    /// result is a snapshot that was written.
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stderr: &[u8],
+        exit_code: Option<i32>,
+    ) -> Option<String> {
+        let SnapshotCommand::DumpScreen { file } = purpose_of::<SnapshotCommand>(context)?;
+        debug!(?exit_code, "DumpScreen result");
+        if exit_code == Some(0) {
+            self.status = Some(format!("screen pinned to {}", file));
+            Some(file)
+        } else {
+            let err = String::from_utf8_lossy(stderr);
+            error!(%err, %file, "Failed to snapshot the screen");
+            self.status = Some(format!("failed to save {}", file));
+            None
+        }
+    }
+}
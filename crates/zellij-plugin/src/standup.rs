@@ -0,0 +1,103 @@
+//! Writing "what I did today" standup reports.
+//!
+//! The report itself is built by [`crumbeez_lib::Standup`]; this module only
+//! writes it to `summaries/standup-YYYY-MM-DD.md`.  Generate one from the
+//! command line:
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::standup
+//! ```
+//!
+//! or from a keybind:
+//!
+//! ```kdl
+//! keybinds {
+//!     shared {
+//!         bind "Alt s" { MessagePlugin "file:crumbeez.wasm" { name "crumbeez::standup"; }; }
+//!     }
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use tracing::{debug, error, info};
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, split_tag, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+
+/// Pipe message name that writes a standup report.
+pub const STANDUP_PIPE: &str = "crumbeez::standup";
+
+pub const CTX_PURPOSE: &str = "crumbeez_standup_purpose";
+
+#[derive(Debug)]
+enum StandupCommand {
+    WriteReport { path: PathBuf },
+}
+
+impl ContextTag for StandupCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::WriteReport { path } => format!("WriteReport:{}", path.display()),
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match split_tag(tag) {
+            ("WriteReport", Some(path)) => Some(Self::WriteReport { path: path.into() }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct StandupIO {
+    host: HostHandle,
+    /// How the last report went, shown in the plugin pane.
+    pub status: Option<String>,
+}
+
+impl StandupIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Write `report` to `path`, replacing any earlier report from the same
+    /// day.
+    #[cfg_attr(not(feature = "analytics"), allow(dead_code))]
+    pub fn write(&mut self, path: PathBuf, report: &str, cwd: PathBuf) {
+        info!(?path, "Writing standup report");
+        let cmd = shell::platform().write_text(&path, report);
+        self.status = Some(format!("writing {}…", path.display()));
+        command_router::run(&self.host, &StandupCommand::WriteReport { path }, &cmd, cwd);
+    }
+
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stderr: &[u8],
+        exit_code: Option<i32>,
+    ) -> bool {
+        let Some(StandupCommand::WriteReport { path }) = purpose_of::<StandupCommand>(context)
+        else {
+            return false;
+        };
+        debug!(?exit_code, "WriteReport result");
+        if exit_code == Some(0) {
+            self.status = Some(format!("standup written to {}", path.display()));
+        } else {
+            let err = String::from_utf8_lossy(stderr);
+            error!(%err, ?path, "Failed to write standup report");
+            self.status = Some(format!("failed to write {}", path.display()));
+        }
+        true
+    }
+}
@@ -0,0 +1,196 @@
+//! Summaries, read back from the daily files.
+//!
+//! Summaries are appended to `summaries/YYYY-MM-DD.md` as they're made (see
+//! [`crate::rollup`]), so that file, not memory, is what the plugin pane
+//! shows: it's read once the data dir is known, so a reload picks up where
+//! it left off, and again in viewer mode whenever it changes.  The
+//! [browser](crate::browser) reads every day's file at once, as does a
+//! search from the command line:
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::search -- "oauth bug"
+//! ```
+//!
+//! which prints the best matches, ranked as
+//! [`SummaryStore::search`] ranks them.  The work on an issue is listed
+//! from the issue index instead, which outlasts the daily files:
+//!
+//! ```sh
+//! zellij pipe --name crumbeez::issue -- PROJ-142
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use crumbeez_lib::{IssueLink, StoredSummary, SummaryStore};
+use tracing::debug;
+
+use crate::command_router;
+use crate::context_tag::{purpose_of, ContextTag};
+use crate::host::HostHandle;
+use crate::shell;
+
+pub const CTX_PURPOSE: &str = "crumbeez_summaries_purpose";
+
+/// Pipe message name for searching every day's summaries.
+pub const SEARCH_PIPE: &str = "crumbeez::search";
+
+/// Pipe message name for listing the work on an issue.
+pub const ISSUE_PIPE: &str = "crumbeez::issue";
+
+/// Most summaries a search prints.
+const MAX_SEARCH_RESULTS: usize = 20;
+
+#[derive(Debug)]
+enum SummariesCommand {
+    ReadDaily,
+    ReadHistory,
+    ReadIssues,
+}
+
+impl ContextTag for SummariesCommand {
+    const KEY: &'static str = CTX_PURPOSE;
+
+    fn to_tag(&self) -> String {
+        match self {
+            Self::ReadDaily => "ReadDaily",
+            Self::ReadHistory => "ReadHistory",
+            Self::ReadIssues => "ReadIssues",
+        }
+        .to_string()
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "ReadDaily" => Some(Self::ReadDaily),
+            "ReadHistory" => Some(Self::ReadHistory),
+            "ReadIssues" => Some(Self::ReadIssues),
+            _ => None,
+        }
+    }
+}
+
+/// Summaries read by [`SummariesIO`].
+#[derive(Debug)]
+pub enum Loaded {
+    /// Today's, from [`load`](SummariesIO::load).
+    Today(SummaryStore),
+    /// Every day's, from [`load_history`](SummariesIO::load_history).
+    History(SummaryStore),
+    /// The issue index, from [`load_issues`](SummariesIO::load_issues).
+    Issues(Vec<IssueLink>),
+}
+
+#[derive(Default)]
+pub struct SummariesIO {
+    host: HostHandle,
+}
+
+impl SummariesIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self { host }
+    }
+
+    /// Read the summary file for `day` in the data dir `dir`.
+    pub fn load(&mut self, dir: &Path, day: &str, cwd: PathBuf) {
+        let path = crumbeez_lib::daily_summary_path(dir, day);
+        debug!(?path, "Reading summaries");
+        let cmd = shell::platform().read_first_existing(&[&path]);
+        command_router::run(&self.host, &SummariesCommand::ReadDaily, &cmd, cwd);
+    }
+
+    /// Read every daily summary file in the data dir `dir`, oldest first.
+    pub fn load_history(&mut self, dir: &Path, cwd: PathBuf) {
+        let summaries_dir = crumbeez_lib::summaries_dir_from_crumbeez_dir(dir);
+        debug!(?summaries_dir, "Reading summary history");
+        let cmd = shell::platform().read_matching(&summaries_dir, "????-??-??.md");
+        command_router::run(&self.host, &SummariesCommand::ReadHistory, &cmd, cwd);
+    }
+
+    /// Read the issue index in the data dir `dir`.
+    pub fn load_issues(&mut self, dir: &Path, cwd: PathBuf) {
+        let path = crumbeez_lib::issue_index_path(dir);
+        debug!(?path, "Reading issue index");
+        let cmd = shell::platform().read_first_existing(&[&path]);
+        command_router::run(&self.host, &SummariesCommand::ReadIssues, &cmd, cwd);
+    }
+
+    /// The summaries read, if this is the result of [`load`](Self::load),
+    /// [`load_history`](Self::load_history), or
+    /// [`load_issues`](Self::load_issues) and it worked.  A missing file
+    /// holds none.
+    pub fn handle_result(
+        &mut self,
+        context: &BTreeMap<String, String>,
+        stdout: &[u8],
+        exit_code: Option<i32>,
+    ) -> Option<Loaded> {
+        let command = purpose_of::<SummariesCommand>(context)?;
+        if exit_code != Some(0) {
+            return None;
+        }
+        let text = String::from_utf8_lossy(stdout);
+        Some(match command {
+            SummariesCommand::ReadDaily => Loaded::Today(SummaryStore::parse(&text)),
+            SummariesCommand::ReadHistory => Loaded::History(SummaryStore::parse(&text)),
+            SummariesCommand::ReadIssues => Loaded::Issues(IssueLink::parse_all(&text)),
+        })
+    }
+}
+
+/// A summary as shown in the plugin pane.
+pub fn summary_lines(summary: &StoredSummary) -> Vec<String> {
+    #[cfg(feature = "narrative")]
+    let lines = summary.outline();
+    #[cfg(not(feature = "narrative"))]
+    let lines = vec![summary.headline().to_string()];
+    lines
+}
+
+/// The summaries in `store` best matching `query`, one line each with its
+/// day, headline, and ID.
+pub fn search_report(store: &SummaryStore, query: &str) -> String {
+    let found = store.search(query);
+    if found.is_empty() {
+        return format!("no summaries match \"{}\"\n", query);
+    }
+    let mut out = String::new();
+    for summary in found.iter().take(MAX_SEARCH_RESULTS) {
+        let _ = write!(
+            out,
+            "{} · {}",
+            summary.day().as_deref().unwrap_or("----------"),
+            summary.headline()
+        );
+        if let Some(id) = summary.id {
+            let _ = write!(out, " · {}", id);
+        }
+        out.push('\n');
+    }
+    if found.len() > MAX_SEARCH_RESULTS {
+        let _ = writeln!(out, "… {} more", found.len() - MAX_SEARCH_RESULTS);
+    }
+    out
+}
+
+/// The summaries in `index` that mention `issue`, newest first, one line
+/// each with its day, headline, and ID.
+pub fn issue_report(index: &[IssueLink], issue: &str) -> String {
+    let found: Vec<&IssueLink> = index
+        .iter()
+        .rev()
+        .filter(|link| link.is_about(issue))
+        .collect();
+    if found.is_empty() {
+        return format!("no summaries mention {}\n", issue);
+    }
+    let mut out = String::new();
+    for link in found.iter().take(MAX_SEARCH_RESULTS) {
+        let _ = writeln!(out, "{} · {} · {}", link.day, link.headline, link.summary);
+    }
+    if found.len() > MAX_SEARCH_RESULTS {
+        let _ = writeln!(out, "… {} more", found.len() - MAX_SEARCH_RESULTS);
+    }
+    out
+}
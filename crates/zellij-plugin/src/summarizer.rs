@@ -0,0 +1,213 @@
+//! Summarizing: deciding when the events logged so far are summarized,
+//! handing them to the summary worker, and keeping what comes back.
+//!
+//! [`State`](crate::State) tells the [`Summarizer`] whenever the recorder
+//! logs something.  Once that's been quiet for the inactivity timeout, or at
+//! a boundary such as a pane switch, it hands the unconsumed events to the
+//! summary worker, and keeps the finished summaries for the pane.  Saving
+//! them and the log is left to the [`Io`](crate::io::Io).
+
+use std::time::{Duration, SystemTime};
+use tracing::info;
+
+#[cfg(feature = "analytics")]
+use crumbeez_lib::{split_work_blocks, EntryIter, WorkBlock, DEFAULT_IDLE_GAP_MS};
+use crumbeez_lib::{
+    EventLog, ProjectConfig, ProjectName, Remnants, RetentionConfig, SummaryStore, SummaryTrigger,
+};
+
+use crate::host::HostHandle;
+use crate::summary_worker::{FinishedSummary, SummaryWorkerIO};
+use crate::MS_PER_DAY;
+
+/// What's been summarized and when, and today's summaries.
+#[derive(Default)]
+pub struct Summarizer {
+    host: HostHandle,
+    /// Runs summaries off the main thread.
+    pub worker: SummaryWorkerIO,
+    last_activity_time: Option<SystemTime>,
+    last_summary_time: Option<SystemTime>,
+    /// Start of the UTC day the log was last compacted on, so it's
+    /// compacted once a day.
+    compacted_day_ms: Option<u64>,
+    /// The session's tags, as last set with `crumbeez::tag`.
+    pub session_tags: Vec<String>,
+    /// The session's tags as the unsummarized events began.
+    unsummarized_tags: Vec<String>,
+    /// Events the previous run left unsummarized, found when the log was
+    /// first loaded and summarized on their own.
+    pub recovered: Option<Remnants>,
+    /// The log has been loaded once, so later loads aren't checked for
+    /// remnants.
+    recovery_checked: bool,
+    /// Today's summaries, read back from the daily file and added to as
+    /// they're made.
+    pub summaries: SummaryStore,
+    /// Headlines of today's work blocks, refreshed whenever events are
+    /// summarized.
+    pub work_blocks: Vec<String>,
+}
+
+impl Summarizer {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            worker: SummaryWorkerIO::new(host.clone()),
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Something was logged: a summary is due once it's been quiet for a
+    /// while.
+    pub fn note_activity(&mut self) {
+        self.last_activity_time = Some(self.host.now());
+    }
+
+    /// The activity since the last summary so far isn't worth one of its
+    /// own, e.g. after a break or a bookmark.
+    pub fn forget_activity(&mut self) {
+        self.last_activity_time = None;
+    }
+
+    /// Activity so far is summarized, or needn't be.
+    pub fn note_summarized(&mut self) {
+        self.last_summary_time = Some(self.host.now());
+    }
+
+    /// When activity since the last summary will have been quiet for
+    /// `timeout`, if there's been any.
+    pub fn due_at(&self, timeout: Duration) -> Option<SystemTime> {
+        self.last_activity_time
+            .filter(|last| self.last_summary_time.is_none_or(|summary| *last > summary))
+            .map(|last| last + timeout)
+    }
+
+    /// Whether activity since the last summary has been quiet for
+    /// `timeout`.
+    pub fn is_quiet(&self, timeout: Duration) -> bool {
+        self.due_at(timeout)
+            .is_some_and(|due| self.host.now() >= due)
+    }
+
+    /// Take up the session's tags from a log just loaded, where they're
+    /// kept.
+    pub fn reload_tags(&mut self, event_log: &EventLog) {
+        let consumed = event_log.total_count() - event_log.unconsumed_count();
+        self.session_tags = crumbeez_lib::session_tags(event_log.entries());
+        self.unsummarized_tags = crumbeez_lib::session_tags(event_log.entries().take(consumed));
+    }
+
+    /// Set the session's tags.  Returns whether they changed.
+    pub fn set_tags(&mut self, tags: Vec<String>) -> bool {
+        if tags == self.session_tags {
+            return false;
+        }
+        self.session_tags = tags;
+        true
+    }
+
+    /// What the previous run left unsummarized in the log just loaded, the
+    /// first time it's loaded, kept to be shown.
+    pub fn remnants(&mut self, event_log: &EventLog) -> Option<Remnants> {
+        if self.recovery_checked {
+            return None;
+        }
+        self.recovered = Remnants::find(event_log.unconsumed());
+        self.recovered
+    }
+
+    /// Only what the first load of the log found is left from a previous
+    /// run.
+    pub fn note_loaded(&mut self, loaded: bool) {
+        self.recovery_checked |= loaded;
+    }
+
+    /// Drop event log entries older than the configured retention window,
+    /// and compact those older than `raw_days`.  Returns whether that
+    /// shrank the log, so it needs rewriting.
+    pub fn prune(
+        &mut self,
+        event_log: &mut EventLog,
+        retention: &RetentionConfig,
+        now_ms: u64,
+    ) -> bool {
+        if let Some(days) = retention.max_age_days {
+            let cutoff = now_ms.saturating_sub(days * MS_PER_DAY);
+            event_log.prune_before(cutoff);
+        }
+        self.compact(event_log, retention, now_ms)
+    }
+
+    /// Once a day, compact summarized entries from before the last
+    /// `raw_days` days.  Returns whether any were.
+    fn compact(
+        &mut self,
+        event_log: &mut EventLog,
+        retention: &RetentionConfig,
+        now_ms: u64,
+    ) -> bool {
+        let Some(days) = retention.raw_days else {
+            return false;
+        };
+        let today = now_ms - now_ms % MS_PER_DAY;
+        if self.compacted_day_ms == Some(today) {
+            return false;
+        }
+        self.compacted_day_ms = Some(today);
+        let level = retention.compaction;
+        let removed = event_log.compact_before(today.saturating_sub(days * MS_PER_DAY), level);
+        info!(removed, %level, "Compacted old events");
+        removed > 0
+    }
+
+    /// Recompute today's work blocks (UTC) from the in-memory log.
+    #[cfg(feature = "analytics")]
+    pub fn refresh_work_blocks(&mut self, event_log: &EventLog, now_ms: u64) {
+        let start_of_day = now_ms - now_ms % MS_PER_DAY;
+        let blocks = split_work_blocks(
+            event_log
+                .entries()
+                .between(start_of_day, start_of_day + MS_PER_DAY),
+            DEFAULT_IDLE_GAP_MS,
+        );
+        self.work_blocks = blocks.iter().map(WorkBlock::headline).collect();
+    }
+
+    #[cfg(not(feature = "analytics"))]
+    pub fn refresh_work_blocks(&mut self, _event_log: &EventLog, _now_ms: u64) {}
+
+    /// Hand the unconsumed events to the summary worker, tagged with the
+    /// session's tags as they began.  Returns `false` if there were none.
+    pub fn summarize(
+        &mut self,
+        event_log: &mut EventLog,
+        now_ms: u64,
+        config: &ProjectConfig,
+        project: Option<&ProjectName>,
+        trigger: SummaryTrigger,
+    ) -> bool {
+        let summarized = self.worker.summarize(
+            event_log,
+            now_ms,
+            config,
+            project,
+            &self.unsummarized_tags,
+            trigger,
+        );
+        if summarized {
+            self.unsummarized_tags = self.session_tags.clone();
+        }
+        summarized
+    }
+
+    /// Show a summary back from the worker.
+    pub fn add(&mut self, summary: &FinishedSummary) {
+        self.summaries.push_markdown(&summary.markdown);
+    }
+
+    /// Show today's summaries as read back from the daily file.
+    pub fn reload(&mut self, summaries: SummaryStore) {
+        self.summaries.reload(summaries);
+    }
+}
@@ -0,0 +1,238 @@
+//! Summaries made off the update path.
+//!
+//! Summarizing thousands of events takes long enough to hold up `update()`,
+//! and with it the keys being forwarded to the focused pane.
+//! [`SummaryWorkerIO::summarize`] hands the unconsumed events to
+//! [`SummaryWorker`] instead, and marks them consumed so the next batch
+//! starts after them.  The worker posts the rendered summary back as a
+//! [`SUMMARY_DONE`] message, which [`SummaryWorkerIO::handle_message`]
+//! turns into a [`FinishedSummary`] for the plugin to show and append.
+//! The worker takes jobs in order, so summaries arrive in the order they
+//! were asked for.
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info};
+use zellij_tile::prelude::*;
+
+use crate::host::HostHandle;
+use crate::shell::{base64_decode, base64_encode};
+use crumbeez_lib::{
+    EventLog, IssueLink, Locale, LogEntry, OutboundRedaction, ProjectConfig, ProjectName, Regex,
+    StoredSummary, Summary, SummaryId, SummaryLink, SummaryTrigger,
+};
+
+/// Name the summary worker is registered under (see `register_worker!` in
+/// `main.rs`, which appends `_worker`).
+pub const WORKER_NAME: &str = "summary";
+
+/// Message asking the worker to summarize a [`SummaryJob`].
+const SUMMARIZE_MESSAGE: &str = "summarize";
+
+/// Message the worker sends the plugin with a [`FinishedSummary`].
+pub const SUMMARY_DONE: &str = "crumbeez_summary_done";
+
+/// Events to summarize, with what the worker needs of the plugin's state.
+#[derive(Debug, Serialize, Deserialize)]
+struct SummaryJob {
+    entries: Vec<LogEntry>,
+    /// Sequence number of the first entry.
+    first_seq: u64,
+    id: String,
+    /// The worker doesn't see the plugin's configuration: the locale code,
+    /// and each `redact_outbound` rule's pattern and replacement.
+    locale: String,
+    redact_outbound: Vec<(String, String)>,
+    /// `long_text`'s `display=` length.
+    text_chars: usize,
+    project: Option<String>,
+    trigger: String,
+    /// The session's tags as the entries began.
+    tags: Vec<String>,
+    /// The git branch checked out as the entries began.
+    branch: Option<String>,
+}
+
+/// A summary rendered by the worker.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FinishedSummary {
+    /// `YYYY-MM-DD`, the daily file it belongs in.
+    pub day: String,
+    pub markdown: String,
+    /// Its line in the links file.
+    pub link: String,
+    /// Its lines in the issue index, if it mentions any issues.
+    pub issues: String,
+}
+
+#[derive(Default)]
+pub struct SummaryWorkerIO {
+    host: HostHandle,
+    /// Jobs sent that haven't come back yet.
+    pub in_flight: usize,
+}
+
+impl SummaryWorkerIO {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Send the unconsumed events to the worker under a new ID made at
+    /// `now_ms`, and mark them consumed.  Returns `false` if there were
+    /// none.
+    pub fn summarize(
+        &mut self,
+        event_log: &mut EventLog,
+        now_ms: u64,
+        config: &ProjectConfig,
+        project: Option<&ProjectName>,
+        tags: &[String],
+        trigger: SummaryTrigger,
+    ) -> bool {
+        let entries: Vec<LogEntry> = event_log.unconsumed().cloned().collect();
+        if entries.is_empty() {
+            return false;
+        }
+        let consumed = event_log.total_count() - entries.len();
+        let branch = crumbeez_lib::session_branch(event_log.entries().take(consumed));
+        let job = SummaryJob {
+            first_seq: event_log.consumed_seq(),
+            id: SummaryId::new(now_ms, self.host.random()).to_string(),
+            locale: config.locale.code().to_string(),
+            redact_outbound: config
+                .redact_outbound
+                .iter()
+                .map(|rule| (rule.pattern.to_string(), rule.replacement.clone()))
+                .collect(),
+            text_chars: config.long_text.display_chars,
+            project: project.map(|p| p.name.clone()),
+            trigger: trigger.name().to_string(),
+            tags: tags.to_vec(),
+            branch,
+            entries,
+        };
+        let payload = match rmp_serde::to_vec(&job) {
+            Ok(bytes) => base64_encode(&bytes),
+            Err(e) => {
+                error!(%e, "Failed to serialize summary job");
+                return false;
+            }
+        };
+        info!(
+            events = job.entries.len(),
+            id = %job.id,
+            "Handing events to the summary worker"
+        );
+        self.host.post_message_to(PluginMessage::new_to_worker(
+            WORKER_NAME,
+            SUMMARIZE_MESSAGE,
+            &payload,
+        ));
+        event_log.consume(job.entries.len());
+        self.in_flight += 1;
+        true
+    }
+
+    /// The summary in a [`SUMMARY_DONE`] message from the worker.
+    pub fn handle_message(&mut self, payload: &str) -> Option<FinishedSummary> {
+        self.in_flight = self.in_flight.saturating_sub(1);
+        let finished = base64_decode(payload)
+            .and_then(|bytes| rmp_serde::from_slice::<FinishedSummary>(&bytes).ok());
+        if finished.is_none() {
+            error!("Malformed summary from the worker");
+        }
+        finished
+    }
+}
+
+// ── Worker ───────────────────────────────────────────────────────
+
+/// Plugin worker that summarizes [`SummaryJob`]s and posts the
+/// [`FinishedSummary`] back.
+#[derive(Default, Serialize, Deserialize)]
+pub struct SummaryWorker {
+    #[serde(skip)]
+    host: HostHandle,
+}
+
+impl SummaryWorker {
+    pub fn new(host: HostHandle) -> Self {
+        Self { host }
+    }
+}
+
+impl<'de> ZellijWorker<'de> for SummaryWorker {
+    fn on_message(&mut self, message: String, payload: String) {
+        if message != SUMMARIZE_MESSAGE {
+            return;
+        }
+        // Workers run in their own instance with no subscriber installed.
+        crate::logging::init(&crate::logging::LogSettings::default());
+        let Some(bytes) = base64_decode(&payload) else {
+            error!("Malformed summary job: invalid base64");
+            return;
+        };
+        let job: SummaryJob = match rmp_serde::from_slice(&bytes) {
+            Ok(job) => job,
+            Err(e) => {
+                error!(%e, "Malformed summary job");
+                return;
+            }
+        };
+        let Some(finished) = summarize(job) else {
+            return;
+        };
+        let payload = match rmp_serde::to_vec(&finished) {
+            Ok(bytes) => base64_encode(&bytes),
+            Err(e) => {
+                error!(%e, "Failed to serialize summary");
+                return;
+            }
+        };
+        self.host
+            .post_message_to_plugin(PluginMessage::new_to_plugin(SUMMARY_DONE, &payload));
+    }
+}
+
+fn summarize(job: SummaryJob) -> Option<FinishedSummary> {
+    let mut config = ProjectConfig {
+        locale: Locale::from_code(&job.locale).unwrap_or_default(),
+        ..ProjectConfig::default()
+    };
+    for (pattern, replacement) in job.redact_outbound {
+        // The plugin compiled these already.
+        if let Ok(pattern) = Regex::new(&pattern) {
+            config.redact_outbound.push(OutboundRedaction {
+                pattern,
+                replacement,
+            });
+        }
+    }
+
+    let mut summary = Summary::from_events(job.entries.into_iter());
+    summary.id = job.id.parse().ok();
+    summary.locale = config.locale;
+    summary.project = job.project;
+    summary.trigger = SummaryTrigger::from_name(&job.trigger);
+    summary.carry_tags(&job.tags);
+    summary.carry_branch(job.branch.as_deref());
+    #[cfg(feature = "analytics")]
+    summary.find_topics();
+    summary.shorten_text(job.text_chars);
+    summary.redact_text(&|text| config.redact_outbound(text));
+    let link = SummaryLink::new(&summary, job.first_seq)?;
+    debug!(events = summary.events_consumed, id = %link.id, "Summarized");
+    let markdown = summary.to_markdown();
+    let issues = IssueLink::all_in(&StoredSummary::new(&markdown))
+        .iter()
+        .map(IssueLink::to_kdl)
+        .collect();
+    Some(FinishedSummary {
+        day: link.day.clone(),
+        markdown,
+        link: link.to_kdl(),
+        issues,
+    })
+}
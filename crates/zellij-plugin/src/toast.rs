@@ -0,0 +1,109 @@
+//! Short-lived notices in crumbeez's pane title.
+//!
+//! Zellij has no notification API for plugins, so a toast renames the
+//! plugin's pane for [`TOAST_DURATION_MS`] and then puts the title back.
+//! The frame shows it even while the pane is scrolled or covered by a
+//! prompt, which changing what the pane prints wouldn't.  The `toasts`
+//! plugin setting picks which [`Severity`]s are shown.
+
+use std::fmt;
+
+#[cfg(not(feature = "minimal"))]
+use tracing::debug;
+
+#[cfg(not(feature = "minimal"))]
+use crate::host::HostHandle;
+
+/// How long a toast stays up.
+#[cfg(not(feature = "minimal"))]
+pub const TOAST_DURATION_MS: u64 = 5 * 1000;
+
+/// The pane title while no toast is up.
+#[cfg(not(feature = "minimal"))]
+const TITLE: &str = "crumbeez";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    pub const ALL: [Self; 3] = [Self::Info, Self::Warn, Self::Error];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|severity| severity.name() == name)
+    }
+
+    #[cfg(not(feature = "minimal"))]
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Info => "✓",
+            Self::Warn => "⚠",
+            Self::Error => "✗",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(not(feature = "minimal"))]
+#[derive(Debug, Default)]
+pub struct Toasts {
+    host: HostHandle,
+    /// When the toast that's up should be taken down.
+    shown_until_ms: Option<u64>,
+}
+
+#[cfg(not(feature = "minimal"))]
+impl Toasts {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            host,
+            shown_until_ms: None,
+        }
+    }
+
+    /// Show `text` in the pane title if `enabled` includes `severity`.  A
+    /// newer toast replaces one that's still up.
+    pub fn show(&mut self, enabled: &[Severity], severity: Severity, text: &str, now_ms: u64) {
+        if !enabled.contains(&severity) {
+            return;
+        }
+        debug!(%severity, text, "Showing toast");
+        self.host.rename_plugin_pane(
+            self.host.get_plugin_ids().plugin_id,
+            format!("{} {} {}", TITLE, severity.icon(), text),
+        );
+        self.shown_until_ms = Some(now_ms + TOAST_DURATION_MS);
+    }
+
+    /// Take the toast down if its time is up by `now_ms`.
+    pub fn expire(&mut self, now_ms: u64) {
+        if self.shown_until_ms.is_some_and(|until| until <= now_ms) {
+            self.shown_until_ms = None;
+            self.host
+                .rename_plugin_pane(self.host.get_plugin_ids().plugin_id, TITLE);
+        }
+    }
+
+    /// When the toast that's up should be taken down, if one is.
+    pub fn expires_at(&self) -> Option<u64> {
+        self.shown_until_ms
+    }
+}
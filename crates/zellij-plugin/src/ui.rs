@@ -0,0 +1,927 @@
+//! The plugin pane: the views it shows, the keys it takes while focused,
+//! and the notices it flashes.
+//!
+//! The [`Ui`] owns what the views need between renders: which one is open,
+//! where the browser and search are, and whether the pane is hidden.  It
+//! draws from a [`View`] of everything else, and what a key asks of the
+//! rest of the plugin comes back as a [`KeyOutcome`].
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+use zellij_tile::prelude::*;
+
+use crumbeez_lib::{
+    fill, format_hm, sparkline, EntryIter, EventLog, HumanDuration, HumanSize, InstanceRole,
+    KeystrokeActivity, KeystrokeEvent, LogEntry, Onboarding, OnboardingInput, OnboardingStep,
+    PowerProfile, ProjectConfig, SummaryStore, TimeAgo, SPARKLINE_MINUTES, SUMMARY_PAGE_SIZE,
+};
+
+use crate::browser::{BrowserOutcome, SummaryBrowser};
+use crate::clipboard;
+use crate::event_log_io::EventLogIO;
+use crate::host::HostHandle;
+use crate::io::Io;
+use crate::io_probe::ProbeResult;
+use crate::logging;
+use crate::mark::{MarkPrompt, PromptOutcome, RiskyCommand};
+use crate::plugin_config::{ConfigProblem, PluginConfig};
+use crate::rollup::RollupIO;
+#[cfg(feature = "analytics")]
+use crate::rollup::CALENDAR_PIPE;
+use crate::root_discovery::RootDiscovery;
+use crate::search::{highlight, Search, SearchOutcome};
+use crate::shell;
+use crate::summaries;
+use crate::summarizer::Summarizer;
+use crate::toast::Toasts;
+use crate::{DOCTOR_PIPE, PERMISSIONS_PIPE};
+
+/// What the plugin pane shows, and whether it can be seen.
+#[derive(Default)]
+pub struct Ui {
+    host: HostHandle,
+    /// The notice flashed in the pane title, if any.
+    pub toasts: Toasts,
+    /// First-run setup, active while discovery is `AwaitingOnboarding`.
+    pub onboarding: Option<Onboarding>,
+    /// The bookmark prompt, while open.  Keystrokes go to it instead of the
+    /// focused pane.
+    pub mark_prompt: Option<MarkPrompt>,
+    /// Past summaries being browsed, in place of the usual view.
+    pub browser: Option<SummaryBrowser>,
+    /// The search open in the plugin pane.  Its keys go to it instead of
+    /// being logged.
+    pub search: Option<Search>,
+    /// What was last copied to the clipboard, or why nothing was.
+    copy_status: Option<String>,
+    /// Show the doctor report instead of the usual view.
+    pub doctor: bool,
+    /// A time in the month the calendar view shows, in place of the usual
+    /// view.
+    pub calendar: Option<u64>,
+    /// The page of summaries shown, 0 for the newest.
+    pub summary_page: usize,
+    /// The tab in front and whether its floating panes are shown, from the
+    /// last tab update.
+    active_tab: Option<(usize, bool)>,
+    /// Where the plugin's own pane is, from the last pane update.
+    own_pane: Option<OwnPane>,
+    /// The plugin pane can't be seen: it's on a tab in the background or
+    /// hidden on its own, or Zellij said so.
+    pub hidden: bool,
+}
+
+/// Where the plugin's own pane is, to tell whether it can be seen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OwnPane {
+    pub tab_index: usize,
+    pub floating: bool,
+    pub suppressed: bool,
+}
+
+/// Everything outside the [`Ui`] that the views show, lent for a render.
+pub struct View<'a> {
+    pub discovery: &'a RootDiscovery,
+    pub permissions_granted: bool,
+    pub role: InstanceRole,
+    /// Canned activity is played in place of intercepted keys.
+    pub demo: bool,
+    pub event_log: &'a EventLog,
+    /// The keys typed lately.
+    pub keystrokes: &'a KeystrokeActivity,
+    pub summarizer: &'a Summarizer,
+    pub io: &'a Io,
+    pub config: &'a ProjectConfig,
+    pub plugin_config: &'a PluginConfig,
+    pub power: PowerProfile,
+    /// Quiet time before activity is summarized, as the power profile has
+    /// it.
+    pub inactivity_timeout: Duration,
+    pub now_ms: u64,
+}
+
+/// What a key taken by the pane asks of the rest of the plugin.
+pub enum KeyOutcome {
+    /// The view doesn't take the key.
+    Ignored,
+    /// The view took it, and needs redrawing.
+    Handled,
+    /// Drop a bookmark with this name.
+    Bookmark(String),
+    /// Log a risky command, with the reason given for it if there was one.
+    Risky(KeystrokeEvent),
+    /// Onboarding finished: set up these roots with this config.
+    Onboarded(Vec<PathBuf>, Box<ProjectConfig>),
+}
+
+impl KeyOutcome {
+    /// Whether the pane needs redrawing.
+    pub fn changed(&self) -> bool {
+        !matches!(self, Self::Ignored)
+    }
+}
+
+/// Width of a section header line, title included.
+const SECTION_RULE_WIDTH: usize = 55;
+
+/// A section header in the plugin pane: `title` on a horizontal rule.
+fn section_header(title: &str) -> String {
+    let rule = SECTION_RULE_WIDTH.saturating_sub(title.chars().count() + 5);
+    format!("─── {} {}", title, "─".repeat(rule.max(3)))
+}
+
+/// Most of the event log's search matches listed at once.
+const SEARCH_RESULT_LINES: usize = 5;
+
+/// `line` cut to `cols` characters, ending in `…` if anything was cut.
+fn fit(line: String, cols: usize) -> String {
+    if cols > 4 && line.chars().count() > cols {
+        let mut s: String = line.chars().take(cols - 1).collect();
+        s.push('…');
+        s
+    } else {
+        line
+    }
+}
+
+/// How long before `now_ms` `time` was, `just now` if the clock has since
+/// stepped back.
+fn time_ago(time: SystemTime, now_ms: u64) -> TimeAgo {
+    let now = UNIX_EPOCH + Duration::from_millis(now_ms);
+    TimeAgo(now.duration_since(time).unwrap_or_default())
+}
+
+/// Maximum number of lines the debug panel takes from the pane.
+const DEBUG_PANEL_LINES: usize = 8;
+
+/// The alert shown while event log writes keep failing: how many in a row,
+/// and how long before `now_ms` the log was last saved.
+pub fn save_alert(event_log: &EventLogIO, now_ms: u64) -> Option<String> {
+    if !event_log.alerting() {
+        return None;
+    }
+    let since = match event_log.last_saved {
+        Some(saved) => format!("last saved {}", time_ago(saved, now_ms)),
+        None => "not saved since the plugin started".to_string(),
+    };
+    Some(format!(
+        "⚠ {} event log writes failed in a row, {}; retrying every {}",
+        event_log.consecutive_failures,
+        since,
+        HumanDuration(event_log.flush_interval())
+    ))
+}
+
+pub fn render_config_problems(problems: &[ConfigProblem]) {
+    println!("crumbeez — configuration error");
+    println!();
+    println!("The plugin configuration has problems, so crumbeez isn't running:");
+    println!();
+    for problem in problems {
+        println!("  ✗ {}", problem);
+    }
+    println!();
+    println!("Fix them in the plugin's block of your Zellij layout or config,");
+    println!("then reload the plugin.");
+}
+
+impl Ui {
+    pub fn new(host: HostHandle) -> Self {
+        Self {
+            toasts: Toasts::new(host.clone()),
+            host,
+            ..Self::default()
+        }
+    }
+
+    /// Whether a change is worth drawing.  While the pane can't be seen,
+    /// `background` work like logging keys doesn't redraw it, and unless
+    /// the power profile has it `render_hidden` nothing does; it's drawn
+    /// afresh once it's in view.
+    pub fn worth_rendering(&self, changed: bool, background: bool, render_hidden: bool) -> bool {
+        changed && (!self.hidden || (!background && render_hidden))
+    }
+
+    /// Note which tab is in front from a tab update.
+    pub fn follow_tabs(&mut self, tabs: &[TabInfo]) {
+        self.active_tab = tabs
+            .iter()
+            .find(|t| t.active)
+            .map(|t| (t.position, t.are_floating_panes_visible));
+        self.update_visibility();
+    }
+
+    /// Find the plugin's own pane, `plugin_id`, in a pane update.
+    pub fn follow_own_pane(&mut self, manifest: &PaneManifest, plugin_id: u32) {
+        self.own_pane = manifest.panes.iter().find_map(|(tab_index, panes)| {
+            panes
+                .iter()
+                .find(|pane| pane.is_plugin && pane.id == plugin_id)
+                .map(|pane| OwnPane {
+                    tab_index: *tab_index,
+                    floating: pane.is_floating,
+                    suppressed: pane.is_suppressed,
+                })
+        });
+        self.update_visibility();
+    }
+
+    /// Work out whether the plugin pane can be seen from the last tab and
+    /// pane updates, once both have come.
+    fn update_visibility(&mut self) {
+        let (Some((active_tab, floating_shown)), Some(own)) = (self.active_tab, self.own_pane)
+        else {
+            return;
+        };
+        let hidden =
+            own.tab_index != active_tab || own.suppressed || (own.floating && !floating_shown);
+        if hidden != self.hidden {
+            debug!(hidden, "Plugin pane visibility changed");
+        }
+        self.hidden = hidden;
+    }
+
+    /// Start onboarding if discovery just paused for it.
+    pub fn maybe_start_onboarding(&mut self, discovery: &RootDiscovery) {
+        if self.onboarding.is_some() {
+            return;
+        }
+        if let crumbeez_lib::DiscoveryPhase::AwaitingOnboarding { ref roots } = discovery.phase {
+            self.onboarding = Some(Onboarding::new(
+                roots.clone(),
+                discovery.initial_cwd.clone(),
+            ));
+        }
+    }
+
+    pub fn handle_onboarding_key(&mut self, key: &KeyWithModifier) -> KeyOutcome {
+        let input = match key.bare_key {
+            BareKey::Up | BareKey::Char('k') => OnboardingInput::Up,
+            BareKey::Down | BareKey::Char('j') => OnboardingInput::Down,
+            BareKey::Enter | BareKey::Char(' ') => OnboardingInput::Select,
+            BareKey::Esc | BareKey::Backspace => OnboardingInput::Back,
+            _ => return KeyOutcome::Ignored,
+        };
+        let Some(onboarding) = self.onboarding.as_mut() else {
+            return KeyOutcome::Ignored;
+        };
+        let changed = onboarding.handle(input);
+        if onboarding.is_done() {
+            info!("Onboarding complete");
+            let outcome =
+                KeyOutcome::Onboarded(onboarding.chosen_roots(), Box::new(onboarding.config()));
+            self.onboarding = None;
+            return outcome;
+        }
+        if changed {
+            KeyOutcome::Handled
+        } else {
+            KeyOutcome::Ignored
+        }
+    }
+
+    pub fn handle_mark_key(&mut self, key: &KeyWithModifier) -> KeyOutcome {
+        let Some(prompt) = self.mark_prompt.as_mut() else {
+            return KeyOutcome::Ignored;
+        };
+        let (text, risky) = match prompt.handle_key(key) {
+            PromptOutcome::Editing => return KeyOutcome::Handled,
+            PromptOutcome::Submit(text) => (Some(text), prompt.risky.take()),
+            PromptOutcome::Cancel => (None, prompt.risky.take()),
+        };
+        self.mark_prompt = None;
+        match (risky, text) {
+            // Without a reason, the command is still worth noting.
+            (Some(risky), reason) => KeyOutcome::Risky(risky.into_event(reason)),
+            (None, Some(name)) => KeyOutcome::Bookmark(name),
+            (None, None) => KeyOutcome::Handled,
+        }
+    }
+
+    /// Ask why `risky` was run, in the bookmark prompt.  It's given back if
+    /// the prompt is already open, to log without a reason.
+    pub fn ask_why(&mut self, risky: RiskyCommand) -> Result<(), RiskyCommand> {
+        if self.mark_prompt.is_some() {
+            return Err(risky);
+        }
+        self.mark_prompt = Some(MarkPrompt {
+            text: String::new(),
+            risky: Some(risky),
+        });
+        Ok(())
+    }
+
+    pub fn handle_browser_key(&mut self, key: &KeyWithModifier, config: &ProjectConfig) -> bool {
+        let Some(browser) = self.browser.as_mut() else {
+            return false;
+        };
+        match browser.handle_key(key) {
+            BrowserOutcome::Browsing => {}
+            BrowserOutcome::Copy(markdown) => self.copy(&markdown, "the summary", config),
+            BrowserOutcome::Close => self.browser = None,
+        }
+        true
+    }
+
+    /// Copy the newest of `summaries` on the page shown to the clipboard.
+    pub fn copy_summary(&mut self, summaries: &SummaryStore, config: &ProjectConfig) {
+        match summaries.page(self.summary_page, SUMMARY_PAGE_SIZE).last() {
+            Some(summary) => self.copy(&summary.markdown, "the latest summary", config),
+            None => self.copy_status = Some("no summary to copy yet".to_string()),
+        }
+    }
+
+    /// Copy `markdown` to the clipboard, redacted as anything leaving the
+    /// plugin is.
+    fn copy(&mut self, markdown: &str, what: &str, config: &ProjectConfig) {
+        let text = config.redact_outbound(markdown);
+        debug!(len = text.len(), "Copying to the clipboard");
+        self.copy_status = Some(clipboard::copy(&self.host, &text, what));
+    }
+
+    /// Open the search.
+    pub fn open_search(&mut self) {
+        self.search = Some(Search::open());
+    }
+
+    /// The entries of `event_log` matching the open search, newest first.
+    fn search_matches<'a>(&self, event_log: &'a EventLog) -> Vec<&'a LogEntry> {
+        let Some(ref search) = self.search else {
+            return Vec::new();
+        };
+        if search.query.is_empty() {
+            return Vec::new();
+        }
+        let mut found: Vec<&LogEntry> = event_log.entries().matching(&search.query).collect();
+        found.reverse();
+        found
+    }
+
+    pub fn handle_search_key(&mut self, key: &KeyWithModifier, event_log: &EventLog) -> bool {
+        let matches = self.search_matches(event_log).len();
+        let Some(search) = self.search.as_mut() else {
+            return false;
+        };
+        if let SearchOutcome::Close = search.handle_key(key, matches) {
+            self.search = None;
+        }
+        true
+    }
+
+    /// Open the calendar view on the month containing `now_ms`, or close it
+    /// if it's open.  Returns whether it opened, so the summary index
+    /// should be read for it.
+    #[cfg(feature = "analytics")]
+    pub fn toggle_calendar(&mut self, now_ms: u64) -> bool {
+        if self.calendar.take().is_some() {
+            return false;
+        }
+        debug!("Opening calendar");
+        self.calendar = Some(now_ms);
+        true
+    }
+
+    /// There's no calendar without analytics; reading the index says so.
+    #[cfg(not(feature = "analytics"))]
+    pub fn toggle_calendar(&mut self, _now_ms: u64) -> bool {
+        true
+    }
+
+    /// The project at `root`'s activity in the month containing
+    /// `month_ms`, from the index as last read.
+    #[cfg(feature = "analytics")]
+    fn calendar_month(
+        month_ms: u64,
+        rollup: &RollupIO,
+        root: Option<&PathBuf>,
+    ) -> crumbeez_lib::MonthHeatmap {
+        let empty = crumbeez_lib::SummaryIndex::default();
+        let index = rollup.calendar_index.as_ref().unwrap_or(&empty);
+        let root = root.cloned().unwrap_or_default();
+        crumbeez_lib::MonthHeatmap::from_index(index, &root, month_ms)
+    }
+
+    /// ←/→ step through the months, Esc closes the calendar.
+    #[cfg(feature = "analytics")]
+    pub fn handle_calendar_key(
+        &mut self,
+        key: &KeyWithModifier,
+        rollup: &RollupIO,
+        root: Option<&PathBuf>,
+    ) -> bool {
+        let Some(month_ms) = self.calendar else {
+            return false;
+        };
+        let month = Self::calendar_month(month_ms, rollup, root);
+        self.calendar = match key.bare_key {
+            BareKey::Esc => None,
+            BareKey::Left => Some(month.previous_ms().unwrap_or(month_ms)),
+            BareKey::Right => Some(month.next_ms()),
+            _ => return false,
+        };
+        true
+    }
+
+    #[cfg(not(feature = "analytics"))]
+    pub fn handle_calendar_key(
+        &mut self,
+        _key: &KeyWithModifier,
+        _rollup: &RollupIO,
+        _root: Option<&PathBuf>,
+    ) -> bool {
+        false
+    }
+
+    /// Open the summary browser, or close it if it's open.  Returns
+    /// whether it opened, so every day's summaries should be read into it.
+    pub fn toggle_browser(&mut self) -> bool {
+        if self.browser.take().is_some() {
+            return false;
+        }
+        debug!("Opening summary browser");
+        self.browser = Some(SummaryBrowser::default());
+        true
+    }
+
+    /// Fill the browser, if it's open, with every day's summaries.
+    /// Returns whether it was.
+    pub fn load_history(&mut self, history: SummaryStore) -> bool {
+        let Some(ref mut browser) = self.browser else {
+            return false;
+        };
+        browser.load(history);
+        true
+    }
+
+    /// PgUp/PgDn page through `pages` pages of summaries.
+    pub fn turn_page(&mut self, key: BareKey, pages: usize) {
+        let last = pages.saturating_sub(1);
+        self.summary_page = match key {
+            BareKey::PageUp => (self.summary_page + 1).min(last),
+            _ => self.summary_page.saturating_sub(1),
+        };
+    }
+
+    /// Keep the page shown within the `pages` there are.
+    pub fn fit_page(&mut self, pages: usize) {
+        self.summary_page = self.summary_page.min(pages.saturating_sub(1));
+    }
+
+    pub fn render(&mut self, view: &View, rows: usize, cols: usize) {
+        if let Some(ref onboarding) = self.onboarding {
+            render_onboarding(onboarding);
+            return;
+        }
+
+        if self.doctor {
+            Self::render_doctor(view);
+            return;
+        }
+
+        #[cfg(feature = "analytics")]
+        if let Some(month_ms) = self.calendar {
+            Self::render_calendar(view, month_ms);
+            return;
+        }
+
+        if let Some(ref mut browser) = self.browser {
+            for line in browser.render(rows, cols) {
+                println!("{}", line);
+            }
+            return;
+        }
+
+        self.render_main(view, rows, cols);
+    }
+
+    /// The calendar view: a month of the project's daily event counts.
+    #[cfg(feature = "analytics")]
+    fn render_calendar(view: &View, month_ms: u64) {
+        let rollup = &view.io.rollup;
+        let month = Self::calendar_month(month_ms, rollup, view.discovery.roots.first());
+        println!("crumbeez calendar — {}", month.title());
+        println!();
+        if rollup.calendar_index.is_some() {
+            for line in month.to_lines() {
+                println!("  {}", line);
+            }
+            println!();
+            println!(
+                "  {} active days · {} events",
+                month.active_days(),
+                month.total_events()
+            );
+        } else {
+            let status = rollup.status.as_deref();
+            println!("  {}", status.unwrap_or("reading the summary index…"));
+        }
+        println!();
+        println!(
+            "  ←/→ month · Esc or `zellij pipe --name {}` to close",
+            CALENDAR_PIPE
+        );
+    }
+
+    /// Everything about the host and storage that could explain missing or
+    /// unsaved breadcrumbs.
+    fn render_doctor(view: &View) {
+        println!("crumbeez doctor");
+        println!();
+        println!("─── Host ──────────────────────────────────────────────");
+        println!("  platform: {:?}", shell::platform());
+        println!(
+            "  permissions: {}",
+            if view.permissions_granted {
+                "granted"
+            } else {
+                "not granted"
+            }
+        );
+        match shell::platform() {
+            shell::Platform::Windows => println!("  binary IO: PowerShell"),
+            shell::Platform::Unix if !view.io.probe.is_done() => {
+                println!("  binary IO: probing…")
+            }
+            shell::Platform::Unix => println!("  binary IO: {}", shell::io_strategy()),
+        }
+        for (io, result) in &view.io.probe.results {
+            match result {
+                ProbeResult::Passed => println!("    ✓ {}", io),
+                ProbeResult::Failed(reason) => println!("    ✗ {}: {}", io, reason),
+            }
+        }
+
+        println!();
+        println!("─── Storage ───────────────────────────────────────────");
+        println!("  discovery: {}", view.discovery.phase);
+        if let Some(ref project) = view.io.project_name.name {
+            println!("  project name: {} (from {})", project.name, project.source);
+        }
+        for timeout in &view.discovery.timeouts {
+            println!("  ⚠ {}", timeout);
+        }
+        for root in &view.discovery.fallback_roots {
+            println!(
+                "  ⚠ {} not writable, using fallback storage",
+                root.display()
+            );
+        }
+        match view.io.event_log.log_path() {
+            Some(path) => println!("  event log: {}", path.display()),
+            None => println!("  event log: not loaded yet"),
+        }
+        if let Some(size) = view.io.event_log.size {
+            println!("  log size: {}", HumanSize(size));
+        }
+        if let Some(saved) = view.io.event_log.last_saved {
+            println!("  last save: {}", time_ago(saved, view.now_ms));
+        }
+        if let Some(ref damage) = view.io.event_log.damage {
+            println!("  ⚠ event log: {}", damage);
+        }
+        if let Some(alert) = save_alert(&view.io.event_log, view.now_ms) {
+            println!("  \u{1b}[31m{}\u{1b}[0m", alert);
+        }
+        if view.summarizer.worker.in_flight > 0 {
+            println!(
+                "  summaries in progress: {}",
+                view.summarizer.worker.in_flight
+            );
+        }
+        match view.io.config.error {
+            Some(ref err) => println!("  ⚠ config: {}", err),
+            None if view.io.config.loaded => println!("  config: ok"),
+            None => println!("  config: not loaded yet"),
+        }
+        if let Some(ref values) = view.io.session_context.values {
+            let keys: Vec<&str> = values.keys().map(String::as_str).collect();
+            println!(
+                "  session context: {}",
+                if keys.is_empty() {
+                    "nothing found".to_string()
+                } else {
+                    keys.join(", ")
+                }
+            );
+        }
+        match view.io.atuin.available {
+            Some(true) => println!("  atuin: commands looked up"),
+            Some(false) => println!("  atuin: not found"),
+            None => {}
+        }
+        let profile = view.power;
+        println!(
+            "  power: {}{}{}",
+            view.plugin_config.power,
+            match view.io.power.on_battery {
+                Some(true) => ", on battery",
+                Some(false) => ", on mains",
+                None => "",
+            },
+            if profile.is_low() {
+                format!(
+                    ", low power: log written every {}, summaries after {} quiet",
+                    HumanDuration(profile.flush_interval),
+                    HumanDuration(view.inactivity_timeout)
+                )
+            } else {
+                String::new()
+            }
+        );
+        println!(
+            "  log level: {}{}",
+            view.plugin_config.log.level,
+            if view.plugin_config.log.file {
+                ", mirrored to plugin.log"
+            } else {
+                ""
+            }
+        );
+
+        println!();
+        println!("  p to ask for permissions again");
+        println!("  Esc or `zellij pipe --name {}` to close", DOCTOR_PIPE);
+    }
+    /// The usual view: activity, storage, summaries, and the keys typed
+    /// lately.
+    fn render_main(&self, view: &View, rows: usize, cols: usize) {
+        println!("crumbeez — breadcrumb logger");
+        // Events per minute over the last hour, the current minute on the
+        // right, cut to fit a narrow pane.
+        let minutes = SPARKLINE_MINUTES.min(cols.saturating_sub(1));
+        let timestamps = view.event_log.entries().map(|entry| entry.timestamp_ms);
+        println!("{}", sparkline(timestamps, view.now_ms, minutes));
+        println!();
+        if let Some(alert) = save_alert(&view.io.event_log, view.now_ms) {
+            println!("\u{1b}[1;31m{}\u{1b}[0m", alert);
+            println!();
+        }
+        if let Some(ref prompt) = self.mark_prompt {
+            match &prompt.risky {
+                Some(risky) => {
+                    println!("⚠ Why run {:?}? {}▏", risky.command, prompt.text);
+                    println!("  Enter save · Esc skip");
+                }
+                None => {
+                    println!("📌 Bookmark: {}▏", prompt.text);
+                    println!("  Enter save · Esc cancel");
+                }
+            }
+            println!();
+        }
+        if view.plugin_config.viewer {
+            println!("👀 Viewer: showing the log as another crumbeez writes it");
+            println!();
+        } else if view.demo {
+            println!("🎬 Demo: replaying canned activity, nothing you type is logged");
+            println!();
+        } else if let InstanceRole::Viewer { recorder } = view.role {
+            println!(
+                "👀 Viewing only: crumbeez in plugin pane {} is recording",
+                recorder
+            );
+            println!();
+        }
+        println!("Root discovery: {}", view.discovery.phase);
+        if !view.permissions_granted
+            && matches!(
+                view.discovery.phase,
+                crumbeez_lib::DiscoveryPhase::Failed(_)
+            )
+        {
+            println!(
+                "  p or `zellij pipe --name {}` to ask for permissions again",
+                PERMISSIONS_PIPE
+            );
+        }
+
+        if let Some(name) = view.io.project_name() {
+            println!("  project: {}", name);
+        }
+        if !view.summarizer.session_tags.is_empty() {
+            println!("  tags: {}", view.summarizer.session_tags.join(" "));
+        }
+        if let Some(ref git_root) = view.discovery.git_root {
+            println!("  git root: {}", git_root.display());
+        }
+        if let Some(ref parent) = view.discovery.parent_git_root {
+            println!("  parent repo: {}", parent.display());
+        }
+        for timeout in &view.discovery.timeouts {
+            println!("  ⚠ {}", timeout);
+        }
+        for root in &view.discovery.fallback_roots {
+            println!(
+                "  ⚠ {} not writable, using fallback storage",
+                root.display()
+            );
+        }
+        if let Some(ref err) = view.io.config.error {
+            println!("  ⚠ config: {}", err);
+        }
+        if let Some(ref damage) = view.io.event_log.damage {
+            println!("  ⚠ event log: {}", damage);
+        }
+        if let Some(remnants) = view.summarizer.recovered {
+            println!(
+                "  ⚠ recovered {} events from a run that stopped at {} UTC",
+                remnants.events,
+                format_hm(remnants.last_ms)
+            );
+        }
+        if let Some(ref status) = view.io.standup.status {
+            println!("  📝 {}", status);
+        }
+        if let Some(ref status) = view.io.pull_request.status {
+            println!("  🔀 {}", status);
+        }
+        if let Some(ref status) = view.io.rollup.status {
+            println!("  🗓 {}", status);
+        }
+        if let Some(ref status) = view.io.history.status {
+            println!("  🕘 {}", status);
+        }
+        if let Some(ref status) = view.io.snapshot.status {
+            println!("  📸 {}", status);
+        }
+        if let Some(ref status) = view.io.long_text.status {
+            println!("  ✂ {}", status);
+        }
+        if let Some(ref status) = self.copy_status {
+            println!("  📋 {}", status);
+        }
+
+        let strings = view.config.locale.strings();
+        println!();
+        println!("{}", section_header(strings.ui_event_log));
+        let stats = view.event_log.stats();
+        println!(
+            "  {}",
+            fill(
+                strings.ui_event_totals,
+                &[&stats.total_events, &stats.unconsumed_events]
+            )
+        );
+        if let Some(hour) = stats.busiest_hour {
+            println!(
+                "  {} days · {} panes · {} chars typed · busiest at {:02}:00 UTC",
+                stats.events_per_day.len(),
+                stats.unique_panes,
+                stats.typed_chars,
+                hour
+            );
+        }
+        let most_reworked = stats
+            .rework_by_app
+            .iter()
+            .filter(|(app, totals)| !app.is_empty() && totals.deleted > 0)
+            .max_by(|a, b| a.1.rework().total_cmp(&b.1.rework()));
+        if let Some((app, totals)) = most_reworked {
+            println!(
+                "  most rework in {}: {:.0}% of {} typed chars deleted",
+                app,
+                totals.rework() * 100.0,
+                totals.typed
+            );
+        }
+        let mut search_lines = 0;
+        if let Some(ref search) = self.search {
+            let matches = self.search_matches(view.event_log);
+            println!("  {}", search.status(matches.len()));
+            // The matches around the selected one, oldest at the top.
+            let selected = search.selected.min(matches.len().saturating_sub(1));
+            let first = selected
+                .saturating_sub(SEARCH_RESULT_LINES / 2)
+                .min(matches.len().saturating_sub(SEARCH_RESULT_LINES));
+            let shown = first..(first + SEARCH_RESULT_LINES).min(matches.len());
+            search_lines = 1 + shown.len();
+            for i in shown.rev() {
+                let entry = matches[i];
+                let marker = if i == selected { "▶" } else { " " };
+                let line = format!(
+                    "  {} {} {}",
+                    marker,
+                    format_hm(entry.timestamp_ms),
+                    entry.event.shortened(view.config.long_text.display_chars)
+                );
+                println!("{}", highlight(&fit(line, cols), &search.query));
+            }
+        }
+
+        if !view.summarizer.work_blocks.is_empty() {
+            println!();
+            println!("{}", section_header(strings.ui_work_blocks));
+            for headline in &view.summarizer.work_blocks {
+                let line = format!("  {}", headline);
+                let truncated = if cols > 4 && line.chars().count() > cols {
+                    let mut s: String = line.chars().take(cols - 1).collect();
+                    s.push('…');
+                    s
+                } else {
+                    line
+                };
+                println!("{}", truncated);
+            }
+        }
+
+        if !view.summarizer.summaries.is_empty() {
+            println!();
+            let pages = view.summarizer.summaries.page_count(SUMMARY_PAGE_SIZE);
+            if pages > 1 {
+                let title = format!(
+                    "{} {}/{} (PgUp/PgDn)",
+                    strings.ui_summaries,
+                    self.summary_page + 1,
+                    pages
+                );
+                println!("{}", section_header(&title));
+            } else {
+                println!("{}", section_header(strings.ui_summaries));
+            }
+            for summary in view
+                .summarizer
+                .summaries
+                .page(self.summary_page, SUMMARY_PAGE_SIZE)
+            {
+                for line in summaries::summary_lines(summary) {
+                    let truncated = if cols > 4 && line.chars().count() > cols {
+                        let mut s: String = line.chars().take(cols - 1).collect();
+                        s.push('…');
+                        s
+                    } else {
+                        line
+                    };
+                    println!("{}", truncated);
+                }
+            }
+        }
+
+        println!();
+        println!("{}", section_header(strings.ui_keystrokes));
+
+        let query = self
+            .search
+            .as_ref()
+            .map_or("", |search| search.query.as_str());
+        let events: Vec<&KeystrokeEvent> = view
+            .keystrokes
+            .events()
+            .iter()
+            .filter(|event| event.matches(query))
+            .collect();
+        if events.is_empty() {
+            println!("  {}", strings.ui_no_keystrokes);
+        } else {
+            let panel_lines = if view.plugin_config.log.panel {
+                DEBUG_PANEL_LINES + 2
+            } else {
+                0
+            };
+            let available_lines = rows.saturating_sub(15 + panel_lines + search_lines).max(1);
+            let skip = events.len().saturating_sub(available_lines);
+            for event in events.iter().skip(skip) {
+                let event = event.shortened(view.config.long_text.display_chars);
+                let line = fit(format!("  {}", event), cols);
+                println!("{}", highlight(&line, query));
+            }
+        }
+
+        if view.plugin_config.log.panel {
+            println!();
+            println!(
+                "─── Debug Log ({}) ─────────────────────────────────",
+                view.plugin_config.log.level
+            );
+            for line in logging::recent_lines(DEBUG_PANEL_LINES) {
+                let truncated = if cols > 4 && line.chars().count() > cols {
+                    let mut s: String = line.chars().take(cols - 1).collect();
+                    s.push('…');
+                    s
+                } else {
+                    line
+                };
+                println!("{}", truncated);
+            }
+        }
+    }
+}
+
+fn render_onboarding(onboarding: &Onboarding) {
+    println!("crumbeez — first-run setup");
+    println!();
+    println!("{}", onboarding.step.title());
+    println!();
+    for (i, option) in onboarding.options().iter().enumerate() {
+        let marker = if onboarding.step == OnboardingStep::Confirm {
+            "  "
+        } else if i == onboarding.selected {
+            "▶ "
+        } else {
+            "  "
+        };
+        println!("  {}{}", marker, option);
+    }
+    println!();
+    println!("  ↑/↓ choose · Enter select · Esc back");
+}
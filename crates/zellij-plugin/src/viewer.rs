@@ -0,0 +1,31 @@
+//! Read-only viewer mode, for a breadcrumb dashboard on a second monitor.
+//!
+//! With `viewer "true"` in its plugin configuration, an instance never
+//! intercepts keys or writes anything.  It loads the project's event log
+//! and today's summary file, written by another instance or the CLI, and
+//! reloads them whenever Zellij reports that they changed.
+
+use std::path::{Path, PathBuf};
+
+use crumbeez_lib::{EVENT_LOG_FILE, SUMMARIES_SUBDIR};
+
+/// What a batch of file changes means for a viewer.
+#[derive(Debug, Default, PartialEq)]
+pub struct Changes {
+    pub event_log: bool,
+    pub summaries: bool,
+}
+
+impl Changes {
+    pub fn of(paths: &[PathBuf]) -> Self {
+        let mut changes = Self::default();
+        for path in paths {
+            changes.event_log |= path.file_name().is_some_and(|name| name == EVENT_LOG_FILE);
+            changes.summaries |= path
+                .parent()
+                .and_then(Path::file_name)
+                .is_some_and(|name| name == SUMMARIES_SUBDIR);
+        }
+        changes
+    }
+}